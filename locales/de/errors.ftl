@@ -0,0 +1,73 @@
+## Customer-facing messages for domain errors, returned as problem detail.
+
+account-invalid-withdraw = Kontostand { $balance } reicht nicht aus, um { $withdraw-amount } abzuheben
+account-max-balance-exceeded = Kontostand { $balance } zuzüglich { $amount } würde den Höchstbetrag { $max-balance } überschreiten
+account-not-yet-created = Dieses Konto wurde noch nicht eröffnet
+account-already-created = Dieses Konto wurde bereits eröffnet
+account-hold-already-placed = Die Vormerkung { $id } wurde bereits angelegt
+account-unknown-hold = Unbekannte Vormerkung { $id }
+account-invalid-capture = Betrag { $amount } übersteigt den vorgemerkten Betrag { $hold-amount }
+account-zero-adjustment = Der Korrekturbetrag muss positiv sein
+account-missing-justification = Eine Korrektur erfordert eine Begründung
+account-closed = Dieses Konto wurde aufgelöst
+account-active-holds = Konto mit { $count } aktiven Vormerkungen kann nicht aufgelöst werden
+account-non-zero-balance = Konto mit Kontostand { $balance } kann ohne Konto für den Übertrag nicht aufgelöst werden
+account-sweep-to-self = Der Kontostand kann nicht auf dasselbe Konto übertragen werden
+account-not-closed = Dieses Konto wurde nicht aufgelöst
+account-reopen-window-expired = Dieses Konto wurde am { $closed-at } aufgelöst, zu lange her für eine Wiedereröffnung
+account-purged = Dieses Konto wurde gelöscht
+account-retention-not-expired = Dieses Konto wurde am { $closed-at } aufgelöst, zu kurz her für eine Löschung
+account-external-ref-in-use = Die externe Referenz { $external-ref } wird bereits verwendet
+account-tenant-quota-exceeded = Die Höchstzahl von { $max } Konten pro Mandant ist erreicht
+account-customer-quota-exceeded = Die Höchstzahl von { $max } Konten pro Kunde ist erreicht
+account-created-differently = Das Konto { $id } wurde bereits mit anderen Parametern eröffnet
+
+books-not-contiguous = Die Periode muss am Ende der zuletzt abgeschlossenen Periode beginnen
+books-empty = Die Periode muss nach ihrem Beginn enden
+books-not-in-past = Die Periode muss in der Vergangenheit enden
+
+consent-expiry-not-in-future = Das Ablaufdatum { $expiry } liegt nicht in der Zukunft
+consent-not-yet-granted = Diese Einwilligung wurde noch nicht erteilt
+consent-already-granted = Diese Einwilligung wurde bereits erteilt
+consent-already-revoked = Diese Einwilligung wurde bereits widerrufen
+
+loan-not-yet-created = Dieser Kredit wurde noch nicht angelegt
+loan-already-created = Dieser Kredit wurde bereits angelegt
+loan-zero-principal = Der Kreditbetrag muss positiv sein
+loan-no-installments = Mindestens eine Rate ist erforderlich
+loan-account-mismatch = Dieser Kredit gehört nicht zum Konto { $account-id }
+loan-already-disbursed = Dieser Kredit wurde bereits ausgezahlt
+loan-not-yet-disbursed = Dieser Kredit wurde noch nicht ausgezahlt
+loan-unknown-installment = Unbekannte Rate { $number }
+loan-invalid-installment-status = Die Rate { $number } ist nicht in einem Zustand, der dies erlaubt
+
+mandate-not-yet-created = Dieses Mandat wurde noch nicht angelegt
+mandate-already-created = Dieses Mandat wurde bereits angelegt
+mandate-cancelled = Dieses Mandat wurde widerrufen
+mandate-max-amount-exceeded = Betrag { $amount } übersteigt den Höchstbetrag { $max-amount }
+mandate-collection-already-scheduled = Der Einzug { $id } wurde bereits geplant
+mandate-unknown-collection = Unbekannter Einzug { $id }
+mandate-invalid-collection-status = Der Einzug { $id } ist nicht in einem Zustand, der dies erlaubt
+mandate-refund-period-expired = Die Erstattungsfrist für den Einzug { $id } ist abgelaufen
+
+term-deposit-not-yet-opened = Diese Festgeldanlage wurde noch nicht eröffnet
+term-deposit-already-opened = Diese Festgeldanlage wurde bereits eröffnet
+term-deposit-zero-amount = Der Betrag muss positiv sein
+term-deposit-zero-term = Die Laufzeit muss mindestens einen Tag betragen
+term-deposit-not-yet-matured = Diese Festgeldanlage ist noch nicht fällig
+term-deposit-maturity-reached = Diese Festgeldanlage ist bereits fällig
+term-deposit-closed = Diese Festgeldanlage wurde bereits aufgelöst
+
+dispute-not-yet-opened = Diese Reklamation wurde noch nicht eröffnet
+dispute-already-opened = Diese Reklamation wurde bereits eröffnet
+dispute-zero-amount = Der Betrag muss positiv sein
+dispute-zero-deadline = Die Frist muss mindestens einen Tag betragen
+dispute-already-credited = Der reklamierte Betrag wurde bereits vorläufig gutgeschrieben
+dispute-deadline-not-reached = Die Frist dieser Reklamation ist noch nicht abgelaufen
+dispute-resolved = Diese Reklamation wurde bereits entschieden
+
+transfer-not-yet-initiated = Diese Überweisung wurde noch nicht veranlasst
+transfer-already-initiated = Diese Überweisung wurde bereits veranlasst
+transfer-zero-amount = Der Betrag muss positiv sein
+transfer-same-account = Quell- und Zielkonto müssen sich unterscheiden
+transfer-invalid-status = Diese Überweisung ist nicht in einem Zustand, der dies erlaubt
@@ -0,0 +1,73 @@
+## Customer-facing messages for domain errors, returned as problem detail.
+
+account-invalid-withdraw = Balance { $balance } insufficient to withdraw amount { $withdraw-amount }
+account-max-balance-exceeded = Balance { $balance } plus amount { $amount } would exceed maximum balance { $max-balance }
+account-not-yet-created = This account has not been created yet
+account-already-created = This account has already been created
+account-hold-already-placed = Hold { $id } has already been placed
+account-unknown-hold = Unknown hold { $id }
+account-invalid-capture = Amount { $amount } exceeds hold amount { $hold-amount }
+account-zero-adjustment = Adjustment amount must be positive
+account-missing-justification = Adjustment requires a justification
+account-closed = This account has been closed
+account-active-holds = Cannot close account with { $count } active holds
+account-non-zero-balance = Cannot close account with balance { $balance } without an account to sweep it to
+account-sweep-to-self = Cannot sweep the balance of an account to itself
+account-not-closed = This account has not been closed
+account-reopen-window-expired = This account has been closed at { $closed-at }, too long ago to be reopened
+account-purged = This account has been purged
+account-retention-not-expired = This account has been closed at { $closed-at }, too recently to be purged
+account-external-ref-in-use = External reference { $external-ref } already in use
+account-tenant-quota-exceeded = Maximum number of { $max } accounts per tenant reached
+account-customer-quota-exceeded = Maximum number of { $max } accounts per customer reached
+account-created-differently = Account { $id } has already been created with different parameters
+
+books-not-contiguous = Period must start at the end of the last closed period
+books-empty = Period must end after it starts
+books-not-in-past = Period must end in the past
+
+consent-expiry-not-in-future = The expiry { $expiry } is not in the future
+consent-not-yet-granted = This consent has not been granted yet
+consent-already-granted = This consent has already been granted
+consent-already-revoked = This consent has already been revoked
+
+loan-not-yet-created = This loan has not been created yet
+loan-already-created = This loan has already been created
+loan-zero-principal = Principal must be positive
+loan-no-installments = At least one installment is required
+loan-account-mismatch = This loan does not belong to account { $account-id }
+loan-already-disbursed = This loan has already been disbursed
+loan-not-yet-disbursed = This loan has not been disbursed yet
+loan-unknown-installment = Unknown installment { $number }
+loan-invalid-installment-status = Installment { $number } is not in a state allowing this
+
+mandate-not-yet-created = This mandate has not been created yet
+mandate-already-created = This mandate has already been created
+mandate-cancelled = This mandate has been cancelled
+mandate-max-amount-exceeded = Amount { $amount } exceeds maximum amount { $max-amount }
+mandate-collection-already-scheduled = Collection { $id } has already been scheduled
+mandate-unknown-collection = Unknown collection { $id }
+mandate-invalid-collection-status = Collection { $id } is not in a state allowing this
+mandate-refund-period-expired = Refund period for collection { $id } has expired
+
+term-deposit-not-yet-opened = This term deposit has not been opened yet
+term-deposit-already-opened = This term deposit has already been opened
+term-deposit-zero-amount = Amount must be positive
+term-deposit-zero-term = Term must be at least one day
+term-deposit-not-yet-matured = This term deposit has not yet matured
+term-deposit-maturity-reached = This term deposit has already reached maturity
+term-deposit-closed = This term deposit has already been closed
+
+dispute-not-yet-opened = This dispute has not been opened yet
+dispute-already-opened = This dispute has already been opened
+dispute-zero-amount = Amount must be positive
+dispute-zero-deadline = Deadline must be at least one day
+dispute-already-credited = The disputed amount has already been credited provisionally
+dispute-deadline-not-reached = The deadline of this dispute has not been reached yet
+dispute-resolved = This dispute has already been resolved
+
+transfer-not-yet-initiated = This transfer has not been initiated yet
+transfer-already-initiated = This transfer has already been initiated
+transfer-zero-amount = Amount must be positive
+transfer-same-account = Source and target account must differ
+transfer-invalid-status = This transfer is not in a state allowing this
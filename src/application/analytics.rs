@@ -0,0 +1,286 @@
+use crate::{
+    domain::{clock::Clock, euro_cent::EuroCent},
+    infra::{
+        error_code::ErrorCode,
+        ledger::{EntryKind, LedgerEntry, LedgerProjection},
+    },
+};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{collections::HashMap, num::NonZeroU16, str::FromStr, sync::Arc};
+use thiserror::Error;
+use time::{Date, Duration, OffsetDateTime, Time};
+use uuid::Uuid;
+
+/// Configuration for [Analytics].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Number of completed days of history the patterns are derived from.
+    lookback_days: NonZeroU16,
+
+    /// Maximum number of days to forecast.
+    max_horizon_days: NonZeroU16,
+}
+
+/// Analytics on top of the read models, e.g. forecasting the balance of an account.
+#[derive(Debug, Clone)]
+pub struct Analytics<G> {
+    config: Config,
+    ledger: G,
+    clock: Arc<dyn Clock>,
+}
+
+impl<G> Analytics<G>
+where
+    G: LedgerProjection,
+{
+    #[allow(missing_docs)]
+    pub fn new(config: Config, ledger: G, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            config,
+            ledger,
+            clock,
+        }
+    }
+
+    /// Forecast the balance of the account with the given ID, starting with the given current
+    /// balance, for the given [Horizon]. As there are no standing orders or scheduled transfers,
+    /// the forecast is based on historical patterns only: the expected net flow of a day is the
+    /// average net flow of the same day of the month over the lookback period, such that monthly
+    /// payments like salary or rent show up on their usual days.
+    pub async fn forecast(
+        &self,
+        account_id: Uuid,
+        balance: EuroCent,
+        horizon: Horizon,
+    ) -> Result<Forecast, AnalyticsError> {
+        let max_horizon_days = self.config.max_horizon_days.get();
+        if horizon.0 > max_horizon_days {
+            return Err(AnalyticsError::InvalidHorizon(horizon.0, max_horizon_days));
+        }
+
+        let as_of = self.clock.now();
+        let today = as_of.date();
+        let lookback_days = self.config.lookback_days.get();
+        let from = today - Duration::days(lookback_days as i64);
+        let entries = self
+            .ledger
+            .entries(midnight(from), midnight(today))
+            .await
+            .into_iter()
+            .filter(|entry| entry.account_id == account_id)
+            .collect::<Vec<_>>();
+
+        let points = project(balance, &entries, from, today, horizon.0);
+        Ok(Forecast {
+            account_id,
+            balance,
+            as_of,
+            lookback_days,
+            points,
+        })
+    }
+}
+
+/// Number of days to forecast, e.g. from `30d`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Horizon(u16);
+
+impl Default for Horizon {
+    fn default() -> Self {
+        Horizon(30)
+    }
+}
+
+impl FromStr for Horizon {
+    type Err = AnalyticsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_suffix('d')
+            .and_then(|days| days.parse::<u16>().ok())
+            .filter(|days| *days > 0)
+            .map(Horizon)
+            .ok_or_else(|| AnalyticsError::InvalidHorizonFormat(s.to_string()))
+    }
+}
+
+/// Forecast of the balance of an account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Forecast {
+    pub account_id: Uuid,
+    /// The current balance the forecast starts with.
+    pub balance: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub as_of: OffsetDateTime,
+    pub lookback_days: u16,
+    pub points: Vec<ForecastPoint>,
+}
+
+/// The expected balance at the end of a single day. Amounts are in cents and might be negative,
+/// i.e. an expected shortfall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ForecastPoint {
+    #[serde(serialize_with = "serialize_date")]
+    pub date: Date,
+    pub net_flow: i64,
+    pub balance: i64,
+}
+
+/// Errors of analytics.
+#[derive(Debug, Error)]
+pub enum AnalyticsError {
+    #[error("invalid horizon {0}, expected number of days like 30d")]
+    InvalidHorizonFormat(String),
+
+    #[error("horizon of {0} days exceeds maximum of {1} days")]
+    InvalidHorizon(u16, u16),
+}
+
+impl From<&AnalyticsError> for ErrorCode {
+    fn from(error: &AnalyticsError) -> Self {
+        match error {
+            AnalyticsError::InvalidHorizonFormat(_) | AnalyticsError::InvalidHorizon(_, _) => {
+                ErrorCode::InvalidRequest
+            }
+        }
+    }
+}
+
+/// Project the given balance over the given number of days after `today`, based on the net flows
+/// of the given entries per day of the month, booked from `from` until before `today`. Opening
+/// balances are one-offs, hence not part of any pattern.
+fn project(
+    balance: EuroCent,
+    entries: &[LedgerEntry],
+    from: Date,
+    today: Date,
+    horizon_days: u16,
+) -> Vec<ForecastPoint> {
+    let mut net_flows = HashMap::<Date, i64>::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.kind != EntryKind::OpeningBalance)
+    {
+        let amount = u64::from(entry.amount) as i64;
+        let net_flow = net_flows.entry(entry.at.date()).or_default();
+        if entry.kind.is_debit() {
+            *net_flow -= amount;
+        } else {
+            *net_flow += amount;
+        }
+    }
+
+    // Sum of the net flows and number of days per day of the month.
+    let mut patterns = HashMap::<u8, (i64, i64)>::new();
+    let mut date = from;
+    while date < today {
+        let (sum, days) = patterns.entry(date.day()).or_default();
+        *sum += net_flows.get(&date).copied().unwrap_or_default();
+        *days += 1;
+        date += Duration::days(1);
+    }
+
+    let mut balance = u64::from(balance) as i64;
+    (1..=horizon_days as i64)
+        .map(|day| {
+            let date = today + Duration::days(day);
+            let net_flow = patterns
+                .get(&date.day())
+                .map(|(sum, days)| sum / days)
+                .unwrap_or_default();
+            balance += net_flow;
+            ForecastPoint {
+                date,
+                net_flow,
+                balance,
+            }
+        })
+        .collect()
+}
+
+fn midnight(date: Date) -> OffsetDateTime {
+    date.with_time(Time::MIDNIGHT).assume_utc()
+}
+
+/// Serialize dates like 2023-06-30.
+fn serialize_date<S>(date: &Date, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Month;
+
+    #[test]
+    fn test_horizon() {
+        assert_eq!("30d".parse::<Horizon>().ok(), Some(Horizon(30)));
+        assert!("0d".parse::<Horizon>().is_err());
+        assert!("30".parse::<Horizon>().is_err());
+        assert!("1w".parse::<Horizon>().is_err());
+    }
+
+    #[test]
+    fn test_project() {
+        let date = |month, day| Date::from_calendar_date(2023, month, day).unwrap();
+        let entry = |at: Date, kind, amount: u64| LedgerEntry {
+            id: Uuid::now_v7(),
+            account_id: Uuid::nil(),
+            tenant: None,
+            sandbox: false,
+            kind,
+            amount: EuroCent::from(amount),
+            balance: EuroCent::default(),
+            at: midnight(at),
+            value_date: midnight(at),
+            booked_at: midnight(at),
+        };
+
+        // Salary on the first and rent on the third of each month.
+        let entries = [Month::January, Month::February, Month::March]
+            .into_iter()
+            .flat_map(|month| {
+                [
+                    entry(date(month, 1), EntryKind::Deposit, 300_000),
+                    entry(date(month, 3), EntryKind::Withdrawal, 100_000),
+                ]
+            })
+            .chain([entry(
+                date(Month::January, 1),
+                EntryKind::OpeningBalance,
+                42,
+            )])
+            .collect::<Vec<_>>();
+
+        let points = project(
+            EuroCent::from(50_000u64),
+            &entries,
+            date(Month::January, 1),
+            date(Month::March, 31),
+            3,
+        );
+        assert_eq!(
+            points,
+            vec![
+                ForecastPoint {
+                    date: date(Month::April, 1),
+                    net_flow: 300_000,
+                    balance: 350_000,
+                },
+                ForecastPoint {
+                    date: date(Month::April, 2),
+                    net_flow: 0,
+                    balance: 350_000,
+                },
+                ForecastPoint {
+                    date: date(Month::April, 3),
+                    net_flow: -100_000,
+                    balance: 250_000,
+                },
+            ]
+        );
+    }
+}
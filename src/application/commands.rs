@@ -0,0 +1,69 @@
+use crate::{
+    domain::account::{self, Snapshot},
+    infra::{account::AccountFactory, error_code::ErrorCode, lru_cache_factory::Priority},
+};
+use anyhow::Context;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// All write-model access, i.e. commands for the entities, such that the API layers issue
+/// commands uniformly, with the same errors. Queries live in [queries](super::queries).
+#[derive(Debug, Clone)]
+pub struct Commands<F> {
+    account_factory: F,
+}
+
+impl<F> Commands<F>
+where
+    F: AccountFactory,
+{
+    #[allow(missing_docs)]
+    pub fn new(account_factory: F) -> Self {
+        Self { account_factory }
+    }
+
+    /// Handle the given command for the account with the given ID with [Priority::Normal] and, if
+    /// successful, return the resulting [Snapshot].
+    pub async fn account(&self, id: Uuid, cmd: account::Cmd) -> Result<Snapshot, CommandError> {
+        self.account_with_priority(id, cmd, Priority::Normal).await
+    }
+
+    /// Like [Commands::account], but with the given [Priority].
+    pub async fn account_with_priority(
+        &self,
+        id: Uuid,
+        cmd: account::Cmd,
+        priority: Priority,
+    ) -> Result<Snapshot, CommandError> {
+        let cmd_name = cmd.name();
+        let result = self
+            .account_factory
+            .get_with_priority(id, priority)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(cmd)
+            .await
+            .with_context(|| format!("Cannot handle {cmd_name} command"))?;
+        result.map_err(CommandError::Rejected)
+    }
+}
+
+/// Errors of commands: either rejected by the entity, i.e. caused by the client, or failed because
+/// of the infrastructure.
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error(transparent)]
+    Rejected(account::Error),
+
+    #[error(transparent)]
+    Failed(#[from] anyhow::Error),
+}
+
+impl From<&CommandError> for ErrorCode {
+    fn from(error: &CommandError) -> Self {
+        match error {
+            CommandError::Rejected(error) => error.into(),
+            CommandError::Failed(error) => ErrorCode::of(error),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::infra::{
+    error_code::ErrorCode,
+    ledger::{EntryKind, LedgerEntry, LedgerProjection},
+    transactions::{Transaction, TransactionsProjection},
+    treasury::{Positions, PositionsProjection},
+};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroUsize;
+use thiserror::Error;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Default number of items per [Page].
+const DEFAULT_LIMIT: usize = 100;
+
+/// Maximum number of items per [Page].
+const MAX_LIMIT: usize = 1_000;
+
+/// All read-model access, i.e. queries against the projections, such that the API layers compose
+/// queries uniformly, with the same pagination and errors, instead of each reaching into the
+/// projections. Commands live in [commands](super::commands).
+#[derive(Debug, Clone)]
+pub struct Queries<Q, G, R> {
+    positions: Q,
+    ledger: G,
+    transactions: R,
+}
+
+impl<Q, G, R> Queries<Q, G, R>
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+{
+    #[allow(missing_docs)]
+    pub fn new(positions: Q, ledger: G, transactions: R) -> Self {
+        Self {
+            positions,
+            ledger,
+            transactions,
+        }
+    }
+
+    /// The current bank-level [Positions].
+    pub async fn positions(&self) -> Positions {
+        self.positions.positions().await
+    }
+
+    /// The [LedgerEntry] of the transaction with the given ID, if projected.
+    pub async fn ledger_entry(&self, id: Uuid) -> Option<LedgerEntry> {
+        self.ledger.entry(id).await
+    }
+
+    /// The requested [Page] of the [LedgerEntry]s matching the given [EntryFilter], in the order
+    /// of their booking timestamps.
+    pub async fn ledger_entries(
+        &self,
+        filter: EntryFilter,
+        page: PageRequest,
+    ) -> Result<Page<LedgerEntry>, QueryError> {
+        let from = filter.from.unwrap_or(OffsetDateTime::UNIX_EPOCH);
+        let to = filter.to.unwrap_or_else(OffsetDateTime::now_utc);
+        if from > to {
+            return Err(QueryError::InvalidFilter("from after to"));
+        }
+
+        let entries = self
+            .ledger
+            .entries(from, to)
+            .await
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .collect();
+        page.apply(entries)
+    }
+
+    /// The requested [Page] of the [Transaction]s of the account with the given ID within the
+    /// period of the given [PeriodFilter], in the order of their timestamps; `None` if the account
+    /// is unknown.
+    pub async fn transactions(
+        &self,
+        account_id: Uuid,
+        filter: PeriodFilter,
+        page: PageRequest,
+    ) -> Result<Option<Page<Transaction>>, QueryError> {
+        let from = filter.from.unwrap_or(OffsetDateTime::UNIX_EPOCH);
+        let to = filter.to.unwrap_or_else(OffsetDateTime::now_utc);
+        if from > to {
+            return Err(QueryError::InvalidFilter("from after to"));
+        }
+
+        self.transactions
+            .transactions(account_id, from, to)
+            .await
+            .map(|transactions| page.apply(transactions))
+            .transpose()
+    }
+}
+
+/// Filter for [LedgerEntry]s; all criteria are optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EntryFilter {
+    pub account_id: Option<Uuid>,
+    pub kind: Option<EntryKind>,
+    /// Booked at or after, the beginning of time if not given.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    /// Booked before, now if not given.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+}
+
+impl EntryFilter {
+    fn matches(&self, entry: &LedgerEntry) -> bool {
+        self.account_id.map_or(true, |id| entry.account_id == id)
+            && self.kind.map_or(true, |kind| entry.kind == kind)
+    }
+}
+
+/// Filter for a period; both bounds are optional.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PeriodFilter {
+    /// At or after, the beginning of time if not given.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    /// Before, now if not given.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+}
+
+/// Offset based pagination, e.g. from the `offset` and `limit` query parameters. The limit
+/// defaults to 100 and must not exceed 1000.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PageRequest {
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<NonZeroUsize>,
+}
+
+impl PageRequest {
+    /// Cut the requested [Page] out of all the given items.
+    pub fn apply<T>(self, items: Vec<T>) -> Result<Page<T>, QueryError> {
+        let limit = self.limit.map_or(DEFAULT_LIMIT, NonZeroUsize::get);
+        if limit > MAX_LIMIT {
+            return Err(QueryError::InvalidPage(limit));
+        }
+
+        let total = items.len();
+        let items = items
+            .into_iter()
+            .skip(self.offset)
+            .take(limit)
+            .collect::<Vec<_>>();
+        let next_offset = (self.offset + items.len() < total).then_some(self.offset + items.len());
+        Ok(Page {
+            items,
+            offset: self.offset,
+            limit,
+            total,
+            next_offset,
+        })
+    }
+}
+
+/// A page of the results of a query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub offset: usize,
+    pub limit: usize,
+    /// Total number of results.
+    pub total: usize,
+    /// Offset of the next page, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+/// Errors of queries.
+#[derive(Debug, Error)]
+pub enum QueryError {
+    #[error("limit {0} exceeds maximum of {MAX_LIMIT}")]
+    InvalidPage(usize),
+
+    #[error("invalid filter: {0}")]
+    InvalidFilter(&'static str),
+}
+
+impl From<&QueryError> for ErrorCode {
+    fn from(error: &QueryError) -> Self {
+        match error {
+            QueryError::InvalidPage(_) | QueryError::InvalidFilter(_) => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_request() {
+        let items = (0..250).collect::<Vec<_>>();
+
+        let page = PageRequest::default().apply(items.clone()).unwrap();
+        assert_eq!(page.items.len(), 100);
+        assert_eq!(page.total, 250);
+        assert_eq!(page.next_offset, Some(100));
+
+        let page = PageRequest {
+            offset: 200,
+            limit: NonZeroUsize::new(100),
+        }
+        .apply(items.clone())
+        .unwrap();
+        assert_eq!(page.items, (200..250).collect::<Vec<_>>());
+        assert_eq!(page.next_offset, None);
+
+        let result = PageRequest {
+            offset: 0,
+            limit: NonZeroUsize::new(1_001),
+        }
+        .apply(items);
+        assert!(matches!(result, Err(QueryError::InvalidPage(1_001))));
+    }
+}
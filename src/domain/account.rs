@@ -1,19 +1,37 @@
-use crate::domain::euro_cent::EuroCent;
+use crate::domain::{
+    channel::Channel,
+    clock::{Clock, SystemClock},
+    currency::Currency,
+    customer::CustomerId,
+    euro_cent::EuroCent,
+    iban::{BankCode, Iban},
+    redaction::Redacted,
+    state_machine::{self, Format, Transition},
+    tenant::TenantId,
+};
 use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
 use serde::{Deserialize, Serialize};
-use std::num::NonZeroU64;
+use std::{collections::BTreeMap, fmt::Display, num::NonZeroU64, str::FromStr, sync::Arc};
 use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tokio::sync::watch;
 use tracing::{debug, error};
 use uuid::Uuid;
 
 pub const ACCOUNT_LIFECYCLE_TAG: &str = "account-lifecycle";
 
-/// An account. Defaults to a zero balance and no snapshot.
-#[derive(Debug, Default, Clone)]
+pub const ACCOUNT_TX_TAG: &str = "account-tx";
+
+/// An account. Defaults to a zero balance, no snapshot, no maximum balances and the
+/// [SystemClock].
+#[derive(Debug, Clone)]
 pub struct Account {
     snapshot_after: Option<NonZeroU64>,
+    clock: Arc<dyn Clock>,
+    max_balances: MaxBalances,
     state: State,
-    evt_count: u64,
+    seq_no: u64,
+    snapshots: Arc<watch::Sender<Snapshot>>,
 }
 
 impl Account {
@@ -24,40 +42,1503 @@ impl Account {
             ..self
         }
     }
+
+    /// Use the given [Clock] for timestamping events.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Reject deposits exceeding the given [MaxBalances].
+    pub fn with_max_balances(self, max_balances: MaxBalances) -> Self {
+        Self {
+            max_balances,
+            ..self
+        }
+    }
+
+    /// The [MaxBalances] deposits are checked against.
+    pub fn max_balances(&self) -> MaxBalances {
+        self.max_balances
+    }
+
+    /// Observe the [Snapshot]s of this account, updated after each handled event.
+    pub fn subscribe(&self) -> watch::Receiver<Snapshot> {
+        self.snapshots.subscribe()
+    }
+
+    /// Handle the given command without persisting the resulting event, i.e. a dry run, and return
+    /// this event along with the would-be [Snapshot].
+    pub fn dry_run(&self, cmd: Cmd) -> Result<(Evt, Snapshot), Error> {
+        let evt = self.decide(cmd)?;
+        let mut account = Account {
+            snapshot_after: None,
+            clock: self.clock.clone(),
+            max_balances: self.max_balances,
+            state: self.state.clone(),
+            seq_no: self.seq_no,
+            snapshots: Arc::new(watch::channel(self.snapshot()).0),
+        };
+        account.handle_evt(evt.clone());
+        Ok((evt, account.snapshot()))
+    }
+
+    /// The command handler, shared by [EventSourced::handle_cmd] and [Account::dry_run]: the
+    /// command is handled by the first applicable [Rule] for the current state.
+    fn decide(&self, cmd: Cmd) -> Result<Evt, Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let ctx = Ctx {
+            state: &self.state,
+            max_balances: self.max_balances,
+            at: self.clock.now(),
+        };
+        let state = self.state.name();
+        RULES
+            .iter()
+            .filter(|rule| rule.transition.state == state && rule.transition.cmd == cmd.name())
+            .find_map(|rule| (rule.apply)(&ctx, &cmd))
+            .unwrap_or_else(|| {
+                panic!(
+                    "Transition table must cover command '{}' in state {state}",
+                    cmd.name()
+                )
+            })
+    }
+
+    /// The current [Snapshot].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            state: self.state.clone(),
+            seq_no: self.seq_no,
+        }
+    }
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self {
+            snapshot_after: None,
+            clock: Arc::new(SystemClock),
+            max_balances: MaxBalances::default(),
+            state: State::default(),
+            seq_no: 0,
+            snapshots: Arc::new(watch::channel(Snapshot::default()).0),
+        }
+    }
+}
+
+/// The transition table of an [Account], i.e. its command handler: for each state and command the
+/// rules, tried in order, with the last one having no guard.
+const RULES: &[Rule] = &[
+    // In State::NonExistent:
+    Rule {
+        transition: Transition::rejected(
+            NON_EXISTENT,
+            "create",
+            Some("opening balance > max balance"),
+            "MaxBalanceExceeded",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Create {
+                opening_balance,
+                product,
+                ..
+            } => ctx
+                .max_balance_exceeded(product, EuroCent::default(), *opening_balance)
+                .map(Err),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(NON_EXISTENT, "create", None, "Created", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Create {
+                id,
+                tenant,
+                customer,
+                iban,
+                currency,
+                opening_balance,
+                external_ref,
+                product,
+                sandbox,
+            } => Some(Ok(Evt::Created {
+                id: *id,
+                tenant: *tenant,
+                customer: *customer,
+                iban: iban.clone(),
+                currency: *currency,
+                opening_balance: *opening_balance,
+                external_ref: external_ref.clone(),
+                product: *product,
+                sandbox: *sandbox,
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "deposit", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "withdraw", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(
+            NON_EXISTENT,
+            "set-notification-prefs",
+            None,
+            "NotYetCreated",
+        ),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "set-labels", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "place-hold", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "capture-hold", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "release-hold", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "adjust", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "close", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "reopen", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    Rule {
+        transition: Transition::rejected(NON_EXISTENT, "purge", None, "NotYetCreated"),
+        apply: not_yet_created,
+    },
+    // In State::Created:
+    Rule {
+        transition: Transition::rejected(CREATED, "create", None, "AlreadyCreated"),
+        apply: |_, cmd| {
+            error!("Cannot handle command '{cmd:?}' in state Created");
+            Some(Err(Error::AlreadyCreated))
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "deposit",
+            Some("balance + amount > max balance"),
+            "MaxBalanceExceeded",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Deposit(_, amount, ..) => ctx
+                .product()
+                .and_then(|product| ctx.max_balance_exceeded(&product, ctx.balance(), *amount))
+                .map(Err),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "deposit", None, "Deposited", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Deposit(id, amount, value_date, channel) => Some(Ok(Evt::Deposited {
+                id: *id,
+                account_id: ctx.account_id(),
+                old_balance: ctx.balance(),
+                amount: *amount,
+                value_date: *value_date,
+                channel: channel.clone(),
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "withdraw",
+            Some("available balance < amount"),
+            "InvalidWithdraw",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Withdraw(_, amount, ..) if ctx.available_balance() < *amount => {
+                Some(Err(Error::InvalidWithdraw {
+                    balance: ctx.available_balance(),
+                    withdraw_amount: *amount,
+                }))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "withdraw", None, "Withdrawn", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Withdraw(id, amount, value_date, channel) => Some(Ok(Evt::Withdrawn {
+                id: *id,
+                account_id: ctx.account_id(),
+                old_balance: ctx.balance(),
+                amount: *amount,
+                value_date: *value_date,
+                channel: channel.clone(),
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(
+            CREATED,
+            "set-notification-prefs",
+            None,
+            "NotificationPrefsSet",
+            CREATED,
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::SetNotificationPrefs(notification_prefs) => Some(Ok(Evt::NotificationPrefsSet {
+                account_id: ctx.account_id(),
+                notification_prefs: *notification_prefs,
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "set-labels", None, "LabelsSet", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::SetLabels(labels) => Some(Ok(Evt::LabelsSet {
+                account_id: ctx.account_id(),
+                labels: labels.clone(),
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "place-hold",
+            Some("hold already placed"),
+            "HoldAlreadyPlaced",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::PlaceHold(id, _) if ctx.hold(*id).is_some() => {
+                Some(Err(Error::HoldAlreadyPlaced(*id)))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "place-hold",
+            Some("available balance < amount"),
+            "InvalidWithdraw",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::PlaceHold(_, amount) if ctx.available_balance() < *amount => {
+                Some(Err(Error::InvalidWithdraw {
+                    balance: ctx.available_balance(),
+                    withdraw_amount: *amount,
+                }))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "place-hold", None, "HoldPlaced", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::PlaceHold(id, amount) => Some(Ok(Evt::HoldPlaced {
+                id: *id,
+                account_id: ctx.account_id(),
+                amount: *amount,
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "capture-hold",
+            Some("unknown hold"),
+            "UnknownHold",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::CaptureHold(id, _) if ctx.hold(*id).is_none() => {
+                Some(Err(Error::UnknownHold(*id)))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "capture-hold",
+            Some("hold amount < amount"),
+            "InvalidCapture",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::CaptureHold(id, amount) => ctx
+                .hold(*id)
+                .filter(|hold_amount| hold_amount < amount)
+                .map(|hold_amount| {
+                    Err(Error::InvalidCapture {
+                        hold_amount,
+                        amount: *amount,
+                    })
+                }),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "capture-hold", None, "HoldCaptured", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::CaptureHold(id, amount) => Some(Ok(Evt::HoldCaptured {
+                id: *id,
+                account_id: ctx.account_id(),
+                old_balance: ctx.balance(),
+                amount: *amount,
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "release-hold",
+            Some("unknown hold"),
+            "UnknownHold",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::ReleaseHold(id) if ctx.hold(*id).is_none() => Some(Err(Error::UnknownHold(*id))),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "release-hold", None, "HoldReleased", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::ReleaseHold(id) => Some(Ok(Evt::HoldReleased {
+                id: *id,
+                account_id: ctx.account_id(),
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(CREATED, "adjust", Some("zero amount"), "ZeroAdjustment"),
+        apply: |_, cmd| match cmd {
+            Cmd::Adjust { amount, .. } if *amount == EuroCent::default() => {
+                Some(Err(Error::ZeroAdjustment))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "adjust",
+            Some("blank justification"),
+            "MissingJustification",
+        ),
+        apply: |_, cmd| match cmd {
+            Cmd::Adjust { justification, .. } if justification.trim().is_empty() => {
+                Some(Err(Error::MissingJustification))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "adjust",
+            Some("debit and available balance < amount"),
+            "InvalidWithdraw",
+        ),
+        // Like withdrawals, debits must not spend held amounts, else capturing these would
+        // exceed the balance.
+        apply: |ctx, cmd| match cmd {
+            Cmd::Adjust {
+                direction: AdjustmentDirection::Debit,
+                amount,
+                ..
+            } if ctx.available_balance() < *amount => Some(Err(Error::InvalidWithdraw {
+                balance: ctx.available_balance(),
+                withdraw_amount: *amount,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "adjust", None, "Adjusted", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Adjust {
+                id,
+                direction,
+                amount,
+                reason,
+                justification,
+            } => Some(Ok(Evt::Adjusted {
+                id: *id,
+                account_id: ctx.account_id(),
+                old_balance: ctx.balance(),
+                direction: *direction,
+                amount: *amount,
+                reason: *reason,
+                justification: justification.clone(),
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(CREATED, "close", Some("active holds"), "ActiveHolds"),
+        apply: |ctx, cmd| match (cmd, ctx.state) {
+            (Cmd::Close { .. }, State::Created { holds, .. }) if !holds.is_empty() => {
+                Some(Err(Error::ActiveHolds(holds.len())))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "close",
+            Some("sweep to this account"),
+            "SweepToSelf",
+        ),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Close {
+                sweep_to: Some(sweep_to),
+                ..
+            } if *sweep_to == ctx.account_id() => Some(Err(Error::SweepToSelf)),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CREATED,
+            "close",
+            Some("balance > 0, no sweep and no sandbox"),
+            "NonZeroBalance",
+        ),
+        // The balance of sandbox accounts is test data, which may simply be discarded.
+        apply: |ctx, cmd| match cmd {
+            Cmd::Close { sweep_to: None, .. }
+                if ctx.balance() != EuroCent::default() && !ctx.state.is_sandbox() =>
+            {
+                Some(Err(Error::NonZeroBalance(ctx.balance())))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CREATED, "close", None, "Closed", CLOSED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Close { id, sweep_to } => Some(Ok(Evt::Closed {
+                id: *id,
+                account_id: ctx.account_id(),
+                old_balance: ctx.balance(),
+                sweep_to: *sweep_to,
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(CREATED, "reopen", None, "NotClosed"),
+        apply: |_, _| Some(Err(Error::NotClosed)),
+    },
+    Rule {
+        transition: Transition::rejected(CREATED, "purge", None, "NotClosed"),
+        apply: |_, _| Some(Err(Error::NotClosed)),
+    },
+    // In State::Closed:
+    Rule {
+        transition: Transition::rejected(CLOSED, "create", None, "AlreadyCreated"),
+        apply: |_, cmd| {
+            error!("Cannot handle command '{cmd:?}' in state Closed");
+            Some(Err(Error::AlreadyCreated))
+        },
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "deposit", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "withdraw", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "set-notification-prefs", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "set-labels", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "place-hold", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "capture-hold", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "release-hold", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "adjust", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "close", None, "Closed"),
+        apply: closed,
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "reopen", Some("purged"), "Purged"),
+        apply: |ctx, _| ctx.purged().then_some(Err(Error::Purged)),
+    },
+    Rule {
+        transition: Transition::rejected(
+            CLOSED,
+            "reopen",
+            Some("blank justification"),
+            "MissingJustification",
+        ),
+        apply: |_, cmd| match cmd {
+            Cmd::Reopen { justification, .. } if justification.trim().is_empty() => {
+                Some(Err(Error::MissingJustification))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(
+            CLOSED,
+            "reopen",
+            Some("reopen window expired"),
+            "ReopenWindowExpired",
+        ),
+        apply: |ctx, cmd| match (cmd, ctx.state) {
+            (Cmd::Reopen { window_days, .. }, State::Closed { closed_at, .. })
+                if ctx.at > *closed_at + Duration::days(*window_days as i64) =>
+            {
+                Some(Err(Error::ReopenWindowExpired {
+                    closed_at: *closed_at,
+                }))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CLOSED, "reopen", None, "Reopened", CREATED),
+        apply: |ctx, cmd| match cmd {
+            Cmd::Reopen { justification, .. } => Some(Ok(Evt::Reopened {
+                account_id: ctx.account_id(),
+                justification: justification.clone(),
+                at: ctx.at,
+            })),
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::rejected(CLOSED, "purge", Some("purged"), "Purged"),
+        apply: |ctx, _| ctx.purged().then_some(Err(Error::Purged)),
+    },
+    Rule {
+        transition: Transition::rejected(
+            CLOSED,
+            "purge",
+            Some("retention period not expired"),
+            "RetentionNotExpired",
+        ),
+        apply: |ctx, cmd| match (cmd, ctx.state) {
+            (Cmd::Purge { retention_days }, State::Closed { closed_at, .. })
+                if ctx.at <= *closed_at + Duration::days(*retention_days as i64) =>
+            {
+                Some(Err(Error::RetentionNotExpired {
+                    closed_at: *closed_at,
+                }))
+            }
+            _ => None,
+        },
+    },
+    Rule {
+        transition: Transition::evt(CLOSED, "purge", None, "Purged", CLOSED),
+        apply: |ctx, _| {
+            Some(Ok(Evt::Purged {
+                account_id: ctx.account_id(),
+                at: ctx.at,
+            }))
+        },
+    },
+];
+
+const NON_EXISTENT: &str = "NonExistent";
+const CREATED: &str = "Created";
+const CLOSED: &str = "Closed";
+
+/// A rule of the transition table: the [Transition] it implements and the function applying it to
+/// a command, returning `None` if not applicable, i.e. if the guard does not hold.
+struct Rule {
+    transition: Transition,
+    apply: fn(&Ctx, &Cmd) -> Option<Result<Evt, Error>>,
+}
+
+/// What [Rule]s see of an account: its state and the time of command handling.
+struct Ctx<'a> {
+    state: &'a State,
+    max_balances: MaxBalances,
+    at: OffsetDateTime,
+}
+
+impl Ctx<'_> {
+    fn account_id(&self) -> Uuid {
+        match self.state {
+            State::NonExistent => Uuid::nil(),
+            State::Created { id, .. } | State::Closed { id, .. } => *id,
+        }
+    }
+
+    fn balance(&self) -> EuroCent {
+        self.state.balance().unwrap_or_default()
+    }
+
+    fn available_balance(&self) -> EuroCent {
+        self.state.available_balance().unwrap_or_default()
+    }
+
+    /// The [Product], if created with recorded terms.
+    fn product(&self) -> Option<Product> {
+        match self.state {
+            State::Created {
+                terms: Some(terms), ..
+            } => Some(terms.product),
+            _ => None,
+        }
+    }
+
+    /// The error for crediting the given amount to the given balance, if exceeding the maximum
+    /// balance for the given [Product].
+    fn max_balance_exceeded(
+        &self,
+        product: &Product,
+        balance: EuroCent,
+        amount: EuroCent,
+    ) -> Option<Error> {
+        self.max_balances
+            .of(product)
+            .filter(|max_balance| balance + amount > *max_balance)
+            .map(|max_balance| Error::MaxBalanceExceeded {
+                balance,
+                amount,
+                max_balance,
+            })
+    }
+
+    /// Whether closed and purged.
+    fn purged(&self) -> bool {
+        matches!(self.state, State::Closed { purged: true, .. })
+    }
+
+    /// The amount of the hold with the given ID, if any.
+    fn hold(&self, id: Uuid) -> Option<EuroCent> {
+        match self.state {
+            State::NonExistent | State::Closed { .. } => None,
+            State::Created { holds, .. } => holds.get(&id).copied(),
+        }
+    }
+}
+
+fn not_yet_created(_ctx: &Ctx, cmd: &Cmd) -> Option<Result<Evt, Error>> {
+    error!("Cannot handle command '{cmd:?}' in state NonExistent");
+    Some(Err(Error::NotYetCreated))
+}
+
+fn closed(_ctx: &Ctx, cmd: &Cmd) -> Option<Result<Evt, Error>> {
+    debug!("Cannot handle command '{cmd:?}' in state Closed");
+    Some(Err(Error::Closed))
+}
+
+/// The transitions of an [Account], i.e. of its transition table, e.g. to generate documentation.
+pub fn transitions() -> Vec<Transition> {
+    RULES.iter().map(|rule| rule.transition).collect()
+}
+
+/// The state machine of an [Account] as diagram in the given [Format].
+pub fn state_machine_diagram(format: Format) -> String {
+    state_machine::diagram("account", NON_EXISTENT, &transitions(), format)
+}
+
+const MAX_EXTERNAL_REF_LEN: usize = 64;
+
+const MAX_LABELS: usize = 32;
+
+const MAX_LABEL_LEN: usize = 63;
+
+/// The product type of an account.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Product {
+    #[default]
+    Standard,
+
+    /// Accumulating many small credits, e.g. card payments, the daily net of which is settled to
+    /// the given account.
+    Merchant { settlement_account: Uuid },
+}
+
+/// Maximum balances by [Product], e.g. as required for an e-money licence: deposits, including
+/// opening balances, which would exceed these are rejected. Unlimited if not given.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MaxBalances {
+    #[serde(default)]
+    pub standard: Option<EuroCent>,
+    #[serde(default)]
+    pub merchant: Option<EuroCent>,
+}
+
+impl MaxBalances {
+    /// The maximum balance for the given [Product], if any.
+    pub fn of(&self, product: &Product) -> Option<EuroCent> {
+        match product {
+            Product::Standard => self.standard,
+            Product::Merchant { .. } => self.merchant,
+        }
+    }
+}
+
+/// Reference of an account in an external system, e.g. a core banking system the account has been
+/// migrated from. Unique per tenant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ExternalRef(String);
+
+impl TryFrom<String> for ExternalRef {
+    type Error = InvalidExternalRef;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty()
+            || value.len() > MAX_EXTERNAL_REF_LEN
+            || !value.bytes().all(|b| b.is_ascii_graphic())
+        {
+            Err(InvalidExternalRef(value))
+        } else {
+            Ok(ExternalRef(value))
+        }
+    }
+}
+
+impl AsRef<str> for ExternalRef {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ExternalRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Error for invalid [ExternalRef]s.
+#[derive(Debug, Clone, Error)]
+#[error(
+    "Invalid external reference '{0}', must have 1 to {MAX_EXTERNAL_REF_LEN} printable ASCII characters"
+)]
+pub struct InvalidExternalRef(String);
+
+/// Labels of an account, i.e. key-value pairs for grouping accounts ad hoc, e.g. `team=payroll`.
+/// At most 32 labels; keys and values have 1 to 63 characters, ASCII alphanumerics, `-`, `_` or
+/// `.`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BTreeMap<String, String>")]
+pub struct Labels(BTreeMap<String, String>);
+
+impl Labels {
+    /// Whether these labels contain the given [Label].
+    pub fn contains(&self, label: &Label) -> bool {
+        self.0.get(&label.key) == Some(&label.value)
+    }
+}
+
+impl TryFrom<BTreeMap<String, String>> for Labels {
+    type Error = InvalidLabel;
+
+    fn try_from(value: BTreeMap<String, String>) -> Result<Self, Self::Error> {
+        if value.len() > MAX_LABELS {
+            return Err(InvalidLabel(format!("{} labels", value.len())));
+        }
+        if let Some((key, label_value)) = value
+            .iter()
+            .find(|(key, value)| !is_label_part(key) || !is_label_part(value))
+        {
+            return Err(InvalidLabel(format!("{key}={label_value}")));
+        }
+        Ok(Labels(value))
+    }
+}
+
+/// A single label, e.g. from `team=payroll` to filter accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Label {
+    type Err = InvalidLabel;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once('=')
+            .filter(|(key, value)| is_label_part(key) && is_label_part(value))
+            .map(|(key, value)| Label {
+                key: key.to_string(),
+                value: value.to_string(),
+            })
+            .ok_or_else(|| InvalidLabel(s.to_string()))
+    }
+}
+
+/// Error for invalid [Labels] or [Label]s.
+#[derive(Debug, Clone, Error)]
+#[error(
+    "Invalid label '{0}', must be at most {MAX_LABELS} labels with keys and values of 1 to {MAX_LABEL_LEN} ASCII alphanumerics, '-', '_' or '.'"
+)]
+pub struct InvalidLabel(String);
+
+fn is_label_part(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= MAX_LABEL_LEN
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+/// Notification preferences of an account, consumed by the notifier.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationPrefs {
+    #[serde(default)]
+    pub channels: NotificationChannels,
+
+    /// If given, notify on withdrawals above this amount.
+    #[serde(default)]
+    pub withdrawal_threshold: Option<EuroCent>,
+
+    /// If given, notify when the balance falls below this amount.
+    #[serde(default)]
+    pub low_balance_threshold: Option<EuroCent>,
+}
+
+/// Channels to send notifications to; addresses are taken from the account holder.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationChannels {
+    #[serde(default)]
+    pub email: bool,
+
+    #[serde(default)]
+    pub sms: bool,
+
+    #[serde(default)]
+    pub webhook: bool,
 }
 
 /// Commands for an eventsourced [Account].
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Cmd {
-    Create(Uuid),
-    Deposit(Uuid, EuroCent),
-    Withdraw(Uuid, EuroCent),
+    Create {
+        id: Uuid,
+        tenant: TenantId,
+        customer: Option<CustomerId>,
+        iban: Iban,
+        #[serde(default)]
+        currency: Currency,
+        opening_balance: EuroCent,
+        external_ref: Option<ExternalRef>,
+        #[serde(default)]
+        product: Product,
+        #[serde(default)]
+        sandbox: bool,
+    },
+    /// Deposit with an optional value date, which defaults to the time of command handling, and
+    /// the [Channel], if known.
+    Deposit(
+        Uuid,
+        EuroCent,
+        #[serde(with = "time::serde::rfc3339::option")] Option<OffsetDateTime>,
+        #[serde(default)] Option<Channel>,
+    ),
+    /// Withdraw with an optional value date, which defaults to the time of command handling, and
+    /// the [Channel], if known.
+    Withdraw(
+        Uuid,
+        EuroCent,
+        #[serde(with = "time::serde::rfc3339::option")] Option<OffsetDateTime>,
+        #[serde(default)] Option<Channel>,
+    ),
+    SetNotificationPrefs(NotificationPrefs),
+    /// Replace all labels.
+    SetLabels(Labels),
+    PlaceHold(Uuid, EuroCent),
+    CaptureHold(Uuid, EuroCent),
+    ReleaseHold(Uuid),
+    /// Correct the balance, e.g. after a booking error; reserved for admins.
+    Adjust {
+        id: Uuid,
+        direction: AdjustmentDirection,
+        amount: EuroCent,
+        reason: ReasonCode,
+        justification: String,
+    },
+    /// Close with the given ID, which requires no active holds and a zero balance, unless the
+    /// balance is swept to the given account, i.e. transferred there by a final transfer with the
+    /// same ID.
+    Close {
+        id: Uuid,
+        sweep_to: Option<Uuid>,
+    },
+    /// Reopen a closed account within the given number of days after closing; reserved for
+    /// admins.
+    Reopen {
+        justification: String,
+        window_days: u16,
+    },
+    /// Purge a closed account more than the given number of days after closing, i.e. remove it
+    /// from the projections once its events have been archived; it cannot be reopened afterwards.
+    Purge {
+        retention_days: u16,
+    },
 }
 
-/// Events for an eventsourced [Account].
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+impl Cmd {
+    /// The name of the type of this command, e.g. for metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cmd::Create { .. } => "create",
+            Cmd::Deposit(..) => "deposit",
+            Cmd::Withdraw(..) => "withdraw",
+            Cmd::SetNotificationPrefs(_) => "set-notification-prefs",
+            Cmd::SetLabels(_) => "set-labels",
+            Cmd::PlaceHold(..) => "place-hold",
+            Cmd::CaptureHold(..) => "capture-hold",
+            Cmd::ReleaseHold(_) => "release-hold",
+            Cmd::Adjust { .. } => "adjust",
+            Cmd::Close { .. } => "close",
+            Cmd::Reopen { .. } => "reopen",
+            Cmd::Purge { .. } => "purge",
+        }
+    }
+}
+
+/// Events for an eventsourced [Account], timestamped with the time of command handling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Evt {
-    Created(Uuid),
+    /// Also deserialized from the legacy shape only carrying the ID.
+    #[serde(deserialize_with = "deserialize_created")]
+    Created {
+        id: Uuid,
+        tenant: TenantId,
+        customer: Option<CustomerId>,
+        iban: Iban,
+        /// EUR for accounts created before currencies were introduced.
+        #[serde(default)]
+        currency: Currency,
+        opening_balance: EuroCent,
+        external_ref: Option<ExternalRef>,
+        /// Standard for accounts created before product types were introduced.
+        #[serde(default)]
+        product: Product,
+        /// Sandbox accounts hold test data, e.g. for partners testing end-to-end against the
+        /// production API: they are excluded from treasury and regulatory aggregates.
+        #[serde(default)]
+        sandbox: bool,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
     Deposited {
         id: Uuid,
+        account_id: Uuid,
         old_balance: EuroCent,
         amount: EuroCent,
+        /// Back- or future-dated value date, if any, else the value date is `at`. Only affects
+        /// interest and statements, whereas the balance changes in event order.
+        #[serde(default, with = "time::serde::rfc3339::option")]
+        value_date: Option<OffsetDateTime>,
+        /// The [Channel] the command has been issued through, if known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        channel: Option<Channel>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
     },
     Withdrawn {
         id: Uuid,
+        account_id: Uuid,
+        old_balance: EuroCent,
+        amount: EuroCent,
+        /// Back- or future-dated value date, if any, else the value date is `at`. Only affects
+        /// interest and statements, whereas the balance changes in event order.
+        #[serde(default, with = "time::serde::rfc3339::option")]
+        value_date: Option<OffsetDateTime>,
+        /// The [Channel] the command has been issued through, if known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        channel: Option<Channel>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    NotificationPrefsSet {
+        account_id: Uuid,
+        notification_prefs: NotificationPrefs,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    LabelsSet {
+        account_id: Uuid,
+        labels: Labels,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    HoldPlaced {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    HoldCaptured {
+        id: Uuid,
+        account_id: Uuid,
+        old_balance: EuroCent,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    HoldReleased {
+        id: Uuid,
+        account_id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    /// A correction of the balance, distinct from deposits and withdrawals, such that it always
+    /// shows up flagged.
+    Adjusted {
+        id: Uuid,
+        account_id: Uuid,
         old_balance: EuroCent,
+        direction: AdjustmentDirection,
         amount: EuroCent,
+        reason: ReasonCode,
+        justification: String,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    /// Closed, with the old balance swept to the given account, if any, else the old balance is
+    /// zero. Either way the balance is zero afterwards.
+    Closed {
+        id: Uuid,
+        account_id: Uuid,
+        old_balance: EuroCent,
+        sweep_to: Option<Uuid>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    /// Reopened by an admin, with the justification recorded for auditing, like for adjustments.
+    Reopened {
+        account_id: Uuid,
+        justification: String,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Purged {
+        account_id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
     },
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Representation of [Evt::Created] for deserialization, either current or legacy, i.e. only
+/// carrying the ID, as persisted before events were timestamped.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CreatedRepr {
+    Current {
+        id: Uuid,
+        tenant: TenantId,
+        customer: Option<CustomerId>,
+        iban: Iban,
+        #[serde(default)]
+        currency: Currency,
+        opening_balance: EuroCent,
+        external_ref: Option<ExternalRef>,
+        #[serde(default)]
+        product: Product,
+        #[serde(default)]
+        sandbox: bool,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+
+    Legacy(Uuid),
+}
+
+/// Fields of [Evt::Created] in declaration order.
+type CreatedFields = (
+    Uuid,
+    TenantId,
+    Option<CustomerId>,
+    Iban,
+    Currency,
+    EuroCent,
+    Option<ExternalRef>,
+    Product,
+    bool,
+    OffsetDateTime,
+);
+
+/// Deserialize the fields of [Evt::Created], upcasting the legacy shape, i.e. `{"Created":"<id>"}`,
+/// with the defaults for the missing fields: as neither the creation time nor the configured bank
+/// code are known, these are the Unix epoch and the IBAN derived with the bank code 0.
+fn deserialize_created<'de, D>(deserializer: D) -> Result<CreatedFields, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let fields = match CreatedRepr::deserialize(deserializer)? {
+        CreatedRepr::Current {
+            id,
+            tenant,
+            customer,
+            iban,
+            currency,
+            opening_balance,
+            external_ref,
+            product,
+            sandbox,
+            at,
+        } => (
+            id,
+            tenant,
+            customer,
+            iban,
+            currency,
+            opening_balance,
+            external_ref,
+            product,
+            sandbox,
+            at,
+        ),
+
+        CreatedRepr::Legacy(id) => {
+            let bank_code = BankCode::try_from(0).expect("0 is a valid bank code");
+            (
+                id,
+                TenantId::default(),
+                None,
+                Iban::for_account(bank_code, id),
+                Currency::default(),
+                EuroCent::default(),
+                None,
+                Product::default(),
+                false,
+                OffsetDateTime::UNIX_EPOCH,
+            )
+        }
+    };
+    Ok(fields)
+}
+
+/// Direction of an adjustment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AdjustmentDirection {
+    Credit,
+    Debit,
+}
+
+impl AdjustmentDirection {
+    /// The balance after adjusting the given balance by the given amount.
+    pub fn apply(&self, balance: EuroCent, amount: EuroCent) -> EuroCent {
+        match self {
+            AdjustmentDirection::Credit => balance + amount,
+            AdjustmentDirection::Debit => balance - amount,
+        }
+    }
+}
+
+/// Mandatory reason code of an adjustment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReasonCode {
+    BookingError,
+    FeeRefund,
+    InterestCorrection,
+    Goodwill,
+    Regulatory,
+}
+
+impl Evt {
+    /// The ID of the account.
+    pub fn account_id(&self) -> Uuid {
+        match self {
+            Evt::Created { id, .. } => *id,
+            Evt::Deposited { account_id, .. }
+            | Evt::Withdrawn { account_id, .. }
+            | Evt::NotificationPrefsSet { account_id, .. }
+            | Evt::LabelsSet { account_id, .. }
+            | Evt::HoldPlaced { account_id, .. }
+            | Evt::HoldCaptured { account_id, .. }
+            | Evt::HoldReleased { account_id, .. }
+            | Evt::Adjusted { account_id, .. }
+            | Evt::Closed { account_id, .. }
+            | Evt::Reopened { account_id, .. }
+            | Evt::Purged { account_id, .. } => *account_id,
+        }
+    }
+
+    /// This event for the account with the given ID instead, e.g. for importing an account under
+    /// a different ID. References to other accounts, i.e. `sweep_to`, are kept.
+    pub fn with_account_id(mut self, new_id: Uuid) -> Self {
+        match &mut self {
+            Evt::Created { id, .. } => *id = new_id,
+            Evt::Deposited { account_id, .. }
+            | Evt::Withdrawn { account_id, .. }
+            | Evt::NotificationPrefsSet { account_id, .. }
+            | Evt::LabelsSet { account_id, .. }
+            | Evt::HoldPlaced { account_id, .. }
+            | Evt::HoldCaptured { account_id, .. }
+            | Evt::HoldReleased { account_id, .. }
+            | Evt::Adjusted { account_id, .. }
+            | Evt::Closed { account_id, .. }
+            | Evt::Reopened { account_id, .. }
+            | Evt::Purged { account_id, .. } => *account_id = new_id,
+        }
+        self
+    }
+
+    /// The tag, separating lifecycle from transaction events.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Evt::Created { .. }
+            | Evt::NotificationPrefsSet { .. }
+            | Evt::LabelsSet { .. }
+            | Evt::Closed { .. }
+            | Evt::Reopened { .. }
+            | Evt::Purged { .. } => ACCOUNT_LIFECYCLE_TAG,
+            Evt::Deposited { .. }
+            | Evt::Withdrawn { .. }
+            | Evt::HoldPlaced { .. }
+            | Evt::HoldCaptured { .. }
+            | Evt::HoldReleased { .. }
+            | Evt::Adjusted { .. } => ACCOUNT_TX_TAG,
+        }
+    }
+
+    /// The time of command handling.
+    pub fn at(&self) -> OffsetDateTime {
+        match self {
+            Evt::Created { at, .. }
+            | Evt::Deposited { at, .. }
+            | Evt::Withdrawn { at, .. }
+            | Evt::NotificationPrefsSet { at, .. }
+            | Evt::LabelsSet { at, .. }
+            | Evt::HoldPlaced { at, .. }
+            | Evt::HoldCaptured { at, .. }
+            | Evt::HoldReleased { at, .. }
+            | Evt::Adjusted { at, .. }
+            | Evt::Closed { at, .. }
+            | Evt::Reopened { at, .. }
+            | Evt::Purged { at, .. } => *at,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum State {
     #[default]
     NonExistent,
     Created {
         id: Uuid,
+        tenant: TenantId,
+        iban: Iban,
+        #[serde(default)]
+        currency: Currency,
         balance: EuroCent,
+        #[serde(default)]
+        notification_prefs: NotificationPrefs,
+        /// Amounts of holds by their ID, reserved from the balance until captured or released.
+        #[serde(default)]
+        holds: BTreeMap<Uuid, EuroCent>,
+        /// `None` for accounts created before the terms were recorded.
+        #[serde(default)]
+        terms: Option<Terms>,
     },
+    /// Closed with a zero balance, the former balance swept to `sweep_to`, if any.
+    Closed {
+        id: Uuid,
+        tenant: TenantId,
+        iban: Iban,
+        currency: Currency,
+        notification_prefs: NotificationPrefs,
+        final_balance: EuroCent,
+        sweep_to: Option<Uuid>,
+        #[serde(with = "time::serde::rfc3339")]
+        closed_at: OffsetDateTime,
+        /// Whether removed from the projections after the retention period.
+        #[serde(default)]
+        purged: bool,
+        /// `None` for accounts created before the terms were recorded.
+        #[serde(default)]
+        terms: Option<Terms>,
+    },
+}
+
+impl State {
+    /// The name of this state, as used in the transition table.
+    pub fn name(&self) -> &'static str {
+        match self {
+            State::NonExistent => NON_EXISTENT,
+            State::Created { .. } => CREATED,
+            State::Closed { .. } => CLOSED,
+        }
+    }
+
+    /// The [Iban], if created.
+    pub fn iban(&self) -> Option<&Iban> {
+        match self {
+            State::NonExistent => None,
+            State::Created { iban, .. } | State::Closed { iban, .. } => Some(iban),
+        }
+    }
+
+    /// The [Currency], if created.
+    pub fn currency(&self) -> Option<Currency> {
+        match self {
+            State::NonExistent => None,
+            State::Created { currency, .. } | State::Closed { currency, .. } => Some(*currency),
+        }
+    }
+
+    /// The balance, if created, which is zero if closed.
+    pub fn balance(&self) -> Option<EuroCent> {
+        match self {
+            State::NonExistent => None,
+            State::Created { balance, .. } => Some(*balance),
+            State::Closed { .. } => Some(EuroCent::default()),
+        }
+    }
+
+    /// Whether the given command is a [Cmd::Create] which would have created this account as it
+    /// is, such that repeating it is idempotent rather than conflicting. Accounts created before
+    /// their terms were recorded are only compared by tenant, IBAN and currency.
+    pub fn is_created_by(&self, cmd: &Cmd) -> bool {
+        let Cmd::Create {
+            id,
+            tenant,
+            customer,
+            iban,
+            currency,
+            opening_balance,
+            external_ref,
+            product,
+            sandbox,
+        } = cmd
+        else {
+            return false;
+        };
+
+        match self {
+            State::NonExistent => false,
+            State::Created {
+                id: created_id,
+                tenant: created_tenant,
+                iban: created_iban,
+                currency: created_currency,
+                terms,
+                ..
+            }
+            | State::Closed {
+                id: created_id,
+                tenant: created_tenant,
+                iban: created_iban,
+                currency: created_currency,
+                terms,
+                ..
+            } => {
+                created_id == id
+                    && created_tenant == tenant
+                    && created_iban == iban
+                    && created_currency == currency
+                    && terms.as_ref().map_or(true, |terms| {
+                        terms.customer == *customer
+                            && terms.opening_balance == *opening_balance
+                            && terms.external_ref == *external_ref
+                            && terms.product == *product
+                            && terms.sandbox == *sandbox
+                    })
+            }
+        }
+    }
+
+    /// Whether this is a sandbox account holding test data.
+    pub fn is_sandbox(&self) -> bool {
+        match self {
+            State::NonExistent => false,
+            State::Created { terms, .. } | State::Closed { terms, .. } => {
+                terms.as_ref().is_some_and(|terms| terms.sandbox)
+            }
+        }
+    }
+
+    /// The balance minus all holds, if created.
+    pub fn available_balance(&self) -> Option<EuroCent> {
+        match self {
+            State::NonExistent => None,
+            State::Closed { .. } => Some(EuroCent::default()),
+            State::Created { balance, holds, .. } => {
+                let held = holds
+                    .values()
+                    .fold(EuroCent::default(), |held, amount| held + *amount);
+                Some(if held < *balance {
+                    *balance - held
+                } else {
+                    EuroCent::default()
+                })
+            }
+        }
+    }
+}
+
+/// The terms an account has been created with, i.e. the parameters of [Cmd::Create] not otherwise
+/// part of its [State].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Terms {
+    pub customer: Option<CustomerId>,
+    pub opening_balance: EuroCent,
+    pub external_ref: Option<ExternalRef>,
+    pub product: Product,
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+/// The [State] of an [Account] along with its sequence number, i.e. the number of events it is
+/// based on.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub state: State,
+    pub seq_no: u64,
 }
 
 /// Command handler errors for an eventsourced [Account].
@@ -69,11 +1550,62 @@ pub enum Error {
         withdraw_amount: EuroCent,
     },
 
-    #[error("This account has not been created yet")]
-    NotYetCreated,
+    #[error("This account has not been created yet")]
+    NotYetCreated,
+
+    #[error("This account has already been created")]
+    AlreadyCreated,
+
+    #[error(
+        "Balance '{balance}' plus amount '{amount}' would exceed maximum balance '{max_balance}'"
+    )]
+    MaxBalanceExceeded {
+        balance: EuroCent,
+        amount: EuroCent,
+        max_balance: EuroCent,
+    },
+
+    #[error("Hold '{0}' has already been placed")]
+    HoldAlreadyPlaced(Uuid),
+
+    #[error("Unknown hold '{0}'")]
+    UnknownHold(Uuid),
+
+    #[error("Amount '{amount}' exceeds hold amount '{hold_amount}'")]
+    InvalidCapture {
+        hold_amount: EuroCent,
+        amount: EuroCent,
+    },
+
+    #[error("Adjustment amount must be positive")]
+    ZeroAdjustment,
+
+    #[error("Adjustment requires a justification")]
+    MissingJustification,
+
+    #[error("This account has been closed")]
+    Closed,
+
+    #[error("Cannot close account with {0} active holds")]
+    ActiveHolds(usize),
+
+    #[error("Cannot close account with balance '{0}' without an account to sweep it to")]
+    NonZeroBalance(EuroCent),
+
+    #[error("Cannot sweep the balance of an account to itself")]
+    SweepToSelf,
+
+    #[error("This account has not been closed")]
+    NotClosed,
 
-    #[error("This account has already been created")]
-    AlreadyCreated,
+    #[error("This account has been closed at '{closed_at}', too long ago to be reopened")]
+    ReopenWindowExpired { closed_at: OffsetDateTime },
+
+    #[error("This account has been purged")]
+    Purged,
+
+    #[error("This account has been closed at '{closed_at}', too recently to be purged")]
+    RetentionNotExpired { closed_at: OffsetDateTime },
 }
 
 impl EventSourced for Account {
@@ -81,154 +1613,1064 @@ impl EventSourced for Account {
 
     type Evt = Evt;
 
-    type State = State;
+    type State = Snapshot;
 
     type Error = Error;
 
     fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
-        debug!(?cmd, "Handling command");
+        self.decide(cmd).map(|evt| {
+            let tag = evt.tag();
+            evt.with_tag(tag)
+        })
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
 
-        match (self.state, cmd) {
+        match (&mut self.state, evt) {
             // In State::NonExistent:
-            (State::NonExistent, Cmd::Create(id)) => {
-                Ok(Evt::Created(id).with_tag(ACCOUNT_LIFECYCLE_TAG))
-            }
-            (State::NonExistent, other) => {
-                error!("Cannot handle command '{other:?}' in state NonExistent");
-                Err(Error::NotYetCreated)
+            (
+                State::NonExistent,
+                Evt::Created {
+                    id,
+                    tenant,
+                    customer,
+                    iban,
+                    currency,
+                    opening_balance,
+                    external_ref,
+                    product,
+                    sandbox,
+                    ..
+                },
+            ) => {
+                self.state = State::Created {
+                    id,
+                    tenant,
+                    iban,
+                    currency,
+                    balance: opening_balance,
+                    notification_prefs: NotificationPrefs::default(),
+                    holds: BTreeMap::new(),
+                    terms: Some(Terms {
+                        customer,
+                        opening_balance,
+                        external_ref,
+                        product,
+                        sandbox,
+                    }),
+                }
             }
 
+            (State::NonExistent, evt) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+
             // In State::Created:
-            (State::Created { balance, .. }, Cmd::Deposit(id, amount)) => Ok(Evt::Deposited {
-                id,
-                old_balance: balance,
-                amount,
+            (State::Created { balance, .. }, Evt::Deposited { amount, .. }) => {
+                *balance = *balance + amount;
             }
-            .into_tagged_evt()),
-            (State::Created { balance, .. }, Cmd::Withdraw(_, amount)) if balance < amount => {
-                Err(Error::InvalidWithdraw {
-                    balance,
-                    withdraw_amount: amount,
-                })
+
+            (State::Created { balance, .. }, Evt::Withdrawn { amount, .. }) => {
+                *balance = *balance - amount;
             }
-            (State::Created { balance, .. }, Cmd::Withdraw(id, amount)) => Ok(Evt::Withdrawn {
-                id,
-                old_balance: balance,
-                amount,
+
+            (
+                State::Created {
+                    notification_prefs, ..
+                },
+                Evt::NotificationPrefsSet {
+                    notification_prefs: new_notification_prefs,
+                    ..
+                },
+            ) => {
+                *notification_prefs = new_notification_prefs;
             }
-            .into_tagged_evt()),
-            (State::Created { .. }, other) => {
-                error!("Cannot handle command '{other:?}' in state Created");
-                Err(Error::AlreadyCreated)
+
+            // Labels are only used by the projections.
+            (State::Created { .. }, Evt::LabelsSet { .. }) => {}
+
+            (State::Created { holds, .. }, Evt::HoldPlaced { id, amount, .. }) => {
+                holds.insert(id, amount);
             }
-        }
-    }
 
-    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
-        debug!(?evt, "Handling event");
+            (State::Created { balance, holds, .. }, Evt::HoldCaptured { id, amount, .. }) => {
+                holds.remove(&id);
+                *balance = *balance - amount;
+            }
 
-        match (self.state, evt) {
-            // In State::NonExistent:
-            (State::NonExistent, Evt::Created(id)) => self.set_state(State::Created {
-                id,
-                balance: EuroCent::default(),
-            }),
+            (State::Created { holds, .. }, Evt::HoldReleased { id, .. }) => {
+                holds.remove(&id);
+            }
 
-            (State::NonExistent, _) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+            (
+                State::Created { balance, .. },
+                Evt::Adjusted {
+                    direction, amount, ..
+                },
+            ) => {
+                *balance = direction.apply(*balance, amount);
+            }
 
-            // In State::Created:
             (
-                State::Created { id, balance },
-                Evt::Deposited {
-                    id: _,
-                    old_balance: _,
-                    amount,
+                State::Created {
+                    id,
+                    tenant,
+                    iban,
+                    currency,
+                    notification_prefs,
+                    terms,
+                    ..
                 },
-            ) => self.set_state(State::Created {
-                id,
-                balance: balance + amount,
-            }),
+                Evt::Closed {
+                    old_balance,
+                    sweep_to,
+                    at,
+                    ..
+                },
+            ) => {
+                self.state = State::Closed {
+                    id: *id,
+                    tenant: *tenant,
+                    iban: *iban,
+                    currency: *currency,
+                    notification_prefs: *notification_prefs,
+                    final_balance: old_balance,
+                    sweep_to,
+                    closed_at: at,
+                    purged: false,
+                    terms: terms.take(),
+                };
+            }
 
+            (State::Created { .. }, evt) => panic!("Illegal event '{evt:?}' in state Created"),
+
+            // In State::Closed:
             (
-                State::Created { id, balance },
-                Evt::Withdrawn {
-                    id: _,
-                    old_balance: _,
-                    amount,
+                State::Closed {
+                    id,
+                    tenant,
+                    iban,
+                    currency,
+                    notification_prefs,
+                    final_balance,
+                    sweep_to,
+                    terms,
+                    ..
                 },
-            ) => self.set_state(State::Created {
-                id,
-                balance: balance - amount,
-            }),
+                Evt::Reopened { .. },
+            ) => {
+                // A swept balance has left the account, else the final balance is restored.
+                let balance = match sweep_to {
+                    Some(_) => EuroCent::default(),
+                    None => *final_balance,
+                };
+                self.state = State::Created {
+                    id: *id,
+                    tenant: *tenant,
+                    iban: *iban,
+                    currency: *currency,
+                    balance,
+                    notification_prefs: *notification_prefs,
+                    holds: BTreeMap::new(),
+                    terms: terms.take(),
+                };
+            }
 
-            (State::Created { .. }, _) => panic!("Illegal event '{evt:?}' in state Created"),
+            (State::Closed { purged, .. }, Evt::Purged { .. }) => *purged = true,
+
+            (State::Closed { .. }, evt) => panic!("Illegal event '{evt:?}' in state Closed"),
         }
 
-        self.evt_count += 1;
+        self.seq_no += 1;
+        self.snapshots.send_replace(self.snapshot());
+
         self.snapshot_after
-            .filter(|snapshot_after| self.evt_count % snapshot_after.get() == 0)
+            .filter(|snapshot_after| self.seq_no % snapshot_after.get() == 0)
             .map(|_| {
-                debug!(self.evt_count, "Taking snapshot");
-                self.state
+                debug!(self.seq_no, "Taking snapshot");
+                self.snapshot()
             })
     }
 
-    fn set_state(&mut self, state: Self::State) {
-        self.state = state
+    fn set_state(&mut self, Snapshot { state, seq_no }: Self::State) {
+        self.state = state;
+        self.seq_no = seq_no;
+        self.snapshots.send_replace(self.snapshot());
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::{clock::ManualClock, state_machine::Outcome};
+    use std::{collections::BTreeSet, fmt::Debug};
 
     #[test]
     fn test_handle_cmd_and_evt() {
-        let mut account = Account::default();
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut account = Account::default().with_clock(Arc::new(clock.clone()));
 
         // Command Deposit fails in state NotCreated.
         assert!(account
-            .handle_cmd(Cmd::Deposit(Uuid::now_v7(), 1u64.into()))
+            .handle_cmd(Cmd::Deposit(Uuid::now_v7(), 1u64.into(), None, None))
             .is_err());
 
         // Command Withdraw fails in state NotCreated.
         assert!(account
-            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into()))
+            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into(), None, None))
             .is_err());
 
         // Command Create succeeds in state NotCreated.
-        assert!(account.handle_cmd(Cmd::Create(Uuid::now_v7())).is_ok());
+        let account_id = Uuid::now_v7();
+        assert!(account
+            .handle_cmd(create(account_id, EuroCent::default()))
+            .is_ok());
 
         // Handle event Created.
-        account.handle_evt(Evt::Created(Uuid::now_v7()));
+        account.handle_evt(created(account_id, EuroCent::default()));
+
+        // Command Create fails in state Created.
+        assert!(account
+            .handle_cmd(create(account_id, EuroCent::default()))
+            .is_err());
 
         // Command Withdraw fails in state Created with insufficient balance.
         assert!(account
-            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into()))
+            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into(), None, None))
             .is_err());
 
         // Handle event Deposited.
         account.handle_evt(Evt::Deposited {
             id: Uuid::now_v7(),
+            account_id,
             old_balance: 0u64.into(),
             amount: 1u64.into(),
+            value_date: None,
+            channel: None,
+            at: clock.now(),
         });
 
         // Command Withdraw succeeds in state Created.
         assert!(account
-            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into()))
+            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into(), None, None))
             .is_ok());
 
         // Handle event Withdrawn.
         account.handle_evt(Evt::Withdrawn {
             id: Uuid::now_v7(),
+            account_id,
             old_balance: 1u64.into(),
             amount: 1u64.into(),
+            value_date: None,
+            channel: None,
+            at: clock.now(),
         });
 
         // Command Withdraw fails in state Created with insufficient balance.
         assert!(account
-            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into()))
+            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 1u64.into(), None, None))
+            .is_err());
+    }
+
+    #[test]
+    fn test_deserialize_created() {
+        let id = Uuid::now_v7();
+        let iban = Iban::for_account(BankCode::try_from(12345678).unwrap(), id);
+        let evt = Evt::Created {
+            id,
+            tenant: TenantId::default(),
+            customer: None,
+            iban,
+            currency: Currency::default(),
+            opening_balance: 42.into(),
+            external_ref: None,
+            product: Product::default(),
+            sandbox: false,
+            at: OffsetDateTime::UNIX_EPOCH,
+        };
+        let json = serde_json::to_string(&evt).unwrap();
+        assert_eq!(serde_json::from_str::<Evt>(&json).unwrap(), evt);
+
+        // Legacy events only carry the ID.
+        let evt = serde_json::from_str::<Evt>(&format!(r#"{{"Created":"{id}"}}"#)).unwrap();
+        let Evt::Created {
+            id: created_id,
+            iban,
+            opening_balance,
+            at,
+            ..
+        } = evt
+        else {
+            panic!("Expected Created event");
+        };
+        assert_eq!(created_id, id);
+        assert_eq!(iban, Iban::for_account(BankCode::try_from(0).unwrap(), id));
+        assert_eq!(opening_balance, EuroCent::default());
+        assert_eq!(at, OffsetDateTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let mut account = Account::default();
+        let snapshots = account.subscribe();
+
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, EuroCent::default()));
+        account.handle_evt(Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 0u64.into(),
+            amount: 42u64.into(),
+            value_date: None,
+            channel: None,
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert_eq!(
+            *snapshots.borrow(),
+            Snapshot {
+                state: State::Created {
+                    id,
+                    tenant: TenantId::default(),
+                    iban: iban(id),
+                    currency: Currency::EUR,
+                    balance: 42u64.into(),
+                    notification_prefs: NotificationPrefs::default(),
+                    holds: BTreeMap::new(),
+                    terms: Some(Terms {
+                        customer: None,
+                        opening_balance: EuroCent::default(),
+                        external_ref: None,
+                        product: Product::Standard,
+                        sandbox: false,
+                    }),
+                },
+                seq_no: 2
+            }
+        );
+
+        account.set_state(Snapshot {
+            state: State::Created {
+                id,
+                tenant: TenantId::default(),
+                iban: iban(id),
+                currency: Currency::EUR,
+                balance: 666u64.into(),
+                notification_prefs: NotificationPrefs::default(),
+                holds: BTreeMap::new(),
+                terms: None,
+            },
+            seq_no: 42,
+        });
+        assert_eq!(snapshots.borrow().seq_no, 42);
+        assert_eq!(snapshots.borrow().state.balance(), Some(666u64.into()));
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let mut account = Account::default();
+        let snapshots = account.subscribe();
+
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, 42u64.into()));
+
+        let result = account.dry_run(Cmd::Withdraw(Uuid::now_v7(), 40u64.into(), None, None));
+        assert!(matches!(
+            result,
+            Ok((Evt::Withdrawn { .. }, Snapshot { seq_no: 2, .. }))
+        ));
+        assert_eq!(
+            result.unwrap().1.state.balance(),
+            Some(EuroCent::from(2u64))
+        );
+
+        // Dry runs neither change the state nor publish snapshots.
+        assert_eq!(account.state.balance(), Some(42u64.into()));
+        assert_eq!(snapshots.borrow().seq_no, 1);
+
+        assert!(account
+            .dry_run(Cmd::Withdraw(Uuid::now_v7(), 43u64.into(), None, None))
+            .is_err());
+    }
+
+    #[test]
+    fn test_create_with_opening_balance() {
+        let mut account = Account::default();
+
+        let id = Uuid::now_v7();
+        assert!(account.handle_cmd(create(id, 42u64.into())).is_ok());
+
+        account.handle_evt(created(id, 42u64.into()));
+        assert_eq!(account.state.balance(), Some(42u64.into()));
+
+        // Opening balance can be withdrawn right away.
+        assert!(account
+            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 42u64.into(), None, None))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_max_balance() {
+        let max_balances = MaxBalances {
+            standard: Some(100u64.into()),
+            merchant: None,
+        };
+        let mut account = Account::default().with_max_balances(max_balances);
+
+        let id = Uuid::now_v7();
+        assert!(matches!(
+            account.handle_cmd(create(id, 101u64.into())),
+            Err(Error::MaxBalanceExceeded { .. })
+        ));
+        assert!(account.handle_cmd(create(id, 100u64.into())).is_ok());
+
+        account.handle_evt(created(id, 60u64.into()));
+        assert!(account
+            .handle_cmd(Cmd::Deposit(Uuid::now_v7(), 40u64.into(), None, None))
+            .is_ok());
+        assert!(matches!(
+            account.handle_cmd(Cmd::Deposit(Uuid::now_v7(), 41u64.into(), None, None)),
+            Err(Error::MaxBalanceExceeded {
+                balance,
+                amount,
+                max_balance,
+            }) if balance == 60u64.into() && amount == 41u64.into() && max_balance == 100u64.into()
+        ));
+
+        // Merchant accounts are unlimited.
+        let mut evt = created(id, 60u64.into());
+        if let Evt::Created { product, .. } = &mut evt {
+            *product = Product::Merchant {
+                settlement_account: Uuid::now_v7(),
+            };
+        }
+        let mut account = Account::default().with_max_balances(max_balances);
+        account.handle_evt(evt);
+        assert!(account
+            .handle_cmd(Cmd::Deposit(Uuid::now_v7(), 41u64.into(), None, None))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_set_notification_prefs() {
+        let mut account = Account::default();
+        let notification_prefs = NotificationPrefs {
+            channels: NotificationChannels {
+                email: true,
+                ..Default::default()
+            },
+            withdrawal_threshold: Some(10_000u64.into()),
+            low_balance_threshold: None,
+        };
+
+        // Command SetNotificationPrefs fails in state NonExistent.
+        assert!(account
+            .handle_cmd(Cmd::SetNotificationPrefs(notification_prefs))
+            .is_err());
+
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, EuroCent::default()));
+
+        // Command SetNotificationPrefs succeeds in state Created.
+        assert!(account
+            .handle_cmd(Cmd::SetNotificationPrefs(notification_prefs))
+            .is_ok());
+
+        account.handle_evt(Evt::NotificationPrefsSet {
+            account_id: id,
+            notification_prefs,
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert!(matches!(
+            account.state,
+            State::Created { notification_prefs: prefs, .. } if prefs == notification_prefs
+        ));
+    }
+
+    #[test]
+    fn test_holds() {
+        let mut account = Account::default();
+
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, 100u64.into()));
+
+        // Command PlaceHold fails with insufficient balance.
+        let hold_id = Uuid::now_v7();
+        assert!(account
+            .handle_cmd(Cmd::PlaceHold(hold_id, 101u64.into()))
+            .is_err());
+
+        // Command PlaceHold succeeds with sufficient balance.
+        assert!(account
+            .handle_cmd(Cmd::PlaceHold(hold_id, 80u64.into()))
+            .is_ok());
+        account.handle_evt(Evt::HoldPlaced {
+            id: hold_id,
+            account_id: id,
+            amount: 80u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert_eq!(account.state.balance(), Some(100u64.into()));
+        assert_eq!(account.state.available_balance(), Some(20u64.into()));
+
+        // Held amounts cannot be withdrawn.
+        assert!(account
+            .handle_cmd(Cmd::Withdraw(Uuid::now_v7(), 21u64.into(), None, None))
+            .is_err());
+
+        // Command CaptureHold fails for unknown holds or amounts exceeding the hold.
+        assert!(account
+            .handle_cmd(Cmd::CaptureHold(Uuid::now_v7(), 10u64.into()))
+            .is_err());
+        assert!(account
+            .handle_cmd(Cmd::CaptureHold(hold_id, 81u64.into()))
+            .is_err());
+
+        // Command CaptureHold succeeds for an amount up to the hold.
+        assert!(account
+            .handle_cmd(Cmd::CaptureHold(hold_id, 70u64.into()))
+            .is_ok());
+        account.handle_evt(Evt::HoldCaptured {
+            id: hold_id,
+            account_id: id,
+            old_balance: 100u64.into(),
+            amount: 70u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert_eq!(account.state.balance(), Some(30u64.into()));
+        assert_eq!(account.state.available_balance(), Some(30u64.into()));
+
+        // Captured holds cannot be released.
+        assert!(account.handle_cmd(Cmd::ReleaseHold(hold_id)).is_err());
+    }
+
+    #[test]
+    fn test_external_ref() {
+        assert!(ExternalRef::try_from("legacy-42".to_string()).is_ok());
+        assert!(ExternalRef::try_from("".to_string()).is_err());
+        assert!(ExternalRef::try_from("legacy 42".to_string()).is_err());
+        assert!(ExternalRef::try_from("x".repeat(MAX_EXTERNAL_REF_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_is_created_by() {
+        let mut account = Account::default();
+        let id = Uuid::now_v7();
+        assert!(!account.state.is_created_by(&create(id, 42u64.into())));
+
+        account.handle_evt(created(id, 42u64.into()));
+        assert!(!account.state.is_sandbox());
+        assert!(account.state.is_created_by(&create(id, 42u64.into())));
+        assert!(!account.state.is_created_by(&create(id, 666u64.into())));
+        let mut cmd = create(id, 42u64.into());
+        if let Cmd::Create { currency, .. } = &mut cmd {
+            *currency = "USD".parse().unwrap();
+        }
+        assert!(!account.state.is_created_by(&cmd));
+        assert!(!account.state.is_created_by(&close(None)));
+
+        // Without recorded terms, only tenant, IBAN and currency are compared.
+        if let State::Created { terms, .. } = &mut account.state {
+            *terms = None;
+        }
+        assert!(account.state.is_created_by(&create(id, 666u64.into())));
+    }
+
+    #[test]
+    fn test_labels() {
+        let label = "team=payroll".parse::<Label>().unwrap();
+        assert!("team".parse::<Label>().is_err());
+        assert!("team=".parse::<Label>().is_err());
+        assert!("team=pay roll".parse::<Label>().is_err());
+
+        let labels = Labels::try_from(BTreeMap::from([(
+            "team".to_string(),
+            "payroll".to_string(),
+        )]))
+        .unwrap();
+        assert!(labels.contains(&label));
+        assert!(!Labels::default().contains(&label));
+
+        let too_many = (0..=MAX_LABELS)
+            .map(|n| (n.to_string(), n.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        assert!(Labels::try_from(too_many).is_err());
+        assert!(Labels::try_from(BTreeMap::from([("a=b".to_string(), "c".to_string())])).is_err());
+    }
+
+    #[test]
+    fn test_adjust() {
+        let mut account = Account::default();
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, 42u64.into()));
+
+        let adjust = |direction, amount: u64, justification: &str| Cmd::Adjust {
+            id: Uuid::now_v7(),
+            direction,
+            amount: amount.into(),
+            reason: ReasonCode::BookingError,
+            justification: justification.to_string(),
+        };
+
+        // Adjustments require a positive amount and a justification.
+        assert!(account
+            .handle_cmd(adjust(AdjustmentDirection::Credit, 0, "Typo"))
+            .is_err());
+        assert!(account
+            .handle_cmd(adjust(AdjustmentDirection::Credit, 1, " "))
+            .is_err());
+
+        // Debits must not exceed the balance.
+        assert!(account
+            .handle_cmd(adjust(AdjustmentDirection::Debit, 43, "Typo"))
             .is_err());
+
+        let result = account.dry_run(adjust(AdjustmentDirection::Debit, 40, "Typo"));
+        assert!(matches!(result, Ok((Evt::Adjusted { .. }, _))));
+        let (evt, _) = result.unwrap();
+        account.handle_evt(evt);
+        assert_eq!(account.state.balance(), Some(2u64.into()));
+    }
+
+    #[test]
+    fn test_adjust_held_amount() {
+        let mut account = Account::default();
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, 10u64.into()));
+
+        let hold_id = Uuid::now_v7();
+        account.handle_evt(Evt::HoldPlaced {
+            id: hold_id,
+            account_id: id,
+            amount: 10u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+
+        // Debits must not spend held amounts.
+        let debit = Cmd::Adjust {
+            id: Uuid::now_v7(),
+            direction: AdjustmentDirection::Debit,
+            amount: 10u64.into(),
+            reason: ReasonCode::BookingError,
+            justification: "Typo".to_string(),
+        };
+        assert!(matches!(
+            account.handle_cmd(debit),
+            Err(Error::InvalidWithdraw { balance, .. }) if balance == EuroCent::default()
+        ));
+
+        // Hence the hold can still be captured in full.
+        let result = account.dry_run(Cmd::CaptureHold(hold_id, 10u64.into()));
+        assert!(matches!(result, Ok((Evt::HoldCaptured { .. }, _))));
+        let (evt, _) = result.unwrap();
+        account.handle_evt(evt);
+        assert_eq!(account.state.balance(), Some(EuroCent::default()));
+    }
+
+    #[test]
+    fn test_close_sandbox() {
+        let mut account = Account::default();
+        let id = Uuid::now_v7();
+        account.handle_evt(Evt::Created {
+            id,
+            tenant: TenantId::default(),
+            customer: None,
+            iban: iban(id),
+            currency: Currency::EUR,
+            opening_balance: 42u64.into(),
+            external_ref: None,
+            product: Product::Standard,
+            sandbox: true,
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert!(account.state.is_sandbox());
+
+        // The balance of sandbox accounts is test data, discarded when closing without a sweep.
+        let result = account.dry_run(close(None));
+        assert!(matches!(
+            result,
+            Ok((Evt::Closed { sweep_to: None, .. }, _))
+        ));
+    }
+
+    #[test]
+    fn test_close() {
+        let mut account = Account::default();
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, 42u64.into()));
+        let hold_id = Uuid::now_v7();
+        account.handle_evt(Evt::HoldPlaced {
+            id: hold_id,
+            account_id: id,
+            amount: 2u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+
+        // Accounts with active holds cannot be closed, not even with a sweep.
+        assert!(matches!(
+            account.handle_cmd(close(Some(Uuid::now_v7()))),
+            Err(Error::ActiveHolds(1))
+        ));
+        account.handle_evt(Evt::HoldReleased {
+            id: hold_id,
+            account_id: id,
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+
+        // Accounts with a non-zero balance can only be closed with a sweep to another account.
+        assert!(matches!(
+            account.handle_cmd(close(None)),
+            Err(Error::NonZeroBalance(balance)) if balance == 42u64.into()
+        ));
+        assert!(matches!(
+            account.handle_cmd(close(Some(id))),
+            Err(Error::SweepToSelf)
+        ));
+        let sweep_to = Uuid::now_v7();
+        let result = account.dry_run(close(Some(sweep_to)));
+        assert!(matches!(
+            result,
+            Ok((Evt::Closed { old_balance, sweep_to: Some(to), .. }, _))
+                if old_balance == 42u64.into() && to == sweep_to
+        ));
+        let (evt, snapshot) = result.unwrap();
+        assert_eq!(snapshot.state.balance(), Some(EuroCent::default()));
+        account.handle_evt(evt);
+        assert!(matches!(
+            account.state,
+            State::Closed { final_balance, sweep_to: Some(to), .. }
+                if final_balance == 42u64.into() && to == sweep_to
+        ));
+
+        // Accounts with a zero balance can be closed without a sweep.
+        let mut account = Account::default();
+        account.handle_evt(created(id, EuroCent::default()));
+        assert!(account.handle_cmd(close(None)).is_ok());
+        account.handle_evt(closed_evt(id, EuroCent::default(), None));
+
+        // Closed accounts reject further commands.
+        assert!(matches!(
+            account.handle_cmd(Cmd::Deposit(Uuid::now_v7(), 1u64.into(), None, None)),
+            Err(Error::Closed)
+        ));
+        assert!(matches!(
+            account.handle_cmd(close(None)),
+            Err(Error::Closed)
+        ));
+    }
+
+    #[test]
+    fn test_reopen() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut account = Account::default().with_clock(Arc::new(clock.clone()));
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, EuroCent::default()));
+        account.handle_evt(Evt::NotificationPrefsSet {
+            account_id: id,
+            notification_prefs: NotificationPrefs {
+                withdrawal_threshold: Some(42u64.into()),
+                ..Default::default()
+            },
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        let open_state = account.state.clone();
+
+        // Only closed accounts can be reopened.
+        assert!(matches!(
+            account.handle_cmd(reopen("mistake", 30)),
+            Err(Error::NotClosed)
+        ));
+        account.handle_evt(closed_evt(id, EuroCent::default(), None));
+
+        // Reopening requires a justification and is only possible within the window.
+        assert!(matches!(
+            account.handle_cmd(reopen(" ", 30)),
+            Err(Error::MissingJustification)
+        ));
+        clock.advance(Duration::days(31));
+        assert!(matches!(
+            account.handle_cmd(reopen("mistake", 30)),
+            Err(Error::ReopenWindowExpired { .. })
+        ));
+
+        // Reopening restores the previous state.
+        let result = account.dry_run(reopen("mistake", 31));
+        assert!(matches!(result, Ok((Evt::Reopened { .. }, _))));
+        let (evt, _) = result.unwrap();
+        account.handle_evt(evt);
+        assert_eq!(account.state, open_state);
+    }
+
+    #[test]
+    fn test_purge() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut account = Account::default().with_clock(Arc::new(clock.clone()));
+        let id = Uuid::now_v7();
+        account.handle_evt(created(id, EuroCent::default()));
+
+        // Only closed accounts can be purged, after the retention period.
+        assert!(matches!(
+            account.handle_cmd(Cmd::Purge { retention_days: 0 }),
+            Err(Error::NotClosed)
+        ));
+        account.handle_evt(closed_evt(id, EuroCent::default(), None));
+        clock.advance(Duration::days(10));
+        assert!(matches!(
+            account.handle_cmd(Cmd::Purge { retention_days: 10 }),
+            Err(Error::RetentionNotExpired { .. })
+        ));
+        let result = account.dry_run(Cmd::Purge { retention_days: 9 });
+        assert!(matches!(result, Ok((Evt::Purged { .. }, _))));
+        let (evt, _) = result.unwrap();
+        account.handle_evt(evt);
+
+        // Purged accounts can neither be purged again nor reopened.
+        assert!(matches!(
+            account.handle_cmd(Cmd::Purge { retention_days: 9 }),
+            Err(Error::Purged)
+        ));
+        assert!(matches!(
+            account.handle_cmd(reopen("mistake", u16::MAX)),
+            Err(Error::Purged)
+        ));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let account_id = Uuid::now_v7();
+        let hold_id = Uuid::now_v7();
+        let max_balances = MaxBalances {
+            standard: Some(10u64.into()),
+            merchant: None,
+        };
+        let non_existent = Account::default();
+        let limited = Account::default().with_max_balances(max_balances);
+        let mut existing = Account::default();
+        existing.handle_evt(created(account_id, 10u64.into()));
+        let at_max_balance = existing.clone().with_max_balances(max_balances);
+        let settled = existing.clone();
+        existing.handle_evt(Evt::HoldPlaced {
+            id: hold_id,
+            account_id,
+            amount: 5u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        let mut closed = settled.clone();
+        closed.handle_evt(closed_evt(account_id, 10u64.into(), Some(Uuid::now_v7())));
+        let mut purged = closed.clone();
+        purged.handle_evt(Evt::Purged {
+            account_id,
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        let adjust = |direction, amount: u64, justification: &str| Cmd::Adjust {
+            id: Uuid::now_v7(),
+            direction,
+            amount: amount.into(),
+            reason: ReasonCode::BookingError,
+            justification: justification.to_string(),
+        };
+
+        let samples = [
+            (&limited, create(account_id, 11u64.into())),
+            (&non_existent, create(account_id, 0u64.into())),
+            (
+                &non_existent,
+                Cmd::Deposit(Uuid::now_v7(), 1u64.into(), None, None),
+            ),
+            (
+                &non_existent,
+                Cmd::Withdraw(Uuid::now_v7(), 1u64.into(), None, None),
+            ),
+            (&non_existent, Cmd::SetNotificationPrefs(Default::default())),
+            (&non_existent, Cmd::SetLabels(Default::default())),
+            (&non_existent, Cmd::PlaceHold(Uuid::now_v7(), 1u64.into())),
+            (&non_existent, Cmd::CaptureHold(hold_id, 1u64.into())),
+            (&non_existent, Cmd::ReleaseHold(hold_id)),
+            (
+                &non_existent,
+                adjust(AdjustmentDirection::Credit, 1, "typo"),
+            ),
+            (&non_existent, close(None)),
+            (&non_existent, reopen("mistake", 30)),
+            (&non_existent, Cmd::Purge { retention_days: 0 }),
+            (&existing, create(account_id, 0u64.into())),
+            (
+                &at_max_balance,
+                Cmd::Deposit(Uuid::now_v7(), 1u64.into(), None, None),
+            ),
+            (
+                &existing,
+                Cmd::Deposit(Uuid::now_v7(), 1u64.into(), None, None),
+            ),
+            (
+                &existing,
+                Cmd::Withdraw(Uuid::now_v7(), 6u64.into(), None, None),
+            ),
+            (
+                &existing,
+                Cmd::Withdraw(Uuid::now_v7(), 5u64.into(), None, None),
+            ),
+            (&existing, Cmd::SetNotificationPrefs(Default::default())),
+            (&existing, Cmd::SetLabels(Default::default())),
+            (&existing, Cmd::PlaceHold(hold_id, 1u64.into())),
+            (&existing, Cmd::PlaceHold(Uuid::now_v7(), 6u64.into())),
+            (&existing, Cmd::PlaceHold(Uuid::now_v7(), 5u64.into())),
+            (&existing, Cmd::CaptureHold(Uuid::now_v7(), 1u64.into())),
+            (&existing, Cmd::CaptureHold(hold_id, 6u64.into())),
+            (&existing, Cmd::CaptureHold(hold_id, 5u64.into())),
+            (&existing, Cmd::ReleaseHold(Uuid::now_v7())),
+            (&existing, Cmd::ReleaseHold(hold_id)),
+            (&existing, adjust(AdjustmentDirection::Credit, 0, "typo")),
+            (&existing, adjust(AdjustmentDirection::Credit, 1, " ")),
+            (&existing, adjust(AdjustmentDirection::Debit, 11, "typo")),
+            (&existing, adjust(AdjustmentDirection::Debit, 10, "typo")),
+            (&existing, close(Some(Uuid::now_v7()))),
+            (&settled, close(Some(account_id))),
+            (&settled, close(None)),
+            (&settled, close(Some(Uuid::now_v7()))),
+            (&existing, reopen("mistake", 30)),
+            (&existing, Cmd::Purge { retention_days: 0 }),
+            (&closed, create(account_id, 0u64.into())),
+            (
+                &closed,
+                Cmd::Deposit(Uuid::now_v7(), 1u64.into(), None, None),
+            ),
+            (
+                &closed,
+                Cmd::Withdraw(Uuid::now_v7(), 1u64.into(), None, None),
+            ),
+            (&closed, Cmd::SetNotificationPrefs(Default::default())),
+            (&closed, Cmd::SetLabels(Default::default())),
+            (&closed, Cmd::PlaceHold(Uuid::now_v7(), 1u64.into())),
+            (&closed, Cmd::CaptureHold(hold_id, 1u64.into())),
+            (&closed, Cmd::ReleaseHold(hold_id)),
+            (&closed, adjust(AdjustmentDirection::Credit, 1, "typo")),
+            (&closed, close(None)),
+            (&purged, reopen("mistake", u16::MAX)),
+            (&closed, reopen(" ", u16::MAX)),
+            (&closed, reopen("mistake", 0)),
+            (&closed, reopen("mistake", u16::MAX)),
+            (&purged, Cmd::Purge { retention_days: 0 }),
+            (
+                &closed,
+                Cmd::Purge {
+                    retention_days: u16::MAX,
+                },
+            ),
+            (&closed, Cmd::Purge { retention_days: 0 }),
+        ];
+
+        // One sample per transition, in the same order, such that all transitions are covered.
+        let transitions = transitions();
+        assert_eq!(samples.len(), transitions.len());
+        for ((account, cmd), transition) in samples.into_iter().zip(transitions) {
+            let state = account.state.name();
+            assert_eq!(state, transition.state);
+            assert_eq!(cmd.name(), transition.cmd);
+
+            let outcome = match account.decide(cmd) {
+                Ok(evt) => {
+                    let mut account = account.clone();
+                    account.handle_evt(evt.clone());
+                    (variant(&evt), account.state.name())
+                }
+                Err(error) => (variant(&error), state),
+            };
+            let expected = match transition.outcome {
+                Outcome::Evt { evt, to } => (evt.to_string(), to),
+                Outcome::Rejected { error } => (error.to_string(), transition.state),
+            };
+            assert_eq!(outcome, expected, "{transition:?}");
+        }
+    }
+
+    #[test]
+    fn test_transition_table_is_exhaustive() {
+        let transitions = transitions();
+        let states = transitions.iter().map(|t| t.state).collect::<BTreeSet<_>>();
+        let cmds = transitions.iter().map(|t| t.cmd).collect::<BTreeSet<_>>();
+        assert_eq!(states, BTreeSet::from([NON_EXISTENT, CREATED, CLOSED]));
+
+        // Each command is covered in each state, with only the last rule having no guard.
+        for state in &states {
+            for cmd in &cmds {
+                let guards = transitions
+                    .iter()
+                    .filter(|t| t.state == *state && t.cmd == *cmd)
+                    .map(|t| t.guard)
+                    .collect::<Vec<_>>();
+                let (last, others) = guards
+                    .split_last()
+                    .unwrap_or_else(|| panic!("Command '{cmd}' not covered in state {state}"));
+                assert!(
+                    last.is_none(),
+                    "Last rule for '{cmd}' in {state} has a guard"
+                );
+                assert!(others.iter().all(Option::is_some));
+            }
+        }
+    }
+
+    /// The name of the enum variant of the given value.
+    fn variant(value: &impl Debug) -> String {
+        format!("{value:?}")
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect()
+    }
+
+    fn create(id: Uuid, opening_balance: EuroCent) -> Cmd {
+        Cmd::Create {
+            id,
+            tenant: TenantId::default(),
+            customer: None,
+            iban: iban(id),
+            currency: Currency::EUR,
+            opening_balance,
+            external_ref: None,
+            product: Product::Standard,
+            sandbox: false,
+        }
+    }
+
+    fn created(id: Uuid, opening_balance: EuroCent) -> Evt {
+        Evt::Created {
+            id,
+            tenant: TenantId::default(),
+            customer: None,
+            iban: iban(id),
+            currency: Currency::EUR,
+            opening_balance,
+            external_ref: None,
+            product: Product::Standard,
+            sandbox: false,
+            at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn close(sweep_to: Option<Uuid>) -> Cmd {
+        Cmd::Close {
+            id: Uuid::now_v7(),
+            sweep_to,
+        }
+    }
+
+    fn reopen(justification: &str, window_days: u16) -> Cmd {
+        Cmd::Reopen {
+            justification: justification.to_string(),
+            window_days,
+        }
+    }
+
+    fn closed_evt(account_id: Uuid, old_balance: EuroCent, sweep_to: Option<Uuid>) -> Evt {
+        Evt::Closed {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance,
+            sweep_to,
+            at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn iban(id: Uuid) -> Iban {
+        Iban::for_account(BankCode::try_from(12345678).unwrap(), id)
     }
 }
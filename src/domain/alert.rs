@@ -0,0 +1,176 @@
+use crate::domain::{
+    account::{Evt, NotificationPrefs},
+    euro_cent::EuroCent,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// An alert for the holder of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Alert {
+    LowBalance {
+        account_id: Uuid,
+        balance: EuroCent,
+        threshold: EuroCent,
+    },
+    LargeWithdrawal {
+        account_id: Uuid,
+        withdrawal_id: Uuid,
+        amount: EuroCent,
+        threshold: EuroCent,
+    },
+}
+
+/// Evaluates the alert rules of an account according to its [NotificationPrefs]. Low-balance
+/// alerts are deduplicated: these only fire when the balance drops below the threshold and not
+/// again until it has been restored.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AlertRules {
+    notification_prefs: NotificationPrefs,
+    balance: EuroCent,
+    below_threshold: bool,
+}
+
+impl AlertRules {
+    /// Evaluate the rules for the given event, returning the fired alerts.
+    pub fn evaluate(&mut self, evt: &Evt) -> Vec<Alert> {
+        let mut alerts = vec![];
+
+        match evt {
+            Evt::Created {
+                opening_balance, ..
+            } => self.balance = *opening_balance,
+
+            Evt::Deposited {
+                old_balance,
+                amount,
+                ..
+            } => self.balance = *old_balance + *amount,
+
+            Evt::Withdrawn {
+                id,
+                account_id,
+                old_balance,
+                amount,
+                ..
+            } => {
+                self.balance = *old_balance - *amount;
+                if let Some(threshold) = self.notification_prefs.withdrawal_threshold {
+                    if *amount > threshold {
+                        alerts.push(Alert::LargeWithdrawal {
+                            account_id: *account_id,
+                            withdrawal_id: *id,
+                            amount: *amount,
+                            threshold,
+                        });
+                    }
+                }
+            }
+
+            Evt::HoldCaptured {
+                old_balance,
+                amount,
+                ..
+            } => self.balance = *old_balance - *amount,
+
+            Evt::Adjusted {
+                old_balance,
+                direction,
+                amount,
+                ..
+            } => self.balance = direction.apply(*old_balance, *amount),
+
+            Evt::Closed { .. } => self.balance = EuroCent::default(),
+
+            Evt::NotificationPrefsSet {
+                notification_prefs, ..
+            } => self.notification_prefs = *notification_prefs,
+
+            Evt::LabelsSet { .. }
+            | Evt::HoldPlaced { .. }
+            | Evt::HoldReleased { .. }
+            | Evt::Reopened { .. }
+            | Evt::Purged { .. } => {}
+        }
+
+        let threshold = self.notification_prefs.low_balance_threshold;
+        let below_threshold = matches!(threshold, Some(threshold) if self.balance < threshold);
+        if let (Some(threshold), true, false) = (threshold, below_threshold, self.below_threshold) {
+            alerts.push(Alert::LowBalance {
+                account_id: evt.account_id(),
+                balance: self.balance,
+                threshold,
+            });
+        }
+        self.below_threshold = below_threshold;
+
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::OffsetDateTime;
+
+    #[test]
+    fn test_evaluate() {
+        let account_id = Uuid::now_v7();
+        let mut rules = AlertRules::default();
+
+        let alerts = rules.evaluate(&Evt::NotificationPrefsSet {
+            account_id,
+            notification_prefs: NotificationPrefs {
+                withdrawal_threshold: Some(500u64.into()),
+                low_balance_threshold: Some(100u64.into()),
+                ..Default::default()
+            },
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert_eq!(
+            alerts,
+            vec![Alert::LowBalance {
+                account_id,
+                balance: 0u64.into(),
+                threshold: 100u64.into()
+            }]
+        );
+
+        // Restoring the balance re-arms the low-balance alert.
+        let alerts = rules.evaluate(&deposited(account_id, 0, 1_000));
+        assert!(alerts.is_empty());
+
+        // Large withdrawal dropping the balance below the threshold fires both alerts.
+        let alerts = rules.evaluate(&withdrawn(account_id, 1_000, 950));
+        assert_eq!(alerts.len(), 2);
+
+        // Still below the threshold does not fire again.
+        let alerts = rules.evaluate(&withdrawn(account_id, 50, 10));
+        assert!(alerts.is_empty());
+    }
+
+    fn deposited(account_id: Uuid, old_balance: u64, amount: u64) -> Evt {
+        Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: old_balance.into(),
+            amount: amount.into(),
+            value_date: None,
+            channel: None,
+            at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn withdrawn(account_id: Uuid, old_balance: u64, amount: u64) -> Evt {
+        Evt::Withdrawn {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: old_balance.into(),
+            amount: amount.into(),
+            value_date: None,
+            channel: None,
+            at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    euro_cent::EuroCent,
+    redaction::Redacted,
+};
+use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing::debug;
+use uuid::Uuid;
+
+pub const BOOKS_TAG: &str = "books";
+
+/// The ID of the single [Books] entity.
+pub const BOOKS_ID: Uuid = Uuid::nil();
+
+/// The books of the bank, closed period by period: each closed period is recorded with an
+/// immutable snapshot of its totals, and periods can neither overlap nor be reopened. Defaults to
+/// the [SystemClock].
+#[derive(Debug, Clone)]
+pub struct Books {
+    clock: Arc<dyn Clock>,
+    state: State,
+}
+
+impl Books {
+    /// Use the given [Clock] for timestamping events and checking period ends.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+}
+
+impl Default for Books {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            state: State::default(),
+        }
+    }
+}
+
+/// Commands for the eventsourced [Books].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmd {
+    /// Close the period from the end of the last closed one, if any, to the given end. The given
+    /// start must match, such that concurrent closes are rejected.
+    ClosePeriod {
+        #[serde(with = "time::serde::rfc3339::option")]
+        from: Option<OffsetDateTime>,
+        #[serde(with = "time::serde::rfc3339")]
+        to: OffsetDateTime,
+        totals: PeriodTotals,
+    },
+}
+
+/// Events for the eventsourced [Books], timestamped with the time of command handling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evt {
+    PeriodClosed {
+        #[serde(with = "time::serde::rfc3339::option")]
+        from: Option<OffsetDateTime>,
+        #[serde(with = "time::serde::rfc3339")]
+        to: OffsetDateTime,
+        totals: PeriodTotals,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+/// Totals of a closed period: count and sum per kind of transaction and the closing balance, i.e.
+/// the sum of the balances of all accounts at the end of the period.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeriodTotals {
+    pub kinds: Vec<KindTotal>,
+    pub closing_balance: EuroCent,
+    pub accounts: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KindTotal {
+    pub kind: String,
+    pub count: u64,
+    pub sum: EuroCent,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    #[serde(with = "time::serde::rfc3339::option")]
+    closed_until: Option<OffsetDateTime>,
+}
+
+/// Command handler errors for the eventsourced [Books].
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("Period must start at the end of the last closed period")]
+    NotContiguous,
+
+    #[error("Period must end after it starts")]
+    Empty,
+
+    #[error("Period must end in the past")]
+    NotInPast,
+}
+
+impl EventSourced for Books {
+    type Cmd = Cmd;
+
+    type Evt = Evt;
+
+    type State = State;
+
+    type Error = Error;
+
+    fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let at = self.clock.now();
+
+        match cmd {
+            Cmd::ClosePeriod { from, .. } if from != self.state.closed_until => {
+                Err(Error::NotContiguous)
+            }
+            Cmd::ClosePeriod {
+                from: Some(from),
+                to,
+                ..
+            } if to <= from => Err(Error::Empty),
+            Cmd::ClosePeriod { to, .. } if to > at => Err(Error::NotInPast),
+            Cmd::ClosePeriod { from, to, totals } => Ok(Evt::PeriodClosed {
+                from,
+                to,
+                totals,
+                at,
+            }
+            .with_tag(BOOKS_TAG)),
+        }
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
+
+        match evt {
+            Evt::PeriodClosed { to, .. } => self.state.closed_until = Some(to),
+        }
+
+        None
+    }
+
+    fn set_state(&mut self, state: Self::State) {
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+    use time::Duration;
+
+    #[test]
+    fn test_handle_cmd_and_evt() {
+        let t0 = OffsetDateTime::UNIX_EPOCH;
+        let clock = ManualClock::new(t0 + Duration::days(2));
+        let mut books = Books::default().with_clock(Arc::new(clock.clone()));
+
+        let close = |from, to| Cmd::ClosePeriod {
+            from,
+            to,
+            totals: PeriodTotals::default(),
+        };
+
+        // Periods must end in the past.
+        assert!(books
+            .handle_cmd(close(None, t0 + Duration::days(3)))
+            .is_err());
+
+        // The first period can be closed without start.
+        assert!(books
+            .handle_cmd(close(None, t0 + Duration::days(1)))
+            .is_ok());
+        books.handle_evt(Evt::PeriodClosed {
+            from: None,
+            to: t0 + Duration::days(1),
+            totals: PeriodTotals::default(),
+            at: clock.now(),
+        });
+
+        // Closed periods cannot be closed again.
+        assert!(books
+            .handle_cmd(close(None, t0 + Duration::days(1)))
+            .is_err());
+
+        // Periods must be contiguous and not empty.
+        assert!(books
+            .handle_cmd(close(Some(t0), t0 + Duration::days(2)))
+            .is_err());
+        assert!(books
+            .handle_cmd(close(Some(t0 + Duration::days(1)), t0 + Duration::days(1)))
+            .is_err());
+        assert!(books
+            .handle_cmd(close(Some(t0 + Duration::days(1)), t0 + Duration::days(2)))
+            .is_ok());
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::{net::IpAddr, str::FromStr};
+use thiserror::Error;
+
+const MAX_DEVICE_ID_LEN: usize = 64;
+
+const MAX_USER_AGENT_LEN: usize = 256;
+
+const MICRODEGREES: f64 = 1_000_000.0;
+
+/// Metadata about the channel a command has been issued through, e.g. the device of the customer,
+/// recorded with the resulting event for risk scoring and auditing. All parts are optional, as
+/// provided by the client.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Channel {
+    pub device_id: Option<String>,
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub geolocation: Option<Geolocation>,
+}
+
+impl Channel {
+    /// Create a [Channel] from the given parts, truncating the device ID and user agent to bound
+    /// the size of events; `None` if all parts are missing.
+    pub fn new(
+        device_id: Option<String>,
+        ip: Option<IpAddr>,
+        user_agent: Option<String>,
+        geolocation: Option<Geolocation>,
+    ) -> Option<Self> {
+        let channel = Self {
+            device_id: device_id.map(|device_id| truncate(device_id, MAX_DEVICE_ID_LEN)),
+            ip,
+            user_agent: user_agent.map(|user_agent| truncate(user_agent, MAX_USER_AGENT_LEN)),
+            geolocation,
+        };
+        (channel != Self::default()).then_some(channel)
+    }
+}
+
+/// Latitude and longitude in microdegrees, i.e. with a precision of about 0.1m.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Geolocation {
+    pub latitude: i32,
+    pub longitude: i32,
+}
+
+impl FromStr for Geolocation {
+    type Err = InvalidGeolocation;
+
+    /// Parse from latitude and longitude in degrees, separated by a comma, e.g. "52.52,13.405".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidGeolocation(s.to_string());
+        let (latitude, longitude) = s.split_once(',').ok_or_else(invalid)?;
+        let latitude = latitude.trim().parse::<f64>().map_err(|_| invalid())?;
+        let longitude = longitude.trim().parse::<f64>().map_err(|_| invalid())?;
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            latitude: (latitude * MICRODEGREES).round() as i32,
+            longitude: (longitude * MICRODEGREES).round() as i32,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid geolocation '{0}'")]
+pub struct InvalidGeolocation(String);
+
+/// Truncate the given string to at most the given number of bytes at a char boundary.
+fn truncate(mut s: String, max_len: usize) -> String {
+    if s.len() > max_len {
+        let len = (0..=max_len)
+            .rev()
+            .find(|len| s.is_char_boundary(*len))
+            .unwrap_or_default();
+        s.truncate(len);
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel() {
+        assert_eq!(Channel::new(None, None, None, None), None);
+
+        let channel = Channel::new(
+            Some("d".repeat(100)),
+            "192.0.2.1".parse().ok(),
+            Some("ü".repeat(200)),
+            None,
+        )
+        .unwrap();
+        assert_eq!(channel.device_id.unwrap().len(), MAX_DEVICE_ID_LEN);
+        assert_eq!(channel.user_agent.unwrap().len(), MAX_USER_AGENT_LEN);
+    }
+
+    #[test]
+    fn test_geolocation() {
+        assert_eq!(
+            "52.52, 13.405".parse::<Geolocation>().unwrap(),
+            Geolocation {
+                latitude: 52_520_000,
+                longitude: 13_405_000
+            }
+        );
+        assert!("52.52".parse::<Geolocation>().is_err());
+        assert!("91,0".parse::<Geolocation>().is_err());
+        assert!("north,east".parse::<Geolocation>().is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use parking_lot::RwLock;
+use std::{fmt::Debug, sync::Arc};
+use time::{Duration, OffsetDateTime};
+
+/// Source for the current time. Injected wherever time is needed instead of calling system time
+/// directly, such that time dependent logic can be tested deterministically and replayed
+/// consistently.
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// The current time.
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// A [Clock] backed by the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// A [Clock] which only moves when told to, e.g. for tests. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<RwLock<OffsetDateTime>>);
+
+impl ManualClock {
+    #[allow(missing_docs)]
+    pub fn new(now: OffsetDateTime) -> Self {
+        Self(Arc::new(RwLock::new(now)))
+    }
+
+    /// Set the current time.
+    pub fn set(&self, now: OffsetDateTime) {
+        *self.0.write() = now;
+    }
+
+    /// Move the current time by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        *self.0.write() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> OffsetDateTime {
+        *self.0.read()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(clock.now(), OffsetDateTime::UNIX_EPOCH);
+
+        let clone = clock.clone();
+        clone.advance(Duration::days(1));
+        assert_eq!(clock.now(), OffsetDateTime::UNIX_EPOCH + Duration::days(1));
+
+        clock.set(OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(clone.now(), OffsetDateTime::UNIX_EPOCH);
+    }
+}
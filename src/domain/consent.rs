@@ -0,0 +1,282 @@
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    redaction::Redacted,
+};
+use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub const CONSENT_TAG: &str = "consent";
+
+/// A consent of an account holder to share account data with a third party. Defaults to the
+/// [SystemClock].
+#[derive(Debug, Clone)]
+pub struct Consent {
+    clock: Arc<dyn Clock>,
+    state: State,
+}
+
+impl Consent {
+    /// Use the given [Clock] for timestamping events and checking expiry.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+}
+
+impl Default for Consent {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            state: State::default(),
+        }
+    }
+}
+
+/// Kind of account data a consent grants access to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConsentScope {
+    Balances,
+    Transactions,
+}
+
+/// Commands for an eventsourced [Consent].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmd {
+    Grant {
+        id: Uuid,
+        account_id: Uuid,
+        third_party: String,
+        scope: ConsentScope,
+        #[serde(with = "time::serde::rfc3339")]
+        expires_at: OffsetDateTime,
+    },
+    Revoke,
+}
+
+/// Events for an eventsourced [Consent].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evt {
+    Granted {
+        id: Uuid,
+        account_id: Uuid,
+        third_party: String,
+        scope: ConsentScope,
+        #[serde(with = "time::serde::rfc3339")]
+        expires_at: OffsetDateTime,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Revoked {
+        id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum State {
+    #[default]
+    NonExistent,
+    Granted {
+        id: Uuid,
+        account_id: Uuid,
+        third_party: String,
+        scope: ConsentScope,
+        #[serde(with = "time::serde::rfc3339")]
+        expires_at: OffsetDateTime,
+    },
+    Revoked,
+}
+
+/// Command handler errors for an eventsourced [Consent].
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("The expiry '{0}' is not in the future")]
+    ExpiryNotInFuture(OffsetDateTime),
+
+    #[error("This consent has not been granted yet")]
+    NotYetGranted,
+
+    #[error("This consent has already been granted")]
+    AlreadyGranted,
+
+    #[error("This consent has already been revoked")]
+    AlreadyRevoked,
+}
+
+impl EventSourced for Consent {
+    type Cmd = Cmd;
+
+    type Evt = Evt;
+
+    type State = State;
+
+    type Error = Error;
+
+    fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let at = self.clock.now();
+
+        match (&self.state, cmd) {
+            // In State::NonExistent:
+            (State::NonExistent, Cmd::Grant { expires_at, .. }) if expires_at <= at => {
+                Err(Error::ExpiryNotInFuture(expires_at))
+            }
+            (
+                State::NonExistent,
+                Cmd::Grant {
+                    id,
+                    account_id,
+                    third_party,
+                    scope,
+                    expires_at,
+                },
+            ) => Ok(Evt::Granted {
+                id,
+                account_id,
+                third_party,
+                scope,
+                expires_at,
+                at,
+            }
+            .with_tag(CONSENT_TAG)),
+            (State::NonExistent, other) => {
+                error!("Cannot handle command '{other:?}' in state NonExistent");
+                Err(Error::NotYetGranted)
+            }
+
+            // In State::Granted:
+            (State::Granted { id, .. }, Cmd::Revoke) => {
+                Ok(Evt::Revoked { id: *id, at }.with_tag(CONSENT_TAG))
+            }
+            (State::Granted { .. }, other) => {
+                error!("Cannot handle command '{other:?}' in state Granted");
+                Err(Error::AlreadyGranted)
+            }
+
+            // In State::Revoked:
+            (State::Revoked, other) => {
+                error!("Cannot handle command '{other:?}' in state Revoked");
+                Err(Error::AlreadyRevoked)
+            }
+        }
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
+
+        match (&self.state, evt) {
+            // In State::NonExistent:
+            (
+                State::NonExistent,
+                Evt::Granted {
+                    id,
+                    account_id,
+                    third_party,
+                    scope,
+                    expires_at,
+                    ..
+                },
+            ) => {
+                self.state = State::Granted {
+                    id,
+                    account_id,
+                    third_party,
+                    scope,
+                    expires_at,
+                }
+            }
+            (State::NonExistent, evt) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+
+            // In State::Granted:
+            (State::Granted { .. }, Evt::Revoked { .. }) => self.state = State::Revoked,
+            (State::Granted { .. }, evt) => panic!("Illegal event '{evt:?}' in state Granted"),
+
+            // In State::Revoked:
+            (State::Revoked, evt) => panic!("Illegal event '{evt:?}' in state Revoked"),
+        }
+
+        None
+    }
+
+    fn set_state(&mut self, state: Self::State) {
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+    use time::Duration;
+
+    #[test]
+    fn test_handle_cmd_and_evt() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut consent = Consent::default().with_clock(Arc::new(clock.clone()));
+
+        let id = Uuid::now_v7();
+        let account_id = Uuid::now_v7();
+        let grant = |expires_at| Cmd::Grant {
+            id,
+            account_id,
+            third_party: "fintech".to_string(),
+            scope: ConsentScope::Balances,
+            expires_at,
+        };
+        let expires_at = OffsetDateTime::UNIX_EPOCH + Duration::days(90);
+
+        // Command Revoke fails in state NonExistent.
+        assert!(consent.handle_cmd(Cmd::Revoke).is_err());
+
+        // Command Grant fails with an expiry not in the future.
+        assert!(consent
+            .handle_cmd(grant(OffsetDateTime::UNIX_EPOCH))
+            .is_err());
+
+        // Command Grant succeeds in state NonExistent.
+        assert!(consent.handle_cmd(grant(expires_at)).is_ok());
+
+        // Handle event Granted.
+        consent.handle_evt(Evt::Granted {
+            id,
+            account_id,
+            third_party: "fintech".to_string(),
+            scope: ConsentScope::Balances,
+            expires_at,
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert_eq!(
+            consent.state,
+            State::Granted {
+                id,
+                account_id,
+                third_party: "fintech".to_string(),
+                scope: ConsentScope::Balances,
+                expires_at,
+            }
+        );
+
+        // Command Grant fails in state Granted.
+        assert!(consent.handle_cmd(grant(expires_at)).is_err());
+
+        // Command Revoke succeeds in state Granted.
+        assert!(consent.handle_cmd(Cmd::Revoke).is_ok());
+
+        // Handle event Revoked.
+        consent.handle_evt(Evt::Revoked {
+            id,
+            at: OffsetDateTime::UNIX_EPOCH,
+        });
+        assert_eq!(consent.state, State::Revoked);
+
+        // Command Revoke fails in state Revoked.
+        assert!(consent.handle_cmd(Cmd::Revoke).is_err());
+    }
+}
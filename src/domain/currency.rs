@@ -0,0 +1,166 @@
+use crate::domain::tenant::TenantId;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// ISO 4217 currency code, i.e. three uppercase ASCII letters. Defaults to EUR, the currency of
+/// accounts created before currencies were introduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(into = "String")]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const EUR: Currency = Currency(*b"EUR");
+
+    /// The currency code, e.g. "EUR".
+    pub fn as_str(&self) -> &str {
+        // Only ASCII letters can be parsed, hence valid UTF-8.
+        std::str::from_utf8(&self.0).expect("currency code is ASCII")
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::EUR
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = ParseCurrencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[a, b, c] if [a, b, c].iter().all(u8::is_ascii_uppercase) => Ok(Currency([a, b, c])),
+            _ => Err(ParseCurrencyError(s.to_string())),
+        }
+    }
+}
+
+impl From<Currency> for String {
+    fn from(currency: Currency) -> Self {
+        currency.to_string()
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Configuration of the currencies of accounts: the default currency and the supported ones per
+/// tenant. Tenants without own settings only support the default currency.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Currency of new accounts not specifying one.
+    #[serde(default)]
+    default: Currency,
+
+    /// Settings of individual tenants.
+    #[serde(default)]
+    tenants: Vec<TenantCurrencies>,
+}
+
+/// Currency settings of a tenant.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TenantCurrencies {
+    tenant: TenantId,
+
+    /// Currency of new accounts of this tenant not specifying one; the overall default if not
+    /// given.
+    default: Option<Currency>,
+
+    /// Currencies supported for this tenant in addition to its default currency.
+    #[serde(default)]
+    supported: Vec<Currency>,
+}
+
+impl Config {
+    /// The default currency for new accounts of the given tenant.
+    pub fn default_for(&self, tenant: TenantId) -> Currency {
+        self.tenant(tenant)
+            .and_then(|currencies| currencies.default)
+            .unwrap_or(self.default)
+    }
+
+    /// Whether the given currency is supported for the given tenant.
+    pub fn is_supported(&self, tenant: TenantId, currency: Currency) -> bool {
+        currency == self.default_for(tenant)
+            || self
+                .tenant(tenant)
+                .is_some_and(|currencies| currencies.supported.contains(&currency))
+    }
+
+    fn tenant(&self, tenant: TenantId) -> Option<&TenantCurrencies> {
+        self.tenants
+            .iter()
+            .find(|currencies| currencies.tenant == tenant)
+    }
+}
+
+/// Error for parsing a [Currency] from an invalid code.
+#[derive(Debug, Error)]
+#[error("invalid currency code '{0}', expected three uppercase letters, e.g. EUR")]
+pub struct ParseCurrencyError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_currency() {
+        assert_eq!("CHF".parse::<Currency>().unwrap().as_str(), "CHF");
+        assert_eq!(Currency::default(), Currency::EUR);
+        assert!("chf".parse::<Currency>().is_err());
+        assert!("EURO".parse::<Currency>().is_err());
+        assert!("".parse::<Currency>().is_err());
+
+        let json = serde_json::to_string(&Currency::EUR).unwrap();
+        assert_eq!(json, r#""EUR""#);
+        assert_eq!(
+            serde_json::from_str::<Currency>(&json).unwrap(),
+            Currency::EUR
+        );
+        assert!(serde_json::from_str::<Currency>(r#""E1R""#).is_err());
+    }
+
+    #[test]
+    fn test_config() {
+        let chf = "CHF".parse::<Currency>().unwrap();
+        let usd = "USD".parse::<Currency>().unwrap();
+        let swiss = TenantId::from(uuid::Uuid::now_v7());
+        let config = Config {
+            default: Currency::EUR,
+            tenants: vec![TenantCurrencies {
+                tenant: swiss,
+                default: Some(chf),
+                supported: vec![Currency::EUR],
+            }],
+        };
+
+        assert_eq!(config.default_for(TenantId::default()), Currency::EUR);
+        assert!(config.is_supported(TenantId::default(), Currency::EUR));
+        assert!(!config.is_supported(TenantId::default(), chf));
+
+        assert_eq!(config.default_for(swiss), chf);
+        assert!(config.is_supported(swiss, chf));
+        assert!(config.is_supported(swiss, Currency::EUR));
+        assert!(!config.is_supported(swiss, usd));
+    }
+}
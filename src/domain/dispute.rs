@@ -0,0 +1,434 @@
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    euro_cent::EuroCent,
+    redaction::Redacted,
+};
+use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub const DISPUTE_TAG: &str = "dispute";
+
+/// A dispute of a transaction of an account, e.g. a chargeback of a card payment: once opened, the
+/// disputed amount might be credited provisionally; the dispute is then either upheld, i.e. the
+/// amount is credited permanently, or denied, i.e. a provisional credit is clawed back. Disputes
+/// not resolved by their deadline are upheld. Defaults to the [SystemClock].
+#[derive(Debug, Clone)]
+pub struct Dispute {
+    clock: Arc<dyn Clock>,
+    state: State,
+}
+
+impl Dispute {
+    /// Use the given [Clock] for timestamping events and checking deadlines.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+}
+
+impl Default for Dispute {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            state: State::default(),
+        }
+    }
+}
+
+/// Commands for an eventsourced [Dispute].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmd {
+    /// Open a dispute of the given amount of the given transaction, to be resolved within the
+    /// given number of days.
+    Open {
+        id: Uuid,
+        account_id: Uuid,
+        transaction_id: Uuid,
+        amount: EuroCent,
+        reason: String,
+        deadline_days: u16,
+    },
+    CreditProvisionally,
+    Uphold,
+    Deny,
+    /// Uphold a dispute not resolved by its deadline.
+    Expire,
+}
+
+/// Events for an eventsourced [Dispute], timestamped with the time of command handling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evt {
+    Opened {
+        id: Uuid,
+        account_id: Uuid,
+        transaction_id: Uuid,
+        amount: EuroCent,
+        reason: String,
+        #[serde(with = "time::serde::rfc3339")]
+        deadline: OffsetDateTime,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    ProvisionallyCredited {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Upheld {
+        id: Uuid,
+        account_id: Uuid,
+        /// The amount still to be credited, i.e. zero if already credited provisionally.
+        credit: EuroCent,
+        /// Whether upheld because not resolved by the deadline.
+        expired: bool,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Denied {
+        id: Uuid,
+        account_id: Uuid,
+        /// The provisionally credited amount to be clawed back, if any.
+        claw_back: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+impl Evt {
+    /// The ID of the dispute.
+    pub fn dispute_id(&self) -> Uuid {
+        match self {
+            Evt::Opened { id, .. }
+            | Evt::ProvisionallyCredited { id, .. }
+            | Evt::Upheld { id, .. }
+            | Evt::Denied { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum State {
+    #[default]
+    NonExistent,
+    Open {
+        id: Uuid,
+        account_id: Uuid,
+        transaction_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        deadline: OffsetDateTime,
+        provisionally_credited: bool,
+    },
+    Resolved {
+        id: Uuid,
+        account_id: Uuid,
+        transaction_id: Uuid,
+        outcome: Outcome,
+    },
+}
+
+/// The outcome of a resolved [Dispute].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    Upheld,
+    Denied,
+}
+
+/// Command handler errors for an eventsourced [Dispute].
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("This dispute has not been opened yet")]
+    NotYetOpened,
+
+    #[error("This dispute has already been opened")]
+    AlreadyOpened,
+
+    #[error("Amount must be positive")]
+    ZeroAmount,
+
+    #[error("Deadline must be at least one day")]
+    ZeroDeadline,
+
+    #[error("The disputed amount has already been credited provisionally")]
+    AlreadyCredited,
+
+    #[error("The deadline of this dispute has not been reached yet")]
+    DeadlineNotReached,
+
+    #[error("This dispute has already been resolved")]
+    Resolved,
+}
+
+impl EventSourced for Dispute {
+    type Cmd = Cmd;
+
+    type Evt = Evt;
+
+    type State = State;
+
+    type Error = Error;
+
+    fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let at = self.clock.now();
+
+        match (self.state, cmd) {
+            // In State::NonExistent:
+            (State::NonExistent, Cmd::Open { amount, .. }) if amount == EuroCent::default() => {
+                Err(Error::ZeroAmount)
+            }
+            (
+                State::NonExistent,
+                Cmd::Open {
+                    deadline_days: 0, ..
+                },
+            ) => Err(Error::ZeroDeadline),
+            (
+                State::NonExistent,
+                Cmd::Open {
+                    id,
+                    account_id,
+                    transaction_id,
+                    amount,
+                    reason,
+                    deadline_days,
+                },
+            ) => Ok(Evt::Opened {
+                id,
+                account_id,
+                transaction_id,
+                amount,
+                reason,
+                deadline: at + Duration::days(deadline_days as i64),
+                at,
+            }
+            .with_tag(DISPUTE_TAG)),
+            (State::NonExistent, other) => {
+                error!("Cannot handle command '{other:?}' in state NonExistent");
+                Err(Error::NotYetOpened)
+            }
+
+            // In State::Open:
+            (State::Open { .. }, Cmd::Open { .. }) => Err(Error::AlreadyOpened),
+
+            (
+                State::Open {
+                    provisionally_credited: true,
+                    ..
+                },
+                Cmd::CreditProvisionally,
+            ) => Err(Error::AlreadyCredited),
+            (
+                State::Open {
+                    id,
+                    account_id,
+                    amount,
+                    ..
+                },
+                Cmd::CreditProvisionally,
+            ) => Ok(Evt::ProvisionallyCredited {
+                id,
+                account_id,
+                amount,
+                at,
+            }
+            .with_tag(DISPUTE_TAG)),
+
+            (State::Open { deadline, .. }, Cmd::Expire) if at < deadline => {
+                Err(Error::DeadlineNotReached)
+            }
+            (
+                State::Open {
+                    id,
+                    account_id,
+                    amount,
+                    provisionally_credited,
+                    ..
+                },
+                cmd @ (Cmd::Uphold | Cmd::Expire),
+            ) => Ok(Evt::Upheld {
+                id,
+                account_id,
+                credit: if provisionally_credited {
+                    EuroCent::default()
+                } else {
+                    amount
+                },
+                expired: matches!(cmd, Cmd::Expire),
+                at,
+            }
+            .with_tag(DISPUTE_TAG)),
+
+            (
+                State::Open {
+                    id,
+                    account_id,
+                    amount,
+                    provisionally_credited,
+                    ..
+                },
+                Cmd::Deny,
+            ) => Ok(Evt::Denied {
+                id,
+                account_id,
+                claw_back: if provisionally_credited {
+                    amount
+                } else {
+                    EuroCent::default()
+                },
+                at,
+            }
+            .with_tag(DISPUTE_TAG)),
+
+            // In State::Resolved:
+            (State::Resolved { .. }, Cmd::Open { .. }) => Err(Error::AlreadyOpened),
+            (State::Resolved { .. }, _) => Err(Error::Resolved),
+        }
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
+
+        match (&mut self.state, evt) {
+            // In State::NonExistent:
+            (
+                State::NonExistent,
+                Evt::Opened {
+                    id,
+                    account_id,
+                    transaction_id,
+                    amount,
+                    deadline,
+                    ..
+                },
+            ) => {
+                self.state = State::Open {
+                    id,
+                    account_id,
+                    transaction_id,
+                    amount,
+                    deadline,
+                    provisionally_credited: false,
+                }
+            }
+            (State::NonExistent, evt) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+
+            // In State::Open:
+            (
+                State::Open {
+                    provisionally_credited,
+                    ..
+                },
+                Evt::ProvisionallyCredited { .. },
+            ) => *provisionally_credited = true,
+            (
+                State::Open {
+                    id,
+                    account_id,
+                    transaction_id,
+                    ..
+                },
+                evt @ (Evt::Upheld { .. } | Evt::Denied { .. }),
+            ) => {
+                self.state = State::Resolved {
+                    id: *id,
+                    account_id: *account_id,
+                    transaction_id: *transaction_id,
+                    outcome: match evt {
+                        Evt::Upheld { .. } => Outcome::Upheld,
+                        _ => Outcome::Denied,
+                    },
+                }
+            }
+            (State::Open { .. }, evt) => panic!("Illegal event '{evt:?}' in state Open"),
+
+            // In State::Resolved:
+            (State::Resolved { .. }, evt) => panic!("Illegal event '{evt:?}' in state Resolved"),
+        }
+
+        None
+    }
+
+    fn set_state(&mut self, state: Self::State) {
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+
+    #[test]
+    fn test_handle_cmd_and_evt() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut dispute = Dispute::default().with_clock(Arc::new(clock.clone()));
+
+        let id = Uuid::now_v7();
+        let account_id = Uuid::now_v7();
+        let amount = EuroCent::from(4_200u64);
+
+        // Command Uphold fails in state NonExistent.
+        assert!(matches!(
+            dispute.handle_cmd(Cmd::Uphold),
+            Err(Error::NotYetOpened)
+        ));
+
+        dispute.handle_evt(Evt::Opened {
+            id,
+            account_id,
+            transaction_id: Uuid::now_v7(),
+            amount,
+            reason: "not received".to_string(),
+            deadline: clock.now() + Duration::days(45),
+            at: clock.now(),
+        });
+
+        // Command Expire fails before the deadline.
+        assert!(matches!(
+            dispute.handle_cmd(Cmd::Expire),
+            Err(Error::DeadlineNotReached)
+        ));
+
+        // Command Deny succeeds while open.
+        assert!(dispute.handle_cmd(Cmd::Deny).is_ok());
+
+        dispute.handle_evt(Evt::ProvisionallyCredited {
+            id,
+            account_id,
+            amount,
+            at: clock.now(),
+        });
+
+        // Command CreditProvisionally fails once credited.
+        assert!(matches!(
+            dispute.handle_cmd(Cmd::CreditProvisionally),
+            Err(Error::AlreadyCredited)
+        ));
+
+        // Command Expire succeeds at the deadline.
+        clock.advance(Duration::days(45));
+        assert!(dispute.handle_cmd(Cmd::Expire).is_ok());
+
+        dispute.handle_evt(Evt::Denied {
+            id,
+            account_id,
+            claw_back: amount,
+            at: clock.now(),
+        });
+
+        // Resolved disputes cannot be resolved again.
+        assert!(matches!(
+            dispute.handle_cmd(Cmd::Uphold),
+            Err(Error::Resolved)
+        ));
+    }
+}
@@ -1,6 +1,12 @@
 use natural_derive::{Add, Sub};
 use serde::{Deserialize, Serialize};
-use std::fmt::Display;
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use thiserror::Error;
+
+const BPS_PER_UNIT: u64 = 10_000;
 
 /// EUR cent. Defaults to 0€.
 #[derive(
@@ -8,6 +14,55 @@ use std::fmt::Display;
 )]
 pub struct EuroCent(u64);
 
+impl EuroCent {
+    /// The sum of this and the given amount, saturating at the maximum.
+    pub fn saturating_add(self, other: EuroCent) -> Self {
+        EuroCent(self.0.saturating_add(other.0))
+    }
+
+    /// This amount multiplied by the given numerator and divided by the given denominator, rounded
+    /// to full cents with the given [Rounding], saturating at the maximum. Calculated with
+    /// integers, hence precise.
+    ///
+    /// # Panics
+    /// Panics if the denominator is zero.
+    pub fn mul_div(self, numerator: u64, denominator: u64, rounding: Rounding) -> Self {
+        let product = self.0 as u128 * numerator as u128;
+        let quotient = rounding.div(product, denominator as u128);
+        EuroCent(u64::try_from(quotient).unwrap_or(u64::MAX))
+    }
+
+    /// The given basis points of this amount, e.g. for fees.
+    pub fn bps(self, bps: u64, rounding: Rounding) -> Self {
+        self.mul_div(bps, BPS_PER_UNIT, rounding)
+    }
+
+    /// Simple interest on this amount for the given annual rate in basis points, prorated over the
+    /// given number of days with the given [DayCount] convention.
+    pub fn interest(
+        self,
+        rate_bps: u64,
+        days: u64,
+        day_count: DayCount,
+        rounding: Rounding,
+    ) -> Self {
+        let product = (self.0 as u128)
+            .saturating_mul(rate_bps as u128)
+            .saturating_mul(days as u128);
+        let denominator = BPS_PER_UNIT as u128 * day_count.days_per_year() as u128;
+        let quotient = rounding.div(product, denominator);
+        EuroCent(u64::try_from(quotient).unwrap_or(u64::MAX))
+    }
+
+    /// Display this amount following the conventions of the given [Locale].
+    pub fn localized(self, locale: Locale) -> Localized {
+        Localized {
+            amount: self,
+            locale,
+        }
+    }
+}
+
 impl Display for EuroCent {
     /// Format [EuroCent] as 123.05€.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -17,12 +72,163 @@ impl Display for EuroCent {
     }
 }
 
+/// Parse [EuroCent] from a decimal amount of EUR with at most two decimal places and either a
+/// decimal point or comma, e.g. 123.45, 123,45, 123.4 or 123.
+impl FromStr for EuroCent {
+    type Err = ParseEuroCentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseEuroCentError(s.to_string());
+
+        let (eur, cent) = s.split_once(['.', ',']).unwrap_or((s, ""));
+        let is_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+        if eur.is_empty() || !is_digits(eur) || cent.len() > 2 || !is_digits(cent) {
+            return Err(error());
+        }
+        if s.contains(['.', ',']) && cent.is_empty() {
+            return Err(error());
+        }
+
+        let eur = eur.parse::<u64>().map_err(|_| error())?;
+        let cent = format!("{cent:0<2}").parse::<u64>().map_err(|_| error())?;
+        eur.checked_mul(100)
+            .and_then(|eur| eur.checked_add(cent))
+            .map(EuroCent)
+            .ok_or_else(error)
+    }
+}
+
+/// Error for amounts which cannot be parsed as [EuroCent].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invalid amount '{0}', expected e.g. 123.45 or 123,45")]
+pub struct ParseEuroCentError(String);
+
 impl From<u64> for EuroCent {
     fn from(value: u64) -> Self {
         EuroCent(value)
     }
 }
 
+impl From<EuroCent> for u64 {
+    fn from(value: EuroCent) -> Self {
+        value.0
+    }
+}
+
+/// Locales with their conventions for formatting amounts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Locale {
+    /// English, e.g. €1,234.56.
+    #[default]
+    En,
+
+    /// German, e.g. 1.234,56 €.
+    De,
+}
+
+impl Locale {
+    /// The locale for the given language, e.g. "de" or "de-AT", if supported.
+    pub fn for_language(language: &str) -> Option<Self> {
+        match language.split(['-', '_']).next()? {
+            "en" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+}
+
+/// [EuroCent] displayed following the conventions of a [Locale].
+#[derive(Debug, Clone, Copy)]
+pub struct Localized {
+    amount: EuroCent,
+    locale: Locale,
+}
+
+impl Display for Localized {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (grouping, decimal) = match self.locale {
+            Locale::En => (',', '.'),
+            Locale::De => ('.', ','),
+        };
+
+        let eur = (self.amount.0 / 100).to_string();
+        let cent = self.amount.0 % 100;
+        let mut grouped = String::with_capacity(eur.len() + eur.len() / 3);
+        for (n, digit) in eur.chars().enumerate() {
+            if n > 0 && (eur.len() - n) % 3 == 0 {
+                grouped.push(grouping);
+            }
+            grouped.push(digit);
+        }
+
+        match self.locale {
+            Locale::En => write!(f, "€{grouped}{decimal}{cent:02}"),
+            Locale::De => write!(f, "{grouped}{decimal}{cent:02} €"),
+        }
+    }
+}
+
+/// Rounding modes for fractions of cents.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rounding {
+    /// Towards zero, i.e. truncating.
+    Down,
+
+    /// Away from zero.
+    Up,
+
+    /// To the nearest neighbour, halves away from zero.
+    HalfUp,
+
+    /// To the nearest neighbour, halves to the even one ("banker's rounding").
+    #[default]
+    HalfEven,
+}
+
+impl Rounding {
+    /// The given numerator divided by the given denominator, rounded with this mode.
+    ///
+    /// # Panics
+    /// Panics if the denominator is zero.
+    pub fn div(self, numerator: u128, denominator: u128) -> u128 {
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        if remainder == 0 {
+            return quotient;
+        }
+
+        let half = (remainder * 2).cmp(&denominator);
+        let up = match self {
+            Rounding::Down => false,
+            Rounding::Up => true,
+            Rounding::HalfUp => half.is_ge(),
+            Rounding::HalfEven => half.is_gt() || half.is_eq() && quotient % 2 == 1,
+        };
+        quotient + u128::from(up)
+    }
+}
+
+/// Day count conventions for prorating annual rates: actual days over a year of 360 or 365 days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DayCount {
+    #[serde(rename = "act/360")]
+    Act360,
+
+    #[serde(rename = "act/365")]
+    Act365,
+}
+
+impl DayCount {
+    fn days_per_year(self) -> u64 {
+        match self {
+            DayCount::Act360 => 360,
+            DayCount::Act365 => 365,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,4 +241,133 @@ mod tests {
         assert_eq!(EuroCent(66642).to_string(), "666.42€");
         assert_eq!(EuroCent(66607).to_string(), "666.07€");
     }
+
+    #[test]
+    fn test_euro_cent_saturating_add() {
+        assert_eq!(EuroCent(40).saturating_add(EuroCent(2)), EuroCent(42));
+        assert_eq!(
+            EuroCent(u64::MAX).saturating_add(EuroCent(1)),
+            EuroCent(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_euro_cent_localized() {
+        let amount = EuroCent(123_456);
+        assert_eq!(amount.localized(Locale::En).to_string(), "€1,234.56");
+        assert_eq!(amount.localized(Locale::De).to_string(), "1.234,56 €");
+        assert_eq!(EuroCent(5).localized(Locale::De).to_string(), "0,05 €");
+        assert_eq!(
+            EuroCent(123_456_789_00).localized(Locale::En).to_string(),
+            "€123,456,789.00"
+        );
+        assert_eq!(Locale::for_language("de-AT"), Some(Locale::De));
+        assert_eq!(Locale::for_language("fr"), None);
+    }
+
+    #[test]
+    fn test_euro_cent_from_str() {
+        assert_eq!("123.45".parse(), Ok(EuroCent(12_345)));
+        assert_eq!("123,45".parse(), Ok(EuroCent(12_345)));
+        assert_eq!("123.4".parse(), Ok(EuroCent(12_340)));
+        assert_eq!("123".parse(), Ok(EuroCent(12_300)));
+        assert_eq!("0,05".parse(), Ok(EuroCent(5)));
+
+        for invalid in ["", "123.", ".45", "1.234,56", "123.456", "-1", "12a", " 1"] {
+            assert!(invalid.parse::<EuroCent>().is_err(), "{invalid}");
+        }
+        assert!("184467440737095516.16".parse::<EuroCent>().is_err());
+    }
+
+    #[test]
+    fn test_rounding() {
+        use Rounding::*;
+
+        // Tenths of a cent and the expected cents for Down, Up, HalfUp and HalfEven.
+        let fixtures = [
+            (20, [2, 2, 2, 2]),
+            (21, [2, 3, 2, 2]),
+            (24, [2, 3, 2, 2]),
+            (25, [2, 3, 3, 2]),
+            (26, [2, 3, 3, 3]),
+            (29, [2, 3, 3, 3]),
+            (30, [3, 3, 3, 3]),
+            (35, [3, 4, 4, 4]),
+            (45, [4, 5, 5, 4]),
+            (5, [0, 1, 1, 0]),
+            (0, [0, 0, 0, 0]),
+        ];
+        for (tenths, expected) in fixtures {
+            for (rounding, expected) in [Down, Up, HalfUp, HalfEven].into_iter().zip(expected) {
+                assert_eq!(
+                    EuroCent(tenths).mul_div(1, 10, rounding),
+                    EuroCent(expected),
+                    "{tenths} with {rounding:?}"
+                );
+            }
+        }
+
+        assert_eq!(
+            EuroCent(u64::MAX).mul_div(2, 1, HalfEven),
+            EuroCent(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_bps() {
+        assert_eq!(
+            EuroCent(1_000_000).bps(250, Rounding::Down),
+            EuroCent(25_000)
+        );
+        assert_eq!(EuroCent(12_345).bps(100, Rounding::Down), EuroCent(123));
+        assert_eq!(EuroCent(12_345).bps(100, Rounding::HalfEven), EuroCent(123));
+        assert_eq!(EuroCent(12_350).bps(100, Rounding::HalfEven), EuroCent(124));
+        assert_eq!(EuroCent(12_345).bps(100, Rounding::Up), EuroCent(124));
+        assert_eq!(EuroCent(1).bps(1, Rounding::Up), EuroCent(1));
+        assert_eq!(EuroCent(1).bps(1, Rounding::HalfUp), EuroCent(0));
+    }
+
+    #[test]
+    fn test_interest() {
+        // 1,000,000.00€ at 5% for 90 days.
+        let amount = EuroCent(100_000_000);
+        assert_eq!(
+            amount.interest(500, 90, DayCount::Act360, Rounding::Down),
+            EuroCent(1_250_000)
+        );
+        // 1,232,876.71...
+        assert_eq!(
+            amount.interest(500, 90, DayCount::Act365, Rounding::Down),
+            EuroCent(1_232_876)
+        );
+        assert_eq!(
+            amount.interest(500, 90, DayCount::Act365, Rounding::HalfEven),
+            EuroCent(1_232_877)
+        );
+
+        // 1,000.00€ at 3.65% for 1 day: exactly 10 cents with ACT/365, 10.138... with ACT/360.
+        let amount = EuroCent(100_000);
+        assert_eq!(
+            amount.interest(365, 1, DayCount::Act365, Rounding::Up),
+            EuroCent(10)
+        );
+        assert_eq!(
+            amount.interest(365, 1, DayCount::Act360, Rounding::HalfUp),
+            EuroCent(10)
+        );
+        assert_eq!(
+            amount.interest(365, 1, DayCount::Act360, Rounding::Up),
+            EuroCent(11)
+        );
+
+        // Large amounts and rates do not overflow.
+        assert_eq!(
+            EuroCent(u64::MAX).interest(10_000, 360, DayCount::Act360, Rounding::Down),
+            EuroCent(u64::MAX)
+        );
+        assert_eq!(
+            EuroCent(100).interest(500, 0, DayCount::Act365, Rounding::Up),
+            EuroCent(0)
+        );
+    }
 }
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+use uuid::Uuid;
+
+const MAX_BANK_CODE: u32 = 99_999_999;
+
+const ACCOUNT_NUMBER_MODULUS: u128 = 10_000_000_000;
+
+/// German bank code (BLZ) with eight digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u32")]
+pub struct BankCode(u32);
+
+impl TryFrom<u32> for BankCode {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value > MAX_BANK_CODE {
+            Err(Error::InvalidBankCode(value))
+        } else {
+            Ok(BankCode(value))
+        }
+    }
+}
+
+/// German IBAN, consisting of a [BankCode] and a ten digit account number, formatted as e.g.
+/// DE89370400440532013000.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Iban {
+    bank_code: u32,
+    account_number: u64,
+}
+
+impl Iban {
+    /// Derive the [Iban] for the account with the given ID.
+    pub fn for_account(bank_code: BankCode, id: Uuid) -> Self {
+        Self::nth_for_account(bank_code, id, 0)
+    }
+
+    /// Derive the [Iban] for the account with the given ID for the given attempt, such that
+    /// another one can be derived, if the account number of the former one is already taken. The
+    /// first attempt, i.e. 0, is the one of [Iban::for_account].
+    pub fn nth_for_account(bank_code: BankCode, id: Uuid, attempt: u32) -> Self {
+        let id = if attempt == 0 {
+            id
+        } else {
+            Uuid::new_v5(&id, &attempt.to_be_bytes())
+        };
+        Self {
+            bank_code: bank_code.0,
+            account_number: (id.as_u128() % ACCOUNT_NUMBER_MODULUS) as u64,
+        }
+    }
+
+    fn check_digits(&self) -> u8 {
+        // "DE" is moved to the end with letters replaced by numbers (D = 13, E = 14), followed by
+        // "00" as placeholder check digits.
+        let digits = format!("{:08}{:010}131400", self.bank_code, self.account_number);
+        let remainder = digits.bytes().fold(0, |remainder, digit| {
+            (remainder * 10 + (digit - b'0') as u32) % 97
+        });
+        (98 - remainder) as u8
+    }
+}
+
+impl Display for Iban {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "DE{:02}{:08}{:010}",
+            self.check_digits(),
+            self.bank_code,
+            self.account_number
+        )
+    }
+}
+
+impl FromStr for Iban {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidIban(s.to_string());
+
+        if s.len() != 22 || !s.starts_with("DE") || !s[2..].bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let check_digits = s[2..4].parse::<u8>().map_err(|_| invalid())?;
+        let bank_code = s[4..12].parse::<u32>().map_err(|_| invalid())?;
+        let account_number = s[12..].parse::<u64>().map_err(|_| invalid())?;
+        let iban = Iban {
+            bank_code,
+            account_number,
+        };
+
+        if iban.check_digits() != check_digits {
+            return Err(invalid());
+        }
+
+        Ok(iban)
+    }
+}
+
+impl From<Iban> for String {
+    fn from(iban: Iban) -> Self {
+        iban.to_string()
+    }
+}
+
+impl TryFrom<String> for Iban {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Errors for [BankCode]s and [Iban]s.
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("Invalid bank code '{0}', must have at most eight digits")]
+    InvalidBankCode(u32),
+
+    #[error("Invalid IBAN '{0}'")]
+    InvalidIban(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iban_display_and_from_str() {
+        let iban = Iban {
+            bank_code: 37040044,
+            account_number: 532013000,
+        };
+        assert_eq!(iban.to_string(), "DE89370400440532013000");
+        assert_eq!("DE89370400440532013000".parse::<Iban>().unwrap(), iban);
+
+        assert!("DE88370400440532013000".parse::<Iban>().is_err());
+        assert!("DE8937040044053201300".parse::<Iban>().is_err());
+        assert!("NL89370400440532013000".parse::<Iban>().is_err());
+    }
+
+    #[test]
+    fn test_iban_for_account() {
+        let bank_code = BankCode::try_from(37040044).unwrap();
+        let id = Uuid::now_v7();
+        let iban = Iban::for_account(bank_code, id);
+        assert_eq!(iban.to_string().parse::<Iban>().unwrap(), iban);
+        assert_eq!(Iban::for_account(bank_code, id), iban);
+        assert_eq!(Iban::nth_for_account(bank_code, id, 0), iban);
+        assert_ne!(Iban::nth_for_account(bank_code, id, 1), iban);
+
+        assert!(BankCode::try_from(100_000_000).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::domain::clock::Clock;
+use parking_lot::Mutex;
+use std::{fmt::Debug, sync::Arc};
+use uuid::Uuid;
+
+/// Maximum value of the counter in the 12 `rand_a` bits of a UUIDv7.
+const MAX_COUNTER: u16 = 0xfff;
+
+/// Mask for the 62 `rand_b` bits of a UUIDv7.
+const RAND_B_MASK: u128 = (1 << 62) - 1;
+
+/// Source for the IDs of accounts, transactions and sagas. Injected wherever IDs are generated,
+/// such that sorting and idempotency logic can rely on their order and tests can be deterministic.
+pub trait IdGenerator: Debug + Send + Sync + 'static {
+    /// A new ID, greater than all IDs generated before by this generator.
+    fn next_id(&self) -> Uuid;
+}
+
+/// An [IdGenerator] producing UUIDv7s with the timestamp of the given [Clock]. Within the same
+/// millisecond or if the clock regresses, e.g. after an NTP adjustment, the timestamp of the last
+/// ID is kept and a per-process counter in the `rand_a` bits is incremented, moving on to the next
+/// millisecond on overflow. Hence the IDs are strictly monotonic per process.
+#[derive(Debug, Clone)]
+pub struct UuidV7Generator {
+    clock: Arc<dyn Clock>,
+    /// Timestamp in milliseconds and counter of the last ID. Clones share the same state.
+    last: Arc<Mutex<(u64, u16)>>,
+}
+
+impl UuidV7Generator {
+    #[allow(missing_docs)]
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            last: Default::default(),
+        }
+    }
+}
+
+impl IdGenerator for UuidV7Generator {
+    fn next_id(&self) -> Uuid {
+        let now_ms = (self.clock.now().unix_timestamp_nanos() / 1_000_000).max(0) as u64;
+
+        let mut last = self.last.lock();
+        let (ms, counter) = match *last {
+            (last_ms, _) if now_ms > last_ms => (now_ms, 0),
+            (last_ms, counter) if counter < MAX_COUNTER => (last_ms, counter + 1),
+            (last_ms, _) => (last_ms + 1, 0),
+        };
+        *last = (ms, counter);
+        drop(last);
+
+        // The random bits of a UUIDv4 are taken from the random number generator of the OS.
+        let rand_b = Uuid::new_v4().as_u128() & RAND_B_MASK;
+        let bits = (ms as u128) << 80 | 0x7 << 76 | (counter as u128) << 64 | 0b10 << 62 | rand_b;
+        Uuid::from_u128(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+    use time::{Duration, OffsetDateTime};
+
+    #[test]
+    fn test_uuid_v7_generator() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::days(19_000);
+        let clock = ManualClock::new(now);
+        let ids = UuidV7Generator::new(Arc::new(clock.clone()));
+
+        let id = ids.next_id();
+        assert_eq!(id.get_version_num(), 7);
+        assert_eq!(id.get_variant(), uuid::Variant::RFC4122);
+        assert_eq!(
+            (id.as_u128() >> 80) as i128,
+            now.unix_timestamp_nanos() / 1_000_000
+        );
+
+        // Same millisecond, regressed clock and advanced clock.
+        let mut last = id;
+        for advance in [Duration::ZERO, Duration::seconds(-1), Duration::seconds(2)] {
+            clock.advance(advance);
+            let id = ids.next_id();
+            assert!(id > last);
+            last = id;
+        }
+
+        // Counter overflow.
+        clock.advance(Duration::seconds(-1));
+        let ids = (0..=MAX_COUNTER as usize + 1)
+            .map(|_| ids.next_id())
+            .collect::<Vec<_>>();
+        assert!(ids.windows(2).all(|ids| ids[0] < ids[1]));
+    }
+}
@@ -0,0 +1,498 @@
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    euro_cent::{EuroCent, Rounding},
+    redaction::Redacted,
+};
+use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub const LOAN_TAG: &str = "loan";
+
+/// Period between installments, starting with the disbursement.
+pub const INSTALLMENT_PERIOD: Duration = Duration::days(30);
+
+/// A loan sub-ledger for an account, repaid in installments with linear amortization. Defaults to
+/// the [SystemClock].
+#[derive(Debug, Clone)]
+pub struct Loan {
+    clock: Arc<dyn Clock>,
+    state: State,
+}
+
+impl Loan {
+    /// Use the given [Clock] for timestamping events and scheduling installments.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+}
+
+impl Default for Loan {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            state: State::default(),
+        }
+    }
+}
+
+/// Commands for an eventsourced [Loan].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmd {
+    Create {
+        id: Uuid,
+        account_id: Uuid,
+        principal: EuroCent,
+        /// Annual interest rate in basis points.
+        rate_bps: u32,
+        installments: u16,
+    },
+    /// Disburse the principal, scheduling the installments; the account ID must match the one of
+    /// the loan.
+    Disburse(Uuid),
+    /// Complete a due or missed installment, either collected or missed, e.g. for insufficient
+    /// funds.
+    CompleteInstallment { number: usize, collected: bool },
+}
+
+/// Events for an eventsourced [Loan], timestamped with the time of command handling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evt {
+    Created {
+        id: Uuid,
+        account_id: Uuid,
+        principal: EuroCent,
+        rate_bps: u32,
+        installments: u16,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Disbursed {
+        id: Uuid,
+        account_id: Uuid,
+        principal: EuroCent,
+        schedule: Vec<Installment>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    InstallmentPaid {
+        id: Uuid,
+        account_id: Uuid,
+        number: usize,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    InstallmentMissed {
+        id: Uuid,
+        account_id: Uuid,
+        number: usize,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+impl Evt {
+    /// The ID of the loan.
+    pub fn loan_id(&self) -> Uuid {
+        match self {
+            Evt::Created { id, .. }
+            | Evt::Disbursed { id, .. }
+            | Evt::InstallmentPaid { id, .. }
+            | Evt::InstallmentMissed { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum State {
+    #[default]
+    NonExistent,
+    Created {
+        id: Uuid,
+        account_id: Uuid,
+        principal: EuroCent,
+        rate_bps: u32,
+        installments: u16,
+        /// Empty until disbursed.
+        schedule: Vec<Installment>,
+    },
+}
+
+/// An installment of a [Loan], i.e. the principal share plus the interest for the period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Installment {
+    #[serde(with = "time::serde::rfc3339")]
+    pub due_at: OffsetDateTime,
+    pub amount: EuroCent,
+    pub status: InstallmentStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstallmentStatus {
+    Due,
+    Paid,
+    /// Missed, i.e. in arrears, until eventually paid.
+    Missed,
+}
+
+/// Command handler errors for an eventsourced [Loan].
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("This loan has not been created yet")]
+    NotYetCreated,
+
+    #[error("This loan has already been created")]
+    AlreadyCreated,
+
+    #[error("Principal must be positive")]
+    ZeroPrincipal,
+
+    #[error("At least one installment is required")]
+    NoInstallments,
+
+    #[error("This loan does not belong to account '{0}'")]
+    AccountMismatch(Uuid),
+
+    #[error("This loan has already been disbursed")]
+    AlreadyDisbursed,
+
+    #[error("This loan has not been disbursed yet")]
+    NotYetDisbursed,
+
+    #[error("Unknown installment '{0}'")]
+    UnknownInstallment(usize),
+
+    #[error("Installment '{0}' is not in a state allowing this")]
+    InvalidInstallmentStatus(usize),
+}
+
+impl EventSourced for Loan {
+    type Cmd = Cmd;
+
+    type Evt = Evt;
+
+    type State = State;
+
+    type Error = Error;
+
+    fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let at = self.clock.now();
+
+        let State::Created {
+            id,
+            account_id,
+            principal,
+            rate_bps,
+            installments,
+            schedule: current_schedule,
+        } = &self.state
+        else {
+            // In State::NonExistent:
+            return match cmd {
+                Cmd::Create { principal, .. } if principal == EuroCent::default() => {
+                    Err(Error::ZeroPrincipal)
+                }
+                Cmd::Create {
+                    installments: 0, ..
+                } => Err(Error::NoInstallments),
+                Cmd::Create {
+                    id,
+                    account_id,
+                    principal,
+                    rate_bps,
+                    installments,
+                } => Ok(Evt::Created {
+                    id,
+                    account_id,
+                    principal,
+                    rate_bps,
+                    installments,
+                    at,
+                }
+                .with_tag(LOAN_TAG)),
+                other => {
+                    error!("Cannot handle command '{other:?}' in state NonExistent");
+                    Err(Error::NotYetCreated)
+                }
+            };
+        };
+
+        // In State::Created:
+        let id = *id;
+        let account_id = *account_id;
+        match cmd {
+            Cmd::Create { .. } => Err(Error::AlreadyCreated),
+
+            Cmd::Disburse(other_account_id) if other_account_id != account_id => {
+                Err(Error::AccountMismatch(other_account_id))
+            }
+            Cmd::Disburse(_) if !current_schedule.is_empty() => Err(Error::AlreadyDisbursed),
+            Cmd::Disburse(_) => Ok(Evt::Disbursed {
+                id,
+                account_id,
+                principal: *principal,
+                schedule: schedule(*principal, *rate_bps, *installments, at),
+                at,
+            }
+            .with_tag(LOAN_TAG)),
+
+            Cmd::CompleteInstallment { .. } if current_schedule.is_empty() => {
+                Err(Error::NotYetDisbursed)
+            }
+            Cmd::CompleteInstallment { number, collected } => match current_schedule.get(number) {
+                None => Err(Error::UnknownInstallment(number)),
+                Some(Installment {
+                    amount,
+                    status: status @ (InstallmentStatus::Due | InstallmentStatus::Missed),
+                    ..
+                }) => {
+                    let amount = *amount;
+                    if collected {
+                        Ok(Evt::InstallmentPaid {
+                            id,
+                            account_id,
+                            number,
+                            amount,
+                            at,
+                        }
+                        .with_tag(LOAN_TAG))
+                    } else if *status == InstallmentStatus::Due {
+                        Ok(Evt::InstallmentMissed {
+                            id,
+                            account_id,
+                            number,
+                            amount,
+                            at,
+                        }
+                        .with_tag(LOAN_TAG))
+                    } else {
+                        Err(Error::InvalidInstallmentStatus(number))
+                    }
+                }
+                Some(_) => Err(Error::InvalidInstallmentStatus(number)),
+            },
+        }
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
+
+        match (&mut self.state, evt) {
+            // In State::NonExistent:
+            (
+                State::NonExistent,
+                Evt::Created {
+                    id,
+                    account_id,
+                    principal,
+                    rate_bps,
+                    installments,
+                    ..
+                },
+            ) => {
+                self.state = State::Created {
+                    id,
+                    account_id,
+                    principal,
+                    rate_bps,
+                    installments,
+                    schedule: vec![],
+                }
+            }
+            (State::NonExistent, evt) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+
+            // In State::Created:
+            (
+                State::Created { schedule, .. },
+                Evt::Disbursed {
+                    schedule: new_schedule,
+                    ..
+                },
+            ) => *schedule = new_schedule,
+
+            (State::Created { schedule, .. }, Evt::InstallmentPaid { number, .. }) => {
+                set_status(schedule, number, InstallmentStatus::Paid)
+            }
+
+            (State::Created { schedule, .. }, Evt::InstallmentMissed { number, .. }) => {
+                set_status(schedule, number, InstallmentStatus::Missed)
+            }
+
+            (State::Created { .. }, evt) => panic!("Illegal event '{evt:?}' in state Created"),
+        }
+
+        None
+    }
+
+    fn set_state(&mut self, state: Self::State) {
+        self.state = state;
+    }
+}
+
+/// The repayment schedule for the given principal, annual interest rate in basis points and number
+/// of installments: equal principal shares, the last one taking the remainder, plus the interest
+/// on the outstanding principal for the period, rounded down to full cents.
+pub fn schedule(
+    principal: EuroCent,
+    rate_bps: u32,
+    installments: u16,
+    disbursed_at: OffsetDateTime,
+) -> Vec<Installment> {
+    let principal = u64::from(principal);
+    let share = principal / installments as u64;
+    let periods_per_year =
+        (Duration::days(365).whole_days() / INSTALLMENT_PERIOD.whole_days()) as u64;
+
+    let mut outstanding = principal;
+    (1..=installments)
+        .map(|n| {
+            let share = if n == installments {
+                outstanding
+            } else {
+                share
+            };
+            let interest = EuroCent::from(outstanding).mul_div(
+                rate_bps as u64,
+                10_000 * periods_per_year,
+                Rounding::Down,
+            );
+            outstanding -= share;
+            Installment {
+                due_at: disbursed_at + INSTALLMENT_PERIOD * n as u32,
+                amount: EuroCent::from(share) + interest,
+                status: InstallmentStatus::Due,
+            }
+        })
+        .collect()
+}
+
+fn set_status(schedule: &mut [Installment], number: usize, status: InstallmentStatus) {
+    if let Some(installment) = schedule.get_mut(number) {
+        installment.status = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+
+    #[test]
+    fn test_schedule() {
+        let schedule = schedule(1_000u64.into(), 1_200, 3, OffsetDateTime::UNIX_EPOCH);
+        let amounts = schedule
+            .iter()
+            .map(|installment| u64::from(installment.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(amounts, vec![343, 339, 337]);
+        assert_eq!(
+            schedule[2].due_at,
+            OffsetDateTime::UNIX_EPOCH + INSTALLMENT_PERIOD * 3
+        );
+    }
+
+    #[test]
+    fn test_handle_cmd_and_evt() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut loan = Loan::default().with_clock(Arc::new(clock.clone()));
+
+        let id = Uuid::now_v7();
+        let account_id = Uuid::now_v7();
+
+        // Command Disburse fails in state NonExistent.
+        assert!(loan.handle_cmd(Cmd::Disburse(account_id)).is_err());
+
+        // Command Create fails without installments.
+        assert!(loan
+            .handle_cmd(Cmd::Create {
+                id,
+                account_id,
+                principal: 1_000u64.into(),
+                rate_bps: 500,
+                installments: 0,
+            })
+            .is_err());
+
+        loan.handle_evt(Evt::Created {
+            id,
+            account_id,
+            principal: 1_000u64.into(),
+            rate_bps: 0,
+            installments: 2,
+            at: clock.now(),
+        });
+
+        // Command CompleteInstallment fails before disbursement.
+        assert!(loan
+            .handle_cmd(Cmd::CompleteInstallment {
+                number: 0,
+                collected: true,
+            })
+            .is_err());
+
+        // Command Disburse fails for other accounts.
+        assert!(loan.handle_cmd(Cmd::Disburse(Uuid::now_v7())).is_err());
+
+        // Command Disburse succeeds.
+        assert!(loan.handle_cmd(Cmd::Disburse(account_id)).is_ok());
+        loan.handle_evt(Evt::Disbursed {
+            id,
+            account_id,
+            principal: 1_000u64.into(),
+            schedule: schedule(1_000u64.into(), 0, 2, clock.now()),
+            at: clock.now(),
+        });
+
+        // Missed installments are in arrears until paid.
+        loan.handle_evt(Evt::InstallmentMissed {
+            id,
+            account_id,
+            number: 0,
+            amount: 500u64.into(),
+            at: clock.now(),
+        });
+        assert!(matches!(
+            &loan.state,
+            State::Created { schedule, .. } if schedule[0].status == InstallmentStatus::Missed
+        ));
+        assert!(loan
+            .handle_cmd(Cmd::CompleteInstallment {
+                number: 0,
+                collected: false,
+            })
+            .is_err());
+        assert!(loan
+            .handle_cmd(Cmd::CompleteInstallment {
+                number: 0,
+                collected: true,
+            })
+            .is_ok());
+        loan.handle_evt(Evt::InstallmentPaid {
+            id,
+            account_id,
+            number: 0,
+            amount: 500u64.into(),
+            at: clock.now(),
+        });
+        assert!(matches!(
+            &loan.state,
+            State::Created { schedule, .. } if schedule[0].status == InstallmentStatus::Paid
+        ));
+
+        // Paid installments cannot be completed again.
+        assert!(loan
+            .handle_cmd(Cmd::CompleteInstallment {
+                number: 0,
+                collected: true,
+            })
+            .is_err());
+    }
+}
@@ -0,0 +1,522 @@
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    euro_cent::EuroCent,
+    redaction::Redacted,
+};
+use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub const MANDATE_TAG: &str = "mandate";
+
+/// Period after collection within which the debtor can claim a refund.
+pub const REFUND_PERIOD: Duration = Duration::weeks(8);
+
+/// A direct debit mandate, authorizing a creditor to collect from an account. Defaults to the
+/// [SystemClock].
+#[derive(Debug, Clone)]
+pub struct Mandate {
+    clock: Arc<dyn Clock>,
+    state: State,
+}
+
+impl Mandate {
+    /// Use the given [Clock] for timestamping events and checking the refund period.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+}
+
+impl Default for Mandate {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            state: State::default(),
+        }
+    }
+}
+
+/// Commands for an eventsourced [Mandate].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmd {
+    Create {
+        id: Uuid,
+        account_id: Uuid,
+        creditor: String,
+        max_amount: Option<EuroCent>,
+    },
+    Amend {
+        creditor: String,
+        max_amount: Option<EuroCent>,
+    },
+    Cancel,
+    ScheduleCollection {
+        collection_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        due_at: OffsetDateTime,
+    },
+    /// Complete a due collection, either collected or returned, e.g. for insufficient funds.
+    CompleteCollection {
+        collection_id: Uuid,
+        collected: bool,
+    },
+    RefundCollection(Uuid),
+}
+
+/// Events for an eventsourced [Mandate], timestamped with the time of command handling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evt {
+    Created {
+        id: Uuid,
+        account_id: Uuid,
+        creditor: String,
+        max_amount: Option<EuroCent>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Amended {
+        id: Uuid,
+        creditor: String,
+        max_amount: Option<EuroCent>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Cancelled {
+        id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    CollectionScheduled {
+        id: Uuid,
+        account_id: Uuid,
+        collection_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        due_at: OffsetDateTime,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    CollectionCollected {
+        id: Uuid,
+        collection_id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    CollectionReturned {
+        id: Uuid,
+        collection_id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    CollectionRefunded {
+        id: Uuid,
+        account_id: Uuid,
+        collection_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+impl Evt {
+    /// The ID of the mandate.
+    pub fn mandate_id(&self) -> Uuid {
+        match self {
+            Evt::Created { id, .. }
+            | Evt::Amended { id, .. }
+            | Evt::Cancelled { id, .. }
+            | Evt::CollectionScheduled { id, .. }
+            | Evt::CollectionCollected { id, .. }
+            | Evt::CollectionReturned { id, .. }
+            | Evt::CollectionRefunded { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum State {
+    #[default]
+    NonExistent,
+    Created {
+        id: Uuid,
+        account_id: Uuid,
+        creditor: String,
+        max_amount: Option<EuroCent>,
+        cancelled: bool,
+        collections: BTreeMap<Uuid, Collection>,
+    },
+}
+
+/// A collection under a [Mandate].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Collection {
+    pub amount: EuroCent,
+    pub status: CollectionStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollectionStatus {
+    Scheduled,
+    Collected {
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Returned,
+    Refunded,
+}
+
+/// Command handler errors for an eventsourced [Mandate].
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("This mandate has not been created yet")]
+    NotYetCreated,
+
+    #[error("This mandate has already been created")]
+    AlreadyCreated,
+
+    #[error("This mandate has been cancelled")]
+    Cancelled,
+
+    #[error("Amount '{amount}' exceeds maximum amount '{max_amount}'")]
+    MaxAmountExceeded {
+        max_amount: EuroCent,
+        amount: EuroCent,
+    },
+
+    #[error("Collection '{0}' has already been scheduled")]
+    CollectionAlreadyScheduled(Uuid),
+
+    #[error("Unknown collection '{0}'")]
+    UnknownCollection(Uuid),
+
+    #[error("Collection '{0}' is not in a state allowing this")]
+    InvalidCollectionStatus(Uuid),
+
+    #[error("Refund period for collection '{0}' has expired")]
+    RefundPeriodExpired(Uuid),
+}
+
+impl EventSourced for Mandate {
+    type Cmd = Cmd;
+
+    type Evt = Evt;
+
+    type State = State;
+
+    type Error = Error;
+
+    fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let at = self.clock.now();
+
+        let State::Created {
+            id,
+            account_id,
+            max_amount,
+            cancelled,
+            collections,
+            ..
+        } = &self.state
+        else {
+            // In State::NonExistent:
+            return match cmd {
+                Cmd::Create {
+                    id,
+                    account_id,
+                    creditor,
+                    max_amount,
+                } => Ok(Evt::Created {
+                    id,
+                    account_id,
+                    creditor,
+                    max_amount,
+                    at,
+                }
+                .with_tag(MANDATE_TAG)),
+                other => {
+                    error!("Cannot handle command '{other:?}' in state NonExistent");
+                    Err(Error::NotYetCreated)
+                }
+            };
+        };
+
+        // In State::Created:
+        let id = *id;
+        match cmd {
+            Cmd::Create { .. } => Err(Error::AlreadyCreated),
+
+            Cmd::Amend { .. } | Cmd::Cancel | Cmd::ScheduleCollection { .. } if *cancelled => {
+                Err(Error::Cancelled)
+            }
+
+            Cmd::Amend {
+                creditor,
+                max_amount,
+            } => Ok(Evt::Amended {
+                id,
+                creditor,
+                max_amount,
+                at,
+            }
+            .with_tag(MANDATE_TAG)),
+
+            Cmd::Cancel => Ok(Evt::Cancelled { id, at }.with_tag(MANDATE_TAG)),
+
+            Cmd::ScheduleCollection { collection_id, .. }
+                if collections.contains_key(&collection_id) =>
+            {
+                Err(Error::CollectionAlreadyScheduled(collection_id))
+            }
+            Cmd::ScheduleCollection { amount, .. } if matches!(max_amount, Some(max_amount) if amount > *max_amount) => {
+                Err(Error::MaxAmountExceeded {
+                    max_amount: max_amount.unwrap_or_default(),
+                    amount,
+                })
+            }
+            Cmd::ScheduleCollection {
+                collection_id,
+                amount,
+                due_at,
+            } => Ok(Evt::CollectionScheduled {
+                id,
+                account_id: *account_id,
+                collection_id,
+                amount,
+                due_at,
+                at,
+            }
+            .with_tag(MANDATE_TAG)),
+
+            Cmd::CompleteCollection {
+                collection_id,
+                collected,
+            } => match collections.get(&collection_id) {
+                None => Err(Error::UnknownCollection(collection_id)),
+                Some(Collection {
+                    status: CollectionStatus::Scheduled,
+                    ..
+                }) => {
+                    let evt = if collected {
+                        Evt::CollectionCollected {
+                            id,
+                            collection_id,
+                            at,
+                        }
+                    } else {
+                        Evt::CollectionReturned {
+                            id,
+                            collection_id,
+                            at,
+                        }
+                    };
+                    Ok(evt.with_tag(MANDATE_TAG))
+                }
+                Some(_) => Err(Error::InvalidCollectionStatus(collection_id)),
+            },
+
+            Cmd::RefundCollection(collection_id) => match collections.get(&collection_id) {
+                None => Err(Error::UnknownCollection(collection_id)),
+                Some(Collection {
+                    amount,
+                    status: CollectionStatus::Collected { at: collected_at },
+                }) => {
+                    if at - *collected_at > REFUND_PERIOD {
+                        Err(Error::RefundPeriodExpired(collection_id))
+                    } else {
+                        Ok(Evt::CollectionRefunded {
+                            id,
+                            account_id: *account_id,
+                            collection_id,
+                            amount: *amount,
+                            at,
+                        }
+                        .with_tag(MANDATE_TAG))
+                    }
+                }
+                Some(_) => Err(Error::InvalidCollectionStatus(collection_id)),
+            },
+        }
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
+
+        match (&mut self.state, evt) {
+            // In State::NonExistent:
+            (
+                State::NonExistent,
+                Evt::Created {
+                    id,
+                    account_id,
+                    creditor,
+                    max_amount,
+                    ..
+                },
+            ) => {
+                self.state = State::Created {
+                    id,
+                    account_id,
+                    creditor,
+                    max_amount,
+                    cancelled: false,
+                    collections: BTreeMap::new(),
+                }
+            }
+            (State::NonExistent, evt) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+
+            // In State::Created:
+            (
+                State::Created {
+                    creditor,
+                    max_amount,
+                    ..
+                },
+                Evt::Amended {
+                    creditor: new_creditor,
+                    max_amount: new_max_amount,
+                    ..
+                },
+            ) => {
+                *creditor = new_creditor;
+                *max_amount = new_max_amount;
+            }
+
+            (State::Created { cancelled, .. }, Evt::Cancelled { .. }) => *cancelled = true,
+
+            (
+                State::Created { collections, .. },
+                Evt::CollectionScheduled {
+                    collection_id,
+                    amount,
+                    ..
+                },
+            ) => {
+                collections.insert(
+                    collection_id,
+                    Collection {
+                        amount,
+                        status: CollectionStatus::Scheduled,
+                    },
+                );
+            }
+
+            (
+                State::Created { collections, .. },
+                Evt::CollectionCollected {
+                    collection_id, at, ..
+                },
+            ) => set_status(
+                collections,
+                collection_id,
+                CollectionStatus::Collected { at },
+            ),
+
+            (State::Created { collections, .. }, Evt::CollectionReturned { collection_id, .. }) => {
+                set_status(collections, collection_id, CollectionStatus::Returned)
+            }
+
+            (State::Created { collections, .. }, Evt::CollectionRefunded { collection_id, .. }) => {
+                set_status(collections, collection_id, CollectionStatus::Refunded)
+            }
+
+            (State::Created { .. }, evt) => panic!("Illegal event '{evt:?}' in state Created"),
+        }
+
+        None
+    }
+
+    fn set_state(&mut self, state: Self::State) {
+        self.state = state;
+    }
+}
+
+fn set_status(collections: &mut BTreeMap<Uuid, Collection>, id: Uuid, status: CollectionStatus) {
+    if let Some(collection) = collections.get_mut(&id) {
+        collection.status = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+
+    #[test]
+    fn test_handle_cmd_and_evt() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut mandate = Mandate::default().with_clock(Arc::new(clock.clone()));
+
+        let id = Uuid::now_v7();
+        let account_id = Uuid::now_v7();
+        let collection_id = Uuid::now_v7();
+        let schedule = |amount: u64| Cmd::ScheduleCollection {
+            collection_id,
+            amount: amount.into(),
+            due_at: OffsetDateTime::UNIX_EPOCH,
+        };
+
+        // Command ScheduleCollection fails in state NonExistent.
+        assert!(mandate.handle_cmd(schedule(1)).is_err());
+
+        mandate.handle_evt(Evt::Created {
+            id,
+            account_id,
+            creditor: "utility".to_string(),
+            max_amount: Some(100u64.into()),
+            at: clock.now(),
+        });
+
+        // Command ScheduleCollection fails for amounts exceeding the maximum.
+        assert!(mandate.handle_cmd(schedule(101)).is_err());
+
+        // Command ScheduleCollection succeeds.
+        assert!(mandate.handle_cmd(schedule(100)).is_ok());
+        mandate.handle_evt(Evt::CollectionScheduled {
+            id,
+            account_id,
+            collection_id,
+            amount: 100u64.into(),
+            due_at: OffsetDateTime::UNIX_EPOCH,
+            at: clock.now(),
+        });
+
+        // Command RefundCollection fails for scheduled collections.
+        assert!(mandate
+            .handle_cmd(Cmd::RefundCollection(collection_id))
+            .is_err());
+
+        mandate.handle_evt(Evt::CollectionCollected {
+            id,
+            collection_id,
+            at: clock.now(),
+        });
+
+        // Command RefundCollection succeeds within the refund period only.
+        assert!(mandate
+            .handle_cmd(Cmd::RefundCollection(collection_id))
+            .is_ok());
+        clock.advance(REFUND_PERIOD + Duration::days(1));
+        assert!(mandate
+            .handle_cmd(Cmd::RefundCollection(collection_id))
+            .is_err());
+
+        // Cancelled mandates do not accept new collections.
+        mandate.handle_evt(Evt::Cancelled {
+            id,
+            at: clock.now(),
+        });
+        assert!(mandate
+            .handle_cmd(Cmd::ScheduleCollection {
+                collection_id: Uuid::now_v7(),
+                amount: 1u64.into(),
+                due_at: OffsetDateTime::UNIX_EPOCH,
+            })
+            .is_err());
+    }
+}
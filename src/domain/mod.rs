@@ -1,2 +1,20 @@
 pub mod account;
+pub mod alert;
+pub mod books;
+pub mod channel;
+pub mod clock;
+pub mod consent;
+pub mod currency;
+pub mod customer;
+pub mod dispute;
 pub mod euro_cent;
+pub mod iban;
+pub mod id;
+pub mod loan;
+pub mod mandate;
+pub mod money;
+pub mod redaction;
+pub mod state_machine;
+pub mod tenant;
+pub mod term_deposit;
+pub mod transfer;
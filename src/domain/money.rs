@@ -0,0 +1,124 @@
+use crate::domain::euro_cent::EuroCent;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display, Formatter},
+    iter::Sum,
+    ops::{Add, Neg, Sub},
+};
+use thiserror::Error;
+
+/// Signed EUR cent backed by an `i128`, for large amounts, e.g. bank-level positions, and signed
+/// intermediate results, e.g. net flows or reversals, which would overflow or underflow
+/// [EuroCent]. Defaults to 0€.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Money(i128);
+
+impl Money {
+    /// Is this amount negative?
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// The cents of this amount.
+    pub fn cents(self) -> i128 {
+        self.0
+    }
+}
+
+impl Display for Money {
+    /// Format [Money] as 123.05€ or -123.05€.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let eur = self.0.unsigned_abs() / 100;
+        let cent = self.0.unsigned_abs() % 100;
+        write!(f, "{sign}{eur}.{cent:02}€")
+    }
+}
+
+impl Add for Money {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        iter.fold(Money::default(), Add::add)
+    }
+}
+
+impl From<i128> for Money {
+    fn from(value: i128) -> Self {
+        Money(value)
+    }
+}
+
+impl From<EuroCent> for Money {
+    fn from(value: EuroCent) -> Self {
+        Money(u64::from(value) as i128)
+    }
+}
+
+/// Conversion into [EuroCent], failing for negative amounts or amounts exceeding its range.
+impl TryFrom<Money> for EuroCent {
+    type Error = OutOfRange;
+
+    fn try_from(value: Money) -> Result<Self, Self::Error> {
+        u64::try_from(value.0)
+            .map(EuroCent::from)
+            .map_err(|_| OutOfRange(value))
+    }
+}
+
+/// Error for [Money] out of the range of [EuroCent].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Amount '{0}' out of range of EUR cent")]
+pub struct OutOfRange(Money);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_money() {
+        let max = Money::from(EuroCent::from(u64::MAX));
+        let sum = [max, max, max].into_iter().sum::<Money>();
+        assert_eq!(sum.cents(), 3 * u64::MAX as i128);
+        assert!(EuroCent::try_from(sum).is_err());
+        assert_eq!(
+            EuroCent::try_from(sum - max - max),
+            Ok(EuroCent::from(u64::MAX))
+        );
+
+        let reversal = Money::from(EuroCent::from(42)) - Money::from(EuroCent::from(142));
+        assert!(reversal.is_negative());
+        assert_eq!(reversal.to_string(), "-1.00€");
+        assert_eq!((-reversal).to_string(), "1.00€");
+        assert!(EuroCent::try_from(reversal).is_err());
+
+        assert_eq!(serde_json::to_string(&reversal).unwrap(), "-100");
+    }
+}
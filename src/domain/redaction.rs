@@ -0,0 +1,264 @@
+use serde::Deserialize;
+use std::{
+    fmt::{self, Debug, Formatter},
+    iter::Peekable,
+    str::CharIndices,
+    sync::OnceLock,
+};
+
+const MASK: &str = "***";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Configuration for redacting sensitive data, e.g. amounts or personal data, from logs and spans.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// If false, nothing is redacted, e.g. for development.
+    enabled: bool,
+
+    /// Request headers logged with their values; the values of all others are masked.
+    allowed_headers: Vec<String>,
+
+    /// Fields of commands, events, etc. logged with their values; the values of all others are
+    /// masked.
+    allowed_fields: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_headers: vec![],
+            allowed_fields: vec![],
+        }
+    }
+}
+
+/// Initialize redaction with the given [Config]; must be invoked before logging, else the default
+/// configuration, redacting everything, is used.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// Whether the value of the request header with the given name may be logged.
+pub fn is_allowed_header(name: &str) -> bool {
+    let config = config();
+    !config.enabled
+        || config
+            .allowed_headers
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+}
+
+/// Wrapper for logging a value, e.g. a command or event, via its [Debug] representation with all
+/// string and numeric values masked, unless belonging to an allowed field.
+pub struct Redacted<'a, T>(pub &'a T);
+
+impl<T> Debug for Redacted<'_, T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let debug = format!("{:?}", self.0);
+        let config = config();
+        if config.enabled {
+            f.write_str(&redact(&debug, &config.allowed_fields))
+        } else {
+            f.write_str(&debug)
+        }
+    }
+}
+
+/// A secret from the configuration, e.g. a key, which is never logged, regardless of the redaction
+/// configuration.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// The secret value; not to be logged.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(MASK)
+    }
+}
+
+fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Mask the string literals and the words containing digits, e.g. numbers, dates or IBANs, of the
+/// given [Debug] representation, unless in the scope of one of the given fields.
+fn redact(debug: &str, allowed_fields: &[String]) -> String {
+    let mut redacted = String::with_capacity(debug.len());
+    let mut chars = debug.char_indices().peekable();
+
+    // Innermost field for each nesting level, innermost level last.
+    let mut fields = vec![None::<&str>];
+    let allowed = |fields: &[Option<&str>]| {
+        fields
+            .iter()
+            .rev()
+            .find_map(|field| *field)
+            .map(|field| allowed_fields.iter().any(|allowed| allowed == field))
+            .unwrap_or_default()
+    };
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' => {
+                let end = string_end(&mut chars).unwrap_or(debug.len());
+                if allowed(&fields) {
+                    redacted.push_str(&debug[start..end]);
+                } else {
+                    redacted.push('"');
+                    redacted.push_str(MASK);
+                    redacted.push('"');
+                }
+            }
+
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let end = word_end(debug, &mut chars);
+                let word = &debug[start..end];
+                let is_field = debug[end..].starts_with(": ");
+                if is_field {
+                    *fields.last_mut().expect("fields not empty") = Some(word);
+                    redacted.push_str(word);
+                } else if word.bytes().any(|b| b.is_ascii_digit()) && !allowed(&fields) {
+                    redacted.push_str(MASK);
+                } else {
+                    redacted.push_str(word);
+                }
+            }
+
+            '(' | '{' | '[' => {
+                fields.push(None);
+                redacted.push(c);
+            }
+
+            ')' | '}' | ']' => {
+                if fields.len() > 1 {
+                    fields.pop();
+                }
+                redacted.push(c);
+            }
+
+            ',' => {
+                *fields.last_mut().expect("fields not empty") = None;
+                redacted.push(c);
+            }
+
+            c => redacted.push(c),
+        }
+    }
+
+    redacted
+}
+
+/// Consume a string literal after its opening quote, returning the index after its closing quote.
+fn string_end(chars: &mut Peekable<CharIndices<'_>>) -> Option<usize> {
+    while let Some((n, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some(n + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Consume a word after its first character, returning the index after its last character. Words
+/// are made of alphanumeric characters and underscores, joined by hyphens, dots or single colons,
+/// e.g. UUIDs, decimals or times.
+fn word_end(debug: &str, chars: &mut Peekable<CharIndices<'_>>) -> usize {
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    loop {
+        match chars.peek().copied() {
+            Some((_, c)) if is_word_char(c) => {
+                chars.next();
+            }
+
+            Some((n, c @ ('-' | '.' | ':'))) => {
+                let joins = debug[n + 1..].chars().next().is_some_and(is_word_char);
+                let is_path = c == ':' && debug[n + 1..].starts_with(':');
+                if joins && !is_path {
+                    chars.next();
+                } else {
+                    return n;
+                }
+            }
+
+            Some((n, _)) => return n,
+
+            None => return debug.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    enum Cmd {
+        Deposit {
+            id: Uuid,
+            amount: Amount,
+            reference: String,
+        },
+    }
+
+    #[derive(Debug)]
+    struct Amount(u64);
+
+    #[test]
+    fn test_redact() {
+        let id = Uuid::from_u128(0x1234_5678_9abc_def0_1234_5678_9abc_def0);
+        let cmd = Cmd::Deposit {
+            id,
+            amount: Amount(4_200),
+            reference: "Rent \"May\"".to_string(),
+        };
+
+        let redacted = redact(&format!("{cmd:?}"), &[]);
+        assert_eq!(
+            redacted,
+            r#"Deposit { id: ***, amount: Amount(***), reference: "***" }"#
+        );
+
+        let redacted = redact(&format!("{cmd:?}"), &["id".to_string()]);
+        assert_eq!(
+            redacted,
+            format!(r#"Deposit {{ id: {id}, amount: Amount(***), reference: "***" }}"#)
+        );
+
+        let redacted = redact(&format!("{cmd:?}"), &["amount".to_string()]);
+        assert_eq!(
+            redacted,
+            r#"Deposit { id: ***, amount: Amount(4200), reference: "***" }"#
+        );
+    }
+
+    #[test]
+    fn test_secret() {
+        let secret = Secret::from("secret".to_string());
+        assert_eq!(secret.expose(), "secret");
+        assert_eq!(format!("{secret:?}"), "***");
+    }
+}
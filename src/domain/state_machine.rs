@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// A transition of a state machine: handling a command in a state, if the guard holds, either
+/// results in an event and the resulting state or is rejected with an error. Transitions for the
+/// same state and command are tried in order, hence only the last one may be without guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Transition {
+    pub state: &'static str,
+    pub cmd: &'static str,
+    pub guard: Option<&'static str>,
+    pub outcome: Outcome,
+}
+
+impl Transition {
+    /// A [Transition] resulting in the given event and state.
+    pub const fn evt(
+        state: &'static str,
+        cmd: &'static str,
+        guard: Option<&'static str>,
+        evt: &'static str,
+        to: &'static str,
+    ) -> Self {
+        Self {
+            state,
+            cmd,
+            guard,
+            outcome: Outcome::Evt { evt, to },
+        }
+    }
+
+    /// A [Transition] rejected with the given error.
+    pub const fn rejected(
+        state: &'static str,
+        cmd: &'static str,
+        guard: Option<&'static str>,
+        error: &'static str,
+    ) -> Self {
+        Self {
+            state,
+            cmd,
+            guard,
+            outcome: Outcome::Rejected { error },
+        }
+    }
+}
+
+/// Outcome of a [Transition].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    /// The command results in the given event and state.
+    Evt { evt: &'static str, to: &'static str },
+
+    /// The command is rejected with the given error and the state is unchanged.
+    Rejected { error: &'static str },
+}
+
+/// Diagram formats.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    #[default]
+    Mermaid,
+    Dot,
+}
+
+/// Render the given [Transition]s, starting in the given initial state, as a diagram in the given
+/// [Format]. Rejections are rendered as loops.
+pub fn diagram(name: &str, initial: &str, transitions: &[Transition], format: Format) -> String {
+    match format {
+        Format::Mermaid => mermaid(initial, transitions),
+        Format::Dot => dot(name, initial, transitions),
+    }
+}
+
+fn mermaid(initial: &str, transitions: &[Transition]) -> String {
+    let mut diagram = format!("stateDiagram-v2\n    [*] --> {initial}\n");
+    for transition in transitions {
+        let (to, label) = to_and_label(transition);
+        let _ = writeln!(diagram, "    {} --> {to}: {label}", transition.state);
+    }
+    diagram
+}
+
+fn dot(name: &str, initial: &str, transitions: &[Transition]) -> String {
+    let mut diagram =
+        format!("digraph \"{name}\" {{\n    start [shape=point];\n    start -> \"{initial}\";\n");
+    for transition in transitions {
+        let (to, label) = to_and_label(transition);
+        let style = match transition.outcome {
+            Outcome::Evt { .. } => "",
+            Outcome::Rejected { .. } => ", style=dashed, color=red",
+        };
+        let _ = writeln!(
+            diagram,
+            "    \"{}\" -> \"{to}\" [label=\"{label}\"{style}];",
+            transition.state
+        );
+    }
+    diagram.push_str("}\n");
+    diagram
+}
+
+fn to_and_label(transition: &Transition) -> (&'static str, String) {
+    let guard = transition
+        .guard
+        .map(|guard| format!(" [{guard}]"))
+        .unwrap_or_default();
+    match transition.outcome {
+        Outcome::Evt { evt, to } => (to, format!("{}{guard} / {evt}", transition.cmd)),
+        Outcome::Rejected { error } => (
+            transition.state,
+            format!("{}{guard} / error {error}", transition.cmd),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSITIONS: &[Transition] = &[
+        Transition::evt("Closed", "open", None, "Opened", "Open"),
+        Transition::rejected("Open", "open", None, "AlreadyOpen"),
+    ];
+
+    #[test]
+    fn test_diagram() {
+        assert_eq!(
+            diagram("door", "Closed", TRANSITIONS, Format::Mermaid),
+            "stateDiagram-v2\n    [*] --> Closed\n    Closed --> Open: open / Opened\n    Open --> \
+             Open: open / error AlreadyOpen\n"
+        );
+
+        let dot = diagram("door", "Closed", TRANSITIONS, Format::Dot);
+        assert!(dot.starts_with("digraph \"door\" {"));
+        assert!(dot.contains("\"Closed\" -> \"Open\" [label=\"open / Opened\"];"));
+        assert!(dot.contains(
+            "\"Open\" -> \"Open\" [label=\"open / error AlreadyOpen\", style=dashed, color=red];"
+        ));
+    }
+}
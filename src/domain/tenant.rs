@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+use uuid::Uuid;
+
+/// ID of a tenant, i.e. a bank or brand served by this deployment. Defaults to the nil UUID, the
+/// default tenant for requests not specifying one.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct TenantId(Uuid);
+
+impl Display for TenantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for TenantId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(TenantId)
+    }
+}
+
+impl From<Uuid> for TenantId {
+    fn from(value: Uuid) -> Self {
+        TenantId(value)
+    }
+}
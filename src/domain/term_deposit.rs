@@ -0,0 +1,352 @@
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    euro_cent::{DayCount, EuroCent, Rounding},
+    redaction::Redacted,
+};
+use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub const TERM_DEPOSIT_TAG: &str = "term-deposit";
+
+/// Penalty in basis points of the amount for withdrawing before maturity, in which case no interest
+/// is paid.
+pub const EARLY_WITHDRAWAL_PENALTY_BPS: u64 = 100;
+
+/// A term deposit, locking funds moved from an account until maturity, when they are paid back
+/// together with the interest. Defaults to the [SystemClock].
+#[derive(Debug, Clone)]
+pub struct TermDeposit {
+    clock: Arc<dyn Clock>,
+    state: State,
+}
+
+impl TermDeposit {
+    /// Use the given [Clock] for timestamping events and checking maturity.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+}
+
+impl Default for TermDeposit {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            state: State::default(),
+        }
+    }
+}
+
+/// Commands for an eventsourced [TermDeposit].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmd {
+    /// Open a term deposit for the given amount, which must already have been withdrawn from the
+    /// account.
+    Open {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        /// Annual interest rate in basis points.
+        rate_bps: u32,
+        term_days: u16,
+    },
+    Mature,
+    WithdrawEarly,
+}
+
+/// Events for an eventsourced [TermDeposit], timestamped with the time of command handling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evt {
+    Opened {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        rate_bps: u32,
+        #[serde(with = "time::serde::rfc3339")]
+        matures_at: OffsetDateTime,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Matured {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        interest: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    WithdrawnEarly {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        penalty: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+impl Evt {
+    /// The ID of the term deposit.
+    pub fn term_deposit_id(&self) -> Uuid {
+        match self {
+            Evt::Opened { id, .. } | Evt::Matured { id, .. } | Evt::WithdrawnEarly { id, .. } => {
+                *id
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum State {
+    #[default]
+    NonExistent,
+    Open {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        rate_bps: u32,
+        #[serde(with = "time::serde::rfc3339")]
+        opened_at: OffsetDateTime,
+        #[serde(with = "time::serde::rfc3339")]
+        matures_at: OffsetDateTime,
+    },
+    Closed,
+}
+
+/// Command handler errors for an eventsourced [TermDeposit].
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("This term deposit has not been opened yet")]
+    NotYetOpened,
+
+    #[error("This term deposit has already been opened")]
+    AlreadyOpened,
+
+    #[error("Amount must be positive")]
+    ZeroAmount,
+
+    #[error("Term must be at least one day")]
+    ZeroTerm,
+
+    #[error("This term deposit has not yet matured")]
+    NotYetMatured,
+
+    #[error("This term deposit has already reached maturity")]
+    MaturityReached,
+
+    #[error("This term deposit has already been closed")]
+    Closed,
+}
+
+impl EventSourced for TermDeposit {
+    type Cmd = Cmd;
+
+    type Evt = Evt;
+
+    type State = State;
+
+    type Error = Error;
+
+    fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let at = self.clock.now();
+
+        match (self.state, cmd) {
+            // In State::NonExistent:
+            (State::NonExistent, Cmd::Open { amount, .. }) if amount == EuroCent::default() => {
+                Err(Error::ZeroAmount)
+            }
+            (State::NonExistent, Cmd::Open { term_days: 0, .. }) => Err(Error::ZeroTerm),
+            (
+                State::NonExistent,
+                Cmd::Open {
+                    id,
+                    account_id,
+                    amount,
+                    rate_bps,
+                    term_days,
+                },
+            ) => Ok(Evt::Opened {
+                id,
+                account_id,
+                amount,
+                rate_bps,
+                matures_at: at + Duration::days(term_days as i64),
+                at,
+            }
+            .with_tag(TERM_DEPOSIT_TAG)),
+            (State::NonExistent, other) => {
+                error!("Cannot handle command '{other:?}' in state NonExistent");
+                Err(Error::NotYetOpened)
+            }
+
+            // In State::Open:
+            (State::Open { .. }, Cmd::Open { .. }) => Err(Error::AlreadyOpened),
+
+            (State::Open { matures_at, .. }, Cmd::Mature) if at < matures_at => {
+                Err(Error::NotYetMatured)
+            }
+            (
+                State::Open {
+                    id,
+                    account_id,
+                    amount,
+                    rate_bps,
+                    opened_at,
+                    matures_at,
+                },
+                Cmd::Mature,
+            ) => Ok(Evt::Matured {
+                id,
+                account_id,
+                amount,
+                interest: interest(amount, rate_bps, matures_at - opened_at),
+                at,
+            }
+            .with_tag(TERM_DEPOSIT_TAG)),
+
+            (State::Open { matures_at, .. }, Cmd::WithdrawEarly) if at >= matures_at => {
+                Err(Error::MaturityReached)
+            }
+            (
+                State::Open {
+                    id,
+                    account_id,
+                    amount,
+                    ..
+                },
+                Cmd::WithdrawEarly,
+            ) => Ok(Evt::WithdrawnEarly {
+                id,
+                account_id,
+                amount,
+                penalty: amount.bps(EARLY_WITHDRAWAL_PENALTY_BPS, Rounding::Down),
+                at,
+            }
+            .with_tag(TERM_DEPOSIT_TAG)),
+
+            // In State::Closed:
+            (State::Closed, Cmd::Open { .. }) => Err(Error::AlreadyOpened),
+            (State::Closed, _) => Err(Error::Closed),
+        }
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
+
+        match (self.state, evt) {
+            // In State::NonExistent:
+            (
+                State::NonExistent,
+                Evt::Opened {
+                    id,
+                    account_id,
+                    amount,
+                    rate_bps,
+                    matures_at,
+                    at,
+                },
+            ) => {
+                self.state = State::Open {
+                    id,
+                    account_id,
+                    amount,
+                    rate_bps,
+                    opened_at: at,
+                    matures_at,
+                }
+            }
+            (State::NonExistent, evt) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+
+            // In State::Open:
+            (State::Open { .. }, Evt::Matured { .. } | Evt::WithdrawnEarly { .. }) => {
+                self.state = State::Closed
+            }
+            (State::Open { .. }, evt) => panic!("Illegal event '{evt:?}' in state Open"),
+
+            // In State::Closed:
+            (State::Closed, evt) => panic!("Illegal event '{evt:?}' in state Closed"),
+        }
+
+        None
+    }
+
+    fn set_state(&mut self, state: Self::State) {
+        self.state = state;
+    }
+}
+
+/// Simple interest for the given amount, annual interest rate in basis points and term, prorated
+/// over the whole days with ACT/365 and rounded down to full cents.
+fn interest(amount: EuroCent, rate_bps: u32, term: Duration) -> EuroCent {
+    let days = term.whole_days().max(0) as u64;
+    amount.interest(rate_bps as u64, days, DayCount::Act365, Rounding::Down)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+
+    #[test]
+    fn test_interest() {
+        assert_eq!(
+            interest(100_000u64.into(), 365, Duration::days(100)),
+            1_000u64.into()
+        );
+        assert_eq!(
+            interest(100_000u64.into(), 365, Duration::days(1) - Duration::SECOND),
+            EuroCent::default()
+        );
+    }
+
+    #[test]
+    fn test_handle_cmd_and_evt() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut term_deposit = TermDeposit::default().with_clock(Arc::new(clock.clone()));
+
+        let id = Uuid::now_v7();
+        let account_id = Uuid::now_v7();
+
+        // Command Mature fails in state NonExistent.
+        assert!(term_deposit.handle_cmd(Cmd::Mature).is_err());
+
+        term_deposit.handle_evt(Evt::Opened {
+            id,
+            account_id,
+            amount: 100_000u64.into(),
+            rate_bps: 365,
+            matures_at: clock.now() + Duration::days(100),
+            at: clock.now(),
+        });
+
+        // Command Mature fails before maturity.
+        assert!(term_deposit.handle_cmd(Cmd::Mature).is_err());
+
+        // Command WithdrawEarly succeeds before maturity.
+        assert!(term_deposit.handle_cmd(Cmd::WithdrawEarly).is_ok());
+
+        // Command Mature succeeds at maturity.
+        clock.advance(Duration::days(100));
+        assert!(term_deposit.handle_cmd(Cmd::Mature).is_ok());
+
+        // Command WithdrawEarly fails at maturity.
+        assert!(term_deposit.handle_cmd(Cmd::WithdrawEarly).is_err());
+
+        term_deposit.handle_evt(Evt::Matured {
+            id,
+            account_id,
+            amount: 100_000u64.into(),
+            interest: 1_000u64.into(),
+            at: clock.now(),
+        });
+
+        // Closed term deposits cannot be matured again.
+        assert!(term_deposit.handle_cmd(Cmd::Mature).is_err());
+    }
+}
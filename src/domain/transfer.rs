@@ -0,0 +1,527 @@
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    euro_cent::EuroCent,
+    redaction::Redacted,
+};
+use eventsourced::{EventSourced, EvtExt, IntoTaggedEvt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub const TRANSFER_TAG: &str = "transfer";
+
+/// A transfer of money from one account to another, i.e. the state of the saga debiting the
+/// source account and crediting the target account, or crediting the source account back, if the
+/// credit is rejected. Sweeps of the balance of closed accounts start debited, as closing has
+/// already debited the source account. The steps are executed by a process manager and recorded
+/// here, such that transfers can be resumed, e.g. after a restart. Defaults to the [SystemClock].
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    clock: Arc<dyn Clock>,
+    state: State,
+}
+
+impl Transfer {
+    /// Use the given [Clock] for timestamping events.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+}
+
+impl Default for Transfer {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            state: State::default(),
+        }
+    }
+}
+
+/// Commands for an eventsourced [Transfer].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmd {
+    Initiate {
+        id: Uuid,
+        from_account: Uuid,
+        to_account: Uuid,
+        amount: EuroCent,
+    },
+    /// Initiate sweeping the balance of the closed source account, with the ID of the closure.
+    InitiateSweep {
+        id: Uuid,
+        from_account: Uuid,
+        to_account: Uuid,
+        amount: EuroCent,
+    },
+    /// Record that the source account has been debited.
+    RecordDebit,
+    /// Record that debiting the source account has been rejected for the given reason.
+    RejectDebit(String),
+    /// Record that the target account has been credited.
+    RecordCredit,
+    /// Record that crediting the target account has been rejected for the given reason.
+    RejectCredit(String),
+    /// Record that the source account has been credited back.
+    RecordCompensation,
+    /// Record that crediting back the source account has been rejected for the given reason.
+    RejectCompensation(String),
+}
+
+/// Events for an eventsourced [Transfer], timestamped with the time of command handling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evt {
+    Initiated {
+        id: Uuid,
+        from_account: Uuid,
+        to_account: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    SweepInitiated {
+        id: Uuid,
+        from_account: Uuid,
+        to_account: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Debited {
+        id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    DebitRejected {
+        id: Uuid,
+        reason: String,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Credited {
+        id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    CreditRejected {
+        id: Uuid,
+        reason: String,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Compensated {
+        id: Uuid,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    CompensationRejected {
+        id: Uuid,
+        reason: String,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+impl Evt {
+    /// The ID of the transfer.
+    pub fn transfer_id(&self) -> Uuid {
+        match self {
+            Evt::Initiated { id, .. }
+            | Evt::SweepInitiated { id, .. }
+            | Evt::Debited { id, .. }
+            | Evt::DebitRejected { id, .. }
+            | Evt::Credited { id, .. }
+            | Evt::CreditRejected { id, .. }
+            | Evt::Compensated { id, .. }
+            | Evt::CompensationRejected { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum State {
+    #[default]
+    NonExistent,
+    Created {
+        id: Uuid,
+        from_account: Uuid,
+        to_account: Uuid,
+        amount: EuroCent,
+        status: TransferStatus,
+        /// The reason for rejecting a step, if any.
+        reason: Option<String>,
+    },
+}
+
+/// Status of a [Transfer]; `Completed`, `Rejected`, `Compensated` and `CompensationFailed` are
+/// final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransferStatus {
+    /// The source account is to be debited.
+    Initiated,
+    /// The target account is to be credited.
+    Debited,
+    Completed,
+    /// Debiting the source account has been rejected, e.g. for insufficient funds.
+    Rejected,
+    /// Crediting the target account has been rejected, e.g. because it does not exist, hence the
+    /// source account is to be credited back.
+    Compensating,
+    Compensated,
+    /// Crediting back the source account has been rejected, which requires a manual booking.
+    CompensationFailed,
+}
+
+impl TransferStatus {
+    /// Whether the transfer is done, i.e. no further steps are to be executed.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TransferStatus::Completed
+                | TransferStatus::Rejected
+                | TransferStatus::Compensated
+                | TransferStatus::CompensationFailed
+        )
+    }
+}
+
+/// Command handler errors for an eventsourced [Transfer].
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("This transfer has not been initiated yet")]
+    NotYetInitiated,
+
+    #[error("This transfer has already been initiated")]
+    AlreadyInitiated,
+
+    #[error("Amount must be positive")]
+    ZeroAmount,
+
+    #[error("Source and target account must differ")]
+    SameAccount,
+
+    #[error("This transfer is not in a state allowing this")]
+    InvalidStatus,
+}
+
+impl EventSourced for Transfer {
+    type Cmd = Cmd;
+
+    type Evt = Evt;
+
+    type State = State;
+
+    type Error = Error;
+
+    fn handle_cmd(&self, cmd: Self::Cmd) -> Result<impl IntoTaggedEvt<Self::Evt>, Self::Error> {
+        debug!(cmd = ?Redacted(&cmd), "Handling command");
+
+        let at = self.clock.now();
+
+        let State::Created { id, status, .. } = &self.state else {
+            // In State::NonExistent:
+            return match cmd {
+                Cmd::Initiate { amount, .. } | Cmd::InitiateSweep { amount, .. }
+                    if amount == EuroCent::default() =>
+                {
+                    Err(Error::ZeroAmount)
+                }
+                Cmd::Initiate {
+                    from_account,
+                    to_account,
+                    ..
+                }
+                | Cmd::InitiateSweep {
+                    from_account,
+                    to_account,
+                    ..
+                } if from_account == to_account => Err(Error::SameAccount),
+                Cmd::InitiateSweep {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                } => Ok(Evt::SweepInitiated {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                    at,
+                }
+                .with_tag(TRANSFER_TAG)),
+                Cmd::Initiate {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                } => Ok(Evt::Initiated {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                    at,
+                }
+                .with_tag(TRANSFER_TAG)),
+                other => {
+                    error!("Cannot handle command '{other:?}' in state NonExistent");
+                    Err(Error::NotYetInitiated)
+                }
+            };
+        };
+
+        // In State::Created:
+        let id = *id;
+        let evt = match (status, cmd) {
+            (_, Cmd::Initiate { .. } | Cmd::InitiateSweep { .. }) => {
+                return Err(Error::AlreadyInitiated)
+            }
+            (TransferStatus::Initiated, Cmd::RecordDebit) => Evt::Debited { id, at },
+            (TransferStatus::Initiated, Cmd::RejectDebit(reason)) => {
+                Evt::DebitRejected { id, reason, at }
+            }
+            (TransferStatus::Debited, Cmd::RecordCredit) => Evt::Credited { id, at },
+            (TransferStatus::Debited, Cmd::RejectCredit(reason)) => {
+                Evt::CreditRejected { id, reason, at }
+            }
+            (TransferStatus::Compensating, Cmd::RecordCompensation) => Evt::Compensated { id, at },
+            (TransferStatus::Compensating, Cmd::RejectCompensation(reason)) => {
+                Evt::CompensationRejected { id, reason, at }
+            }
+            _ => return Err(Error::InvalidStatus),
+        };
+        Ok(evt.with_tag(TRANSFER_TAG))
+    }
+
+    fn handle_evt(&mut self, evt: Self::Evt) -> Option<Self::State> {
+        debug!(evt = ?Redacted(&evt), "Handling event");
+
+        match (&mut self.state, evt) {
+            // In State::NonExistent:
+            (
+                State::NonExistent,
+                Evt::Initiated {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                    ..
+                },
+            ) => {
+                self.state = State::Created {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                    status: TransferStatus::Initiated,
+                    reason: None,
+                }
+            }
+            (
+                State::NonExistent,
+                Evt::SweepInitiated {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                    ..
+                },
+            ) => {
+                self.state = State::Created {
+                    id,
+                    from_account,
+                    to_account,
+                    amount,
+                    status: TransferStatus::Debited,
+                    reason: None,
+                }
+            }
+            (State::NonExistent, evt) => panic!("Illegal event '{evt:?}' in state NonExistent"),
+
+            // In State::Created:
+            (State::Created { .. }, evt @ (Evt::Initiated { .. } | Evt::SweepInitiated { .. })) => {
+                panic!("Illegal event '{evt:?}' in state Created")
+            }
+            (State::Created { status, reason, .. }, evt) => {
+                let (new_status, new_reason) = match evt {
+                    Evt::Debited { .. } => (TransferStatus::Debited, None),
+                    Evt::DebitRejected { reason, .. } => (TransferStatus::Rejected, Some(reason)),
+                    Evt::Credited { .. } => (TransferStatus::Completed, None),
+                    Evt::CreditRejected { reason, .. } => {
+                        (TransferStatus::Compensating, Some(reason))
+                    }
+                    Evt::Compensated { .. } => (TransferStatus::Compensated, reason.take()),
+                    Evt::CompensationRejected { reason, .. } => {
+                        (TransferStatus::CompensationFailed, Some(reason))
+                    }
+                    Evt::Initiated { .. } | Evt::SweepInitiated { .. } => unreachable!(),
+                };
+                *status = new_status;
+                *reason = new_reason;
+            }
+        }
+
+        None
+    }
+
+    fn set_state(&mut self, state: Self::State) {
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+
+    #[test]
+    fn test_handle_cmd_and_evt() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut transfer = Transfer::default().with_clock(Arc::new(clock.clone()));
+
+        let id = Uuid::now_v7();
+        let from_account = Uuid::now_v7();
+        let initiate = |to_account, amount: u64| Cmd::Initiate {
+            id,
+            from_account,
+            to_account,
+            amount: amount.into(),
+        };
+
+        // Command RecordDebit fails in state NonExistent.
+        assert!(transfer.handle_cmd(Cmd::RecordDebit).is_err());
+
+        // Command Initiate fails for zero amounts and the same accounts.
+        assert!(matches!(
+            transfer.handle_cmd(initiate(Uuid::now_v7(), 0)),
+            Err(Error::ZeroAmount)
+        ));
+        assert!(matches!(
+            transfer.handle_cmd(initiate(from_account, 42)),
+            Err(Error::SameAccount)
+        ));
+
+        let to_account = Uuid::now_v7();
+        assert!(transfer.handle_cmd(initiate(to_account, 42)).is_ok());
+        transfer.handle_evt(Evt::Initiated {
+            id,
+            from_account,
+            to_account,
+            amount: 42u64.into(),
+            at: clock.now(),
+        });
+
+        // Steps must be recorded in order.
+        assert!(matches!(
+            transfer.handle_cmd(Cmd::RecordCredit),
+            Err(Error::InvalidStatus)
+        ));
+        assert!(transfer.handle_cmd(Cmd::RecordDebit).is_ok());
+        transfer.handle_evt(Evt::Debited {
+            id,
+            at: clock.now(),
+        });
+
+        // A rejected credit is compensated.
+        assert!(transfer
+            .handle_cmd(Cmd::RejectCredit("unknown account".to_string()))
+            .is_ok());
+        transfer.handle_evt(Evt::CreditRejected {
+            id,
+            reason: "unknown account".to_string(),
+            at: clock.now(),
+        });
+        assert!(matches!(
+            &transfer.state,
+            State::Created {
+                status: TransferStatus::Compensating,
+                ..
+            }
+        ));
+        transfer.handle_evt(Evt::Compensated {
+            id,
+            at: clock.now(),
+        });
+        assert!(matches!(
+            &transfer.state,
+            State::Created {
+                status: TransferStatus::Compensated,
+                reason: Some(reason),
+                ..
+            } if reason == "unknown account"
+        ));
+
+        // Final transfers do not accept further steps.
+        assert!(matches!(
+            transfer.handle_cmd(Cmd::RecordCompensation),
+            Err(Error::InvalidStatus)
+        ));
+    }
+
+    #[test]
+    fn test_rejected_sweep() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let mut transfer = Transfer::default().with_clock(Arc::new(clock.clone()));
+
+        let id = Uuid::now_v7();
+        let from_account = Uuid::now_v7();
+        let to_account = Uuid::now_v7();
+        let initiate_sweep = Cmd::InitiateSweep {
+            id,
+            from_account,
+            to_account,
+            amount: 42u64.into(),
+        };
+        assert!(transfer.handle_cmd(initiate_sweep.clone()).is_ok());
+        transfer.handle_evt(Evt::SweepInitiated {
+            id,
+            from_account,
+            to_account,
+            amount: 42u64.into(),
+            at: clock.now(),
+        });
+
+        // Sweeps start debited, as closing has debited the source account.
+        assert!(matches!(
+            &transfer.state,
+            State::Created {
+                status: TransferStatus::Debited,
+                ..
+            }
+        ));
+        assert!(matches!(
+            transfer.handle_cmd(initiate_sweep),
+            Err(Error::AlreadyInitiated)
+        ));
+        assert!(matches!(
+            transfer.handle_cmd(Cmd::RecordDebit),
+            Err(Error::InvalidStatus)
+        ));
+
+        // A rejected credit is compensated; as closed accounts reject deposits, this fails, which
+        // is recorded with the reason, such that the balance is not lost.
+        transfer.handle_evt(Evt::CreditRejected {
+            id,
+            reason: "closed".to_string(),
+            at: clock.now(),
+        });
+        assert!(transfer
+            .handle_cmd(Cmd::RejectCompensation("closed".to_string()))
+            .is_ok());
+        transfer.handle_evt(Evt::CompensationRejected {
+            id,
+            reason: "closed".to_string(),
+            at: clock.now(),
+        });
+        assert!(matches!(
+            &transfer.state,
+            State::Created {
+                status: TransferStatus::CompensationFailed,
+                amount,
+                ..
+            } if *amount == 42u64.into()
+        ));
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{
+    domain::{
+        account::{
+            self, AdjustmentDirection, ExternalRef, Labels, NotificationPrefs, Product, ReasonCode,
+        },
+        clock::Clock,
+        currency::Currency,
+        customer::CustomerId,
+        euro_cent::EuroCent,
+        iban::Iban,
+        tenant::TenantId,
+    },
+    infra::namespace::{EntityType, Namespace},
+};
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::{StreamExt, TryStreamExt};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::{collections::HashMap, convert::Infallible, future::Future, sync::Arc};
+use time::OffsetDateTime;
+use tokio::task;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Exports all data of an account in a machine-readable format, e.g. for data portability
+/// requests. Exports are generated asynchronously, because histories might be large.
+pub trait DataExporter: Clone + Send + Sync + 'static {
+    /// Start exporting the data of the account with the given ID, unless already in progress.
+    fn start(&self, id: Uuid) -> impl Future<Output = ()> + Send + '_;
+
+    /// The [DataExportStatus] for the account with the given ID, if any export has been started.
+    fn status(&self, id: Uuid) -> impl Future<Output = Option<DataExportStatus>> + Send + '_;
+}
+
+/// Status of an export.
+#[derive(Debug, Clone)]
+pub enum DataExportStatus {
+    Pending,
+    Ready(Arc<DataExport>),
+    Failed,
+}
+
+/// All data of an account.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataExport {
+    pub account: Option<AccountData>,
+    pub transactions: Vec<TransactionData>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+}
+
+/// Account and holder data.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountData {
+    pub id: Uuid,
+    pub tenant: TenantId,
+    pub customer: Option<CustomerId>,
+    pub iban: Iban,
+    pub currency: Currency,
+    pub opening_balance: EuroCent,
+    pub external_ref: Option<ExternalRef>,
+    pub product: Product,
+    pub sandbox: bool,
+    pub notification_prefs: NotificationPrefs,
+    pub labels: Labels,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// A deposit, withdrawal, card payment or adjustment.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionData {
+    pub id: Uuid,
+    pub kind: &'static str,
+    pub amount: EuroCent,
+    pub balance: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub value_date: OffsetDateTime,
+    /// Only for adjustments, which are always flagged as such.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<AdjustmentData>,
+}
+
+/// Reason and justification of an adjustment.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdjustmentData {
+    pub reason: ReasonCode,
+    pub justification: String,
+}
+
+/// [DataExporter] reading the events of an account from the event log and keeping the exports in
+/// memory.
+#[derive(Debug, Clone)]
+pub struct EvtLogDataExporter<L> {
+    evt_log: L,
+    namespace: Namespace,
+    clock: Arc<dyn Clock>,
+    exports: Arc<RwLock<HashMap<Uuid, DataExportStatus>>>,
+}
+
+impl<L> EvtLogDataExporter<L>
+where
+    L: EvtLog,
+{
+    #[allow(missing_docs)]
+    pub fn new(evt_log: L, namespace: Namespace, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            evt_log,
+            namespace,
+            clock,
+            exports: Default::default(),
+        }
+    }
+}
+
+impl<L> DataExporter for EvtLogDataExporter<L>
+where
+    L: EvtLog,
+{
+    async fn start(&self, id: Uuid) {
+        {
+            let mut exports = self.exports.write();
+            if matches!(exports.get(&id), Some(DataExportStatus::Pending)) {
+                return;
+            }
+            exports.insert(id, DataExportStatus::Pending);
+        }
+
+        debug!(%id, "Starting data export");
+        let evt_log = self.evt_log.clone();
+        let stream_id = self.namespace.id(EntityType::Account, id);
+        let clock = self.clock.clone();
+        let exports = self.exports.clone();
+        task::spawn(async move {
+            let status = match export(&evt_log, stream_id, clock.now()).await {
+                Ok(export) => DataExportStatus::Ready(Arc::new(export)),
+                Err(error) => {
+                    error!(%id, error = format!("{error:#}"), "Cannot export data");
+                    DataExportStatus::Failed
+                }
+            };
+            exports.write().insert(id, status);
+        });
+    }
+
+    async fn status(&self, id: Uuid) -> Option<DataExportStatus> {
+        self.exports.read().get(&id).cloned()
+    }
+}
+
+async fn export<L>(evt_log: &L, id: Uuid, now: OffsetDateTime) -> Result<DataExport>
+where
+    L: EvtLog,
+{
+    let mut export = DataExport {
+        account: None,
+        transactions: vec![],
+        exported_at: now,
+    };
+
+    let Some(last_seq_no) = evt_log
+        .last_seq_no(id)
+        .await
+        .context("Cannot get last sequence number")?
+    else {
+        return Ok(export);
+    };
+
+    let evts = evt_log
+        .evts_by_id::<Bytes, _, _, _>(id, SeqNo::MIN, raw)
+        .await
+        .context("Cannot get events")?
+        .take_while(|evt| {
+            let more = !matches!(evt, Ok((seq_no, _)) if *seq_no > last_seq_no);
+            async move { more }
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .context("Cannot get events")?;
+
+    for (_, evt) in evts {
+        let evt =
+            serde_json::from_slice::<account::Evt>(&evt).context("Cannot deserialize event")?;
+        match evt {
+            account::Evt::Created {
+                id,
+                tenant,
+                customer,
+                iban,
+                currency,
+                opening_balance,
+                external_ref,
+                product,
+                sandbox,
+                at,
+            } => {
+                export.account = Some(AccountData {
+                    id,
+                    tenant,
+                    customer,
+                    iban,
+                    currency,
+                    opening_balance,
+                    external_ref,
+                    product,
+                    sandbox,
+                    notification_prefs: NotificationPrefs::default(),
+                    labels: Labels::default(),
+                    created_at: at,
+                })
+            }
+
+            account::Evt::Deposited {
+                id,
+                old_balance,
+                amount,
+                value_date,
+                at,
+                ..
+            } => export.transactions.push(TransactionData {
+                id,
+                kind: "deposit",
+                amount,
+                balance: old_balance + amount,
+                at,
+                value_date: value_date.unwrap_or(at),
+                adjustment: None,
+            }),
+
+            account::Evt::Withdrawn {
+                id,
+                old_balance,
+                amount,
+                value_date,
+                at,
+                ..
+            } => export.transactions.push(TransactionData {
+                id,
+                kind: "withdrawal",
+                amount,
+                balance: old_balance - amount,
+                at,
+                value_date: value_date.unwrap_or(at),
+                adjustment: None,
+            }),
+
+            account::Evt::HoldCaptured {
+                id,
+                old_balance,
+                amount,
+                at,
+                ..
+            } => export.transactions.push(TransactionData {
+                id,
+                kind: "card-payment",
+                amount,
+                balance: old_balance - amount,
+                at,
+                value_date: at,
+                adjustment: None,
+            }),
+
+            account::Evt::Adjusted {
+                id,
+                old_balance,
+                direction,
+                amount,
+                reason,
+                justification,
+                at,
+                ..
+            } => export.transactions.push(TransactionData {
+                id,
+                kind: match direction {
+                    AdjustmentDirection::Credit => "adjustment-credit",
+                    AdjustmentDirection::Debit => "adjustment-debit",
+                },
+                amount,
+                balance: direction.apply(old_balance, amount),
+                at,
+                value_date: at,
+                adjustment: Some(AdjustmentData {
+                    reason,
+                    justification,
+                }),
+            }),
+
+            account::Evt::Closed {
+                id,
+                old_balance,
+                sweep_to: Some(_),
+                at,
+                ..
+            } => export.transactions.push(TransactionData {
+                id,
+                kind: "sweep",
+                amount: old_balance,
+                balance: EuroCent::default(),
+                at,
+                value_date: at,
+                adjustment: None,
+            }),
+
+            account::Evt::HoldPlaced { .. }
+            | account::Evt::HoldReleased { .. }
+            | account::Evt::Closed { sweep_to: None, .. }
+            | account::Evt::Reopened { .. }
+            | account::Evt::Purged { .. } => {}
+
+            account::Evt::NotificationPrefsSet {
+                notification_prefs, ..
+            } => {
+                if let Some(account) = &mut export.account {
+                    account.notification_prefs = notification_prefs;
+                }
+            }
+
+            account::Evt::LabelsSet { labels, .. } => {
+                if let Some(account) = &mut export.account {
+                    account.labels = labels;
+                }
+            }
+        }
+    }
+
+    Ok(export)
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
@@ -0,0 +1,269 @@
+use crate::{
+    domain::{
+        account::{self, Account, Snapshot},
+        clock::Clock,
+    },
+    infra::namespace::{EntityType, Namespace},
+};
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use eventsourced::{EventSourced, EvtLog, SeqNo};
+use futures::{StreamExt, TryStreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::{future::Future, sync::Arc};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Past and hypothetical states of accounts, i.e. time travel and what-if analysis, e.g. for
+/// dispute resolution.
+pub trait AccountHistory: Clone + Send + Sync + 'static {
+    /// The [Snapshot] of the account with the given ID as of the given time, i.e. after all its
+    /// events handled at or before that time; in state `NonExistent` if not yet created then.
+    fn snapshot_as_of(
+        &self,
+        id: Uuid,
+        as_of: OffsetDateTime,
+    ) -> impl Future<Output = Result<Snapshot>> + Send + '_;
+
+    /// Apply the given hypothetical commands, in order, to a sandboxed copy of the account with
+    /// the given ID, without persisting anything, and return the resulting [WhatIf].
+    fn what_if(
+        &self,
+        id: Uuid,
+        cmds: Vec<account::Cmd>,
+    ) -> impl Future<Output = Result<WhatIf>> + Send + '_;
+
+    /// Whether the account with the given ID has a deposit or withdrawal with the given
+    /// transaction ID, e.g. to check whether a command has already been handled before retrying it,
+    /// as these commands are not idempotent.
+    fn contains_tx(&self, id: Uuid, tx_id: Uuid) -> impl Future<Output = Result<bool>> + Send + '_;
+}
+
+/// The outcome of applying hypothetical commands to a sandboxed account.
+#[derive(Debug, Clone, Serialize)]
+pub struct WhatIf {
+    pub before: Snapshot,
+    pub after: Snapshot,
+    /// The events of the applied commands.
+    pub evts: Vec<account::Evt>,
+    /// The first rejected command, if any, after which no further commands are applied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected: Option<Rejection>,
+    /// The differences between the states before and after.
+    pub changes: Vec<Change>,
+}
+
+/// A rejected hypothetical command.
+#[derive(Debug, Clone, Serialize)]
+pub struct Rejection {
+    /// Index of the command.
+    pub index: usize,
+    pub error: String,
+}
+
+/// A changed field of the state, `null` if absent before or after, e.g. when changing the state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    /// JSON pointer to the field.
+    pub path: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// [AccountHistory] replaying the events of an account from the event log.
+#[derive(Debug, Clone)]
+pub struct EvtLogAccountHistory<L> {
+    evt_log: L,
+    namespace: Namespace,
+    clock: Arc<dyn Clock>,
+}
+
+impl<L> EvtLogAccountHistory<L>
+where
+    L: EvtLog,
+{
+    #[allow(missing_docs)]
+    pub fn new(evt_log: L, namespace: Namespace, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            evt_log,
+            namespace,
+            clock,
+        }
+    }
+
+    /// Replay the events of the account with the given ID, optionally only those handled at or
+    /// before the given time, into a fresh, i.e. sandboxed, [Account].
+    async fn replay(&self, id: Uuid, as_of: Option<OffsetDateTime>) -> Result<Account> {
+        let mut account = Account::default().with_clock(self.clock.clone());
+        for evt in self.evts(id, as_of).await? {
+            account.handle_evt(evt);
+        }
+        Ok(account)
+    }
+
+    /// The current events of the account with the given ID, optionally only those handled at or
+    /// before the given time.
+    async fn evts(&self, id: Uuid, as_of: Option<OffsetDateTime>) -> Result<Vec<account::Evt>> {
+        let stream_id = self.namespace.id(EntityType::Account, id);
+        let Some(last_seq_no) = self
+            .evt_log
+            .last_seq_no(stream_id)
+            .await
+            .context("Cannot get last sequence number")?
+        else {
+            return Ok(vec![]);
+        };
+
+        self.evt_log
+            .evts_by_id::<account::Evt, _, _, _>(stream_id, SeqNo::MIN, from_bytes)
+            .await
+            .context("Cannot get events")?
+            .take_while(|evt| {
+                let more = !matches!(
+                    evt,
+                    Ok((seq_no, evt))
+                        if *seq_no > last_seq_no || as_of.is_some_and(|as_of| evt.at() > as_of)
+                );
+                async move { more }
+            })
+            .map_ok(|(_, evt)| evt)
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Cannot get events")
+    }
+}
+
+impl<L> AccountHistory for EvtLogAccountHistory<L>
+where
+    L: EvtLog,
+{
+    async fn snapshot_as_of(&self, id: Uuid, as_of: OffsetDateTime) -> Result<Snapshot> {
+        let account = self.replay(id, Some(as_of)).await?;
+        let snapshot = account.subscribe().borrow().clone();
+        Ok(snapshot)
+    }
+
+    async fn contains_tx(&self, id: Uuid, tx_id: Uuid) -> Result<bool> {
+        let evts = self.evts(id, None).await?;
+        let contains_tx = evts.iter().any(|evt| {
+            matches!(
+                evt,
+                account::Evt::Deposited { id, .. } | account::Evt::Withdrawn { id, .. }
+                    if *id == tx_id
+            )
+        });
+        Ok(contains_tx)
+    }
+
+    async fn what_if(&self, id: Uuid, cmds: Vec<account::Cmd>) -> Result<WhatIf> {
+        let mut account = self.replay(id, None).await?;
+        let snapshots = account.subscribe();
+        let before = snapshots.borrow().clone();
+
+        let mut evts = vec![];
+        let mut rejected = None;
+        for (index, cmd) in cmds.into_iter().enumerate() {
+            match account.dry_run(cmd) {
+                Ok((evt, _)) => {
+                    account.handle_evt(evt.clone());
+                    evts.push(evt);
+                }
+
+                Err(error) => {
+                    rejected = Some(Rejection {
+                        index,
+                        error: error.to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+
+        let after = snapshots.borrow().clone();
+        let mut changes = vec![];
+        diff(
+            "",
+            &serde_json::to_value(&before.state).context("Cannot serialize state")?,
+            &serde_json::to_value(&after.state).context("Cannot serialize state")?,
+            &mut changes,
+        );
+
+        Ok(WhatIf {
+            before,
+            after,
+            evts,
+            rejected,
+            changes,
+        })
+    }
+}
+
+/// Collect the [Change]s between the given JSON values, recursing into objects.
+fn diff(path: &str, before: &Value, after: &Value, changes: &mut Vec<Change>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut keys = before.keys().chain(after.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                diff(
+                    &format!("{path}/{key}"),
+                    before.get(key).unwrap_or(&Value::Null),
+                    after.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+
+        (before, after) if before != after => changes.push(Change {
+            path: path.to_string(),
+            before: before.clone(),
+            after: after.clone(),
+        }),
+
+        _ => {}
+    }
+}
+
+fn from_bytes(bytes: Bytes) -> Result<account::Evt, serde_json::Error> {
+    serde_json::from_slice(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff() {
+        let before = json!({ "Created": { "balance": 100, "holds": { "a": 10 }, "iban": "x" } });
+        let after = json!({ "Created": { "balance": 90, "holds": {}, "iban": "x" } });
+        let mut changes = vec![];
+        diff("", &before, &after, &mut changes);
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    path: "/Created/balance".to_string(),
+                    before: json!(100),
+                    after: json!(90),
+                },
+                Change {
+                    path: "/Created/holds/a".to_string(),
+                    before: json!(10),
+                    after: Value::Null,
+                },
+            ]
+        );
+
+        let mut changes = vec![];
+        diff(
+            "",
+            &json!({ "Created": {} }),
+            &json!({ "Closed": {} }),
+            &mut changes,
+        );
+        assert_eq!(changes.len(), 2);
+    }
+}
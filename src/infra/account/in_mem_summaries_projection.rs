@@ -0,0 +1,521 @@
+use super::{AccountStatus, AccountSummariesProjection, AccountSummary};
+use crate::{
+    domain::{
+        account,
+        account::{ExternalRef, Label, Labels},
+        clock::Clock,
+        customer::CustomerId,
+        euro_cent::EuroCent,
+        iban::Iban,
+        id::IdGenerator,
+        tenant::TenantId,
+    },
+    infra::{
+        dead_letter::DeadLetterQueue,
+        projection::{
+            ErrorPolicies, PartitionedWorkers, ProjectionHandler, ProjectionRunner,
+            ProjectionStore, Projections, RestartConfig,
+        },
+    },
+};
+use anyhow::Context;
+use dashmap::DashMap;
+use eventsourced::EvtLog;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, future::Future, num::NonZeroUsize, sync::Arc};
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
+use tracing::debug;
+use uuid::Uuid;
+
+const NAME: &str = "account-summaries";
+
+/// Number of account updates buffered for slow subscribers, which miss older ones.
+const UPDATES_CAPACITY: usize = 1_024;
+
+#[derive(Debug, Clone)]
+pub struct InMemAccountSummariesProjection {
+    account_summaries: Arc<AccountSummaries>,
+    updates_sdr: broadcast::Sender<Uuid>,
+}
+
+/// Sharded maps, such that reads on request paths do not contend with the workers applying
+/// events, e.g. during replay bursts.
+#[derive(Debug, Default)]
+struct AccountSummaries {
+    by_id: DashMap<Uuid, AccountSummary>,
+    ids_by_external_ref: DashMap<(TenantId, ExternalRef), Uuid>,
+    ids_by_iban: DashMap<Iban, Uuid>,
+    counts_by_tenant: DashMap<TenantId, usize>,
+    counts_by_customer: DashMap<CustomerId, usize>,
+    labels_by_id: DashMap<Uuid, Labels>,
+}
+
+impl InMemAccountSummariesProjection {
+    /// Events which cannot be deserialized or handled are treated according to the
+    /// [ErrorPolicy](crate::infra::projection::ErrorPolicy) for this projection from the given
+    /// [ErrorPolicies], e.g. added to the given [DeadLetterQueue] or retried. Events are applied in
+    /// batches by workers partitioned by account ID. Progress is recorded in the given
+    /// [Projections]. Failed queries are restarted according to the given [RestartConfig]; once
+    /// exhausted, the returned future completes. If a [ProjectionStore] is given, snapshots are
+    /// saved and the projection resumes from these instead of handling all events on startup.
+    pub async fn new<L, D>(
+        config: Config,
+        restart: RestartConfig,
+        error_policies: &ErrorPolicies,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        evt_log: L,
+        dead_letter_queue: D,
+        projections: Projections,
+        projection_store: Option<Arc<dyn ProjectionStore>>,
+    ) -> (Self, impl Future<Output = ()>)
+    where
+        L: EvtLog,
+        D: DeadLetterQueue,
+    {
+        let account_summaries = Arc::new(AccountSummaries::default());
+        let (updates_sdr, _) = broadcast::channel(UPDATES_CAPACITY);
+
+        let workers = {
+            let account_summaries = account_summaries.clone();
+            let updates_sdr = updates_sdr.clone();
+            let projections = projections.clone();
+            let clock = clock.clone();
+            PartitionedWorkers::spawn(
+                config.workers,
+                config.batch_size,
+                move |evts: Vec<(u64, account::Evt)>| {
+                    let now = clock.now();
+                    for (seq_no, evt) in evts {
+                        let account_id = evt.account_id();
+                        let evt_at = evt.at();
+                        account_summaries.apply(evt);
+                        projections.record(NAME, seq_no, evt_at, now);
+                        // Sending only fails without subscribers.
+                        let _ = updates_sdr.send(account_id);
+                    }
+                },
+            )
+        };
+
+        let terminated = ProjectionRunner::new(
+            NAME,
+            vec![account::ACCOUNT_LIFECYCLE_TAG, account::ACCOUNT_TX_TAG],
+            clock,
+            ids,
+            evt_log,
+            dead_letter_queue,
+            projections,
+        )
+        .with_error_policy(error_policies.get(NAME))
+        .with_restart(restart)
+        .with_store(projection_store)
+        .spawn(SummariesHandler {
+            workers,
+            account_summaries: account_summaries.clone(),
+        });
+
+        (
+            Self {
+                account_summaries,
+                updates_sdr,
+            },
+            terminated.map(|_| ()),
+        )
+    }
+}
+
+/// Configuration for the [InMemAccountSummariesProjection].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Number of workers applying events.
+    workers: NonZeroUsize,
+
+    /// Maximum number of events applied at once by a worker.
+    batch_size: NonZeroUsize,
+}
+
+impl AccountSummariesProjection for InMemAccountSummariesProjection {
+    async fn contains(&self, id: Uuid) -> bool {
+        self.account_summaries.by_id.contains_key(&id)
+    }
+
+    async fn summary(&self, id: Uuid) -> Option<AccountSummary> {
+        self.account_summaries
+            .by_id
+            .get(&id)
+            .map(|summary| *summary)
+    }
+
+    async fn account_id_by_external_ref(
+        &self,
+        tenant: TenantId,
+        external_ref: ExternalRef,
+    ) -> Option<Uuid> {
+        self.account_summaries
+            .ids_by_external_ref
+            .get(&(tenant, external_ref))
+            .map(|id| *id)
+    }
+
+    async fn account_id_by_iban(&self, iban: Iban) -> Option<Uuid> {
+        self.account_summaries.ids_by_iban.get(&iban).map(|id| *id)
+    }
+
+    async fn count_by_tenant(&self, tenant: TenantId) -> usize {
+        self.account_summaries
+            .counts_by_tenant
+            .get(&tenant)
+            .map(|count| *count)
+            .unwrap_or_default()
+    }
+
+    async fn count_by_customer(&self, customer: CustomerId) -> usize {
+        self.account_summaries
+            .counts_by_customer
+            .get(&customer)
+            .map(|count| *count)
+            .unwrap_or_default()
+    }
+
+    async fn closed_before(&self, before: OffsetDateTime) -> Vec<(Uuid, AccountSummary)> {
+        self.account_summaries
+            .by_id
+            .iter()
+            .filter(|entry| entry.closed_at.is_some_and(|closed_at| closed_at < before))
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect()
+    }
+
+    async fn sandbox(&self) -> Vec<(Uuid, AccountSummary)> {
+        self.account_summaries
+            .by_id
+            .iter()
+            .filter(|entry| entry.sandbox)
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect()
+    }
+
+    async fn labeled(&self, tenant: TenantId, label: Label) -> Vec<(Uuid, AccountSummary)> {
+        let account_summaries = &self.account_summaries;
+        let mut labeled = account_summaries
+            .labels_by_id
+            .iter()
+            .filter(|entry| entry.value().contains(&label))
+            .filter_map(|entry| {
+                let id = *entry.key();
+                account_summaries
+                    .by_id
+                    .get(&id)
+                    .filter(|summary| summary.tenant == tenant)
+                    .map(|summary| (id, *summary))
+            })
+            .collect::<Vec<_>>();
+        labeled.sort_unstable_by_key(|(id, _)| *id);
+        labeled
+    }
+
+    fn updates(&self) -> Option<broadcast::Receiver<Uuid>> {
+        Some(self.updates_sdr.subscribe())
+    }
+}
+
+/// Dispatches events to the workers, which record progress.
+struct SummariesHandler {
+    workers: PartitionedWorkers<(u64, account::Evt)>,
+    account_summaries: Arc<AccountSummaries>,
+}
+
+impl ProjectionHandler for SummariesHandler {
+    type Evt = account::Evt;
+
+    fn decode(&self, evt: &[u8]) -> Result<Self::Evt, serde_json::Error> {
+        serde_json::from_slice(evt)
+    }
+
+    async fn handle(
+        &mut self,
+        seq_no: u64,
+        evt: Self::Evt,
+    ) -> anyhow::Result<Option<OffsetDateTime>> {
+        self.workers
+            .dispatch(evt.account_id(), (seq_no, evt))
+            .await
+            .context("Cannot dispatch event")?;
+        Ok(None)
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        self.workers.flush().await.context("Cannot flush workers")
+    }
+
+    /// Only called after flushing, i.e. while the workers are idle, hence consistent.
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        let account_summaries = &self.account_summaries;
+        let snapshot = SummariesSnapshot {
+            by_id: account_summaries
+                .by_id
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+            ids_by_external_ref: account_summaries
+                .ids_by_external_ref
+                .iter()
+                .map(|entry| {
+                    let (tenant, external_ref) = entry.key();
+                    (*tenant, external_ref.clone(), *entry.value())
+                })
+                .collect(),
+            ids_by_iban: Some(
+                account_summaries
+                    .ids_by_iban
+                    .iter()
+                    .map(|entry| (*entry.key(), *entry.value()))
+                    .collect(),
+            ),
+            counts_by_tenant: account_summaries
+                .counts_by_tenant
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+            counts_by_customer: account_summaries
+                .counts_by_customer
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+            labels_by_id: account_summaries
+                .labels_by_id
+                .iter()
+                .map(|entry| (*entry.key(), entry.value().clone()))
+                .collect(),
+        };
+        serde_json::to_value(snapshot).ok()
+    }
+
+    /// Called before any event has been handled. Snapshots taken before IBANs were projected are
+    /// not restored, such that all events are handled.
+    fn restore(&mut self, snapshot: serde_json::Value) -> Result<bool, serde_json::Error> {
+        let snapshot = serde_json::from_value::<SummariesSnapshot>(snapshot)?;
+        let Some(ids_by_iban) = snapshot.ids_by_iban else {
+            return Ok(false);
+        };
+        let account_summaries = &self.account_summaries;
+        for (iban, id) in ids_by_iban {
+            account_summaries.ids_by_iban.insert(iban, id);
+        }
+        for (id, summary) in snapshot.by_id {
+            account_summaries.by_id.insert(id, summary);
+        }
+        for (tenant, external_ref, id) in snapshot.ids_by_external_ref {
+            account_summaries
+                .ids_by_external_ref
+                .insert((tenant, external_ref), id);
+        }
+        for (tenant, count) in snapshot.counts_by_tenant {
+            account_summaries.counts_by_tenant.insert(tenant, count);
+        }
+        for (customer, count) in snapshot.counts_by_customer {
+            account_summaries.counts_by_customer.insert(customer, count);
+        }
+        for (id, labels) in snapshot.labels_by_id {
+            account_summaries.labels_by_id.insert(id, labels);
+        }
+        Ok(true)
+    }
+}
+
+/// External references are kept as list, because tuples cannot be JSON keys.
+#[derive(Debug, Serialize, Deserialize)]
+struct SummariesSnapshot {
+    by_id: HashMap<Uuid, AccountSummary>,
+    ids_by_external_ref: Vec<(TenantId, ExternalRef, Uuid)>,
+    /// Missing in snapshots taken before IBANs were projected.
+    #[serde(default)]
+    ids_by_iban: Option<Vec<(Iban, Uuid)>>,
+    counts_by_tenant: HashMap<TenantId, usize>,
+    counts_by_customer: HashMap<CustomerId, usize>,
+    /// Missing in snapshots taken before labels were introduced.
+    #[serde(default)]
+    labels_by_id: HashMap<Uuid, Labels>,
+}
+
+impl AccountSummaries {
+    /// Lifecycle and transaction events are queried separately, hence their relative order is not
+    /// guaranteed: the balance is taken from the transaction events, which carry the old balance,
+    /// rather than accumulated.
+    fn apply(&self, evt: account::Evt) {
+        match evt {
+            account::Evt::Created {
+                id,
+                tenant,
+                customer,
+                iban,
+                opening_balance,
+                external_ref,
+                sandbox,
+                ..
+            } => {
+                debug!(%id, "Inserting summary");
+                {
+                    let mut summary = self.by_id.entry(id).or_insert(AccountSummary {
+                        balance: opening_balance,
+                        ..Default::default()
+                    });
+                    summary.tenant = tenant;
+                    summary.sandbox = sandbox;
+                }
+                if let Some(external_ref) = external_ref {
+                    self.ids_by_external_ref.insert((tenant, external_ref), id);
+                }
+                self.ids_by_iban.insert(iban, id);
+                *self.counts_by_tenant.entry(tenant).or_default() += 1;
+                if let Some(customer) = customer {
+                    *self.counts_by_customer.entry(customer).or_default() += 1;
+                }
+            }
+
+            account::Evt::Deposited {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            } => {
+                debug!(%account_id, "Updating summary");
+                self.by_id.entry(account_id).or_default().balance = old_balance + amount;
+            }
+
+            account::Evt::Withdrawn {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            }
+            | account::Evt::HoldCaptured {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            } => {
+                debug!(%account_id, "Updating summary");
+                self.by_id.entry(account_id).or_default().balance = old_balance - amount;
+            }
+
+            account::Evt::Adjusted {
+                account_id,
+                old_balance,
+                direction,
+                amount,
+                ..
+            } => {
+                debug!(%account_id, "Updating summary");
+                self.by_id.entry(account_id).or_default().balance =
+                    direction.apply(old_balance, amount);
+            }
+
+            // Closed accounts are kept until purged: these can still be read, reopened accounts
+            // keep their summary and the retention finds these via their closing time.
+            account::Evt::Closed { account_id, at, .. } => {
+                debug!(%account_id, "Closing summary");
+                let mut summary = self.by_id.entry(account_id).or_default();
+                summary.status = AccountStatus::Closed;
+                summary.balance = EuroCent::default();
+                summary.closed_at = Some(at);
+            }
+
+            account::Evt::Reopened { account_id, .. } => {
+                debug!(%account_id, "Reopening summary");
+                let mut summary = self.by_id.entry(account_id).or_default();
+                summary.status = AccountStatus::Open;
+                summary.closed_at = None;
+            }
+
+            account::Evt::Purged { account_id, .. } => {
+                debug!(%account_id, "Removing summary");
+                self.by_id.remove(&account_id);
+                self.ids_by_external_ref.retain(|_, id| *id != account_id);
+                self.labels_by_id.remove(&account_id);
+            }
+
+            account::Evt::LabelsSet {
+                account_id, labels, ..
+            } => {
+                debug!(%account_id, "Updating labels");
+                self.labels_by_id.insert(account_id, labels);
+            }
+
+            account::Evt::NotificationPrefsSet { .. }
+            | account::Evt::HoldPlaced { .. }
+            | account::Evt::HoldReleased { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{currency::Currency, iban::BankCode};
+
+    #[test]
+    fn test_apply() {
+        let account_summaries = AccountSummaries::default();
+        let id = Uuid::now_v7();
+        let tenant = TenantId::default();
+        let at = OffsetDateTime::UNIX_EPOCH;
+
+        // Transaction events might be applied before the lifecycle events.
+        account_summaries.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 42.into(),
+            amount: 58.into(),
+            value_date: None,
+            channel: None,
+            at,
+        });
+        account_summaries.apply(account::Evt::Created {
+            id,
+            tenant,
+            customer: None,
+            iban: Iban::for_account(BankCode::try_from(12345678).unwrap(), id),
+            currency: Currency::default(),
+            opening_balance: 42.into(),
+            external_ref: None,
+            product: Default::default(),
+            sandbox: false,
+            at,
+        });
+        let summary = *account_summaries.by_id.get(&id).unwrap();
+        assert_eq!(summary.status, AccountStatus::Open);
+        assert_eq!(summary.balance, 100.into());
+        assert_eq!(*account_summaries.counts_by_tenant.get(&tenant).unwrap(), 1);
+
+        account_summaries.apply(account::Evt::Withdrawn {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 100.into(),
+            amount: 30.into(),
+            value_date: None,
+            channel: None,
+            at,
+        });
+        assert_eq!(account_summaries.by_id.get(&id).unwrap().balance, 70.into());
+
+        account_summaries.apply(account::Evt::Closed {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 70.into(),
+            sweep_to: None,
+            at,
+        });
+        let summary = *account_summaries.by_id.get(&id).unwrap();
+        assert_eq!(summary.status, AccountStatus::Closed);
+        assert_eq!(summary.balance, EuroCent::default());
+        assert_eq!(summary.closed_at, Some(at));
+
+        account_summaries.apply(account::Evt::Purged { account_id: id, at });
+        assert!(!account_summaries.by_id.contains_key(&id));
+    }
+}
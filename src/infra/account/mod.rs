@@ -1,9 +1,34 @@
-pub mod in_mem_ids_projection;
-pub mod lru_cache_factory;
+pub mod data_export;
+pub mod history;
+pub mod in_mem_summaries_projection;
+pub mod portability;
+pub mod sandbox;
 
-use crate::domain::account::Account;
-use eventsourced::EntityRef;
-use std::{error::Error as StdError, future::Future};
+use crate::{
+    domain::{
+        account::{self, Account, ExternalRef, Label, MaxBalances, Snapshot},
+        clock::Clock,
+        customer::CustomerId,
+        euro_cent::EuroCent,
+        iban::Iban,
+        tenant::TenantId,
+    },
+    infra::{
+        cmd_metrics,
+        lru_cache_factory::{
+            self, Evictor, LruCacheEntityFactory, ManagedEntity, Priority, TenantLookup,
+        },
+        namespace::EntityType,
+    },
+};
+use anyhow::{Context, Result};
+use eventsourced::{EntityRef, EventSourced};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::{error::Error as StdError, future::Future, num::NonZeroU64, sync::Arc, time::Instant};
+use time::OffsetDateTime;
+use tokio::sync::{broadcast, watch};
+use tracing::warn;
 use uuid::Uuid;
 
 /// A factory for [Account]s, either creating new ones or returning existing managed ones.
@@ -11,13 +36,369 @@ pub trait AccountFactory: Clone + Send + Sync + 'static {
     type Error: StdError + Send + Sync + 'static;
 
     /// Create a new [Account] or return an existing managed one.
-    fn get(
+    fn get(&self, id: Uuid) -> impl Future<Output = Result<AccountRef, Self::Error>> + Send + '_;
+
+    /// Like [AccountFactory::get], but with the given [Priority], e.g. [Priority::Bulk] for batch
+    /// processing; ignored if not supported.
+    fn get_with_priority(
+        &self,
+        id: Uuid,
+        _priority: Priority,
+    ) -> impl Future<Output = Result<AccountRef, Self::Error>> + Send + '_ {
+        self.get(id)
+    }
+
+    /// The IDs of the currently hot, i.e. pinned, accounts; empty if not tracked.
+    fn hot_accounts(&self) -> Vec<Uuid> {
+        vec![]
+    }
+}
+
+impl AccountFactory for LruCacheEntityFactory<Account> {
+    type Error = lru_cache_factory::Error;
+
+    async fn get(&self, id: Uuid) -> Result<AccountRef, Self::Error> {
+        self.entity(id).await
+    }
+
+    async fn get_with_priority(
         &self,
         id: Uuid,
-    ) -> impl Future<Output = Result<EntityRef<Account>, Self::Error>> + Send + '_;
+        priority: Priority,
+    ) -> Result<AccountRef, Self::Error> {
+        self.entity_with_priority(id, priority).await
+    }
+
+    fn hot_accounts(&self) -> Vec<Uuid> {
+        self.hot_entities()
+    }
+}
+
+/// [TenantLookup] for accounts via their [AccountSummary], such that accounts are spawned with the
+/// settings of the cohort of their tenant. Accounts not yet projected, e.g. just created ones, get
+/// the default settings until respawned.
+#[derive(Debug, Clone)]
+pub struct AccountTenants<P>(pub P);
+
+impl<P> TenantLookup for AccountTenants<P>
+where
+    P: AccountSummariesProjection,
+{
+    async fn tenant(&self, id: Uuid) -> Option<TenantId> {
+        self.0.summary(id).await.map(|summary| summary.tenant)
+    }
 }
 
-pub trait AccountIdsProjection: Clone + Send + Sync + 'static {
+impl ManagedEntity for Account {
+    const ENTITY_TYPE: EntityType = EntityType::Account;
+
+    type Settings = MaxBalances;
+
+    type Observer = (watch::Receiver<Snapshot>, MaxBalances);
+
+    type Ref = AccountRef;
+
+    fn create(
+        max_balances: &MaxBalances,
+        clock: Arc<dyn Clock>,
+        snapshot_after: Option<NonZeroU64>,
+    ) -> Self {
+        Account::default()
+            .with_snapshot_after(snapshot_after)
+            .with_clock(clock)
+            .with_max_balances(*max_balances)
+    }
+
+    fn observe(&self) -> Self::Observer {
+        (self.subscribe(), self.max_balances())
+    }
+
+    fn into_ref(
+        entity: EntityRef<Self>,
+        (snapshots, max_balances): Self::Observer,
+        evictor: Evictor,
+    ) -> AccountRef {
+        AccountRef {
+            entity,
+            snapshots,
+            max_balances,
+            evictor,
+        }
+    }
+}
+
+/// A handle to a managed [Account] entity, which also gives access to its latest [Snapshot].
+///
+/// Events are appended with the expected sequence number, hence if another process has spawned the
+/// same account, conflicting appends fail and terminate the entity instead of forking its history.
+/// A terminated entity is reported for eviction, such that it gets respawned from the event log.
+#[derive(Debug, Clone)]
+pub struct AccountRef {
+    entity: EntityRef<Account>,
+    snapshots: watch::Receiver<Snapshot>,
+    max_balances: MaxBalances,
+    evictor: Evictor,
+}
+
+impl AccountRef {
+    /// Handle the given command and, if successful, return the resulting [Snapshot]. As commands
+    /// for the same account might be handled concurrently, the snapshot might already reflect
+    /// subsequent commands, hence it is only a hint.
+    pub async fn handle_cmd(&self, cmd: account::Cmd) -> Result<Result<Snapshot, account::Error>> {
+        let cmd_name = cmd.name();
+        let start = Instant::now();
+        let result = self.entity.handle_cmd(cmd).await;
+        cmd_metrics::record(
+            Account::ENTITY_TYPE,
+            self.evictor.id(),
+            cmd_name,
+            start.elapsed(),
+        );
+
+        let result = result.inspect_err(|_| {
+            counter!("account_entity_evictions", 1);
+            self.evictor.evict();
+        });
+        let result = result.context("Account entity terminated, e.g. by a conflicting append")?;
+        match &result {
+            Ok(_) => self.evictor.mutated(),
+
+            // Compliance alert: incoming funds rejected because of the maximum balance.
+            Err(account::Error::MaxBalanceExceeded {
+                balance,
+                amount,
+                max_balance,
+            }) => {
+                warn!(
+                    id = %self.evictor.id(),
+                    cmd = cmd_name,
+                    %balance,
+                    %amount,
+                    %max_balance,
+                    "Compliance alert: maximum balance exceeded"
+                );
+                counter!("compliance_alerts_total", 1, "kind" => "max-balance-exceeded");
+            }
+
+            Err(_) => {}
+        }
+        Ok(result.map(|_| self.snapshot()))
+    }
+
+    /// Handle the given command against the latest [Snapshot] without persisting anything, i.e. a
+    /// dry run, and return the would-be [Snapshot].
+    pub fn dry_run(&self, cmd: account::Cmd) -> Result<Snapshot, account::Error> {
+        let mut account = Account::default().with_max_balances(self.max_balances);
+        account.set_state(self.snapshot());
+        account.dry_run(cmd).map(|(_, snapshot)| snapshot)
+    }
+
+    /// The latest [Snapshot].
+    pub fn snapshot(&self) -> Snapshot {
+        self.snapshots.borrow().clone()
+    }
+}
+
+/// A projection of all accounts to their [AccountSummary].
+pub trait AccountSummariesProjection: Clone + Send + Sync + 'static {
     /// Is the given ID in the set of all account IDs?
     fn contains(&self, id: Uuid) -> impl Future<Output = bool> + Send + '_;
+
+    /// The [AccountSummary] for the given ID, if any.
+    fn summary(&self, id: Uuid) -> impl Future<Output = Option<AccountSummary>> + Send + '_;
+
+    /// The ID of the account with the given [ExternalRef] for the given tenant, if any.
+    fn account_id_by_external_ref(
+        &self,
+        tenant: TenantId,
+        external_ref: ExternalRef,
+    ) -> impl Future<Output = Option<Uuid>> + Send + '_;
+
+    /// The ID of the account with the given [Iban], if any. IBANs of purged accounts are not
+    /// released, such that these are never reused.
+    fn account_id_by_iban(&self, iban: Iban) -> impl Future<Output = Option<Uuid>> + Send + '_;
+
+    /// The number of accounts of the given tenant.
+    fn count_by_tenant(&self, tenant: TenantId) -> impl Future<Output = usize> + Send + '_;
+
+    /// The number of accounts of the given customer.
+    fn count_by_customer(&self, customer: CustomerId) -> impl Future<Output = usize> + Send + '_;
+
+    /// The IDs and [AccountSummary]s of the accounts closed before the given time.
+    fn closed_before(
+        &self,
+        before: OffsetDateTime,
+    ) -> impl Future<Output = Vec<(Uuid, AccountSummary)>> + Send + '_;
+
+    /// The IDs and [AccountSummary]s of all sandbox accounts.
+    fn sandbox(&self) -> impl Future<Output = Vec<(Uuid, AccountSummary)>> + Send + '_;
+
+    /// The IDs and [AccountSummary]s of the accounts of the given tenant with the given [Label],
+    /// ordered by ID.
+    fn labeled(
+        &self,
+        tenant: TenantId,
+        label: Label,
+    ) -> impl Future<Output = Vec<(Uuid, AccountSummary)>> + Send + '_;
+
+    /// Receiver for the IDs of the accounts with new events observed by this projection, e.g. for
+    /// invalidating caches; `None` if not supported.
+    fn updates(&self) -> Option<broadcast::Receiver<Uuid>> {
+        None
+    }
+}
+
+/// [AccountSummariesProjection] either backed by a projection or, for command-side-only
+/// deployments without projections, by the account entities. The latter only supports existence
+/// checks and summaries, whereas lookups by external reference or label, counts, closed and
+/// sandbox accounts find nothing, i.e. the pre-checks based on them, e.g. quotas, are skipped.
+#[derive(Debug, Clone)]
+pub enum AccountSummaries<P, F> {
+    Projection(P),
+    Entities(F),
+}
+
+impl<P, F> AccountSummaries<P, F>
+where
+    F: AccountFactory,
+{
+    async fn entity_summary(factory: &F, id: Uuid) -> Option<AccountSummary> {
+        let account = factory
+            .get(id)
+            .await
+            .inspect_err(|error| warn!(%id, %error, "Cannot get account entity for summary"))
+            .ok()?;
+        let state = account.snapshot().state;
+        let sandbox = state.is_sandbox();
+        match state {
+            account::State::NonExistent => None,
+            account::State::Created {
+                tenant, balance, ..
+            } => Some(AccountSummary {
+                tenant,
+                status: AccountStatus::Open,
+                balance,
+                closed_at: None,
+                sandbox,
+            }),
+            account::State::Closed { purged: true, .. } => None,
+            account::State::Closed {
+                tenant, closed_at, ..
+            } => Some(AccountSummary {
+                tenant,
+                status: AccountStatus::Closed,
+                balance: EuroCent::default(),
+                closed_at: Some(closed_at),
+                sandbox,
+            }),
+        }
+    }
+}
+
+impl<P, F> AccountSummariesProjection for AccountSummaries<P, F>
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    async fn contains(&self, id: Uuid) -> bool {
+        match self {
+            AccountSummaries::Projection(projection) => projection.contains(id).await,
+            AccountSummaries::Entities(factory) => {
+                Self::entity_summary(factory, id).await.is_some()
+            }
+        }
+    }
+
+    async fn summary(&self, id: Uuid) -> Option<AccountSummary> {
+        match self {
+            AccountSummaries::Projection(projection) => projection.summary(id).await,
+            AccountSummaries::Entities(factory) => Self::entity_summary(factory, id).await,
+        }
+    }
+
+    async fn account_id_by_external_ref(
+        &self,
+        tenant: TenantId,
+        external_ref: ExternalRef,
+    ) -> Option<Uuid> {
+        match self {
+            AccountSummaries::Projection(projection) => {
+                projection
+                    .account_id_by_external_ref(tenant, external_ref)
+                    .await
+            }
+            AccountSummaries::Entities(_) => None,
+        }
+    }
+
+    async fn account_id_by_iban(&self, iban: Iban) -> Option<Uuid> {
+        match self {
+            AccountSummaries::Projection(projection) => projection.account_id_by_iban(iban).await,
+            AccountSummaries::Entities(_) => None,
+        }
+    }
+
+    async fn count_by_tenant(&self, tenant: TenantId) -> usize {
+        match self {
+            AccountSummaries::Projection(projection) => projection.count_by_tenant(tenant).await,
+            AccountSummaries::Entities(_) => 0,
+        }
+    }
+
+    async fn count_by_customer(&self, customer: CustomerId) -> usize {
+        match self {
+            AccountSummaries::Projection(projection) => {
+                projection.count_by_customer(customer).await
+            }
+            AccountSummaries::Entities(_) => 0,
+        }
+    }
+
+    async fn closed_before(&self, before: OffsetDateTime) -> Vec<(Uuid, AccountSummary)> {
+        match self {
+            AccountSummaries::Projection(projection) => projection.closed_before(before).await,
+            AccountSummaries::Entities(_) => vec![],
+        }
+    }
+
+    async fn sandbox(&self) -> Vec<(Uuid, AccountSummary)> {
+        match self {
+            AccountSummaries::Projection(projection) => projection.sandbox().await,
+            AccountSummaries::Entities(_) => vec![],
+        }
+    }
+
+    async fn labeled(&self, tenant: TenantId, label: Label) -> Vec<(Uuid, AccountSummary)> {
+        match self {
+            AccountSummaries::Projection(projection) => projection.labeled(tenant, label).await,
+            AccountSummaries::Entities(_) => vec![],
+        }
+    }
+
+    fn updates(&self) -> Option<broadcast::Receiver<Uuid>> {
+        match self {
+            AccountSummaries::Projection(projection) => projection.updates(),
+            AccountSummaries::Entities(_) => None,
+        }
+    }
+}
+
+/// Summary of an account. Might lag behind the actual account, hence only useful for pre-checks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountSummary {
+    pub tenant: TenantId,
+    pub status: AccountStatus,
+    pub balance: EuroCent,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub closed_at: Option<OffsetDateTime>,
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+/// Status of an account.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountStatus {
+    #[default]
+    Open,
+    Closed,
 }
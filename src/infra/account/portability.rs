@@ -0,0 +1,324 @@
+use crate::{
+    domain::{
+        account::{self, Account, Snapshot},
+        clock::Clock,
+        tenant::TenantId,
+    },
+    infra::{
+        backfill::evts_by_id,
+        error_code::ErrorCode,
+        namespace::{EntityType, Namespace},
+    },
+};
+use anyhow::Context;
+use eventsourced::{convert, EventSourced, EvtLog, SnapshotStore};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, sync::Arc};
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Version of the [AccountArchive] format.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Root namespace for the IDs of imported accounts, derived from their original IDs.
+const IMPORT_ROOT: Uuid = Uuid::from_u128(0x3b7e_91d4_0c5a_4f28_a6d1_52e8_c49f_07b3);
+
+/// Exports single accounts as portable [AccountArchive]s and imports these, e.g. into another
+/// deployment for tenant migrations or support escalations.
+pub trait AccountPorter: Clone + Send + Sync + 'static {
+    /// The [AccountArchive] of the account with the given ID, if existing.
+    fn export(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = anyhow::Result<Option<AccountArchive>>> + Send + '_;
+
+    /// Import the given [AccountArchive] under the given ID or, if not given, under an ID derived
+    /// from the original one, such that importing the same archive again is detected.
+    fn import(
+        &self,
+        archive: AccountArchive,
+        id: Option<Uuid>,
+    ) -> impl Future<Output = Result<Imported, ImportError>> + Send + '_;
+}
+
+/// The full stream of an account, i.e. all its events along with its latest snapshot, if any, and
+/// metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountArchive {
+    pub version: u32,
+    pub account_id: Uuid,
+    pub tenant: TenantId,
+    /// The head, i.e. last, sequence number.
+    pub seq_no: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    pub evts: Vec<account::Evt>,
+    pub snapshot: Option<Snapshot>,
+}
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Imported {
+    pub account_id: Uuid,
+    pub original_account_id: Uuid,
+    pub evts: usize,
+    /// Whether the archive had already been imported before, in which case nothing was written.
+    pub duplicate: bool,
+}
+
+/// Errors of importing an [AccountArchive].
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("unsupported archive version {0}, expected {ARCHIVE_VERSION}")]
+    UnsupportedVersion(u32),
+
+    #[error("invalid archive: {0}")]
+    Invalid(&'static str),
+
+    #[error("account {0} already exists with different events")]
+    Conflict(Uuid),
+
+    #[error(transparent)]
+    Failed(#[from] anyhow::Error),
+}
+
+impl From<&ImportError> for ErrorCode {
+    fn from(error: &ImportError) -> Self {
+        match error {
+            ImportError::UnsupportedVersion(_) | ImportError::Invalid(_) => {
+                ErrorCode::InvalidRequest
+            }
+            ImportError::Conflict(_) => ErrorCode::AlreadyCreated,
+            ImportError::Failed(error) => ErrorCode::of(error),
+        }
+    }
+}
+
+/// [AccountPorter] reading from and writing to the event log directly. Imported events are
+/// persisted with their tags, such that projections pick them up; snapshots are only used for
+/// verifying the events, as entities rebuild their state from these.
+#[derive(Debug, Clone)]
+pub struct EvtLogAccountPorter<L, S> {
+    evt_log: L,
+    snapshot_store: S,
+    namespace: Namespace,
+    clock: Arc<dyn Clock>,
+}
+
+impl<L, S> EvtLogAccountPorter<L, S>
+where
+    L: EvtLog,
+    S: SnapshotStore,
+{
+    #[allow(missing_docs)]
+    pub fn new(evt_log: L, snapshot_store: S, namespace: Namespace, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            evt_log,
+            snapshot_store,
+            namespace,
+            clock,
+        }
+    }
+}
+
+impl<L, S> AccountPorter for EvtLogAccountPorter<L, S>
+where
+    L: EvtLog,
+    S: SnapshotStore,
+{
+    async fn export(&self, id: Uuid) -> anyhow::Result<Option<AccountArchive>> {
+        let stream_id = self.namespace.id(EntityType::Account, id);
+
+        // The snapshot is loaded before the events, such that it is not ahead of these.
+        let snapshot = self
+            .snapshot_store
+            .load::<Snapshot, _, _>(stream_id, convert::serde_json::from_bytes)
+            .await
+            .context("Cannot load snapshot")?
+            .map(|snapshot| snapshot.state);
+        let evts = evts_by_id(&self.evt_log, stream_id).await?;
+        let Some(account::Evt::Created { tenant, .. }) = evts.first() else {
+            return Ok(None);
+        };
+
+        debug!(%id, evts = evts.len(), "Exporting account");
+        Ok(Some(AccountArchive {
+            version: ARCHIVE_VERSION,
+            account_id: id,
+            tenant: *tenant,
+            seq_no: evts.len() as u64,
+            exported_at: self.clock.now(),
+            evts,
+            snapshot,
+        }))
+    }
+
+    async fn import(
+        &self,
+        archive: AccountArchive,
+        id: Option<Uuid>,
+    ) -> Result<Imported, ImportError> {
+        verify(&archive)?;
+
+        let original_account_id = archive.account_id;
+        let account_id =
+            id.unwrap_or_else(|| Uuid::new_v5(&IMPORT_ROOT, original_account_id.as_bytes()));
+        let evts = archive
+            .evts
+            .into_iter()
+            .map(|evt| evt.with_account_id(account_id))
+            .collect::<Vec<_>>();
+
+        // Duplicate detection: the same events might have been imported before, possibly only
+        // partially, e.g. if interrupted.
+        let stream_id = self.namespace.id(EntityType::Account, account_id);
+        let written = evts_by_id(&self.evt_log, stream_id).await?;
+        if written.len() > evts.len() || written[..] != evts[..written.len()] {
+            return Err(ImportError::Conflict(account_id));
+        }
+        let imported = Imported {
+            account_id,
+            original_account_id,
+            evts: evts.len() - written.len(),
+            duplicate: written.len() == evts.len(),
+        };
+        if imported.duplicate {
+            debug!(%account_id, %original_account_id, "Account already imported");
+            return Ok(imported);
+        }
+
+        let mut evt_log = self.evt_log.clone();
+        for evt in &evts[written.len()..] {
+            evt_log
+                .persist(
+                    stream_id,
+                    evt,
+                    Some(evt.tag().to_string()),
+                    &convert::serde_json::to_bytes::<account::Evt>,
+                )
+                .await
+                .context(format!("Cannot persist event for account {account_id}"))?;
+        }
+
+        info!(%account_id, %original_account_id, evts = imported.evts, "Imported account");
+        Ok(imported)
+    }
+}
+
+/// Verify the given [AccountArchive]: all events must belong to the account, starting with its
+/// creation, and replaying them must yield the snapshot, if any.
+fn verify(archive: &AccountArchive) -> Result<(), ImportError> {
+    if archive.version != ARCHIVE_VERSION {
+        return Err(ImportError::UnsupportedVersion(archive.version));
+    }
+    if !matches!(archive.evts.first(), Some(account::Evt::Created { tenant, .. }) if *tenant == archive.tenant)
+    {
+        return Err(ImportError::Invalid(
+            "events must start with the creation for the tenant",
+        ));
+    }
+    if archive.evts.len() as u64 != archive.seq_no {
+        return Err(ImportError::Invalid(
+            "number of events must match sequence number",
+        ));
+    }
+    if archive
+        .evts
+        .iter()
+        .any(|evt| evt.account_id() != archive.account_id)
+    {
+        return Err(ImportError::Invalid("events must belong to the account"));
+    }
+
+    if let Some(snapshot) = &archive.snapshot {
+        let Some(evts) = archive.evts.get(..snapshot.seq_no as usize) else {
+            return Err(ImportError::Invalid(
+                "snapshot must not be ahead of the events",
+            ));
+        };
+        let mut account = Account::default();
+        for evt in evts {
+            account.handle_evt(evt.clone());
+        }
+        if account.snapshot() != *snapshot {
+            return Err(ImportError::Invalid("snapshot must match the events"));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        currency::Currency,
+        iban::{BankCode, Iban},
+    };
+
+    #[test]
+    fn test_verify() {
+        let account_id = Uuid::now_v7();
+        let tenant = TenantId::default();
+        let at = OffsetDateTime::UNIX_EPOCH;
+        let evts = vec![
+            account::Evt::Created {
+                id: account_id,
+                tenant,
+                customer: None,
+                iban: Iban::for_account(BankCode::try_from(12345678).unwrap(), account_id),
+                currency: Currency::default(),
+                opening_balance: 42.into(),
+                external_ref: None,
+                product: Default::default(),
+                sandbox: false,
+                at,
+            },
+            account::Evt::Deposited {
+                id: Uuid::now_v7(),
+                account_id,
+                old_balance: 42.into(),
+                amount: 58.into(),
+                value_date: None,
+                channel: None,
+                at,
+            },
+        ];
+        let mut account = Account::default();
+        account.handle_evt(evts[0].clone());
+        let archive = AccountArchive {
+            version: ARCHIVE_VERSION,
+            account_id,
+            tenant,
+            seq_no: 2,
+            exported_at: at,
+            evts,
+            snapshot: Some(account.snapshot()),
+        };
+        assert!(verify(&archive).is_ok());
+
+        let mut invalid = archive.clone();
+        invalid.version = 0;
+        assert!(matches!(
+            verify(&invalid),
+            Err(ImportError::UnsupportedVersion(0))
+        ));
+
+        let mut invalid = archive.clone();
+        invalid.evts.remove(0);
+        invalid.seq_no = 1;
+        assert!(matches!(verify(&invalid), Err(ImportError::Invalid(_))));
+
+        let mut invalid = archive.clone();
+        invalid.evts[1] = invalid.evts[1].clone().with_account_id(Uuid::now_v7());
+        assert!(matches!(verify(&invalid), Err(ImportError::Invalid(_))));
+
+        let mut invalid = archive;
+        if let Some(snapshot) = &mut invalid.snapshot {
+            snapshot.seq_no = 2;
+        }
+        assert!(matches!(verify(&invalid), Err(ImportError::Invalid(_))));
+    }
+}
@@ -0,0 +1,80 @@
+use super::{AccountFactory, AccountStatus, AccountSummariesProjection};
+use crate::domain::{account, id::IdGenerator};
+use anyhow::{Context, Result};
+use metrics::counter;
+use serde::Serialize;
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// The outcome of purging all sandbox accounts.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SandboxPurge {
+    pub purged: Vec<Uuid>,
+    /// Accounts which could not be purged, e.g. because of active holds; to be retried.
+    pub failed: Vec<Uuid>,
+}
+
+/// Purge all sandbox accounts in bulk, e.g. after partners have finished testing: open accounts
+/// are closed first, discarding their balance, then all are purged right away. Their events are
+/// not archived, because they are test data only.
+pub async fn purge_sandbox<P, F>(
+    account_summaries: &P,
+    account_factory: &F,
+    ids: &dyn IdGenerator,
+) -> SandboxPurge
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    let mut outcome = SandboxPurge::default();
+    for (id, summary) in account_summaries.sandbox().await {
+        match purge(account_factory, ids, id, summary.status).await {
+            Ok(()) => {
+                info!(%id, "Purged sandbox account");
+                counter!("sandbox_purges_total", 1, "status" => "purged");
+                outcome.purged.push(id);
+            }
+
+            Err(error) => {
+                error!(%id, error = format!("{error:#}"), "Cannot purge sandbox account");
+                counter!("sandbox_purges_total", 1, "status" => "failed");
+                outcome.failed.push(id);
+            }
+        }
+    }
+    outcome
+}
+
+async fn purge<F>(
+    account_factory: &F,
+    ids: &dyn IdGenerator,
+    id: Uuid,
+    status: AccountStatus,
+) -> Result<()>
+where
+    F: AccountFactory,
+{
+    let account = account_factory
+        .get(id)
+        .await
+        .context("Cannot get Account entity")?;
+
+    if status == AccountStatus::Open {
+        let cmd = account::Cmd::Close {
+            id: ids.next_id(),
+            sweep_to: None,
+        };
+        account
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Close command")?
+            .context("Cannot close account")?;
+    }
+
+    account
+        .handle_cmd(account::Cmd::Purge { retention_days: 0 })
+        .await
+        .context("Cannot handle Purge command")?
+        .context("Cannot purge account")?;
+    Ok(())
+}
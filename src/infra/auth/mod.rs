@@ -0,0 +1,320 @@
+use crate::domain::{clock::Clock, id::IdGenerator, redaction::Secret};
+use argon2::{password_hash::SaltString, Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, num::NonZeroU64, sync::Arc};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tokio::task;
+use uuid::Uuid;
+
+/// Salt for the password hash verified for unknown users.
+const DUMMY_SALT: &str = "ZHVtbXlzYWx0ZHVtbXk";
+
+/// Issues short-lived JWT access tokens and rotating refresh tokens for first-party clients
+/// authenticating with username and password.
+#[derive(Debug, Clone)]
+pub struct Auth {
+    config: Arc<Config>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    refresh_tokens: Arc<RwLock<HashMap<Uuid, RefreshToken>>>,
+    /// IDs of revoked access tokens with their expiry.
+    revoked: Arc<RwLock<HashMap<Uuid, i64>>>,
+    /// Verified for unknown users, such that these cannot be told apart by response times.
+    dummy_password_hash: Arc<str>,
+}
+
+impl Auth {
+    #[allow(missing_docs)]
+    pub fn new(config: Config, clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        let salt = SaltString::from_b64(DUMMY_SALT).expect("valid salt");
+        let dummy_password_hash = Argon2::default()
+            .hash_password(b"", &salt)
+            .expect("dummy password can be hashed")
+            .to_string();
+
+        Self {
+            config: Arc::new(config),
+            clock,
+            ids,
+            refresh_tokens: Default::default(),
+            revoked: Default::default(),
+            dummy_password_hash: dummy_password_hash.into(),
+        }
+    }
+
+    /// Verify the given credentials against the argon2 password hash of the user and, if valid,
+    /// issue [Tokens].
+    pub async fn login(&self, username: String, password: String) -> Result<Tokens, Error> {
+        let user = self
+            .config
+            .users
+            .iter()
+            .find(|user| user.username == username);
+        let password_hash = user
+            .map(|user| user.password_hash.clone())
+            .unwrap_or_else(|| self.dummy_password_hash.to_string());
+
+        // Hashing is expensive by design, hence do not block the runtime.
+        let valid = task::spawn_blocking(move || {
+            PasswordHash::new(&password_hash)
+                .map(|password_hash| {
+                    Argon2::default()
+                        .verify_password(password.as_bytes(), &password_hash)
+                        .is_ok()
+                })
+                .unwrap_or_default()
+        })
+        .await
+        .unwrap_or_default();
+        match user {
+            Some(user) if valid => self.issue(user),
+            _ => Err(Error::InvalidCredentials),
+        }
+    }
+
+    /// Exchange the given refresh token, which gets revoked, for new [Tokens].
+    pub fn refresh(&self, refresh_token: Uuid) -> Result<Tokens, Error> {
+        let refresh_token = self
+            .refresh_tokens
+            .write()
+            .remove(&refresh_token)
+            .filter(|refresh_token| refresh_token.expires_at > self.clock.now())
+            .ok_or(Error::InvalidRefreshToken)?;
+        let user = self
+            .config
+            .users
+            .iter()
+            .find(|user| user.username == refresh_token.username)
+            .ok_or(Error::InvalidRefreshToken)?;
+        self.issue(user)
+    }
+
+    /// Revoke the given refresh token and the access token issued together with it. Returns
+    /// `false`, if there is no such refresh token.
+    pub fn logout(&self, refresh_token: Uuid) -> bool {
+        let Some(refresh_token) = self.refresh_tokens.write().remove(&refresh_token) else {
+            return false;
+        };
+
+        let now = self.clock.now().unix_timestamp();
+        let mut revoked = self.revoked.write();
+        revoked.retain(|_, exp| *exp > now);
+        revoked.insert(refresh_token.jti, refresh_token.exp);
+        true
+    }
+
+    /// Validate the given access token, i.e. its signature, expiry and that it has not been
+    /// revoked, and return its [Claims].
+    pub fn validate(&self, access_token: &str) -> Result<Claims, Error> {
+        // The expiry is validated against the clock below.
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.validate_exp = false;
+        let claims = jsonwebtoken::decode::<Claims>(
+            access_token,
+            &DecodingKey::from_secret(self.config.jwt_secret.expose().as_bytes()),
+            &validation,
+        )
+        .map_err(Error::InvalidAccessToken)?
+        .claims;
+
+        if claims.exp <= self.clock.now().unix_timestamp()
+            || self.revoked.read().contains_key(&claims.jti)
+        {
+            return Err(Error::ExpiredAccessToken);
+        }
+
+        Ok(claims)
+    }
+
+    fn issue(&self, user: &UserConfig) -> Result<Tokens, Error> {
+        let now = self.clock.now();
+        let access_token_ttl = Duration::seconds(self.config.access_token_ttl_secs.get() as i64);
+        let refresh_token_ttl = Duration::seconds(self.config.refresh_token_ttl_secs.get() as i64);
+
+        let claims = Claims {
+            sub: user.username.clone(),
+            iat: now.unix_timestamp(),
+            exp: (now + access_token_ttl).unix_timestamp(),
+            jti: self.ids.next_id(),
+            scope: user.scopes.join(" "),
+        };
+        let access_token = jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.config.jwt_secret.expose().as_bytes()),
+        )
+        .map_err(Error::Jwt)?;
+
+        let refresh_token = Uuid::new_v4();
+        let mut refresh_tokens = self.refresh_tokens.write();
+        refresh_tokens.retain(|_, refresh_token| refresh_token.expires_at > now);
+        refresh_tokens.insert(
+            refresh_token,
+            RefreshToken {
+                username: user.username.clone(),
+                expires_at: now + refresh_token_ttl,
+                jti: claims.jti,
+                exp: claims.exp,
+            },
+        );
+
+        Ok(Tokens {
+            access_token,
+            token_type: "Bearer",
+            expires_in: access_token_ttl.whole_seconds(),
+            refresh_token,
+        })
+    }
+}
+
+/// Access and refresh tokens.
+#[derive(Debug, Clone, Serialize)]
+pub struct Tokens {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+    pub refresh_token: Uuid,
+}
+
+/// Claims of JWT access tokens, added to the request extensions for authenticated requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub iat: i64,
+    pub exp: i64,
+    pub jti: Uuid,
+    /// Space-separated scopes.
+    pub scope: String,
+}
+
+impl Claims {
+    /// The scopes of the token.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.split_whitespace()
+    }
+}
+
+/// A refresh token with the ID and expiry of the access token issued together with it.
+#[derive(Debug, Clone)]
+struct RefreshToken {
+    username: String,
+    expires_at: OffsetDateTime,
+    jti: Uuid,
+    exp: i64,
+}
+
+/// Auth configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    jwt_secret: Secret,
+    access_token_ttl_secs: NonZeroU64,
+    refresh_token_ttl_secs: NonZeroU64,
+    users: Vec<UserConfig>,
+}
+
+/// Credentials of a user; the password hash is an argon2 PHC string. The scopes are granted to
+/// the access tokens of the user.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UserConfig {
+    username: String,
+    password_hash: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+
+    #[error("Invalid or expired refresh token")]
+    InvalidRefreshToken,
+
+    #[error("Invalid access token")]
+    InvalidAccessToken(#[source] jsonwebtoken::errors::Error),
+
+    #[error("Expired or revoked access token")]
+    ExpiredAccessToken,
+
+    #[error("Cannot encode JWT")]
+    Jwt(#[source] jsonwebtoken::errors::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{clock::ManualClock, id::UuidV7Generator};
+
+    #[tokio::test]
+    async fn test_login_refresh_logout() {
+        let password_hash = Argon2::default()
+            .hash_password(
+                b"secret",
+                &SaltString::from_b64("c2FsdHNhbHRzYWx0").unwrap(),
+            )
+            .unwrap()
+            .to_string();
+        let config = Config {
+            jwt_secret: Secret::from("jwt-secret".to_string()),
+            access_token_ttl_secs: NonZeroU64::new(60).unwrap(),
+            refresh_token_ttl_secs: NonZeroU64::new(3_600).unwrap(),
+            users: vec![UserConfig {
+                username: "alice".to_string(),
+                password_hash,
+                scopes: vec!["accounts:read".to_string()],
+            }],
+        };
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let auth = Auth::new(
+            config,
+            Arc::new(clock.clone()),
+            Arc::new(UuidV7Generator::new(Arc::new(clock.clone()))),
+        );
+
+        let result = auth.login("alice".to_string(), "wrong".to_string()).await;
+        assert!(matches!(result, Err(Error::InvalidCredentials)));
+        let result = auth.login("bob".to_string(), "secret".to_string()).await;
+        assert!(matches!(result, Err(Error::InvalidCredentials)));
+
+        let tokens = auth.login("alice".to_string(), "secret".to_string()).await;
+        assert!(tokens.is_ok());
+        let tokens = tokens.unwrap();
+        assert_eq!(tokens.expires_in, 60);
+
+        // Access tokens carry the scopes of the user.
+        let claims = auth.validate(&tokens.access_token);
+        assert!(claims.is_ok_and(|claims| claims.scopes().eq(["accounts:read"])));
+        let result = auth.validate("invalid");
+        assert!(matches!(result, Err(Error::InvalidAccessToken(_))));
+
+        // Refresh tokens are rotated.
+        let refreshed = auth.refresh(tokens.refresh_token);
+        assert!(refreshed.is_ok());
+        let refreshed = refreshed.unwrap();
+        let result = auth.refresh(tokens.refresh_token);
+        assert!(matches!(result, Err(Error::InvalidRefreshToken)));
+
+        // Refresh tokens expire.
+        clock.advance(Duration::hours(2));
+        let result = auth.refresh(refreshed.refresh_token);
+        assert!(matches!(result, Err(Error::InvalidRefreshToken)));
+
+        // Access tokens expire.
+        let result = auth.validate(&refreshed.access_token);
+        assert!(matches!(result, Err(Error::ExpiredAccessToken)));
+
+        // Logout revokes the access token, too.
+        let tokens = auth.login("alice".to_string(), "secret".to_string()).await;
+        let tokens = tokens.unwrap();
+        assert!(auth.validate(&tokens.access_token).is_ok());
+        assert!(auth.logout(tokens.refresh_token));
+        assert!(!auth.logout(tokens.refresh_token));
+        let result = auth.validate(&tokens.access_token);
+        assert!(matches!(result, Err(Error::ExpiredAccessToken)));
+    }
+}
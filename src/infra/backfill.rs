@@ -0,0 +1,153 @@
+use crate::{
+    domain::account::{self, ACCOUNT_LIFECYCLE_TAG},
+    infra::namespace::{EntityType, Namespace},
+};
+use anyhow::{anyhow, Context, Result};
+use bytes::Bytes;
+use eventsourced::{convert, EvtLog, SeqNo};
+use futures::{StreamExt, TryStreamExt};
+use serde::Deserialize;
+use std::{convert::Infallible, num::NonZeroU64, time::Duration};
+use tokio::time::timeout;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Configuration for backfilling.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Accounts are discovered via their lifecycle events; discovery ends once there has been no
+    /// further lifecycle event for this long.
+    idle_timeout_secs: NonZeroU64,
+}
+
+/// Outcome of a backfill.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackfillReport {
+    pub accounts: usize,
+    pub evts: usize,
+    /// Events already written by a previous, interrupted backfill.
+    pub skipped: usize,
+}
+
+/// Copy the events of all accounts from the given source to the given target [EvtLog],
+/// transforming them on the way: events are upcast to the current schema by deserializing them
+/// with the defaults for new fields and they are tagged according to [account::Evt::tag]. The
+/// written events are read back and verified. Backfills can be resumed by running them again, as
+/// events already written to the target are skipped. With `dry_run` nothing is written.
+pub async fn backfill<L, T>(
+    config: Config,
+    namespace: Namespace,
+    source: L,
+    mut target: T,
+    dry_run: bool,
+) -> Result<BackfillReport>
+where
+    L: EvtLog,
+    T: EvtLog,
+{
+    let mut report = BackfillReport::default();
+
+    let idle_timeout = Duration::from_secs(config.idle_timeout_secs.get());
+    for id in account_ids(&source, idle_timeout).await? {
+        report.accounts += 1;
+        let stream_id = namespace.id(EntityType::Account, id);
+
+        let evts = evts_by_id(&source, stream_id)
+            .await
+            .context(format!("Cannot get events of account {id} from source"))?;
+        let written = evts_by_id(&target, stream_id)
+            .await
+            .context(format!("Cannot get events of account {id} from target"))?;
+        if written.len() > evts.len() || written[..] != evts[..written.len()] {
+            return Err(anyhow!("Target has diverging events for account {id}"));
+        }
+        if !written.is_empty() {
+            debug!(%id, written = written.len(), "Resuming backfill of account");
+        }
+
+        report.evts += evts.len() - written.len();
+        report.skipped += written.len();
+        if dry_run {
+            continue;
+        }
+
+        for evt in &evts[written.len()..] {
+            target
+                .persist(
+                    stream_id,
+                    evt,
+                    Some(evt.tag().to_string()),
+                    &convert::serde_json::to_bytes::<account::Evt>,
+                )
+                .await
+                .context(format!("Cannot persist event for account {id}"))?;
+        }
+
+        // Verify by reading back the events from the target.
+        let backfilled = evts_by_id(&target, stream_id)
+            .await
+            .context(format!("Cannot verify events of account {id}"))?;
+        if backfilled != evts {
+            return Err(anyhow!("Verification failed for account {id}"));
+        }
+    }
+
+    info!(?report, dry_run, "Backfilled account events");
+    Ok(report)
+}
+
+/// The IDs of all accounts, in the order of their creation. Accounts are discovered via their
+/// lifecycle events; discovery ends once there has been no further one for the given timeout.
+pub async fn account_ids<L>(evt_log: &L, idle_timeout: Duration) -> Result<Vec<Uuid>>
+where
+    L: EvtLog,
+{
+    let evts = evt_log
+        .evts_by_tag::<Bytes, _, _, _>(ACCOUNT_LIFECYCLE_TAG, SeqNo::MIN, raw)
+        .await
+        .context("Cannot create events-by-tag query")?;
+    let mut evts = Box::pin(evts);
+
+    let mut ids = vec![];
+    while let Ok(Some(evt)) = timeout(idle_timeout, evts.next()).await {
+        let (_, evt) = evt.context("Cannot get next event")?;
+        let evt = serde_json::from_slice::<account::Evt>(&evt)
+            .context("Cannot deserialize lifecycle event")?;
+        if let account::Evt::Created { id, .. } = evt {
+            ids.push(id);
+        }
+    }
+
+    Ok(ids)
+}
+
+/// The events of the entity with the given stream ID, upcast to the current account schema.
+pub async fn evts_by_id<L>(evt_log: &L, id: Uuid) -> Result<Vec<account::Evt>>
+where
+    L: EvtLog,
+{
+    let Some(last_seq_no) = evt_log
+        .last_seq_no(id)
+        .await
+        .context("Cannot get last sequence number")?
+    else {
+        return Ok(vec![]);
+    };
+
+    evt_log
+        .evts_by_id::<Bytes, _, _, _>(id, SeqNo::MIN, raw)
+        .await
+        .context("Cannot get events")?
+        .take(last_seq_no.as_u64() as usize)
+        .map_err(|error| anyhow!("Cannot get next event: {error}"))
+        .and_then(|(_, evt)| async move {
+            serde_json::from_slice::<account::Evt>(&evt).context("Cannot deserialize event")
+        })
+        .try_collect()
+        .await
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
@@ -0,0 +1,447 @@
+use crate::{
+    domain::account,
+    infra::{
+        backfill::{account_ids, evts_by_id},
+        namespace::{EntityType, Namespace},
+    },
+};
+use anyhow::{anyhow, Context, Result};
+use bytes::Bytes;
+use eventsourced::{convert, EvtLog, SeqNo, SnapshotStore};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    num::{NonZeroU64, NonZeroUsize},
+    path::Path,
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+const MANIFEST: &str = "manifest.json";
+
+const RECORDS: &str = "records.ndjson";
+
+/// Configuration for backups.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Accounts are discovered via their lifecycle events; discovery ends once there has been no
+    /// further lifecycle event for this long.
+    idle_timeout_secs: NonZeroU64,
+}
+
+/// Manifest of a backup, written once all records have been written and verified, i.e. a backup
+/// without manifest is incomplete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Manifest {
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// The head, i.e. last, sequence number of each account.
+    pub heads: BTreeMap<Uuid, NonZeroUsize>,
+    pub snapshots: usize,
+}
+
+/// Outcome of a restore.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub accounts: usize,
+    pub evts: usize,
+    /// Events already written by a previous, interrupted restore.
+    pub skipped: usize,
+    pub snapshots: usize,
+}
+
+/// A line of the records file of a backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum Record {
+    Evt {
+        account_id: Uuid,
+        seq_no: usize,
+        evt: account::Evt,
+    },
+    Snapshot {
+        account_id: Uuid,
+        seq_no: usize,
+        state: account::Snapshot,
+    },
+}
+
+/// The events and the snapshot, if any, of a single account to be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AccountRestore {
+    id: Uuid,
+    evts: Vec<account::Evt>,
+    snapshot: Option<(usize, account::Snapshot)>,
+}
+
+/// Back up the events and snapshots of all accounts into the given directory, which must not
+/// contain a backup yet. This is a portable export, complementing backend-native backups, e.g.
+/// Postgres base backups or NATS stream snapshots, which can be restored into any backend. The
+/// written records are read back and verified against the head sequence numbers of the manifest.
+/// Can run while the server is running, as accounts are backed up up to their head at the time.
+pub async fn backup<L, S>(
+    config: Config,
+    namespace: Namespace,
+    evt_log: L,
+    snapshot_store: S,
+    dir: &Path,
+    now: OffsetDateTime,
+) -> Result<Manifest>
+where
+    L: EvtLog,
+    S: SnapshotStore,
+{
+    let manifest_path = dir.join(MANIFEST);
+    if manifest_path.exists() {
+        return Err(anyhow!("Backup already exists in {}", dir.display()));
+    }
+    fs::create_dir_all(dir).context(format!("Cannot create directory {}", dir.display()))?;
+
+    let records_path = dir.join(RECORDS);
+    let mut records = BufWriter::new(
+        File::create(&records_path).context(format!("Cannot create {}", records_path.display()))?,
+    );
+    let mut manifest = Manifest {
+        created_at: now,
+        heads: BTreeMap::new(),
+        snapshots: 0,
+    };
+
+    let idle_timeout = Duration::from_secs(config.idle_timeout_secs.get());
+    for account_id in account_ids(&evt_log, idle_timeout).await? {
+        let stream_id = namespace.id(EntityType::Account, account_id);
+
+        // The snapshot is loaded before the events, such that it is not ahead of these while the
+        // server is running, else the snapshot store is inconsistent.
+        let snapshot = snapshot_store
+            .load::<account::Snapshot, _, _>(stream_id, convert::serde_json::from_bytes)
+            .await
+            .context(format!("Cannot load snapshot of account {account_id}"))?;
+        let evts = evts_by_id(&evt_log, stream_id)
+            .await
+            .context(format!("Cannot get events of account {account_id}"))?;
+        let Some(head) = NonZeroUsize::new(evts.len()) else {
+            continue;
+        };
+        if let Some(snapshot) = &snapshot {
+            if snapshot.seq_no.as_u64() as usize > head.get() {
+                return Err(anyhow!(
+                    "Snapshot of account {account_id} ahead of its events"
+                ));
+            }
+        }
+
+        for (n, evt) in evts.into_iter().enumerate() {
+            write(
+                &mut records,
+                &Record::Evt {
+                    account_id,
+                    seq_no: n + 1,
+                    evt,
+                },
+            )?;
+        }
+        if let Some(snapshot) = snapshot {
+            write(
+                &mut records,
+                &Record::Snapshot {
+                    account_id,
+                    seq_no: snapshot.seq_no.as_u64() as usize,
+                    state: snapshot.state,
+                },
+            )?;
+            manifest.snapshots += 1;
+        }
+
+        debug!(%account_id, head = head.get(), "Backed up account");
+        manifest.heads.insert(account_id, head);
+    }
+    records
+        .into_inner()
+        .map_err(|error| error.into_error())
+        .and_then(|file| file.sync_all())
+        .context(format!("Cannot write {}", records_path.display()))?;
+
+    // Verify by reading back the records.
+    plan(&manifest, read_records(dir)?, None)?;
+
+    let file = File::create(&manifest_path)
+        .context(format!("Cannot create {}", manifest_path.display()))?;
+    serde_json::to_writer_pretty(file, &manifest)
+        .context(format!("Cannot write {}", manifest_path.display()))?;
+
+    info!(
+        accounts = manifest.heads.len(),
+        snapshots = manifest.snapshots,
+        "Backed up account events"
+    );
+    Ok(manifest)
+}
+
+/// Restore the events and snapshots of all accounts from the backup in the given directory into
+/// the given [EvtLog] and [SnapshotStore], which are expected to be empty, e.g. freshly set up.
+/// If `until` is given, only events up to this time are restored, i.e. a point-in-time restore,
+/// and snapshots after the last restored event are dropped. After restoring, the head sequence
+/// numbers of the event log are verified. Restores can be resumed by running them again, as
+/// events already written are skipped. Must not run while the server is running.
+pub async fn restore<L, S>(
+    namespace: Namespace,
+    mut evt_log: L,
+    mut snapshot_store: S,
+    dir: &Path,
+    until: Option<OffsetDateTime>,
+) -> Result<RestoreReport>
+where
+    L: EvtLog,
+    S: SnapshotStore,
+{
+    let manifest = read_manifest(dir)?;
+    let accounts = plan(&manifest, read_records(dir)?, until)?;
+    let mut report = RestoreReport::default();
+
+    for AccountRestore { id, evts, snapshot } in accounts {
+        report.accounts += 1;
+        let stream_id = namespace.id(EntityType::Account, id);
+
+        let written = evts_by_id(&evt_log, stream_id)
+            .await
+            .context(format!("Cannot get events of account {id}"))?;
+        if written.len() > evts.len() || written[..] != evts[..written.len()] {
+            return Err(anyhow!("Event log has diverging events for account {id}"));
+        }
+        report.evts += evts.len() - written.len();
+        report.skipped += written.len();
+
+        for evt in &evts[written.len()..] {
+            evt_log
+                .persist(
+                    stream_id,
+                    evt,
+                    Some(evt.tag().to_string()),
+                    &convert::serde_json::to_bytes::<account::Evt>,
+                )
+                .await
+                .context(format!("Cannot persist event for account {id}"))?;
+        }
+
+        // Verify the head sequence number.
+        let head = evt_log
+            .last_seq_no(stream_id)
+            .await
+            .context(format!("Cannot get last sequence number of account {id}"))?
+            .map(|seq_no| seq_no.as_u64() as usize)
+            .unwrap_or_default();
+        if head != evts.len() {
+            return Err(anyhow!(
+                "Verification failed for account {id}: head sequence number {head}, expected {}",
+                evts.len()
+            ));
+        }
+
+        // A snapshot ahead of the restored events would hide the restored state.
+        let existing = snapshot_store
+            .load::<Bytes, _, _>(stream_id, raw)
+            .await
+            .context(format!("Cannot load snapshot of account {id}"))?;
+        if existing.is_some_and(|existing| existing.seq_no.as_u64() as usize > head) {
+            return Err(anyhow!(
+                "Snapshot store has a newer snapshot of account {id}"
+            ));
+        }
+        if let Some((seq_no, state)) = snapshot {
+            let seq_no = NonZeroU64::new(seq_no as u64).context("Invalid sequence number")?;
+            snapshot_store
+                .save(
+                    stream_id,
+                    SeqNo::new(seq_no),
+                    state,
+                    &convert::serde_json::to_bytes::<account::Snapshot>,
+                )
+                .await
+                .context(format!("Cannot save snapshot of account {id}"))?;
+            report.snapshots += 1;
+        }
+    }
+
+    info!(?report, ?until, "Restored account events");
+    Ok(report)
+}
+
+/// Group the given records by account, verifying them against the given [Manifest], i.e. that
+/// each account has exactly the events up to its head sequence number, and cut them off after
+/// `until`, if given. Accounts without events up to `until` are skipped.
+fn plan(
+    manifest: &Manifest,
+    records: Vec<Record>,
+    until: Option<OffsetDateTime>,
+) -> Result<Vec<AccountRestore>> {
+    let mut accounts = manifest
+        .heads
+        .keys()
+        .map(|&id| {
+            let account = AccountRestore {
+                id,
+                evts: vec![],
+                snapshot: None,
+            };
+            (id, account)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    for record in records {
+        match record {
+            Record::Evt {
+                account_id,
+                seq_no,
+                evt,
+            } => {
+                let account = accounts
+                    .get_mut(&account_id)
+                    .ok_or_else(|| anyhow!("Account {account_id} not in manifest"))?;
+                if seq_no != account.evts.len() + 1 {
+                    return Err(anyhow!(
+                        "Sequence gap for account {account_id} at sequence number {seq_no}"
+                    ));
+                }
+                account.evts.push(evt);
+            }
+
+            Record::Snapshot {
+                account_id,
+                seq_no,
+                state,
+            } => {
+                let account = accounts
+                    .get_mut(&account_id)
+                    .ok_or_else(|| anyhow!("Account {account_id} not in manifest"))?;
+                account.snapshot = Some((seq_no, state));
+            }
+        }
+    }
+
+    for (id, head) in &manifest.heads {
+        let evts = accounts[id].evts.len();
+        if evts != head.get() {
+            return Err(anyhow!(
+                "Account {id} has {evts} events, expected head sequence number {head}"
+            ));
+        }
+    }
+
+    let accounts = accounts
+        .into_values()
+        .filter_map(|mut account| {
+            if let Some(until) = until {
+                let n = account
+                    .evts
+                    .iter()
+                    .take_while(|evt| evt.at() <= until)
+                    .count();
+                account.evts.truncate(n);
+                account.snapshot = account.snapshot.filter(|(seq_no, _)| *seq_no <= n);
+            }
+            (!account.evts.is_empty()).then_some(account)
+        })
+        .collect();
+    Ok(accounts)
+}
+
+fn write(records: &mut impl Write, record: &Record) -> Result<()> {
+    serde_json::to_writer(&mut *records, record).context("Cannot write record")?;
+    writeln!(records).context("Cannot write record")
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest> {
+    let path = dir.join(MANIFEST);
+    let file = File::open(&path).context(format!(
+        "Cannot open {}, backup missing or incomplete",
+        path.display()
+    ))?;
+    serde_json::from_reader(file).context(format!("Cannot read {}", path.display()))
+}
+
+fn read_records(dir: &Path) -> Result<Vec<Record>> {
+    let path = dir.join(RECORDS);
+    let file = File::open(&path).context(format!("Cannot open {}", path.display()))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(n, line)| {
+            let line = line.context(format!("Cannot read {}", path.display()))?;
+            serde_json::from_str(&line).context(format!("Invalid record in line {}", n + 1))
+        })
+        .collect()
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    #[test]
+    fn test_plan() {
+        let account_id = Uuid::now_v7();
+        let at = OffsetDateTime::UNIX_EPOCH;
+        let evt = |seq_no: usize| Record::Evt {
+            account_id,
+            seq_no,
+            evt: account::Evt::Deposited {
+                id: Uuid::now_v7(),
+                account_id,
+                old_balance: 0.into(),
+                amount: 1.into(),
+                value_date: None,
+                channel: None,
+                at: at + Duration::days(seq_no as i64),
+            },
+        };
+        let manifest = Manifest {
+            created_at: at,
+            heads: BTreeMap::from([(account_id, NonZeroUsize::new(3).unwrap())]),
+            snapshots: 1,
+        };
+        let records = vec![
+            evt(1),
+            evt(2),
+            evt(3),
+            Record::Snapshot {
+                account_id,
+                seq_no: 2,
+                state: account::Snapshot::default(),
+            },
+        ];
+
+        let accounts = plan(&manifest, records.clone(), None).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].evts.len(), 3);
+        assert!(accounts[0].snapshot.is_some());
+
+        // Point in time before the snapshot.
+        let accounts = plan(&manifest, records.clone(), Some(at + Duration::days(1))).unwrap();
+        assert_eq!(accounts[0].evts.len(), 1);
+        assert!(accounts[0].snapshot.is_none());
+
+        // Point in time before the first event.
+        let accounts = plan(&manifest, records.clone(), Some(at)).unwrap();
+        assert!(accounts.is_empty());
+
+        // Missing event.
+        let mut gap = records.clone();
+        gap.remove(1);
+        assert!(plan(&manifest, gap, None).is_err());
+
+        // Missing head.
+        assert!(plan(&manifest, records[..2].to_vec(), None).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{
+    domain::{
+        books::{self, Books, KindTotal, PeriodTotals, BOOKS_ID, BOOKS_TAG},
+        clock::Clock,
+        euro_cent::EuroCent,
+    },
+    infra::{
+        gl_export::{GlExport, GlExporter},
+        ledger::LedgerProjection,
+        namespace::{EntityType, Namespace},
+        reporting::ReportPeriod,
+    },
+};
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use eventsourced::{convert, EntityRef, EventSourcedExt, EvtLog, SeqNo, SnapshotStore};
+use futures::StreamExt;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    num::NonZeroUsize,
+    sync::Arc,
+};
+use time::OffsetDateTime;
+use tokio::task;
+use tracing::{error, info, warn};
+
+/// Closes periods of the [Books] with the totals taken from the ledger and keeps track of all
+/// closed periods. If a [GlExport] is given, the entries of closed periods are posted to the GL.
+#[derive(Debug, Clone)]
+pub struct BookKeeper<G, E> {
+    clock: Arc<dyn Clock>,
+    books: EntityRef<Books>,
+    ledger_projection: G,
+    closed_periods: ClosedPeriods,
+    gl_export: Option<GlExport<E>>,
+}
+
+impl<G, E> BookKeeper<G, E>
+where
+    G: LedgerProjection,
+    E: GlExporter,
+{
+    /// Spawn the [Books] entity and keep track of the closed periods.
+    pub async fn spawn<L, S>(
+        namespace: Namespace,
+        clock: Arc<dyn Clock>,
+        evt_log: L,
+        snapshot_store: S,
+        ledger_projection: G,
+    ) -> Result<Self>
+    where
+        L: EvtLog,
+        S: SnapshotStore,
+    {
+        let books = Books::default()
+            .with_clock(clock.clone())
+            .spawn(
+                namespace.id(EntityType::Books, BOOKS_ID),
+                NonZeroUsize::MIN,
+                evt_log.clone(),
+                snapshot_store,
+                convert::serde_json::binarizer(),
+            )
+            .await
+            .context("Cannot spawn Books entity")?;
+
+        let evts = evt_log
+            .evts_by_tag::<Bytes, _, _, _>(BOOKS_TAG, SeqNo::MIN, raw)
+            .await
+            .context("Cannot create events-by-tag query")?;
+        let closed_periods = ClosedPeriods::default();
+        let closed_periods_clone = closed_periods.clone();
+        task::spawn(async move {
+            let mut evts = Box::pin(evts);
+            while let Some(evt) = evts.next().await {
+                let evt = match evt {
+                    Ok((_, evt)) => evt,
+                    Err(error) => {
+                        error!(error = format!("{error:#}"), "Cannot get next event");
+                        break;
+                    }
+                };
+                let Ok(books::Evt::PeriodClosed {
+                    from,
+                    to,
+                    totals,
+                    at,
+                }) = serde_json::from_slice::<books::Evt>(&evt)
+                else {
+                    warn!("Cannot deserialize books event");
+                    continue;
+                };
+                closed_periods_clone.0.write().push(ClosedPeriod {
+                    from,
+                    to,
+                    totals,
+                    closed_at: at,
+                });
+            }
+            error!("Tracking closed periods terminated");
+        });
+
+        Ok(Self {
+            clock,
+            books,
+            ledger_projection,
+            closed_periods,
+            gl_export: None,
+        })
+    }
+
+    /// Post the entries of closed periods to the GL via the given [GlExport].
+    pub fn with_gl_export(self, gl_export: GlExport<E>) -> Self {
+        Self {
+            gl_export: Some(gl_export),
+            ..self
+        }
+    }
+
+    /// Close the period from the end of the last closed one through the end of the last completed
+    /// day or month. Fails with [books::Error::NotContiguous] if another period has been closed
+    /// concurrently.
+    pub async fn close(&self, period: ReportPeriod) -> Result<Result<ClosedPeriod, books::Error>> {
+        let (_, to) = period.last_completed(self.clock.now());
+        let from = self.closed_periods.closed_until();
+        let entries = self
+            .ledger_projection
+            .entries(OffsetDateTime::UNIX_EPOCH, to)
+            .await;
+
+        // Totals per kind for the period, closing balances over all entries, excluding the test
+        // data of sandbox accounts.
+        let mut kinds = BTreeMap::<&'static str, (u64, EuroCent)>::new();
+        let mut balances = HashMap::new();
+        let mut period_entries = vec![];
+        for entry in entries.into_iter().filter(|entry| !entry.sandbox) {
+            if from.map_or(true, |from| entry.booked_at >= from) {
+                let (count, sum) = kinds.entry(entry.kind.as_str()).or_default();
+                *count += 1;
+                *sum = *sum + entry.amount;
+                period_entries.push(entry);
+            }
+            balances.insert(entry.account_id, entry.balance);
+        }
+        let totals = PeriodTotals {
+            kinds: kinds
+                .into_iter()
+                .map(|(kind, (count, sum))| KindTotal {
+                    kind: kind.to_string(),
+                    count,
+                    sum,
+                })
+                .collect(),
+            closing_balance: balances
+                .values()
+                .fold(EuroCent::default(), |total, balance| total + *balance),
+            accounts: balances.len(),
+        };
+
+        let cmd = books::Cmd::ClosePeriod {
+            from,
+            to,
+            totals: totals.clone(),
+        };
+        let result = self
+            .books
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle ClosePeriod command")?;
+        if let Err(error) = result {
+            return Ok(Err(error));
+        }
+        info!(?from, %to, "Closed period");
+
+        // The period stays closed if the export fails, which is logged and counted.
+        if let Some(gl_export) = &self.gl_export {
+            if let Err(error) = gl_export.export(from, to, &period_entries).await {
+                error!(?from, %to, error = format!("{error:#}"), "Cannot export period to GL");
+            }
+        }
+
+        Ok(Ok(ClosedPeriod {
+            from,
+            to,
+            totals,
+            closed_at: self.clock.now(),
+        }))
+    }
+
+    /// The closed periods, kept up to date.
+    pub fn closed_periods(&self) -> ClosedPeriods {
+        self.closed_periods.clone()
+    }
+}
+
+/// Handle to the closed periods, kept up to date by the [BookKeeper].
+#[derive(Debug, Clone, Default)]
+pub struct ClosedPeriods(Arc<RwLock<Vec<ClosedPeriod>>>);
+
+impl ClosedPeriods {
+    /// All closed periods, oldest first.
+    pub fn list(&self) -> Vec<ClosedPeriod> {
+        self.0.read().clone()
+    }
+
+    /// The end of the last closed period, if any.
+    pub fn closed_until(&self) -> Option<OffsetDateTime> {
+        self.0.read().last().map(|period| period.to)
+    }
+}
+
+/// A closed period with the immutable snapshot of its totals.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClosedPeriod {
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    pub totals: PeriodTotals,
+    #[serde(with = "time::serde::rfc3339")]
+    pub closed_at: OffsetDateTime,
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
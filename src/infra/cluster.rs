@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    body::Body,
+    http::{header::HOST, uri::PathAndQuery, HeaderValue, Request, Uri},
+    response::{IntoResponse, Response},
+};
+use hyper::{client::HttpConnector, Client};
+use serde::Deserialize;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
+    sync::Arc,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Request header marking requests forwarded by another node, which must not be forwarded again.
+pub const FORWARDED_BY: &str = "forwarded-by";
+
+/// Cluster configuration with static membership: all nodes must be configured with the same nodes
+/// and virtual nodes, differing only in their own node ID.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    node_id: String,
+    nodes: Vec<NodeConfig>,
+    virtual_nodes: NonZeroUsize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NodeConfig {
+    id: String,
+    url: String,
+}
+
+/// A cluster of nodes, each owning the accounts assigned to it by consistent hashing of the account
+/// ID, such that no two nodes spawn the same account entity.
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    node_id: Arc<str>,
+    ring: Arc<HashRing>,
+    client: Client<HttpConnector>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    id: String,
+    url: Uri,
+}
+
+impl Cluster {
+    pub fn new(config: Config) -> Result<Self> {
+        let nodes = config
+            .nodes
+            .into_iter()
+            .map(|NodeConfig { id, url }| {
+                url.parse::<Uri>()
+                    .with_context(|| format!("Invalid URL for node '{id}'"))
+                    .map(|url| Node { id, url })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if !nodes.iter().any(|node| node.id == config.node_id) {
+            return Err(anyhow!(
+                "Node ID '{}' not contained in nodes",
+                config.node_id
+            ));
+        }
+
+        Ok(Self {
+            node_id: config.node_id.into(),
+            ring: Arc::new(HashRing::new(nodes, config.virtual_nodes)),
+            client: Client::new(),
+        })
+    }
+
+    /// Is the account with the given ID owned by this node?
+    pub fn is_local(&self, id: Uuid) -> bool {
+        self.ring.owner(id).id == *self.node_id
+    }
+
+    /// Forward the given request for the account with the given ID to its owner, if that is not
+    /// this node and the request has not already been forwarded, else give it back.
+    pub async fn forward(
+        &self,
+        id: Uuid,
+        mut request: Request<Body>,
+    ) -> Result<Response, Request<Body>> {
+        let owner = self.ring.owner(id);
+        if owner.id == *self.node_id || request.headers().contains_key(FORWARDED_BY) {
+            return Err(request);
+        }
+
+        debug!(%id, node_id = owner.id, "Forwarding request");
+
+        let mut uri = owner.url.clone().into_parts();
+        uri.path_and_query = request
+            .uri()
+            .path_and_query()
+            .cloned()
+            .or_else(|| Some(PathAndQuery::from_static("/")));
+        let Ok(uri) = Uri::from_parts(uri) else {
+            return Err(request);
+        };
+        *request.uri_mut() = uri;
+        request.headers_mut().remove(HOST);
+        if let Ok(node_id) = HeaderValue::from_str(&self.node_id) {
+            request.headers_mut().insert(FORWARDED_BY, node_id);
+        }
+
+        Ok(match self.client.request(request).await {
+            Ok(response) => response.into_response(),
+            Err(error) => {
+                debug!(%id, error = format!("{error:#}"), "Cannot forward request");
+                hyper::StatusCode::BAD_GATEWAY.into_response()
+            }
+        })
+    }
+}
+
+/// Consistent hash ring with virtual nodes. Uses [DefaultHasher], which is stable for the same
+/// binary, hence all nodes must run the same version.
+#[derive(Debug)]
+struct HashRing {
+    nodes: Vec<Node>,
+    ring: BTreeMap<u64, usize>,
+}
+
+impl HashRing {
+    fn new(nodes: Vec<Node>, virtual_nodes: NonZeroUsize) -> Self {
+        let ring = nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(n, node)| {
+                (0..virtual_nodes.get()).map(move |v| (hash(&(node.id.as_str(), v)), n))
+            })
+            .collect();
+        Self { nodes, ring }
+    }
+
+    fn owner(&self, id: Uuid) -> &Node {
+        let hash = hash(&id);
+        let n = self
+            .ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, n)| *n)
+            .expect("ring is not empty");
+        &self.nodes[n]
+    }
+}
+
+fn hash<T>(t: &T) -> u64
+where
+    T: Hash,
+{
+    let mut hasher = DefaultHasher::new();
+    t.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_ring() {
+        let nodes = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| Node {
+                    id: id.to_string(),
+                    url: Uri::from_static("http://localhost"),
+                })
+                .collect::<Vec<_>>()
+        };
+        let virtual_nodes = NonZeroUsize::new(64).unwrap();
+        let ring_3 = HashRing::new(nodes(&["a", "b", "c"]), virtual_nodes);
+        let ring_2 = HashRing::new(nodes(&["a", "b"]), virtual_nodes);
+
+        let ids = (0..1_000).map(|_| Uuid::now_v7()).collect::<Vec<_>>();
+
+        // All nodes own some accounts.
+        for node_id in ["a", "b", "c"] {
+            assert!(ids.iter().any(|id| ring_3.owner(*id).id == node_id));
+        }
+
+        // Removing a node only reassigns the accounts owned by that node.
+        for id in &ids {
+            let owner = &ring_3.owner(*id).id;
+            if owner != "c" {
+                assert_eq!(&ring_2.owner(*id).id, owner);
+            }
+        }
+    }
+}
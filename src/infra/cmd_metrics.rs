@@ -0,0 +1,76 @@
+use crate::infra::namespace::EntityType;
+use metrics::{counter, histogram};
+use serde::Deserialize;
+use std::{num::NonZeroU64, sync::OnceLock, time::Duration};
+use tracing::warn;
+use uuid::Uuid;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Configuration for command metrics.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Commands taking at least this long are logged as slow.
+    slow_threshold_millis: NonZeroU64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            slow_threshold_millis: NonZeroU64::new(500).expect("500 is not zero"),
+        }
+    }
+}
+
+/// Initialize command metrics with the given [Config]; must be invoked before handling commands,
+/// else the default configuration is used.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// Record the duration of handling a command of the given type by the entity with the given type
+/// and ID, including persisting its events, and log the command if slow. Together with the
+/// duration of getting the entity from its factory, this tells whether slowness is caused by the
+/// factory or by the entity, i.e. its aggregate and event log.
+pub fn record(entity_type: EntityType, id: Uuid, cmd: &'static str, duration: Duration) {
+    histogram!(
+        "entity_cmd_duration_seconds",
+        duration.as_secs_f64(),
+        "entity_type" => entity_type.as_str(),
+        "cmd" => cmd
+    );
+
+    if is_slow(duration) {
+        counter!(
+            "entity_slow_cmds_total",
+            1,
+            "entity_type" => entity_type.as_str(),
+            "cmd" => cmd
+        );
+        warn!(
+            entity_type = entity_type.as_str(),
+            %id,
+            cmd,
+            duration_millis = duration.as_millis() as u64,
+            "Slow command"
+        );
+    }
+}
+
+fn is_slow(duration: Duration) -> bool {
+    let config = CONFIG.get_or_init(Config::default);
+    duration >= Duration::from_millis(config.slow_threshold_millis.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_slow() {
+        // Not initialized, hence the default threshold applies.
+        assert!(!is_slow(Duration::from_millis(499)));
+        assert!(is_slow(Duration::from_millis(500)));
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{
+    domain::{clock::Clock, consent::Consent},
+    infra::{
+        lru_cache_factory::{self, Evictor, LruCacheEntityFactory, ManagedEntity},
+        namespace::EntityType,
+    },
+};
+use eventsourced::EntityRef;
+use std::{future::Future, num::NonZeroU64, sync::Arc};
+use uuid::Uuid;
+
+/// A factory for [Consent]s, either creating new ones or returning existing managed ones.
+pub trait ConsentFactory: Clone + Send + Sync + 'static {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Create a new [Consent] or return an existing managed one.
+    fn get(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = Result<EntityRef<Consent>, Self::Error>> + Send + '_;
+}
+
+impl ConsentFactory for LruCacheEntityFactory<Consent> {
+    type Error = lru_cache_factory::Error;
+
+    async fn get(&self, id: Uuid) -> Result<EntityRef<Consent>, Self::Error> {
+        self.entity(id).await
+    }
+}
+
+impl ManagedEntity for Consent {
+    const ENTITY_TYPE: EntityType = EntityType::Consent;
+
+    type Settings = ();
+
+    type Observer = ();
+
+    type Ref = EntityRef<Consent>;
+
+    fn create(_settings: &(), clock: Arc<dyn Clock>, _snapshot_after: Option<NonZeroU64>) -> Self {
+        Consent::default().with_clock(clock)
+    }
+
+    fn observe(&self) -> Self::Observer {}
+
+    fn into_ref(entity: EntityRef<Self>, _observer: (), _evictor: Evictor) -> Self::Ref {
+        entity
+    }
+}
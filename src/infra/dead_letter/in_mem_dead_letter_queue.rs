@@ -0,0 +1,176 @@
+use super::{DeadLetter, DeadLetterQueue, DeadLetterStore};
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// [DeadLetterQueue] keeping the [DeadLetter]s in memory and, if a [DeadLetterStore] is given,
+/// persisting these, such that they survive restarts and can be replayed after an upgrade.
+#[derive(Debug, Clone, Default)]
+pub struct InMemDeadLetterQueue {
+    dead_letters: Arc<RwLock<Vec<DeadLetter>>>,
+    replay_sdrs: Arc<RwLock<HashMap<&'static str, mpsc::UnboundedSender<DeadLetter>>>>,
+    store: Option<Arc<dyn DeadLetterStore>>,
+}
+
+impl InMemDeadLetterQueue {
+    /// Create an [InMemDeadLetterQueue] persisting the [DeadLetter]s in the given
+    /// [DeadLetterStore], if any, loading the ones already stored.
+    pub fn new(store: Option<Arc<dyn DeadLetterStore>>) -> Result<Self> {
+        let dead_letters = match &store {
+            Some(store) => store.load().context("Cannot load dead letters")?,
+            None => vec![],
+        };
+        Ok(Self {
+            dead_letters: Arc::new(RwLock::new(dead_letters)),
+            replay_sdrs: Arc::default(),
+            store,
+        })
+    }
+}
+
+impl DeadLetterQueue for InMemDeadLetterQueue {
+    async fn register(&self, projection: &'static str) -> mpsc::UnboundedReceiver<DeadLetter> {
+        let (replay_sdr, replay_rcv) = mpsc::unbounded_channel();
+        self.replay_sdrs.write().insert(projection, replay_sdr);
+        replay_rcv
+    }
+
+    async fn push(&self, dead_letter: DeadLetter) {
+        warn!(?dead_letter, "Adding dead letter");
+        self.save(&dead_letter);
+        self.dead_letters.write().push(dead_letter);
+    }
+
+    async fn list(&self) -> Vec<DeadLetter> {
+        self.dead_letters.read().clone()
+    }
+
+    async fn replay(&self, id: Uuid, evt: Option<String>) -> bool {
+        let Some(mut dead_letter) = self.take(id) else {
+            return false;
+        };
+        let original = dead_letter.clone();
+        if let Some(evt) = evt {
+            dead_letter.evt = evt;
+        }
+
+        let replay_sdr = self
+            .replay_sdrs
+            .read()
+            .get(dead_letter.projection.as_str())
+            .cloned();
+        match replay_sdr.map(|replay_sdr| replay_sdr.send(dead_letter)) {
+            Some(Ok(())) => {
+                debug!(%id, "Replaying dead letter");
+                true
+            }
+
+            _ => {
+                self.save(&original);
+                self.dead_letters.write().push(original);
+                false
+            }
+        }
+    }
+
+    async fn discard(&self, id: Uuid) -> bool {
+        self.take(id).is_some()
+    }
+}
+
+impl InMemDeadLetterQueue {
+    fn take(&self, id: Uuid) -> Option<DeadLetter> {
+        let dead_letter = {
+            let mut dead_letters = self.dead_letters.write();
+            dead_letters
+                .iter()
+                .position(|dead_letter| dead_letter.id == id)
+                .map(|n| dead_letters.remove(n))
+        };
+        if dead_letter.is_some() {
+            if let Some(store) = &self.store {
+                if let Err(error) = store.remove(id) {
+                    error!(%id, error = format!("{error:#}"), "Cannot remove dead letter");
+                }
+            }
+        }
+        dead_letter
+    }
+
+    fn save(&self, dead_letter: &DeadLetter) {
+        if let Some(store) = &self.store {
+            if let Err(error) = store.save(dead_letter) {
+                error!(
+                    id = %dead_letter.id,
+                    error = format!("{error:#}"),
+                    "Cannot save dead letter"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use time::OffsetDateTime;
+
+    #[derive(Debug, Default)]
+    struct TestStore(Mutex<Vec<DeadLetter>>);
+
+    impl DeadLetterStore for TestStore {
+        fn load(&self) -> Result<Vec<DeadLetter>> {
+            Ok(self.0.lock().clone())
+        }
+
+        fn save(&self, dead_letter: &DeadLetter) -> Result<()> {
+            self.0.lock().push(dead_letter.clone());
+            Ok(())
+        }
+
+        fn remove(&self, id: Uuid) -> Result<()> {
+            self.0.lock().retain(|dead_letter| dead_letter.id != id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_persisted() {
+        let store = Arc::new(TestStore::default());
+        let dead_letter = DeadLetter {
+            id: Uuid::from_u128(1),
+            projection: "test".to_string(),
+            seq_no: 42,
+            evt: "invalid".to_string(),
+            error: "Cannot decode".to_string(),
+            at: OffsetDateTime::UNIX_EPOCH,
+        };
+
+        let queue = InMemDeadLetterQueue::new(Some(store.clone())).unwrap();
+        queue.push(dead_letter.clone()).await;
+
+        // After a restart, the dead letter is still there.
+        let queue = InMemDeadLetterQueue::new(Some(store.clone())).unwrap();
+        assert_eq!(queue.list().await, vec![dead_letter.clone()]);
+
+        // Without the projection being registered, the dead letter is kept.
+        assert!(!queue.replay(dead_letter.id, None).await);
+        assert_eq!(store.load().unwrap(), vec![dead_letter.clone()]);
+
+        let mut replays = queue.register("test").await;
+        assert!(
+            queue
+                .replay(dead_letter.id, Some("valid".to_string()))
+                .await
+        );
+        let replayed = replays.recv().await.unwrap();
+        assert_eq!(replayed.evt, "valid");
+        assert_eq!(replayed.seq_no, 42);
+        assert!(queue.list().await.is_empty());
+        assert!(store.load().unwrap().is_empty());
+    }
+}
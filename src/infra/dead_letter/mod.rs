@@ -0,0 +1,59 @@
+pub mod in_mem_dead_letter_queue;
+#[cfg(feature = "sled")]
+pub mod sled_dead_letter_store;
+
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, future::Future};
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// A queue for events a projection failed to handle, such that these can be inspected and
+/// replayed instead of terminating the projection.
+pub trait DeadLetterQueue: Clone + Send + Sync + 'static {
+    /// Register the projection with the given name, returning a receiver for replayed
+    /// [DeadLetter]s.
+    fn register(
+        &self,
+        projection: &'static str,
+    ) -> impl Future<Output = mpsc::UnboundedReceiver<DeadLetter>> + Send + '_;
+
+    /// Add the given [DeadLetter].
+    fn push(&self, dead_letter: DeadLetter) -> impl Future<Output = ()> + Send + '_;
+
+    /// All current [DeadLetter]s.
+    fn list(&self) -> impl Future<Output = Vec<DeadLetter>> + Send + '_;
+
+    /// Remove the [DeadLetter] with the given ID and send it back to its projection, optionally
+    /// with the given corrected event, as events which cannot be decoded can only succeed with a
+    /// corrected event or with a fixed decoder after an upgrade. Returns `false`, if there is no
+    /// such dead letter or its projection is not registered.
+    fn replay(&self, id: Uuid, evt: Option<String>) -> impl Future<Output = bool> + Send + '_;
+
+    /// Remove the [DeadLetter] with the given ID. Returns `false`, if there is no such dead letter.
+    fn discard(&self, id: Uuid) -> impl Future<Output = bool> + Send + '_;
+}
+
+/// Store for [DeadLetter]s, such that these survive restarts and upgrades.
+pub trait DeadLetterStore: Debug + Send + Sync + 'static {
+    /// All stored [DeadLetter]s.
+    fn load(&self) -> anyhow::Result<Vec<DeadLetter>>;
+
+    /// Save the given [DeadLetter].
+    fn save(&self, dead_letter: &DeadLetter) -> anyhow::Result<()>;
+
+    /// Remove the [DeadLetter] with the given ID.
+    fn remove(&self, id: Uuid) -> anyhow::Result<()>;
+}
+
+/// An event a projection failed to handle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub projection: String,
+    pub seq_no: u64,
+    pub evt: String,
+    pub error: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+}
@@ -0,0 +1,59 @@
+use super::{DeadLetter, DeadLetterStore};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// [DeadLetterStore] based upon an embedded sled database, e.g. for single-node deployments.
+#[derive(Debug, Clone)]
+pub struct SledDeadLetterStore {
+    db: sled::Db,
+}
+
+impl SledDeadLetterStore {
+    /// Open the database at the configured path, creating it if not yet existing.
+    pub fn open(config: Config) -> Result<Self> {
+        let db = sled::open(&config.path).context(format!(
+            "Cannot open sled database at {}",
+            config.path.display()
+        ))?;
+        Ok(Self { db })
+    }
+}
+
+impl DeadLetterStore for SledDeadLetterStore {
+    fn load(&self) -> Result<Vec<DeadLetter>> {
+        self.db
+            .iter()
+            .values()
+            .map(|dead_letter| {
+                let dead_letter = dead_letter.context("Cannot load dead letter")?;
+                serde_json::from_slice(&dead_letter).context("Cannot deserialize dead letter")
+            })
+            .collect()
+    }
+
+    fn save(&self, dead_letter: &DeadLetter) -> Result<()> {
+        let bytes = serde_json::to_vec(dead_letter).context("Cannot serialize dead letter")?;
+        self.db
+            .insert(dead_letter.id.as_bytes(), bytes)
+            .context(format!("Cannot save dead letter {}", dead_letter.id))?;
+        self.db.flush().context("Cannot flush dead letters")?;
+        Ok(())
+    }
+
+    fn remove(&self, id: Uuid) -> Result<()> {
+        self.db
+            .remove(id.as_bytes())
+            .context(format!("Cannot remove dead letter {id}"))?;
+        self.db.flush().context("Cannot flush dead letters")?;
+        Ok(())
+    }
+}
+
+/// Configuration for the [SledDeadLetterStore].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    path: PathBuf,
+}
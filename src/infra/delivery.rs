@@ -0,0 +1,202 @@
+use crate::domain::clock::Clock;
+use dashmap::DashMap;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    num::{NonZeroU32, NonZeroU64},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::{task, time::sleep};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Configuration for the [FileDrop].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Directory to drop the files into, e.g. the root of an SFTP server or a directory synced to
+    /// an S3 bucket.
+    dir: PathBuf,
+
+    /// Maximum number of attempts per file.
+    max_attempts: NonZeroU32,
+
+    /// Delay before the first retry, doubled for each further one.
+    retry_delay_secs: NonZeroU64,
+}
+
+/// Delivers generated files, e.g. statements or reports, into a drop directory, from where
+/// corporate clients collect them, e.g. via SFTP. Files are written to a temporary file and renamed
+/// when complete, such that collectors never see partial files. Failed deliveries are retried with
+/// exponential backoff and the status of each delivery is tracked.
+#[derive(Debug, Clone)]
+pub struct FileDrop {
+    config: Arc<Config>,
+    clock: Arc<dyn Clock>,
+    statuses: Arc<DashMap<Uuid, DeliveryStatus>>,
+}
+
+impl FileDrop {
+    #[allow(missing_docs)]
+    pub fn new(config: Config, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            config: Arc::new(config),
+            clock,
+            statuses: Default::default(),
+        }
+    }
+
+    /// Spawn delivering the given content as file with the given name, tracked under the given ID,
+    /// e.g. of a report run.
+    pub fn deliver(&self, id: Uuid, file_name: String, content: String) {
+        self.statuses
+            .insert(id, DeliveryStatus::Pending { attempts: 0 });
+
+        let file_drop = self.clone();
+        task::spawn(async move {
+            let mut delay = Duration::from_secs(file_drop.config.retry_delay_secs.get());
+            for attempt in 1..=file_drop.config.max_attempts.get() {
+                let dir = file_drop.config.dir.clone();
+                let name = file_name.clone();
+                let content = content.clone();
+                let result = task::spawn_blocking(move || write_atomically(&dir, &name, &content))
+                    .await
+                    .unwrap_or_else(|error| Err(io::Error::new(io::ErrorKind::Other, error)));
+
+                match result {
+                    Ok(()) => {
+                        info!(%id, file_name, attempt, "Delivered file");
+                        counter!("file_deliveries_total", 1, "status" => "delivered");
+                        file_drop.statuses.insert(
+                            id,
+                            DeliveryStatus::Delivered {
+                                attempts: attempt,
+                                at: file_drop.clock.now(),
+                            },
+                        );
+                        return;
+                    }
+
+                    Err(error) if attempt < file_drop.config.max_attempts.get() => {
+                        warn!(%id, file_name, attempt, %error, "Cannot deliver file, retrying");
+                        file_drop
+                            .statuses
+                            .insert(id, DeliveryStatus::Pending { attempts: attempt });
+                        sleep(delay).await;
+                        delay *= 2;
+                    }
+
+                    Err(error) => {
+                        warn!(%id, file_name, attempt, %error, "Cannot deliver file, giving up");
+                        counter!("file_deliveries_total", 1, "status" => "failed");
+                        file_drop.statuses.insert(
+                            id,
+                            DeliveryStatus::Failed {
+                                attempts: attempt,
+                                error: error.to_string(),
+                            },
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    /// The status of the delivery with the given ID, if any.
+    pub fn status(&self, id: Uuid) -> Option<DeliveryStatus> {
+        self.statuses.get(&id).map(|status| status.clone())
+    }
+}
+
+/// Status of a delivery.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum DeliveryStatus {
+    /// Not yet delivered, possibly after failed attempts.
+    Pending { attempts: u32 },
+
+    /// Delivered at the given time.
+    Delivered {
+        attempts: u32,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+
+    /// All attempts failed, the last one with the given error.
+    Failed { attempts: u32, error: String },
+}
+
+fn write_atomically(dir: &Path, file_name: &str, content: &str) -> io::Result<()> {
+    let tmp = dir.join(format!(".{file_name}.tmp"));
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, dir.join(file_name))?;
+    debug!(file_name, dir = %dir.display(), "Wrote file");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+    use std::env;
+
+    async fn await_done(file_drop: &FileDrop, id: Uuid) -> DeliveryStatus {
+        for _ in 0..100 {
+            match file_drop.status(id) {
+                Some(DeliveryStatus::Pending { .. }) | None => {
+                    sleep(Duration::from_millis(10)).await
+                }
+                Some(status) => return status,
+            }
+        }
+        panic!("Delivery not done");
+    }
+
+    #[tokio::test]
+    async fn test_deliver() {
+        let dir = env::temp_dir().join(format!("file-drop-{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+        let clock = Arc::new(ManualClock::new(OffsetDateTime::UNIX_EPOCH));
+        let file_drop = FileDrop::new(
+            Config {
+                dir: dir.clone(),
+                max_attempts: NonZeroU32::new(1).unwrap(),
+                retry_delay_secs: NonZeroU64::new(1).unwrap(),
+            },
+            clock.clone(),
+        );
+
+        let id = Uuid::now_v7();
+        file_drop.deliver(id, "statement.csv".to_string(), "a,b\n".to_string());
+        let status = await_done(&file_drop, id).await;
+        assert!(matches!(
+            status,
+            DeliveryStatus::Delivered { attempts: 1, at } if at == OffsetDateTime::UNIX_EPOCH
+        ));
+        assert_eq!(
+            fs::read_to_string(dir.join("statement.csv")).unwrap(),
+            "a,b\n"
+        );
+        assert!(!dir.join(".statement.csv.tmp").exists());
+
+        let file_drop = FileDrop::new(
+            Config {
+                dir: dir.join("missing"),
+                max_attempts: NonZeroU32::new(1).unwrap(),
+                retry_delay_secs: NonZeroU64::new(1).unwrap(),
+            },
+            clock,
+        );
+        let id = Uuid::now_v7();
+        file_drop.deliver(id, "statement.csv".to_string(), "a,b\n".to_string());
+        let status = await_done(&file_drop, id).await;
+        assert!(matches!(status, DeliveryStatus::Failed { attempts: 1, .. }));
+    }
+}
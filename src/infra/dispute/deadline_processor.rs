@@ -0,0 +1,224 @@
+use super::DisputeFactory;
+use crate::{
+    domain::{account, clock::Clock, dispute, euro_cent::EuroCent, id::IdGenerator},
+    infra::{account::AccountFactory, leader::Leadership, lru_cache_factory::Priority},
+};
+use anyhow::Context;
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    num::NonZeroU64,
+    sync::Arc,
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::{pin, select, task, time::interval};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Configuration for the deadline processor.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Interval for checking for disputes past their deadline.
+    interval_secs: NonZeroU64,
+}
+
+/// Spawn processing disputes: those past their deadline are expired, i.e. upheld, and credits
+/// and claw-backs are booked to their accounts. Events are tracked on every node, but only the
+/// leader processes disputes. Bookings from before spawning, i.e. replayed ones, are not booked
+/// again.
+pub fn spawn<L, A, D>(
+    config: Config,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    evt_log: L,
+    account_factory: A,
+    dispute_factory: D,
+    leadership: Leadership,
+) where
+    L: EvtLog,
+    A: AccountFactory,
+    D: DisputeFactory,
+{
+    task::spawn(async move {
+        let started_at = clock.now();
+
+        let evts = evt_log
+            .evts_by_tag::<Bytes, _, _, _>(dispute::DISPUTE_TAG, SeqNo::MIN, raw)
+            .await
+            .context("Cannot create events-by-tag query");
+        let evts = match evts {
+            Ok(evts) => evts,
+            Err(error) => {
+                error!(error = format!("{error:#}"), "Cannot process disputes");
+                return;
+            }
+        };
+
+        let mut open = HashMap::<Uuid, OffsetDateTime>::new();
+        let mut in_flight = HashSet::<Uuid>::new();
+        let mut interval = interval(Duration::from_secs(config.interval_secs.get()));
+        pin!(evts);
+        loop {
+            select! {
+                evt = evts.next() => {
+                    let evt = match evt {
+                        Some(Ok((_, evt))) => evt,
+                        Some(Err(error)) => {
+                            error!(error = format!("{error:#}"), "Cannot get next event");
+                            break;
+                        }
+                        None => break,
+                    };
+                    let Ok(evt) = serde_json::from_slice::<dispute::Evt>(&evt) else {
+                        warn!("Cannot deserialize dispute event");
+                        continue;
+                    };
+                    debug!(dispute_id = %evt.dispute_id(), "Processing dispute event");
+
+                    let booking = match evt {
+                        dispute::Evt::Opened { id, deadline, .. } => {
+                            open.insert(id, deadline);
+                            None
+                        }
+
+                        dispute::Evt::ProvisionallyCredited {
+                            id,
+                            account_id,
+                            amount,
+                            at,
+                        } => (at >= started_at).then_some((id, account_id, Booking::Credit(amount))),
+
+                        dispute::Evt::Upheld {
+                            id,
+                            account_id,
+                            credit,
+                            at,
+                            ..
+                        } => {
+                            open.remove(&id);
+                            in_flight.remove(&id);
+                            (at >= started_at && credit > EuroCent::default())
+                                .then_some((id, account_id, Booking::Credit(credit)))
+                        }
+
+                        dispute::Evt::Denied {
+                            id,
+                            account_id,
+                            claw_back,
+                            at,
+                        } => {
+                            open.remove(&id);
+                            in_flight.remove(&id);
+                            (at >= started_at && claw_back > EuroCent::default())
+                                .then_some((id, account_id, Booking::ClawBack(claw_back)))
+                        }
+                    };
+                    if let Some((id, account_id, booking)) = booking {
+                        if leadership.is_leader() {
+                            book(&account_factory, ids.as_ref(), id, account_id, booking).await;
+                        }
+                    }
+                }
+
+                _ = interval.tick() => {
+                    if !leadership.is_leader() {
+                        continue;
+                    }
+
+                    let now = clock.now();
+                    let expired = open
+                        .iter()
+                        .filter(|(id, deadline)| **deadline <= now && !in_flight.contains(id))
+                        .map(|(id, _)| *id)
+                        .collect::<Vec<_>>();
+                    for id in expired {
+                        if expire(&dispute_factory, id).await {
+                            in_flight.insert(id);
+                        }
+                    }
+                }
+            }
+        }
+
+        error!("Deadline processor terminated");
+    });
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Booking {
+    Credit(EuroCent),
+    ClawBack(EuroCent),
+}
+
+/// Expire the given dispute; returns `false` on errors, such that it is retried.
+async fn expire<D>(dispute_factory: &D, id: Uuid) -> bool
+where
+    D: DisputeFactory,
+{
+    debug!(%id, "Expiring dispute");
+
+    let expired = async {
+        dispute_factory
+            .get(id)
+            .await
+            .context("Cannot get Dispute entity")?
+            .handle_cmd(dispute::Cmd::Expire)
+            .await
+            .context("Cannot handle Expire command")
+    }
+    .await;
+    match expired {
+        Ok(Ok(())) => true,
+
+        Ok(Err(error)) => {
+            error!(%id, %error, "Cannot expire dispute");
+            false
+        }
+
+        Err(error) => {
+            error!(%id, error = format!("{error:#}"), "Cannot expire dispute");
+            false
+        }
+    }
+}
+
+/// Failed claw-backs, e.g. because of an insufficient balance, require a manual booking.
+async fn book<A>(
+    account_factory: &A,
+    ids: &dyn IdGenerator,
+    id: Uuid,
+    account_id: Uuid,
+    booking: Booking,
+) where
+    A: AccountFactory,
+{
+    debug!(%id, ?booking, "Booking dispute");
+
+    let cmd = match booking {
+        Booking::Credit(amount) => account::Cmd::Deposit(ids.next_id(), amount, None, None),
+        Booking::ClawBack(amount) => account::Cmd::Withdraw(ids.next_id(), amount, None, None),
+    };
+    let booked = async {
+        account_factory
+            .get_with_priority(account_id, Priority::Bulk)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle command")
+    }
+    .await;
+    if !matches!(booked, Ok(Ok(_))) {
+        error!(%id, %account_id, ?booking, "Cannot book dispute, manual booking required");
+    }
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
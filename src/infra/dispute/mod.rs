@@ -0,0 +1,51 @@
+pub mod deadline_processor;
+
+use crate::{
+    domain::{clock::Clock, dispute::Dispute},
+    infra::{
+        lru_cache_factory::{self, Evictor, LruCacheEntityFactory, ManagedEntity},
+        namespace::EntityType,
+    },
+};
+use eventsourced::EntityRef;
+use std::{future::Future, num::NonZeroU64, sync::Arc};
+use uuid::Uuid;
+
+/// A factory for [Dispute]s, either creating new ones or returning existing managed ones.
+pub trait DisputeFactory: Clone + Send + Sync + 'static {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Create a new [Dispute] or return an existing managed one.
+    fn get(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = Result<EntityRef<Dispute>, Self::Error>> + Send + '_;
+}
+
+impl DisputeFactory for LruCacheEntityFactory<Dispute> {
+    type Error = lru_cache_factory::Error;
+
+    async fn get(&self, id: Uuid) -> Result<EntityRef<Dispute>, Self::Error> {
+        self.entity(id).await
+    }
+}
+
+impl ManagedEntity for Dispute {
+    const ENTITY_TYPE: EntityType = EntityType::Dispute;
+
+    type Settings = ();
+
+    type Observer = ();
+
+    type Ref = EntityRef<Dispute>;
+
+    fn create(_settings: &(), clock: Arc<dyn Clock>, _snapshot_after: Option<NonZeroU64>) -> Self {
+        Dispute::default().with_clock(clock)
+    }
+
+    fn observe(&self) -> Self::Observer {}
+
+    fn into_ref(entity: EntityRef<Self>, _observer: (), _evictor: Evictor) -> Self::Ref {
+        entity
+    }
+}
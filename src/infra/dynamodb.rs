@@ -0,0 +1,527 @@
+use crate::domain::clock::Clock;
+use aws_sdk_dynamodb::{
+    primitives::Blob,
+    types::{AttributeValue, Put, ReturnValue, TransactWriteItem, Update},
+    Client,
+};
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo, Snapshot, SnapshotStore, TrySeqNoFromZero};
+use futures::{stream, Stream};
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error as StdError,
+    fmt::Debug,
+    num::{NonZeroU64, NonZeroUsize},
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::time::sleep;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Sort key of the head item of each entity, holding its last sequence number. The head item of
+/// the nil UUID, which is never used for entities, holds the global sequence number.
+const HEAD: &str = "0";
+
+type Item = HashMap<String, AttributeValue>;
+
+/// [EvtLog] based upon DynamoDB. Like for NATS, sequence numbers are global, i.e. shared by all
+/// entities; they are allocated from a counter item. Events are persisted in a transaction with
+/// the head item of their entity, conditional on the last sequence number seen by this event log,
+/// such that concurrent writers for the same entity, e.g. during a rolling deployment, are
+/// detected and rejected with [Error::Conflict]. Events-by-tag queries poll the `tag-index` and
+/// only emit events older than the configured settle time, because events with lower sequence
+/// numbers might still be in flight.
+///
+/// The table is expected to exist with partition key `id` (binary), sort key `seq_no` (number) and
+/// a global secondary index named `tag-index` with partition key `tag` (string), sort key `seq_no`
+/// (number) and all attributes projected.
+#[derive(Debug, Clone)]
+pub struct DynamoEvtLog {
+    client: Client,
+    table: String,
+    poll_interval: Duration,
+    settle: Duration,
+    last_seq_nos: Arc<Mutex<LruCache<Uuid, SeqNo>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl DynamoEvtLog {
+    #[allow(missing_docs)]
+    pub async fn new(config: EvtLogConfig, clock: Arc<dyn Clock>) -> Result<Self, Error> {
+        debug!(?config, "Creating DynamoEvtLog");
+
+        Ok(Self {
+            client: client(config.endpoint_url).await,
+            table: config.table,
+            poll_interval: Duration::from_millis(config.poll_interval_millis.get()),
+            settle: Duration::from_millis(config.settle_millis),
+            last_seq_nos: Arc::new(Mutex::new(LruCache::new(config.cache_capacity))),
+            clock,
+        })
+    }
+
+    async fn next_seq_no(&self) -> Result<SeqNo, Error> {
+        let output = self
+            .client
+            .update_item()
+            .table_name(&self.table)
+            .key("id", id_value(Uuid::nil()))
+            .key("seq_no", AttributeValue::N(HEAD.to_string()))
+            .update_expression("ADD last_seq_no :one")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await
+            .map_err(aws_sdk_dynamodb::Error::from)?;
+
+        let seq_no = output
+            .attributes()
+            .and_then(|attributes| attributes.get("last_seq_no"))
+            .ok_or(Error::InvalidItem("last_seq_no"))?;
+        Ok(number(seq_no, "last_seq_no")?.try_into()?)
+    }
+
+    fn now_millis(&self) -> u64 {
+        (self.clock.now().unix_timestamp_nanos() / 1_000_000).max(0) as u64
+    }
+
+    async fn evts_by_tag_from(
+        &self,
+        tag: String,
+        from_seq_no: u64,
+    ) -> Result<VecDeque<(u64, Bytes)>, Error> {
+        let output = self
+            .client
+            .query()
+            .table_name(&self.table)
+            .index_name("tag-index")
+            .key_condition_expression("tag = :tag AND seq_no >= :from")
+            .expression_attribute_values(":tag", AttributeValue::S(tag))
+            .expression_attribute_values(":from", AttributeValue::N(from_seq_no.to_string()))
+            .send()
+            .await
+            .map_err(aws_sdk_dynamodb::Error::from)?;
+
+        // Only emit events older than the settle time, in the order of their sequence numbers.
+        let settled = self
+            .now_millis()
+            .saturating_sub(self.settle.as_millis() as u64);
+        let mut evts = VecDeque::new();
+        for item in output.items().unwrap_or_default() {
+            if number(get(item, "created")?, "created")? > settled {
+                break;
+            }
+            evts.push_back(evt(item)?);
+        }
+        Ok(evts)
+    }
+}
+
+impl EvtLog for DynamoEvtLog {
+    type Error = Error;
+
+    async fn persist<'a, E, EvtToBytes, EvtToBytesError>(
+        &'a mut self,
+        id: Uuid,
+        evt: &'a E,
+        tag: Option<String>,
+        evt_to_bytes: &'a EvtToBytes,
+    ) -> Result<SeqNo, Self::Error>
+    where
+        E: Debug + Send + Sync + 'a,
+        EvtToBytes: Fn(&E) -> Result<Bytes, EvtToBytesError> + Send + Sync,
+        EvtToBytesError: StdError + Send + Sync + 'static,
+    {
+        let bytes = evt_to_bytes(evt).map_err(|source| Error::EvtToBytes(Box::new(source)))?;
+
+        let cached_seq_no = self.last_seq_nos.lock().get(&id).copied();
+        let expected_seq_no = match cached_seq_no {
+            Some(seq_no) => Some(seq_no),
+            None => self.last_seq_no(id).await?,
+        };
+        let seq_no = self.next_seq_no().await?;
+
+        let mut item = Item::from([
+            ("id".to_string(), id_value(id)),
+            ("seq_no".to_string(), AttributeValue::N(seq_no.to_string())),
+            (
+                "evt".to_string(),
+                AttributeValue::B(Blob::new(bytes.to_vec())),
+            ),
+            (
+                "created".to_string(),
+                AttributeValue::N(self.now_millis().to_string()),
+            ),
+        ]);
+        if let Some(tag) = tag {
+            item.insert("tag".to_string(), AttributeValue::S(tag));
+        }
+        let put = Put::builder()
+            .table_name(&self.table)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(seq_no)")
+            .build();
+
+        let update = Update::builder()
+            .table_name(&self.table)
+            .key("id", id_value(id))
+            .key("seq_no", AttributeValue::N(HEAD.to_string()))
+            .update_expression("SET last_seq_no = :seq_no")
+            .expression_attribute_values(":seq_no", AttributeValue::N(seq_no.to_string()));
+        let update = match expected_seq_no {
+            Some(expected_seq_no) => update
+                .condition_expression("last_seq_no = :expected")
+                .expression_attribute_values(
+                    ":expected",
+                    AttributeValue::N(expected_seq_no.to_string()),
+                ),
+            None => update.condition_expression("attribute_not_exists(last_seq_no)"),
+        }
+        .build();
+
+        let result = self
+            .client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().put(put).build())
+            .transact_items(TransactWriteItem::builder().update(update).build())
+            .send()
+            .await
+            .map_err(aws_sdk_dynamodb::Error::from);
+
+        match result {
+            Ok(_) => {
+                self.last_seq_nos.lock().put(id, seq_no);
+                Ok(seq_no)
+            }
+
+            Err(error) => {
+                self.last_seq_nos.lock().pop(&id);
+                match error {
+                    aws_sdk_dynamodb::Error::TransactionCanceledException(_) => {
+                        Err(Error::Conflict(id))
+                    }
+                    error => Err(error.into()),
+                }
+            }
+        }
+    }
+
+    async fn last_seq_no(&self, id: Uuid) -> Result<Option<SeqNo>, Self::Error> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("id", id_value(id))
+            .key("seq_no", AttributeValue::N(HEAD.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(aws_sdk_dynamodb::Error::from)?;
+
+        let seq_no = output
+            .item()
+            .map(|item| number(get(item, "last_seq_no")?, "last_seq_no"))
+            .transpose()?
+            .map(SeqNo::try_from)
+            .transpose()?;
+
+        match seq_no {
+            Some(seq_no) => self.last_seq_nos.lock().put(id, seq_no),
+            None => self.last_seq_nos.lock().pop(&id),
+        };
+        Ok(seq_no)
+    }
+
+    async fn evts_by_id<'a, E, EvtFromBytes, EvtFromBytesError>(
+        &'a self,
+        id: Uuid,
+        from_seq_no: SeqNo,
+        evt_from_bytes: EvtFromBytes,
+    ) -> Result<impl Stream<Item = Result<(SeqNo, E), Self::Error>> + Send, Self::Error>
+    where
+        E: Debug + Send + 'a,
+        EvtFromBytes: Fn(Bytes) -> Result<E, EvtFromBytesError> + Copy + Send + Sync + 'static,
+        EvtFromBytesError: StdError + Send + Sync + 'static,
+    {
+        debug!(%id, %from_seq_no, "Building events by ID stream");
+
+        let mut evts = vec![];
+        let mut start_key = None;
+        loop {
+            let output = self
+                .client
+                .query()
+                .table_name(&self.table)
+                .key_condition_expression("id = :id AND seq_no >= :from")
+                .expression_attribute_values(":id", id_value(id))
+                .expression_attribute_values(":from", AttributeValue::N(from_seq_no.to_string()))
+                .consistent_read(true)
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(aws_sdk_dynamodb::Error::from)?;
+
+            for item in output.items().unwrap_or_default() {
+                evts.push(evt(item)?);
+            }
+
+            start_key = output.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        let evts = evts
+            .into_iter()
+            .map(move |(seq_no, evt)| decode(seq_no, evt, evt_from_bytes));
+        Ok(stream::iter(evts))
+    }
+
+    async fn evts_by_tag<'a, E, T, EvtFromBytes, EvtFromBytesError>(
+        &'a self,
+        tag: T,
+        from_seq_no: SeqNo,
+        evt_from_bytes: EvtFromBytes,
+    ) -> Result<impl Stream<Item = Result<(SeqNo, E), Self::Error>> + Send, Self::Error>
+    where
+        E: Debug + Send + 'a,
+        EvtFromBytes: Fn(Bytes) -> Result<E, EvtFromBytesError> + Copy + Send + Sync + 'static,
+        EvtFromBytesError: StdError + Send + Sync + 'static,
+        T: Into<String> + Send,
+    {
+        let tag = tag.into();
+        debug!(tag, %from_seq_no, "Building events by tag stream");
+
+        let state = TagQuery {
+            evt_log: self.clone(),
+            tag,
+            next_seq_no: from_seq_no.as_u64(),
+            evts: VecDeque::new(),
+        };
+        let evts = stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some((seq_no, evt)) = state.evts.pop_front() {
+                    state.next_seq_no = seq_no + 1;
+                    return Some((decode(seq_no, evt, evt_from_bytes), state));
+                }
+
+                match state
+                    .evt_log
+                    .evts_by_tag_from(state.tag.clone(), state.next_seq_no)
+                    .await
+                {
+                    Ok(evts) if evts.is_empty() => sleep(state.evt_log.poll_interval).await,
+                    Ok(evts) => state.evts = evts,
+                    Err(error) => return Some((Err(error), state)),
+                }
+            }
+        });
+
+        Ok(evts)
+    }
+}
+
+/// State of a polling events-by-tag query.
+struct TagQuery {
+    evt_log: DynamoEvtLog,
+    tag: String,
+    next_seq_no: u64,
+    evts: VecDeque<(u64, Bytes)>,
+}
+
+/// [SnapshotStore] based upon DynamoDB. Snapshots are limited by the DynamoDB item size of 400KB.
+/// The table is expected to exist with partition key `id` (binary).
+#[derive(Debug, Clone)]
+pub struct DynamoSnapshotStore {
+    client: Client,
+    table: String,
+}
+
+impl DynamoSnapshotStore {
+    #[allow(missing_docs)]
+    pub async fn new(config: SnapshotStoreConfig) -> Result<Self, Error> {
+        debug!(?config, "Creating DynamoSnapshotStore");
+
+        Ok(Self {
+            client: client(config.endpoint_url).await,
+            table: config.table,
+        })
+    }
+}
+
+impl SnapshotStore for DynamoSnapshotStore {
+    type Error = Error;
+
+    async fn save<'a, S, StateToBytes, StateToBytesError>(
+        &'a mut self,
+        id: Uuid,
+        seq_no: SeqNo,
+        state: S,
+        state_to_bytes: &'a StateToBytes,
+    ) -> Result<(), Self::Error>
+    where
+        S: Send + Sync + 'a,
+        StateToBytes: Fn(&S) -> Result<Bytes, StateToBytesError> + Send + Sync + 'static,
+        StateToBytesError: StdError + Send + Sync + 'static,
+    {
+        let bytes =
+            state_to_bytes(&state).map_err(|source| Error::StateToBytes(Box::new(source)))?;
+
+        // Never replace a snapshot with an older one.
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table)
+            .item("id", id_value(id))
+            .item("seq_no", AttributeValue::N(seq_no.to_string()))
+            .item("state", AttributeValue::B(Blob::new(bytes.to_vec())))
+            .condition_expression("attribute_not_exists(seq_no) OR seq_no < :seq_no")
+            .expression_attribute_values(":seq_no", AttributeValue::N(seq_no.to_string()))
+            .send()
+            .await
+            .map_err(aws_sdk_dynamodb::Error::from);
+
+        match result {
+            Ok(_) | Err(aws_sdk_dynamodb::Error::ConditionalCheckFailedException(_)) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn load<'a, S, StateFromBytes, StateFromBytesError>(
+        &'a self,
+        id: Uuid,
+        state_from_bytes: StateFromBytes,
+    ) -> Result<Option<Snapshot<S>>, Self::Error>
+    where
+        S: 'a,
+        StateFromBytes: Fn(Bytes) -> Result<S, StateFromBytesError> + Copy + Send + Sync + 'static,
+        StateFromBytesError: StdError + Send + Sync + 'static,
+    {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("id", id_value(id))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(aws_sdk_dynamodb::Error::from)?;
+
+        output
+            .item()
+            .map(|item| {
+                let seq_no = number(get(item, "seq_no")?, "seq_no")?.try_into()?;
+                let state = blob(get(item, "state")?, "state")?;
+                let state = state_from_bytes(state)
+                    .map_err(|source| Error::StateFromBytes(Box::new(source)))?;
+                Ok(Snapshot::new(seq_no, state))
+            })
+            .transpose()
+    }
+}
+
+/// Configuration for the [DynamoEvtLog].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EvtLogConfig {
+    table: String,
+    /// Overrides the endpoint resolved from the environment, e.g. for DynamoDB local.
+    endpoint_url: Option<String>,
+    poll_interval_millis: NonZeroU64,
+    settle_millis: u64,
+    /// Capacity of the cache for the last sequence numbers of the entities.
+    cache_capacity: NonZeroUsize,
+}
+
+/// Configuration for the [DynamoSnapshotStore].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnapshotStoreConfig {
+    table: String,
+    /// Overrides the endpoint resolved from the environment, e.g. for DynamoDB local.
+    endpoint_url: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("DynamoDB error")]
+    Dynamo(#[from] aws_sdk_dynamodb::Error),
+
+    #[error("Concurrent write for entity {0}")]
+    Conflict(Uuid),
+
+    #[error("Missing or invalid attribute {0}")]
+    InvalidItem(&'static str),
+
+    #[error("Invalid sequence number")]
+    InvalidSeqNo(#[from] TrySeqNoFromZero),
+
+    #[error("Cannot convert event to bytes")]
+    EvtToBytes(#[source] Box<dyn StdError + Send + Sync>),
+
+    #[error("Cannot convert bytes to event")]
+    EvtFromBytes(#[source] Box<dyn StdError + Send + Sync>),
+
+    #[error("Cannot convert snapshot state to bytes")]
+    StateToBytes(#[source] Box<dyn StdError + Send + Sync>),
+
+    #[error("Cannot convert bytes to snapshot state")]
+    StateFromBytes(#[source] Box<dyn StdError + Send + Sync>),
+}
+
+/// Create a client configured from the environment, e.g. via `AWS_REGION` or an ECS task role.
+async fn client(endpoint_url: Option<String>) -> Client {
+    let sdk_config = aws_config::load_from_env().await;
+    let config = aws_sdk_dynamodb::config::Builder::from(&sdk_config)
+        .set_endpoint_url(endpoint_url)
+        .build();
+    Client::from_conf(config)
+}
+
+fn id_value(id: Uuid) -> AttributeValue {
+    AttributeValue::B(Blob::new(id.as_bytes().to_vec()))
+}
+
+fn get<'a>(item: &'a Item, name: &'static str) -> Result<&'a AttributeValue, Error> {
+    item.get(name).ok_or(Error::InvalidItem(name))
+}
+
+fn number(value: &AttributeValue, name: &'static str) -> Result<u64, Error> {
+    value
+        .as_n()
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .ok_or(Error::InvalidItem(name))
+}
+
+fn blob(value: &AttributeValue, name: &'static str) -> Result<Bytes, Error> {
+    value
+        .as_b()
+        .map(|b| Bytes::copy_from_slice(b.as_ref()))
+        .map_err(|_| Error::InvalidItem(name))
+}
+
+fn evt(item: &Item) -> Result<(u64, Bytes), Error> {
+    let seq_no = number(get(item, "seq_no")?, "seq_no")?;
+    let evt = blob(get(item, "evt")?, "evt")?;
+    Ok((seq_no, evt))
+}
+
+fn decode<E, EvtFromBytes, EvtFromBytesError>(
+    seq_no: u64,
+    evt: Bytes,
+    evt_from_bytes: EvtFromBytes,
+) -> Result<(SeqNo, E), Error>
+where
+    EvtFromBytes: Fn(Bytes) -> Result<E, EvtFromBytesError>,
+    EvtFromBytesError: StdError + Send + Sync + 'static,
+{
+    let seq_no = seq_no.try_into()?;
+    let evt = evt_from_bytes(evt).map_err(|source| Error::EvtFromBytes(Box::new(source)))?;
+    Ok((seq_no, evt))
+}
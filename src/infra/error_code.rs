@@ -0,0 +1,290 @@
+use crate::{
+    domain::{account, books, consent, dispute, loan, mandate, term_deposit, transfer},
+    infra::lru_cache_factory,
+};
+use eventsourced::EntityRefError;
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+
+/// Stable, crate-wide error codes, used in problem+json responses, logs and metrics labels. Once
+/// released, codes must not be renamed, because clients and alerting rules depend on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    /// The balance is insufficient, e.g. for a withdrawal.
+    InsufficientBalance,
+
+    /// An amount exceeds a limit, e.g. a captured amount exceeding the held one.
+    LimitExceeded,
+
+    /// A deposit would exceed the maximum balance of an account, e.g. as required for an e-money
+    /// licence.
+    MaxBalanceExceeded,
+
+    /// The entity, e.g. an account, has not been created yet.
+    NotYetCreated,
+
+    /// The entity, e.g. an account, has already been created.
+    AlreadyCreated,
+
+    /// A referenced item of an entity, e.g. a hold of an account, is unknown.
+    UnknownReference,
+
+    /// The entity or a referenced item is not in a state allowing the command.
+    InvalidState,
+
+    /// The request is invalid, e.g. a zero amount or a malformed header.
+    InvalidRequest,
+
+    /// The external reference of an account to be created is already used by another account.
+    ExternalRefInUse,
+
+    /// A quota, e.g. the maximum number of accounts per tenant, has been reached.
+    QuotaExceeded,
+
+    /// The entity, e.g. an account, has already been created with other parameters than the
+    /// requested ones, hence creating it is not idempotent.
+    ParametersMismatch,
+
+    /// The requested resource does not exist.
+    NotFound,
+
+    /// The caller has exceeded its rate limit.
+    RateLimited,
+
+    /// An entity factory is overloaded, i.e. its request queue is full; retrying later may
+    /// succeed.
+    Overloaded,
+
+    /// An entity cannot be spawned or reached, e.g. because the event log is unavailable.
+    EntityUnavailable,
+
+    /// An entity has terminated, e.g. because of a conflicting append.
+    EntityTerminated,
+
+    /// Any other infrastructure failure.
+    Internal,
+}
+
+impl ErrorCode {
+    /// The stable code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InsufficientBalance => "insufficient-balance",
+            ErrorCode::LimitExceeded => "limit-exceeded",
+            ErrorCode::MaxBalanceExceeded => "max-balance-exceeded",
+            ErrorCode::NotYetCreated => "not-yet-created",
+            ErrorCode::AlreadyCreated => "already-created",
+            ErrorCode::UnknownReference => "unknown-reference",
+            ErrorCode::InvalidState => "invalid-state",
+            ErrorCode::InvalidRequest => "invalid-request",
+            ErrorCode::ExternalRefInUse => "external-ref-in-use",
+            ErrorCode::QuotaExceeded => "quota-exceeded",
+            ErrorCode::ParametersMismatch => "parameters-mismatch",
+            ErrorCode::NotFound => "not-found",
+            ErrorCode::RateLimited => "rate-limited",
+            ErrorCode::Overloaded => "overloaded",
+            ErrorCode::EntityUnavailable => "entity-unavailable",
+            ErrorCode::EntityTerminated => "entity-terminated",
+            ErrorCode::Internal => "internal",
+        }
+    }
+
+    /// The [ErrorClass], e.g. to only alert on infrastructure failures.
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            ErrorCode::Overloaded
+            | ErrorCode::EntityUnavailable
+            | ErrorCode::EntityTerminated
+            | ErrorCode::Internal => ErrorClass::Infra,
+            _ => ErrorClass::Client,
+        }
+    }
+
+    /// The error code for the given infrastructure failure, determined from the first known error
+    /// in its chain of causes.
+    pub fn of(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .find_map(|error| {
+                if let Some(error) = error.downcast_ref::<lru_cache_factory::Error>() {
+                    match error {
+                        lru_cache_factory::Error::Overloaded => Some(ErrorCode::Overloaded),
+                        _ => Some(ErrorCode::EntityUnavailable),
+                    }
+                } else if error.is::<EntityRefError>() {
+                    Some(ErrorCode::EntityTerminated)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorCode::Internal)
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Classification of [ErrorCode]s: caused by the client, e.g. an insufficient balance, or by the
+/// infrastructure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Client,
+    Infra,
+}
+
+impl ErrorClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::Client => "client",
+            ErrorClass::Infra => "infra",
+        }
+    }
+}
+
+impl From<&account::Error> for ErrorCode {
+    fn from(error: &account::Error) -> Self {
+        match error {
+            account::Error::InvalidWithdraw { .. } => ErrorCode::InsufficientBalance,
+            account::Error::MaxBalanceExceeded { .. } => ErrorCode::MaxBalanceExceeded,
+            account::Error::NotYetCreated => ErrorCode::NotYetCreated,
+            account::Error::AlreadyCreated => ErrorCode::AlreadyCreated,
+            account::Error::HoldAlreadyPlaced(_) => ErrorCode::InvalidState,
+            account::Error::UnknownHold(_) => ErrorCode::UnknownReference,
+            account::Error::InvalidCapture { .. } => ErrorCode::LimitExceeded,
+            account::Error::ZeroAdjustment
+            | account::Error::MissingJustification
+            | account::Error::SweepToSelf => ErrorCode::InvalidRequest,
+            account::Error::Closed
+            | account::Error::ActiveHolds(_)
+            | account::Error::NonZeroBalance(_)
+            | account::Error::NotClosed
+            | account::Error::ReopenWindowExpired { .. }
+            | account::Error::Purged
+            | account::Error::RetentionNotExpired { .. } => ErrorCode::InvalidState,
+        }
+    }
+}
+
+impl From<&books::Error> for ErrorCode {
+    fn from(_error: &books::Error) -> Self {
+        ErrorCode::InvalidRequest
+    }
+}
+
+impl From<&consent::Error> for ErrorCode {
+    fn from(error: &consent::Error) -> Self {
+        match error {
+            consent::Error::ExpiryNotInFuture(_) => ErrorCode::InvalidRequest,
+            consent::Error::NotYetGranted => ErrorCode::NotYetCreated,
+            consent::Error::AlreadyGranted => ErrorCode::AlreadyCreated,
+            consent::Error::AlreadyRevoked => ErrorCode::InvalidState,
+        }
+    }
+}
+
+impl From<&loan::Error> for ErrorCode {
+    fn from(error: &loan::Error) -> Self {
+        match error {
+            loan::Error::NotYetCreated => ErrorCode::NotYetCreated,
+            loan::Error::AlreadyCreated => ErrorCode::AlreadyCreated,
+            loan::Error::ZeroPrincipal
+            | loan::Error::NoInstallments
+            | loan::Error::AccountMismatch(_) => ErrorCode::InvalidRequest,
+            loan::Error::AlreadyDisbursed
+            | loan::Error::NotYetDisbursed
+            | loan::Error::InvalidInstallmentStatus(_) => ErrorCode::InvalidState,
+            loan::Error::UnknownInstallment(_) => ErrorCode::UnknownReference,
+        }
+    }
+}
+
+impl From<&mandate::Error> for ErrorCode {
+    fn from(error: &mandate::Error) -> Self {
+        match error {
+            mandate::Error::NotYetCreated => ErrorCode::NotYetCreated,
+            mandate::Error::AlreadyCreated => ErrorCode::AlreadyCreated,
+            mandate::Error::MaxAmountExceeded { .. } => ErrorCode::LimitExceeded,
+            mandate::Error::UnknownCollection(_) => ErrorCode::UnknownReference,
+            mandate::Error::Cancelled
+            | mandate::Error::CollectionAlreadyScheduled(_)
+            | mandate::Error::InvalidCollectionStatus(_)
+            | mandate::Error::RefundPeriodExpired(_) => ErrorCode::InvalidState,
+        }
+    }
+}
+
+impl From<&term_deposit::Error> for ErrorCode {
+    fn from(error: &term_deposit::Error) -> Self {
+        match error {
+            term_deposit::Error::NotYetOpened => ErrorCode::NotYetCreated,
+            term_deposit::Error::AlreadyOpened => ErrorCode::AlreadyCreated,
+            term_deposit::Error::ZeroAmount | term_deposit::Error::ZeroTerm => {
+                ErrorCode::InvalidRequest
+            }
+            term_deposit::Error::NotYetMatured
+            | term_deposit::Error::MaturityReached
+            | term_deposit::Error::Closed => ErrorCode::InvalidState,
+        }
+    }
+}
+
+impl From<&dispute::Error> for ErrorCode {
+    fn from(error: &dispute::Error) -> Self {
+        match error {
+            dispute::Error::NotYetOpened => ErrorCode::NotYetCreated,
+            dispute::Error::AlreadyOpened => ErrorCode::AlreadyCreated,
+            dispute::Error::ZeroAmount | dispute::Error::ZeroDeadline => ErrorCode::InvalidRequest,
+            dispute::Error::AlreadyCredited
+            | dispute::Error::DeadlineNotReached
+            | dispute::Error::Resolved => ErrorCode::InvalidState,
+        }
+    }
+}
+
+impl From<&transfer::Error> for ErrorCode {
+    fn from(error: &transfer::Error) -> Self {
+        match error {
+            transfer::Error::NotYetInitiated => ErrorCode::NotYetCreated,
+            transfer::Error::AlreadyInitiated => ErrorCode::AlreadyCreated,
+            transfer::Error::ZeroAmount | transfer::Error::SameAccount => ErrorCode::InvalidRequest,
+            transfer::Error::InvalidStatus => ErrorCode::InvalidState,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_code_of() {
+        let error = anyhow::Error::from(lru_cache_factory::Error::Send).context("Cannot deposit");
+        assert_eq!(ErrorCode::of(&error), ErrorCode::EntityUnavailable);
+        assert_eq!(ErrorCode::of(&error).class(), ErrorClass::Infra);
+
+        let error = anyhow::Error::from(lru_cache_factory::Error::Overloaded)
+            .context("Cannot get Account entity");
+        assert_eq!(ErrorCode::of(&error), ErrorCode::Overloaded);
+
+        let error = Err::<(), _>(anyhow::anyhow!("boom"))
+            .context("Cannot deposit")
+            .unwrap_err();
+        assert_eq!(ErrorCode::of(&error), ErrorCode::Internal);
+    }
+
+    #[test]
+    fn test_error_code_from_account_error() {
+        let error = account::Error::InvalidWithdraw {
+            balance: 1.into(),
+            withdraw_amount: 2.into(),
+        };
+        let code = ErrorCode::from(&error);
+        assert_eq!(code.as_str(), "insufficient-balance");
+        assert_eq!(code.class(), ErrorClass::Client);
+    }
+}
@@ -0,0 +1,106 @@
+use super::{GlBatch, GlExporter, GlSide};
+use crate::{
+    domain::clock::Clock,
+    infra::{
+        delivery::{self, FileDrop},
+        reporting::decimal,
+    },
+};
+use std::{fmt::Write, sync::Arc};
+
+/// [GlExporter] delivering each [GlBatch] as CSV file via a [FileDrop], e.g. for collection via
+/// SFTP by the GL system. The file name contains the batch ID, such that redelivered batches can be
+/// detected by the importer.
+#[derive(Debug, Clone)]
+pub struct CsvGlExporter {
+    file_drop: FileDrop,
+}
+
+impl CsvGlExporter {
+    #[allow(missing_docs)]
+    pub fn new(config: delivery::Config, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            file_drop: FileDrop::new(config, clock),
+        }
+    }
+}
+
+impl GlExporter for CsvGlExporter {
+    async fn export(&self, batch: GlBatch) -> anyhow::Result<()> {
+        let file_name = format!("gl-{}-{}.csv", batch.to.date(), batch.id);
+        self.file_drop
+            .deliver(batch.id, file_name, render_csv(&batch));
+        Ok(())
+    }
+}
+
+fn render_csv(batch: &GlBatch) -> String {
+    let mut csv =
+        "batch_id,transaction_id,account_id,kind,gl_account,debit,credit,booked_at,value_date\n"
+            .to_string();
+    for posting in &batch.postings {
+        let amount = decimal(posting.amount);
+        let (debit, credit) = match posting.side {
+            GlSide::Debit => (amount.as_str(), ""),
+            GlSide::Credit => ("", amount.as_str()),
+        };
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{}",
+            batch.id,
+            posting.transaction_id,
+            posting.account_id,
+            posting.kind.as_str(),
+            posting.gl_account,
+            debit,
+            credit,
+            posting.booked_at.date(),
+            posting.value_date.date()
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::{gl_export::GlPosting, ledger::EntryKind};
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_render_csv() {
+        let id = Uuid::nil();
+        let posting = |gl_account: &str, side| GlPosting {
+            transaction_id: id,
+            account_id: id,
+            kind: EntryKind::Deposit,
+            gl_account: gl_account.to_string(),
+            side,
+            amount: 1_005u64.into(),
+            booked_at: OffsetDateTime::UNIX_EPOCH,
+            value_date: OffsetDateTime::UNIX_EPOCH,
+        };
+        let batch = GlBatch {
+            id,
+            from: None,
+            to: OffsetDateTime::UNIX_EPOCH,
+            postings: vec![
+                posting("1000", GlSide::Debit),
+                posting("2000", GlSide::Credit),
+            ],
+        };
+
+        let csv = render_csv(&batch);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            format!("{id},{id},{id},deposit,1000,10.05,,1970-01-01,1970-01-01")
+        );
+        assert_eq!(
+            lines[2],
+            format!("{id},{id},{id},deposit,2000,,10.05,1970-01-01,1970-01-01")
+        );
+    }
+}
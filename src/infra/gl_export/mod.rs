@@ -0,0 +1,264 @@
+pub mod csv_gl_exporter;
+
+use crate::{
+    domain::{clock::Clock, euro_cent::EuroCent, id::IdGenerator},
+    infra::{
+        delivery,
+        ledger::{EntryKind, LedgerEntry},
+    },
+};
+use csv_gl_exporter::CsvGlExporter;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::{future::Future, sync::Arc};
+use time::OffsetDateTime;
+use tracing::info;
+use uuid::Uuid;
+
+/// Posts the activity of closed periods into an external general ledger (GL), e.g. NetSuite or
+/// SAP, by file transfer or via their SOAP or REST APIs.
+pub trait GlExporter: Clone + Send + Sync + 'static {
+    /// Post the given [GlBatch]. Exporters must be idempotent per batch ID, as a batch might be
+    /// posted more than once.
+    fn export(&self, batch: GlBatch) -> impl Future<Output = anyhow::Result<()>> + Send + '_;
+}
+
+/// Configuration for the [GlExport].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    accounts: GlAccounts,
+
+    /// Delivery of the batches as CSV files, e.g. into the root of an SFTP server.
+    delivery: delivery::Config,
+}
+
+/// Posts the ledger entries of closed periods to the GL accounts via a [GlExporter].
+#[derive(Debug, Clone)]
+pub struct GlExport<E> {
+    accounts: Arc<GlAccounts>,
+    exporter: E,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl GlExport<CsvGlExporter> {
+    /// Create a [GlExport] delivering CSV files as configured.
+    pub fn csv(config: Config, clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self::new(
+            config.accounts,
+            CsvGlExporter::new(config.delivery, clock),
+            ids,
+        )
+    }
+}
+
+impl<E> GlExport<E>
+where
+    E: GlExporter,
+{
+    #[allow(missing_docs)]
+    pub fn new(accounts: GlAccounts, exporter: E, ids: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            accounts: Arc::new(accounts),
+            exporter,
+            ids,
+        }
+    }
+
+    /// Post the given ledger entries of the period with the given bounds as [GlBatch].
+    pub async fn export(
+        &self,
+        from: Option<OffsetDateTime>,
+        to: OffsetDateTime,
+        entries: &[LedgerEntry],
+    ) -> anyhow::Result<GlBatch> {
+        let batch = GlBatch::new(self.ids.next_id(), from, to, entries, &self.accounts);
+        let result = self.exporter.export(batch.clone()).await;
+        let status = if result.is_ok() { "exported" } else { "failed" };
+        counter!("gl_exports_total", 1, "status" => status);
+        result?;
+
+        info!(id = %batch.id, ?from, %to, postings = batch.postings.len(), "Exported GL batch");
+        Ok(batch)
+    }
+}
+
+/// Numbers of the GL accounts the ledger entries are posted to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GlAccounts {
+    /// Liability for the balances of customer accounts.
+    customer_deposits: String,
+
+    /// Clearing account for cash movements, i.e. deposits, withdrawals and sweeps.
+    cash_clearing: String,
+
+    /// Clearing account for card payments, settled with the card processor.
+    card_settlement: String,
+
+    /// Income or expense for manual adjustments.
+    adjustments: String,
+}
+
+/// The double-entry postings of all ledger entries booked within a period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GlBatch {
+    pub id: Uuid,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    pub postings: Vec<GlPosting>,
+}
+
+impl GlBatch {
+    /// Create a [GlBatch] for the period with the given bounds from the given ledger entries,
+    /// posting each to the given [GlAccounts] as one debit and one credit, such that the batch is
+    /// balanced. Entries of sandbox accounts are skipped.
+    pub fn new(
+        id: Uuid,
+        from: Option<OffsetDateTime>,
+        to: OffsetDateTime,
+        entries: &[LedgerEntry],
+        accounts: &GlAccounts,
+    ) -> Self {
+        let postings = entries
+            .iter()
+            .filter(|entry| !entry.sandbox)
+            .flat_map(|entry| {
+                let counter_account = match entry.kind {
+                    EntryKind::OpeningBalance
+                    | EntryKind::Deposit
+                    | EntryKind::Withdrawal
+                    | EntryKind::Sweep => &accounts.cash_clearing,
+                    EntryKind::CardPayment => &accounts.card_settlement,
+                    EntryKind::AdjustmentCredit | EntryKind::AdjustmentDebit => {
+                        &accounts.adjustments
+                    }
+                };
+                // Customer balances are liabilities: decreasing these is a debit.
+                let (debit_account, credit_account) = if entry.kind.is_debit() {
+                    (&accounts.customer_deposits, counter_account)
+                } else {
+                    (counter_account, &accounts.customer_deposits)
+                };
+                [
+                    GlPosting::new(entry, debit_account, GlSide::Debit),
+                    GlPosting::new(entry, credit_account, GlSide::Credit),
+                ]
+            })
+            .collect();
+
+        Self {
+            id,
+            from,
+            to,
+            postings,
+        }
+    }
+
+    /// The total of the postings on the given side.
+    pub fn total(&self, side: GlSide) -> EuroCent {
+        self.postings
+            .iter()
+            .filter(|posting| posting.side == side)
+            .fold(EuroCent::default(), |total, posting| total + posting.amount)
+    }
+}
+
+/// A single debit or credit of a GL account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GlPosting {
+    pub transaction_id: Uuid,
+    pub account_id: Uuid,
+    pub kind: EntryKind,
+    pub gl_account: String,
+    pub side: GlSide,
+    pub amount: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub booked_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub value_date: OffsetDateTime,
+}
+
+impl GlPosting {
+    fn new(entry: &LedgerEntry, gl_account: &str, side: GlSide) -> Self {
+        Self {
+            transaction_id: entry.id,
+            account_id: entry.account_id,
+            kind: entry.kind,
+            gl_account: gl_account.to_string(),
+            side,
+            amount: entry.amount,
+            booked_at: entry.booked_at,
+            value_date: entry.value_date,
+        }
+    }
+}
+
+/// Side of a [GlPosting].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GlSide {
+    Debit,
+    Credit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gl_batch() {
+        let accounts = GlAccounts {
+            customer_deposits: "2000".to_string(),
+            cash_clearing: "1000".to_string(),
+            card_settlement: "1100".to_string(),
+            adjustments: "6000".to_string(),
+        };
+        let entry = |kind, amount: u64| LedgerEntry {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            tenant: None,
+            sandbox: false,
+            kind,
+            amount: amount.into(),
+            balance: 0u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+            value_date: OffsetDateTime::UNIX_EPOCH,
+            booked_at: OffsetDateTime::UNIX_EPOCH,
+        };
+        let entries = vec![
+            entry(EntryKind::Deposit, 1_000),
+            entry(EntryKind::CardPayment, 250),
+            LedgerEntry {
+                sandbox: true,
+                ..entry(EntryKind::Withdrawal, 500)
+            },
+        ];
+
+        let batch = GlBatch::new(
+            Uuid::now_v7(),
+            None,
+            OffsetDateTime::UNIX_EPOCH,
+            &entries,
+            &accounts,
+        );
+        let postings = batch
+            .postings
+            .iter()
+            .map(|posting| (posting.gl_account.as_str(), posting.side))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            postings,
+            vec![
+                ("1000", GlSide::Debit),
+                ("2000", GlSide::Credit),
+                ("2000", GlSide::Debit),
+                ("1100", GlSide::Credit),
+            ]
+        );
+        assert_eq!(batch.total(GlSide::Debit), 1_250u64.into());
+        assert_eq!(batch.total(GlSide::Debit), batch.total(GlSide::Credit));
+    }
+}
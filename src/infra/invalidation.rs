@@ -0,0 +1,121 @@
+use crate::{
+    domain::id::IdGenerator,
+    infra::lru_cache_factory::{LruCacheEntityFactory, ManagedEntity},
+};
+use anyhow::{Context, Result};
+use async_nats::Client;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::{sync::broadcast::error::RecvError, task};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Configuration for [Invalidation].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    server_addr: String,
+
+    /// Prefix of the subjects, followed by the entity type, e.g. `invalidations.account`.
+    subject_prefix: String,
+}
+
+/// Broadcasts mutations of entities to the other instances via NATS, such that they drop their
+/// cached entities, which would otherwise be stale until a conflicting append terminates them.
+/// Notifications are best effort, i.e. lost ones only widen the window of staleness.
+#[derive(Debug, Clone)]
+pub struct Invalidation {
+    client: Client,
+    subject_prefix: String,
+    instance_id: Uuid,
+}
+
+/// Notification about a mutated entity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Mutation {
+    instance_id: Uuid,
+    id: Uuid,
+}
+
+impl Invalidation {
+    /// Connect to NATS; the ID of this instance is taken from the given [IdGenerator].
+    pub async fn connect(config: Config, ids: Arc<dyn IdGenerator>) -> Result<Self> {
+        let client = async_nats::connect(&config.server_addr)
+            .await
+            .context("Cannot connect to NATS for invalidation")?;
+        Ok(Self {
+            client,
+            subject_prefix: config.subject_prefix,
+            instance_id: ids.next_id(),
+        })
+    }
+
+    /// Spawn publishing the mutations of the entities of the given factory and invalidating its
+    /// cached entities mutated by other instances.
+    pub async fn spawn<E>(&self, factory: LruCacheEntityFactory<E>) -> Result<()>
+    where
+        E: ManagedEntity,
+    {
+        let entity_type = E::ENTITY_TYPE.as_str();
+        let subject = format!("{}.{entity_type}", self.subject_prefix);
+        let mut subscriber = self
+            .client
+            .subscribe(subject.clone())
+            .await
+            .with_context(|| format!("Cannot subscribe to {subject}"))?;
+
+        // Publish local mutations.
+        let mut mutations = factory.mutations();
+        let client = self.client.clone();
+        let instance_id = self.instance_id;
+        let publish_subject = subject.clone();
+        task::spawn(async move {
+            loop {
+                let id = match mutations.recv().await {
+                    Ok(id) => id,
+                    Err(RecvError::Lagged(n)) => {
+                        warn!(entity_type, n, "Missed mutations to publish");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let mutation = Mutation { instance_id, id };
+                let payload = serde_json::to_vec(&mutation).expect("mutation can be serialized");
+                if let Err(error) = client
+                    .publish(publish_subject.clone(), payload.into())
+                    .await
+                {
+                    warn!(entity_type, %id, %error, "Cannot publish mutation");
+                }
+            }
+        });
+
+        // Invalidate entities mutated by other instances.
+        task::spawn(async move {
+            while let Some(message) = subscriber.next().await {
+                match serde_json::from_slice::<Mutation>(&message.payload) {
+                    Ok(Mutation {
+                        instance_id: other,
+                        id,
+                    }) if other != instance_id => {
+                        debug!(entity_type, %id, "Invalidating entity mutated elsewhere");
+                        metrics::counter!(
+                            "entity_invalidations_total",
+                            1,
+                            "entity_type" => entity_type
+                        );
+                        factory.invalidate(id);
+                    }
+
+                    Ok(_) => {}
+
+                    Err(error) => warn!(entity_type, %error, "Cannot deserialize mutation"),
+                }
+            }
+            warn!(subject, "Invalidation subscription ended");
+        });
+
+        Ok(())
+    }
+}
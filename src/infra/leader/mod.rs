@@ -0,0 +1,53 @@
+#[cfg(feature = "nats")]
+pub mod nats_kv_leader_election;
+
+use tokio::{select, sync::watch, task};
+
+/// Leadership of this node among multiple instances, such that singleton tasks, e.g. projections
+/// with side effects, run on exactly one node. Leadership might change over time. Notice that
+/// in-memory read models like the account summaries projection must run on every node.
+#[derive(Debug, Clone)]
+pub struct Leadership(watch::Receiver<bool>);
+
+impl Leadership {
+    /// Leadership for a single instance which always is the leader.
+    pub fn standalone() -> Self {
+        let (_, leader) = watch::channel(true);
+        Self::new(leader)
+    }
+
+    #[allow(missing_docs)]
+    pub fn new(leader: watch::Receiver<bool>) -> Self {
+        Self(leader)
+    }
+
+    /// Is this node currently the leader?
+    pub fn is_leader(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Leadership only while the given condition holds as well, e.g. for a standby only once it
+    /// has been promoted.
+    pub fn and(self, mut condition: watch::Receiver<bool>) -> Self {
+        let Self(mut leader) = self;
+        let (sdr, rcv) = watch::channel(*leader.borrow() && *condition.borrow());
+
+        task::spawn(async move {
+            // Closed channels keep their last value, e.g. for standalone leadership.
+            let mut leader_open = true;
+            let mut condition_open = true;
+            loop {
+                select! {
+                    result = leader.changed(), if leader_open => leader_open = result.is_ok(),
+                    result = condition.changed(), if condition_open => {
+                        condition_open = result.is_ok()
+                    }
+                    else => break,
+                }
+                sdr.send_replace(*leader.borrow() && *condition.borrow());
+            }
+        });
+
+        Self(rcv)
+    }
+}
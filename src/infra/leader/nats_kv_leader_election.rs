@@ -0,0 +1,81 @@
+use super::Leadership;
+use anyhow::{anyhow, Context, Result};
+use async_nats::{
+    jetstream::{self, kv},
+    Event,
+};
+use serde::Deserialize;
+use std::{num::NonZeroU64, time::Duration};
+use tokio::{sync::watch, task, time};
+use tracing::{debug, info, warn};
+
+/// Elect a leader via a lease in a NATS key-value bucket: the node which manages to create the
+/// lease key becomes the leader and keeps renewing the lease; if it fails to do so, the key expires
+/// after the lease duration and another node takes over.
+pub async fn spawn(config: Config) -> Result<Leadership> {
+    let client = async_nats::ConnectOptions::new()
+        .event_callback(|event| async move {
+            match event {
+                Event::Connected => {
+                    info!("Reconnected to NATS");
+                    metrics::counter!("nats_reconnects_total", 1);
+                }
+                Event::Disconnected => warn!("Disconnected from NATS"),
+                _ => {}
+            }
+        })
+        .connect(&config.server_addr)
+        .await
+        .context("Cannot connect to NATS")?;
+    let lease = Duration::from_secs(config.lease_secs.get());
+    let bucket = jetstream::new(client)
+        .create_key_value(kv::Config {
+            bucket: config.bucket.clone(),
+            history: 1,
+            max_age: lease,
+            ..Default::default()
+        })
+        .await
+        .map_err(|error| anyhow!(error))
+        .context("Cannot create key-value bucket for leader election")?;
+
+    let (leader_sdr, leader_rcv) = watch::channel(false);
+    task::spawn(async move {
+        let mut revision = None;
+        let mut interval = time::interval(lease / 3);
+        loop {
+            interval.tick().await;
+
+            let value = config.node_id.clone().into();
+            let result = match revision {
+                Some(revision) => bucket.update(&config.key, value, revision).await,
+                None => bucket.create(&config.key, value).await,
+            };
+            revision = result
+                .inspect_err(|error| debug!(%error, "Cannot acquire or renew lease"))
+                .ok();
+
+            let leader = revision.is_some();
+            if leader_sdr.send_replace(leader) != leader {
+                if leader {
+                    info!(node_id = config.node_id, "Became leader");
+                } else {
+                    warn!(node_id = config.node_id, "Lost leadership");
+                }
+            }
+        }
+    });
+
+    Ok(Leadership::new(leader_rcv))
+}
+
+/// Configuration for leader election via NATS key-value.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    server_addr: String,
+    bucket: String,
+    key: String,
+    node_id: String,
+    lease_secs: NonZeroU64,
+}
@@ -0,0 +1,367 @@
+use super::{EntryKind, LedgerEntry, LedgerProjection};
+use crate::{
+    domain::{
+        account::{self, AdjustmentDirection},
+        books,
+        clock::Clock,
+        euro_cent::EuroCent,
+        id::IdGenerator,
+        tenant::TenantId,
+    },
+    infra::{
+        dead_letter::DeadLetterQueue,
+        projection::{
+            ErrorPolicies, ProjectionHandler, ProjectionRunner, Projections, RestartConfig,
+        },
+    },
+};
+use eventsourced::EvtLog;
+use parking_lot::RwLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const NAME: &str = "ledger";
+
+#[derive(Debug, Clone)]
+pub struct InMemLedgerProjection {
+    ledger: Arc<RwLock<Ledger>>,
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    entries: Vec<LedgerEntry>,
+    tenants: HashMap<Uuid, TenantId>,
+    sandbox: HashSet<Uuid>,
+    closed_until: Option<OffsetDateTime>,
+}
+
+impl InMemLedgerProjection {
+    /// Besides the account events, the events of the [Books](books::Books) are projected, such that
+    /// closed periods are frozen. Failed events are treated according to the
+    /// [ErrorPolicy](crate::infra::projection::ErrorPolicy) for this projection from the given
+    /// [ErrorPolicies], e.g. added to the given [DeadLetterQueue] and skipped. Progress is recorded
+    /// in the given [Projections]. Failed queries are restarted according to the given
+    /// [RestartConfig].
+    pub fn spawn<L, D>(
+        restart: RestartConfig,
+        error_policies: &ErrorPolicies,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        evt_log: L,
+        dead_letter_queue: D,
+        projections: Projections,
+    ) -> Self
+    where
+        L: EvtLog,
+        D: DeadLetterQueue,
+    {
+        let ledger = Arc::new(RwLock::new(Ledger::default()));
+
+        ProjectionRunner::new(
+            NAME,
+            vec![
+                account::ACCOUNT_LIFECYCLE_TAG,
+                account::ACCOUNT_TX_TAG,
+                books::BOOKS_TAG,
+            ],
+            clock,
+            ids,
+            evt_log,
+            dead_letter_queue,
+            projections,
+        )
+        .with_error_policy(error_policies.get(NAME))
+        .with_restart(restart)
+        .spawn(LedgerHandler(ledger.clone()));
+
+        Self { ledger }
+    }
+}
+
+impl LedgerProjection for InMemLedgerProjection {
+    async fn entries(&self, from: OffsetDateTime, to: OffsetDateTime) -> Vec<LedgerEntry> {
+        let ledger = self.ledger.read();
+        let start = ledger
+            .entries
+            .partition_point(|entry| entry.booked_at < from);
+        let end = ledger.entries.partition_point(|entry| entry.booked_at < to);
+        ledger.entries[start..end.max(start)]
+            .iter()
+            .map(|entry| LedgerEntry {
+                tenant: ledger.tenants.get(&entry.account_id).copied(),
+                sandbox: ledger.sandbox.contains(&entry.account_id),
+                ..*entry
+            })
+            .collect()
+    }
+
+    async fn entry(&self, id: Uuid) -> Option<LedgerEntry> {
+        let ledger = self.ledger.read();
+        ledger
+            .entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| LedgerEntry {
+                tenant: ledger.tenants.get(&entry.account_id).copied(),
+                sandbox: ledger.sandbox.contains(&entry.account_id),
+                ..*entry
+            })
+    }
+}
+
+struct LedgerHandler(Arc<RwLock<Ledger>>);
+
+impl ProjectionHandler for LedgerHandler {
+    type Evt = LedgerEvt;
+
+    fn decode(&self, evt: &[u8]) -> Result<Self::Evt, serde_json::Error> {
+        serde_json::from_slice::<account::Evt>(evt)
+            .map(LedgerEvt::Account)
+            .or_else(|_| serde_json::from_slice::<books::Evt>(evt).map(LedgerEvt::Books))
+    }
+
+    async fn handle(
+        &mut self,
+        _seq_no: u64,
+        evt: Self::Evt,
+    ) -> anyhow::Result<Option<OffsetDateTime>> {
+        match evt {
+            LedgerEvt::Account(evt) => {
+                let evt_at = evt.at();
+                self.0.write().apply(evt);
+                Ok(Some(evt_at))
+            }
+
+            LedgerEvt::Books(books::Evt::PeriodClosed { to, at, .. }) => {
+                self.0.write().close(to);
+                Ok(Some(at))
+            }
+        }
+    }
+}
+
+enum LedgerEvt {
+    Account(account::Evt),
+    Books(books::Evt),
+}
+
+impl Ledger {
+    /// Entries are kept ordered by booking timestamp, because lifecycle and transaction events are
+    /// queried separately. Tenants and sandbox flags are resolved when querying, because the
+    /// lifecycle event of an account might be projected after its transactions.
+    fn apply(&mut self, evt: account::Evt) {
+        let entry = match evt {
+            account::Evt::Created {
+                id,
+                tenant,
+                opening_balance,
+                sandbox,
+                at,
+                ..
+            } => {
+                self.tenants.insert(id, tenant);
+                if sandbox {
+                    self.sandbox.insert(id);
+                }
+                LedgerEntry {
+                    id,
+                    account_id: id,
+                    tenant: None,
+                    sandbox: false,
+                    kind: EntryKind::OpeningBalance,
+                    amount: opening_balance,
+                    balance: opening_balance,
+                    at,
+                    value_date: at,
+                    booked_at: at,
+                }
+            }
+
+            account::Evt::Deposited {
+                id,
+                account_id,
+                old_balance,
+                amount,
+                value_date,
+                channel: _,
+                at,
+            } => LedgerEntry {
+                id,
+                account_id,
+                tenant: None,
+                sandbox: false,
+                kind: EntryKind::Deposit,
+                amount,
+                balance: old_balance + amount,
+                at,
+                value_date: value_date.unwrap_or(at),
+                booked_at: at,
+            },
+
+            account::Evt::Withdrawn {
+                id,
+                account_id,
+                old_balance,
+                amount,
+                value_date,
+                channel: _,
+                at,
+            } => LedgerEntry {
+                id,
+                account_id,
+                tenant: None,
+                sandbox: false,
+                kind: EntryKind::Withdrawal,
+                amount,
+                balance: old_balance - amount,
+                at,
+                value_date: value_date.unwrap_or(at),
+                booked_at: at,
+            },
+
+            account::Evt::HoldCaptured {
+                id,
+                account_id,
+                old_balance,
+                amount,
+                at,
+            } => LedgerEntry {
+                id,
+                account_id,
+                tenant: None,
+                sandbox: false,
+                kind: EntryKind::CardPayment,
+                amount,
+                balance: old_balance - amount,
+                at,
+                value_date: at,
+                booked_at: at,
+            },
+
+            account::Evt::Adjusted {
+                id,
+                account_id,
+                old_balance,
+                direction,
+                amount,
+                at,
+                ..
+            } => LedgerEntry {
+                id,
+                account_id,
+                tenant: None,
+                sandbox: false,
+                kind: match direction {
+                    AdjustmentDirection::Credit => EntryKind::AdjustmentCredit,
+                    AdjustmentDirection::Debit => EntryKind::AdjustmentDebit,
+                },
+                amount,
+                balance: direction.apply(old_balance, amount),
+                at,
+                value_date: at,
+                booked_at: at,
+            },
+
+            account::Evt::Closed {
+                id,
+                account_id,
+                old_balance,
+                sweep_to: Some(_),
+                at,
+            } => LedgerEntry {
+                id,
+                account_id,
+                tenant: None,
+                sandbox: false,
+                kind: EntryKind::Sweep,
+                amount: old_balance,
+                balance: EuroCent::default(),
+                at,
+                value_date: at,
+                booked_at: at,
+            },
+
+            account::Evt::NotificationPrefsSet { .. }
+            | account::Evt::LabelsSet { .. }
+            | account::Evt::HoldPlaced { .. }
+            | account::Evt::HoldReleased { .. }
+            | account::Evt::Closed { sweep_to: None, .. }
+            | account::Evt::Reopened { .. }
+            | account::Evt::Purged { .. } => return,
+        };
+
+        let entry = match self.closed_until {
+            Some(closed_until) if entry.at < closed_until => LedgerEntry {
+                booked_at: closed_until,
+                ..entry
+            },
+            _ => entry,
+        };
+        let index = self
+            .entries
+            .partition_point(|other| other.booked_at <= entry.booked_at);
+        self.entries.insert(index, entry);
+    }
+
+    /// Freeze the periods until the given end.
+    fn close(&mut self, to: OffsetDateTime) {
+        self.closed_until = self.closed_until.max(Some(to));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    #[test]
+    fn test_apply() {
+        let mut ledger = Ledger::default();
+        let account_id = Uuid::now_v7();
+        let t0 = OffsetDateTime::UNIX_EPOCH;
+
+        ledger.apply(account::Evt::Withdrawn {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: 42u64.into(),
+            amount: 2u64.into(),
+            value_date: None,
+            channel: None,
+            at: t0 + Duration::seconds(2),
+        });
+        ledger.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: 0u64.into(),
+            amount: 42u64.into(),
+            value_date: None,
+            channel: None,
+            at: t0 + Duration::seconds(1),
+        });
+
+        let kinds = ledger
+            .entries
+            .iter()
+            .map(|entry| entry.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![EntryKind::Deposit, EntryKind::Withdrawal]);
+        assert_eq!(ledger.entries[1].balance, 40u64.into());
+
+        // Entries projected after their period has been closed are booked at its end.
+        ledger.close(t0 + Duration::seconds(3));
+        ledger.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: 40u64.into(),
+            amount: 2u64.into(),
+            value_date: None,
+            channel: None,
+            at: t0,
+        });
+        assert_eq!(ledger.entries[2].booked_at, t0 + Duration::seconds(3));
+    }
+}
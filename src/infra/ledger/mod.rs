@@ -0,0 +1,86 @@
+pub mod in_mem_ledger_projection;
+
+use crate::domain::{euro_cent::EuroCent, tenant::TenantId};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// A projection of all transactions to a journal of [LedgerEntry]s.
+pub trait LedgerProjection: Clone + Send + Sync + 'static {
+    /// The [LedgerEntry]s booked at or after `from` and before `to`, in the order of their booking
+    /// timestamps.
+    fn entries(
+        &self,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> impl Future<Output = Vec<LedgerEntry>> + Send + '_;
+
+    /// The [LedgerEntry] with the given transaction ID, if any.
+    fn entry(&self, id: Uuid) -> impl Future<Output = Option<LedgerEntry>> + Send + '_;
+}
+
+/// An entry of the ledger, i.e. a single transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LedgerEntry {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    /// Might be unknown, if the lifecycle event of the account has not yet been projected.
+    pub tenant: Option<TenantId>,
+    /// Whether the account is a sandbox account, i.e. the entry is test data to be excluded from
+    /// aggregates; resolved like the tenant.
+    pub sandbox: bool,
+    pub kind: EntryKind,
+    pub amount: EuroCent,
+    /// The balance of the account after the transaction.
+    pub balance: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    /// The date the transaction takes effect, which might be back- or future-dated; defaults to
+    /// `at`.
+    #[serde(with = "time::serde::rfc3339")]
+    pub value_date: OffsetDateTime,
+    /// Usually the same as `at`, but entries projected after their period has been closed are
+    /// booked at the end of the closed periods, such that closed periods stay frozen.
+    #[serde(with = "time::serde::rfc3339")]
+    pub booked_at: OffsetDateTime,
+}
+
+/// Kind of a [LedgerEntry].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryKind {
+    OpeningBalance,
+    Deposit,
+    Withdrawal,
+    CardPayment,
+    AdjustmentCredit,
+    AdjustmentDebit,
+    /// The final transfer of the balance of a closed account to another one.
+    Sweep,
+}
+
+impl EntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::OpeningBalance => "opening-balance",
+            EntryKind::Deposit => "deposit",
+            EntryKind::Withdrawal => "withdrawal",
+            EntryKind::CardPayment => "card-payment",
+            EntryKind::AdjustmentCredit => "adjustment-credit",
+            EntryKind::AdjustmentDebit => "adjustment-debit",
+            EntryKind::Sweep => "sweep",
+        }
+    }
+
+    /// Whether entries of this kind decrease the balance of the account.
+    pub fn is_debit(&self) -> bool {
+        matches!(
+            self,
+            EntryKind::Withdrawal
+                | EntryKind::CardPayment
+                | EntryKind::AdjustmentDebit
+                | EntryKind::Sweep
+        )
+    }
+}
@@ -0,0 +1,51 @@
+pub mod servicer;
+
+use crate::{
+    domain::{clock::Clock, loan::Loan},
+    infra::{
+        lru_cache_factory::{self, Evictor, LruCacheEntityFactory, ManagedEntity},
+        namespace::EntityType,
+    },
+};
+use eventsourced::EntityRef;
+use std::{future::Future, num::NonZeroU64, sync::Arc};
+use uuid::Uuid;
+
+/// A factory for [Loan]s, either creating new ones or returning existing managed ones.
+pub trait LoanFactory: Clone + Send + Sync + 'static {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Create a new [Loan] or return an existing managed one.
+    fn get(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = Result<EntityRef<Loan>, Self::Error>> + Send + '_;
+}
+
+impl LoanFactory for LruCacheEntityFactory<Loan> {
+    type Error = lru_cache_factory::Error;
+
+    async fn get(&self, id: Uuid) -> Result<EntityRef<Loan>, Self::Error> {
+        self.entity(id).await
+    }
+}
+
+impl ManagedEntity for Loan {
+    const ENTITY_TYPE: EntityType = EntityType::Loan;
+
+    type Settings = ();
+
+    type Observer = ();
+
+    type Ref = EntityRef<Loan>;
+
+    fn create(_settings: &(), clock: Arc<dyn Clock>, _snapshot_after: Option<NonZeroU64>) -> Self {
+        Loan::default().with_clock(clock)
+    }
+
+    fn observe(&self) -> Self::Observer {}
+
+    fn into_ref(entity: EntityRef<Self>, _observer: (), _evictor: Evictor) -> Self::Ref {
+        entity
+    }
+}
@@ -0,0 +1,241 @@
+use super::LoanFactory;
+use crate::{
+    domain::{account, clock::Clock, euro_cent::EuroCent, id::IdGenerator, loan},
+    infra::{account::AccountFactory, leader::Leadership, lru_cache_factory::Priority},
+};
+use anyhow::Context;
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    num::NonZeroU64,
+    sync::Arc,
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::{pin, select, task, time::interval};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Configuration for the loan servicer.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Interval for checking for due installments.
+    interval_secs: NonZeroU64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    account_id: Uuid,
+    amount: EuroCent,
+    due_at: OffsetDateTime,
+    missed: bool,
+}
+
+/// Spawn servicing loans: disbursed principals are deposited to their accounts and due
+/// installments are withdrawn and completed as paid or, e.g. for insufficient funds, as missed.
+/// Missed installments are in arrears and retried until paid. Events are tracked on every node,
+/// but only the leader services loans. Disbursements from before spawning, i.e. replayed ones, are
+/// not deposited again.
+pub fn spawn<L, A, F>(
+    config: Config,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    evt_log: L,
+    account_factory: A,
+    loan_factory: F,
+    leadership: Leadership,
+) where
+    L: EvtLog,
+    A: AccountFactory,
+    F: LoanFactory,
+{
+    task::spawn(async move {
+        let started_at = clock.now();
+
+        let evts = evt_log
+            .evts_by_tag::<Bytes, _, _, _>(loan::LOAN_TAG, SeqNo::MIN, raw)
+            .await
+            .context("Cannot create events-by-tag query");
+        let evts = match evts {
+            Ok(evts) => evts,
+            Err(error) => {
+                error!(error = format!("{error:#}"), "Cannot service loans");
+                return;
+            }
+        };
+
+        let mut pending = HashMap::<(Uuid, usize), Pending>::new();
+        let mut in_flight = HashSet::<(Uuid, usize)>::new();
+        let mut interval = interval(Duration::from_secs(config.interval_secs.get()));
+        pin!(evts);
+        loop {
+            select! {
+                evt = evts.next() => {
+                    let evt = match evt {
+                        Some(Ok((_, evt))) => evt,
+                        Some(Err(error)) => {
+                            error!(error = format!("{error:#}"), "Cannot get next event");
+                            break;
+                        }
+                        None => break,
+                    };
+                    let Ok(evt) = serde_json::from_slice::<loan::Evt>(&evt) else {
+                        warn!("Cannot deserialize loan event");
+                        continue;
+                    };
+                    debug!(loan_id = %evt.loan_id(), "Processing loan event");
+
+                    match evt {
+                        loan::Evt::Disbursed {
+                            id,
+                            account_id,
+                            principal,
+                            schedule,
+                            at,
+                        } => {
+                            for (number, installment) in schedule.into_iter().enumerate() {
+                                let installment = Pending {
+                                    account_id,
+                                    amount: installment.amount,
+                                    due_at: installment.due_at,
+                                    missed: false,
+                                };
+                                pending.insert((id, number), installment);
+                            }
+                            if at >= started_at && leadership.is_leader() {
+                                disburse(&account_factory, id, account_id, principal).await;
+                            }
+                        }
+
+                        loan::Evt::InstallmentPaid { id, number, .. } => {
+                            pending.remove(&(id, number));
+                            in_flight.remove(&(id, number));
+                        }
+
+                        loan::Evt::InstallmentMissed { id, number, .. } => {
+                            if let Some(installment) = pending.get_mut(&(id, number)) {
+                                installment.missed = true;
+                            }
+                            in_flight.remove(&(id, number));
+                        }
+
+                        loan::Evt::Created { .. } => {}
+                    }
+                }
+
+                _ = interval.tick() => {
+                    if !leadership.is_leader() {
+                        continue;
+                    }
+
+                    let now = clock.now();
+                    let due = pending
+                        .iter()
+                        .filter(|(key, installment)| {
+                            installment.due_at <= now && !in_flight.contains(key)
+                        })
+                        .map(|(key, installment)| (*key, *installment))
+                        .collect::<Vec<_>>();
+                    for (key, installment) in due {
+                        if collect(&account_factory, ids.as_ref(), &loan_factory, key, installment).await {
+                            in_flight.insert(key);
+                        }
+                    }
+                }
+            }
+        }
+
+        error!("Loan servicer terminated");
+    });
+}
+
+/// Withdraw the given installment and complete it; returns whether a completing event is to be
+/// expected, i.e. `false` on technical errors or for failed retries of missed installments, such
+/// that it is retried.
+async fn collect<A, F>(
+    account_factory: &A,
+    ids: &dyn IdGenerator,
+    loan_factory: &F,
+    (loan_id, number): (Uuid, usize),
+    installment: Pending,
+) -> bool
+where
+    A: AccountFactory,
+    F: LoanFactory,
+{
+    debug!(%loan_id, number, "Collecting installment");
+
+    let withdrawn = async {
+        account_factory
+            .get_with_priority(installment.account_id, Priority::Bulk)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(account::Cmd::Withdraw(
+                ids.next_id(),
+                installment.amount,
+                None,
+                None,
+            ))
+            .await
+            .context("Cannot handle Withdraw command")
+    }
+    .await;
+    let collected = match withdrawn {
+        Ok(Ok(_)) => true,
+        Ok(Err(_)) if installment.missed => return false,
+        Ok(Err(error)) => {
+            warn!(%loan_id, number, %error, "Missed installment");
+            false
+        }
+        Err(error) => {
+            error!(%loan_id, number, error = format!("{error:#}"), "Cannot collect installment");
+            return false;
+        }
+    };
+
+    let completed = async {
+        loan_factory
+            .get(loan_id)
+            .await
+            .context("Cannot get Loan entity")?
+            .handle_cmd(loan::Cmd::CompleteInstallment { number, collected })
+            .await
+            .context("Cannot handle CompleteInstallment command")
+    }
+    .await;
+    if !matches!(completed, Ok(Ok(_))) {
+        error!(%loan_id, number, "Cannot complete installment");
+    }
+
+    true
+}
+
+async fn disburse<A>(account_factory: &A, loan_id: Uuid, account_id: Uuid, principal: EuroCent)
+where
+    A: AccountFactory,
+{
+    debug!(%loan_id, "Disbursing");
+
+    let deposited = async {
+        account_factory
+            .get_with_priority(account_id, Priority::Bulk)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(account::Cmd::Deposit(loan_id, principal, None, None))
+            .await
+            .context("Cannot handle Deposit command")
+    }
+    .await;
+    if !matches!(deposited, Ok(Ok(_))) {
+        error!(%loan_id, "Cannot disburse loan");
+    }
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
@@ -0,0 +1,726 @@
+use crate::{
+    domain::{clock::Clock, tenant::TenantId},
+    infra::namespace::{EntityType, Namespace},
+};
+use anyhow::Context;
+use bytes::Bytes;
+use eventsourced::{
+    convert, Binarizer, EntityRef, EventSourced, EventSourcedExt, EvtLog, SnapshotStore,
+};
+use lru::LruCache;
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error as StdError,
+    future::{self, Future},
+    num::{NonZeroU32, NonZeroU64, NonZeroUsize},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::{
+    runtime::Handle,
+    select,
+    sync::{
+        broadcast,
+        mpsc::{
+            self,
+            error::{SendTimeoutError, TrySendError},
+        },
+        oneshot,
+    },
+    task::{self, JoinError},
+    time,
+};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Number of mutations buffered for slow subscribers, which miss older ones.
+const MUTATIONS_CAPACITY: usize = 1_024;
+
+/// An [EventSourced] entity which can be managed by a [LruCacheEntityFactory].
+pub trait ManagedEntity: EventSourced {
+    /// The type of this entity, used to namespace its ID.
+    const ENTITY_TYPE: EntityType;
+
+    /// Settings for creating entities, e.g. limits; `()` if not needed.
+    type Settings: Clone + Send + Sync + 'static;
+
+    /// Observes a spawned entity, e.g. its state; `()` if not needed.
+    type Observer: Send + 'static;
+
+    /// Handle to a spawned entity, handed out by the factory.
+    type Ref: Clone + Send + Sync + 'static;
+
+    /// Create a new entity with the given settings, using the given [Clock] and taking a snapshot
+    /// after the given number of events, if supported.
+    fn create(
+        settings: &Self::Settings,
+        clock: Arc<dyn Clock>,
+        snapshot_after: Option<NonZeroU64>,
+    ) -> Self;
+
+    /// Observe this entity before it gets spawned.
+    fn observe(&self) -> Self::Observer;
+
+    /// Create the handle for the given spawned entity.
+    fn into_ref(entity: EntityRef<Self>, observer: Self::Observer, evictor: Evictor) -> Self::Ref;
+}
+
+/// Encoding of the events and snapshots of entities of type `E` for the event log and snapshot
+/// store.
+pub trait Codec<E>: Copy + Send + Sync + 'static
+where
+    E: EventSourced,
+{
+    type Error: StdError + Send + Sync + 'static;
+
+    fn evt_to_bytes(&self, evt: &E::Evt) -> Result<Bytes, Self::Error>;
+
+    fn evt_from_bytes(&self, bytes: Bytes) -> Result<E::Evt, Self::Error>;
+
+    fn state_to_bytes(&self, state: &E::State) -> Result<Bytes, Self::Error>;
+
+    fn state_from_bytes(&self, bytes: Bytes) -> Result<E::State, Self::Error>;
+}
+
+/// [Codec] based upon serde_json.
+#[derive(Debug, Clone, Copy)]
+pub struct SerdeJsonCodec;
+
+impl<E> Codec<E> for SerdeJsonCodec
+where
+    E: EventSourced,
+    E::Evt: Serialize + DeserializeOwned,
+    E::State: Serialize + DeserializeOwned,
+{
+    type Error = serde_json::Error;
+
+    fn evt_to_bytes(&self, evt: &E::Evt) -> Result<Bytes, Self::Error> {
+        convert::serde_json::to_bytes(evt)
+    }
+
+    fn evt_from_bytes(&self, bytes: Bytes) -> Result<E::Evt, Self::Error> {
+        convert::serde_json::from_bytes(bytes)
+    }
+
+    fn state_to_bytes(&self, state: &E::State) -> Result<Bytes, Self::Error> {
+        convert::serde_json::to_bytes(state)
+    }
+
+    fn state_from_bytes(&self, bytes: Bytes) -> Result<E::State, Self::Error> {
+        convert::serde_json::from_bytes(bytes)
+    }
+}
+
+/// Looks up the tenant of an entity, such that the settings of the [Cohort] of the tenant apply.
+pub trait TenantLookup: Clone + Send + Sync + 'static {
+    /// The tenant of the entity with the given ID, if known.
+    fn tenant(&self, id: Uuid) -> impl Future<Output = Option<TenantId>> + Send + '_;
+}
+
+/// [TenantLookup] for entities without cohorts.
+impl TenantLookup for () {
+    fn tenant(&self, _id: Uuid) -> impl Future<Output = Option<TenantId>> + Send + '_ {
+        future::ready(None)
+    }
+}
+
+/// Priority of requests for entities, each priority having its own bounded queue, such that
+/// latency-critical requests, e.g. for card authorizations, are not stuck behind bulk ones, e.g.
+/// from batch processing. Queues of higher priority are always served first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Latency-critical requests, e.g. for card authorizations.
+    Critical,
+
+    /// Regular requests, e.g. from the API.
+    Normal,
+
+    /// Bulk requests, e.g. from batch processing.
+    Bulk,
+}
+
+type GetEntity<E> = (
+    Uuid,
+    oneshot::Sender<Result<<E as ManagedEntity>::Ref, Error>>,
+);
+
+/// Reports a terminated entity for eviction, such that it gets respawned from the event log, and
+/// mutations of an entity, e.g. for invalidating caches of other instances.
+#[derive(Debug, Clone)]
+pub struct Evictor {
+    id: Uuid,
+    generation: u64,
+    evict_sdr: mpsc::UnboundedSender<(Uuid, u64)>,
+    mutation_sdr: broadcast::Sender<Uuid>,
+}
+
+impl Evictor {
+    /// The ID of the entity.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Evict the entity, unless already replaced by a respawned one.
+    pub fn evict(&self) {
+        let _ = self.evict_sdr.send((self.id, self.generation));
+    }
+
+    /// Report that the entity has been mutated, i.e. events have been persisted.
+    pub fn mutated(&self) {
+        // Sending only fails without subscribers.
+        let _ = self.mutation_sdr.send(self.id);
+    }
+}
+
+/// Factory for [ManagedEntity]s, either spawning new ones or returning cached ones. At most the
+/// configured number of entities is cached, evicting the least recently used ones. If configured,
+/// consistently hot entities are pinned, i.e. exempt from eviction, such that bursts of one-off
+/// activity do not evict them.
+#[derive(Debug, Clone)]
+pub struct LruCacheEntityFactory<E>
+where
+    E: ManagedEntity,
+{
+    critical_sdr: mpsc::Sender<GetEntity<E>>,
+    normal_sdr: mpsc::Sender<GetEntity<E>>,
+    bulk_sdr: mpsc::Sender<GetEntity<E>>,
+    queue_wait: Duration,
+    hot_ids: Arc<RwLock<HashSet<Uuid>>>,
+    invalidate_sdr: mpsc::UnboundedSender<Uuid>,
+    mutation_sdr: broadcast::Sender<Uuid>,
+}
+
+impl<E> LruCacheEntityFactory<E>
+where
+    E: ManagedEntity,
+{
+    /// Entities are spawned with the settings of the [Cohort] of their tenant, looked up via the
+    /// given [TenantLookup], or with the default settings if not belonging to any cohort.
+    pub async fn spawn<L, S, C, T>(
+        config: Config,
+        namespace: Namespace,
+        settings: E::Settings,
+        clock: Arc<dyn Clock>,
+        evt_log: L,
+        snapshot_store: S,
+        codec: C,
+        tenant_lookup: T,
+    ) -> Self
+    where
+        L: EvtLog,
+        S: SnapshotStore,
+        C: Codec<E>,
+        T: TenantLookup,
+    {
+        let config = Arc::new(config);
+        let entities: Arc<RwLock<LruCache<Uuid, (u64, E::Ref)>>> =
+            Arc::new(RwLock::new(LruCache::new(config.cache_capacity)));
+
+        let (critical_sdr, mut critical_rcv) =
+            mpsc::channel::<GetEntity<E>>(config.cache_buffer.get());
+        let (normal_sdr, mut normal_rcv) = mpsc::channel::<GetEntity<E>>(config.cache_buffer.get());
+        let (bulk_sdr, mut bulk_rcv) = mpsc::channel::<GetEntity<E>>(config.cache_buffer.get());
+        let (evict_sdr, mut evict_rcv) = mpsc::unbounded_channel::<(Uuid, u64)>();
+        let (invalidate_sdr, mut invalidate_rcv) = mpsc::unbounded_channel::<Uuid>();
+        let (mutation_sdr, _) = broadcast::channel(MUTATIONS_CAPACITY);
+        let queue_wait = Duration::from_millis(config.queue_wait_millis);
+        let hot_ids = Arc::new(RwLock::new(HashSet::new()));
+        let hot_config = config.hot;
+        let mut hot_interval = time::interval(
+            hot_config
+                .map(|hot_config| Duration::from_secs(hot_config.window_secs.get()))
+                .unwrap_or(Duration::from_secs(3_600)),
+        );
+        hot_interval.reset();
+
+        task::spawn({
+            let hot_ids = hot_ids.clone();
+            let mutation_sdr = mutation_sdr.clone();
+            async move {
+                let mut generation = 0;
+                let mut rates = Rates::default();
+                let mut pinned = HashMap::<Uuid, (u64, E::Ref)>::new();
+                loop {
+                    // Evictions, invalidations and windows go first, then the queues by priority.
+                    // All senders are held by the factory, hence all queues close together.
+                    let (id, entity_sdr) = select! {
+                        biased;
+
+                        Some((id, evicted_generation)) = evict_rcv.recv() => {
+                            if pinned.get(&id).map(|(generation, _)| *generation)
+                                == Some(evicted_generation)
+                            {
+                                pinned.remove(&id);
+                                hot_ids.write().remove(&id);
+                            }
+                            evict::<E>(&entities, id, evicted_generation);
+                            continue;
+                        }
+
+                        Some(id) = invalidate_rcv.recv() => {
+                            if pinned.remove(&id).is_some() {
+                                hot_ids.write().remove(&id);
+                            }
+                            if entities.write().pop(&id).is_some() {
+                                debug!(
+                                    entity_type = E::ENTITY_TYPE.as_str(),
+                                    %id, "Invalidated entity"
+                                );
+                            }
+                            continue;
+                        }
+
+                        _ = hot_interval.tick(), if hot_config.is_some() => {
+                            let hot_config = hot_config.expect("hot config is some");
+                            let hot = rates.roll(&hot_config);
+                            repin::<E>(&entities, &mut pinned, hot, &hot_config);
+                            *hot_ids.write() = pinned.keys().copied().collect();
+                            metrics::gauge!(
+                                "hot_entities",
+                                pinned.len() as f64,
+                                "entity_type" => E::ENTITY_TYPE.as_str()
+                            );
+                            continue;
+                        }
+
+                        get_entity = critical_rcv.recv() => match get_entity {
+                            Some(get_entity) => get_entity,
+                            None => break,
+                        },
+
+                        get_entity = normal_rcv.recv() => match get_entity {
+                            Some(get_entity) => get_entity,
+                            None => break,
+                        },
+
+                        get_entity = bulk_rcv.recv() => match get_entity {
+                            Some(get_entity) => get_entity,
+                            None => break,
+                        },
+                    };
+
+                    if hot_config.is_some() {
+                        rates.record(id);
+                    }
+                    if let Some((_, entity)) = pinned.get(&id) {
+                        if entity_sdr.send(Ok(entity.clone())).is_err() {
+                            error!(%id, "Cannot send back pinned entity");
+                        }
+                        continue;
+                    }
+
+                    generation += 1;
+                    let entities = entities.clone();
+                    let settings = settings.clone();
+                    let clock = clock.clone();
+                    let evt_log = evt_log.clone();
+                    let snapshot_store = snapshot_store.clone();
+                    let evict_sdr = evict_sdr.clone();
+                    let mutation_sdr = mutation_sdr.clone();
+                    let config = config.clone();
+                    let tenant_lookup = tenant_lookup.clone();
+
+                    let entity = task::spawn_blocking(move || {
+                        entities
+                            .write()
+                            .get_or_insert(id, || {
+                                Handle::current().block_on(async move {
+                                    let tenant = tenant_lookup.tenant(id).await;
+                                    let (cohort, snapshot_after, cmd_buffer) =
+                                        config.settings(tenant);
+                                    if let Some(cohort) = cohort {
+                                        debug!(
+                                            entity_type = E::ENTITY_TYPE.as_str(),
+                                            %id, cohort, "Spawning entity of cohort"
+                                        );
+                                    }
+                                    let entity = E::create(&settings, clock, snapshot_after);
+                                    let observer = entity.observe();
+                                    entity
+                                        .spawn(
+                                            namespace.id(E::ENTITY_TYPE, id),
+                                            cmd_buffer,
+                                            evt_log,
+                                            snapshot_store,
+                                            binarizer(codec),
+                                        )
+                                        .await
+                                        .context("Cannot spawn entity")
+                                        .inspect_err(|error| {
+                                            error!(
+                                                entity_type = E::ENTITY_TYPE.as_str(),
+                                                error = format!("{error:#}"),
+                                                "Cannot get entity"
+                                            )
+                                        })
+                                        .map(|entity| {
+                                            let evictor = Evictor {
+                                                id,
+                                                generation,
+                                                evict_sdr,
+                                                mutation_sdr,
+                                            };
+                                            (generation, E::into_ref(entity, observer, evictor))
+                                        })
+                                        .unwrap()
+                                })
+                            })
+                            .1
+                            .clone()
+                    })
+                    .await
+                    .map_err(Error::SpawnEntity);
+
+                    if entity_sdr.send(entity).is_err() {
+                        error!(%id, "Cannot send back spawn result");
+                    }
+                }
+            }
+        });
+
+        Self {
+            critical_sdr,
+            normal_sdr,
+            bulk_sdr,
+            queue_wait,
+            hot_ids,
+            invalidate_sdr,
+            mutation_sdr,
+        }
+    }
+
+    /// Drop the cached entity for the given ID, if any, e.g. because it has been mutated by another
+    /// instance, such that it gets respawned from the event log when requested next.
+    pub fn invalidate(&self, id: Uuid) {
+        let _ = self.invalidate_sdr.send(id);
+    }
+
+    /// Subscribe to the IDs of mutated entities, as reported via [Evictor::mutated].
+    pub fn mutations(&self) -> broadcast::Receiver<Uuid> {
+        self.mutation_sdr.subscribe()
+    }
+
+    /// The IDs of the currently pinned, i.e. consistently hot, entities.
+    pub fn hot_entities(&self) -> Vec<Uuid> {
+        self.hot_ids.read().iter().copied().collect()
+    }
+
+    /// Spawn a new entity for the given ID or return the cached one, with [Priority::Normal].
+    pub async fn entity(&self, id: Uuid) -> Result<E::Ref, Error> {
+        self.entity_with_priority(id, Priority::Normal).await
+    }
+
+    /// Spawn a new entity for the given ID or return the cached one, with the given [Priority].
+    pub async fn entity_with_priority(
+        &self,
+        id: Uuid,
+        priority: Priority,
+    ) -> Result<E::Ref, Error> {
+        let get_entity_sdr = match priority {
+            Priority::Critical => &self.critical_sdr,
+            Priority::Normal => &self.normal_sdr,
+            Priority::Bulk => &self.bulk_sdr,
+        };
+        let start = Instant::now();
+        let (entity_sdr, entity_rcv) = oneshot::channel();
+
+        // Rather fail fast than pile up requests on a saturated factory.
+        let sent = match get_entity_sdr.try_send((id, entity_sdr)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Closed(_)) => Err(Error::Send),
+            Err(TrySendError::Full(_)) if self.queue_wait.is_zero() => Err(Error::Overloaded),
+            Err(TrySendError::Full(get_entity)) => get_entity_sdr
+                .send_timeout(get_entity, self.queue_wait)
+                .await
+                .map_err(|error| match error {
+                    SendTimeoutError::Timeout(_) => Error::Overloaded,
+                    SendTimeoutError::Closed(_) => Error::Send,
+                }),
+        };
+        if let Err(Error::Overloaded) = sent {
+            warn!(entity_type = E::ENTITY_TYPE.as_str(), %id, ?priority, "Entity factory overloaded");
+            metrics::counter!(
+                "entity_factory_overloaded_total",
+                1,
+                "entity_type" => E::ENTITY_TYPE.as_str()
+            );
+        }
+        sent?;
+
+        let entity = entity_rcv.await.map_err(Error::Rcv)?;
+        metrics::histogram!(
+            "entity_factory_get_duration_seconds",
+            start.elapsed().as_secs_f64(),
+            "entity_type" => E::ENTITY_TYPE.as_str()
+        );
+        entity
+    }
+}
+
+/// Evict the cached entity for the given ID, but only if it is of the given generation, i.e. not
+/// already replaced by a respawned entity.
+fn evict<E>(entities: &RwLock<LruCache<Uuid, (u64, E::Ref)>>, id: Uuid, generation: u64)
+where
+    E: ManagedEntity,
+{
+    let mut entities = entities.write();
+    if entities.peek(&id).map(|(generation, _)| *generation) == Some(generation) {
+        entities.pop(&id);
+        warn!(
+            entity_type = E::ENTITY_TYPE.as_str(),
+            %id, generation, "Evicted terminated entity"
+        );
+    }
+}
+
+/// Pin the given hot entities, at most the configured number with the highest rates, by moving
+/// them from the cache, and unpin the no longer hot ones by moving them back.
+fn repin<E>(
+    entities: &RwLock<LruCache<Uuid, (u64, E::Ref)>>,
+    pinned: &mut HashMap<Uuid, (u64, E::Ref)>,
+    hot: Vec<Uuid>,
+    hot_config: &HotConfig,
+) where
+    E: ManagedEntity,
+{
+    let mut entities = entities.write();
+
+    let unpinned = pinned
+        .keys()
+        .filter(|id| !hot.contains(id))
+        .copied()
+        .collect::<Vec<_>>();
+    for id in unpinned {
+        if let Some(entity) = pinned.remove(&id) {
+            debug!(entity_type = E::ENTITY_TYPE.as_str(), %id, "Unpinning entity");
+            entities.push(id, entity);
+        }
+    }
+
+    for id in hot {
+        if pinned.len() >= hot_config.max_pinned.get() {
+            break;
+        }
+        if pinned.contains_key(&id) {
+            continue;
+        }
+        // Entities not cached, e.g. just evicted ones, are pinned once spawned again.
+        if let Some(entity) = entities.pop(&id) {
+            debug!(entity_type = E::ENTITY_TYPE.as_str(), %id, "Pinning hot entity");
+            pinned.insert(id, entity);
+        }
+    }
+}
+
+/// Numbers of requests per entity in the current window and numbers of consecutive hot windows.
+#[derive(Debug, Default)]
+struct Rates {
+    counts: HashMap<Uuid, u64>,
+    hot_windows: HashMap<Uuid, u32>,
+}
+
+impl Rates {
+    fn record(&mut self, id: Uuid) {
+        *self.counts.entry(id).or_default() += 1;
+    }
+
+    /// Close the current window and return the IDs of the entities hot for the configured number
+    /// of consecutive windows, the ones with the highest counts first.
+    fn roll(&mut self, hot_config: &HotConfig) -> Vec<Uuid> {
+        let counts = std::mem::take(&mut self.counts);
+        let hot_windows = std::mem::take(&mut self.hot_windows);
+
+        let mut hot = vec![];
+        for (id, count) in counts {
+            if count >= hot_config.threshold.get() {
+                let windows = hot_windows.get(&id).copied().unwrap_or_default() + 1;
+                self.hot_windows.insert(id, windows);
+                if windows >= hot_config.windows.get() {
+                    hot.push((id, count));
+                }
+            }
+        }
+
+        hot.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+        hot.into_iter().map(|(id, _)| id).collect()
+    }
+}
+
+fn binarizer<E, C>(
+    codec: C,
+) -> Binarizer<
+    impl Fn(&E::Evt) -> Result<Bytes, C::Error> + Send + Sync + 'static,
+    impl Fn(Bytes) -> Result<E::Evt, C::Error> + Copy + Send + Sync + 'static,
+    impl Fn(&E::State) -> Result<Bytes, C::Error> + Send + Sync + 'static,
+    impl Fn(Bytes) -> Result<E::State, C::Error> + Copy + Send + Sync + 'static,
+>
+where
+    E: EventSourced,
+    C: Codec<E>,
+{
+    Binarizer {
+        evt_to_bytes: move |evt: &E::Evt| codec.evt_to_bytes(evt),
+        evt_from_bytes: move |bytes| codec.evt_from_bytes(bytes),
+        state_to_bytes: move |state: &E::State| codec.state_to_bytes(state),
+        state_from_bytes: move |bytes| codec.state_from_bytes(bytes),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    cache_capacity: NonZeroUsize,
+    cache_buffer: NonZeroUsize,
+    entity_cmd_buffer: NonZeroUsize,
+    entity_snapshot_after: Option<NonZeroU64>,
+
+    /// Cohorts of entities with their own settings, e.g. merchant accounts with many events.
+    #[serde(default)]
+    cohorts: Vec<Cohort>,
+
+    /// If given, consistently hot entities are pinned.
+    hot: Option<HotConfig>,
+
+    /// Maximum time to wait for a full request queue before failing with [Error::Overloaded]; if
+    /// zero, requests fail immediately.
+    #[serde(default = "queue_wait_millis_default")]
+    queue_wait_millis: u64,
+}
+
+impl Config {
+    /// The name of the cohort, if any, and the snapshot-after and command buffer settings for
+    /// entities of the given tenant.
+    fn settings(
+        &self,
+        tenant: Option<TenantId>,
+    ) -> (Option<&str>, Option<NonZeroU64>, NonZeroUsize) {
+        let cohort = tenant.and_then(|tenant| {
+            self.cohorts
+                .iter()
+                .find(|cohort| cohort.tenants.contains(&tenant))
+        });
+        match cohort {
+            Some(cohort) => (
+                Some(&cohort.name),
+                cohort.entity_snapshot_after.or(self.entity_snapshot_after),
+                cohort.entity_cmd_buffer.unwrap_or(self.entity_cmd_buffer),
+            ),
+            None => (None, self.entity_snapshot_after, self.entity_cmd_buffer),
+        }
+    }
+}
+
+fn queue_wait_millis_default() -> u64 {
+    100
+}
+
+/// Configuration for pinning hot entities.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HotConfig {
+    /// Length of the windows in which requests are counted.
+    window_secs: NonZeroU64,
+
+    /// Minimum number of requests per window for an entity to be hot.
+    threshold: NonZeroU64,
+
+    /// Number of consecutive hot windows for an entity to get pinned; it gets unpinned after the
+    /// first window it is not hot.
+    windows: NonZeroU32,
+
+    /// Maximum number of pinned entities, in addition to the cache capacity.
+    max_pinned: NonZeroUsize,
+}
+
+/// Cohort of entities, selected by tenant, overriding the default settings where given.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Cohort {
+    name: String,
+    tenants: Vec<TenantId>,
+    entity_cmd_buffer: Option<NonZeroUsize>,
+    entity_snapshot_after: Option<NonZeroU64>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Cannot spawn entity")]
+    SpawnEntity(JoinError),
+
+    #[error("Cannot send spawn command to entity factory")]
+    Send,
+
+    #[error("Entity factory overloaded")]
+    Overloaded,
+
+    #[error("Cannot receive result from entity factory")]
+    Rcv(oneshot::error::RecvError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings() {
+        let merchants = TenantId::from(Uuid::from_u128(1));
+        let config = Config {
+            cache_capacity: NonZeroUsize::new(100).unwrap(),
+            cache_buffer: NonZeroUsize::new(7).unwrap(),
+            entity_cmd_buffer: NonZeroUsize::new(7).unwrap(),
+            entity_snapshot_after: NonZeroU64::new(10),
+            cohorts: vec![Cohort {
+                name: "merchant".to_string(),
+                tenants: vec![merchants],
+                entity_cmd_buffer: None,
+                entity_snapshot_after: NonZeroU64::new(1_000),
+            }],
+            hot: None,
+            queue_wait_millis: 100,
+        };
+
+        let (cohort, snapshot_after, cmd_buffer) = config.settings(Some(merchants));
+        assert_eq!(cohort, Some("merchant"));
+        assert_eq!(snapshot_after, NonZeroU64::new(1_000));
+        assert_eq!(cmd_buffer.get(), 7);
+
+        let (cohort, snapshot_after, _) = config.settings(Some(TenantId::default()));
+        assert_eq!(cohort, None);
+        assert_eq!(snapshot_after, NonZeroU64::new(10));
+
+        let (cohort, _, _) = config.settings(None);
+        assert_eq!(cohort, None);
+    }
+
+    #[test]
+    fn test_rates() {
+        let hot_config = HotConfig {
+            window_secs: NonZeroU64::new(60).unwrap(),
+            threshold: NonZeroU64::new(2).unwrap(),
+            windows: NonZeroU32::new(2).unwrap(),
+            max_pinned: NonZeroUsize::new(10).unwrap(),
+        };
+        let merchant = Uuid::from_u128(1);
+        let retail = Uuid::from_u128(2);
+        let mut rates = Rates::default();
+
+        // First hot window: not yet consistently hot.
+        (0..3).for_each(|_| rates.record(merchant));
+        (0..2).for_each(|_| rates.record(retail));
+        assert!(rates.roll(&hot_config).is_empty());
+
+        // Second hot window: both consistently hot, the one with more requests first.
+        (0..3).for_each(|_| rates.record(merchant));
+        (0..2).for_each(|_| rates.record(retail));
+        assert_eq!(rates.roll(&hot_config), vec![merchant, retail]);
+
+        // A burst of one-off activity is not hot.
+        rates.record(merchant);
+        (0..2).for_each(|_| rates.record(merchant));
+        rates.record(retail);
+        assert_eq!(rates.roll(&hot_config), vec![merchant]);
+    }
+}
@@ -0,0 +1,242 @@
+use super::MandateFactory;
+use crate::{
+    domain::{account, clock::Clock, euro_cent::EuroCent, mandate},
+    infra::{account::AccountFactory, leader::Leadership, lru_cache_factory::Priority},
+};
+use anyhow::Context;
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    num::NonZeroU64,
+    sync::Arc,
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::{pin, select, task, time::interval};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Configuration for the collection processor.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Interval for checking for due collections.
+    interval_secs: NonZeroU64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    mandate_id: Uuid,
+    account_id: Uuid,
+    amount: EuroCent,
+    due_at: OffsetDateTime,
+}
+
+/// Spawn processing direct debit collections: due collections are withdrawn from their accounts
+/// and either completed as collected or, e.g. for insufficient funds, as returned; refunded
+/// collections are deposited back. Events are tracked on every node, but only the leader
+/// processes collections. Refunds from before spawning, i.e. replayed ones, are not deposited
+/// again.
+pub fn spawn<L, A, M>(
+    config: Config,
+    clock: Arc<dyn Clock>,
+    evt_log: L,
+    account_factory: A,
+    mandate_factory: M,
+    leadership: Leadership,
+) where
+    L: EvtLog,
+    A: AccountFactory,
+    M: MandateFactory,
+{
+    task::spawn(async move {
+        let started_at = clock.now();
+
+        let evts = evt_log
+            .evts_by_tag::<Bytes, _, _, _>(mandate::MANDATE_TAG, SeqNo::MIN, raw)
+            .await
+            .context("Cannot create events-by-tag query");
+        let evts = match evts {
+            Ok(evts) => evts,
+            Err(error) => {
+                error!(error = format!("{error:#}"), "Cannot process collections");
+                return;
+            }
+        };
+
+        let mut pending = HashMap::<Uuid, Pending>::new();
+        let mut in_flight = HashSet::<Uuid>::new();
+        let mut interval = interval(Duration::from_secs(config.interval_secs.get()));
+        pin!(evts);
+        loop {
+            select! {
+                evt = evts.next() => {
+                    let evt = match evt {
+                        Some(Ok((_, evt))) => evt,
+                        Some(Err(error)) => {
+                            error!(error = format!("{error:#}"), "Cannot get next event");
+                            break;
+                        }
+                        None => break,
+                    };
+                    let Ok(evt) = serde_json::from_slice::<mandate::Evt>(&evt) else {
+                        warn!("Cannot deserialize mandate event");
+                        continue;
+                    };
+                    debug!(mandate_id = %evt.mandate_id(), "Processing mandate event");
+
+                    match evt {
+                        mandate::Evt::CollectionScheduled {
+                            id,
+                            account_id,
+                            collection_id,
+                            amount,
+                            due_at,
+                            ..
+                        } => {
+                            let collection = Pending {
+                                mandate_id: id,
+                                account_id,
+                                amount,
+                                due_at,
+                            };
+                            pending.insert(collection_id, collection);
+                        }
+
+                        mandate::Evt::CollectionCollected { collection_id, .. }
+                        | mandate::Evt::CollectionReturned { collection_id, .. } => {
+                            pending.remove(&collection_id);
+                            in_flight.remove(&collection_id);
+                        }
+
+                        mandate::Evt::CollectionRefunded {
+                            account_id,
+                            collection_id,
+                            amount,
+                            at,
+                            ..
+                        } if at >= started_at && leadership.is_leader() => {
+                            refund(&account_factory, account_id, collection_id, amount).await;
+                        }
+
+                        _ => {}
+                    }
+                }
+
+                _ = interval.tick() => {
+                    if !leadership.is_leader() {
+                        continue;
+                    }
+
+                    let now = clock.now();
+                    let due = pending
+                        .iter()
+                        .filter(|(id, collection)| {
+                            collection.due_at <= now && !in_flight.contains(id)
+                        })
+                        .map(|(id, collection)| (*id, *collection))
+                        .collect::<Vec<_>>();
+                    for (collection_id, collection) in due {
+                        if collect(&account_factory, &mandate_factory, collection_id, collection)
+                            .await
+                        {
+                            in_flight.insert(collection_id);
+                        }
+                    }
+                }
+            }
+        }
+
+        error!("Collection processor terminated");
+    });
+}
+
+/// Withdraw the given collection and complete it; returns `false` on technical errors, such that
+/// it is retried.
+async fn collect<A, M>(
+    account_factory: &A,
+    mandate_factory: &M,
+    collection_id: Uuid,
+    collection: Pending,
+) -> bool
+where
+    A: AccountFactory,
+    M: MandateFactory,
+{
+    debug!(%collection_id, "Collecting");
+
+    let withdrawn = async {
+        account_factory
+            .get_with_priority(collection.account_id, Priority::Bulk)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(account::Cmd::Withdraw(
+                collection_id,
+                collection.amount,
+                None,
+                None,
+            ))
+            .await
+            .context("Cannot handle Withdraw command")
+    }
+    .await;
+    let collected = match withdrawn {
+        Ok(Ok(_)) => true,
+        Ok(Err(error)) => {
+            warn!(%collection_id, %error, "Returning collection");
+            false
+        }
+        Err(error) => {
+            error!(%collection_id, error = format!("{error:#}"), "Cannot collect");
+            return false;
+        }
+    };
+
+    let completed = async {
+        mandate_factory
+            .get(collection.mandate_id)
+            .await
+            .context("Cannot get Mandate entity")?
+            .handle_cmd(mandate::Cmd::CompleteCollection {
+                collection_id,
+                collected,
+            })
+            .await
+            .context("Cannot handle CompleteCollection command")
+    }
+    .await;
+    if !matches!(completed, Ok(Ok(_))) {
+        error!(%collection_id, "Cannot complete collection");
+    }
+
+    true
+}
+
+async fn refund<A>(account_factory: &A, account_id: Uuid, collection_id: Uuid, amount: EuroCent)
+where
+    A: AccountFactory,
+{
+    debug!(%collection_id, "Refunding");
+
+    let deposited = async {
+        account_factory
+            .get_with_priority(account_id, Priority::Bulk)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(account::Cmd::Deposit(collection_id, amount, None, None))
+            .await
+            .context("Cannot handle Deposit command")
+    }
+    .await;
+    if !matches!(deposited, Ok(Ok(_))) {
+        error!(%collection_id, "Cannot refund collection");
+    }
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
@@ -0,0 +1,51 @@
+pub mod collection_processor;
+
+use crate::{
+    domain::{clock::Clock, mandate::Mandate},
+    infra::{
+        lru_cache_factory::{self, Evictor, LruCacheEntityFactory, ManagedEntity},
+        namespace::EntityType,
+    },
+};
+use eventsourced::EntityRef;
+use std::{future::Future, num::NonZeroU64, sync::Arc};
+use uuid::Uuid;
+
+/// A factory for [Mandate]s, either creating new ones or returning existing managed ones.
+pub trait MandateFactory: Clone + Send + Sync + 'static {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Create a new [Mandate] or return an existing managed one.
+    fn get(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = Result<EntityRef<Mandate>, Self::Error>> + Send + '_;
+}
+
+impl MandateFactory for LruCacheEntityFactory<Mandate> {
+    type Error = lru_cache_factory::Error;
+
+    async fn get(&self, id: Uuid) -> Result<EntityRef<Mandate>, Self::Error> {
+        self.entity(id).await
+    }
+}
+
+impl ManagedEntity for Mandate {
+    const ENTITY_TYPE: EntityType = EntityType::Mandate;
+
+    type Settings = ();
+
+    type Observer = ();
+
+    type Ref = EntityRef<Mandate>;
+
+    fn create(_settings: &(), clock: Arc<dyn Clock>, _snapshot_after: Option<NonZeroU64>) -> Self {
+        Mandate::default().with_clock(clock)
+    }
+
+    fn observe(&self) -> Self::Observer {}
+
+    fn into_ref(entity: EntityRef<Self>, _observer: (), _evictor: Evictor) -> Self::Ref {
+        entity
+    }
+}
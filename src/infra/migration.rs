@@ -0,0 +1,353 @@
+use crate::{
+    domain::{
+        account::{self, ExternalRef, ACCOUNT_LIFECYCLE_TAG, ACCOUNT_TX_TAG},
+        currency::Currency,
+        customer::CustomerId,
+        euro_cent::EuroCent,
+        iban::{BankCode, Iban},
+        tenant::TenantId,
+    },
+    infra::namespace::{EntityType, Namespace},
+};
+use anyhow::{Context, Result};
+use eventsourced::{convert, EvtLog};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// A record of legacy data: an account or one of its transactions.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Record {
+    Account {
+        id: Uuid,
+        tenant: TenantId,
+        customer: Option<CustomerId>,
+        /// Defaults to the IBAN derived from the ID.
+        iban: Option<Iban>,
+        #[serde(default)]
+        currency: Currency,
+        #[serde(default)]
+        opening_balance: EuroCent,
+        external_ref: Option<ExternalRef>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Deposit {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Withdrawal {
+        id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+/// Format of the legacy data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON object per line.
+    Ndjson,
+    /// A header line with the field names followed by one record per line. Empty values are
+    /// omitted and quoting is not supported.
+    Csv,
+}
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub accounts: usize,
+    pub evts: usize,
+    /// Events already written by a previous, interrupted import.
+    pub skipped: usize,
+}
+
+/// Errors for legacy data, with the number of the offending line or record.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Line {0}: cannot parse record: {1}")]
+    Parse(usize, String),
+
+    #[error("Record {0}: account {1} already defined")]
+    DuplicateAccount(usize, Uuid),
+
+    #[error("Record {0}: unknown account {1}, accounts must be defined before their transactions")]
+    UnknownAccount(usize, Uuid),
+
+    #[error("Record {0}: amount must be positive")]
+    ZeroAmount(usize),
+
+    #[error("Record {0}: timestamp before the previous one of account {1}")]
+    OutOfOrder(usize, Uuid),
+
+    #[error("Record {0}: withdrawal exceeds balance {1} of account {2}")]
+    Overdrawn(usize, EuroCent, Uuid),
+}
+
+/// Parse the given legacy data into [Record]s.
+pub fn parse(data: &str, format: Format) -> Result<Vec<Record>, Error> {
+    let mut lines = data
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    match format {
+        Format::Ndjson => lines
+            .map(|(n, line)| {
+                serde_json::from_str(line).map_err(|error| Error::Parse(n, error.to_string()))
+            })
+            .collect(),
+
+        Format::Csv => {
+            let Some((_, header)) = lines.next() else {
+                return Ok(vec![]);
+            };
+            let names = header.split(',').map(str::trim).collect::<Vec<_>>();
+            lines
+                .map(|(n, line)| {
+                    let values = line.split(',').map(str::trim).collect::<Vec<_>>();
+                    if values.len() != names.len() {
+                        return Err(Error::Parse(n, "wrong number of values".to_string()));
+                    }
+                    let record = names
+                        .iter()
+                        .zip(values)
+                        .filter(|(_, value)| !value.is_empty())
+                        .map(|(name, value)| {
+                            let value = match *name {
+                                "amount" | "opening_balance" => value
+                                    .parse::<u64>()
+                                    .map(Value::from)
+                                    .unwrap_or_else(|_| Value::from(value)),
+                                _ => Value::from(value),
+                            };
+                            (name.to_string(), value)
+                        })
+                        .collect::<Map<_, _>>();
+                    serde_json::from_value(Value::Object(record))
+                        .map_err(|error| Error::Parse(n, error.to_string()))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Validate the given [Record]s and turn them into the events for each account, in the order of
+/// the records. The events keep the legacy timestamps.
+pub fn plan(
+    records: Vec<Record>,
+    bank_code: BankCode,
+) -> Result<Vec<(Uuid, Vec<account::Evt>)>, Error> {
+    let mut accounts = Vec::<(Uuid, Vec<account::Evt>)>::new();
+    let mut indexes = HashMap::<Uuid, (usize, EuroCent, OffsetDateTime)>::new();
+
+    for (n, record) in records.into_iter().enumerate() {
+        let n = n + 1;
+        match record {
+            Record::Account {
+                id,
+                tenant,
+                customer,
+                iban,
+                currency,
+                opening_balance,
+                external_ref,
+                at,
+            } => {
+                if indexes.contains_key(&id) {
+                    return Err(Error::DuplicateAccount(n, id));
+                }
+                indexes.insert(id, (accounts.len(), opening_balance, at));
+                accounts.push((
+                    id,
+                    vec![account::Evt::Created {
+                        id,
+                        tenant,
+                        customer,
+                        iban: iban.unwrap_or_else(|| Iban::for_account(bank_code, id)),
+                        currency,
+                        opening_balance,
+                        external_ref,
+                        product: account::Product::Standard,
+                        sandbox: false,
+                        at,
+                    }],
+                ));
+            }
+
+            Record::Deposit {
+                id,
+                account_id,
+                amount,
+                at,
+            }
+            | Record::Withdrawal {
+                id,
+                account_id,
+                amount,
+                at,
+            } => {
+                let deposit = matches!(record, Record::Deposit { .. });
+                let (index, balance, last_at) = indexes
+                    .get_mut(&account_id)
+                    .ok_or(Error::UnknownAccount(n, account_id))?;
+                if amount == EuroCent::default() {
+                    return Err(Error::ZeroAmount(n));
+                }
+                if at < *last_at {
+                    return Err(Error::OutOfOrder(n, account_id));
+                }
+                let old_balance = *balance;
+                let evt = if deposit {
+                    *balance = old_balance + amount;
+                    account::Evt::Deposited {
+                        id,
+                        account_id,
+                        old_balance,
+                        amount,
+                        value_date: None,
+                        channel: None,
+                        at,
+                    }
+                } else {
+                    if old_balance < amount {
+                        return Err(Error::Overdrawn(n, old_balance, account_id));
+                    }
+                    *balance = old_balance - amount;
+                    account::Evt::Withdrawn {
+                        id,
+                        account_id,
+                        old_balance,
+                        amount,
+                        value_date: None,
+                        channel: None,
+                        at,
+                    }
+                };
+                *last_at = at;
+                accounts[*index].1.push(evt);
+            }
+        }
+    }
+
+    Ok(accounts)
+}
+
+/// Write the planned events of each account to the given [EvtLog], unless `dry_run`. Events
+/// already written for an account, e.g. by an interrupted import, are skipped, such that imports
+/// can be resumed by running them again. Must not run while accounts are being served, because
+/// running entities would not see the written events.
+pub async fn import<L>(
+    mut evt_log: L,
+    namespace: Namespace,
+    accounts: Vec<(Uuid, Vec<account::Evt>)>,
+    dry_run: bool,
+) -> Result<ImportReport>
+where
+    L: EvtLog,
+{
+    let mut report = ImportReport::default();
+
+    for (id, evts) in accounts {
+        report.accounts += 1;
+        let stream_id = namespace.id(EntityType::Account, id);
+
+        let written = if dry_run {
+            0
+        } else {
+            evt_log
+                .last_seq_no(stream_id)
+                .await
+                .context(format!("Cannot get last sequence number for account {id}"))?
+                .map(|seq_no| seq_no.as_u64() as usize)
+                .unwrap_or_default()
+        };
+        if written > 0 {
+            debug!(%id, written, "Resuming import of account");
+        }
+
+        for evt in evts.into_iter().skip(written) {
+            report.evts += 1;
+            if dry_run {
+                continue;
+            }
+            let tag = match evt {
+                account::Evt::Created { .. } => ACCOUNT_LIFECYCLE_TAG,
+                _ => ACCOUNT_TX_TAG,
+            };
+            evt_log
+                .persist(
+                    stream_id,
+                    &evt,
+                    Some(tag.to_string()),
+                    &convert::serde_json::to_bytes::<account::Evt>,
+                )
+                .await
+                .context(format!("Cannot persist event for account {id}"))?;
+        }
+        report.skipped += written;
+    }
+
+    info!(?report, dry_run, "Imported legacy data");
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_plan() {
+        let id = Uuid::now_v7();
+        let tenant = Uuid::now_v7();
+        let csv = format!(
+            "kind,id,account_id,tenant,opening_balance,amount,at
+             account,{id},,{tenant},100,,2020-01-01T00:00:00Z
+             deposit,{},{id},,,42,2020-01-02T00:00:00Z
+             withdrawal,{},{id},,,142,2020-01-03T00:00:00Z",
+            Uuid::now_v7(),
+            Uuid::now_v7()
+        );
+        let records = parse(&csv, Format::Csv).unwrap();
+        assert_eq!(records.len(), 3);
+
+        let ndjson = format!(
+            r#"{{"kind":"account","id":"{id}","tenant":"{tenant}","opening_balance":100,"at":"2020-01-01T00:00:00Z"}}"#
+        );
+        assert!(parse(&ndjson, Format::Ndjson).is_ok());
+
+        let bank_code = BankCode::try_from(12345678).unwrap();
+        let accounts = plan(records.clone(), bank_code).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].1.len(), 3);
+        assert!(matches!(
+            accounts[0].1[2],
+            account::Evt::Withdrawn { old_balance, .. } if old_balance == 142u64.into()
+        ));
+
+        // Withdrawals must not exceed the balance.
+        let mut overdrawn = records.clone();
+        overdrawn.swap(1, 2);
+        assert!(matches!(
+            plan(overdrawn, bank_code),
+            Err(Error::Overdrawn(2, _, _))
+        ));
+
+        // Transactions need a known account.
+        assert!(matches!(
+            plan(records[1..].to_vec(), bank_code),
+            Err(Error::UnknownAccount(1, _))
+        ));
+    }
+}
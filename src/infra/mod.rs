@@ -1,2 +1,44 @@
 pub mod account;
+#[cfg(feature = "auth")]
+pub mod auth;
+pub mod backfill;
+pub mod backup;
+pub mod books;
+pub mod cluster;
+pub mod cmd_metrics;
+pub mod consent;
+pub mod dead_letter;
+pub mod delivery;
+pub mod dispute;
+#[cfg(feature = "dynamodb")]
+pub mod dynamodb;
+pub mod error_code;
+pub mod gl_export;
+#[cfg(feature = "nats")]
+pub mod invalidation;
+pub mod leader;
+pub mod ledger;
+pub mod loan;
+pub mod lru_cache_factory;
+pub mod mandate;
+pub mod migration;
+pub mod namespace;
+pub mod notification;
+#[cfg(feature = "oidc")]
+pub mod oidc;
+pub mod projection;
+pub mod quote;
+pub mod receipt;
+pub mod reporting;
+pub mod retention;
 pub mod server;
+pub mod settlement;
+pub mod snapshot_store;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod standby;
+pub mod step_up;
+pub mod term_deposit;
+pub mod transactions;
+pub mod transfer;
+pub mod treasury;
@@ -0,0 +1,116 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Root namespace from which the namespaces of the [EntityType]s are derived.
+const ROOT: Uuid = Uuid::from_u128(0x8f0c_2a6e_5b1d_4c37_9e42_d7a1_3f60_b58c);
+
+/// The types of entities sharing the event log and the snapshot store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityType {
+    Account,
+    Books,
+    Consent,
+    Dispute,
+    Loan,
+    Mandate,
+    TermDeposit,
+    Transfer,
+}
+
+impl EntityType {
+    /// The name of this entity type, which by convention is the prefix of all its tags.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntityType::Account => "account",
+            EntityType::Books => "books",
+            EntityType::Consent => "consent",
+            EntityType::Dispute => "dispute",
+            EntityType::Loan => "loan",
+            EntityType::Mandate => "mandate",
+            EntityType::TermDeposit => "term-deposit",
+            EntityType::Transfer => "transfer",
+        }
+    }
+}
+
+/// Maps entity IDs to the IDs under which entities are stored, i.e. their streams in the event
+/// log and their keys in the snapshot store. If namespacing is enabled, these are name-based UUIDs
+/// derived from the [EntityType] and the entity ID, such that entities of different types never
+/// share streams or snapshots, even if their IDs collide. Tags are namespaced by convention.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Namespace {
+    namespaced: bool,
+}
+
+impl Namespace {
+    #[allow(missing_docs)]
+    pub fn new(config: Config) -> Self {
+        Self {
+            namespaced: config.namespaced,
+        }
+    }
+
+    /// The ID under which the entity of the given type with the given ID is stored.
+    pub fn id(self, entity_type: EntityType, id: Uuid) -> Uuid {
+        if self.namespaced {
+            let namespace = Uuid::new_v5(&ROOT, entity_type.as_str().as_bytes());
+            Uuid::new_v5(&namespace, id.as_bytes())
+        } else {
+            id
+        }
+    }
+}
+
+/// Configuration for [Namespace].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Existing entities written without namespacing are no longer found once this is enabled.
+    namespaced: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        account::{ACCOUNT_LIFECYCLE_TAG, ACCOUNT_TX_TAG},
+        books::BOOKS_TAG,
+        consent::CONSENT_TAG,
+        dispute::DISPUTE_TAG,
+        loan::LOAN_TAG,
+        mandate::MANDATE_TAG,
+        term_deposit::TERM_DEPOSIT_TAG,
+        transfer::TRANSFER_TAG,
+    };
+
+    #[test]
+    fn test_id() {
+        let id = Uuid::now_v7();
+
+        let namespace = Namespace::default();
+        assert_eq!(namespace.id(EntityType::Account, id), id);
+
+        let namespace = Namespace::new(Config { namespaced: true });
+        let account_id = namespace.id(EntityType::Account, id);
+        assert_ne!(account_id, id);
+        assert_eq!(namespace.id(EntityType::Account, id), account_id);
+        assert_ne!(namespace.id(EntityType::Loan, id), account_id);
+    }
+
+    #[test]
+    fn test_tags() {
+        for (entity_type, tag) in [
+            (EntityType::Account, ACCOUNT_LIFECYCLE_TAG),
+            (EntityType::Account, ACCOUNT_TX_TAG),
+            (EntityType::Books, BOOKS_TAG),
+            (EntityType::Consent, CONSENT_TAG),
+            (EntityType::Dispute, DISPUTE_TAG),
+            (EntityType::Loan, LOAN_TAG),
+            (EntityType::Mandate, MANDATE_TAG),
+            (EntityType::TermDeposit, TERM_DEPOSIT_TAG),
+            (EntityType::Transfer, TRANSFER_TAG),
+        ] {
+            assert!(tag.starts_with(entity_type.as_str()));
+        }
+    }
+}
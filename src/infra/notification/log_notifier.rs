@@ -0,0 +1,13 @@
+use super::Notifier;
+use crate::domain::{account::NotificationChannels, alert::Alert, redaction::Redacted};
+use tracing::info;
+
+/// [Notifier] only logging alerts, e.g. for development.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    async fn notify(&self, alert: Alert, channels: NotificationChannels) {
+        info!(alert = ?Redacted(&alert), ?channels, "Notifying");
+    }
+}
@@ -0,0 +1,106 @@
+pub mod log_notifier;
+
+use crate::{
+    domain::{
+        account::{self, NotificationChannels},
+        alert::{Alert, AlertRules},
+        clock::Clock,
+        redaction::Redacted,
+    },
+    infra::leader::Leadership,
+};
+use anyhow::Context;
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::{stream, StreamExt};
+use metrics::counter;
+use std::{collections::HashMap, convert::Infallible, future::Future, sync::Arc};
+use tokio::{pin, task};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Sends [Alert]s to account holders via the given channels.
+pub trait Notifier: Clone + Send + Sync + 'static {
+    /// Send the given [Alert] via the given channels.
+    fn notify(
+        &self,
+        alert: Alert,
+        channels: NotificationChannels,
+    ) -> impl Future<Output = ()> + Send + '_;
+}
+
+/// Spawn evaluating the [AlertRules] of all accounts and sending fired alerts via the given
+/// [Notifier]. Rules are evaluated on every node, such that the state is up to date after a
+/// leadership change, but only the leader sends alerts. Alerts for events from before spawning,
+/// i.e. replayed ones, are not sent.
+pub fn spawn<L, N>(clock: Arc<dyn Clock>, evt_log: L, notifier: N, leadership: Leadership)
+where
+    L: EvtLog,
+    N: Notifier,
+{
+    task::spawn(async move {
+        let started_at = clock.now();
+
+        let evts = async {
+            let lifecycle_evts = evt_log
+                .evts_by_tag::<Bytes, _, _, _>(account::ACCOUNT_LIFECYCLE_TAG, SeqNo::MIN, raw)
+                .await?;
+            let tx_evts = evt_log
+                .evts_by_tag::<Bytes, _, _, _>(account::ACCOUNT_TX_TAG, SeqNo::MIN, raw)
+                .await?;
+            Ok::<_, L::Error>(stream::select(lifecycle_evts, tx_evts))
+        }
+        .await
+        .context("Cannot create events-by-tag query");
+
+        let evts = match evts {
+            Ok(evts) => evts,
+            Err(error) => {
+                error!(error = format!("{error:#}"), "Cannot evaluate alert rules");
+                return;
+            }
+        };
+
+        let mut rules = HashMap::<Uuid, AlertRules>::new();
+        let mut channels = HashMap::<Uuid, NotificationChannels>::new();
+        pin!(evts);
+        while let Some(evt) = evts.next().await {
+            let evt = match evt {
+                Ok((_, evt)) => evt,
+                Err(error) => {
+                    error!(error = format!("{error:#}"), "Cannot get next event");
+                    break;
+                }
+            };
+            // Poison events are dead lettered by the account summaries projection.
+            let Ok(evt) = serde_json::from_slice::<account::Evt>(&evt) else {
+                continue;
+            };
+
+            let account_id = evt.account_id();
+            if let account::Evt::NotificationPrefsSet {
+                notification_prefs, ..
+            } = &evt
+            {
+                channels.insert(account_id, notification_prefs.channels);
+            }
+
+            let alerts = rules.entry(account_id).or_default().evaluate(&evt);
+            if evt.at() < started_at || !leadership.is_leader() {
+                continue;
+            }
+            for alert in alerts {
+                debug!(alert = ?Redacted(&alert), "Alert fired");
+                counter!("alerts", 1);
+                let channels = channels.get(&account_id).copied().unwrap_or_default();
+                notifier.notify(alert, channels).await;
+            }
+        }
+
+        error!("Alert rules evaluation terminated");
+    });
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, DecodingKey, Validation};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use std::{
+    num::NonZeroU64,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::{task, time};
+use tracing::{debug, error};
+
+/// Minimum interval between refreshing the JWKS because of an unknown key ID, such that tokens with
+/// bogus key IDs cannot trigger a flood of requests to the issuer.
+const MIN_JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Validates bearer tokens issued by an OIDC provider, using the keys from the JWKS found via the
+/// discovery document of the configured issuer. Keys are refreshed periodically and whenever a
+/// token refers to an unknown key ID, such that key rotation is picked up automatically.
+#[derive(Debug, Clone)]
+pub struct Oidc {
+    config: Arc<Config>,
+    client: reqwest::Client,
+    jwks_uri: Arc<str>,
+    jwks: Arc<RwLock<(JwkSet, Instant)>>,
+}
+
+impl Oidc {
+    /// Fetch the discovery document and the JWKS of the configured issuer and spawn periodically
+    /// refreshing the JWKS.
+    pub async fn discover(config: Config) -> Result<Self> {
+        let client = reqwest::Client::new();
+
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            config.issuer_url.trim_end_matches('/')
+        );
+        let discovery = client
+            .get(discovery_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Cannot get OIDC discovery document")?
+            .json::<Discovery>()
+            .await
+            .context("Cannot parse OIDC discovery document")?;
+        let jwks = fetch_jwks(&client, &discovery.jwks_uri)
+            .await
+            .context("Cannot get JWKS")?;
+
+        let oidc = Self {
+            config: Arc::new(config),
+            client,
+            jwks_uri: discovery.jwks_uri.into(),
+            jwks: Arc::new(RwLock::new((jwks, Instant::now()))),
+        };
+
+        let refresh_interval = Duration::from_secs(oidc.config.jwks_refresh_secs.get());
+        task::spawn({
+            let oidc = oidc.clone();
+            async move {
+                let mut interval = time::interval(refresh_interval);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if let Err(error) = oidc.refresh_jwks().await {
+                        error!(error = format!("{error:#}"), "Cannot refresh JWKS");
+                    }
+                }
+            }
+        });
+
+        Ok(oidc)
+    }
+
+    /// Validate the given token, i.e. its signature, issuer, audience and expiry, the latter with
+    /// the configured clock skew tolerance, and return its [Claims].
+    pub async fn validate(&self, token: &str) -> Result<Claims, Error> {
+        let header = decode_header(token).map_err(Error::InvalidToken)?;
+        let kid = header.kid.ok_or(Error::MissingKeyId)?;
+
+        let mut key = self.decoding_key(&kid)?;
+        if key.is_none() {
+            let last_refresh = self.jwks.read().1;
+            if last_refresh.elapsed() >= MIN_JWKS_REFRESH_INTERVAL {
+                debug!(kid, "Unknown key ID, refreshing JWKS");
+                self.refresh_jwks().await?;
+                key = self.decoding_key(&kid)?;
+            }
+        }
+        let key = key.ok_or(Error::UnknownKeyId(kid))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer_url]);
+        validation.set_audience(&[&self.config.audience]);
+        validation.leeway = self.config.leeway_secs;
+
+        decode::<Claims>(token, &key, &validation)
+            .map(|token_data| token_data.claims)
+            .map_err(Error::InvalidToken)
+    }
+
+    fn decoding_key(&self, kid: &str) -> Result<Option<DecodingKey>, Error> {
+        self.jwks
+            .read()
+            .0
+            .find(kid)
+            .map(DecodingKey::from_jwk)
+            .transpose()
+            .map_err(Error::InvalidToken)
+    }
+
+    async fn refresh_jwks(&self) -> Result<(), Error> {
+        let jwks = fetch_jwks(&self.client, &self.jwks_uri)
+            .await
+            .map_err(Error::Jwks)?;
+        *self.jwks.write() = (jwks, Instant::now());
+        Ok(())
+    }
+}
+
+/// Claims of validated tokens.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+
+    /// Space separated scopes.
+    #[serde(default)]
+    pub scope: String,
+}
+
+impl Claims {
+    /// The scopes of the token.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.split_whitespace()
+    }
+}
+
+/// OIDC configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    issuer_url: String,
+    audience: String,
+    leeway_secs: u64,
+    jwks_refresh_secs: NonZeroU64,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Invalid token")]
+    InvalidToken(#[source] jsonwebtoken::errors::Error),
+
+    #[error("Token has no key ID")]
+    MissingKeyId,
+
+    #[error("Unknown key ID {0}")]
+    UnknownKeyId(String),
+
+    #[error("Cannot get JWKS")]
+    Jwks(#[source] reqwest::Error),
+}
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+async fn fetch_jwks(client: &reqwest::Client, jwks_uri: &str) -> Result<JwkSet, reqwest::Error> {
+    client
+        .get(jwks_uri)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<JwkSet>()
+        .await
+}
@@ -0,0 +1,916 @@
+#[cfg(feature = "sled")]
+pub mod sled_projection_store;
+
+use crate::{
+    domain::{clock::Clock, id::IdGenerator},
+    infra::dead_letter::{DeadLetter, DeadLetterQueue},
+};
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::{stream, StreamExt};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    convert::Infallible,
+    fmt::Debug,
+    future::{self, Future},
+    hash::{Hash, Hasher},
+    num::{NonZeroU64, NonZeroUsize},
+    sync::Arc,
+};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tokio::{
+    select,
+    sync::{mpsc, oneshot},
+    task::{self, JoinHandle},
+    time::sleep,
+};
+use tracing::{debug, error, warn};
+
+/// Registry for the [ProjectionStatus]es of all projections, e.g. to expose their lag and
+/// throughput.
+#[derive(Debug, Clone, Default)]
+pub struct Projections(Arc<RwLock<BTreeMap<&'static str, ProjectionStatus>>>);
+
+impl Projections {
+    /// Register the projection with the given name and [ErrorPolicy] as running.
+    pub fn register(&self, projection: &'static str, error_policy: ErrorPolicy) {
+        let mut projections = self.0.write();
+        let status = projections
+            .entry(projection)
+            .or_insert_with(|| ProjectionStatus::new(projection));
+        status.running = true;
+        status.error_policy = error_policy;
+    }
+
+    /// Record that the given projection has handled the event with the given sequence number and
+    /// timestamp at the given time.
+    pub fn record(
+        &self,
+        projection: &'static str,
+        seq_no: u64,
+        evt_at: OffsetDateTime,
+        now: OffsetDateTime,
+    ) {
+        let mut projections = self.0.write();
+        let status = projections
+            .entry(projection)
+            .or_insert_with(|| ProjectionStatus::new(projection));
+        status.record(seq_no, evt_at, now);
+
+        metrics::counter!("projection_evts_total", 1, "projection" => projection);
+        metrics::gauge!("projection_seq_no", seq_no as f64, "projection" => projection);
+        metrics::gauge!(
+            "projection_lag_seconds",
+            status.lag_ms as f64 / 1_000.0,
+            "projection" => projection
+        );
+        metrics::gauge!(
+            "projection_evts_per_second",
+            status.evts_per_sec as f64,
+            "projection" => projection
+        );
+    }
+
+    /// Register the given projection as terminated.
+    pub fn terminate(&self, projection: &'static str) {
+        if let Some(status) = self.0.write().get_mut(projection) {
+            status.running = false;
+        }
+    }
+
+    /// The [ProjectionStatus]es of all projections.
+    pub fn statuses(&self) -> Vec<ProjectionStatus> {
+        self.0.read().values().cloned().collect()
+    }
+}
+
+/// Status of a projection: the last handled event, the lag, i.e. the time between appending and
+/// handling the last event, and the throughput over the last full second.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectionStatus {
+    pub name: &'static str,
+    pub running: bool,
+    pub error_policy: ErrorPolicy,
+    pub evt_count: u64,
+    pub last_seq_no: Option<u64>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_evt_at: Option<OffsetDateTime>,
+    pub lag_ms: i64,
+    pub evts_per_sec: u64,
+
+    #[serde(skip)]
+    window_start: Option<OffsetDateTime>,
+
+    #[serde(skip)]
+    window_count: u64,
+}
+
+impl ProjectionStatus {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            running: false,
+            error_policy: ErrorPolicy::default(),
+            evt_count: 0,
+            last_seq_no: None,
+            last_evt_at: None,
+            lag_ms: 0,
+            evts_per_sec: 0,
+            window_start: None,
+            window_count: 0,
+        }
+    }
+
+    fn record(&mut self, seq_no: u64, evt_at: OffsetDateTime, now: OffsetDateTime) {
+        self.evt_count += 1;
+        self.last_seq_no = Some(seq_no);
+        self.last_evt_at = Some(evt_at);
+        self.lag_ms = (now - evt_at).whole_milliseconds() as i64;
+
+        match self.window_start {
+            Some(window_start) if now - window_start < Duration::SECOND => self.window_count += 1,
+
+            Some(window_start) => {
+                // Unless the completed window is directly followed by this event, there have been
+                // seconds without any events.
+                self.evts_per_sec = if now - window_start < Duration::seconds(2) {
+                    self.window_count
+                } else {
+                    0
+                };
+                self.window_start = Some(now);
+                self.window_count = 1;
+            }
+
+            None => {
+                self.window_start = Some(now);
+                self.window_count = 1;
+            }
+        }
+    }
+}
+
+/// Handles the events of a projection run by a [ProjectionRunner].
+pub trait ProjectionHandler: Send + 'static {
+    /// Decoded events.
+    type Evt: Send;
+
+    /// Decode the given event.
+    fn decode(&self, evt: &[u8]) -> Result<Self::Evt, serde_json::Error>;
+
+    /// Handle the given event with the given sequence number and return its timestamp for
+    /// recording progress, unless recorded otherwise, e.g. by workers. Errors are treated according
+    /// to the [ErrorPolicy] of the projection.
+    fn handle(
+        &mut self,
+        seq_no: u64,
+        evt: Self::Evt,
+    ) -> impl Future<Output = anyhow::Result<Option<OffsetDateTime>>> + Send + '_;
+
+    /// Wait until all handled events are reflected in the state, e.g. applied by workers, before a
+    /// snapshot is taken.
+    fn flush(&mut self) -> impl Future<Output = anyhow::Result<()>> + Send + '_ {
+        future::ready(Ok(()))
+    }
+
+    /// A snapshot of the state of this projection to be saved in a [ProjectionStore], if
+    /// supported.
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Restore the state of this projection from the given snapshot and return whether supported.
+    fn restore(&mut self, _snapshot: serde_json::Value) -> Result<bool, serde_json::Error> {
+        Ok(false)
+    }
+}
+
+/// Store for the snapshots of projections, i.e. their state along with their checkpoints, such
+/// that projections can resume after a restart instead of handling all events again.
+pub trait ProjectionStore: Debug + Send + Sync + 'static {
+    /// The latest snapshot of the projection with the given name, if any.
+    fn load(&self, projection: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Save the given snapshot of the projection with the given name, replacing the former one.
+    fn save(&self, projection: &str, snapshot: &[u8]) -> anyhow::Result<()>;
+
+    /// Number of events after which projections save a snapshot.
+    fn snapshot_after(&self) -> NonZeroU64;
+
+    /// Remove the snapshots of all projections, such that these are rebuilt from the event log,
+    /// e.g. after restoring a backup.
+    fn clear(&self) -> anyhow::Result<()>;
+}
+
+/// Snapshot of a projection as saved in a [ProjectionStore].
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    checkpoints: HashMap<String, u64>,
+    state: serde_json::Value,
+}
+
+/// What to do with events which cannot be decoded or handled. Events which cannot be decoded are
+/// always added to the [DeadLetterQueue], as retrying these is pointless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorPolicy {
+    /// Add failed events to the [DeadLetterQueue] and continue with the next one.
+    #[default]
+    Skip,
+
+    /// Retry handling failed events up to the given number of times with the backoff of the
+    /// [RestartConfig], then halt; events which cannot be decoded are skipped.
+    Retry(NonZeroUsize),
+
+    /// Halt the projection.
+    Halt,
+}
+
+/// The [ErrorPolicy] for each projection, falling back to a default one, which in turn defaults
+/// to [ErrorPolicy::Skip].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ErrorPolicies {
+    #[serde(default)]
+    default: ErrorPolicy,
+
+    /// Error policies by projection name, e.g. "account-summaries".
+    #[serde(default)]
+    projections: HashMap<String, ErrorPolicy>,
+}
+
+impl ErrorPolicies {
+    /// The [ErrorPolicy] for the projection with the given name.
+    pub fn get(&self, projection: &str) -> ErrorPolicy {
+        self.projections
+            .get(projection)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// How to restart failed events-by-tag queries, e.g. while the event log is being restarted:
+/// up to `max_restarts` times in a row with an exponential backoff between the given bounds.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RestartConfig {
+    max_restarts: usize,
+    min_backoff_millis: NonZeroU64,
+    max_backoff_millis: NonZeroU64,
+}
+
+impl RestartConfig {
+    /// The backoff before the next restart, counting it in the given number of restarts in a row,
+    /// or `None`, if exhausted.
+    pub fn next_backoff(&self, restarts: &mut usize) -> Option<std::time::Duration> {
+        if *restarts >= self.max_restarts {
+            return None;
+        }
+
+        *restarts += 1;
+        Some(self.backoff(*restarts))
+    }
+
+    /// The backoff before the given restart, starting with 1.
+    fn backoff(&self, restart: usize) -> std::time::Duration {
+        let backoff = self
+            .min_backoff_millis
+            .get()
+            .saturating_mul(1 << (restart.saturating_sub(1)).min(32))
+            .min(self.max_backoff_millis.get());
+        std::time::Duration::from_millis(backoff)
+    }
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            max_restarts: 0,
+            min_backoff_millis: NonZeroU64::MIN,
+            max_backoff_millis: NonZeroU64::MIN,
+        }
+    }
+}
+
+/// Runs a projection: queries the events with the given tags, decodes them and hands them to a
+/// [ProjectionHandler], records progress in [Projections] and treats events which cannot be
+/// decoded or handled according to its [ErrorPolicy]. The last handled sequence number per tag is
+/// kept as checkpoint, such that failed queries can be restarted without handling events twice.
+pub struct ProjectionRunner<L, D> {
+    name: &'static str,
+    tags: Vec<&'static str>,
+    error_policy: ErrorPolicy,
+    restart: RestartConfig,
+    store: Option<Arc<dyn ProjectionStore>>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    evt_log: L,
+    dead_letter_queue: D,
+    projections: Projections,
+}
+
+impl<L, D> ProjectionRunner<L, D>
+where
+    L: EvtLog,
+    D: DeadLetterQueue,
+{
+    /// By default failed events are skipped and failed queries are not restarted.
+    pub fn new(
+        name: &'static str,
+        tags: Vec<&'static str>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        evt_log: L,
+        dead_letter_queue: D,
+        projections: Projections,
+    ) -> Self {
+        Self {
+            name,
+            tags,
+            error_policy: ErrorPolicy::Skip,
+            restart: RestartConfig::default(),
+            store: None,
+            clock,
+            ids,
+            evt_log,
+            dead_letter_queue,
+            projections,
+        }
+    }
+
+    /// Use the given [ErrorPolicy].
+    pub fn with_error_policy(self, error_policy: ErrorPolicy) -> Self {
+        Self {
+            error_policy,
+            ..self
+        }
+    }
+
+    /// Restart failed queries from the checkpoints according to the given [RestartConfig]. The
+    /// number of restarts is reset once an event has been handled again.
+    pub fn with_restart(self, restart: RestartConfig) -> Self {
+        Self { restart, ..self }
+    }
+
+    /// Save snapshots in the given [ProjectionStore], if any, and resume from the latest one.
+    pub fn with_store(self, store: Option<Arc<dyn ProjectionStore>>) -> Self {
+        Self { store, ..self }
+    }
+
+    /// Spawn the projection with the given [ProjectionHandler]. The returned [JoinHandle]
+    /// completes once the projection has terminated.
+    pub fn spawn<H>(self, mut handler: H) -> JoinHandle<()>
+    where
+        H: ProjectionHandler,
+    {
+        task::spawn(async move {
+            let name = self.name;
+            let mut replays = self.dead_letter_queue.register(name).await;
+            self.projections.register(name, self.error_policy);
+
+            let mut checkpoints = vec![None::<SeqNo>; self.tags.len()];
+            if let Some(store) = &self.store {
+                match restore(store.as_ref(), name, &self.tags, &mut handler) {
+                    Ok(Some(restored)) => {
+                        debug!(projection = name, ?restored, "Restored projection");
+                        checkpoints = restored;
+                    }
+                    Ok(None) => {}
+                    Err(error) => warn!(
+                        projection = name,
+                        error = format!("{error:#}"),
+                        "Cannot restore projection, handling all events"
+                    ),
+                }
+            }
+            let mut handled = 0u64;
+            let mut restarts = 0;
+            'run: loop {
+                let mut tag_evts = Vec::with_capacity(self.tags.len());
+                for (n, tag) in self.tags.iter().enumerate() {
+                    let from_seq_no = checkpoints[n].map_or(SeqNo::MIN, |seq_no| seq_no.succ());
+                    match self
+                        .evt_log
+                        .evts_by_tag::<Bytes, _, _, _>(*tag, from_seq_no, raw)
+                        .await
+                    {
+                        Ok(evts) => tag_evts.push(Box::pin(evts.map(move |evt| (n, evt)))),
+
+                        Err(error) => {
+                            error!(
+                                projection = name,
+                                error = format!("{error:#}"),
+                                "Cannot create events-by-tag query"
+                            );
+                            if restart(name, &self.restart, &mut restarts).await {
+                                continue 'run;
+                            }
+                            break 'run;
+                        }
+                    }
+                }
+                let mut evts = stream::select_all(tag_evts);
+
+                loop {
+                    let (checkpoint, seq_no, evt) = select! {
+                        evt = evts.next() => match evt {
+                            Some((n, Ok((seq_no, evt)))) => (Some((n, seq_no)), seq_no.as_u64(), evt),
+
+                            Some((_, Err(error))) => {
+                                error!(
+                                    projection = name,
+                                    error = format!("{error:#}"),
+                                    "Cannot get next event"
+                                );
+                                if restart(name, &self.restart, &mut restarts).await {
+                                    continue 'run;
+                                }
+                                break 'run;
+                            }
+
+                            None => break 'run,
+                        },
+
+                        Some(dead_letter) = replays.recv() => {
+                            (None, dead_letter.seq_no, Bytes::from(dead_letter.evt))
+                        }
+                    };
+
+                    match self.handle(&mut handler, seq_no, &evt).await {
+                        Ok(()) => {}
+
+                        Err(Failure::Decode(error)) => {
+                            self.dead_letter(seq_no, &evt, error.to_string()).await;
+                            if self.error_policy == ErrorPolicy::Halt {
+                                break 'run;
+                            }
+                        }
+
+                        // Halting without dead-lettering, as the event is handled again once the
+                        // projection is restarted.
+                        Err(Failure::Handle(error)) if self.error_policy != ErrorPolicy::Skip => {
+                            error!(
+                                projection = name,
+                                seq_no,
+                                error = format!("{error:#}"),
+                                "Cannot handle event"
+                            );
+                            break 'run;
+                        }
+
+                        Err(Failure::Handle(error)) => {
+                            self.dead_letter(seq_no, &evt, format!("{error:#}")).await;
+                        }
+                    }
+
+                    if let Some((n, seq_no)) = checkpoint {
+                        checkpoints[n] = Some(seq_no);
+                        restarts = 0;
+                        handled += 1;
+                        if let Some(store) = &self.store {
+                            if handled % store.snapshot_after().get() == 0 {
+                                let saved = match handler.flush().await {
+                                    Ok(()) => save(
+                                        store.as_ref(),
+                                        name,
+                                        &self.tags,
+                                        &checkpoints,
+                                        &handler,
+                                    ),
+                                    Err(error) => Err(error),
+                                };
+                                saved.unwrap_or_else(|error| {
+                                    error!(
+                                        projection = name,
+                                        error = format!("{error:#}"),
+                                        "Cannot save snapshot"
+                                    )
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+
+            error!(projection = name, "Projection terminated");
+            self.projections.terminate(name);
+        })
+    }
+
+    /// Decode and handle the given event, retrying handling according to the [ErrorPolicy].
+    async fn handle<H>(&self, handler: &mut H, seq_no: u64, evt: &[u8]) -> Result<(), Failure>
+    where
+        H: ProjectionHandler,
+    {
+        let mut retries = 0;
+        loop {
+            let decoded = handler.decode(evt).map_err(Failure::Decode)?;
+            match handler.handle(seq_no, decoded).await {
+                Ok(evt_at) => {
+                    if let Some(evt_at) = evt_at {
+                        self.projections
+                            .record(self.name, seq_no, evt_at, self.clock.now());
+                    }
+                    return Ok(());
+                }
+
+                Err(error) => match self.error_policy {
+                    ErrorPolicy::Retry(max_retries) if retries < max_retries.get() => {
+                        retries += 1;
+                        let backoff = self.restart.backoff(retries);
+                        warn!(
+                            projection = self.name,
+                            seq_no,
+                            retries,
+                            ?backoff,
+                            error = format!("{error:#}"),
+                            "Retrying event"
+                        );
+                        metrics::counter!("projection_retries_total", 1, "projection" => self.name);
+                        sleep(backoff).await;
+                    }
+
+                    _ => return Err(Failure::Handle(error)),
+                },
+            }
+        }
+    }
+
+    async fn dead_letter(&self, seq_no: u64, evt: &[u8], error: String) {
+        let dead_letter = DeadLetter {
+            id: self.ids.next_id(),
+            projection: self.name.to_owned(),
+            seq_no,
+            evt: String::from_utf8_lossy(evt).into_owned(),
+            error,
+            at: self.clock.now(),
+        };
+        self.dead_letter_queue.push(dead_letter).await;
+    }
+}
+
+/// Failure of a [ProjectionHandler] for an event.
+enum Failure {
+    Decode(serde_json::Error),
+    Handle(anyhow::Error),
+}
+
+/// Whether to restart after a failed query, waiting for the backoff if so.
+async fn restart(name: &'static str, config: &RestartConfig, restarts: &mut usize) -> bool {
+    let Some(backoff) = config.next_backoff(restarts) else {
+        return false;
+    };
+
+    warn!(
+        projection = name,
+        restarts = *restarts,
+        ?backoff,
+        "Restarting projection"
+    );
+    metrics::counter!("projection_restarts_total", 1, "projection" => name);
+    sleep(backoff).await;
+    true
+}
+
+/// Restore the given handler from the latest snapshot, if any, returning the checkpoints.
+fn restore<H>(
+    store: &dyn ProjectionStore,
+    projection: &str,
+    tags: &[&'static str],
+    handler: &mut H,
+) -> anyhow::Result<Option<Vec<Option<SeqNo>>>>
+where
+    H: ProjectionHandler,
+{
+    let Some(snapshot) = store.load(projection)? else {
+        return Ok(None);
+    };
+    let snapshot = serde_json::from_slice::<Snapshot>(&snapshot)?;
+    if !handler.restore(snapshot.state)? {
+        return Ok(None);
+    }
+    let checkpoints = tags
+        .iter()
+        .map(|tag| {
+            snapshot
+                .checkpoints
+                .get(*tag)
+                .and_then(|seq_no| SeqNo::try_from(*seq_no).ok())
+        })
+        .collect();
+    Ok(Some(checkpoints))
+}
+
+/// Save a snapshot of the given handler along with the given checkpoints, if supported.
+fn save<H>(
+    store: &dyn ProjectionStore,
+    projection: &str,
+    tags: &[&'static str],
+    checkpoints: &[Option<SeqNo>],
+    handler: &H,
+) -> anyhow::Result<()>
+where
+    H: ProjectionHandler,
+{
+    let Some(state) = handler.snapshot() else {
+        return Ok(());
+    };
+    let checkpoints = tags
+        .iter()
+        .zip(checkpoints)
+        .filter_map(|(tag, seq_no)| seq_no.map(|seq_no| (tag.to_string(), seq_no.as_u64())))
+        .collect();
+    let snapshot = serde_json::to_vec(&Snapshot { checkpoints, state })?;
+    store.save(projection, &snapshot)
+}
+
+/// Pool of workers applying items in batches. Items are partitioned by key, such that items with
+/// the same key are applied in order, whereas items with different keys may be applied in parallel.
+#[derive(Debug)]
+pub struct PartitionedWorkers<T> {
+    item_sdrs: Vec<mpsc::Sender<WorkerMsg<T>>>,
+}
+
+#[derive(Debug)]
+enum WorkerMsg<T> {
+    Item(T),
+    Flush(oneshot::Sender<()>),
+}
+
+impl<T> PartitionedWorkers<T>
+where
+    T: Send + 'static,
+{
+    /// Spawn the given number of workers, each applying up to the given batch size of items at
+    /// once.
+    pub fn spawn<A>(workers: NonZeroUsize, batch_size: NonZeroUsize, apply: A) -> Self
+    where
+        A: Fn(Vec<T>) + Clone + Send + 'static,
+    {
+        let item_sdrs = (0..workers.get())
+            .map(|_| {
+                let (item_sdr, mut item_rcv) = mpsc::channel::<WorkerMsg<T>>(batch_size.get());
+                let apply = apply.clone();
+
+                task::spawn(async move {
+                    while let Some(msg) = item_rcv.recv().await {
+                        let mut batch = Vec::with_capacity(batch_size.get());
+                        let mut flushed = None;
+                        let mut msg = Some(msg);
+                        while let Some(next_msg) = msg.take() {
+                            match next_msg {
+                                WorkerMsg::Item(item) => batch.push(item),
+                                WorkerMsg::Flush(flushed_sdr) => {
+                                    flushed = Some(flushed_sdr);
+                                    break;
+                                }
+                            }
+                            if batch.len() < batch_size.get() {
+                                msg = item_rcv.try_recv().ok();
+                            }
+                        }
+                        if !batch.is_empty() {
+                            apply(batch);
+                        }
+                        if let Some(flushed) = flushed {
+                            let _ = flushed.send(());
+                        }
+                    }
+                });
+
+                item_sdr
+            })
+            .collect();
+
+        Self { item_sdrs }
+    }
+
+    /// Dispatch the given item to the worker for the given key.
+    pub async fn dispatch<K>(&self, key: K, item: T) -> Result<(), WorkerTerminated>
+    where
+        K: Hash,
+    {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let n = (hasher.finish() % self.item_sdrs.len() as u64) as usize;
+        self.item_sdrs[n]
+            .send(WorkerMsg::Item(item))
+            .await
+            .map_err(|_| WorkerTerminated)
+    }
+
+    /// Wait until all items dispatched so far have been applied.
+    pub async fn flush(&self) -> Result<(), WorkerTerminated> {
+        let mut flushed_rcvs = Vec::with_capacity(self.item_sdrs.len());
+        for item_sdr in &self.item_sdrs {
+            let (flushed_sdr, flushed_rcv) = oneshot::channel();
+            item_sdr
+                .send(WorkerMsg::Flush(flushed_sdr))
+                .await
+                .map_err(|_| WorkerTerminated)?;
+            flushed_rcvs.push(flushed_rcv);
+        }
+        for flushed_rcv in flushed_rcvs {
+            flushed_rcv.await.map_err(|_| WorkerTerminated)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error for dispatching to a terminated worker of [PartitionedWorkers].
+#[derive(Debug, Clone, Copy, Error)]
+#[error("Worker terminated")]
+pub struct WorkerTerminated;
+
+/// Events are decoded by the [ProjectionHandler], such that poison events can be dead lettered.
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let projections = Projections::default();
+        projections.register("test", ErrorPolicy::Halt);
+
+        let t0 = OffsetDateTime::UNIX_EPOCH;
+        for n in 0..10 {
+            projections.record(
+                "test",
+                n + 1,
+                t0,
+                t0 + Duration::milliseconds(n as i64 * 100),
+            );
+        }
+        projections.record("test", 11, t0, t0 + Duration::milliseconds(1_100));
+
+        let statuses = projections.statuses();
+        assert_eq!(statuses.len(), 1);
+        let status = &statuses[0];
+        assert!(status.running);
+        assert_eq!(status.error_policy, ErrorPolicy::Halt);
+        assert_eq!(status.evt_count, 11);
+        assert_eq!(status.last_seq_no, Some(11));
+        assert_eq!(status.lag_ms, 1_100);
+        assert_eq!(status.evts_per_sec, 10);
+
+        projections.terminate("test");
+        assert!(!projections.statuses()[0].running);
+    }
+
+    #[test]
+    fn test_restart_backoff() {
+        let config = RestartConfig {
+            max_restarts: 10,
+            min_backoff_millis: NonZeroU64::new(500).unwrap(),
+            max_backoff_millis: NonZeroU64::new(3_000).unwrap(),
+        };
+        let backoffs = (1..=5)
+            .map(|restart| config.backoff(restart).as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs, [500, 1_000, 2_000, 3_000, 3_000]);
+    }
+
+    #[test]
+    fn test_error_policies() {
+        let error_policies = serde_json::from_value::<ErrorPolicies>(serde_json::json!({
+            "default": "halt",
+            "projections": { "account-summaries": { "retry": 3 } }
+        }))
+        .unwrap();
+        assert_eq!(
+            error_policies.get("account-summaries"),
+            ErrorPolicy::Retry(NonZeroUsize::new(3).unwrap())
+        );
+        assert_eq!(error_policies.get("ledger"), ErrorPolicy::Halt);
+        assert_eq!(ErrorPolicies::default().get("ledger"), ErrorPolicy::Skip);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_workers() {
+        let applied = Arc::new(RwLock::new(Vec::<(u8, u32)>::new()));
+
+        let applied_clone = applied.clone();
+        let workers = PartitionedWorkers::spawn(
+            NonZeroUsize::new(4).unwrap(),
+            NonZeroUsize::new(3).unwrap(),
+            move |batch| applied_clone.write().extend(batch),
+        );
+
+        for n in 0..100 {
+            let key = (n % 7) as u8;
+            workers.dispatch(key, (key, n)).await.unwrap();
+        }
+        drop(workers);
+
+        while applied.read().len() < 100 {
+            task::yield_now().await;
+        }
+
+        // Items with the same key have been applied in order.
+        let applied = applied.read();
+        for key in 0..7 {
+            let ns = applied
+                .iter()
+                .filter_map(|(k, n)| (*k == key).then_some(*n))
+                .collect::<Vec<_>>();
+            assert!(ns.windows(2).all(|ns| ns[0] < ns[1]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_workers_flush() {
+        let applied = Arc::new(RwLock::new(Vec::<u32>::new()));
+
+        let applied_clone = applied.clone();
+        let workers = PartitionedWorkers::spawn(
+            NonZeroUsize::new(4).unwrap(),
+            NonZeroUsize::new(3).unwrap(),
+            move |batch| applied_clone.write().extend(batch),
+        );
+
+        for n in 0..100 {
+            workers.dispatch(n, n).await.unwrap();
+        }
+        workers.flush().await.unwrap();
+        assert_eq!(applied.read().len(), 100);
+    }
+
+    #[derive(Debug, Default)]
+    struct TestProjectionStore(RwLock<HashMap<String, Vec<u8>>>);
+
+    impl ProjectionStore for TestProjectionStore {
+        fn load(&self, projection: &str) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.0.read().get(projection).cloned())
+        }
+
+        fn save(&self, projection: &str, snapshot: &[u8]) -> anyhow::Result<()> {
+            self.0
+                .write()
+                .insert(projection.to_string(), snapshot.to_vec());
+            Ok(())
+        }
+
+        fn snapshot_after(&self) -> NonZeroU64 {
+            NonZeroU64::MIN
+        }
+
+        fn clear(&self) -> anyhow::Result<()> {
+            self.0.write().clear();
+            Ok(())
+        }
+    }
+
+    struct TestHandler(u64);
+
+    impl ProjectionHandler for TestHandler {
+        type Evt = u64;
+
+        fn decode(&self, evt: &[u8]) -> Result<Self::Evt, serde_json::Error> {
+            serde_json::from_slice(evt)
+        }
+
+        async fn handle(
+            &mut self,
+            _seq_no: u64,
+            evt: Self::Evt,
+        ) -> anyhow::Result<Option<OffsetDateTime>> {
+            self.0 += evt;
+            Ok(None)
+        }
+
+        fn snapshot(&self) -> Option<serde_json::Value> {
+            Some(self.0.into())
+        }
+
+        fn restore(&mut self, snapshot: serde_json::Value) -> Result<bool, serde_json::Error> {
+            self.0 = serde_json::from_value(snapshot)?;
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn test_save_and_restore() {
+        let store = TestProjectionStore::default();
+        let tags = ["a", "b"];
+
+        let mut handler = TestHandler(0);
+        assert!(matches!(
+            restore(&store, "test", &tags, &mut handler),
+            Ok(None)
+        ));
+
+        let checkpoints = [SeqNo::try_from(42).ok(), None];
+        assert!(save(&store, "test", &tags, &checkpoints, &TestHandler(666)).is_ok());
+
+        let restored = restore(&store, "test", &tags, &mut handler);
+        assert!(matches!(restored, Ok(Some(ref restored)) if restored == &checkpoints));
+        assert_eq!(handler.0, 666);
+    }
+}
@@ -0,0 +1,61 @@
+use super::ProjectionStore;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{num::NonZeroU64, path::PathBuf};
+
+/// [ProjectionStore] based upon an embedded sled database, e.g. for single-node deployments.
+#[derive(Debug, Clone)]
+pub struct SledProjectionStore {
+    db: sled::Db,
+    snapshot_after: NonZeroU64,
+}
+
+impl SledProjectionStore {
+    /// Open the database at the configured path, creating it if not yet existing.
+    pub fn open(config: Config) -> Result<Self> {
+        let db = sled::open(&config.path).context(format!(
+            "Cannot open sled database at {}",
+            config.path.display()
+        ))?;
+        Ok(Self {
+            db,
+            snapshot_after: config.snapshot_after,
+        })
+    }
+}
+
+impl ProjectionStore for SledProjectionStore {
+    fn load(&self, projection: &str) -> Result<Option<Vec<u8>>> {
+        let snapshot = self
+            .db
+            .get(projection)
+            .context(format!("Cannot load snapshot of projection {projection}"))?;
+        Ok(snapshot.map(|snapshot| snapshot.to_vec()))
+    }
+
+    fn save(&self, projection: &str, snapshot: &[u8]) -> Result<()> {
+        self.db
+            .insert(projection, snapshot)
+            .context(format!("Cannot save snapshot of projection {projection}"))?;
+        Ok(())
+    }
+
+    fn snapshot_after(&self) -> NonZeroU64 {
+        self.snapshot_after
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.db
+            .clear()
+            .context("Cannot clear snapshots of projections")?;
+        Ok(())
+    }
+}
+
+/// Configuration for the [SledProjectionStore].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    path: PathBuf,
+    snapshot_after: NonZeroU64,
+}
@@ -0,0 +1,176 @@
+use crate::domain::{
+    clock::Clock,
+    euro_cent::{EuroCent, Rounding},
+    id::IdGenerator,
+};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, num::NonZeroU64, sync::Arc};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// Quotes for withdrawals, such that customers see the fees before confirming. Quotes are valid
+/// for a short time and can be referenced once when withdrawing.
+#[derive(Debug, Clone)]
+pub struct Quotes {
+    config: Config,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    quotes: Arc<RwLock<HashMap<Uuid, Quote>>>,
+}
+
+impl Quotes {
+    #[allow(missing_docs)]
+    pub fn new(config: Config, clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            config,
+            clock,
+            ids,
+            quotes: Default::default(),
+        }
+    }
+
+    /// The fee for withdrawing the given amount.
+    pub fn fee(&self, amount: EuroCent) -> EuroCent {
+        self.config.withdrawal_fee + amount.bps(self.config.withdrawal_fee_bps, Rounding::Down)
+    }
+
+    /// Create a quote for withdrawing the given amount with the given fee, resulting in the given
+    /// balance.
+    pub fn create(
+        &self,
+        account_id: Uuid,
+        amount: EuroCent,
+        fee: EuroCent,
+        balance: EuroCent,
+    ) -> Quote {
+        let now = self.clock.now();
+        let quote = Quote {
+            id: self.ids.next_id(),
+            account_id,
+            amount,
+            fee,
+            total: amount + fee,
+            balance,
+            expires_at: now + Duration::seconds(self.config.ttl_secs.get() as i64),
+        };
+
+        let mut quotes = self.quotes.write();
+        quotes.retain(|_, quote| quote.expires_at > now);
+        quotes.insert(quote.id, quote);
+
+        quote
+    }
+
+    /// Take the quote with the given ID for withdrawing the given amount from the given account,
+    /// such that it cannot be used again.
+    pub fn take(&self, id: Uuid, account_id: Uuid, amount: EuroCent) -> Result<Quote, Error> {
+        let mut quotes = self.quotes.write();
+        let quote = quotes.get(&id).ok_or(Error::Unknown(id))?;
+        if quote.account_id != account_id || quote.amount != amount {
+            return Err(Error::Mismatch(id));
+        }
+        let quote = quotes.remove(&id).expect("quote exists");
+        if quote.expires_at <= self.clock.now() {
+            return Err(Error::Expired(id));
+        }
+        Ok(quote)
+    }
+
+    /// Restore the given taken quote, e.g. because the withdrawal has been rejected, such that it
+    /// can be used again until it expires.
+    pub fn restore(&self, quote: Quote) {
+        if quote.expires_at > self.clock.now() {
+            self.quotes.write().insert(quote.id, quote);
+        }
+    }
+}
+
+/// Configuration for [Quotes].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    ttl_secs: NonZeroU64,
+
+    /// Fixed fee per withdrawal.
+    withdrawal_fee: EuroCent,
+
+    /// Fee per withdrawal in basis points of the amount, added to the fixed fee.
+    withdrawal_fee_bps: u64,
+}
+
+/// A quote for a withdrawal. As only EUR is supported, there is no FX rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Quote {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub amount: EuroCent,
+    pub fee: EuroCent,
+    /// The amount withdrawn, including the fee.
+    pub total: EuroCent,
+    /// The balance of the account after the withdrawal.
+    pub balance: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unknown quote '{0}'")]
+    Unknown(Uuid),
+
+    #[error("Quote '{0}' does not match account and amount")]
+    Mismatch(Uuid),
+
+    #[error("Quote '{0}' has expired")]
+    Expired(Uuid),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{clock::ManualClock, id::UuidV7Generator};
+
+    #[test]
+    fn test_create_and_take() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let config = Config {
+            ttl_secs: NonZeroU64::new(60).unwrap(),
+            withdrawal_fee: 50u64.into(),
+            withdrawal_fee_bps: 100,
+        };
+        let quotes = Quotes::new(
+            config,
+            Arc::new(clock.clone()),
+            Arc::new(UuidV7Generator::new(Arc::new(clock.clone()))),
+        );
+
+        let account_id = Uuid::now_v7();
+        let amount = EuroCent::from(10_000u64);
+        let fee = quotes.fee(amount);
+        assert_eq!(fee, 150u64.into());
+
+        let quote = quotes.create(account_id, amount, fee, 0u64.into());
+        assert_eq!(quote.total, 10_150u64.into());
+        assert!(matches!(
+            quotes.take(quote.id, account_id, 1u64.into()),
+            Err(Error::Mismatch(_))
+        ));
+        let taken = quotes.take(quote.id, account_id, amount).unwrap();
+        assert!(matches!(
+            quotes.take(quote.id, account_id, amount),
+            Err(Error::Unknown(_))
+        ));
+
+        quotes.restore(taken);
+        assert_eq!(quotes.take(quote.id, account_id, amount).unwrap(), quote);
+
+        let quote = quotes.create(account_id, amount, fee, 0u64.into());
+        clock.advance(Duration::seconds(60));
+        assert!(matches!(
+            quotes.take(quote.id, account_id, amount),
+            Err(Error::Expired(_))
+        ));
+    }
+}
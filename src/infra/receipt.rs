@@ -0,0 +1,171 @@
+use crate::{
+    domain::euro_cent::EuroCent,
+    infra::ledger::{EntryKind, LedgerEntry, LedgerProjection},
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{fmt::Write, sync::Arc};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Number of bytes of the signature contained in a verification code.
+const SIGNATURE_LEN: usize = 16;
+
+/// Issues receipts for completed transactions as proof of payment. Each receipt carries a
+/// verification code, made of the transaction ID and a signature over the receipt, such that
+/// anybody can verify a receipt with the bank without further information.
+#[derive(Debug, Clone)]
+pub struct Receipts<G> {
+    signing_key: Arc<[u8]>,
+    ledger_projection: G,
+}
+
+impl<G> Receipts<G>
+where
+    G: LedgerProjection,
+{
+    #[allow(missing_docs)]
+    pub fn new(config: Config, ledger_projection: G) -> Self {
+        Self {
+            signing_key: config.signing_key.into_bytes().into(),
+            ledger_projection,
+        }
+    }
+
+    /// The [Receipt] for the transaction with the given ID of the given account, if already
+    /// projected to the ledger.
+    pub async fn receipt(&self, account_id: Uuid, id: Uuid) -> Option<Receipt> {
+        self.ledger_projection
+            .entry(id)
+            .await
+            .filter(|entry| entry.account_id == account_id)
+            .map(|entry| self.sign(entry))
+    }
+
+    /// The [Receipt] for the given verification code, if valid.
+    pub async fn verify(&self, code: &str) -> Option<Receipt> {
+        let (id, _) = code.split_once('.')?;
+        let id = id.parse().ok()?;
+        self.ledger_projection
+            .entry(id)
+            .await
+            .map(|entry| self.sign(entry))
+            .filter(|receipt| receipt.code == code)
+    }
+
+    fn sign(&self, entry: LedgerEntry) -> Receipt {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.signing_key).expect("HMAC takes keys of any size");
+        mac.update(
+            format!(
+                "{}|{}|{}|{}|{}|{}",
+                entry.id,
+                entry.account_id,
+                entry.kind.as_str(),
+                u64::from(entry.amount),
+                u64::from(entry.balance),
+                entry.at.unix_timestamp_nanos()
+            )
+            .as_bytes(),
+        );
+        let signature = mac.finalize().into_bytes();
+
+        let mut code = format!("{}.", entry.id);
+        for byte in &signature[..SIGNATURE_LEN] {
+            let _ = write!(code, "{byte:02x}");
+        }
+
+        Receipt {
+            transaction_id: entry.id,
+            account_id: entry.account_id,
+            kind: entry.kind,
+            amount: entry.amount,
+            balance: entry.balance,
+            at: entry.at,
+            code,
+        }
+    }
+}
+
+/// Configuration for [Receipts].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    signing_key: String,
+}
+
+/// A receipt for a completed transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Receipt {
+    pub transaction_id: Uuid,
+    pub account_id: Uuid,
+    pub kind: EntryKind,
+    pub amount: EuroCent,
+    /// The balance of the account after the transaction.
+    pub balance: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    pub code: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::{ready, Future};
+
+    #[derive(Debug, Clone)]
+    struct TestLedgerProjection(LedgerEntry);
+
+    impl LedgerProjection for TestLedgerProjection {
+        fn entries(
+            &self,
+            _from: OffsetDateTime,
+            _to: OffsetDateTime,
+        ) -> impl Future<Output = Vec<LedgerEntry>> + Send + '_ {
+            ready(vec![self.0])
+        }
+
+        fn entry(&self, id: Uuid) -> impl Future<Output = Option<LedgerEntry>> + Send + '_ {
+            ready((id == self.0.id).then_some(self.0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_receipt_and_verify() {
+        let entry = LedgerEntry {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            tenant: None,
+            sandbox: false,
+            kind: EntryKind::Deposit,
+            amount: 42u64.into(),
+            balance: 42u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+            value_date: OffsetDateTime::UNIX_EPOCH,
+            booked_at: OffsetDateTime::UNIX_EPOCH,
+        };
+        let config = Config {
+            signing_key: "secret".to_string(),
+        };
+        let receipts = Receipts::new(config, TestLedgerProjection(entry));
+
+        assert!(receipts.receipt(Uuid::now_v7(), entry.id).await.is_none());
+        let receipt = receipts.receipt(entry.account_id, entry.id).await;
+        assert!(receipt.is_some());
+        let receipt = receipt.unwrap();
+
+        assert_eq!(receipts.verify(&receipt.code).await, Some(receipt.clone()));
+        let mut forged = receipt.code.clone();
+        forged.pop();
+        forged.push('x');
+        assert!(receipts.verify(&forged).await.is_none());
+
+        // Receipts signed with another key cannot be verified.
+        let config = Config {
+            signing_key: "other".to_string(),
+        };
+        let other_receipts = Receipts::new(config, TestLedgerProjection(entry));
+        assert!(other_receipts.verify(&receipt.code).await.is_none());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{
+    domain::{clock::Clock, euro_cent::EuroCent, id::IdGenerator, tenant::TenantId},
+    infra::{
+        delivery::{self, DeliveryStatus, FileDrop},
+        leader::Leadership,
+        ledger::{EntryKind, LedgerEntry, LedgerProjection},
+    },
+};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Write,
+    num::{NonZeroU64, NonZeroUsize},
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+use time::{Date, OffsetDateTime, Time};
+use tokio::{task, time::interval};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// Renders the configured [ReportDefinition]s over the ledger, either on demand or on schedule,
+/// and keeps a history of the runs. As the definitions are part of the configuration, reports are
+/// reproducible and auditable, unlike ad-hoc queries.
+#[derive(Debug, Clone)]
+pub struct Reporter<G> {
+    config: Arc<Config>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    ledger_projection: G,
+    runs: Arc<RwLock<VecDeque<Arc<ReportRun>>>>,
+    file_drop: Option<FileDrop>,
+}
+
+impl<G> Reporter<G>
+where
+    G: LedgerProjection,
+{
+    /// Create a [Reporter] and spawn running the scheduled reports, on the leader only.
+    pub fn spawn(
+        config: Config,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        ledger_projection: G,
+        leadership: Leadership,
+    ) -> Self {
+        let file_drop = config
+            .delivery
+            .clone()
+            .map(|delivery| FileDrop::new(delivery, clock.clone()));
+        let reporter = Self {
+            config: Arc::new(config),
+            clock,
+            ids,
+            ledger_projection,
+            runs: Default::default(),
+            file_drop,
+        };
+
+        for definition in &reporter.config.reports {
+            let Some(schedule_secs) = definition.schedule_secs else {
+                continue;
+            };
+
+            let reporter = reporter.clone();
+            let leadership = leadership.clone();
+            let name = definition.name.clone();
+            task::spawn(async move {
+                let mut interval = interval(Duration::from_secs(schedule_secs.get()));
+                // The first tick completes immediately.
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if leadership.is_leader() {
+                        if let Err(error) = reporter.run(&name).await {
+                            error!(%name, %error, "Cannot run scheduled report");
+                        }
+                    }
+                }
+            });
+        }
+
+        reporter
+    }
+
+    /// Run the report with the given name for its last completed period.
+    pub async fn run(&self, name: &str) -> Result<Arc<ReportRun>, Error> {
+        let definition = self
+            .config
+            .reports
+            .iter()
+            .find(|definition| definition.name == name)
+            .ok_or_else(|| Error::UnknownReport(name.to_string()))?;
+
+        let at = self.clock.now();
+        let (from, to) = definition.period.last_completed(at);
+        debug!(%name, %from, %to, "Running report");
+
+        let entries = self.ledger_projection.entries(from, to).await;
+        let rows = aggregate(definition, &entries);
+        let content = match definition.format {
+            ReportFormat::Csv => render_csv(&rows),
+            ReportFormat::Xml => render_xml(&definition.name, from, to, &rows),
+        };
+
+        let run = Arc::new(ReportRun {
+            id: self.ids.next_id(),
+            name: definition.name.clone(),
+            format: definition.format,
+            from,
+            to,
+            at,
+            rows: rows.len(),
+            content,
+        });
+        info!(%name, id = %run.id, rows = run.rows, "Ran report");
+
+        if let Some(file_drop) = self.file_drop.as_ref().filter(|_| definition.deliver) {
+            file_drop.deliver(run.id, run.file_name(), run.content.clone());
+        }
+
+        let mut runs = self.runs.write();
+        runs.push_front(run.clone());
+        runs.truncate(self.config.history_size.get());
+
+        Ok(run)
+    }
+
+    /// The runs, most recent first.
+    pub fn runs(&self) -> Vec<Arc<ReportRun>> {
+        self.runs.read().iter().cloned().collect()
+    }
+
+    /// The status of the delivery of the run with the given ID, if delivered.
+    pub fn delivery_status(&self, id: Uuid) -> Option<DeliveryStatus> {
+        self.file_drop
+            .as_ref()
+            .and_then(|file_drop| file_drop.status(id))
+    }
+
+    /// The run with the given ID, if still in the history.
+    pub fn find_run(&self, id: Uuid) -> Option<Arc<ReportRun>> {
+        self.runs.read().iter().find(|run| run.id == id).cloned()
+    }
+}
+
+/// Configuration for the [Reporter].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Maximum number of runs kept in the history.
+    history_size: NonZeroUsize,
+
+    #[serde(default)]
+    reports: Vec<ReportDefinition>,
+
+    /// If given, runs of reports to be delivered are dropped as files, e.g. for collection via
+    /// SFTP.
+    delivery: Option<delivery::Config>,
+}
+
+/// Definition of a report: the period, the filters for the ledger entries, the aggregation and
+/// the format.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ReportDefinition {
+    name: String,
+    period: ReportPeriod,
+    tenant: Option<TenantId>,
+    /// Kinds of ledger entries to include; all if empty.
+    #[serde(default)]
+    kinds: Vec<EntryKind>,
+    min_amount: Option<EuroCent>,
+    group_by: GroupBy,
+    format: ReportFormat,
+    /// Interval for running the report on schedule, if any.
+    schedule_secs: Option<NonZeroU64>,
+    /// Whether to deliver the runs as files, if delivery is configured.
+    #[serde(default)]
+    deliver: bool,
+}
+
+/// Period covered by a report, always the last completed one in UTC. Also used for closing the
+/// books.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportPeriod {
+    Day,
+    Month,
+}
+
+impl ReportPeriod {
+    /// Start (inclusive) and end (exclusive) of the last completed period before the given time.
+    pub fn last_completed(&self, at: OffsetDateTime) -> (OffsetDateTime, OffsetDateTime) {
+        let today = at.date();
+        let (from, to) = match self {
+            ReportPeriod::Day => (today.previous_day().unwrap_or(today), today),
+            ReportPeriod::Month => {
+                let to = today.replace_day(1).expect("first day of month is valid");
+                let from = to
+                    .previous_day()
+                    .and_then(|date| date.replace_day(1).ok())
+                    .unwrap_or(to);
+                (from, to)
+            }
+        };
+        (midnight(from), midnight(to))
+    }
+}
+
+/// Aggregation key for the rows of a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupBy {
+    Account,
+    Kind,
+    Day,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportFormat {
+    Csv,
+    Xml,
+}
+
+impl ReportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "text/csv",
+            ReportFormat::Xml => "application/xml",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Xml => "xml",
+        }
+    }
+}
+
+/// A run of a report.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRun {
+    pub id: Uuid,
+    pub name: String,
+    pub format: ReportFormat,
+    #[serde(with = "time::serde::rfc3339")]
+    pub from: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    pub rows: usize,
+    #[serde(skip)]
+    pub content: String,
+}
+
+impl ReportRun {
+    /// Name of the file for delivering this run, e.g. `daily-withdrawals-2023-03-14-<id>.csv`.
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}-{}-{}.{}",
+            self.name,
+            self.from.date(),
+            self.id,
+            self.format.extension()
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unknown report '{0}'")]
+    UnknownReport(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    group: String,
+    count: u64,
+    sum: EuroCent,
+}
+
+fn aggregate(definition: &ReportDefinition, entries: &[LedgerEntry]) -> Vec<Row> {
+    let mut rows = BTreeMap::<String, (u64, EuroCent)>::new();
+    for entry in entries {
+        // Test data of sandbox accounts must not show up in regulatory reports.
+        if entry.sandbox {
+            continue;
+        }
+        if definition.tenant.is_some() && entry.tenant != definition.tenant {
+            continue;
+        }
+        if !definition.kinds.is_empty() && !definition.kinds.contains(&entry.kind) {
+            continue;
+        }
+        if definition
+            .min_amount
+            .is_some_and(|min_amount| entry.amount < min_amount)
+        {
+            continue;
+        }
+
+        let group = match definition.group_by {
+            GroupBy::Account => entry.account_id.to_string(),
+            GroupBy::Kind => entry.kind.as_str().to_string(),
+            GroupBy::Day => entry.booked_at.date().to_string(),
+        };
+        let (count, sum) = rows.entry(group).or_default();
+        *count += 1;
+        *sum = *sum + entry.amount;
+    }
+
+    rows.into_iter()
+        .map(|(group, (count, sum))| Row { group, count, sum })
+        .collect()
+}
+
+fn render_csv(rows: &[Row]) -> String {
+    let mut csv = "group,count,sum\n".to_string();
+    for row in rows {
+        let _ = writeln!(csv, "{},{},{}", row.group, row.count, decimal(row.sum));
+    }
+    csv
+}
+
+fn render_xml(name: &str, from: OffsetDateTime, to: OffsetDateTime, rows: &[Row]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<report name=\"{}\" from=\"{}\" to=\"{}\">\n",
+        escape(name),
+        from.date(),
+        to.date()
+    );
+    for row in rows {
+        let _ = writeln!(
+            xml,
+            "  <row group=\"{}\" count=\"{}\" sum=\"{}\"/>",
+            escape(&row.group),
+            row.count,
+            decimal(row.sum)
+        );
+    }
+    xml.push_str("</report>\n");
+    xml
+}
+
+/// Format [EuroCent] as 123.05, i.e. without currency symbol.
+pub fn decimal(amount: EuroCent) -> String {
+    let amount = u64::from(amount);
+    format!("{}.{:02}", amount / 100, amount % 100)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn midnight(date: Date) -> OffsetDateTime {
+    date.with_time(Time::MIDNIGHT).assume_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Month;
+
+    #[test]
+    fn test_last_completed() {
+        let date = |year, month, day| Date::from_calendar_date(year, month, day).unwrap();
+
+        let at = midnight(date(2023, Month::March, 15)) + time::Duration::hours(12);
+        assert_eq!(
+            ReportPeriod::Day.last_completed(at),
+            (
+                midnight(date(2023, Month::March, 14)),
+                midnight(date(2023, Month::March, 15))
+            )
+        );
+
+        let at = midnight(date(2023, Month::January, 15)) + time::Duration::hours(12);
+        assert_eq!(
+            ReportPeriod::Month.last_completed(at),
+            (
+                midnight(date(2022, Month::December, 1)),
+                midnight(date(2023, Month::January, 1))
+            )
+        );
+    }
+
+    #[test]
+    fn test_aggregate_and_render() {
+        let definition = ReportDefinition {
+            name: "withdrawals".to_string(),
+            period: ReportPeriod::Day,
+            tenant: None,
+            kinds: vec![EntryKind::Withdrawal],
+            min_amount: Some(100u64.into()),
+            group_by: GroupBy::Kind,
+            format: ReportFormat::Csv,
+            schedule_secs: None,
+            deliver: false,
+        };
+        let entry = |kind, amount: u64| LedgerEntry {
+            id: Uuid::now_v7(),
+            account_id: Uuid::now_v7(),
+            tenant: None,
+            sandbox: false,
+            kind,
+            amount: amount.into(),
+            balance: 0u64.into(),
+            at: OffsetDateTime::UNIX_EPOCH,
+            value_date: OffsetDateTime::UNIX_EPOCH,
+            booked_at: OffsetDateTime::UNIX_EPOCH,
+        };
+        let entries = vec![
+            entry(EntryKind::Withdrawal, 100),
+            entry(EntryKind::Withdrawal, 99),
+            entry(EntryKind::Deposit, 1_000),
+            entry(EntryKind::Withdrawal, 250),
+            LedgerEntry {
+                sandbox: true,
+                ..entry(EntryKind::Withdrawal, 500)
+            },
+        ];
+
+        let rows = aggregate(&definition, &entries);
+        assert_eq!(
+            render_csv(&rows),
+            "group,count,sum\nwithdrawal,2,3.50\n".to_string()
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{
+    domain::{account, clock::Clock, tenant::TenantId},
+    infra::{
+        account::{AccountFactory, AccountSummariesProjection},
+        leader::Leadership,
+        namespace::{EntityType, Namespace},
+    },
+};
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::{StreamExt, TryStreamExt};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    fs,
+    io::Write,
+    num::NonZeroU64,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use time::{Duration, OffsetDateTime};
+use tokio::{task, time::interval};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// Configuration for [Retention].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Number of days after closing after which accounts are purged.
+    retention_days: u16,
+
+    /// Number of days before purging during which accounts are listed as pending for review.
+    review_days: u16,
+
+    /// Interval of the purge runs.
+    interval_secs: NonZeroU64,
+
+    /// Directory to archive the events of purged accounts into, one JSON lines file per account,
+    /// e.g. a directory synced to cold storage.
+    archive_dir: PathBuf,
+}
+
+/// Purges accounts closed longer than the retention period: their events are archived and they
+/// are removed from the projections. Accounts due within the review period are listed as pending,
+/// such that purges can be reviewed before they happen.
+#[derive(Debug, Clone)]
+pub struct Retention<P> {
+    config: Arc<Config>,
+    clock: Arc<dyn Clock>,
+    account_summaries: P,
+}
+
+/// An account pending to be purged.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingPurge {
+    pub account_id: Uuid,
+    pub tenant: TenantId,
+    #[serde(with = "time::serde::rfc3339")]
+    pub closed_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub purge_at: OffsetDateTime,
+}
+
+impl<P> Retention<P>
+where
+    P: AccountSummariesProjection,
+{
+    /// Create a [Retention] and spawn the purge runs, on the leader only.
+    pub fn spawn<L, F>(
+        config: Config,
+        clock: Arc<dyn Clock>,
+        evt_log: L,
+        namespace: Namespace,
+        account_summaries: P,
+        account_factory: F,
+        leadership: Leadership,
+    ) -> Self
+    where
+        L: EvtLog,
+        F: AccountFactory,
+    {
+        let retention = Self {
+            config: Arc::new(config),
+            clock,
+            account_summaries,
+        };
+
+        let purger = retention.clone();
+        task::spawn(async move {
+            let mut interval = interval(std::time::Duration::from_secs(
+                purger.config.interval_secs.get(),
+            ));
+            // The first tick completes immediately, i.e. before the projection has caught up.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if leadership.is_leader() {
+                    purger.run(&evt_log, namespace, &account_factory).await;
+                }
+            }
+        });
+
+        retention
+    }
+
+    /// The accounts to be purged within the review period, including overdue ones, in the order
+    /// of their purge times.
+    pub async fn pending(&self) -> Vec<PendingPurge> {
+        let retention = Duration::days(self.config.retention_days as i64);
+        let review = Duration::days(self.config.review_days as i64);
+        let mut pending = self
+            .account_summaries
+            .closed_before(self.clock.now() - retention + review)
+            .await
+            .into_iter()
+            .filter_map(|(account_id, summary)| {
+                summary.closed_at.map(|closed_at| PendingPurge {
+                    account_id,
+                    tenant: summary.tenant,
+                    closed_at,
+                    purge_at: closed_at + retention,
+                })
+            })
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|pending| pending.purge_at);
+        pending
+    }
+
+    /// Purge all due accounts; failures are logged and retried with the next run.
+    async fn run<L, F>(&self, evt_log: &L, namespace: Namespace, account_factory: &F)
+    where
+        L: EvtLog,
+        F: AccountFactory,
+    {
+        let now = self.clock.now();
+        let due = self
+            .pending()
+            .await
+            .into_iter()
+            .take_while(|pending| pending.purge_at < now);
+        for PendingPurge { account_id, .. } in due {
+            match self
+                .purge(evt_log, namespace, account_factory, account_id)
+                .await
+            {
+                Ok(()) => {
+                    info!(%account_id, "Purged account");
+                    counter!("account_purges_total", 1, "status" => "purged");
+                }
+
+                Err(error) => {
+                    error!(%account_id, error = format!("{error:#}"), "Cannot purge account");
+                    counter!("account_purges_total", 1, "status" => "failed");
+                }
+            }
+        }
+    }
+
+    /// Archive the events of the given account, then purge it. Archives are overwritten, hence
+    /// retrying after a failed purge is safe.
+    async fn purge<L, F>(
+        &self,
+        evt_log: &L,
+        namespace: Namespace,
+        account_factory: &F,
+        account_id: Uuid,
+    ) -> Result<()>
+    where
+        L: EvtLog,
+        F: AccountFactory,
+    {
+        let evts = evts(evt_log, namespace.id(EntityType::Account, account_id)).await?;
+        let dir = self.config.archive_dir.clone();
+        task::spawn_blocking(move || archive(&dir, account_id, &evts))
+            .await
+            .context("Cannot join archiving task")?
+            .context("Cannot archive events")?;
+
+        let cmd = account::Cmd::Purge {
+            retention_days: self.config.retention_days,
+        };
+        account_factory
+            .get(account_id)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Purge command")?
+            .context("Cannot purge account")?;
+        Ok(())
+    }
+}
+
+/// All events of the stream with the given ID, serialized.
+async fn evts<L>(evt_log: &L, id: Uuid) -> Result<Vec<Bytes>>
+where
+    L: EvtLog,
+{
+    let Some(last_seq_no) = evt_log
+        .last_seq_no(id)
+        .await
+        .context("Cannot get last sequence number")?
+    else {
+        return Ok(vec![]);
+    };
+
+    evt_log
+        .evts_by_id::<Bytes, _, _, _>(id, SeqNo::MIN, raw)
+        .await
+        .context("Cannot get events")?
+        .take_while(|evt| {
+            let more = !matches!(evt, Ok((seq_no, _)) if *seq_no > last_seq_no);
+            async move { more }
+        })
+        .map_ok(|(_, evt)| evt)
+        .try_collect::<Vec<_>>()
+        .await
+        .context("Cannot get events")
+}
+
+/// Write the given events as JSON lines to a temporary file which is renamed when complete.
+fn archive(dir: &Path, account_id: Uuid, evts: &[Bytes]) -> std::io::Result<()> {
+    let file_name = format!("{account_id}.jsonl");
+    let tmp = dir.join(format!(".{file_name}.tmp"));
+    let mut file = fs::File::create(&tmp)?;
+    for evt in evts {
+        file.write_all(evt)?;
+        file.write_all(b"\n")?;
+    }
+    file.sync_all()?;
+    fs::rename(&tmp, dir.join(&file_name))?;
+    debug!(%account_id, dir = %dir.display(), evts = evts.len(), "Archived events");
+    Ok(())
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_archive() {
+        let dir = env::temp_dir().join(format!("retention-{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let account_id = Uuid::now_v7();
+        let evts = vec![Bytes::from_static(b"{\"a\":1}"), Bytes::from_static(b"{}")];
+        archive(&dir, account_id, &evts).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join(format!("{account_id}.jsonl"))).unwrap(),
+            "{\"a\":1}\n{}\n"
+        );
+        assert!(!dir.join(format!(".{account_id}.jsonl.tmp")).exists());
+    }
+}
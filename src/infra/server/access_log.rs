@@ -0,0 +1,240 @@
+use crate::domain::{clock::Clock, id::IdGenerator};
+use anyhow::{Context, Result};
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, State},
+    http::{header::CONTENT_LENGTH, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::{self, BufWriter, Write},
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+};
+use time::OffsetDateTime;
+use tokio::{sync::mpsc, task};
+use tracing::error;
+
+/// Request and response header for the request ID, generated unless given by the client.
+pub const REQUEST_ID: &str = "x-request-id";
+
+/// Configuration for the [AccessLog].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// File to append the records to, stdout if not given.
+    path: Option<PathBuf>,
+
+    /// Maximum number of records not yet written; further records are dropped.
+    buffer: NonZeroUsize,
+}
+
+/// Access log with one structured record per request, written as JSON lines to the configured
+/// sink, independent of tracing, e.g. to be ingested by a SIEM.
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    record_sdr: mpsc::Sender<Record>,
+}
+
+impl AccessLog {
+    /// Open the configured sink and spawn the writer.
+    pub fn spawn(config: Config) -> Result<Self> {
+        let sink: Box<dyn Write + Send> = match &config.path {
+            Some(path) => Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Cannot open access log {}", path.display()))?,
+            ),
+            None => Box::new(io::stdout()),
+        };
+
+        let (record_sdr, mut record_rcv) = mpsc::channel::<Record>(config.buffer.get());
+        task::spawn_blocking(move || {
+            let mut sink = BufWriter::new(sink);
+            while let Some(record) = record_rcv.blocking_recv() {
+                let mut record = Some(record);
+                while let Some(next_record) = record.take() {
+                    if let Err(error) = serde_json::to_writer(&mut sink, &next_record)
+                        .map_err(io::Error::from)
+                        .and_then(|_| sink.write_all(b"\n"))
+                    {
+                        error!(%error, "Cannot write access log record");
+                    }
+                    record = record_rcv.try_recv().ok();
+                }
+                if let Err(error) = sink.flush() {
+                    error!(%error, "Cannot flush access log");
+                }
+            }
+        });
+
+        Ok(Self { record_sdr })
+    }
+
+    fn log(&self, record: Record) {
+        if self.record_sdr.try_send(record).is_err() {
+            metrics::counter!("access_log_dropped_total", 1);
+        }
+    }
+}
+
+/// State for [log]: the [AccessLog], if configured, the [IdGenerator] for request IDs and the
+/// [Clock] for the time of requests.
+#[derive(Debug, Clone)]
+pub struct Logging {
+    access_log: Option<AccessLog>,
+    ids: Arc<dyn IdGenerator>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Logging {
+    #[allow(missing_docs)]
+    pub fn new(
+        access_log: Option<AccessLog>,
+        ids: Arc<dyn IdGenerator>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            access_log,
+            ids,
+            clock,
+        }
+    }
+}
+
+/// The authenticated subject of a request, added to the response extensions for the [AccessLog].
+#[derive(Debug, Clone)]
+pub struct Subject(pub String);
+
+/// A record of the [AccessLog].
+#[derive(Debug, Serialize)]
+struct Record {
+    #[serde(with = "time::serde::rfc3339")]
+    at: OffsetDateTime,
+    request_id: String,
+    method: String,
+    /// The route, i.e. the path template, to avoid logging IDs or other data from the path.
+    route: Option<String>,
+    status: u16,
+    latency_ms: f64,
+    subject: Option<String>,
+    request_bytes: Option<u64>,
+    response_bytes: Option<u64>,
+}
+
+/// Middleware recording each request in the [AccessLog], if configured, and adding the request ID
+/// to the response.
+pub async fn log(
+    State(Logging {
+        access_log,
+        ids,
+        clock,
+    }): State<Logging>,
+    route: Option<MatchedPath>,
+    mut request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID)
+        .and_then(|request_id| request_id.to_str().ok())
+        .map(ToString::to_string)
+        .unwrap_or_else(|| ids.next_id().to_string());
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        request.headers_mut().insert(REQUEST_ID, value);
+    }
+
+    let at = clock.now();
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let request_bytes = content_length(request.headers().get(CONTENT_LENGTH));
+
+    let mut response = next.run(request).await;
+
+    if let Some(access_log) = access_log {
+        let response_bytes = content_length(response.headers().get(CONTENT_LENGTH))
+            .or_else(|| response.body().size_hint().exact());
+        access_log.log(Record {
+            at,
+            request_id: request_id.clone(),
+            method,
+            route: route.map(|route| route.as_str().to_string()),
+            status: response.status().as_u16(),
+            latency_ms: start.elapsed().as_secs_f64() * 1_000.0,
+            subject: response
+                .extensions()
+                .get::<Subject>()
+                .map(|subject| subject.0.clone()),
+            request_bytes,
+            response_bytes,
+        });
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+    response
+}
+
+fn content_length(value: Option<&HeaderValue>) -> Option<u64> {
+    value
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, time::Duration};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_access_log() {
+        let path = env::temp_dir().join(format!("access-log-{}.jsonl", Uuid::now_v7()));
+        let access_log = AccessLog::spawn(Config {
+            path: Some(path.clone()),
+            buffer: NonZeroUsize::new(10).unwrap(),
+        })
+        .unwrap();
+
+        access_log.log(Record {
+            at: OffsetDateTime::UNIX_EPOCH,
+            request_id: "42".to_string(),
+            method: "POST".to_string(),
+            route: Some("/accounts/:id/deposits".to_string()),
+            status: 200,
+            latency_ms: 1.0,
+            subject: Some("teller".to_string()),
+            request_bytes: Some(17),
+            response_bytes: None,
+        });
+        drop(access_log);
+
+        let mut lines = vec![];
+        for _ in 0..100 {
+            lines = fs::read_to_string(&path)
+                .unwrap_or_default()
+                .lines()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            if !lines.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(lines.len(), 1);
+        let record = serde_json::from_str::<serde_json::Value>(&lines[0]).unwrap();
+        assert_eq!(record["route"], "/accounts/:id/deposits");
+        assert_eq!(record["subject"], "teller");
+        assert_eq!(record["status"], 200);
+    }
+}
@@ -0,0 +1,145 @@
+use axum::http::HeaderValue;
+use bytes::Bytes;
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::{num::NonZeroUsize, sync::Arc};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task,
+};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Configuration for the [AccountCache].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Maximum number of cached accounts, evicting the least recently used ones.
+    capacity: NonZeroUsize,
+
+    /// Maximum age clients may cache account representations for, via Cache-Control.
+    max_age_secs: u64,
+}
+
+/// Cache of rendered account representations keyed by account ID, such that polling clients are
+/// served without hitting the account entity or rendering identical data again. Representations of
+/// accounts with new events observed by the projection are dropped, hence cached ones are fresh as
+/// far as the projection is concerned.
+#[derive(Debug, Clone)]
+pub struct AccountCache {
+    config: Config,
+    entries: Arc<Mutex<LruCache<Uuid, CachedAccount>>>,
+}
+
+/// A rendered account representation with the sequence number and balance it has been rendered
+/// at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedAccount {
+    pub seq_no: u64,
+    pub balance: u64,
+    pub body: Bytes,
+}
+
+impl AccountCache {
+    #[allow(missing_docs)]
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            entries: Arc::new(Mutex::new(LruCache::new(config.capacity))),
+        }
+    }
+
+    /// The cached representation of the account with the given ID, if any.
+    pub fn get(&self, id: Uuid) -> Option<CachedAccount> {
+        self.entries.lock().get(&id).cloned()
+    }
+
+    /// Cache the given representation of the account with the given ID, unless a later one is
+    /// already cached.
+    pub fn insert(&self, id: Uuid, account: CachedAccount) {
+        let mut entries = self.entries.lock();
+        if !matches!(entries.peek(&id), Some(cached) if cached.seq_no >= account.seq_no) {
+            entries.put(id, account);
+        }
+    }
+
+    /// Value of the Cache-Control header for account representations.
+    pub fn cache_control(&self) -> HeaderValue {
+        HeaderValue::from_str(&format!("private, max-age={}", self.config.max_age_secs))
+            .expect("valid header value")
+    }
+
+    /// Spawn dropping the cached representations of the accounts with the IDs received from the
+    /// given receiver. If notifications have been missed, all are dropped.
+    pub fn spawn_invalidation(&self, mut updates: broadcast::Receiver<Uuid>) {
+        let entries = self.entries.clone();
+        task::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(id) => {
+                        entries.lock().pop(&id);
+                    }
+
+                    Err(RecvError::Lagged(n)) => {
+                        warn!(n, "Missed account updates, clearing account cache");
+                        entries.lock().clear();
+                    }
+
+                    Err(RecvError::Closed) => {
+                        debug!("Account updates closed, stopping invalidation");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(seq_no: u64) -> CachedAccount {
+        CachedAccount {
+            seq_no,
+            balance: 42,
+            body: Bytes::from(seq_no.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_cache() {
+        let cache = AccountCache::new(Config {
+            capacity: NonZeroUsize::new(1).unwrap(),
+            max_age_secs: 5,
+        });
+        let id = Uuid::now_v7();
+
+        cache.insert(id, account(2));
+        assert_eq!(cache.get(id), Some(account(2)));
+
+        // Earlier representations do not replace later ones.
+        cache.insert(id, account(1));
+        assert_eq!(cache.get(id), Some(account(2)));
+
+        // The least recently used account is evicted.
+        let other_id = Uuid::now_v7();
+        cache.insert(other_id, account(1));
+        assert_eq!(cache.get(id), None);
+        assert_eq!(cache.get(other_id), Some(account(1)));
+
+        let (updates_sdr, updates) = broadcast::channel(1);
+        cache.spawn_invalidation(updates);
+        updates_sdr.send(other_id).unwrap();
+        for _ in 0..100 {
+            if cache.get(other_id).is_none() {
+                break;
+            }
+            task::yield_now().await;
+        }
+        assert_eq!(cache.get(other_id), None);
+
+        assert_eq!(cache.cache_control(), "private, max-age=5");
+    }
+}
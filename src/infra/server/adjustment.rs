@@ -0,0 +1,116 @@
+use super::problem::Problem;
+use crate::{
+    application::commands::{CommandError, Commands},
+    domain::{
+        account::{self, AdjustmentDirection, ReasonCode},
+        euro_cent::EuroCent,
+        id::IdGenerator,
+    },
+    infra::{account::AccountFactory, error_code::ErrorCode},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// Router for the adjustment endpoints, to be merged into the account routes.
+pub fn router<F, S>(commands: Commands<F>, ids: Arc<dyn IdGenerator>) -> Router<S>
+where
+    F: AccountFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/accounts/:id/adjustments", post(adjust_account))
+        .with_state(AdjustmentState { commands, ids })
+}
+
+#[derive(Debug, Clone)]
+struct AdjustmentState<F> {
+    commands: Commands<F>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Adjust {
+    direction: AdjustmentDirection,
+    amount: EuroCent,
+    reason: ReasonCode,
+    justification: String,
+}
+
+/// Representation of an adjustment.
+#[derive(Debug, Clone, Serialize)]
+struct AdjustmentRepr {
+    id: Uuid,
+    account_id: Uuid,
+    direction: AdjustmentDirection,
+    amount: EuroCent,
+    reason: ReasonCode,
+    balance: EuroCent,
+}
+
+async fn adjust_account<F>(
+    State(AdjustmentState { commands, ids }): State<AdjustmentState<F>>,
+    Path(account_id): Path<Uuid>,
+    Json(Adjust {
+        direction,
+        amount,
+        reason,
+        justification,
+    }): Json<Adjust>,
+) -> Response
+where
+    F: AccountFactory,
+{
+    debug!(%account_id, "Endpoint POST /admin/accounts/:id/adjustments invoked");
+
+    let id = ids.next_id();
+    let cmd = account::Cmd::Adjust {
+        id,
+        direction,
+        amount,
+        reason,
+        justification: justification.clone(),
+    };
+    match commands.account(account_id, cmd).await {
+        Ok(snapshot) => {
+            info!(
+                %account_id,
+                %id,
+                ?direction,
+                %amount,
+                ?reason,
+                %justification,
+                "Adjusted account"
+            );
+            let adjustment = AdjustmentRepr {
+                id,
+                account_id,
+                direction,
+                amount,
+                reason,
+                balance: snapshot.state.balance().unwrap_or_default(),
+            };
+            (StatusCode::CREATED, Json(adjustment)).into_response()
+        }
+
+        Err(CommandError::Rejected(account::Error::NotYetCreated)) => {
+            Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Err(CommandError::Rejected(error)) => Problem::from(&error).into_response(),
+
+        Err(CommandError::Failed(error)) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot adjust account");
+            Problem::new(code).into_response()
+        }
+    }
+}
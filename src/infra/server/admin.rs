@@ -0,0 +1,88 @@
+use crate::infra::{dead_letter::DeadLetterQueue, projection::Projections};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Router for the admin endpoints, to be nested under `/admin`.
+pub fn router<D, S>(dead_letter_queue: D, projections: Projections) -> Router<S>
+where
+    D: DeadLetterQueue,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/projections", get(list_projections))
+        .route("/dead-letters", get(list_dead_letters))
+        .route("/dead-letters/:id", delete(discard_dead_letter))
+        .route("/dead-letters/:id/replay", post(replay_dead_letter))
+        .with_state(AdminState {
+            dead_letter_queue,
+            projections,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct AdminState<D> {
+    dead_letter_queue: D,
+    projections: Projections,
+}
+
+async fn list_projections<D>(State(admin_state): State<AdminState<D>>) -> impl IntoResponse
+where
+    D: DeadLetterQueue,
+{
+    debug!("Endpoint /admin/projections invoked");
+    Json(admin_state.projections.statuses())
+}
+
+async fn list_dead_letters<D>(State(admin_state): State<AdminState<D>>) -> impl IntoResponse
+where
+    D: DeadLetterQueue,
+{
+    debug!("Endpoint /admin/dead-letters invoked");
+    Json(admin_state.dead_letter_queue.list().await)
+}
+
+async fn discard_dead_letter<D>(
+    State(admin_state): State<AdminState<D>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse
+where
+    D: DeadLetterQueue,
+{
+    if admin_state.dead_letter_queue.discard(id).await {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Replay the dead letter, optionally with a corrected event.
+async fn replay_dead_letter<D>(
+    State(admin_state): State<AdminState<D>>,
+    Path(id): Path<Uuid>,
+    replay: Option<Json<Replay>>,
+) -> impl IntoResponse
+where
+    D: DeadLetterQueue,
+{
+    debug!(%id, "Endpoint /admin/dead-letters/:id/replay invoked");
+    let evt = replay.and_then(|Json(replay)| replay.evt.map(|evt| evt.to_string()));
+    if admin_state.dead_letter_queue.replay(id, evt).await {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Replay {
+    /// The corrected event replacing the one of the dead letter.
+    evt: Option<serde_json::Value>,
+}
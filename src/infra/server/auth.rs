@@ -0,0 +1,129 @@
+use super::signing::Partner;
+use crate::infra::auth::{self, Auth};
+use axum::{
+    body::Body,
+    extract::State,
+    headers::{authorization::Bearer, Authorization},
+    http::{header::WWW_AUTHENTICATE, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router, TypedHeader,
+};
+use serde::Deserialize;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the auth endpoints, to be nested under `/auth`.
+pub fn router<S>(auth: Auth) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/login", post(login))
+        .route("/refresh", post(refresh))
+        .route("/logout", post(logout))
+        .with_state(auth)
+}
+
+/// State for [authenticate].
+#[derive(Debug, Clone)]
+pub struct Authentication {
+    auth: Auth,
+    fallback: bool,
+}
+
+impl Authentication {
+    /// Requests without valid access token are only passed on, if `fallback` is `true`, i.e. if
+    /// other authentication modes follow; else they are rejected.
+    pub fn new(auth: Auth, fallback: bool) -> Self {
+        Self { auth, fallback }
+    }
+}
+
+/// Middleware authenticating requests with access tokens issued by [Auth] and adding their
+/// [Claims](auth::Claims) to the request extensions. Requests already authenticated by a partner
+/// signature are passed on, as are requests without valid access token if other authentication
+/// modes follow; else these are rejected with `401 Unauthorized`.
+pub async fn authenticate(
+    State(authentication): State<Authentication>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    mut request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if request.extensions().get::<Partner>().is_some() {
+        return next.run(request).await;
+    }
+
+    if let Some(TypedHeader(authorization)) = authorization {
+        match authentication.auth.validate(authorization.token()) {
+            Ok(claims) => {
+                debug!(sub = claims.sub, "Authenticated request");
+                request.extensions_mut().insert(claims);
+                return next.run(request).await;
+            }
+
+            Err(error) => debug!(error = format!("{error:#}"), "Invalid access token"),
+        }
+    }
+
+    if authentication.fallback {
+        next.run(request).await
+    } else {
+        (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Bearer")]).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Login {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Refresh {
+    refresh_token: Uuid,
+}
+
+async fn login(
+    State(auth): State<Auth>,
+    Json(Login { username, password }): Json<Login>,
+) -> Response {
+    debug!(username, "Endpoint /auth/login invoked");
+    tokens_response(auth.login(username, password).await)
+}
+
+async fn refresh(
+    State(auth): State<Auth>,
+    Json(Refresh { refresh_token }): Json<Refresh>,
+) -> Response {
+    debug!("Endpoint /auth/refresh invoked");
+    tokens_response(auth.refresh(refresh_token))
+}
+
+async fn logout(
+    State(auth): State<Auth>,
+    Json(Refresh { refresh_token }): Json<Refresh>,
+) -> impl IntoResponse {
+    debug!("Endpoint /auth/logout invoked");
+    if auth.logout(refresh_token) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+fn tokens_response(tokens: Result<auth::Tokens, auth::Error>) -> Response {
+    match tokens {
+        Ok(tokens) => Json(tokens).into_response(),
+
+        Err(error @ (auth::Error::InvalidCredentials | auth::Error::InvalidRefreshToken)) => {
+            (StatusCode::UNAUTHORIZED, error.to_string()).into_response()
+        }
+
+        Err(error) => {
+            error!(error = format!("{error:#}"), "Cannot issue tokens");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
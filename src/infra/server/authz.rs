@@ -0,0 +1,175 @@
+#[cfg(feature = "mtls")]
+use super::mtls::Principal;
+use super::{access_log::Subject, policy, signing::Partner};
+#[cfg(feature = "auth")]
+use crate::infra::auth;
+#[cfg(feature = "oidc")]
+use crate::infra::oidc::Claims;
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequestParts, MatchedPath},
+    http::{request::Parts, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{collections::HashSet, convert::Infallible};
+use tracing::debug;
+
+/// Scopes required to access routes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    AccountsRead,
+    AccountsWrite,
+    Admin,
+    CardProcessor,
+    Finance,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::AccountsRead => "accounts:read",
+            Scope::AccountsWrite => "accounts:write",
+            Scope::Admin => "admin",
+            Scope::CardProcessor => "card-processor",
+            Scope::Finance => "finance",
+        }
+    }
+}
+
+/// The [Scope] required for the given method and route as defined by its [Policy](policy::Policy),
+/// if any. Routes without policy are denied.
+pub fn required_scope(method: &Method, route: &str) -> Option<Scope> {
+    policy::lookup(method, route).map(|policy| policy.scope)
+}
+
+/// Extractor for the scopes granted to the authenticated caller, i.e. the roles of the signing
+/// partner, the scopes of the access or bearer token and the roles of the client certificate
+/// principal. Empty, if the caller is not authenticated.
+#[derive(Debug, Clone, Default)]
+pub struct Scopes(HashSet<String>);
+
+impl Scopes {
+    /// Is the given [Scope] granted?
+    pub fn grants(&self, scope: Scope) -> bool {
+        self.0.contains(scope.as_str())
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Scopes
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let mut scopes = HashSet::new();
+
+        if let Some(partner) = parts.extensions.get::<Partner>() {
+            scopes.extend(partner.roles.iter().cloned());
+        }
+
+        #[cfg(feature = "auth")]
+        if let Some(claims) = parts.extensions.get::<auth::Claims>() {
+            scopes.extend(claims.scopes().map(ToString::to_string));
+        }
+
+        #[cfg(feature = "oidc")]
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            scopes.extend(claims.scopes().map(ToString::to_string));
+        }
+
+        #[cfg(feature = "mtls")]
+        if let Some(principal) = parts.extensions.get::<Principal>() {
+            scopes.extend(principal.roles.iter().cloned());
+        }
+
+        Ok(Scopes(scopes))
+    }
+}
+
+/// Middleware rejecting requests lacking the [Scope] required for their route. Only to be used if
+/// any authentication is configured, because unauthenticated requests are rejected.
+pub async fn authorize(
+    method: Method,
+    route: Option<MatchedPath>,
+    scopes: Scopes,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let subject = subject(&request);
+    let scope = route.and_then(|route| required_scope(&method, route.as_str()));
+    let mut response = match scope {
+        Some(scope) if scopes.grants(scope) => next.run(request).await,
+
+        _ => {
+            debug!(%method, ?scope, "Forbidden request");
+            StatusCode::FORBIDDEN.into_response()
+        }
+    };
+
+    if let Some(subject) = subject {
+        response.extensions_mut().insert(subject);
+    }
+    response
+}
+
+/// The authenticated subject, i.e. the key ID of the signing partner or the subject of the access
+/// or bearer token or of the client certificate.
+pub fn subject(request: &Request<Body>) -> Option<Subject> {
+    if let Some(partner) = request.extensions().get::<Partner>() {
+        return Some(Subject(partner.key_id.clone()));
+    }
+
+    #[cfg(feature = "auth")]
+    if let Some(claims) = request.extensions().get::<auth::Claims>() {
+        return Some(Subject(claims.sub.clone()));
+    }
+
+    #[cfg(feature = "oidc")]
+    if let Some(claims) = request.extensions().get::<Claims>() {
+        return Some(Subject(claims.sub.clone()));
+    }
+
+    #[cfg(feature = "mtls")]
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        return Some(Subject(principal.subject.clone()));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(
+            required_scope(&Method::POST, "/accounts/:id/deposits"),
+            Some(Scope::AccountsWrite)
+        );
+        assert_eq!(
+            required_scope(&Method::DELETE, "/admin/dead-letters/:id"),
+            Some(Scope::Admin)
+        );
+        assert_eq!(required_scope(&Method::GET, "/accounts/:id/deposits"), None);
+    }
+
+    #[tokio::test]
+    async fn test_scopes() {
+        let (mut parts, _) = Request::get("/accounts/42").body(()).unwrap().into_parts();
+        let scopes = Scopes::from_request_parts(&mut parts, &()).await.unwrap();
+        assert!(!scopes.grants(Scope::AccountsRead));
+
+        parts.extensions.insert(Partner {
+            key_id: "partner".to_string(),
+            roles: vec!["accounts:read".to_string()],
+        });
+        let scopes = Scopes::from_request_parts(&mut parts, &()).await.unwrap();
+        assert!(scopes.grants(Scope::AccountsRead));
+        assert!(!scopes.grants(Scope::AccountsWrite));
+    }
+}
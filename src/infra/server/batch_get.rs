@@ -0,0 +1,101 @@
+use super::{problem::Problem, Tenant};
+use crate::infra::{
+    account::{AccountSummariesProjection, AccountSummary},
+    error_code::ErrorCode,
+};
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use futures::future;
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroUsize;
+use tracing::debug;
+use uuid::Uuid;
+
+/// The custom method of the batch get route, which the router matches as a parameter.
+const BATCH_GET: &str = ":batchGet";
+
+/// Router for reading the summaries of multiple accounts in one request, to be merged into the
+/// account routes.
+pub fn router<P, S>(account_summaries: P, max_ids: NonZeroUsize) -> Router<S>
+where
+    P: AccountSummariesProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts:batchGet", post(batch_get_accounts))
+        .with_state(BatchGetState {
+            account_summaries,
+            max_ids,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct BatchGetState<P> {
+    account_summaries: P,
+    max_ids: NonZeroUsize,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchGet {
+    ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchGetResponse {
+    /// In the order of the requested IDs.
+    results: Vec<BatchGetResult>,
+}
+
+/// Either the summary of an account or the error for its ID, such that some accounts can be found
+/// while others cannot.
+#[derive(Debug, Serialize)]
+struct BatchGetResult {
+    id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<AccountSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorCode>,
+}
+
+/// Accounts of other tenants are not found.
+async fn batch_get_accounts<P>(
+    State(batch_get_state): State<BatchGetState<P>>,
+    Path(method): Path<String>,
+    Tenant(tenant): Tenant,
+    Json(BatchGet { ids }): Json<BatchGet>,
+) -> Response
+where
+    P: AccountSummariesProjection,
+{
+    debug!(ids = ids.len(), "Endpoint POST /accounts:batchGet invoked");
+
+    if method != BATCH_GET {
+        return Problem::new(ErrorCode::NotFound).into_response();
+    }
+    let max_ids = batch_get_state.max_ids.get();
+    if ids.is_empty() || ids.len() > max_ids {
+        return Problem::new(ErrorCode::InvalidRequest)
+            .with_detail(format!("Between 1 and {max_ids} IDs must be given"))
+            .into_response();
+    }
+
+    let account_summaries = &batch_get_state.account_summaries;
+    let results = future::join_all(ids.into_iter().map(|id| async move {
+        let summary = account_summaries
+            .summary(id)
+            .await
+            .filter(|summary| summary.tenant == tenant);
+        BatchGetResult {
+            id,
+            error: summary.is_none().then_some(ErrorCode::NotFound),
+            summary,
+        }
+    }))
+    .await;
+
+    Json(BatchGetResponse { results }).into_response()
+}
@@ -0,0 +1,72 @@
+use super::problem::Problem;
+use crate::{
+    domain::books,
+    infra::{
+        books::BookKeeper, error_code::ErrorCode, gl_export::GlExporter, ledger::LedgerProjection,
+        reporting::ReportPeriod,
+    },
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::{debug, error};
+
+/// Router for the period close endpoints, to be merged into the account routes.
+pub fn router<G, E, S>(book_keeper: BookKeeper<G, E>) -> Router<S>
+where
+    G: LedgerProjection,
+    E: GlExporter,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/periods", get(list_closed_periods))
+        .route("/admin/periods/close", post(close_period))
+        .with_state(book_keeper)
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct ClosePeriod {
+    /// Close through the end of the last completed day (EOD) or month (EOM).
+    period: ReportPeriod,
+}
+
+async fn list_closed_periods<G, E>(State(book_keeper): State<BookKeeper<G, E>>) -> impl IntoResponse
+where
+    G: LedgerProjection,
+    E: GlExporter,
+{
+    debug!("Endpoint GET /admin/periods invoked");
+    Json(book_keeper.closed_periods().list())
+}
+
+async fn close_period<G, E>(
+    State(book_keeper): State<BookKeeper<G, E>>,
+    Json(ClosePeriod { period }): Json<ClosePeriod>,
+) -> Response
+where
+    G: LedgerProjection,
+    E: GlExporter,
+{
+    debug!(?period, "Endpoint POST /admin/periods/close invoked");
+
+    match book_keeper.close(period).await {
+        Ok(Ok(closed_period)) => (StatusCode::CREATED, Json(closed_period)).into_response(),
+
+        Ok(Err(error @ books::Error::NotContiguous)) => {
+            (StatusCode::CONFLICT, error.to_string()).into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, error = format!("{error:#}"), "Cannot close period");
+            Problem::new(code).into_response()
+        }
+    }
+}
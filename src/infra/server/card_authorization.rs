@@ -0,0 +1,212 @@
+use super::{problem::Problem, AppState};
+use crate::{
+    domain::{account, euro_cent::EuroCent, redaction::Redacted},
+    infra::{
+        account::{AccountFactory, AccountSummariesProjection},
+        error_code::ErrorCode,
+        lru_cache_factory::Priority,
+    },
+};
+use anyhow::Context;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::{task, time};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Message from the card processor.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum CardMessage {
+    /// Real-time authorization, placing a hold if approved.
+    Authorization {
+        authorization_id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+    },
+
+    /// Clearing of an approved authorization, capturing the hold.
+    Clearing {
+        authorization_id: Uuid,
+        account_id: Uuid,
+        amount: EuroCent,
+    },
+
+    /// Reversal of an approved authorization, releasing the hold.
+    Reversal {
+        authorization_id: Uuid,
+        account_id: Uuid,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AuthorizationDecision {
+    authorization_id: Uuid,
+    decision: Decision,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Decision {
+    Approve,
+    Decline,
+}
+
+/// Handle messages from the card processor. Authorizations take a fast path directly to the
+/// account entity, bypassing the projection, and are declined if not decided within the configured
+/// latency budget.
+pub async fn ingest_card_message<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Json(message): Json<CardMessage>,
+) -> Response
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    debug!(message = ?Redacted(&message), "Endpoint /ingest/card-authorizations invoked");
+
+    match message {
+        CardMessage::Authorization {
+            authorization_id,
+            account_id,
+            amount,
+        } => {
+            let budget = Duration::from_millis(app_state.config.card_authorization_budget_ms);
+            authorize(
+                app_state.account_factory,
+                authorization_id,
+                account_id,
+                amount,
+                budget,
+            )
+            .await
+        }
+
+        CardMessage::Clearing {
+            authorization_id,
+            account_id,
+            amount,
+        } => {
+            settle(
+                app_state.account_factory,
+                account_id,
+                account::Cmd::CaptureHold(authorization_id, amount),
+            )
+            .await
+        }
+
+        CardMessage::Reversal {
+            authorization_id,
+            account_id,
+        } => {
+            settle(
+                app_state.account_factory,
+                account_id,
+                account::Cmd::ReleaseHold(authorization_id),
+            )
+            .await
+        }
+    }
+}
+
+async fn authorize<F>(
+    account_factory: F,
+    authorization_id: Uuid,
+    account_id: Uuid,
+    amount: EuroCent,
+    budget: Duration,
+) -> Response
+where
+    F: AccountFactory,
+{
+    let mut place_hold = task::spawn(async move {
+        let account = account_factory
+            .get_with_priority(account_id, Priority::Critical)
+            .await
+            .context("Cannot get Account entity")?;
+        let result = account
+            .handle_cmd(account::Cmd::PlaceHold(authorization_id, amount))
+            .await
+            .context("Cannot handle PlaceHold command")?;
+        Ok::<_, anyhow::Error>((account, result))
+    });
+
+    let (decision, reason) = match time::timeout(budget, &mut place_hold).await {
+        Ok(Ok(Ok((_, Ok(_))))) => (Decision::Approve, None),
+
+        Ok(Ok(Ok((_, Err(error))))) => (Decision::Decline, Some(error.to_string())),
+
+        Ok(Ok(Err(error))) => {
+            error!(%account_id, error = format!("{error:#}"), "Cannot authorize");
+            (Decision::Decline, Some("technical error".to_string()))
+        }
+
+        Ok(Err(error)) => {
+            error!(%account_id, error = format!("{error:#}"), "Cannot authorize");
+            (Decision::Decline, Some("technical error".to_string()))
+        }
+
+        // Declined, hence release the hold, if placed nevertheless.
+        Err(_) => {
+            warn!(%account_id, %authorization_id, "Authorization exceeded latency budget");
+            task::spawn(async move {
+                if let Ok(Ok((account, Ok(_)))) = place_hold.await {
+                    let result = account
+                        .handle_cmd(account::Cmd::ReleaseHold(authorization_id))
+                        .await;
+                    if !matches!(result, Ok(Ok(_))) {
+                        error!(%account_id, %authorization_id, "Cannot release late hold");
+                    }
+                }
+            });
+            (Decision::Decline, Some("timeout".to_string()))
+        }
+    };
+
+    Json(AuthorizationDecision {
+        authorization_id,
+        decision,
+        reason,
+    })
+    .into_response()
+}
+
+async fn settle<F>(account_factory: F, account_id: Uuid, cmd: account::Cmd) -> Response
+where
+    F: AccountFactory,
+{
+    let result = async {
+        account_factory
+            .get_with_priority(account_id, Priority::Critical)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(_)) => StatusCode::OK.into_response(),
+
+        Ok(Err(account::Error::UnknownHold(_))) => {
+            Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot settle card message");
+            Problem::new(code).into_response()
+        }
+    }
+}
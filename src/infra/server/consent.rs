@@ -0,0 +1,166 @@
+use super::problem::Problem;
+use crate::{
+    domain::{
+        consent::{self, ConsentScope},
+        id::IdGenerator,
+    },
+    infra::{consent::ConsentFactory, error_code::ErrorCode},
+};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, post},
+    Json, Router, TypedHeader,
+};
+use serde::{Deserialize, Serialize};
+use std::{iter, sync::Arc};
+use time::OffsetDateTime;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the consent endpoints, to be merged into the account routes.
+pub fn router<C, S>(consent_factory: C, ids: Arc<dyn IdGenerator>) -> Router<S>
+where
+    C: ConsentFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/consents", post(grant_consent))
+        .route("/consents/:id", delete(revoke_consent))
+        .with_state(ConsentState {
+            consent_factory,
+            ids,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct ConsentState<C> {
+    consent_factory: C,
+    ids: Arc<dyn IdGenerator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GrantConsent {
+    third_party: String,
+    scope: ConsentScope,
+    #[serde(with = "time::serde::rfc3339")]
+    expires_at: OffsetDateTime,
+}
+
+/// Representation of a consent.
+#[derive(Debug, Clone, Serialize)]
+struct ConsentRepr {
+    id: Uuid,
+    account_id: Uuid,
+    third_party: String,
+    scope: ConsentScope,
+    #[serde(with = "time::serde::rfc3339")]
+    expires_at: OffsetDateTime,
+}
+
+async fn grant_consent<C>(
+    State(ConsentState {
+        consent_factory,
+        ids,
+    }): State<ConsentState<C>>,
+    Path(account_id): Path<Uuid>,
+    Json(GrantConsent {
+        third_party,
+        scope,
+        expires_at,
+    }): Json<GrantConsent>,
+) -> Response
+where
+    C: ConsentFactory,
+{
+    debug!(%account_id, "Endpoint POST /accounts/:id/consents invoked");
+
+    let id = ids.next_id();
+    let cmd = consent::Cmd::Grant {
+        id,
+        account_id,
+        third_party: third_party.clone(),
+        scope,
+        expires_at,
+    };
+    let result = async {
+        consent_factory
+            .get(id)
+            .await
+            .context("Cannot get Consent entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Grant command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            let location_value = HeaderValue::from_str(&format!("/consents/{id}")).unwrap();
+            let mut location_value = iter::once(&location_value);
+            let location = Location::decode(&mut location_value).unwrap();
+            (
+                StatusCode::CREATED,
+                TypedHeader(location),
+                Json(ConsentRepr {
+                    id,
+                    account_id,
+                    third_party,
+                    scope,
+                    expires_at,
+                }),
+            )
+                .into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot grant consent");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn revoke_consent<C>(
+    State(ConsentState {
+        consent_factory, ..
+    }): State<ConsentState<C>>,
+    Path(id): Path<Uuid>,
+) -> Response
+where
+    C: ConsentFactory,
+{
+    debug!(%id, "Endpoint DELETE /consents/:id invoked");
+
+    let result = async {
+        consent_factory
+            .get(id)
+            .await
+            .context("Cannot get Consent entity")?
+            .handle_cmd(consent::Cmd::Revoke)
+            .await
+            .context("Cannot handle Revoke command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) | Ok(Err(consent::Error::AlreadyRevoked)) => {
+            StatusCode::NO_CONTENT.into_response()
+        }
+
+        Ok(Err(consent::Error::NotYetGranted)) => Problem::new(ErrorCode::NotFound).into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot revoke consent");
+            Problem::new(code).into_response()
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::infra::account::data_export::{DataExportStatus, DataExporter};
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{header::CONTENT_DISPOSITION, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router, TypedHeader,
+};
+use serde_json::json;
+use std::iter;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Router for the data export endpoints, to be merged into the account routes.
+pub fn router<X, S>(data_exporter: X) -> Router<S>
+where
+    X: DataExporter,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(
+            "/accounts/:id/data-export",
+            post(start_data_export).get(get_data_export),
+        )
+        .with_state(data_exporter)
+}
+
+async fn start_data_export<X>(
+    State(data_exporter): State<X>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse
+where
+    X: DataExporter,
+{
+    debug!(%id, "Endpoint POST /accounts/:id/data-export invoked");
+    data_exporter.start(id).await;
+
+    let location_value = HeaderValue::from_str(&format!("/accounts/{id}/data-export")).unwrap();
+    let mut location_value = iter::once(&location_value);
+    let location = Location::decode(&mut location_value).unwrap();
+    (StatusCode::ACCEPTED, TypedHeader(location))
+}
+
+async fn get_data_export<X>(State(data_exporter): State<X>, Path(id): Path<Uuid>) -> Response
+where
+    X: DataExporter,
+{
+    debug!(%id, "Endpoint GET /accounts/:id/data-export invoked");
+    match data_exporter.status(id).await {
+        Some(DataExportStatus::Ready(export)) => {
+            let content_disposition = format!("attachment; filename=\"account-{id}.json\"");
+            (
+                [(CONTENT_DISPOSITION, content_disposition)],
+                Json(export.as_ref().clone()),
+            )
+                .into_response()
+        }
+
+        Some(DataExportStatus::Pending) => {
+            (StatusCode::ACCEPTED, Json(json!({ "status": "pending" }))).into_response()
+        }
+
+        Some(DataExportStatus::Failed) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "status": "failed" })),
+        )
+            .into_response(),
+
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -0,0 +1,258 @@
+use super::problem::Problem;
+use crate::{
+    application::queries::Queries,
+    domain::{dispute, euro_cent::EuroCent, id::IdGenerator},
+    infra::{
+        dispute::DisputeFactory, error_code::ErrorCode, ledger::LedgerProjection,
+        transactions::TransactionsProjection, treasury::PositionsProjection,
+    },
+};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router, TypedHeader,
+};
+use serde::{Deserialize, Serialize};
+use std::{iter, sync::Arc};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the dispute endpoints, to be merged into the account routes. Disputes are resolved
+/// within the given number of days, else they are upheld.
+pub fn router<Q, G, R, D, S>(
+    queries: Queries<Q, G, R>,
+    dispute_factory: D,
+    deadline_days: u16,
+    ids: Arc<dyn IdGenerator>,
+) -> Router<S>
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    D: DisputeFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(
+            "/accounts/:id/transactions/:transaction_id/disputes",
+            post(open_dispute),
+        )
+        .route(
+            "/accounts/:id/transactions/:transaction_id/disputes/:dispute_id/provisional-credit",
+            post(credit_dispute_provisionally),
+        )
+        .route(
+            "/accounts/:id/transactions/:transaction_id/disputes/:dispute_id/resolution",
+            post(resolve_dispute),
+        )
+        .with_state(DisputeState {
+            queries,
+            dispute_factory,
+            deadline_days,
+            ids,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct DisputeState<Q, G, R, D> {
+    queries: Queries<Q, G, R>,
+    dispute_factory: D,
+    deadline_days: u16,
+    ids: Arc<dyn IdGenerator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OpenDispute {
+    /// Defaults to the whole amount of the transaction.
+    amount: Option<EuroCent>,
+    reason: String,
+}
+
+/// Representation of a dispute.
+#[derive(Debug, Clone, Serialize)]
+struct DisputeRepr {
+    id: Uuid,
+    account_id: Uuid,
+    transaction_id: Uuid,
+    amount: EuroCent,
+    reason: String,
+    deadline_days: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Resolve {
+    outcome: dispute::Outcome,
+}
+
+/// Only debits of the account, as projected to the ledger, can be disputed, for at most their
+/// amount.
+async fn open_dispute<Q, G, R, D>(
+    State(dispute_state): State<DisputeState<Q, G, R, D>>,
+    Path((account_id, transaction_id)): Path<(Uuid, Uuid)>,
+    Json(OpenDispute { amount, reason }): Json<OpenDispute>,
+) -> Response
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    D: DisputeFactory,
+{
+    debug!(
+        %account_id,
+        %transaction_id,
+        "Endpoint POST /accounts/:id/transactions/:transaction_id/disputes invoked"
+    );
+
+    let Some(entry) = dispute_state
+        .queries
+        .ledger_entry(transaction_id)
+        .await
+        .filter(|entry| entry.account_id == account_id)
+    else {
+        return Problem::new(ErrorCode::NotFound).into_response();
+    };
+    if !entry.kind.is_debit() {
+        return Problem::new(ErrorCode::InvalidRequest)
+            .with_detail("Only debits can be disputed")
+            .into_response();
+    }
+    let amount = amount.unwrap_or(entry.amount);
+    if amount > entry.amount {
+        return Problem::new(ErrorCode::LimitExceeded)
+            .with_detail("Disputed amount exceeds the amount of the transaction")
+            .into_response();
+    }
+
+    let id = dispute_state.ids.next_id();
+    let deadline_days = dispute_state.deadline_days;
+    let cmd = dispute::Cmd::Open {
+        id,
+        account_id,
+        transaction_id,
+        amount,
+        reason: reason.clone(),
+        deadline_days,
+    };
+    let result = async {
+        dispute_state
+            .dispute_factory
+            .get(id)
+            .await
+            .context("Cannot get Dispute entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Open command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            let location_value = HeaderValue::from_str(&format!(
+                "/accounts/{account_id}/transactions/{transaction_id}/disputes/{id}"
+            ))
+            .unwrap();
+            let mut location_value = iter::once(&location_value);
+            let location = Location::decode(&mut location_value).unwrap();
+            (
+                StatusCode::CREATED,
+                TypedHeader(location),
+                Json(DisputeRepr {
+                    id,
+                    account_id,
+                    transaction_id,
+                    amount,
+                    reason,
+                    deadline_days,
+                }),
+            )
+                .into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot open dispute");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn credit_dispute_provisionally<Q, G, R, D>(
+    State(dispute_state): State<DisputeState<Q, G, R, D>>,
+    Path((_, _, id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Response
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    D: DisputeFactory,
+{
+    debug!(
+        %id,
+        "Endpoint POST /accounts/:id/transactions/:transaction_id/disputes/:dispute_id/provisional-credit invoked"
+    );
+    handle_cmd(
+        &dispute_state.dispute_factory,
+        id,
+        dispute::Cmd::CreditProvisionally,
+    )
+    .await
+}
+
+async fn resolve_dispute<Q, G, R, D>(
+    State(dispute_state): State<DisputeState<Q, G, R, D>>,
+    Path((_, _, id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(Resolve { outcome }): Json<Resolve>,
+) -> Response
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    D: DisputeFactory,
+{
+    debug!(
+        %id,
+        ?outcome,
+        "Endpoint POST /accounts/:id/transactions/:transaction_id/disputes/:dispute_id/resolution invoked"
+    );
+    let cmd = match outcome {
+        dispute::Outcome::Upheld => dispute::Cmd::Uphold,
+        dispute::Outcome::Denied => dispute::Cmd::Deny,
+    };
+    handle_cmd(&dispute_state.dispute_factory, id, cmd).await
+}
+
+async fn handle_cmd<D>(dispute_factory: &D, id: Uuid, cmd: dispute::Cmd) -> Response
+where
+    D: DisputeFactory,
+{
+    let result = async {
+        dispute_factory
+            .get(id)
+            .await
+            .context("Cannot get Dispute entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => StatusCode::NO_CONTENT.into_response(),
+
+        Ok(Err(dispute::Error::NotYetOpened)) => Problem::new(ErrorCode::NotFound).into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot handle dispute command");
+            Problem::new(code).into_response()
+        }
+    }
+}
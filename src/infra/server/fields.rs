@@ -0,0 +1,131 @@
+use axum::{
+    body::{boxed, Body, Full},
+    extract::Query,
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        Method, Request, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize)]
+struct FieldsQuery {
+    fields: Option<String>,
+}
+
+/// Selected fields of a JSON response, e.g. from `?fields=id,balance,items.id`: nested fields are
+/// selected with dots and fields of arrays apply to their elements. A field without nested fields
+/// selects its whole value.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Fields(BTreeMap<String, Fields>);
+
+impl Fields {
+    fn parse(s: &str) -> Self {
+        let mut fields = Fields::default();
+        for path in s.split(',').map(str::trim).filter(|path| !path.is_empty()) {
+            let mut node = &mut fields;
+            for name in path.split('.') {
+                node = node.0.entry(name.to_string()).or_default();
+            }
+        }
+        fields
+    }
+
+    fn select(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                object.retain(|name, _| self.0.contains_key(name));
+                for (name, value) in object.iter_mut() {
+                    if let Some(fields) = self.0.get(name).filter(|fields| !fields.0.is_empty()) {
+                        fields.select(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.select(value)),
+            _ => {}
+        }
+    }
+}
+
+/// Middleware selecting the fields given with the `fields` query parameter, i.e. sparse
+/// fieldsets, from successful JSON responses to GET requests, such that clients only fetch what
+/// they need. All other responses, e.g. problems, are passed on unchanged.
+pub async fn select(request: Request<Body>, next: Next<Body>) -> Response {
+    let fields = (request.method() == Method::GET)
+        .then(|| Query::<FieldsQuery>::try_from_uri(request.uri()).ok())
+        .flatten()
+        .and_then(|Query(query)| query.fields)
+        .map(|fields| Fields::parse(&fields))
+        .filter(|fields| !fields.0.is_empty());
+
+    let response = next.run(request).await;
+    let Some(fields) = fields else {
+        return response;
+    };
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|content_type| content_type == "application/json");
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(error) => {
+            warn!(%error, "Cannot read response body for selecting fields");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let mut value = match serde_json::from_slice::<Value>(&body) {
+        Ok(value) => value,
+        Err(error) => {
+            warn!(%error, "Cannot parse JSON response for selecting fields");
+            return Response::from_parts(parts, boxed(Full::from(body)));
+        }
+    };
+    debug!(?fields, "Selecting fields");
+    fields.select(&mut value);
+
+    parts.headers.remove(CONTENT_LENGTH);
+    let body = serde_json::to_vec(&value).expect("JSON value can be serialized");
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select() {
+        let mut value = json!({
+            "items": [
+                { "id": 1, "kind": "deposit", "amount": 42 },
+                { "id": 2, "kind": "withdrawal", "amount": 21 }
+            ],
+            "total": 2,
+            "next_offset": 2
+        });
+        Fields::parse("items.id, items.amount,total").select(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "items": [{ "id": 1, "amount": 42 }, { "id": 2, "amount": 21 }],
+                "total": 2
+            })
+        );
+
+        let mut value = json!({ "account_id": "a", "points": [{ "date": "2023-06-30" }] });
+        Fields::parse("points").select(&mut value);
+        assert_eq!(value, json!({ "points": [{ "date": "2023-06-30" }] }));
+
+        assert!(Fields::parse(" ,").0.is_empty());
+    }
+}
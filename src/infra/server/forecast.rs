@@ -0,0 +1,87 @@
+use super::problem::Problem;
+use crate::{
+    application::analytics::{Analytics, Horizon},
+    infra::{
+        account::{AccountStatus, AccountSummariesProjection},
+        error_code::ErrorCode,
+        ledger::LedgerProjection,
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Router for the forecast endpoints, to be merged into the account routes.
+pub fn router<P, G, S>(account_summaries: P, analytics: Analytics<G>) -> Router<S>
+where
+    P: AccountSummariesProjection,
+    G: LedgerProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/forecast", get(get_forecast))
+        .with_state(ForecastState {
+            account_summaries,
+            analytics,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct ForecastState<P, G> {
+    account_summaries: P,
+    analytics: Analytics<G>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastQuery {
+    /// Number of days like `30d`, defaults to 30 days.
+    horizon: Option<String>,
+}
+
+async fn get_forecast<P, G>(
+    State(forecast_state): State<ForecastState<P, G>>,
+    Path(id): Path<Uuid>,
+    Query(ForecastQuery { horizon }): Query<ForecastQuery>,
+) -> Response
+where
+    P: AccountSummariesProjection,
+    G: LedgerProjection,
+{
+    debug!(%id, ?horizon, "Endpoint GET /accounts/:id/forecast invoked");
+
+    let horizon = match horizon.map(|horizon| horizon.parse::<Horizon>()) {
+        Some(Ok(horizon)) => horizon,
+        Some(Err(error)) => {
+            return Problem::new(ErrorCode::from(&error))
+                .with_detail(error.to_string())
+                .into_response()
+        }
+        None => Horizon::default(),
+    };
+
+    let Some(summary) = forecast_state
+        .account_summaries
+        .summary(id)
+        .await
+        .filter(|summary| summary.status == AccountStatus::Open)
+    else {
+        return Problem::new(ErrorCode::NotFound).into_response();
+    };
+
+    match forecast_state
+        .analytics
+        .forecast(id, summary.balance, horizon)
+        .await
+    {
+        Ok(forecast) => Json(forecast).into_response(),
+        Err(error) => Problem::new(ErrorCode::from(&error))
+            .with_detail(error.to_string())
+            .into_response(),
+    }
+}
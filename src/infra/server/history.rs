@@ -0,0 +1,110 @@
+use super::problem::Problem;
+use crate::{
+    domain::{account, currency::Currency, euro_cent::EuroCent},
+    infra::{account::history::AccountHistory, error_code::ErrorCode},
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the history endpoints, to be merged into the account routes.
+pub fn router<H, S>(account_history: H) -> Router<S>
+where
+    H: AccountHistory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/balance", get(get_balance))
+        .route("/admin/accounts/:id/what-if", post(what_if))
+        .with_state(account_history)
+}
+
+#[derive(Debug, Deserialize)]
+struct AsOfQuery {
+    /// Defaults to now.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    as_of: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+struct Balance {
+    account_id: Uuid,
+    currency: Currency,
+    balance: EuroCent,
+    available_balance: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    as_of: OffsetDateTime,
+    /// Number of events the balance is based on.
+    seq_no: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhatIfRequest {
+    cmds: Vec<account::Cmd>,
+}
+
+async fn get_balance<H>(
+    State(account_history): State<H>,
+    Path(account_id): Path<Uuid>,
+    Query(AsOfQuery { as_of }): Query<AsOfQuery>,
+) -> Response
+where
+    H: AccountHistory,
+{
+    debug!(%account_id, ?as_of, "Endpoint GET /accounts/:id/balance invoked");
+
+    let as_of = as_of.unwrap_or_else(OffsetDateTime::now_utc);
+    match account_history.snapshot_as_of(account_id, as_of).await {
+        Ok(snapshot) => {
+            let state = snapshot.state;
+            let (Some(currency), Some(balance), Some(available_balance)) =
+                (state.currency(), state.balance(), state.available_balance())
+            else {
+                return Problem::new(ErrorCode::NotFound).into_response();
+            };
+            Json(Balance {
+                account_id,
+                currency,
+                balance,
+                available_balance,
+                as_of,
+                seq_no: snapshot.seq_no,
+            })
+            .into_response()
+        }
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot replay account");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn what_if<H>(
+    State(account_history): State<H>,
+    Path(account_id): Path<Uuid>,
+    Json(WhatIfRequest { cmds }): Json<WhatIfRequest>,
+) -> Response
+where
+    H: AccountHistory,
+{
+    debug!(%account_id, cmds = cmds.len(), "Endpoint POST /admin/accounts/:id/what-if invoked");
+
+    match account_history.what_if(account_id, cmds).await {
+        Ok(what_if) => Json(what_if).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot sandbox account");
+            Problem::new(code).into_response()
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use super::AccountConflict;
+use crate::domain::{account, books, consent, dispute, loan, mandate, term_deposit, transfer};
+use axum::{
+    body::Body,
+    http::{header::ACCEPT_LANGUAGE, Request},
+    middleware::Next,
+    response::Response,
+};
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use std::{error::Error as StdError, sync::OnceLock};
+use unic_langid::{langid, LanguageIdentifier};
+
+/// Message catalogs in Fluent syntax, the first one being the default.
+const CATALOGS: [(LanguageIdentifier, &str); 2] = [
+    (
+        langid!("en"),
+        include_str!("../../../locales/en/errors.ftl"),
+    ),
+    (
+        langid!("de"),
+        include_str!("../../../locales/de/errors.ftl"),
+    ),
+];
+
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+tokio::task_local! {
+    /// The language negotiated for the current request.
+    static LANGUAGE: LanguageIdentifier;
+}
+
+/// An error with a customer-facing message in the catalogs.
+pub trait Localize: StdError {
+    /// The ID of the message.
+    fn message_id(&self) -> &'static str;
+
+    /// The arguments of the message, if any.
+    fn message_args(&self) -> FluentArgs<'static> {
+        FluentArgs::new()
+    }
+}
+
+/// Middleware negotiating the language for customer-facing messages from the `Accept-Language`
+/// header of the request.
+pub async fn negotiate_language(request: Request<Body>, next: Next<Body>) -> Response {
+    let language = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(negotiate)
+        .unwrap_or_else(|| CATALOGS[0].0.clone());
+    LANGUAGE.scope(language, next.run(request)).await
+}
+
+/// The message for the given error in the language negotiated for the current request, falling
+/// back to the error's own (English) message if not localizable.
+pub fn localize<E>(error: &E) -> String
+where
+    E: Localize,
+{
+    let language = LANGUAGE
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| CATALOGS[0].0.clone());
+    localize_to(error, &language).unwrap_or_else(|| error.to_string())
+}
+
+fn localize_to<E>(error: &E, language: &LanguageIdentifier) -> Option<String>
+where
+    E: Localize,
+{
+    let bundle = bundles()
+        .iter()
+        .find(|bundle| bundle.locales.first() == Some(language))?;
+    let pattern = bundle.get_message(error.message_id())?.value()?;
+    let args = error.message_args();
+    let mut errors = vec![];
+    let message = bundle.format_pattern(pattern, Some(&args), &mut errors);
+    errors.is_empty().then(|| message.into_owned())
+}
+
+fn bundles() -> &'static [FluentBundle<FluentResource>] {
+    BUNDLES.get_or_init(|| {
+        CATALOGS
+            .into_iter()
+            .map(|(language, catalog)| {
+                let resource =
+                    FluentResource::try_new(catalog.to_string()).expect("catalog can be parsed");
+                let mut bundle = FluentBundle::new_concurrent(vec![language]);
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(resource)
+                    .expect("catalog has unique message IDs");
+                bundle
+            })
+            .collect()
+    })
+}
+
+/// The first supported language of the given `Accept-Language` value, by descending quality.
+fn negotiate(accept_language: &str) -> Option<LanguageIdentifier> {
+    let mut languages = accept_language
+        .split(',')
+        .filter_map(|language| {
+            let mut parts = language.trim().split(';');
+            let language = parts.next()?.parse::<LanguageIdentifier>().ok()?;
+            let quality = parts
+                .find_map(|part| part.trim().strip_prefix("q="))
+                .map(|quality| quality.parse::<f32>().ok())
+                .unwrap_or(Some(1.0))?;
+            Some((language, quality))
+        })
+        .collect::<Vec<_>>();
+    languages.sort_by(|(_, q1), (_, q2)| q2.total_cmp(q1));
+
+    languages.into_iter().find_map(|(language, _)| {
+        CATALOGS
+            .iter()
+            .map(|(supported, _)| supported)
+            .find(|supported| supported.language == language.language)
+            .cloned()
+    })
+}
+
+impl Localize for account::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            account::Error::InvalidWithdraw { .. } => "account-invalid-withdraw",
+            account::Error::MaxBalanceExceeded { .. } => "account-max-balance-exceeded",
+            account::Error::NotYetCreated => "account-not-yet-created",
+            account::Error::AlreadyCreated => "account-already-created",
+            account::Error::HoldAlreadyPlaced(_) => "account-hold-already-placed",
+            account::Error::UnknownHold(_) => "account-unknown-hold",
+            account::Error::InvalidCapture { .. } => "account-invalid-capture",
+            account::Error::ZeroAdjustment => "account-zero-adjustment",
+            account::Error::MissingJustification => "account-missing-justification",
+            account::Error::Closed => "account-closed",
+            account::Error::ActiveHolds(_) => "account-active-holds",
+            account::Error::NonZeroBalance(_) => "account-non-zero-balance",
+            account::Error::SweepToSelf => "account-sweep-to-self",
+            account::Error::NotClosed => "account-not-closed",
+            account::Error::ReopenWindowExpired { .. } => "account-reopen-window-expired",
+            account::Error::Purged => "account-purged",
+            account::Error::RetentionNotExpired { .. } => "account-retention-not-expired",
+        }
+    }
+
+    fn message_args(&self) -> FluentArgs<'static> {
+        let mut args = FluentArgs::new();
+        match self {
+            account::Error::InvalidWithdraw {
+                balance,
+                withdraw_amount,
+            } => {
+                args.set("balance", balance.to_string());
+                args.set("withdraw-amount", withdraw_amount.to_string());
+            }
+            account::Error::MaxBalanceExceeded {
+                balance,
+                amount,
+                max_balance,
+            } => {
+                args.set("balance", balance.to_string());
+                args.set("amount", amount.to_string());
+                args.set("max-balance", max_balance.to_string());
+            }
+            account::Error::HoldAlreadyPlaced(id) | account::Error::UnknownHold(id) => {
+                args.set("id", id.to_string());
+            }
+            account::Error::InvalidCapture {
+                hold_amount,
+                amount,
+            } => {
+                args.set("hold-amount", hold_amount.to_string());
+                args.set("amount", amount.to_string());
+            }
+            account::Error::ActiveHolds(count) => {
+                args.set("count", count.to_string());
+            }
+            account::Error::NonZeroBalance(balance) => {
+                args.set("balance", balance.to_string());
+            }
+            account::Error::ReopenWindowExpired { closed_at }
+            | account::Error::RetentionNotExpired { closed_at } => {
+                args.set("closed-at", closed_at.to_string());
+            }
+            _ => {}
+        }
+        args
+    }
+}
+
+impl Localize for AccountConflict {
+    fn message_id(&self) -> &'static str {
+        match self {
+            AccountConflict::ExternalRefInUse(_) => "account-external-ref-in-use",
+            AccountConflict::TenantQuotaExceeded(_) => "account-tenant-quota-exceeded",
+            AccountConflict::CustomerQuotaExceeded(_) => "account-customer-quota-exceeded",
+            AccountConflict::CreatedDifferently(_) => "account-created-differently",
+        }
+    }
+
+    fn message_args(&self) -> FluentArgs<'static> {
+        let mut args = FluentArgs::new();
+        match self {
+            AccountConflict::ExternalRefInUse(external_ref) => {
+                args.set("external-ref", external_ref.to_string());
+            }
+            AccountConflict::TenantQuotaExceeded(max)
+            | AccountConflict::CustomerQuotaExceeded(max) => {
+                args.set("max", max.to_string());
+            }
+            AccountConflict::CreatedDifferently(id) => {
+                args.set("id", id.to_string());
+            }
+        }
+        args
+    }
+}
+
+impl Localize for books::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            books::Error::NotContiguous => "books-not-contiguous",
+            books::Error::Empty => "books-empty",
+            books::Error::NotInPast => "books-not-in-past",
+        }
+    }
+}
+
+impl Localize for consent::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            consent::Error::ExpiryNotInFuture(_) => "consent-expiry-not-in-future",
+            consent::Error::NotYetGranted => "consent-not-yet-granted",
+            consent::Error::AlreadyGranted => "consent-already-granted",
+            consent::Error::AlreadyRevoked => "consent-already-revoked",
+        }
+    }
+
+    fn message_args(&self) -> FluentArgs<'static> {
+        let mut args = FluentArgs::new();
+        if let consent::Error::ExpiryNotInFuture(expiry) = self {
+            args.set("expiry", expiry.to_string());
+        }
+        args
+    }
+}
+
+impl Localize for loan::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            loan::Error::NotYetCreated => "loan-not-yet-created",
+            loan::Error::AlreadyCreated => "loan-already-created",
+            loan::Error::ZeroPrincipal => "loan-zero-principal",
+            loan::Error::NoInstallments => "loan-no-installments",
+            loan::Error::AccountMismatch(_) => "loan-account-mismatch",
+            loan::Error::AlreadyDisbursed => "loan-already-disbursed",
+            loan::Error::NotYetDisbursed => "loan-not-yet-disbursed",
+            loan::Error::UnknownInstallment(_) => "loan-unknown-installment",
+            loan::Error::InvalidInstallmentStatus(_) => "loan-invalid-installment-status",
+        }
+    }
+
+    fn message_args(&self) -> FluentArgs<'static> {
+        let mut args = FluentArgs::new();
+        match self {
+            loan::Error::AccountMismatch(account_id) => {
+                args.set("account-id", account_id.to_string());
+            }
+            loan::Error::UnknownInstallment(number)
+            | loan::Error::InvalidInstallmentStatus(number) => {
+                args.set("number", number.to_string());
+            }
+            _ => {}
+        }
+        args
+    }
+}
+
+impl Localize for mandate::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            mandate::Error::NotYetCreated => "mandate-not-yet-created",
+            mandate::Error::AlreadyCreated => "mandate-already-created",
+            mandate::Error::Cancelled => "mandate-cancelled",
+            mandate::Error::MaxAmountExceeded { .. } => "mandate-max-amount-exceeded",
+            mandate::Error::CollectionAlreadyScheduled(_) => "mandate-collection-already-scheduled",
+            mandate::Error::UnknownCollection(_) => "mandate-unknown-collection",
+            mandate::Error::InvalidCollectionStatus(_) => "mandate-invalid-collection-status",
+            mandate::Error::RefundPeriodExpired(_) => "mandate-refund-period-expired",
+        }
+    }
+
+    fn message_args(&self) -> FluentArgs<'static> {
+        let mut args = FluentArgs::new();
+        match self {
+            mandate::Error::MaxAmountExceeded { max_amount, amount } => {
+                args.set("max-amount", max_amount.to_string());
+                args.set("amount", amount.to_string());
+            }
+            mandate::Error::CollectionAlreadyScheduled(id)
+            | mandate::Error::UnknownCollection(id)
+            | mandate::Error::InvalidCollectionStatus(id)
+            | mandate::Error::RefundPeriodExpired(id) => {
+                args.set("id", id.to_string());
+            }
+            _ => {}
+        }
+        args
+    }
+}
+
+impl Localize for term_deposit::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            term_deposit::Error::NotYetOpened => "term-deposit-not-yet-opened",
+            term_deposit::Error::AlreadyOpened => "term-deposit-already-opened",
+            term_deposit::Error::ZeroAmount => "term-deposit-zero-amount",
+            term_deposit::Error::ZeroTerm => "term-deposit-zero-term",
+            term_deposit::Error::NotYetMatured => "term-deposit-not-yet-matured",
+            term_deposit::Error::MaturityReached => "term-deposit-maturity-reached",
+            term_deposit::Error::Closed => "term-deposit-closed",
+        }
+    }
+}
+
+impl Localize for dispute::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            dispute::Error::NotYetOpened => "dispute-not-yet-opened",
+            dispute::Error::AlreadyOpened => "dispute-already-opened",
+            dispute::Error::ZeroAmount => "dispute-zero-amount",
+            dispute::Error::ZeroDeadline => "dispute-zero-deadline",
+            dispute::Error::AlreadyCredited => "dispute-already-credited",
+            dispute::Error::DeadlineNotReached => "dispute-deadline-not-reached",
+            dispute::Error::Resolved => "dispute-resolved",
+        }
+    }
+}
+
+impl Localize for transfer::Error {
+    fn message_id(&self) -> &'static str {
+        match self {
+            transfer::Error::NotYetInitiated => "transfer-not-yet-initiated",
+            transfer::Error::AlreadyInitiated => "transfer-already-initiated",
+            transfer::Error::ZeroAmount => "transfer-zero-amount",
+            transfer::Error::SameAccount => "transfer-same-account",
+            transfer::Error::InvalidStatus => "transfer-invalid-status",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("de-DE,de;q=0.9,en;q=0.8"), Some(langid!("de")));
+        assert_eq!(negotiate("fr;q=0.9,en;q=0.5,de;q=0.7"), Some(langid!("de")));
+        assert_eq!(negotiate("fr"), None);
+        assert_eq!(negotiate("*"), None);
+    }
+
+    #[tokio::test]
+    async fn test_localize() {
+        let error = account::Error::InvalidWithdraw {
+            balance: 100.into(),
+            withdraw_amount: 142.into(),
+        };
+
+        let message = LANGUAGE
+            .scope(langid!("de"), async { localize(&error) })
+            .await;
+        assert_eq!(
+            message,
+            "Kontostand 1.00€ reicht nicht aus, um 1.42€ abzuheben"
+        );
+
+        let message = LANGUAGE
+            .scope(langid!("en"), async { localize(&error) })
+            .await;
+        assert_eq!(
+            message,
+            "Balance 1.00€ insufficient to withdraw amount 1.42€"
+        );
+
+        // Outside of a request the default language is used.
+        assert_eq!(
+            localize(&account::Error::NotYetCreated),
+            "This account has not been created yet"
+        );
+
+        let conflict =
+            AccountConflict::ExternalRefInUse("legacy-42".to_string().try_into().unwrap());
+        let message = LANGUAGE
+            .scope(langid!("de"), async { localize(&conflict) })
+            .await;
+        assert_eq!(
+            message,
+            "Die externe Referenz legacy-42 wird bereits verwendet"
+        );
+    }
+}
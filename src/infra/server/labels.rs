@@ -0,0 +1,132 @@
+use super::{problem::Problem, Tenant};
+use crate::{
+    application::{
+        commands::{CommandError, Commands},
+        queries::PageRequest,
+    },
+    domain::account::{self, Label, Labels},
+    infra::{
+        account::{AccountFactory, AccountSummariesProjection, AccountSummary},
+        error_code::ErrorCode,
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the label endpoints, to be merged into the account routes.
+pub fn router<P, F, S>(account_summaries: P, commands: Commands<F>) -> Router<S>
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts", get(list_accounts))
+        .route("/accounts/:id/labels", put(set_labels))
+        .with_state(LabelsState {
+            account_summaries,
+            commands,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct LabelsState<P, F> {
+    account_summaries: P,
+    commands: Commands<F>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelQuery {
+    /// Like `team=payroll`.
+    label: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AccountItem {
+    id: Uuid,
+    #[serde(flatten)]
+    summary: AccountSummary,
+}
+
+/// Listing accounts requires a label, i.e. accounts can only be listed by label.
+async fn list_accounts<P, F>(
+    State(labels_state): State<LabelsState<P, F>>,
+    Tenant(tenant): Tenant,
+    Query(LabelQuery { label }): Query<LabelQuery>,
+    Query(page): Query<PageRequest>,
+) -> Response
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    debug!(?label, ?page, "Endpoint GET /accounts invoked");
+
+    let label = match label.map(|label| label.parse::<Label>()) {
+        Some(Ok(label)) => label,
+        Some(Err(error)) => {
+            return Problem::new(ErrorCode::InvalidRequest)
+                .with_detail(error.to_string())
+                .into_response()
+        }
+        None => {
+            return Problem::new(ErrorCode::InvalidRequest)
+                .with_detail("Missing label, e.g. label=team%3Dpayroll")
+                .into_response()
+        }
+    };
+
+    let accounts = labels_state
+        .account_summaries
+        .labeled(tenant, label)
+        .await
+        .into_iter()
+        .map(|(id, summary)| AccountItem { id, summary })
+        .collect();
+    match page.apply(accounts) {
+        Ok(page) => Json(page).into_response(),
+        Err(error) => Problem::new(ErrorCode::from(&error))
+            .with_detail(error.to_string())
+            .into_response(),
+    }
+}
+
+/// Replace all labels of the account.
+async fn set_labels<P, F>(
+    State(labels_state): State<LabelsState<P, F>>,
+    Path(id): Path<Uuid>,
+    Json(labels): Json<Labels>,
+) -> Response
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    debug!(%id, "Endpoint PUT /accounts/:id/labels invoked");
+
+    match labels_state
+        .commands
+        .account(id, account::Cmd::SetLabels(labels))
+        .await
+    {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+
+        Err(CommandError::Rejected(account::Error::NotYetCreated)) => {
+            Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Err(CommandError::Rejected(error)) => Problem::from(&error).into_response(),
+
+        Err(CommandError::Failed(error)) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot set labels");
+            Problem::new(code).into_response()
+        }
+    }
+}
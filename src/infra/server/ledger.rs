@@ -0,0 +1,48 @@
+use super::problem::Problem;
+use crate::{
+    application::queries::{EntryFilter, PageRequest, Queries},
+    infra::{
+        error_code::ErrorCode, ledger::LedgerProjection, transactions::TransactionsProjection,
+        treasury::PositionsProjection,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use tracing::debug;
+
+/// Router for the ledger endpoints, to be merged into the account routes.
+pub fn router<Q, G, R, S>(queries: Queries<Q, G, R>) -> Router<S>
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/ledger/entries", get(list_ledger_entries))
+        .with_state(queries)
+}
+
+async fn list_ledger_entries<Q, G, R>(
+    State(queries): State<Queries<Q, G, R>>,
+    Query(filter): Query<EntryFilter>,
+    Query(page): Query<PageRequest>,
+) -> Response
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+{
+    debug!(?filter, ?page, "Endpoint GET /admin/ledger/entries invoked");
+
+    match queries.ledger_entries(filter, page).await {
+        Ok(page) => Json(page).into_response(),
+        Err(error) => Problem::new(ErrorCode::from(&error))
+            .with_detail(error.to_string())
+            .into_response(),
+    }
+}
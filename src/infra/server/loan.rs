@@ -0,0 +1,158 @@
+use super::problem::Problem;
+use crate::{
+    domain::{euro_cent::EuroCent, id::IdGenerator, loan},
+    infra::{error_code::ErrorCode, loan::LoanFactory},
+};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router, TypedHeader,
+};
+use serde::{Deserialize, Serialize};
+use std::{iter, sync::Arc};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the loan endpoints, to be merged into the account routes.
+pub fn router<F, S>(loan_factory: F, ids: Arc<dyn IdGenerator>) -> Router<S>
+where
+    F: LoanFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/loans", post(create_loan))
+        .route(
+            "/accounts/:id/loans/:loan_id/disbursement",
+            post(disburse_loan),
+        )
+        .with_state(LoanState { loan_factory, ids })
+}
+
+#[derive(Debug, Clone)]
+struct LoanState<F> {
+    loan_factory: F,
+    ids: Arc<dyn IdGenerator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CreateLoan {
+    principal: EuroCent,
+    rate_bps: u32,
+    installments: u16,
+}
+
+/// Representation of a loan.
+#[derive(Debug, Clone, Serialize)]
+struct LoanRepr {
+    id: Uuid,
+    account_id: Uuid,
+    principal: EuroCent,
+    rate_bps: u32,
+    installments: u16,
+}
+
+async fn create_loan<F>(
+    State(LoanState { loan_factory, ids }): State<LoanState<F>>,
+    Path(account_id): Path<Uuid>,
+    Json(CreateLoan {
+        principal,
+        rate_bps,
+        installments,
+    }): Json<CreateLoan>,
+) -> Response
+where
+    F: LoanFactory,
+{
+    debug!(%account_id, "Endpoint POST /accounts/:id/loans invoked");
+
+    let id = ids.next_id();
+    let cmd = loan::Cmd::Create {
+        id,
+        account_id,
+        principal,
+        rate_bps,
+        installments,
+    };
+    let result = async {
+        loan_factory
+            .get(id)
+            .await
+            .context("Cannot get Loan entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Create command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            let location_value =
+                HeaderValue::from_str(&format!("/accounts/{account_id}/loans/{id}")).unwrap();
+            let mut location_value = iter::once(&location_value);
+            let location = Location::decode(&mut location_value).unwrap();
+            (
+                StatusCode::CREATED,
+                TypedHeader(location),
+                Json(LoanRepr {
+                    id,
+                    account_id,
+                    principal,
+                    rate_bps,
+                    installments,
+                }),
+            )
+                .into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot create loan");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn disburse_loan<F>(
+    State(LoanState { loan_factory, .. }): State<LoanState<F>>,
+    Path((account_id, id)): Path<(Uuid, Uuid)>,
+) -> Response
+where
+    F: LoanFactory,
+{
+    debug!(%account_id, %id, "Endpoint POST /accounts/:id/loans/:loan_id/disbursement invoked");
+
+    let result = async {
+        loan_factory
+            .get(id)
+            .await
+            .context("Cannot get Loan entity")?
+            .handle_cmd(loan::Cmd::Disburse(account_id))
+            .await
+            .context("Cannot handle Disburse command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) | Ok(Err(loan::Error::AlreadyDisbursed)) => {
+            StatusCode::NO_CONTENT.into_response()
+        }
+
+        Ok(Err(loan::Error::NotYetCreated | loan::Error::AccountMismatch(_))) => {
+            Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot disburse loan");
+            Problem::new(code).into_response()
+        }
+    }
+}
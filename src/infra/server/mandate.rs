@@ -0,0 +1,295 @@
+use super::problem::Problem;
+use crate::{
+    domain::{euro_cent::EuroCent, id::IdGenerator, mandate},
+    infra::{error_code::ErrorCode, mandate::MandateFactory},
+};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{post, put},
+    Json, Router, TypedHeader,
+};
+use serde::{Deserialize, Serialize};
+use std::{iter, sync::Arc};
+use time::OffsetDateTime;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the direct debit mandate endpoints, to be merged into the account routes.
+pub fn router<M, S>(mandate_factory: M, ids: Arc<dyn IdGenerator>) -> Router<S>
+where
+    M: MandateFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/mandates", post(create_mandate))
+        .route("/mandates/:id", put(amend_mandate).delete(cancel_mandate))
+        .route("/mandates/:id/collections", post(schedule_collection))
+        .route(
+            "/mandates/:id/collections/:collection_id/refund",
+            post(refund_collection),
+        )
+        .with_state(MandateState {
+            mandate_factory,
+            ids,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct MandateState<M> {
+    mandate_factory: M,
+    ids: Arc<dyn IdGenerator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MandateTerms {
+    creditor: String,
+    max_amount: Option<EuroCent>,
+}
+
+/// Representation of a mandate.
+#[derive(Debug, Clone, Serialize)]
+struct MandateRepr {
+    id: Uuid,
+    account_id: Uuid,
+    creditor: String,
+    max_amount: Option<EuroCent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ScheduleCollection {
+    amount: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    due_at: OffsetDateTime,
+}
+
+/// Representation of a collection.
+#[derive(Debug, Clone, Serialize)]
+struct CollectionRepr {
+    id: Uuid,
+    mandate_id: Uuid,
+    amount: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    due_at: OffsetDateTime,
+}
+
+async fn create_mandate<M>(
+    State(MandateState {
+        mandate_factory,
+        ids,
+    }): State<MandateState<M>>,
+    Path(account_id): Path<Uuid>,
+    Json(MandateTerms {
+        creditor,
+        max_amount,
+    }): Json<MandateTerms>,
+) -> Response
+where
+    M: MandateFactory,
+{
+    debug!(%account_id, "Endpoint POST /accounts/:id/mandates invoked");
+
+    let id = ids.next_id();
+    let cmd = mandate::Cmd::Create {
+        id,
+        account_id,
+        creditor: creditor.clone(),
+        max_amount,
+    };
+    let result = handle_cmd(&mandate_factory, id, cmd).await;
+
+    match result {
+        Ok(Ok(())) => (
+            StatusCode::CREATED,
+            TypedHeader(location(&format!("/mandates/{id}"))),
+            Json(MandateRepr {
+                id,
+                account_id,
+                creditor,
+                max_amount,
+            }),
+        )
+            .into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot create mandate");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn amend_mandate<M>(
+    State(MandateState {
+        mandate_factory, ..
+    }): State<MandateState<M>>,
+    Path(id): Path<Uuid>,
+    Json(MandateTerms {
+        creditor,
+        max_amount,
+    }): Json<MandateTerms>,
+) -> Response
+where
+    M: MandateFactory,
+{
+    debug!(%id, "Endpoint PUT /mandates/:id invoked");
+
+    let cmd = mandate::Cmd::Amend {
+        creditor,
+        max_amount,
+    };
+    let result = handle_cmd(&mandate_factory, id, cmd).await;
+
+    match result {
+        Ok(Ok(())) => StatusCode::NO_CONTENT.into_response(),
+
+        Ok(Err(mandate::Error::NotYetCreated)) => Problem::new(ErrorCode::NotFound).into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot amend mandate");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn cancel_mandate<M>(
+    State(MandateState {
+        mandate_factory, ..
+    }): State<MandateState<M>>,
+    Path(id): Path<Uuid>,
+) -> Response
+where
+    M: MandateFactory,
+{
+    debug!(%id, "Endpoint DELETE /mandates/:id invoked");
+
+    let result = handle_cmd(&mandate_factory, id, mandate::Cmd::Cancel).await;
+
+    match result {
+        Ok(Ok(())) | Ok(Err(mandate::Error::Cancelled)) => StatusCode::NO_CONTENT.into_response(),
+
+        Ok(Err(mandate::Error::NotYetCreated)) => Problem::new(ErrorCode::NotFound).into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot cancel mandate");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn schedule_collection<M>(
+    State(MandateState {
+        mandate_factory,
+        ids,
+    }): State<MandateState<M>>,
+    Path(mandate_id): Path<Uuid>,
+    Json(ScheduleCollection { amount, due_at }): Json<ScheduleCollection>,
+) -> Response
+where
+    M: MandateFactory,
+{
+    debug!(%mandate_id, "Endpoint POST /mandates/:id/collections invoked");
+
+    let id = ids.next_id();
+    let cmd = mandate::Cmd::ScheduleCollection {
+        collection_id: id,
+        amount,
+        due_at,
+    };
+    let result = handle_cmd(&mandate_factory, mandate_id, cmd).await;
+
+    match result {
+        Ok(Ok(())) => (
+            StatusCode::CREATED,
+            TypedHeader(location(&format!(
+                "/mandates/{mandate_id}/collections/{id}"
+            ))),
+            Json(CollectionRepr {
+                id,
+                mandate_id,
+                amount,
+                due_at,
+            }),
+        )
+            .into_response(),
+
+        Ok(Err(mandate::Error::NotYetCreated)) => Problem::new(ErrorCode::NotFound).into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %mandate_id, error = format!("{error:#}"), "Cannot schedule collection");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn refund_collection<M>(
+    State(MandateState {
+        mandate_factory, ..
+    }): State<MandateState<M>>,
+    Path((mandate_id, collection_id)): Path<(Uuid, Uuid)>,
+) -> Response
+where
+    M: MandateFactory,
+{
+    debug!(
+        %mandate_id,
+        %collection_id,
+        "Endpoint POST /mandates/:id/collections/:collection_id/refund invoked"
+    );
+
+    let cmd = mandate::Cmd::RefundCollection(collection_id);
+    let result = handle_cmd(&mandate_factory, mandate_id, cmd).await;
+
+    match result {
+        Ok(Ok(())) => StatusCode::NO_CONTENT.into_response(),
+
+        Ok(Err(mandate::Error::NotYetCreated | mandate::Error::UnknownCollection(_))) => {
+            Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %collection_id, error = format!("{error:#}"), "Cannot refund collection");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn handle_cmd<M>(
+    mandate_factory: &M,
+    id: Uuid,
+    cmd: mandate::Cmd,
+) -> anyhow::Result<Result<(), mandate::Error>>
+where
+    M: MandateFactory,
+{
+    mandate_factory
+        .get(id)
+        .await
+        .context("Cannot get Mandate entity")?
+        .handle_cmd(cmd)
+        .await
+        .context("Cannot handle command")
+}
+
+fn location(uri: &str) -> Location {
+    let location_value = HeaderValue::from_str(uri).unwrap();
+    let mut location_value = iter::once(&location_value);
+    Location::decode(&mut location_value).unwrap()
+}
@@ -0,0 +1,1881 @@
+mod access_log;
+mod account_cache;
+mod adjustment;
+mod admin;
+#[cfg(feature = "auth")]
+mod auth;
+mod authz;
+mod batch_get;
+mod books;
+mod card_authorization;
+mod consent;
+mod data_export;
+mod dispute;
+mod fields;
+mod forecast;
+mod history;
+mod i18n;
+mod labels;
+mod ledger;
+mod loan;
+mod mandate;
+pub mod mode;
+#[cfg(feature = "mtls")]
+pub mod mtls;
+#[cfg(feature = "oidc")]
+mod oidc;
+mod policy;
+mod portability;
+mod problem;
+mod rate_limit;
+mod receipt;
+mod reopening;
+mod reporting;
+mod retention;
+mod sandbox;
+mod settlement;
+pub mod signing;
+mod standby;
+mod step_up;
+mod streams;
+mod term_deposit;
+mod transactions;
+mod transfer;
+mod treasury;
+
+#[cfg(feature = "auth")]
+use super::auth::Auth;
+#[cfg(feature = "oidc")]
+use super::oidc::Oidc;
+use super::{
+    account::{
+        data_export::DataExporter, history::AccountHistory, portability::AccountPorter,
+        AccountFactory, AccountRef, AccountStatus, AccountSummariesProjection,
+    },
+    books::{BookKeeper, ClosedPeriods},
+    cluster::Cluster,
+    consent::ConsentFactory,
+    dead_letter::DeadLetterQueue,
+    dispute::DisputeFactory,
+    error_code::ErrorCode,
+    gl_export::GlExporter,
+    leader::Leadership,
+    ledger::LedgerProjection,
+    loan::LoanFactory,
+    mandate::MandateFactory,
+    projection::Projections,
+    quote::Quotes,
+    receipt::Receipts,
+    reporting::Reporter,
+    retention::Retention,
+    settlement::Settlement,
+    standby::Standby,
+    step_up::StepUp,
+    term_deposit::TermDepositFactory,
+    transactions::TransactionsProjection,
+    transfer::TransferFactory,
+    treasury::PositionsProjection,
+};
+use crate::{
+    application::{analytics::Analytics, commands::Commands, queries::Queries},
+    domain::{
+        account::{self, ExternalRef, NotificationPrefs, Product, Snapshot},
+        channel::{self, Geolocation},
+        clock::Clock,
+        currency::{self, Currency},
+        customer::CustomerId,
+        euro_cent::EuroCent,
+        iban::{BankCode, Iban},
+        id::IdGenerator,
+        redaction,
+        state_machine::Format,
+        tenant::TenantId,
+    },
+};
+use access_log::AccessLog;
+use account_cache::{AccountCache, CachedAccount};
+use anyhow::{Context, Result};
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequestParts, Path, Query, State},
+    headers::{Header, Location},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, USER_AGENT},
+        request::Parts,
+        HeaderMap, HeaderValue, Request, StatusCode,
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router, Server, TypedHeader,
+};
+use eventsourced::EvtLog;
+use futures::{future, stream, FutureExt};
+#[cfg(feature = "mtls")]
+use hyper::server::conn::Http;
+use hyper::server::{accept, conn::AddrIncoming, Builder};
+use metrics_exporter_prometheus::PrometheusHandle;
+use mode::Mode;
+use problem::Problem;
+use rate_limit::RateLimiter;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    fmt::{self, Debug, Formatter},
+    fs,
+    future::{ready, Future},
+    io, iter,
+    net::{IpAddr, SocketAddr},
+    num::{NonZeroU64, NonZeroUsize},
+    path::PathBuf,
+    sync::Arc,
+};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tokio::{net::UnixListener, select, sync::watch, task, time::sleep};
+use tower::ServiceBuilder;
+use tower_http::trace::TraceLayer;
+use tracing::{debug, error, info_span, warn};
+use uuid::Uuid;
+
+/// Request header for the tenant ID.
+const TENANT_ID: &str = "tenant-id";
+
+/// Request header for the ID of the device of the customer.
+const DEVICE_ID: &str = "device-id";
+
+/// Request header for the geolocation of the customer, i.e. latitude and longitude in degrees,
+/// separated by a comma.
+const GEOLOCATION: &str = "geolocation";
+
+/// Request header for the client IP and those of the proxies, set by the gateway.
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Response header for the sequence number of an account after handling a command.
+const ACCOUNT_SEQ_NO: &str = "account-seq-no";
+
+/// Response header for the balance of an account in cent after handling a command.
+const ACCOUNT_BALANCE: &str = "account-balance";
+
+/// Request header for preferences, e.g. [VALIDATION_ONLY].
+const PREFER: &str = "prefer";
+
+/// Preference for dry runs.
+const VALIDATION_ONLY: &str = "validation-only";
+
+/// Maximum number of attempts to derive an IBAN which is not yet taken.
+const MAX_IBAN_ATTEMPTS: u32 = 10;
+
+/// Server configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// The API is served on all of these simultaneously.
+    listeners: Vec<Listener>,
+
+    /// The deployment [Mode], [Mode::Full] if not given.
+    #[serde(default)]
+    mode: Mode,
+
+    /// Bank code for the IBANs of new accounts.
+    bank_code: BankCode,
+
+    /// If given, withdrawals exceeding the projected balance by more than this margin are rejected
+    /// without getting the account entity. The margin accounts for projection lag.
+    withdraw_fast_fail_margin: Option<EuroCent>,
+
+    /// If given, the maximum number of accounts per tenant.
+    max_accounts_per_tenant: Option<usize>,
+
+    /// If given, the maximum number of accounts per customer.
+    max_accounts_per_customer: Option<usize>,
+
+    /// Default and supported currencies of new accounts, only EUR if not given.
+    #[serde(default)]
+    currencies: currency::Config,
+
+    /// Latency budget for card authorizations, which are declined if exceeded.
+    card_authorization_budget_ms: u64,
+
+    /// Maximum number of days the value date of a deposit or withdrawal may lie in the past.
+    max_back_dating_days: u16,
+
+    /// Maximum number of days the value date of a deposit or withdrawal may lie in the future.
+    max_forward_dating_days: u16,
+
+    /// Number of days after closing within which an account can be reopened by an admin.
+    reopen_window_days: u16,
+
+    /// Number of days within which disputes must be resolved, else they are upheld.
+    dispute_deadline_days: u16,
+
+    /// Maximum number of accounts read with a single batch get.
+    batch_get_max_ids: NonZeroUsize,
+
+    /// Whether to keep HTTP/1 connections alive.
+    http1_keep_alive: bool,
+
+    /// If given, HTTP/2 connections are pinged at this interval and closed if a ping is not
+    /// acknowledged within the keep-alive timeout, e.g. to detect connections dropped by a load
+    /// balancer.
+    http2_keep_alive_interval_secs: Option<NonZeroU64>,
+
+    /// Timeout for acknowledging HTTP/2 keep-alive pings, 20 seconds if not given.
+    http2_keep_alive_timeout_secs: Option<NonZeroU64>,
+
+    /// If given, the maximum number of concurrent HTTP/2 streams per connection.
+    http2_max_concurrent_streams: Option<u32>,
+
+    /// If given, TCP keep-alive probes are sent after a connection has been idle this long.
+    tcp_keepalive_secs: Option<NonZeroU64>,
+
+    /// If given, open connections are closed this long after the shutdown signal, else graceful
+    /// shutdown waits for all of them to complete.
+    shutdown_timeout_secs: Option<NonZeroU64>,
+
+    /// If given, each request is recorded in an [AccessLog].
+    access_log: Option<access_log::Config>,
+
+    /// If given, requests are limited per caller by a [RateLimiter].
+    rate_limit: Option<rate_limit::Config>,
+
+    /// If given, account representations are cached in an [AccountCache].
+    account_cache: Option<account_cache::Config>,
+}
+
+impl Config {
+    /// Bank code for the IBANs of new accounts.
+    pub fn bank_code(&self) -> BankCode {
+        self.bank_code
+    }
+
+    /// The deployment [Mode].
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Apply the connection settings to the given server [Builder].
+    fn configure<I, E>(&self, builder: Builder<I, E>) -> Builder<I, E> {
+        let builder = builder
+            .http1_keepalive(self.http1_keep_alive)
+            .http2_keep_alive_interval(self.http2_keep_alive_interval_secs.map(secs))
+            .http2_max_concurrent_streams(self.http2_max_concurrent_streams);
+        match self.http2_keep_alive_timeout_secs {
+            Some(timeout) => builder.http2_keep_alive_timeout(secs(timeout)),
+            None => builder,
+        }
+    }
+
+    /// Connection settings for serving individual connections, e.g. via TLS. HTTP/2 is detected
+    /// via its connection preface, i.e. also supported without TLS.
+    #[cfg(feature = "mtls")]
+    fn http(&self) -> Http {
+        let mut http = Http::new();
+        http.http1_keep_alive(self.http1_keep_alive)
+            .http2_keep_alive_interval(self.http2_keep_alive_interval_secs.map(secs))
+            .http2_max_concurrent_streams(self.http2_max_concurrent_streams);
+        if let Some(timeout) = self.http2_keep_alive_timeout_secs {
+            http.http2_keep_alive_timeout(secs(timeout));
+        }
+        http
+    }
+}
+
+/// The components the server is composed of, i.e. the entity factories, projections and services
+/// backing the routes as well as the authentication modes.
+pub struct Components<P, F, C, M, N, T, U, V, Q, G, R, E, X, H, O, D, L> {
+    pub account_summaries_projection: P,
+    pub account_factory: F,
+    pub consent_factory: C,
+    pub mandate_factory: M,
+    pub loan_factory: N,
+    pub term_deposit_factory: T,
+    pub dispute_factory: U,
+    pub transfer_factory: V,
+    pub queries: Queries<Q, G, R>,
+    pub analytics: Analytics<G>,
+    pub reporter: Reporter<G>,
+    pub retention: Option<Retention<P>>,
+    pub settlement: Option<Settlement<G>>,
+    pub standby: Option<Standby>,
+    pub book_keeper: BookKeeper<G, E>,
+    pub quotes: Quotes,
+    pub step_up: Option<StepUp>,
+    pub receipts: Receipts<G>,
+    pub data_exporter: X,
+    pub account_history: H,
+    pub account_porter: O,
+    pub dead_letter_queue: D,
+    pub evt_log: L,
+    pub projections: Projections,
+    pub cluster: Option<Cluster>,
+    pub ids: Arc<dyn IdGenerator>,
+    pub clock: Arc<dyn Clock>,
+    pub leadership: Leadership,
+    #[cfg(feature = "auth")]
+    pub auth: Auth,
+    #[cfg(feature = "oidc")]
+    pub oidc: Option<Oidc>,
+    #[cfg(feature = "mtls")]
+    pub mtls: Option<mtls::Config>,
+    pub signing: Option<signing::Config>,
+    pub metrics_handle: PrometheusHandle,
+}
+
+/// Run the server with the given [Config] and [Components].
+pub async fn run<P, F, C, M, N, T, U, V, Q, G, R, E, X, H, O, D, L, S>(
+    config: Config,
+    components: Components<P, F, C, M, N, T, U, V, Q, G, R, E, X, H, O, D, L>,
+    shutdown_signal: S,
+) -> Result<()>
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+    C: ConsentFactory,
+    M: MandateFactory,
+    N: LoanFactory,
+    T: TermDepositFactory,
+    U: DisputeFactory,
+    V: TransferFactory,
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    E: GlExporter,
+    X: DataExporter,
+    H: AccountHistory,
+    O: AccountPorter,
+    D: DeadLetterQueue,
+    L: EvtLog,
+    S: Future<Output = ()> + Send + 'static,
+{
+    let Components {
+        account_summaries_projection,
+        account_factory,
+        consent_factory,
+        mandate_factory,
+        loan_factory,
+        term_deposit_factory,
+        dispute_factory,
+        transfer_factory,
+        queries,
+        analytics,
+        reporter,
+        retention,
+        settlement,
+        standby,
+        book_keeper,
+        quotes,
+        step_up,
+        receipts,
+        data_exporter,
+        account_history,
+        account_porter,
+        dead_letter_queue,
+        evt_log,
+        projections,
+        cluster,
+        ids,
+        clock,
+        leadership,
+        #[cfg(feature = "auth")]
+        auth,
+        #[cfg(feature = "oidc")]
+        oidc,
+        #[cfg(feature = "mtls")]
+        mtls,
+        signing,
+        metrics_handle,
+    } = components;
+
+    let access_log = config
+        .access_log
+        .clone()
+        .map(AccessLog::spawn)
+        .transpose()
+        .context("Cannot create access log")?;
+    let logging = access_log::Logging::new(access_log, ids.clone(), clock.clone());
+
+    // Cached accounts are only dropped on updates from the projection, hence caching requires
+    // these.
+    let account_cache = match (config.account_cache, account_summaries_projection.updates()) {
+        (Some(config), Some(updates)) => {
+            let account_cache = AccountCache::new(config);
+            account_cache.spawn_invalidation(updates);
+            Some(account_cache)
+        }
+
+        (Some(_), None) => {
+            warn!("Not caching accounts, because the projection does not publish updates");
+            None
+        }
+
+        (None, _) => None,
+    };
+
+    let app_state = AppState {
+        config: config.clone(),
+        account_summaries_projection,
+        account_factory,
+        account_cache,
+        closed_periods: book_keeper.closed_periods(),
+        quotes,
+        step_up: step_up.clone(),
+        cluster: cluster.clone(),
+        ids,
+        clock,
+    };
+
+    let api = Router::new()
+        .route("/accounts", post(create_account))
+        .route("/accounts/:id", get(get_account).put(put_account))
+        .route("/accounts/:id/deposits", post(deposit_to_account))
+        .route("/accounts/:id/withdrawals", post(withdraw_from_account))
+        .route("/accounts/:id/withdrawals/quote", post(quote_withdrawal))
+        .route(
+            "/accounts/:id/notification-prefs",
+            put(set_notification_prefs),
+        )
+        .route("/accounts/:id/closure", post(close_account))
+        .route(
+            "/ingest/card-authorizations",
+            post(card_authorization::ingest_card_message),
+        )
+        .route("/admin/hot-accounts", get(list_hot_accounts))
+        .merge(batch_get::router(
+            app_state.account_summaries_projection.clone(),
+            config.batch_get_max_ids,
+        ))
+        .merge(data_export::router(data_exporter))
+        .merge(history::router(account_history))
+        .merge(portability::router(account_porter))
+        .merge(forecast::router(
+            app_state.account_summaries_projection.clone(),
+            analytics,
+        ))
+        .merge(consent::router(consent_factory, app_state.ids.clone()))
+        .merge(mandate::router(mandate_factory, app_state.ids.clone()))
+        .merge(loan::router(loan_factory, app_state.ids.clone()))
+        .merge(term_deposit::router(
+            app_state.account_factory.clone(),
+            term_deposit_factory,
+            app_state.ids.clone(),
+        ))
+        .merge(transfer::router(transfer_factory, app_state.ids.clone()))
+        .merge(dispute::router(
+            queries.clone(),
+            dispute_factory,
+            config.dispute_deadline_days,
+            app_state.ids.clone(),
+        ))
+        .merge(treasury::router(queries.clone()))
+        .merge(ledger::router(queries.clone()))
+        .merge(transactions::router(queries))
+        .merge(reporting::router(reporter))
+        .merge(books::router(book_keeper))
+        .merge(receipt::router(receipts))
+        .merge(streams::router(evt_log))
+        .merge(adjustment::router(
+            Commands::new(app_state.account_factory.clone()),
+            app_state.ids.clone(),
+        ))
+        .merge(labels::router(
+            app_state.account_summaries_projection.clone(),
+            Commands::new(app_state.account_factory.clone()),
+        ))
+        .merge(reopening::router(
+            Commands::new(app_state.account_factory.clone()),
+            config.reopen_window_days,
+        ))
+        .merge(sandbox::router(
+            app_state.account_summaries_projection.clone(),
+            app_state.account_factory.clone(),
+            app_state.ids.clone(),
+        ))
+        .nest("/admin", admin::router(dead_letter_queue, projections));
+    let api = match retention {
+        Some(retention) => api.merge(retention::router(retention)),
+        None => api,
+    };
+    let api = match settlement {
+        Some(settlement) => api.merge(settlement::router(settlement)),
+        None => api,
+    };
+    let api = match step_up {
+        Some(step_up) => api.merge(step_up::router(step_up, app_state.account_factory.clone())),
+        None => api,
+    };
+    let api = match standby {
+        Some(standby) => api
+            .route_layer(middleware::from_fn_with_state(
+                standby.clone(),
+                standby::reject_mutations,
+            ))
+            .merge(standby::router(standby)),
+        None => api,
+    };
+    let api = match config.mode {
+        Mode::ReadOnly => api.route_layer(middleware::from_fn(mode::reject_mutations)),
+        Mode::CommandOnly => api.route_layer(middleware::from_fn(mode::reject_queries)),
+        Mode::Full | Mode::Standby => api,
+    };
+    // Authentication modes following access tokens issued by auth, i.e. to which requests without
+    // such are passed on.
+    let external_authn = [
+        #[cfg(feature = "oidc")]
+        oidc.is_some(),
+        #[cfg(feature = "mtls")]
+        mtls.is_some(),
+    ]
+    .contains(&true);
+    // Authentication modes following signing, i.e. to which unsigned requests are passed on.
+    let other_authn = cfg!(feature = "auth") || external_authn;
+
+    // The quota is subject to the policy and authorization like any other route.
+    let rate_limiter = config.rate_limit.map(RateLimiter::new);
+    let api = match &rate_limiter {
+        Some(rate_limiter) => api.merge(rate_limit::router(rate_limiter.clone())),
+        None => api,
+    };
+    let api = api.route_layer(middleware::from_fn(fields::select));
+    let api = api.route_layer(middleware::from_fn(policy::enforce));
+    // Without any authentication there is nothing to authorize; else unauthenticated requests are
+    // denied.
+    let api = if signing.is_some() || other_authn {
+        api.route_layer(middleware::from_fn(authz::authorize))
+    } else {
+        api
+    };
+    let api = match rate_limiter {
+        Some(rate_limiter) => api.route_layer(middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit::limit,
+        )),
+        None => api,
+    };
+    #[cfg(feature = "oidc")]
+    let api = match oidc {
+        Some(oidc) => api.route_layer(middleware::from_fn_with_state(oidc, oidc::authenticate)),
+        None => api,
+    };
+    #[cfg(feature = "auth")]
+    let api = api.route_layer(middleware::from_fn_with_state(
+        auth::Authentication::new(auth.clone(), external_authn),
+        auth::authenticate,
+    ));
+    let api = match signing {
+        Some(config) => api.route_layer(middleware::from_fn_with_state(
+            signing::Signatures::new(config, app_state.clock.clone(), other_authn),
+            signing::verify,
+        )),
+        None => api,
+    };
+
+    let app = Router::new();
+    #[cfg(feature = "auth")]
+    let app = app.nest("/auth", auth::router(auth));
+    let app = app
+        .route("/", get(root))
+        .route(
+            "/health",
+            get(move || {
+                ready(Json(Health {
+                    leader: leadership.is_leader(),
+                }))
+            }),
+        )
+        .route("/metrics", get(move || ready(metrics_handle.render())))
+        .route(
+            "/docs/account-state-machine",
+            get(get_account_state_machine),
+        )
+        .merge(api)
+        .with_state(app_state)
+        .layer(
+            ServiceBuilder::new()
+                .layer(middleware::from_fn_with_state(logging, access_log::log))
+                .layer(
+                    TraceLayer::new_for_http().make_span_with(|request: &Request<Body>| {
+                        let headers = RedactedHeaders(request.headers());
+                        info_span!("request", ?headers)
+                    }),
+                )
+                .layer(middleware::from_fn_with_state(cluster, forward_to_owner))
+                .layer(middleware::from_fn(i18n::negotiate_language)),
+        );
+
+    // The shutdown signal is shared by all listeners.
+    let (shutdown_sdr, shutdown_rcv) = watch::channel(());
+    task::spawn(async move {
+        shutdown_signal.await;
+        let _ = shutdown_sdr.send(());
+    });
+    let graceful_shutdown = |mut shutdown_rcv: watch::Receiver<()>| async move {
+        let _ = shutdown_rcv.changed().await;
+    };
+
+    let mut servers = Vec::with_capacity(config.listeners.len());
+    for listener in &config.listeners {
+        #[cfg(feature = "mtls")]
+        if let Some(mtls) = mtls.clone() {
+            let Listener::Tcp { addr, port } = listener else {
+                anyhow::bail!("Unix domain sockets are not supported with mTLS");
+            };
+            servers.push(
+                mtls::serve(
+                    SocketAddr::new(*addr, *port),
+                    config.http(),
+                    mtls,
+                    app.clone(),
+                    graceful_shutdown(shutdown_rcv.clone()),
+                )
+                .boxed(),
+            );
+            continue;
+        }
+
+        let server = match listener {
+            Listener::Tcp { addr, port } => {
+                let addr = SocketAddr::new(*addr, *port);
+                let mut incoming =
+                    AddrIncoming::bind(&addr).with_context(|| format!("Cannot bind to {addr}"))?;
+                incoming.set_keepalive(config.tcp_keepalive_secs.map(secs));
+                config
+                    .configure(Server::builder(incoming))
+                    .serve(app.clone().into_make_service())
+                    .with_graceful_shutdown(graceful_shutdown(shutdown_rcv.clone()))
+                    .boxed()
+            }
+
+            Listener::Unix { path } => {
+                // Remove a stale socket file left behind by a former run.
+                match fs::remove_file(path) {
+                    Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                        return Err(error).with_context(|| {
+                            format!("Cannot remove socket file {}", path.display())
+                        })
+                    }
+                    _ => {}
+                }
+                let listener = UnixListener::bind(path)
+                    .with_context(|| format!("Cannot bind to {}", path.display()))?;
+                let incoming =
+                    accept::from_stream(stream::unfold(listener, |listener| async move {
+                        let stream = listener.accept().await.map(|(stream, _)| stream);
+                        Some((stream, listener))
+                    }));
+                config
+                    .configure(Server::builder(incoming))
+                    .serve(app.clone().into_make_service())
+                    .with_graceful_shutdown(graceful_shutdown(shutdown_rcv.clone()))
+                    .boxed()
+            }
+        };
+        servers.push(
+            server
+                .map(|server_result| server_result.context("Server completed with error"))
+                .boxed(),
+        );
+    }
+
+    let servers = future::try_join_all(servers.into_iter().map(|server| async {
+        task::spawn(server)
+            .await
+            .context("Server panicked")
+            .and_then(|r| r)
+    }));
+
+    // Graceful shutdown, bounded by the shutdown timeout, if given.
+    select! {
+        servers_result = servers => servers_result.map(|_| ()),
+        _ = shutdown_deadline(shutdown_rcv, config.shutdown_timeout_secs) => {
+            warn!("Graceful shutdown timed out, closing open connections");
+            Ok(())
+        }
+    }
+}
+
+/// Completes once the shutdown timeout has elapsed after the shutdown signal, never if no timeout
+/// is given.
+async fn shutdown_deadline(mut shutdown_rcv: watch::Receiver<()>, timeout: Option<NonZeroU64>) {
+    match (shutdown_rcv.changed().await, timeout) {
+        (Ok(()), Some(timeout)) => sleep(secs(timeout)).await,
+        _ => future::pending().await,
+    }
+}
+
+fn secs(secs: NonZeroU64) -> std::time::Duration {
+    std::time::Duration::from_secs(secs.get())
+}
+
+/// Request headers for the request span, with the values of all headers not allowed by the
+/// redaction configuration masked, e.g. Authorization.
+struct RedactedHeaders<'a>(&'a HeaderMap);
+
+impl Debug for RedactedHeaders<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let headers = self.0.iter().map(|(name, value)| {
+            let value = if redaction::is_allowed_header(name.as_str()) {
+                value.to_str().unwrap_or("<non-visible>")
+            } else {
+                "***"
+            };
+            (name.as_str(), value)
+        });
+        f.debug_map().entries(headers).finish()
+    }
+}
+
+/// Listener for the API, either a TCP address and port or a Unix domain socket, e.g. for sidecar
+/// proxies.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Listener {
+    Tcp { addr: IpAddr, port: u16 },
+    Unix { path: PathBuf },
+}
+
+#[derive(Debug, Clone)]
+struct AppState<P, F> {
+    config: Config,
+    account_summaries_projection: P,
+    account_factory: F,
+    account_cache: Option<AccountCache>,
+    closed_periods: ClosedPeriods,
+    quotes: Quotes,
+    step_up: Option<StepUp>,
+    cluster: Option<Cluster>,
+    ids: Arc<dyn IdGenerator>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Health of this node.
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Health {
+    leader: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct CreateAccount {
+    #[serde(default)]
+    opening_balance: EuroCent,
+
+    /// Defaults to the default currency of the tenant.
+    #[serde(default)]
+    currency: Option<Currency>,
+
+    #[serde(default)]
+    external_ref: Option<ExternalRef>,
+
+    #[serde(default)]
+    customer: Option<CustomerId>,
+
+    #[serde(default)]
+    product: Product,
+
+    /// Whether to create a sandbox account for test data.
+    #[serde(default)]
+    sandbox: bool,
+}
+
+/// Conflicts of an account to be created with existing accounts or with itself, if already created,
+/// returned as problem with status `409 Conflict`.
+#[derive(Debug, Error)]
+enum AccountConflict {
+    #[error("External reference '{0}' already in use")]
+    ExternalRefInUse(ExternalRef),
+
+    #[error("Maximum number of {0} accounts per tenant reached")]
+    TenantQuotaExceeded(usize),
+
+    #[error("Maximum number of {0} accounts per customer reached")]
+    CustomerQuotaExceeded(usize),
+
+    #[error("Account {0} already created with different parameters")]
+    CreatedDifferently(Uuid),
+}
+
+impl From<&AccountConflict> for ErrorCode {
+    fn from(conflict: &AccountConflict) -> Self {
+        match conflict {
+            AccountConflict::ExternalRefInUse(_) => ErrorCode::ExternalRefInUse,
+            AccountConflict::TenantQuotaExceeded(_) | AccountConflict::CustomerQuotaExceeded(_) => {
+                ErrorCode::QuotaExceeded
+            }
+            AccountConflict::CreatedDifferently(_) => ErrorCode::ParametersMismatch,
+        }
+    }
+}
+
+/// Extractor for dry runs, requested via the `dry_run=true` query parameter or the
+/// `Prefer: validation-only` header. Dry runs validate commands against the current state and
+/// return the would-be outcome without persisting anything.
+#[derive(Debug, Clone, Copy)]
+struct DryRun(bool);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for DryRun
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts
+            .uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .any(|param| param == "dry_run=true");
+        let prefer = parts
+            .headers
+            .get_all(PREFER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|preference| preference.trim() == VALIDATION_ONLY);
+        Ok(DryRun(query || prefer))
+    }
+}
+
+/// Extractor for the [TenantId] from the [TENANT_ID] header, defaulting to the default tenant.
+#[derive(Debug, Clone, Copy)]
+struct Tenant(TenantId);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Tenant
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(TENANT_ID) {
+            Some(tenant) => tenant
+                .to_str()
+                .ok()
+                .and_then(|tenant| tenant.parse().ok())
+                .map(Tenant)
+                .ok_or_else(|| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid header '{TENANT_ID}'"),
+                    )
+                }),
+            None => Ok(Tenant(TenantId::default())),
+        }
+    }
+}
+
+/// Extractor for the [Channel](channel::Channel) metadata from the [DEVICE_ID], [GEOLOCATION],
+/// [X_FORWARDED_FOR] and `user-agent` headers, `None` if none is given. Only the geolocation is
+/// validated, as the others are merely recorded.
+#[derive(Debug, Clone)]
+struct Channel(Option<channel::Channel>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Channel
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+
+        let geolocation = header(GEOLOCATION)
+            .map(|geolocation| geolocation.parse::<Geolocation>())
+            .transpose()
+            .map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()))?;
+        let ip = header(X_FORWARDED_FOR)
+            .and_then(|forwarded_for| forwarded_for.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+
+        Ok(Channel(channel::Channel::new(
+            header(DEVICE_ID).map(ToString::to_string),
+            ip,
+            header(USER_AGENT.as_str()).map(ToString::to_string),
+            geolocation,
+        )))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Deposit {
+    amount: EuroCent,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    value_date: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Withdraw {
+    amount: EuroCent,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    value_date: Option<OffsetDateTime>,
+
+    /// Quote for this withdrawal, if any, else the fee is charged without a quote.
+    #[serde(default)]
+    quote_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct QuoteWithdrawal {
+    amount: EuroCent,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct CloseAccount {
+    /// Account to sweep a non-zero balance to, required unless the balance is zero.
+    #[serde(default)]
+    sweep_to: Option<Uuid>,
+}
+
+/// Representation of an account.
+#[derive(Debug, Clone, Serialize)]
+struct AccountRepr {
+    id: Uuid,
+    iban: Iban,
+    currency: Currency,
+    balance: EuroCent,
+    /// Watermark for test data: sandbox accounts hold no real money.
+    sandbox: bool,
+    seq_no: u64,
+    links: AccountLinks,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AccountLinks {
+    #[serde(rename = "self")]
+    self_: String,
+    deposits: String,
+    withdrawals: String,
+}
+
+impl AccountRepr {
+    fn new(id: Uuid, iban: Iban, snapshot: &Snapshot) -> Self {
+        Self {
+            id,
+            iban,
+            currency: snapshot.state.currency().unwrap_or_default(),
+            balance: snapshot.state.balance().unwrap_or_default(),
+            sandbox: snapshot.state.is_sandbox(),
+            seq_no: snapshot.seq_no,
+            links: AccountLinks {
+                self_: format!("/accounts/{id}"),
+                deposits: format!("/accounts/{id}/deposits"),
+                withdrawals: format!("/accounts/{id}/withdrawals"),
+            },
+        }
+    }
+}
+
+/// Representation of a transaction, i.e. a deposit or a withdrawal.
+#[derive(Debug, Clone, Serialize)]
+struct TransactionRepr {
+    id: Uuid,
+    account_id: Uuid,
+    kind: TransactionKind,
+    amount: EuroCent,
+    balance: EuroCent,
+    links: TransactionLinks,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum TransactionKind {
+    Deposit,
+    Withdrawal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TransactionLinks {
+    #[serde(rename = "self")]
+    self_: String,
+    account: String,
+}
+
+impl TransactionRepr {
+    fn new(
+        id: Uuid,
+        account_id: Uuid,
+        kind: TransactionKind,
+        amount: EuroCent,
+        snapshot: &Snapshot,
+    ) -> Self {
+        // Transactions are not exposed individually, only via the transaction history.
+        let self_ = format!("/accounts/{account_id}/transactions");
+        Self {
+            id,
+            account_id,
+            kind,
+            amount,
+            balance: snapshot.state.balance().unwrap_or_default(),
+            links: TransactionLinks {
+                self_,
+                account: format!("/accounts/{account_id}"),
+            },
+        }
+    }
+}
+
+async fn root() -> impl IntoResponse {
+    debug!("Endpoint / invoked");
+    StatusCode::OK
+}
+
+/// The account state machine as Mermaid (default) or Graphviz diagram, generated from the same
+/// transition table the command handler is tested against.
+async fn get_account_state_machine(
+    Query(DiagramQuery { format }): Query<DiagramQuery>,
+) -> impl IntoResponse {
+    debug!("Endpoint GET /docs/account-state-machine invoked");
+    account::state_machine_diagram(format)
+}
+
+#[derive(Debug, Deserialize)]
+struct DiagramQuery {
+    #[serde(default)]
+    format: Format,
+}
+
+/// Forward requests for accounts owned by another node of the [Cluster], if any, to that node.
+async fn forward_to_owner(
+    State(cluster): State<Option<Cluster>>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let id = request
+        .uri()
+        .path()
+        .strip_prefix("/accounts/")
+        .and_then(|path| path.split('/').next())
+        .and_then(|id| id.parse::<Uuid>().ok());
+
+    match (cluster, id) {
+        (Some(cluster), Some(id)) => match cluster.forward(id, request).await {
+            Ok(response) => response,
+            Err(request) => next.run(request).await,
+        },
+        _ => next.run(request).await,
+    }
+}
+
+async fn create_account<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Tenant(tenant): Tenant,
+    create_account: Option<Json<CreateAccount>>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    let create_account = create_account.map(|Json(c)| c).unwrap_or_default();
+
+    // In a cluster, generate an ID for an account owned by this node to avoid forwarding.
+    let id = iter::repeat_with(|| app_state.ids.next_id())
+        .find(|id| {
+            app_state
+                .cluster
+                .as_ref()
+                .map(|cluster| cluster.is_local(*id))
+                .unwrap_or(true)
+        })
+        .expect("infinite iterator");
+
+    create(app_state, id, tenant, create_account).await
+}
+
+async fn get_account<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    debug!(%id, "Endpoint GET /accounts/:id invoked");
+
+    // Cached representations are dropped once the projection observes new events of their
+    // accounts, hence these are served without hitting the account entity.
+    let account_cache = app_state.account_cache.as_ref();
+    if let Some(account_cache) = account_cache {
+        if let Some(account) = account_cache.get(id) {
+            return cached_account_response(account, account_cache);
+        }
+    }
+
+    if !app_state.account_summaries_projection.contains(id).await {
+        return Problem::new(ErrorCode::NotFound).into_response();
+    }
+
+    match app_state
+        .account_factory
+        .get(id)
+        .await
+        .context("Cannot get Account entity")
+    {
+        Ok(account) => {
+            let snapshot = account.snapshot();
+            let Some(account) = account_repr(id, &snapshot) else {
+                return Problem::new(ErrorCode::NotFound).into_response();
+            };
+            let Some(account_cache) = account_cache else {
+                return (snapshot_headers(&snapshot), Json(account)).into_response();
+            };
+
+            match serde_json::to_vec(&account) {
+                Ok(body) => {
+                    let account = CachedAccount {
+                        seq_no: snapshot.seq_no,
+                        balance: snapshot.state.balance().unwrap_or_default().into(),
+                        body: body.into(),
+                    };
+                    account_cache.insert(id, account.clone());
+                    cached_account_response(account, account_cache)
+                }
+
+                Err(error) => {
+                    error!(%id, %error, "Cannot serialize account");
+                    Problem::new(ErrorCode::Internal).into_response()
+                }
+            }
+        }
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot get account");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+fn cached_account_response(account: CachedAccount, account_cache: &AccountCache) -> Response {
+    let CachedAccount {
+        seq_no,
+        balance,
+        body,
+    } = account;
+    (
+        account_headers(seq_no, balance),
+        [
+            (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+            (CACHE_CONTROL, account_cache.cache_control()),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+async fn put_account<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Path(id): Path<Uuid>,
+    Tenant(tenant): Tenant,
+    create_account: Option<Json<CreateAccount>>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    let create_account = create_account.map(|Json(c)| c).unwrap_or_default();
+    create(app_state, id, tenant, create_account).await
+}
+
+/// Create the account with the given ID. Creating an already existing account with the same
+/// parameters is idempotent, i.e. `200 OK`, with different ones a `409 Conflict`.
+async fn create<P, F>(
+    app_state: AppState<P, F>,
+    id: Uuid,
+    tenant: TenantId,
+    CreateAccount {
+        opening_balance,
+        currency,
+        external_ref,
+        customer,
+        product,
+        sandbox,
+    }: CreateAccount,
+) -> Response
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    let currencies = &app_state.config.currencies;
+    let currency = currency.unwrap_or_else(|| currencies.default_for(tenant));
+    if !currencies.is_supported(tenant, currency) {
+        return Problem::new(ErrorCode::InvalidRequest)
+            .with_detail(format!("Currency {currency} not supported"))
+            .into_response();
+    }
+
+    let projection = &app_state.account_summaries_projection;
+
+    // Merchant accounts must only be settled to another existing open account.
+    if let Product::Merchant { settlement_account } = product {
+        let open = settlement_account != id
+            && projection
+                .summary(settlement_account)
+                .await
+                .is_some_and(|summary| summary.status == AccountStatus::Open);
+        if !open {
+            return Problem::new(ErrorCode::InvalidRequest)
+                .with_detail(format!(
+                    "Settlement account {settlement_account} not found or closed"
+                ))
+                .into_response();
+        }
+    }
+
+    // Quotas only apply to new accounts, not to idempotently creating existing ones.
+    if !projection.contains(id).await {
+        if let Some(max) = app_state.config.max_accounts_per_tenant {
+            if projection.count_by_tenant(tenant).await >= max {
+                return Problem::from(&AccountConflict::TenantQuotaExceeded(max)).into_response();
+            }
+        }
+
+        if let (Some(max), Some(customer)) = (app_state.config.max_accounts_per_customer, customer)
+        {
+            if projection.count_by_customer(customer).await >= max {
+                return Problem::from(&AccountConflict::CustomerQuotaExceeded(max)).into_response();
+            }
+        }
+    }
+
+    if let Some(external_ref) = &external_ref {
+        let other_id = projection
+            .account_id_by_external_ref(tenant, external_ref.clone())
+            .await;
+        if matches!(other_id, Some(other_id) if other_id != id) {
+            let conflict = AccountConflict::ExternalRefInUse(external_ref.clone());
+            return Problem::from(&conflict).into_response();
+        }
+    }
+
+    let Some(iban) = allocate_iban(projection, app_state.config.bank_code, id).await else {
+        error!(%id, "Cannot allocate IBAN");
+        return Problem::new(ErrorCode::Internal).into_response();
+    };
+    let cmd = account::Cmd::Create {
+        id,
+        tenant,
+        customer,
+        iban,
+        currency,
+        opening_balance,
+        external_ref,
+        product,
+        sandbox,
+    };
+    match app_state
+        .account_factory
+        .get(id)
+        .await
+        .context("Cannot get Account entity")
+    {
+        Ok(account) => match account
+            .handle_cmd(cmd.clone())
+            .await
+            .context("Cannot handle Create command")
+        {
+            Ok(Ok(snapshot)) => {
+                let location_value = HeaderValue::from_str(&format!("/accounts/{id}")).unwrap();
+                let mut location_value = iter::once(&location_value);
+                let location = Location::decode(&mut location_value).unwrap();
+                (
+                    StatusCode::CREATED,
+                    TypedHeader(location),
+                    Json(AccountRepr::new(id, iban, &snapshot)),
+                )
+                    .into_response()
+            }
+
+            // Repeating the same create is idempotent, creating with different parameters not.
+            Ok(Err(account::Error::AlreadyCreated)) => {
+                let snapshot = account.snapshot();
+                if snapshot.state.is_created_by(&cmd) {
+                    (StatusCode::OK, Json(AccountRepr::new(id, iban, &snapshot))).into_response()
+                } else {
+                    Problem::from(&AccountConflict::CreatedDifferently(id)).into_response()
+                }
+            }
+
+            Ok(Err(error)) => Problem::from(&error).into_response(),
+
+            Err(error) => {
+                let code = ErrorCode::of(&error);
+                error!(%code, %id, error = format!("{error:#}"), "Cannot create account");
+                Problem::new(code).into_response()
+            }
+        },
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot create account");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn deposit_to_account<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Path(id): Path<Uuid>,
+    DryRun(dry_run): DryRun,
+    Channel(channel): Channel,
+    Json(Deposit { amount, value_date }): Json<Deposit>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    if let Err(error) = check_value_date(&app_state, value_date) {
+        return Problem::new(ErrorCode::InvalidRequest)
+            .with_detail(error)
+            .into_response();
+    }
+
+    if app_state.account_summaries_projection.contains(id).await {
+        match app_state
+            .account_factory
+            .get(id)
+            .await
+            .context("Cannot get Account entity")
+        {
+            Ok(account) => {
+                let deposit_id = app_state.ids.next_id();
+                let cmd = account::Cmd::Deposit(deposit_id, amount, value_date, channel);
+                if dry_run {
+                    return dry_run_transaction(
+                        &account,
+                        cmd,
+                        deposit_id,
+                        id,
+                        TransactionKind::Deposit,
+                        amount,
+                    );
+                }
+                match account
+                    .handle_cmd(cmd)
+                    .await
+                    .context("Cannot handle Deposit command")
+                {
+                    Ok(Ok(snapshot)) => {
+                        let location_value =
+                            HeaderValue::from_str(&format!("/accounts/{id}/transactions")).unwrap();
+                        let mut location_value = iter::once(&location_value);
+                        let location = Location::decode(&mut location_value).unwrap();
+                        let deposit = TransactionRepr::new(
+                            deposit_id,
+                            id,
+                            TransactionKind::Deposit,
+                            amount,
+                            &snapshot,
+                        );
+                        (
+                            StatusCode::CREATED,
+                            TypedHeader(location),
+                            snapshot_headers(&snapshot),
+                            Json(deposit),
+                        )
+                            .into_response()
+                    }
+
+                    Ok(Err(error)) => Problem::from(&error).into_response(),
+
+                    Err(error) => {
+                        let code = ErrorCode::of(&error);
+                        error!(%code, %id, error = format!("{error:#}"), "Cannot deposit");
+                        Problem::new(code).into_response()
+                    }
+                }
+            }
+
+            Err(error) => {
+                let code = ErrorCode::of(&error);
+                error!(%code, %id, error = format!("{error:#}"), "Cannot deposit");
+                Problem::new(code).into_response()
+            }
+        }
+    } else {
+        Problem::new(ErrorCode::NotFound).into_response()
+    }
+}
+
+async fn set_notification_prefs<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Path(id): Path<Uuid>,
+    DryRun(dry_run): DryRun,
+    Json(notification_prefs): Json<NotificationPrefs>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    if app_state.account_summaries_projection.contains(id).await {
+        match app_state
+            .account_factory
+            .get(id)
+            .await
+            .context("Cannot get Account entity")
+        {
+            Ok(account) if dry_run => {
+                match account.dry_run(account::Cmd::SetNotificationPrefs(notification_prefs)) {
+                    Ok(snapshot) => {
+                        (StatusCode::NO_CONTENT, snapshot_headers(&snapshot)).into_response()
+                    }
+                    Err(error) => Problem::from(&error).into_response(),
+                }
+            }
+
+            Ok(account) => match account
+                .handle_cmd(account::Cmd::SetNotificationPrefs(notification_prefs))
+                .await
+                .context("Cannot handle SetNotificationPrefs command")
+            {
+                Ok(Ok(snapshot)) => {
+                    (StatusCode::NO_CONTENT, snapshot_headers(&snapshot)).into_response()
+                }
+
+                Ok(Err(error)) => Problem::from(&error).into_response(),
+
+                Err(error) => {
+                    let code = ErrorCode::of(&error);
+                    error!(%code, %id, error = format!("{error:#}"), "Cannot set notification prefs");
+                    Problem::new(code).into_response()
+                }
+            },
+
+            Err(error) => {
+                let code = ErrorCode::of(&error);
+                error!(%code, %id, error = format!("{error:#}"), "Cannot set notification prefs");
+                Problem::new(code).into_response()
+            }
+        }
+    } else {
+        Problem::new(ErrorCode::NotFound).into_response()
+    }
+}
+
+async fn withdraw_from_account<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Path(id): Path<Uuid>,
+    DryRun(dry_run): DryRun,
+    Channel(channel): Channel,
+    Json(Withdraw {
+        amount,
+        value_date,
+        quote_id,
+    }): Json<Withdraw>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    if let Err(error) = check_value_date(&app_state, value_date) {
+        return Problem::new(ErrorCode::InvalidRequest)
+            .with_detail(error)
+            .into_response();
+    }
+
+    // The amount withdrawn includes the fee, either quoted or current. A quote is taken, such that
+    // it cannot be used concurrently, and restored, unless the withdrawal is accepted.
+    let (amount, quote) = match quote_id {
+        Some(quote_id) if !dry_run => match app_state.quotes.take(quote_id, id, amount) {
+            Ok(quote) => (quote.total, Some(quote)),
+            Err(error) => {
+                return Problem::new(ErrorCode::InvalidRequest)
+                    .with_detail(error.to_string())
+                    .into_response()
+            }
+        },
+        _ => (amount.saturating_add(app_state.quotes.fee(amount)), None),
+    };
+
+    let response = withdraw(&app_state, id, amount, value_date, channel, dry_run).await;
+    if let Some(quote) = quote {
+        if !response.status().is_success() {
+            app_state.quotes.restore(quote);
+        }
+    }
+    response
+}
+
+/// Withdraw the given amount, including the fee, from the account with the given ID.
+async fn withdraw<P, F>(
+    app_state: &AppState<P, F>,
+    id: Uuid,
+    amount: EuroCent,
+    value_date: Option<OffsetDateTime>,
+    channel: Option<channel::Channel>,
+    dry_run: bool,
+) -> Response
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    if let Some(summary) = app_state.account_summaries_projection.summary(id).await {
+        if fast_fails(
+            amount,
+            summary.balance,
+            app_state.config.withdraw_fast_fail_margin,
+        ) {
+            debug!(%id, %amount, balance = %summary.balance, "Fast-failing withdrawal");
+            let error = account::Error::InvalidWithdraw {
+                balance: summary.balance,
+                withdraw_amount: amount,
+            };
+            return Problem::from(&error).into_response();
+        }
+
+        match app_state
+            .account_factory
+            .get(id)
+            .await
+            .context("Cannot get Account entity")
+        {
+            Ok(account) => {
+                let withdrawal_id = app_state.ids.next_id();
+                let device_id = channel
+                    .as_ref()
+                    .and_then(|channel| channel.device_id.clone());
+                let cmd = account::Cmd::Withdraw(withdrawal_id, amount, value_date, channel);
+                if dry_run {
+                    return dry_run_transaction(
+                        &account,
+                        cmd,
+                        withdrawal_id,
+                        id,
+                        TransactionKind::Withdrawal,
+                        amount,
+                    );
+                }
+                if let Some(step_up) = &app_state.step_up {
+                    if let Some(reason) = step_up.check(id, amount, device_id.as_deref()) {
+                        return step_up::park_withdrawal(
+                            step_up,
+                            id,
+                            withdrawal_id,
+                            amount,
+                            cmd,
+                            reason,
+                        );
+                    }
+                }
+                match account
+                    .handle_cmd(cmd)
+                    .await
+                    .context("Cannot handle Withdraw command")
+                {
+                    Ok(Ok(snapshot)) => {
+                        if let Some(step_up) = &app_state.step_up {
+                            step_up.record(id, amount, device_id);
+                        }
+                        let location_value =
+                            HeaderValue::from_str(&format!("/accounts/{id}/transactions")).unwrap();
+                        let mut location_value = iter::once(&location_value);
+                        let location = Location::decode(&mut location_value).unwrap();
+                        let withdrawal = TransactionRepr::new(
+                            withdrawal_id,
+                            id,
+                            TransactionKind::Withdrawal,
+                            amount,
+                            &snapshot,
+                        );
+                        (
+                            StatusCode::CREATED,
+                            TypedHeader(location),
+                            snapshot_headers(&snapshot),
+                            Json(withdrawal),
+                        )
+                            .into_response()
+                    }
+
+                    Ok(Err(error)) => Problem::from(&error).into_response(),
+
+                    Err(error) => {
+                        let code = ErrorCode::of(&error);
+                        error!(%code, %id, error = format!("{error:#}"), "Cannot withdraw");
+                        Problem::new(code).into_response()
+                    }
+                }
+            }
+
+            Err(error) => {
+                let code = ErrorCode::of(&error);
+                error!(%code, %id, error = format!("{error:#}"), "Cannot withdraw");
+                Problem::new(code).into_response()
+            }
+        }
+    } else {
+        Problem::new(ErrorCode::NotFound).into_response()
+    }
+}
+
+async fn quote_withdrawal<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Path(id): Path<Uuid>,
+    Json(QuoteWithdrawal { amount }): Json<QuoteWithdrawal>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    if app_state.account_summaries_projection.contains(id).await {
+        match app_state
+            .account_factory
+            .get(id)
+            .await
+            .context("Cannot get Account entity")
+        {
+            Ok(account) => {
+                let fee = app_state.quotes.fee(amount);
+                let cmd = account::Cmd::Withdraw(app_state.ids.next_id(), amount + fee, None, None);
+                match account.dry_run(cmd) {
+                    Ok(snapshot) => {
+                        let balance = snapshot.state.balance().unwrap_or_default();
+                        let quote = app_state.quotes.create(id, amount, fee, balance);
+                        (StatusCode::CREATED, Json(quote)).into_response()
+                    }
+
+                    Err(error) => Problem::from(&error).into_response(),
+                }
+            }
+
+            Err(error) => {
+                let code = ErrorCode::of(&error);
+                error!(%code, %id, error = format!("{error:#}"), "Cannot quote withdrawal");
+                Problem::new(code).into_response()
+            }
+        }
+    } else {
+        Problem::new(ErrorCode::NotFound).into_response()
+    }
+}
+
+async fn close_account<P, F>(
+    State(app_state): State<AppState<P, F>>,
+    Path(id): Path<Uuid>,
+    DryRun(dry_run): DryRun,
+    Json(CloseAccount { sweep_to }): Json<CloseAccount>,
+) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    if !app_state.account_summaries_projection.contains(id).await {
+        return Problem::new(ErrorCode::NotFound).into_response();
+    }
+
+    // The balance must only be swept to an existing open account; checked with the entity, as the
+    // projection might lag behind.
+    if let Some(sweep_to) = sweep_to {
+        let open = match app_state
+            .account_factory
+            .get(sweep_to)
+            .await
+            .context("Cannot get Account entity to sweep to")
+        {
+            Ok(account) => matches!(account.snapshot().state, account::State::Created { .. }),
+            Err(error) => {
+                let code = ErrorCode::of(&error);
+                error!(%code, %id, error = format!("{error:#}"), "Cannot close account");
+                return Problem::new(code).into_response();
+            }
+        };
+        if !open {
+            return Problem::new(ErrorCode::InvalidRequest)
+                .with_detail(format!(
+                    "Account {sweep_to} to sweep to not found or closed"
+                ))
+                .into_response();
+        }
+    }
+
+    let account = match app_state
+        .account_factory
+        .get(id)
+        .await
+        .context("Cannot get Account entity")
+    {
+        Ok(account) => account,
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot close account");
+            return Problem::new(code).into_response();
+        }
+    };
+
+    let closure_id = app_state.ids.next_id();
+    let cmd = account::Cmd::Close {
+        id: closure_id,
+        sweep_to,
+    };
+    if dry_run {
+        return match account.dry_run(cmd) {
+            Ok(snapshot) => (StatusCode::NO_CONTENT, snapshot_headers(&snapshot)).into_response(),
+            Err(error) => Problem::from(&error).into_response(),
+        };
+    }
+
+    // A non-zero balance is swept asynchronously by the transfer process manager, which retries
+    // and compensates.
+    match account
+        .handle_cmd(cmd)
+        .await
+        .context("Cannot handle Close command")
+    {
+        Ok(Ok(snapshot)) => (StatusCode::NO_CONTENT, snapshot_headers(&snapshot)).into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot close account");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+/// Whether a withdrawal of the given amount obviously exceeds the given projected balance, i.e. by
+/// more than the given margin, if any.
+fn fast_fails(amount: EuroCent, balance: EuroCent, margin: Option<EuroCent>) -> bool {
+    margin.is_some_and(|margin| amount > balance.saturating_add(margin))
+}
+
+/// Allocate the [Iban] for the account with the given ID: as account numbers are derived from the
+/// ID, these might collide, hence another one is derived, if already taken by another account. As
+/// the projection might lag behind, this is a best effort check.
+async fn allocate_iban<P>(projection: &P, bank_code: BankCode, id: Uuid) -> Option<Iban>
+where
+    P: AccountSummariesProjection,
+{
+    for attempt in 0..MAX_IBAN_ATTEMPTS {
+        let iban = Iban::nth_for_account(bank_code, id, attempt);
+        match projection.account_id_by_iban(iban).await {
+            Some(other_id) if other_id != id => {
+                warn!(%id, %other_id, %iban, "IBAN already taken");
+            }
+            _ => return Some(iban),
+        }
+    }
+    None
+}
+
+async fn list_hot_accounts<P, F>(State(app_state): State<AppState<P, F>>) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    debug!("Endpoint GET /admin/hot-accounts invoked");
+    Json(app_state.account_factory.hot_accounts())
+}
+
+/// Response for a dry run of a deposit or withdrawal: the would-be transaction, not persisted.
+fn dry_run_transaction(
+    account: &AccountRef,
+    cmd: account::Cmd,
+    id: Uuid,
+    account_id: Uuid,
+    kind: TransactionKind,
+    amount: EuroCent,
+) -> Response {
+    match account.dry_run(cmd) {
+        Ok(snapshot) => {
+            let transaction = TransactionRepr::new(id, account_id, kind, amount, &snapshot);
+            (
+                StatusCode::OK,
+                snapshot_headers(&snapshot),
+                Json(transaction),
+            )
+                .into_response()
+        }
+
+        Err(error) => Problem::from(&error).into_response(),
+    }
+}
+
+/// The representation of the account with the given ID and [Snapshot], unless not yet created or
+/// purged.
+fn account_repr(id: Uuid, snapshot: &Snapshot) -> Option<AccountRepr> {
+    match &snapshot.state {
+        account::State::Closed { purged: true, .. } => None,
+        state => state
+            .iban()
+            .map(|iban| AccountRepr::new(id, iban.clone(), snapshot)),
+    }
+}
+
+/// Headers for the given [Snapshot] of an account, hinting at its sequence number and balance.
+fn snapshot_headers(snapshot: &Snapshot) -> [(&'static str, String); 2] {
+    let balance = snapshot.state.balance().unwrap_or_default();
+    account_headers(snapshot.seq_no, balance.into())
+}
+
+/// Headers hinting at the given sequence number and balance of an account.
+fn account_headers(seq_no: u64, balance: u64) -> [(&'static str, String); 2] {
+    [
+        (ACCOUNT_SEQ_NO, seq_no.to_string()),
+        (ACCOUNT_BALANCE, balance.to_string()),
+    ]
+}
+
+/// Check the optional value date of a deposit or withdrawal against the configured back- and
+/// forward-dating windows and the closed periods.
+fn check_value_date<P, F>(
+    app_state: &AppState<P, F>,
+    value_date: Option<OffsetDateTime>,
+) -> Result<(), String> {
+    let Some(value_date) = value_date else {
+        return Ok(());
+    };
+
+    let now = app_state.clock.now();
+    if value_date < now - Duration::days(app_state.config.max_back_dating_days as i64) {
+        return Err(format!(
+            "Value date must not be more than {} days in the past",
+            app_state.config.max_back_dating_days
+        ));
+    }
+    if value_date > now + Duration::days(app_state.config.max_forward_dating_days as i64) {
+        return Err(format!(
+            "Value date must not be more than {} days in the future",
+            app_state.config.max_forward_dating_days
+        ));
+    }
+    if let Some(closed_until) = app_state.closed_periods.closed_until() {
+        if value_date < closed_until {
+            return Err(format!(
+                "Value date must not be in a closed period, i.e. before {closed_until}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fast_fails() {
+        let balance = EuroCent::from(100);
+        let margin = Some(EuroCent::from(50));
+        assert!(!fast_fails(EuroCent::from(1_000), balance, None));
+        assert!(!fast_fails(EuroCent::from(150), balance, margin));
+        assert!(fast_fails(EuroCent::from(151), balance, margin));
+        assert!(!fast_fails(
+            EuroCent::from(u64::MAX),
+            balance,
+            Some(EuroCent::from(u64::MAX))
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use super::policy;
+use axum::{
+    body::Body,
+    http::{header::ALLOW, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tracing::debug;
+
+/// Deployment mode of an instance, e.g. to scale the read path independently of the command side.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    /// Commands and queries.
+    #[default]
+    Full,
+
+    /// Only projections and read endpoints: mutating requests are rejected and no background
+    /// processing issuing commands, e.g. direct debit collection, is spawned.
+    ReadOnly,
+
+    /// Only command endpoints and entities: read requests are rejected and the account summaries
+    /// projection is not spawned, i.e. accounts are checked via their entities.
+    CommandOnly,
+
+    /// Passive instance in a secondary region, tailing the replicated event log: like
+    /// [Mode::Full], but mutating requests are rejected and no singleton tasks run until it
+    /// has been promoted.
+    Standby,
+}
+
+impl Mode {
+    /// Whether commands are handled, i.e. entities are spawned, in this mode.
+    pub fn handles_cmds(&self) -> bool {
+        *self != Mode::ReadOnly
+    }
+
+    /// Whether queries are handled, i.e. projections are spawned, in this mode.
+    pub fn handles_queries(&self) -> bool {
+        *self != Mode::CommandOnly
+    }
+}
+
+/// Middleware rejecting mutating requests, i.e. all but GET, HEAD, OPTIONS and queries, with
+/// `405 Method Not Allowed`, for [Mode::ReadOnly].
+pub async fn reject_mutations(request: Request<Body>, next: Next<Body>) -> Response {
+    if !is_mutation(&request) {
+        return next.run(request).await;
+    }
+
+    debug!(method = %request.method(), path = request.uri().path(), "Rejecting mutation");
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(ALLOW, HeaderValue::from_static("GET, HEAD, OPTIONS"))],
+        "Read-only instance",
+    )
+        .into_response()
+}
+
+/// Middleware rejecting read requests, i.e. GET, HEAD and queries, with `405 Method Not Allowed`,
+/// for [Mode::CommandOnly].
+pub async fn reject_queries(request: Request<Body>, next: Next<Body>) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) && !is_query_route(&request) {
+        return next.run(request).await;
+    }
+
+    debug!(method = %request.method(), path = request.uri().path(), "Rejecting query");
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(ALLOW, HeaderValue::from_static("POST, PUT, DELETE"))],
+        "Command-only instance",
+    )
+        .into_response()
+}
+
+/// Whether the given request is mutating, i.e. neither GET, HEAD, OPTIONS nor a query.
+pub fn is_mutation(request: &Request<Body>) -> bool {
+    !is_safe(request.method()) && !is_query_route(request)
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn is_query_route(request: &Request<Body>) -> bool {
+    policy::of(request).is_some_and(|policy| policy.query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode() {
+        assert!(Mode::default().handles_cmds());
+        assert!(!Mode::ReadOnly.handles_cmds());
+        assert!(Mode::ReadOnly.handles_queries());
+        assert!(Mode::CommandOnly.handles_cmds());
+        assert!(!Mode::CommandOnly.handles_queries());
+        assert!(Mode::Standby.handles_cmds());
+        assert!(Mode::Standby.handles_queries());
+        assert!(is_safe(&Method::GET));
+        assert!(!is_safe(&Method::POST));
+        assert!(!is_safe(&Method::DELETE));
+    }
+}
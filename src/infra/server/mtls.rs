@@ -0,0 +1,153 @@
+use anyhow::{anyhow, Context, Result};
+use axum::{Extension, Router};
+use hyper::server::conn::Http;
+use serde::Deserialize;
+use std::{
+    fs::File,
+    future::Future,
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{net::TcpListener, pin, select, task};
+use tokio_rustls::{
+    rustls::{
+        server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tracing::{debug, warn};
+
+/// Configuration for requiring client certificates issued by the given CA bundle.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    ca_bundle: PathBuf,
+    cert: PathBuf,
+    key: PathBuf,
+
+    /// Principals with their roles by the common name of the certificate subject.
+    #[serde(default)]
+    principals: Vec<Principal>,
+}
+
+/// The authenticated client, added to the request extensions.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Principal {
+    pub subject: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// Serve the given app via TLS with the given connection settings, requiring valid client
+/// certificates, until the given shutdown signal completes. HTTP/2 is negotiated via ALPN.
+pub async fn serve<S>(
+    addr: SocketAddr,
+    http: Http,
+    config: Config,
+    app: Router,
+    shutdown_signal: S,
+) -> Result<()>
+where
+    S: Future<Output = ()> + Send + 'static,
+{
+    let acceptor = TlsAcceptor::from(tls_config(&config)?);
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Cannot bind to {addr}"))?;
+    let principals = Arc::new(config.principals);
+
+    pin!(shutdown_signal);
+    loop {
+        let (stream, peer_addr) = select! {
+            accepted = listener.accept() => accepted.context("Cannot accept connection")?,
+            _ = &mut shutdown_signal => return Ok(()),
+        };
+
+        let acceptor = acceptor.clone();
+        let principals = principals.clone();
+        let app = app.clone();
+        let http = http.clone();
+        task::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(error) => {
+                    debug!(%peer_addr, %error, "TLS handshake failed");
+                    return;
+                }
+            };
+
+            let subject = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(common_name);
+            let Some(subject) = subject else {
+                warn!(%peer_addr, "Client certificate without common name");
+                return;
+            };
+            let principal = principals
+                .iter()
+                .find(|principal| principal.subject == subject)
+                .cloned()
+                .unwrap_or(Principal {
+                    subject,
+                    roles: vec![],
+                });
+
+            let app = app.layer(Extension(principal));
+            if let Err(error) = http.serve_connection(stream, app).await {
+                debug!(%peer_addr, %error, "Cannot serve connection");
+            }
+        });
+    }
+}
+
+fn tls_config(config: &Config) -> Result<Arc<ServerConfig>> {
+    let mut roots = RootCertStore::empty();
+    for cert in certs(&config.ca_bundle)? {
+        roots.add(&cert).context("Cannot add CA certificate")?;
+    }
+
+    let key = rustls_pemfile::pkcs8_private_keys(&mut reader(&config.key)?)
+        .context("Cannot read private key")?
+        .into_iter()
+        .next()
+        .map(PrivateKey)
+        .ok_or_else(|| anyhow!("No PKCS8 private key in {}", config.key.display()))?;
+
+    let mut tls_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+        .with_single_cert(certs(&config.cert)?, key)
+        .context("Invalid server certificate or key")?;
+    tls_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(Arc::new(tls_config))
+}
+
+fn certs(path: &Path) -> Result<Vec<Certificate>> {
+    rustls_pemfile::certs(&mut reader(path)?)
+        .with_context(|| format!("Cannot read certificates from {}", path.display()))
+        .map(|certs| certs.into_iter().map(Certificate).collect())
+}
+
+fn reader(path: &Path) -> Result<BufReader<File>> {
+    File::open(path)
+        .with_context(|| format!("Cannot open {}", path.display()))
+        .map(BufReader::new)
+}
+
+fn common_name(cert: &Certificate) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(&cert.0).ok()?;
+    let common_name = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|common_name| common_name.as_str().ok())
+        .map(ToString::to_string);
+    common_name
+}
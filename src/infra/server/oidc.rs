@@ -0,0 +1,53 @@
+use super::signing::Partner;
+#[cfg(feature = "auth")]
+use crate::infra::auth;
+use crate::infra::oidc::Oidc;
+use axum::{
+    body::Body,
+    extract::State,
+    headers::{authorization::Bearer, Authorization},
+    http::{header::WWW_AUTHENTICATE, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    TypedHeader,
+};
+use tracing::debug;
+
+/// Middleware rejecting requests without a valid bearer token; the claims of valid tokens are
+/// added to the request extensions. Requests already authenticated by a partner signature or by an
+/// access token issued by auth are passed on.
+pub async fn authenticate(
+    State(oidc): State<Oidc>,
+    authorization: Option<TypedHeader<Authorization<Bearer>>>,
+    mut request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if request.extensions().get::<Partner>().is_some() {
+        return next.run(request).await;
+    }
+    #[cfg(feature = "auth")]
+    if request.extensions().get::<auth::Claims>().is_some() {
+        return next.run(request).await;
+    }
+
+    let Some(TypedHeader(authorization)) = authorization else {
+        return unauthorized();
+    };
+
+    match oidc.validate(authorization.token()).await {
+        Ok(claims) => {
+            debug!(sub = claims.sub, "Authenticated request");
+            request.extensions_mut().insert(claims);
+            next.run(request).await
+        }
+
+        Err(error) => {
+            debug!(error = format!("{error:#}"), "Invalid bearer token");
+            unauthorized()
+        }
+    }
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Bearer")]).into_response()
+}
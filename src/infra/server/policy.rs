@@ -0,0 +1,462 @@
+use super::authz::Scope;
+use axum::{
+    body::{Body, HttpBody},
+    extract::MatchedPath,
+    http::{header::RETRY_AFTER, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{debug, warn};
+
+/// Timeout of routes not overriding it.
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Maximum body size of writing routes not overriding it.
+const DEFAULT_MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// Seconds after which clients may retry retry-safe requests which timed out.
+const TIMEOUT_RETRY_AFTER_SECS: u64 = 1;
+
+/// Policy applied to a route at the HTTP layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    /// The [Scope] required to access the route.
+    pub scope: Scope,
+
+    /// Maximum time for producing the response, not including streaming its body.
+    pub timeout: Duration,
+
+    /// Maximum size of the request body; bodies of unknown size are rejected, unless this is 0.
+    pub max_body_bytes: u64,
+
+    /// Whether requests are idempotent, such that clients can safely retry them, e.g. after a
+    /// timeout.
+    pub retry_safe: bool,
+
+    /// Whether requests only read, even if their method is not safe, e.g. for batch gets via POST
+    /// because the IDs do not fit into a query string.
+    pub query: bool,
+}
+
+impl Policy {
+    /// Policy for reading routes: retry-safe and without body.
+    const fn read(scope: Scope) -> Self {
+        Self {
+            scope,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            max_body_bytes: 0,
+            retry_safe: true,
+            query: true,
+        }
+    }
+
+    /// Policy for idempotent writing routes, e.g. PUT and DELETE.
+    const fn idempotent(scope: Scope) -> Self {
+        Self {
+            scope,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            retry_safe: true,
+            query: false,
+        }
+    }
+
+    /// Policy for writing routes which must not be retried blindly, e.g. most POSTs.
+    const fn write(scope: Scope) -> Self {
+        Self {
+            retry_safe: false,
+            ..Self::idempotent(scope)
+        }
+    }
+
+    const fn with_timeout_secs(self, timeout_secs: u64) -> Self {
+        Self {
+            timeout: Duration::from_secs(timeout_secs),
+            ..self
+        }
+    }
+
+    const fn with_max_body_bytes(self, max_body_bytes: u64) -> Self {
+        Self {
+            max_body_bytes,
+            ..self
+        }
+    }
+}
+
+/// Central, declarative table of the [Policy] per route, such that the HTTP-layer protections can
+/// be audited in one place. Routes not contained are denied, hence adding a route without a policy
+/// cannot silently skip the protections.
+const ROUTE_POLICIES: &[(&str, &str, Policy)] = &[
+    (
+        "POST",
+        "/accounts",
+        Policy::idempotent(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts:batchGet",
+        Policy::read(Scope::AccountsRead).with_max_body_bytes(DEFAULT_MAX_BODY_BYTES),
+    ),
+    ("GET", "/accounts", Policy::read(Scope::AccountsRead)),
+    ("GET", "/accounts/:id", Policy::read(Scope::AccountsRead)),
+    (
+        "PUT",
+        "/accounts/:id",
+        Policy::idempotent(Scope::AccountsWrite),
+    ),
+    (
+        "PUT",
+        "/accounts/:id/labels",
+        Policy::idempotent(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/deposits",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/withdrawals",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/closure",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/withdrawals/quote",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/confirmations/:id",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "PUT",
+        "/accounts/:id/notification-prefs",
+        Policy::idempotent(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/data-export",
+        Policy::write(Scope::AccountsRead),
+    ),
+    (
+        "GET",
+        "/accounts/:id/data-export",
+        Policy::read(Scope::AccountsRead),
+    ),
+    (
+        "GET",
+        "/accounts/:id/balance",
+        Policy::read(Scope::AccountsRead),
+    ),
+    (
+        "GET",
+        "/accounts/:id/transactions",
+        Policy::read(Scope::AccountsRead),
+    ),
+    (
+        "GET",
+        "/accounts/:id/forecast",
+        Policy::read(Scope::AccountsRead),
+    ),
+    (
+        "POST",
+        "/accounts/:id/consents",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "DELETE",
+        "/consents/:id",
+        Policy::idempotent(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/loans",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/loans/:loan_id/disbursement",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/mandates",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "PUT",
+        "/mandates/:id",
+        Policy::idempotent(Scope::AccountsWrite),
+    ),
+    (
+        "DELETE",
+        "/mandates/:id",
+        Policy::idempotent(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/mandates/:id/collections",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/mandates/:id/collections/:collection_id/refund",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/term-deposits",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/transfers",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/transactions/:transaction_id/disputes",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "POST",
+        "/accounts/:id/transactions/:transaction_id/disputes/:dispute_id/provisional-credit",
+        Policy::write(Scope::Admin),
+    ),
+    (
+        "POST",
+        "/accounts/:id/transactions/:transaction_id/disputes/:dispute_id/resolution",
+        Policy::write(Scope::Admin),
+    ),
+    (
+        "POST",
+        "/term-deposits/:id/early-withdrawal",
+        Policy::write(Scope::AccountsWrite),
+    ),
+    (
+        "GET",
+        "/accounts/:id/transactions/:transaction_id/receipt",
+        Policy::read(Scope::AccountsRead),
+    ),
+    (
+        "GET",
+        "/receipts/verify/:code",
+        Policy::read(Scope::AccountsRead),
+    ),
+    ("GET", "/quota", Policy::read(Scope::AccountsRead)),
+    (
+        "POST",
+        "/ingest/card-authorizations",
+        Policy::write(Scope::CardProcessor).with_max_body_bytes(1024 * 1024),
+    ),
+    ("GET", "/treasury/positions", Policy::read(Scope::Finance)),
+    (
+        "GET",
+        "/admin/ledger/entries",
+        Policy::read(Scope::Finance).with_timeout_secs(30),
+    ),
+    (
+        "POST",
+        "/admin/accounts/:id/adjustments",
+        Policy::write(Scope::Admin),
+    ),
+    (
+        "POST",
+        "/admin/accounts/:id/reopening",
+        Policy::write(Scope::Admin),
+    ),
+    (
+        "GET",
+        "/admin/accounts/:id/archive",
+        Policy::read(Scope::Admin).with_timeout_secs(60),
+    ),
+    (
+        "POST",
+        "/admin/account-imports",
+        Policy::idempotent(Scope::Admin)
+            .with_timeout_secs(60)
+            .with_max_body_bytes(16 * 1024 * 1024),
+    ),
+    (
+        "POST",
+        "/admin/accounts/:id/what-if",
+        Policy::write(Scope::Admin).with_timeout_secs(30),
+    ),
+    ("GET", "/admin/projections", Policy::read(Scope::Admin)),
+    ("GET", "/admin/hot-accounts", Policy::read(Scope::Admin)),
+    (
+        "GET",
+        "/streams/account-lifecycle",
+        Policy::read(Scope::Admin),
+    ),
+    ("GET", "/admin/dead-letters", Policy::read(Scope::Admin)),
+    (
+        "DELETE",
+        "/admin/dead-letters/:id",
+        Policy::idempotent(Scope::Admin),
+    ),
+    (
+        "POST",
+        "/admin/dead-letters/:id/replay",
+        Policy::write(Scope::Admin),
+    ),
+    (
+        "POST",
+        "/admin/reports/:name/run",
+        Policy::write(Scope::Admin).with_timeout_secs(60),
+    ),
+    ("GET", "/admin/reports/runs", Policy::read(Scope::Admin)),
+    ("GET", "/admin/reports/runs/:id", Policy::read(Scope::Admin)),
+    (
+        "GET",
+        "/admin/retention/pending",
+        Policy::read(Scope::Admin),
+    ),
+    (
+        "POST",
+        "/admin/sandbox/purge",
+        Policy::idempotent(Scope::Admin).with_timeout_secs(60),
+    ),
+    ("GET", "/admin/settlements", Policy::read(Scope::Finance)),
+    ("GET", "/admin/standby", Policy::read(Scope::Admin)),
+    (
+        "POST",
+        "/admin/standby/promotion",
+        Policy::write(Scope::Admin),
+    ),
+    ("GET", "/admin/periods", Policy::read(Scope::Admin)),
+    (
+        "POST",
+        "/admin/periods/close",
+        Policy::write(Scope::Admin).with_timeout_secs(30),
+    ),
+];
+
+/// The [Policy] for the given method and route, if any.
+pub fn lookup(method: &Method, route: &str) -> Option<&'static Policy> {
+    ROUTE_POLICIES
+        .iter()
+        .find(|(m, r, _)| *m == method.as_str() && *r == route)
+        .map(|(_, _, policy)| policy)
+}
+
+/// The [Policy] for the route the given request has been matched to, if any.
+pub fn of<B>(request: &Request<B>) -> Option<&'static Policy> {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|route| lookup(request.method(), route.as_str()))
+}
+
+/// Middleware enforcing the [Policy] of the matched route: requests with too large bodies are
+/// rejected with `413 Payload Too Large` or, if their size is unknown, with `411 Length Required`.
+/// Requests exceeding the timeout are answered with `503 Service Unavailable` and `Retry-After`,
+/// if retry-safe, else with `504 Gateway Timeout`, because their outcome is unknown.
+pub async fn enforce(request: Request<Body>, next: Next<Body>) -> Response {
+    let Some(policy) = of(&request) else {
+        warn!(method = %request.method(), path = request.uri().path(), "Route without policy");
+        return StatusCode::FORBIDDEN.into_response();
+    };
+
+    if let Some(status) = check_body_size(request.body().size_hint().upper(), policy) {
+        debug!(method = %request.method(), %status, "Rejecting request body");
+        return status.into_response();
+    }
+
+    let method = request.method().clone();
+    match timeout(policy.timeout, next.run(request)).await {
+        Ok(response) => response,
+
+        Err(_) if policy.retry_safe => {
+            warn!(%method, timeout = ?policy.timeout, "Request timed out");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(RETRY_AFTER, HeaderValue::from(TIMEOUT_RETRY_AFTER_SECS))],
+            )
+                .into_response()
+        }
+
+        Err(_) => {
+            warn!(%method, timeout = ?policy.timeout, "Request timed out");
+            StatusCode::GATEWAY_TIMEOUT.into_response()
+        }
+    }
+}
+
+/// The status for rejecting a request body of the given size, if any; `None` for unknown, e.g.
+/// chunked, bodies.
+pub fn check_body_size(size: Option<u64>, policy: &Policy) -> Option<StatusCode> {
+    match size {
+        Some(size) if size > policy.max_body_bytes => Some(StatusCode::PAYLOAD_TOO_LARGE),
+        None if policy.max_body_bytes > 0 => Some(StatusCode::LENGTH_REQUIRED),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_lookup() {
+        let policy = lookup(&Method::POST, "/accounts:batchGet").unwrap();
+        assert_eq!(policy.scope, Scope::AccountsRead);
+        assert!(policy.query);
+        assert!(policy.retry_safe);
+        assert!(policy.max_body_bytes > 0);
+
+        let policy = lookup(&Method::POST, "/accounts/:id/withdrawals").unwrap();
+        assert!(!policy.query);
+        assert!(!policy.retry_safe);
+
+        assert!(lookup(&Method::GET, "/accounts/:id/withdrawals").is_none());
+    }
+
+    #[test]
+    fn test_route_policies() {
+        let mut routes = HashSet::new();
+        for (method, route, policy) in ROUTE_POLICIES {
+            assert!(
+                routes.insert((method, route)),
+                "{method} {route} is duplicate"
+            );
+            if matches!(*method, "GET" | "PUT" | "DELETE") {
+                assert!(policy.retry_safe, "{method} {route} is not retry-safe");
+            }
+            if *method == "GET" {
+                assert_eq!(policy.max_body_bytes, 0, "GET {route} accepts a body");
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_body_size() {
+        let policy = Policy::write(Scope::AccountsWrite);
+        assert_eq!(check_body_size(Some(42), &policy), None);
+        assert_eq!(
+            check_body_size(Some(DEFAULT_MAX_BODY_BYTES + 1), &policy),
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+        assert_eq!(
+            check_body_size(None, &policy),
+            Some(StatusCode::LENGTH_REQUIRED)
+        );
+
+        let policy = Policy::read(Scope::AccountsRead);
+        assert_eq!(check_body_size(Some(0), &policy), None);
+        assert_eq!(
+            check_body_size(Some(1), &policy),
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use super::problem::Problem;
+use crate::infra::{
+    account::portability::{AccountArchive, AccountPorter},
+    error_code::ErrorCode,
+};
+use axum::{
+    extract::{Path, Query, State},
+    headers::{Header, Location},
+    http::{header::CONTENT_DISPOSITION, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router, TypedHeader,
+};
+use serde::Deserialize;
+use std::iter;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the account portability endpoints, i.e. exporting and importing single accounts.
+pub fn router<O, S>(account_porter: O) -> Router<S>
+where
+    O: AccountPorter,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/accounts/:id/archive", get(export_account))
+        .route("/admin/account-imports", post(import_account))
+        .with_state(account_porter)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImportQuery {
+    /// The ID to import the account under, else derived from the original one.
+    account_id: Option<Uuid>,
+}
+
+async fn export_account<O>(State(account_porter): State<O>, Path(id): Path<Uuid>) -> Response
+where
+    O: AccountPorter,
+{
+    debug!(%id, "Endpoint GET /admin/accounts/:id/archive invoked");
+    match account_porter.export(id).await {
+        Ok(Some(archive)) => {
+            let content_disposition = format!("attachment; filename=\"account-{id}.archive.json\"");
+            ([(CONTENT_DISPOSITION, content_disposition)], Json(archive)).into_response()
+        }
+
+        Ok(None) => Problem::new(ErrorCode::NotFound).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot export account");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+/// Imports are idempotent: importing the same archive again responds with `200 OK`.
+async fn import_account<O>(
+    State(account_porter): State<O>,
+    Query(ImportQuery { account_id }): Query<ImportQuery>,
+    Json(archive): Json<AccountArchive>,
+) -> Response
+where
+    O: AccountPorter,
+{
+    debug!(original_account_id = %archive.account_id, "Endpoint POST /admin/account-imports invoked");
+    match account_porter.import(archive, account_id).await {
+        Ok(imported) => {
+            let status = if imported.duplicate {
+                StatusCode::OK
+            } else {
+                StatusCode::CREATED
+            };
+            let location_value =
+                HeaderValue::from_str(&format!("/accounts/{}", imported.account_id)).unwrap();
+            let mut location_value = iter::once(&location_value);
+            let location = Location::decode(&mut location_value).unwrap();
+            (status, TypedHeader(location), Json(imported)).into_response()
+        }
+
+        Err(error) => {
+            let code = ErrorCode::from(&error);
+            error!(%code, error = format!("{error:#}"), "Cannot import account");
+            Problem::new(code)
+                .with_detail(error.to_string())
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use super::i18n::{self, Localize};
+use crate::infra::error_code::ErrorCode;
+use axum::{
+    http::{
+        header::{CONTENT_TYPE, RETRY_AFTER},
+        HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use metrics::counter;
+use serde::Serialize;
+
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Seconds after which clients should retry requests rejected because of overload.
+const OVERLOADED_RETRY_AFTER_SECS: u64 = 1;
+
+/// Problem details as of RFC 7807, carrying a stable [ErrorCode]. Each problem response is counted
+/// by code and class.
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    type_: String,
+    title: &'static str,
+    status: u16,
+    code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl Problem {
+    /// Create a [Problem] for the given [ErrorCode] without detail.
+    pub fn new(code: ErrorCode) -> Self {
+        let status = status(code);
+        Self {
+            type_: format!("urn:rusty-bank:error:{code}"),
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            code,
+            detail: None,
+        }
+    }
+
+    /// Add the given detail, which must not contain sensitive data.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Domain errors are caused by the client, hence their message, localized for the language
+/// negotiated for the request, is returned as detail.
+impl<E> From<&E> for Problem
+where
+    E: Localize,
+    for<'a> &'a E: Into<ErrorCode>,
+{
+    fn from(error: &E) -> Self {
+        Problem::new(error.into()).with_detail(i18n::localize(error))
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        counter!(
+            "errors_total",
+            1,
+            "code" => self.code.as_str(),
+            "class" => self.code.class().as_str()
+        );
+
+        let code = self.code;
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (status, Json(self)).into_response();
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        if code == ErrorCode::Overloaded {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(OVERLOADED_RETRY_AFTER_SECS));
+        }
+        response
+    }
+}
+
+fn status(code: ErrorCode) -> StatusCode {
+    match code {
+        ErrorCode::InsufficientBalance
+        | ErrorCode::LimitExceeded
+        | ErrorCode::MaxBalanceExceeded
+        | ErrorCode::NotYetCreated
+        | ErrorCode::AlreadyCreated
+        | ErrorCode::UnknownReference
+        | ErrorCode::InvalidState
+        | ErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+        ErrorCode::ExternalRefInUse | ErrorCode::QuotaExceeded | ErrorCode::ParametersMismatch => {
+            StatusCode::CONFLICT
+        }
+        ErrorCode::NotFound => StatusCode::NOT_FOUND,
+        ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+        ErrorCode::Overloaded | ErrorCode::EntityUnavailable | ErrorCode::EntityTerminated => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::account, infra::server::AccountConflict};
+
+    #[test]
+    fn test_problem() {
+        let error = account::Error::UnknownHold(Default::default());
+        let response = Problem::from(&error).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_JSON);
+
+        let problem = serde_json::to_value(Problem::new(ErrorCode::EntityTerminated)).unwrap();
+        assert_eq!(problem["status"], 503);
+        assert_eq!(problem["code"], "entity-terminated");
+        assert_eq!(problem["type"], "urn:rusty-bank:error:entity-terminated");
+        assert!(problem.get("detail").is_none());
+
+        let response = Problem::new(ErrorCode::Overloaded).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[RETRY_AFTER], "1");
+
+        let problem = Problem::from(&AccountConflict::TenantQuotaExceeded(10));
+        let problem = serde_json::to_value(problem).unwrap();
+        assert_eq!(problem["status"], 409);
+        assert_eq!(problem["code"], "quota-exceeded");
+        assert_eq!(
+            problem["detail"],
+            "Maximum number of 10 accounts per tenant reached"
+        );
+    }
+}
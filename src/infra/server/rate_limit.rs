@@ -0,0 +1,217 @@
+use super::{authz, problem::Problem};
+use crate::infra::error_code::ErrorCode;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header::RETRY_AFTER, HeaderMap, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    num::{NonZeroU32, NonZeroU64},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+const RATE_LIMIT_LIMIT: &str = "ratelimit-limit";
+const RATE_LIMIT_REMAINING: &str = "ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "ratelimit-reset";
+
+/// Key for requests without an authenticated subject, which share one quota.
+const ANONYMOUS: &str = "anonymous";
+
+/// Configuration for the [RateLimiter].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Maximum number of requests per window and caller.
+    limit: NonZeroU32,
+
+    /// Length of the window.
+    window_secs: NonZeroU64,
+}
+
+/// Rate limiter with a fixed window per caller, i.e. per authenticated subject.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    config: Config,
+    windows: Arc<DashMap<String, Window>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            windows: Default::default(),
+        }
+    }
+
+    /// Count a request for the given caller and return the resulting [Quota].
+    fn acquire(&self, key: &str, now: Instant) -> Quota {
+        let mut window = self.windows.entry(key.to_string()).or_insert(Window {
+            start: now,
+            used: 0,
+        });
+        self.roll(&mut window, now);
+        let exceeded = window.used >= self.config.limit.get();
+        if !exceeded {
+            window.used += 1;
+        }
+        self.quota(&window, now, exceeded)
+    }
+
+    /// The current [Quota] of the given caller, without counting a request.
+    fn peek(&self, key: &str, now: Instant) -> Quota {
+        let mut window = self
+            .windows
+            .get(key)
+            .map(|window| *window)
+            .unwrap_or(Window {
+                start: now,
+                used: 0,
+            });
+        self.roll(&mut window, now);
+        self.quota(&window, now, false)
+    }
+
+    fn roll(&self, window: &mut Window, now: Instant) {
+        if now.duration_since(window.start) >= self.window() {
+            window.start = now;
+            window.used = 0;
+        }
+    }
+
+    fn quota(&self, window: &Window, now: Instant, exceeded: bool) -> Quota {
+        let reset = self
+            .window()
+            .saturating_sub(now.duration_since(window.start));
+        Quota {
+            limit: self.config.limit.get(),
+            remaining: self.config.limit.get().saturating_sub(window.used),
+            reset_secs: reset.as_secs() + u64::from(reset.subsec_nanos() > 0),
+            exceeded,
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_secs.get())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    start: Instant,
+    used: u32,
+}
+
+/// Quota of a caller for the current window.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Quota {
+    limit: u32,
+    remaining: u32,
+    reset_secs: u64,
+    #[serde(skip)]
+    exceeded: bool,
+}
+
+impl Quota {
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(self.remaining));
+        headers.insert(RATE_LIMIT_RESET, HeaderValue::from(self.reset_secs));
+        headers
+    }
+}
+
+/// Router for the quota endpoint, to be merged into the API routes before the policy and
+/// authorization layers.
+pub fn router<S>(rate_limiter: RateLimiter) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/quota", get(get_quota))
+        .with_state(rate_limiter)
+}
+
+/// Middleware rejecting requests exceeding the quota of their caller with 429 Too Many Requests
+/// and adding the rate limit headers to all responses, such that callers can throttle themselves.
+pub async fn limit(
+    State(rate_limiter): State<RateLimiter>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let key = key(&request);
+    let quota = rate_limiter.acquire(&key, Instant::now());
+
+    if quota.exceeded {
+        debug!(key, "Rate limit exceeded");
+        let mut response = Problem::new(ErrorCode::RateLimited).into_response();
+        response.headers_mut().extend(quota.headers());
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(quota.reset_secs));
+        response
+    } else {
+        let mut response = next.run(request).await;
+        response.headers_mut().extend(quota.headers());
+        response
+    }
+}
+
+async fn get_quota(State(rate_limiter): State<RateLimiter>, request: Request<Body>) -> Response {
+    debug!("Endpoint GET /quota invoked");
+
+    let quota = rate_limiter.peek(&key(&request), Instant::now());
+    (quota.headers(), Json(quota)).into_response()
+}
+
+fn key(request: &Request<Body>) -> String {
+    authz::subject(request)
+        .map(|subject| subject.0)
+        .unwrap_or_else(|| ANONYMOUS.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = RateLimiter::new(Config {
+            limit: NonZeroU32::new(2).unwrap(),
+            window_secs: NonZeroU64::new(60).unwrap(),
+        });
+        let now = Instant::now();
+
+        let quota = rate_limiter.acquire("partner", now);
+        assert!(!quota.exceeded);
+        assert_eq!(quota.remaining, 1);
+        assert_eq!(quota.reset_secs, 60);
+
+        let quota = rate_limiter.acquire("partner", now + Duration::from_secs(1));
+        assert!(!quota.exceeded);
+        assert_eq!(quota.remaining, 0);
+        assert_eq!(quota.reset_secs, 59);
+
+        let quota = rate_limiter.acquire("partner", now + Duration::from_secs(2));
+        assert!(quota.exceeded);
+        assert_eq!(quota.remaining, 0);
+
+        // Other callers have their own quota.
+        let quota = rate_limiter.peek("other", now + Duration::from_secs(2));
+        assert_eq!(quota.remaining, 2);
+
+        // The quota is reset after the window.
+        let quota = rate_limiter.acquire("partner", now + Duration::from_secs(60));
+        assert!(!quota.exceeded);
+        assert_eq!(quota.remaining, 1);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::infra::{ledger::LedgerProjection, receipt::Receipts};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Router for the receipt endpoints, to be merged into the account routes.
+pub fn router<G, S>(receipts: Receipts<G>) -> Router<S>
+where
+    G: LedgerProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(
+            "/accounts/:id/transactions/:transaction_id/receipt",
+            get(get_receipt),
+        )
+        .route("/receipts/verify/:code", get(verify_receipt))
+        .with_state(receipts)
+}
+
+async fn get_receipt<G>(
+    State(receipts): State<Receipts<G>>,
+    Path((id, transaction_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse
+where
+    G: LedgerProjection,
+{
+    debug!(%id, %transaction_id, "Endpoint GET /accounts/:id/transactions/:transaction_id/receipt invoked");
+
+    match receipts.receipt(id, transaction_id).await {
+        Some(receipt) => Json(receipt).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn verify_receipt<G>(
+    State(receipts): State<Receipts<G>>,
+    Path(code): Path<String>,
+) -> impl IntoResponse
+where
+    G: LedgerProjection,
+{
+    debug!(%code, "Endpoint GET /receipts/verify/:code invoked");
+
+    match receipts.verify(&code).await {
+        Some(receipt) => Json(receipt).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
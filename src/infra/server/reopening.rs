@@ -0,0 +1,79 @@
+use super::{problem::Problem, snapshot_headers};
+use crate::{
+    application::commands::{CommandError, Commands},
+    domain::account,
+    infra::{account::AccountFactory, error_code::ErrorCode},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// Router for the reopening endpoints, to be merged into the account routes. Closed accounts can
+/// be reopened within the given number of days after closing.
+pub fn router<F, S>(commands: Commands<F>, window_days: u16) -> Router<S>
+where
+    F: AccountFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/accounts/:id/reopening", post(reopen_account))
+        .with_state(ReopeningState {
+            commands,
+            window_days,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct ReopeningState<F> {
+    commands: Commands<F>,
+    window_days: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Reopen {
+    justification: String,
+}
+
+async fn reopen_account<F>(
+    State(ReopeningState {
+        commands,
+        window_days,
+    }): State<ReopeningState<F>>,
+    Path(account_id): Path<Uuid>,
+    Json(Reopen { justification }): Json<Reopen>,
+) -> Response
+where
+    F: AccountFactory,
+{
+    debug!(%account_id, "Endpoint POST /admin/accounts/:id/reopening invoked");
+
+    let cmd = account::Cmd::Reopen {
+        justification: justification.clone(),
+        window_days,
+    };
+    match commands.account(account_id, cmd).await {
+        Ok(snapshot) => {
+            info!(%account_id, %justification, "Reopened account");
+            (StatusCode::NO_CONTENT, snapshot_headers(&snapshot)).into_response()
+        }
+
+        Err(CommandError::Rejected(account::Error::NotYetCreated)) => {
+            Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Err(CommandError::Rejected(error)) => Problem::from(&error).into_response(),
+
+        Err(CommandError::Failed(error)) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot reopen account");
+            Problem::new(code).into_response()
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::infra::{
+    delivery::DeliveryStatus,
+    ledger::LedgerProjection,
+    reporting::{self, ReportRun, Reporter},
+};
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{header::CONTENT_TYPE, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router, TypedHeader,
+};
+use serde::Serialize;
+use std::{iter, sync::Arc};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Router for the reporting endpoints, to be merged into the account routes.
+pub fn router<G, S>(reporter: Reporter<G>) -> Router<S>
+where
+    G: LedgerProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/reports/:name/run", post(run_report))
+        .route("/admin/reports/runs", get(list_report_runs))
+        .route("/admin/reports/runs/:id", get(get_report_run))
+        .with_state(reporter)
+}
+
+async fn run_report<G>(State(reporter): State<Reporter<G>>, Path(name): Path<String>) -> Response
+where
+    G: LedgerProjection,
+{
+    debug!(%name, "Endpoint POST /admin/reports/:name/run invoked");
+
+    match reporter.run(&name).await {
+        Ok(run) => {
+            let location_value =
+                HeaderValue::from_str(&format!("/admin/reports/runs/{}", run.id)).unwrap();
+            let mut location_value = iter::once(&location_value);
+            let location = Location::decode(&mut location_value).unwrap();
+            (StatusCode::CREATED, TypedHeader(location), content(run)).into_response()
+        }
+
+        Err(error @ reporting::Error::UnknownReport(_)) => {
+            (StatusCode::NOT_FOUND, error.to_string()).into_response()
+        }
+    }
+}
+
+async fn list_report_runs<G>(State(reporter): State<Reporter<G>>) -> impl IntoResponse
+where
+    G: LedgerProjection,
+{
+    debug!("Endpoint GET /admin/reports/runs invoked");
+    let runs = reporter
+        .runs()
+        .iter()
+        .map(|run| ReportRunRepr {
+            run: run.as_ref().clone(),
+            delivery: reporter.delivery_status(run.id),
+        })
+        .collect::<Vec<_>>();
+    Json(runs)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReportRunRepr {
+    #[serde(flatten)]
+    run: ReportRun,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery: Option<DeliveryStatus>,
+}
+
+async fn get_report_run<G>(State(reporter): State<Reporter<G>>, Path(id): Path<Uuid>) -> Response
+where
+    G: LedgerProjection,
+{
+    debug!(%id, "Endpoint GET /admin/reports/runs/:id invoked");
+
+    match reporter.find_run(id) {
+        Some(run) => content(run).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn content(run: Arc<ReportRun>) -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, run.format.content_type())],
+        run.content.clone(),
+    )
+}
@@ -0,0 +1,22 @@
+use crate::infra::{account::AccountSummariesProjection, retention::Retention};
+use axum::{extract::State, response::IntoResponse, routing::get, Json, Router};
+use tracing::debug;
+
+/// Router for the retention endpoints, to be merged into the account routes.
+pub fn router<P, S>(retention: Retention<P>) -> Router<S>
+where
+    P: AccountSummariesProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/retention/pending", get(list_pending_purges))
+        .with_state(retention)
+}
+
+async fn list_pending_purges<P>(State(retention): State<Retention<P>>) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+{
+    debug!("Endpoint GET /admin/retention/pending invoked");
+    Json(retention.pending().await)
+}
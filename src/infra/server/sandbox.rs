@@ -0,0 +1,50 @@
+use crate::{
+    domain::id::IdGenerator,
+    infra::account::{sandbox::purge_sandbox, AccountFactory, AccountSummariesProjection},
+};
+use axum::{extract::State, response::IntoResponse, routing::post, Json, Router};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Router for the sandbox admin endpoints.
+pub fn router<P, F, S>(
+    account_summaries: P,
+    account_factory: F,
+    ids: Arc<dyn IdGenerator>,
+) -> Router<S>
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/sandbox/purge", post(purge))
+        .with_state(SandboxState {
+            account_summaries,
+            account_factory,
+            ids,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct SandboxState<P, F> {
+    account_summaries: P,
+    account_factory: F,
+    ids: Arc<dyn IdGenerator>,
+}
+
+async fn purge<P, F>(State(state): State<SandboxState<P, F>>) -> impl IntoResponse
+where
+    P: AccountSummariesProjection,
+    F: AccountFactory,
+{
+    debug!("Endpoint POST /admin/sandbox/purge invoked");
+    Json(
+        purge_sandbox(
+            &state.account_summaries,
+            &state.account_factory,
+            state.ids.as_ref(),
+        )
+        .await,
+    )
+}
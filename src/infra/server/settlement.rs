@@ -0,0 +1,31 @@
+use crate::infra::{
+    ledger::LedgerProjection,
+    settlement::{self, Settlement},
+};
+use axum::{
+    extract::State, http::header::CONTENT_TYPE, response::IntoResponse, routing::get, Router,
+};
+use tracing::debug;
+
+/// Router for the settlement endpoints, to be merged into the account routes.
+pub fn router<G, S>(settlement: Settlement<G>) -> Router<S>
+where
+    G: LedgerProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/settlements", get(export_settlements))
+        .with_state(settlement)
+}
+
+/// The settlement report, most recent settlements first.
+async fn export_settlements<G>(State(settlement): State<Settlement<G>>) -> impl IntoResponse
+where
+    G: LedgerProjection,
+{
+    debug!("Endpoint GET /admin/settlements invoked");
+    (
+        [(CONTENT_TYPE, "text/csv")],
+        settlement::render_csv(&settlement.settlements()),
+    )
+}
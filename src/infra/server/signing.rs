@@ -0,0 +1,314 @@
+use super::policy;
+use crate::domain::{clock::Clock, redaction::Secret};
+use axum::{
+    body::{Body, HttpBody},
+    extract::State,
+    http::{request::Parts, HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{fmt::Write, num::NonZeroU64, sync::Arc};
+use tracing::debug;
+
+const KEY_ID: &str = "x-key-id";
+const TIMESTAMP: &str = "x-timestamp";
+const NONCE: &str = "x-nonce";
+const SIGNATURE: &str = "x-signature";
+
+/// Number of remembered nonces above which expired ones are pruned.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Configuration for [Signatures].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Maximum difference between the timestamp of a request and the server time.
+    max_skew_secs: NonZeroU64,
+
+    /// Keys of the partners allowed to sign requests.
+    keys: Vec<PartnerKey>,
+}
+
+/// API key of a partner with the secret for signing requests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PartnerKey {
+    key_id: String,
+    secret: Secret,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// The partner which has signed the request, added to the request extensions.
+#[derive(Debug, Clone)]
+pub struct Partner {
+    pub key_id: String,
+    pub roles: Vec<String>,
+}
+
+/// Verifies request signatures of partners, i.e. an HMAC-SHA256 over method, path, timestamp,
+/// nonce and body, and rejects replayed requests, i.e. requests with a timestamp outside of the
+/// allowed skew or with an already used nonce.
+#[derive(Debug, Clone)]
+pub struct Signatures {
+    config: Arc<Config>,
+    clock: Arc<dyn Clock>,
+    nonces: Arc<DashMap<(String, String), i64>>,
+    fallback: bool,
+}
+
+impl Signatures {
+    /// Unsigned requests are only passed on, if `fallback` is `true`, i.e. if other authentication
+    /// modes follow; else they are rejected.
+    pub fn new(config: Config, clock: Arc<dyn Clock>, fallback: bool) -> Self {
+        Self {
+            config: Arc::new(config),
+            clock,
+            nonces: Default::default(),
+            fallback,
+        }
+    }
+
+    /// Verify the signature of the request with the given parts and body at the given time
+    /// (seconds since the Unix epoch), returning the [Partner] if valid.
+    fn verify(&self, parts: &Parts, body: &[u8], now: i64) -> Result<Partner, &'static str> {
+        let key_id = header(&parts.headers, KEY_ID)?;
+        let timestamp = header(&parts.headers, TIMESTAMP)?;
+        let nonce = header(&parts.headers, NONCE)?;
+        let signature =
+            decode_hex(header(&parts.headers, SIGNATURE)?).ok_or("Invalid signature")?;
+
+        let key = self
+            .config
+            .keys
+            .iter()
+            .find(|key| key.key_id == key_id)
+            .ok_or("Unknown key")?;
+
+        let max_skew = self.config.max_skew_secs.get() as i64;
+        let timestamp = timestamp.parse::<i64>().map_err(|_| "Invalid timestamp")?;
+        if (now - timestamp).abs() > max_skew {
+            return Err("Timestamp outside of allowed skew");
+        }
+
+        let path = parts
+            .uri
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or_else(|| parts.uri.path());
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.secret.expose().as_bytes())
+            .expect("HMAC takes keys of any size");
+        mac.update(
+            canonical_request(parts.method.as_str(), path, timestamp, nonce, body).as_bytes(),
+        );
+        mac.verify_slice(&signature)
+            .map_err(|_| "Invalid signature")?;
+
+        // Only valid signatures consume a nonce, such that nobody can burn the nonces of others.
+        if self.nonces.len() >= PRUNE_THRESHOLD {
+            self.nonces
+                .retain(|_, timestamp| (now - *timestamp).abs() <= max_skew);
+        }
+        let nonce_key = (key.key_id.clone(), nonce.to_string());
+        if self.nonces.insert(nonce_key, timestamp).is_some() {
+            return Err("Replayed nonce");
+        }
+
+        Ok(Partner {
+            key_id: key.key_id.clone(),
+            roles: key.roles.clone(),
+        })
+    }
+}
+
+/// Middleware verifying signed requests, i.e. requests with a signature header, and adding the
+/// [Partner] to the request extensions. Unsigned requests are passed on to other authentication
+/// modes, if any, else rejected with `401 Unauthorized`.
+pub async fn verify(
+    State(signatures): State<Signatures>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if !request.headers().contains_key(SIGNATURE) {
+        if signatures.fallback {
+            return next.run(request).await;
+        }
+        debug!("Unsigned request without other authentication modes");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    // The body has to be buffered for verification, hence its size has to be checked against the
+    // policy of the route already before authentication.
+    let Some(policy) = policy::of(&request) else {
+        return StatusCode::FORBIDDEN.into_response();
+    };
+    if let Some(status) = policy::check_body_size(request.body().size_hint().upper(), policy) {
+        return status.into_response();
+    }
+    let (mut parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(error) => {
+            debug!(%error, "Cannot read body of signed request");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    let now = signatures.clock.now().unix_timestamp();
+    match signatures.verify(&parts, &body, now) {
+        Ok(partner) => {
+            debug!(key_id = partner.key_id, "Authenticated signed request");
+            parts.extensions.insert(partner);
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+
+        Err(error) => {
+            debug!(error, "Invalid signed request");
+            StatusCode::UNAUTHORIZED.into_response()
+        }
+    }
+}
+
+/// The string to be signed: method, path with query, timestamp, nonce and the hex encoded
+/// SHA-256 of the body, separated by newlines.
+fn canonical_request(method: &str, path: &str, timestamp: i64, nonce: &str, body: &[u8]) -> String {
+    format!(
+        "{method}\n{path}\n{timestamp}\n{nonce}\n{}",
+        encode_hex(&Sha256::digest(body))
+    )
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, &'static str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or("Missing signature header")
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|n| u8::from_str_radix(hex.get(n..n + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::clock::ManualClock;
+    use axum::{middleware, routing::get, Router};
+    use time::OffsetDateTime;
+    use tower::ServiceExt;
+
+    fn sign(
+        secret: &str,
+        method: &str,
+        path: &str,
+        timestamp: i64,
+        nonce: &str,
+        body: &[u8],
+    ) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(canonical_request(method, path, timestamp, nonce, body).as_bytes());
+        encode_hex(&mac.finalize().into_bytes())
+    }
+
+    fn parts(timestamp: i64, nonce: &str, signature: &str) -> Parts {
+        let (parts, _) = Request::post("/accounts/42/deposits?dry-run")
+            .header(KEY_ID, "partner")
+            .header(TIMESTAMP, timestamp.to_string())
+            .header(NONCE, nonce)
+            .header(SIGNATURE, signature)
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts
+    }
+
+    fn signatures(fallback: bool) -> Signatures {
+        Signatures::new(
+            Config {
+                max_skew_secs: NonZeroU64::new(300).unwrap(),
+                keys: vec![PartnerKey {
+                    key_id: "partner".to_string(),
+                    secret: Secret::from("secret".to_string()),
+                    roles: vec!["accounts:write".to_string()],
+                }],
+            },
+            Arc::new(ManualClock::new(OffsetDateTime::UNIX_EPOCH)),
+            fallback,
+        )
+    }
+
+    #[test]
+    fn test_verify() {
+        let signatures = signatures(false);
+        let body = br#"{"amount":42}"#;
+        let now = 1_700_000_000;
+
+        let signature = sign(
+            "secret",
+            "POST",
+            "/accounts/42/deposits?dry-run",
+            now,
+            "n1",
+            body,
+        );
+        let partner = signatures.verify(&parts(now, "n1", &signature), body, now);
+        assert!(partner.is_ok_and(|partner| partner.roles == ["accounts:write"]));
+
+        // Replayed nonce.
+        let result = signatures.verify(&parts(now, "n1", &signature), body, now);
+        assert_eq!(result.unwrap_err(), "Replayed nonce");
+
+        // Tampered body.
+        let signature = sign(
+            "secret",
+            "POST",
+            "/accounts/42/deposits?dry-run",
+            now,
+            "n2",
+            body,
+        );
+        let result = signatures.verify(&parts(now, "n2", &signature), br#"{"amount":4200}"#, now);
+        assert_eq!(result.unwrap_err(), "Invalid signature");
+
+        // Timestamp too old.
+        let signature = sign(
+            "secret",
+            "POST",
+            "/accounts/42/deposits?dry-run",
+            now - 301,
+            "n3",
+            body,
+        );
+        let result = signatures.verify(&parts(now - 301, "n3", &signature), body, now);
+        assert_eq!(result.unwrap_err(), "Timestamp outside of allowed skew");
+    }
+
+    #[tokio::test]
+    async fn test_verify_unsigned() {
+        for (fallback, status) in [(false, StatusCode::UNAUTHORIZED), (true, StatusCode::OK)] {
+            let app = Router::new()
+                .route("/accounts/:id", get(|| async { "account" }))
+                .route_layer(middleware::from_fn_with_state(signatures(fallback), verify));
+            let request = Request::get("/accounts/42").body(Body::empty()).unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), status);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use super::mode;
+use crate::infra::standby::Standby;
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+/// Router for the standby endpoints, i.e. the replication status and promotion.
+pub fn router<S>(standby: Standby) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/admin/standby", get(get_status))
+        .route("/admin/standby/promotion", post(promote))
+        .with_state(standby)
+}
+
+/// Middleware rejecting mutating requests with `503 Service Unavailable` until the standby has
+/// been promoted, such that clients retry against the active region.
+pub async fn reject_mutations(
+    State(standby): State<Standby>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if standby.is_promoted() || !mode::is_mutation(&request) {
+        return next.run(request).await;
+    }
+
+    debug!(method = %request.method(), path = request.uri().path(), "Rejecting mutation");
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Standby instance, not yet promoted",
+    )
+        .into_response()
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PromoteQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+async fn get_status(State(standby): State<Standby>) -> impl IntoResponse {
+    debug!("Endpoint GET /admin/standby invoked");
+    Json(standby.status())
+}
+
+async fn promote(
+    State(standby): State<Standby>,
+    Query(PromoteQuery { force }): Query<PromoteQuery>,
+) -> Response {
+    debug!(force, "Endpoint POST /admin/standby/promotion invoked");
+    match standby.promote(force) {
+        Ok(status) => Json(status).into_response(),
+        Err(error) => {
+            warn!(%error, "Cannot promote standby");
+            (StatusCode::CONFLICT, error.to_string()).into_response()
+        }
+    }
+}
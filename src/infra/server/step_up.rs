@@ -0,0 +1,148 @@
+use super::{problem::Problem, snapshot_headers, TransactionKind, TransactionRepr};
+use crate::{
+    domain::{account, euro_cent::EuroCent},
+    infra::{
+        account::AccountFactory,
+        error_code::ErrorCode,
+        step_up::{self, PendingConfirmation, StepUp, StepUpReason},
+    },
+};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router, TypedHeader,
+};
+use std::iter;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the step-up authentication endpoints, i.e. the confirmation callback.
+pub fn router<F, S>(step_up: StepUp, account_factory: F) -> Router<S>
+where
+    F: AccountFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/confirmations/:id", post(confirm))
+        .with_state(StepUpState {
+            step_up,
+            account_factory,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct StepUpState<F> {
+    step_up: StepUp,
+    account_factory: F,
+}
+
+/// Park the given withdrawal pending confirmation and respond with `202 Accepted`, pointing to the
+/// confirmation callback.
+pub fn park_withdrawal(
+    step_up: &StepUp,
+    account_id: Uuid,
+    withdrawal_id: Uuid,
+    amount: EuroCent,
+    cmd: account::Cmd,
+    reason: StepUpReason,
+) -> Response {
+    match step_up.park(account_id, withdrawal_id, amount, cmd, reason) {
+        Ok(confirmation) => (
+            StatusCode::ACCEPTED,
+            TypedHeader(location(&format!("/confirmations/{}", confirmation.id))),
+            Json(confirmation),
+        )
+            .into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot park withdrawal");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn confirm<F>(
+    State(StepUpState {
+        step_up,
+        account_factory,
+    }): State<StepUpState<F>>,
+    Path(id): Path<Uuid>,
+) -> Response
+where
+    F: AccountFactory,
+{
+    debug!(%id, "Endpoint POST /confirmations/:id invoked");
+
+    let PendingConfirmation {
+        account_id,
+        transaction_id,
+        amount,
+        cmd,
+        ..
+    } = match step_up.confirm(id) {
+        Ok(confirmation) => confirmation,
+        Err(error @ step_up::Error::Unknown(_)) => {
+            return Problem::new(ErrorCode::NotFound)
+                .with_detail(error.to_string())
+                .into_response()
+        }
+        Err(error @ step_up::Error::Expired(_)) => {
+            return Problem::new(ErrorCode::InvalidState)
+                .with_detail(error.to_string())
+                .into_response()
+        }
+    };
+
+    let device_id = match &cmd {
+        account::Cmd::Withdraw(_, _, _, Some(channel)) => channel.device_id.clone(),
+        _ => None,
+    };
+    let result = async {
+        account_factory
+            .get(account_id)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Withdraw command")
+    }
+    .await;
+    match result {
+        Ok(Ok(snapshot)) => {
+            step_up.record(account_id, amount, device_id);
+            let withdrawal = TransactionRepr::new(
+                transaction_id,
+                account_id,
+                TransactionKind::Withdrawal,
+                amount,
+                &snapshot,
+            );
+            (
+                StatusCode::CREATED,
+                TypedHeader(location(&format!("/accounts/{account_id}/transactions"))),
+                snapshot_headers(&snapshot),
+                Json(withdrawal),
+            )
+                .into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot confirm withdrawal");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+fn location(value: &str) -> Location {
+    let value = HeaderValue::from_str(value).unwrap();
+    let mut value = iter::once(&value);
+    Location::decode(&mut value).unwrap()
+}
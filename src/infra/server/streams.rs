@@ -0,0 +1,186 @@
+use crate::domain::{
+    account::{self, ACCOUNT_LIFECYCLE_TAG},
+    tenant::TenantId,
+};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Router,
+};
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use std::convert::Infallible;
+use time::OffsetDateTime;
+use tokio::{sync::mpsc, task};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// Number of events buffered per subscriber.
+const BUFFER: usize = 64;
+
+/// Router for the event streams for internal consumers.
+pub fn router<L, S>(evt_log: L) -> Router<S>
+where
+    L: EvtLog,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/streams/account-lifecycle", get(stream_account_lifecycle))
+        .with_state(evt_log)
+}
+
+/// Data of an account lifecycle event, without any personal data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct LifecycleEvt {
+    account_id: Uuid,
+    /// Only for created accounts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant: Option<TenantId>,
+    #[serde(with = "time::serde::rfc3339")]
+    at: OffsetDateTime,
+}
+
+/// Stream the lifecycle events of all accounts, i.e. created, closed, reopened and purged, as
+/// server-sent events with the sequence number as ID, such that clients can resume after the last
+/// received event via the `Last-Event-ID` header. If the event log fails, the stream ends and
+/// clients are expected to reconnect.
+async fn stream_account_lifecycle<L>(State(evt_log): State<L>, headers: HeaderMap) -> Response
+where
+    L: EvtLog,
+{
+    let from_seq_no = headers
+        .get(LAST_EVENT_ID)
+        .and_then(|last_event_id| last_event_id.to_str().ok())
+        .and_then(|last_event_id| last_event_id.parse::<u64>().ok())
+        .and_then(|last_event_id| SeqNo::try_from(last_event_id).ok())
+        .map(|seq_no| seq_no.succ())
+        .unwrap_or(SeqNo::MIN);
+    debug!(%from_seq_no, "Endpoint GET /streams/account-lifecycle invoked");
+
+    // The events-by-tag stream borrows the event log, hence it is forwarded by a task owning the
+    // event log, which completes once the client has gone.
+    let (event_sdr, event_rcv) = mpsc::channel(BUFFER);
+    task::spawn(forward(evt_log, from_seq_no, event_sdr));
+
+    let events = stream::unfold(event_rcv, |mut event_rcv| async move {
+        event_rcv
+            .recv()
+            .await
+            .map(|event| (Ok::<_, Infallible>(event), event_rcv))
+    });
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn forward<L>(evt_log: L, from_seq_no: SeqNo, event_sdr: mpsc::Sender<Event>)
+where
+    L: EvtLog,
+{
+    let evts = match evt_log
+        .evts_by_tag::<Bytes, _, _, _>(ACCOUNT_LIFECYCLE_TAG, from_seq_no, raw)
+        .await
+    {
+        Ok(evts) => evts,
+        Err(error) => {
+            error!(
+                error = format!("{error:#}"),
+                "Cannot create events-by-tag query"
+            );
+            return;
+        }
+    };
+
+    let mut evts = Box::pin(evts);
+    while let Some(evt) = evts.next().await {
+        let (seq_no, evt) = match evt {
+            Ok(evt) => evt,
+            Err(error) => {
+                error!(error = format!("{error:#}"), "Cannot get next event");
+                return;
+            }
+        };
+        let Ok(evt) = serde_json::from_slice::<account::Evt>(&evt) else {
+            warn!(%seq_no, "Cannot deserialize account event");
+            continue;
+        };
+        let Some((name, lifecycle_evt)) = lifecycle_evt(evt) else {
+            continue;
+        };
+
+        let event = Event::default()
+            .id(seq_no.to_string())
+            .event(name)
+            .json_data(lifecycle_evt)
+            .expect("lifecycle event can be serialized");
+        if event_sdr.send(event).await.is_err() {
+            debug!("Account lifecycle stream closed by client");
+            return;
+        }
+    }
+}
+
+/// The name and data of the given event, if streamed.
+fn lifecycle_evt(evt: account::Evt) -> Option<(&'static str, LifecycleEvt)> {
+    let account_id = evt.account_id();
+    let at = evt.at();
+    let (name, tenant) = match evt {
+        account::Evt::Created { tenant, .. } => ("created", Some(tenant)),
+        account::Evt::Closed { .. } => ("closed", None),
+        account::Evt::Reopened { .. } => ("reopened", None),
+        account::Evt::Purged { .. } => ("purged", None),
+        _ => return None,
+    };
+    Some((
+        name,
+        LifecycleEvt {
+            account_id,
+            tenant,
+            at,
+        },
+    ))
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_evt() {
+        let account_id = Uuid::now_v7();
+        let at = OffsetDateTime::UNIX_EPOCH;
+
+        let evt = account::Evt::Purged { account_id, at };
+        assert_eq!(
+            lifecycle_evt(evt),
+            Some((
+                "purged",
+                LifecycleEvt {
+                    account_id,
+                    tenant: None,
+                    at
+                }
+            ))
+        );
+
+        let evt = account::Evt::Reopened {
+            account_id,
+            justification: "customer request".to_string(),
+            at,
+        };
+        assert_eq!(lifecycle_evt(evt).map(|(name, _)| name), Some("reopened"));
+    }
+}
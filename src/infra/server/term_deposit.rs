@@ -0,0 +1,220 @@
+use super::problem::Problem;
+use crate::{
+    domain::{account, euro_cent::EuroCent, id::IdGenerator, term_deposit},
+    infra::{account::AccountFactory, error_code::ErrorCode, term_deposit::TermDepositFactory},
+};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    headers::{Header, Location},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router, TypedHeader,
+};
+use serde::{Deserialize, Serialize};
+use std::{iter, sync::Arc};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the term deposit endpoints, to be merged into the account routes.
+pub fn router<F, T, S>(
+    account_factory: F,
+    term_deposit_factory: T,
+    ids: Arc<dyn IdGenerator>,
+) -> Router<S>
+where
+    F: AccountFactory,
+    T: TermDepositFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/term-deposits", post(open_term_deposit))
+        .route(
+            "/term-deposits/:id/early-withdrawal",
+            post(withdraw_term_deposit_early),
+        )
+        .with_state(TermDepositState {
+            account_factory,
+            term_deposit_factory,
+            ids,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct TermDepositState<F, T> {
+    account_factory: F,
+    term_deposit_factory: T,
+    ids: Arc<dyn IdGenerator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OpenTermDeposit {
+    amount: EuroCent,
+    rate_bps: u32,
+    term_days: u16,
+}
+
+/// Representation of a term deposit.
+#[derive(Debug, Clone, Serialize)]
+struct TermDepositRepr {
+    id: Uuid,
+    account_id: Uuid,
+    amount: EuroCent,
+    rate_bps: u32,
+    term_days: u16,
+}
+
+/// The amount is withdrawn from the account before opening the term deposit and deposited back if
+/// opening fails.
+async fn open_term_deposit<F, T>(
+    State(term_deposit_state): State<TermDepositState<F, T>>,
+    Path(account_id): Path<Uuid>,
+    Json(OpenTermDeposit {
+        amount,
+        rate_bps,
+        term_days,
+    }): Json<OpenTermDeposit>,
+) -> Response
+where
+    F: AccountFactory,
+    T: TermDepositFactory,
+{
+    debug!(%account_id, "Endpoint POST /accounts/:id/term-deposits invoked");
+
+    let id = term_deposit_state.ids.next_id();
+    let account = match term_deposit_state
+        .account_factory
+        .get(account_id)
+        .await
+        .context("Cannot get Account entity")
+    {
+        Ok(account) => account,
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot open term deposit");
+            return Problem::new(code).into_response();
+        }
+    };
+
+    // Move the amount from the account.
+    match account
+        .handle_cmd(account::Cmd::Withdraw(id, amount, None, None))
+        .await
+        .context("Cannot handle Withdraw command")
+    {
+        Ok(Ok(_)) => {}
+
+        Ok(Err(account::Error::NotYetCreated)) => {
+            return Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Ok(Err(error)) => return Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %account_id, error = format!("{error:#}"), "Cannot open term deposit");
+            return Problem::new(code).into_response();
+        }
+    }
+
+    let cmd = term_deposit::Cmd::Open {
+        id,
+        account_id,
+        amount,
+        rate_bps,
+        term_days,
+    };
+    let result = async {
+        term_deposit_state
+            .term_deposit_factory
+            .get(id)
+            .await
+            .context("Cannot get TermDeposit entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Open command")
+    }
+    .await;
+
+    if !matches!(result, Ok(Ok(()))) {
+        let compensated = account
+            .handle_cmd(account::Cmd::Deposit(
+                term_deposit_state.ids.next_id(),
+                amount,
+                None,
+                None,
+            ))
+            .await;
+        if !matches!(compensated, Ok(Ok(_))) {
+            error!(%id, %account_id, %amount, "Cannot deposit back amount for term deposit");
+        }
+    }
+
+    match result {
+        Ok(Ok(())) => {
+            let location_value = HeaderValue::from_str(&format!("/term-deposits/{id}")).unwrap();
+            let mut location_value = iter::once(&location_value);
+            let location = Location::decode(&mut location_value).unwrap();
+            (
+                StatusCode::CREATED,
+                TypedHeader(location),
+                Json(TermDepositRepr {
+                    id,
+                    account_id,
+                    amount,
+                    rate_bps,
+                    term_days,
+                }),
+            )
+                .into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot open term deposit");
+            Problem::new(code).into_response()
+        }
+    }
+}
+
+async fn withdraw_term_deposit_early<F, T>(
+    State(term_deposit_state): State<TermDepositState<F, T>>,
+    Path(id): Path<Uuid>,
+) -> Response
+where
+    F: AccountFactory,
+    T: TermDepositFactory,
+{
+    debug!(%id, "Endpoint POST /term-deposits/:id/early-withdrawal invoked");
+
+    let result = async {
+        term_deposit_state
+            .term_deposit_factory
+            .get(id)
+            .await
+            .context("Cannot get TermDeposit entity")?
+            .handle_cmd(term_deposit::Cmd::WithdrawEarly)
+            .await
+            .context("Cannot handle WithdrawEarly command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => StatusCode::NO_CONTENT.into_response(),
+
+        Ok(Err(term_deposit::Error::NotYetOpened)) => {
+            Problem::new(ErrorCode::NotFound).into_response()
+        }
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot withdraw term deposit early");
+            Problem::new(code).into_response()
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::problem::Problem;
+use crate::{
+    application::queries::{PageRequest, PeriodFilter, Queries},
+    infra::{
+        error_code::ErrorCode, ledger::LedgerProjection, transactions::TransactionsProjection,
+        treasury::PositionsProjection,
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Router for the transaction history endpoints, to be merged into the account routes.
+pub fn router<Q, G, R, S>(queries: Queries<Q, G, R>) -> Router<S>
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/transactions", get(list_transactions))
+        .with_state(queries)
+}
+
+async fn list_transactions<Q, G, R>(
+    State(queries): State<Queries<Q, G, R>>,
+    Path(id): Path<Uuid>,
+    Query(filter): Query<PeriodFilter>,
+    Query(page): Query<PageRequest>,
+) -> Response
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+{
+    debug!(%id, ?filter, ?page, "Endpoint GET /accounts/:id/transactions invoked");
+
+    match queries.transactions(id, filter, page).await {
+        Ok(Some(page)) => Json(page).into_response(),
+        Ok(None) => Problem::new(ErrorCode::NotFound).into_response(),
+        Err(error) => Problem::new(ErrorCode::from(&error))
+            .with_detail(error.to_string())
+            .into_response(),
+    }
+}
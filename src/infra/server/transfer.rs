@@ -0,0 +1,113 @@
+use super::problem::Problem;
+use crate::{
+    domain::{
+        euro_cent::EuroCent,
+        id::IdGenerator,
+        transfer::{self, TransferStatus},
+    },
+    infra::{error_code::ErrorCode, transfer::TransferFactory},
+};
+use anyhow::Context;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// Router for the transfer endpoints, to be merged into the account routes.
+pub fn router<T, S>(transfer_factory: T, ids: Arc<dyn IdGenerator>) -> Router<S>
+where
+    T: TransferFactory,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:id/transfers", post(initiate_transfer))
+        .with_state(TransferState {
+            transfer_factory,
+            ids,
+        })
+}
+
+#[derive(Debug, Clone)]
+struct TransferState<T> {
+    transfer_factory: T,
+    ids: Arc<dyn IdGenerator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct InitiateTransfer {
+    to_account: Uuid,
+    amount: EuroCent,
+}
+
+/// Representation of a transfer.
+#[derive(Debug, Clone, Serialize)]
+struct TransferRepr {
+    id: Uuid,
+    from_account: Uuid,
+    to_account: Uuid,
+    amount: EuroCent,
+    status: TransferStatus,
+}
+
+/// The transfer is only initiated and then executed asynchronously by the process manager, hence
+/// `202 Accepted`.
+async fn initiate_transfer<T>(
+    State(TransferState {
+        transfer_factory,
+        ids,
+    }): State<TransferState<T>>,
+    Path(from_account): Path<Uuid>,
+    Json(InitiateTransfer { to_account, amount }): Json<InitiateTransfer>,
+) -> Response
+where
+    T: TransferFactory,
+{
+    debug!(%from_account, %to_account, "Endpoint POST /accounts/:id/transfers invoked");
+
+    let id = ids.next_id();
+    let cmd = transfer::Cmd::Initiate {
+        id,
+        from_account,
+        to_account,
+        amount,
+    };
+    let result = async {
+        transfer_factory
+            .get(id)
+            .await
+            .context("Cannot get Transfer entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Initiate command")
+    }
+    .await;
+
+    match result {
+        Ok(Ok(_)) => (
+            StatusCode::ACCEPTED,
+            Json(TransferRepr {
+                id,
+                from_account,
+                to_account,
+                amount,
+                status: TransferStatus::Initiated,
+            }),
+        )
+            .into_response(),
+
+        Ok(Err(error)) => Problem::from(&error).into_response(),
+
+        Err(error) => {
+            let code = ErrorCode::of(&error);
+            error!(%code, %id, error = format!("{error:#}"), "Cannot initiate transfer");
+            Problem::new(code).into_response()
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{
+    application::queries::Queries,
+    infra::{
+        ledger::LedgerProjection, transactions::TransactionsProjection,
+        treasury::PositionsProjection,
+    },
+};
+use axum::{extract::State, response::IntoResponse, routing::get, Json, Router};
+use tracing::debug;
+
+/// Router for the treasury endpoints, to be merged into the account routes.
+pub fn router<Q, G, R, S>(queries: Queries<Q, G, R>) -> Router<S>
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/treasury/positions", get(get_positions))
+        .with_state(queries)
+}
+
+async fn get_positions<Q, G, R>(State(queries): State<Queries<Q, G, R>>) -> impl IntoResponse
+where
+    Q: PositionsProjection,
+    G: LedgerProjection,
+    R: TransactionsProjection,
+{
+    debug!("Endpoint GET /treasury/positions invoked");
+    Json(queries.positions().await)
+}
@@ -0,0 +1,455 @@
+use crate::{
+    domain::{
+        account::{self, Product, ACCOUNT_LIFECYCLE_TAG},
+        clock::Clock,
+        euro_cent::EuroCent,
+    },
+    infra::{
+        account::AccountFactory,
+        leader::Leadership,
+        ledger::{EntryKind, LedgerEntry, LedgerProjection},
+        lru_cache_factory::Priority,
+        reporting::{decimal, ReportPeriod},
+    },
+};
+use anyhow::{anyhow, Context, Result};
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::StreamExt;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    fmt::Write,
+    num::{NonZeroU64, NonZeroUsize},
+    sync::Arc,
+};
+use time::{Duration, OffsetDateTime};
+use tokio::{task, time::interval};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Configuration for [Settlement].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Interval for checking whether the last day has been settled.
+    interval_secs: NonZeroU64,
+
+    /// Time after midnight (UTC) to wait before settling the last day, such that its entries have
+    /// been projected to the ledger.
+    grace_secs: u64,
+
+    /// Maximum number of settlements kept for the report.
+    history_size: NonZeroUsize,
+}
+
+/// End-of-day settlement of merchant accounts: the net of the credits and debits of each day,
+/// as projected to the ledger, is transferred to the settlement account of the merchant. Days are
+/// settled at most once per merchant account, because the settlement transfer has a
+/// deterministic ID. Only the leader settles.
+#[derive(Debug, Clone)]
+pub struct Settlement<G> {
+    config: Arc<Config>,
+    clock: Arc<dyn Clock>,
+    ledger_projection: G,
+    /// Open merchant accounts with their settlement accounts.
+    merchants: Arc<RwLock<HashMap<Uuid, Uuid>>>,
+    settlements: Arc<RwLock<VecDeque<MerchantSettlement>>>,
+}
+
+/// The settlement of one day of a merchant account.
+#[derive(Debug, Clone, Serialize)]
+pub struct MerchantSettlement {
+    /// ID of the settlement transfer.
+    pub id: Uuid,
+    pub merchant_account: Uuid,
+    pub settlement_account: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    pub from: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    pub credits: EuroCent,
+    pub debits: EuroCent,
+    /// Credits minus debits, zero if the debits exceed the credits.
+    pub net: EuroCent,
+    pub status: SettlementStatus,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SettlementStatus {
+    Settled,
+    NothingToSettle,
+    /// The net has not been transferred and is settled with the next day, unless the merchant
+    /// account has been debited but the settlement account could not be credited, which requires
+    /// a manual booking.
+    Failed,
+}
+
+impl SettlementStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettlementStatus::Settled => "settled",
+            SettlementStatus::NothingToSettle => "nothing-to-settle",
+            SettlementStatus::Failed => "failed",
+        }
+    }
+}
+
+impl<G> Settlement<G>
+where
+    G: LedgerProjection,
+{
+    /// Create a [Settlement], spawn tracking the merchant accounts and spawn settling, on the
+    /// leader only.
+    pub fn spawn<L, F>(
+        config: Config,
+        clock: Arc<dyn Clock>,
+        evt_log: L,
+        ledger_projection: G,
+        account_factory: F,
+        leadership: Leadership,
+    ) -> Self
+    where
+        L: EvtLog,
+        F: AccountFactory,
+    {
+        let settlement = Self {
+            config: Arc::new(config),
+            clock,
+            ledger_projection,
+            merchants: Default::default(),
+            settlements: Default::default(),
+        };
+
+        let merchants = settlement.merchants.clone();
+        task::spawn(async move {
+            if let Err(error) = track_merchants(evt_log, merchants).await {
+                error!(
+                    error = format!("{error:#}"),
+                    "Cannot track merchant accounts"
+                );
+            }
+        });
+
+        let settler = settlement.clone();
+        task::spawn(async move {
+            let mut interval = interval(std::time::Duration::from_secs(
+                settler.config.interval_secs.get(),
+            ));
+            // The first tick completes immediately, i.e. before the merchants have been tracked.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if leadership.is_leader() {
+                    settler.settle(&account_factory).await;
+                }
+            }
+        });
+
+        settlement
+    }
+
+    /// The settlements, most recent first.
+    pub fn settlements(&self) -> Vec<MerchantSettlement> {
+        self.settlements.read().iter().cloned().collect()
+    }
+
+    /// Settle the last completed day for all merchant accounts not yet settled for it.
+    async fn settle<F>(&self, account_factory: &F)
+    where
+        F: AccountFactory,
+    {
+        let now = self.clock.now();
+        let grace = Duration::seconds(self.config.grace_secs as i64);
+        let (from, to) = ReportPeriod::Day.last_completed(now - grace);
+
+        let merchants = self
+            .merchants
+            .read()
+            .iter()
+            .map(|(id, settlement_account)| (*id, *settlement_account))
+            .collect::<Vec<_>>();
+        if merchants.is_empty() {
+            return;
+        }
+
+        let mut entries = HashMap::<Uuid, Vec<LedgerEntry>>::new();
+        for entry in self.ledger_projection.entries(from, to).await {
+            entries.entry(entry.account_id).or_default().push(entry);
+        }
+
+        for (merchant_account, settlement_account) in merchants {
+            let id = settlement_id(merchant_account, from);
+            if self.is_settled(id).await {
+                continue;
+            }
+
+            // The settlement of the previous day is booked during this day, but not part of it.
+            let previous_id = settlement_id(merchant_account, from - Duration::days(1));
+            let (credits, debits) = entries
+                .get(&merchant_account)
+                .into_iter()
+                .flatten()
+                .filter(|entry| entry.id != previous_id && entry.kind != EntryKind::OpeningBalance)
+                .fold(
+                    (EuroCent::default(), EuroCent::default()),
+                    |(credits, debits), entry| {
+                        if entry.kind.is_debit() {
+                            (credits, debits + entry.amount)
+                        } else {
+                            (credits + entry.amount, debits)
+                        }
+                    },
+                );
+            let net = if credits > debits {
+                credits - debits
+            } else {
+                EuroCent::default()
+            };
+
+            let status = if net == EuroCent::default() {
+                SettlementStatus::NothingToSettle
+            } else {
+                match transfer(
+                    account_factory,
+                    id,
+                    merchant_account,
+                    settlement_account,
+                    net,
+                )
+                .await
+                {
+                    Ok(()) => {
+                        info!(%merchant_account, %net, "Settled merchant account");
+                        SettlementStatus::Settled
+                    }
+
+                    Err(error) => {
+                        error!(
+                            %merchant_account,
+                            error = format!("{error:#}"),
+                            "Cannot settle merchant account"
+                        );
+                        SettlementStatus::Failed
+                    }
+                }
+            };
+
+            let mut settlements = self.settlements.write();
+            settlements.push_front(MerchantSettlement {
+                id,
+                merchant_account,
+                settlement_account,
+                from,
+                to,
+                credits,
+                debits,
+                net,
+                status,
+                at: now,
+            });
+            settlements.truncate(self.config.history_size.get());
+        }
+    }
+
+    /// Whether the settlement with the given ID has been attempted by this instance or booked,
+    /// e.g. by a former leader.
+    async fn is_settled(&self, id: Uuid) -> bool {
+        let attempted = self.settlements.read().iter().any(|s| s.id == id);
+        attempted || self.ledger_projection.entry(id).await.is_some()
+    }
+}
+
+/// The settlements as CSV, e.g. for exporting them as report.
+pub fn render_csv(settlements: &[MerchantSettlement]) -> String {
+    let mut csv =
+        "date,merchant_account,settlement_account,credits,debits,net,status,id\n".to_string();
+    for settlement in settlements {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{}",
+            settlement.from.date(),
+            settlement.merchant_account,
+            settlement.settlement_account,
+            decimal(settlement.credits),
+            decimal(settlement.debits),
+            decimal(settlement.net),
+            settlement.status.as_str(),
+            settlement.id
+        );
+    }
+    csv
+}
+
+/// Track the open merchant accounts via the lifecycle events of all accounts.
+async fn track_merchants<L>(evt_log: L, merchants: Arc<RwLock<HashMap<Uuid, Uuid>>>) -> Result<()>
+where
+    L: EvtLog,
+{
+    let evts = evt_log
+        .evts_by_tag::<Bytes, _, _, _>(ACCOUNT_LIFECYCLE_TAG, SeqNo::MIN, raw)
+        .await
+        .context("Cannot create events-by-tag query")?;
+
+    // Settlement accounts of closed merchant accounts, in case they are reopened.
+    let mut closed = HashMap::new();
+    let mut evts = Box::pin(evts);
+    while let Some(evt) = evts.next().await {
+        let (_, evt) = evt.context("Cannot get next event")?;
+        let Ok(evt) = serde_json::from_slice::<account::Evt>(&evt) else {
+            warn!("Cannot deserialize account event");
+            continue;
+        };
+
+        match evt {
+            account::Evt::Created {
+                id,
+                product: Product::Merchant { settlement_account },
+                ..
+            } => {
+                debug!(%id, "Tracking merchant account");
+                merchants.write().insert(id, settlement_account);
+            }
+
+            account::Evt::Closed { account_id, .. } => {
+                if let Some(settlement_account) = merchants.write().remove(&account_id) {
+                    closed.insert(account_id, settlement_account);
+                }
+            }
+
+            account::Evt::Reopened { account_id, .. } => {
+                if let Some(settlement_account) = closed.remove(&account_id) {
+                    merchants.write().insert(account_id, settlement_account);
+                }
+            }
+
+            account::Evt::Purged { account_id, .. } => {
+                closed.remove(&account_id);
+            }
+
+            _ => {}
+        }
+    }
+
+    Err(anyhow!("Account lifecycle events ended"))
+}
+
+/// Transfer the given amount from the merchant account to the settlement account: the merchant
+/// account is debited with the settlement ID, then the settlement account is credited; if that
+/// fails, the merchant account is credited back.
+async fn transfer<F>(
+    account_factory: &F,
+    id: Uuid,
+    merchant_account: Uuid,
+    settlement_account: Uuid,
+    amount: EuroCent,
+) -> Result<()>
+where
+    F: AccountFactory,
+{
+    handle_cmd(
+        account_factory,
+        merchant_account,
+        account::Cmd::Withdraw(id, amount, None, None),
+    )
+    .await
+    .context("Cannot debit merchant account")?;
+
+    let credit_id = Uuid::new_v5(&id, b"credit");
+    let credited = handle_cmd(
+        account_factory,
+        settlement_account,
+        account::Cmd::Deposit(credit_id, amount, None, None),
+    )
+    .await;
+    if let Err(error) = credited {
+        let compensation_id = Uuid::new_v5(&id, b"compensation");
+        handle_cmd(
+            account_factory,
+            merchant_account,
+            account::Cmd::Deposit(compensation_id, amount, None, None),
+        )
+        .await
+        .context("Cannot credit back merchant account, manual booking required")?;
+        return Err(error.context("Cannot credit settlement account"));
+    }
+
+    Ok(())
+}
+
+async fn handle_cmd<F>(account_factory: &F, id: Uuid, cmd: account::Cmd) -> Result<()>
+where
+    F: AccountFactory,
+{
+    let cmd_name = cmd.name();
+    account_factory
+        .get_with_priority(id, Priority::Bulk)
+        .await
+        .context("Cannot get Account entity")?
+        .handle_cmd(cmd)
+        .await
+        .with_context(|| format!("Cannot handle {cmd_name} command"))?
+        .with_context(|| format!("{cmd_name} command rejected"))?;
+    Ok(())
+}
+
+/// Deterministic ID of the settlement of the day starting at the given time.
+fn settlement_id(merchant_account: Uuid, from: OffsetDateTime) -> Uuid {
+    Uuid::new_v5(&merchant_account, from.date().to_string().as_bytes())
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settlement_id() {
+        let merchant_account = Uuid::now_v7();
+        let from = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(
+            settlement_id(merchant_account, from),
+            settlement_id(merchant_account, from + Duration::hours(12))
+        );
+        assert_ne!(
+            settlement_id(merchant_account, from),
+            settlement_id(merchant_account, from + Duration::days(1))
+        );
+        assert_ne!(
+            settlement_id(merchant_account, from),
+            settlement_id(Uuid::now_v7(), from)
+        );
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let settlement = MerchantSettlement {
+            id: Uuid::nil(),
+            merchant_account: Uuid::nil(),
+            settlement_account: Uuid::nil(),
+            from: OffsetDateTime::UNIX_EPOCH,
+            to: OffsetDateTime::UNIX_EPOCH + Duration::days(1),
+            credits: 1_042u64.into(),
+            debits: 42u64.into(),
+            net: 1_000u64.into(),
+            status: SettlementStatus::Settled,
+            at: OffsetDateTime::UNIX_EPOCH + Duration::days(1),
+        };
+        let nil = Uuid::nil();
+        assert_eq!(
+            render_csv(&[settlement]),
+            format!(
+                "date,merchant_account,settlement_account,credits,debits,net,status,id\n\
+                 1970-01-01,{nil},{nil},10.42,0.42,10.00,settled,{nil}\n"
+            )
+        );
+    }
+}
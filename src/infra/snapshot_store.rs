@@ -0,0 +1,166 @@
+use bytes::Bytes;
+use eventsourced::{SeqNo, Snapshot, SnapshotStore};
+use std::{convert::Infallible, error::Error as StdError};
+use tracing::warn;
+use uuid::Uuid;
+
+/// [SnapshotStore] ignoring snapshots which cannot be decoded, e.g. taken before the state of an
+/// entity changed incompatibly, such that these entities are recovered from all their events
+/// instead of failing to spawn. Snapshots are only an optimization, hence invalidating these is
+/// safe; they get replaced once the next snapshot is taken.
+#[derive(Debug, Clone)]
+pub struct LenientSnapshotStore<S> {
+    snapshot_store: S,
+}
+
+impl<S> LenientSnapshotStore<S> {
+    #[allow(missing_docs)]
+    pub fn new(snapshot_store: S) -> Self {
+        Self { snapshot_store }
+    }
+}
+
+impl<S> SnapshotStore for LenientSnapshotStore<S>
+where
+    S: SnapshotStore,
+{
+    type Error = S::Error;
+
+    async fn save<'a, T, StateToBytes, StateToBytesError>(
+        &'a mut self,
+        id: Uuid,
+        seq_no: SeqNo,
+        state: T,
+        state_to_bytes: &'a StateToBytes,
+    ) -> Result<(), Self::Error>
+    where
+        T: Send + Sync + 'a,
+        StateToBytes: Fn(&T) -> Result<Bytes, StateToBytesError> + Send + Sync + 'static,
+        StateToBytesError: StdError + Send + Sync + 'static,
+    {
+        self.snapshot_store
+            .save(id, seq_no, state, state_to_bytes)
+            .await
+    }
+
+    async fn load<'a, T, StateFromBytes, StateFromBytesError>(
+        &'a self,
+        id: Uuid,
+        state_from_bytes: StateFromBytes,
+    ) -> Result<Option<Snapshot<T>>, Self::Error>
+    where
+        T: 'a,
+        StateFromBytes: Fn(Bytes) -> Result<T, StateFromBytesError> + Copy + Send + Sync + 'static,
+        StateFromBytesError: StdError + Send + Sync + 'static,
+    {
+        let snapshot = self
+            .snapshot_store
+            .load::<Bytes, _, _>(id, |bytes| Ok::<_, Infallible>(bytes))
+            .await?;
+
+        let snapshot =
+            snapshot.and_then(|Snapshot { seq_no, state }| match state_from_bytes(state) {
+                Ok(state) => Some(Snapshot::new(seq_no, state)),
+
+                Err(error) => {
+                    warn!(%id, %seq_no, %error, "Ignoring snapshot which cannot be decoded");
+                    None
+                }
+            });
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::account;
+    use eventsourced::convert;
+    use parking_lot::Mutex;
+    use std::{collections::HashMap, sync::Arc};
+
+    #[derive(Debug, Clone, Default)]
+    struct TestSnapshotStore(Arc<Mutex<HashMap<Uuid, (SeqNo, Bytes)>>>);
+
+    impl SnapshotStore for TestSnapshotStore {
+        type Error = Infallible;
+
+        async fn save<'a, T, StateToBytes, StateToBytesError>(
+            &'a mut self,
+            id: Uuid,
+            seq_no: SeqNo,
+            state: T,
+            state_to_bytes: &'a StateToBytes,
+        ) -> Result<(), Self::Error>
+        where
+            T: Send + Sync + 'a,
+            StateToBytes: Fn(&T) -> Result<Bytes, StateToBytesError> + Send + Sync + 'static,
+            StateToBytesError: StdError + Send + Sync + 'static,
+        {
+            let bytes = state_to_bytes(&state).unwrap();
+            self.0.lock().insert(id, (seq_no, bytes));
+            Ok(())
+        }
+
+        async fn load<'a, T, StateFromBytes, StateFromBytesError>(
+            &'a self,
+            id: Uuid,
+            state_from_bytes: StateFromBytes,
+        ) -> Result<Option<Snapshot<T>>, Self::Error>
+        where
+            T: 'a,
+            StateFromBytes:
+                Fn(Bytes) -> Result<T, StateFromBytesError> + Copy + Send + Sync + 'static,
+            StateFromBytesError: StdError + Send + Sync + 'static,
+        {
+            let snapshot = self.0.lock().get(&id).cloned();
+            Ok(snapshot
+                .map(|(seq_no, state)| Snapshot::new(seq_no, state_from_bytes(state).unwrap())))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        let mut snapshot_store = LenientSnapshotStore::new(TestSnapshotStore::default());
+        let seq_no = SeqNo::try_from(42).unwrap();
+
+        let id = Uuid::now_v7();
+        let snapshot = account::Snapshot {
+            state: account::State::default(),
+            seq_no: 42,
+        };
+        snapshot_store
+            .save(
+                id,
+                seq_no,
+                snapshot.clone(),
+                &convert::serde_json::to_bytes::<account::Snapshot>,
+            )
+            .await
+            .unwrap();
+        let loaded = snapshot_store
+            .load::<account::Snapshot, _, _>(id, convert::serde_json::from_bytes)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.seq_no, seq_no);
+        assert_eq!(loaded.state, snapshot);
+
+        // Snapshots taken before the sequence number was part of these are ignored.
+        let id = Uuid::now_v7();
+        snapshot_store
+            .save(
+                id,
+                seq_no,
+                account::State::default(),
+                &convert::serde_json::to_bytes::<account::State>,
+            )
+            .await
+            .unwrap();
+        let loaded = snapshot_store
+            .load::<account::Snapshot, _, _>(id, convert::serde_json::from_bytes)
+            .await
+            .unwrap();
+        assert!(loaded.is_none());
+    }
+}
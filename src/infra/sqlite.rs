@@ -0,0 +1,432 @@
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo, Snapshot, SnapshotStore, TrySeqNoFromZero};
+use futures::{stream, Stream};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use std::{collections::VecDeque, error::Error as StdError, fmt::Debug, sync::Arc};
+use thiserror::Error;
+use tokio::{
+    sync::watch,
+    task::{self, JoinError},
+};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Maximum number of events read at once by events-by-tag queries.
+const BATCH_SIZE: i64 = 100;
+
+const EVTS_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS evts (
+        seq_no INTEGER PRIMARY KEY AUTOINCREMENT,
+        id     BLOB NOT NULL,
+        evt    BLOB NOT NULL,
+        tag    TEXT
+    );
+    CREATE INDEX IF NOT EXISTS evts_id ON evts (id, seq_no);
+    CREATE INDEX IF NOT EXISTS evts_tag ON evts (tag, seq_no);
+";
+
+const SNAPSHOTS_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS snapshots (
+        id     BLOB PRIMARY KEY,
+        seq_no INTEGER NOT NULL,
+        state  BLOB NOT NULL
+    );
+";
+
+/// [EvtLog] based upon SQLite, either file based or in-memory, e.g. for edge or demo deployments.
+/// Like for NATS, sequence numbers are global, i.e. shared by all entities. Events-by-tag queries
+/// are live: they are notified about events persisted via this event log or any of its clones,
+/// hence other processes must not write to the same database.
+#[derive(Debug, Clone)]
+pub struct SqliteEvtLog {
+    conn: Arc<Mutex<Connection>>,
+    last_seq_no: Arc<watch::Sender<u64>>,
+}
+
+impl SqliteEvtLog {
+    #[allow(missing_docs)]
+    pub async fn new(config: Config) -> Result<Self, Error> {
+        debug!(?config, "Creating SqliteEvtLog");
+
+        let conn = open(config, EVTS_SCHEMA)?;
+        let last_seq_no = blocking(&conn, |conn| {
+            conn.query_row("SELECT COALESCE(MAX(seq_no), 0) FROM evts", [], |row| {
+                row.get::<_, u64>(0)
+            })
+        })
+        .await?;
+
+        Ok(Self {
+            conn,
+            last_seq_no: Arc::new(watch::channel(last_seq_no).0),
+        })
+    }
+
+    async fn evts_by_tag_from(
+        &self,
+        tag: String,
+        from_seq_no: u64,
+    ) -> Result<Vec<(u64, Vec<u8>)>, Error> {
+        blocking(&self.conn, move |conn| {
+            conn.prepare_cached(
+                "SELECT seq_no, evt FROM evts WHERE tag = ?1 AND seq_no >= ?2
+                 ORDER BY seq_no LIMIT ?3",
+            )?
+            .query_map(params![tag, from_seq_no, BATCH_SIZE], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect()
+        })
+        .await
+    }
+}
+
+impl EvtLog for SqliteEvtLog {
+    type Error = Error;
+
+    async fn persist<'a, E, EvtToBytes, EvtToBytesError>(
+        &'a mut self,
+        id: Uuid,
+        evt: &'a E,
+        tag: Option<String>,
+        evt_to_bytes: &'a EvtToBytes,
+    ) -> Result<SeqNo, Self::Error>
+    where
+        E: Debug + Send + Sync + 'a,
+        EvtToBytes: Fn(&E) -> Result<Bytes, EvtToBytesError> + Send + Sync,
+        EvtToBytesError: StdError + Send + Sync + 'static,
+    {
+        let bytes = evt_to_bytes(evt).map_err(|source| Error::EvtToBytes(Box::new(source)))?;
+
+        let seq_no = blocking(&self.conn, move |conn| {
+            conn.query_row(
+                "INSERT INTO evts (id, evt, tag) VALUES (?1, ?2, ?3) RETURNING seq_no",
+                params![id.as_bytes(), bytes.as_ref(), tag],
+                |row| row.get::<_, u64>(0),
+            )
+        })
+        .await?;
+
+        self.last_seq_no.send_replace(seq_no);
+        Ok(seq_no.try_into()?)
+    }
+
+    async fn last_seq_no(&self, id: Uuid) -> Result<Option<SeqNo>, Self::Error> {
+        let seq_no = blocking(&self.conn, move |conn| {
+            conn.query_row(
+                "SELECT MAX(seq_no) FROM evts WHERE id = ?1",
+                params![id.as_bytes()],
+                |row| row.get::<_, Option<u64>>(0),
+            )
+        })
+        .await?;
+        Ok(seq_no.map(SeqNo::try_from).transpose()?)
+    }
+
+    async fn evts_by_id<'a, E, EvtFromBytes, EvtFromBytesError>(
+        &'a self,
+        id: Uuid,
+        from_seq_no: SeqNo,
+        evt_from_bytes: EvtFromBytes,
+    ) -> Result<impl Stream<Item = Result<(SeqNo, E), Self::Error>> + Send, Self::Error>
+    where
+        E: Debug + Send + 'a,
+        EvtFromBytes: Fn(Bytes) -> Result<E, EvtFromBytesError> + Copy + Send + Sync + 'static,
+        EvtFromBytesError: StdError + Send + Sync + 'static,
+    {
+        debug!(%id, %from_seq_no, "Building events by ID stream");
+
+        let from_seq_no = from_seq_no.as_u64();
+        let evts = blocking(&self.conn, move |conn| {
+            conn.prepare_cached(
+                "SELECT seq_no, evt FROM evts WHERE id = ?1 AND seq_no >= ?2 ORDER BY seq_no",
+            )?
+            .query_map(params![id.as_bytes(), from_seq_no], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<Vec<(u64, Vec<u8>)>, _>>()
+        })
+        .await?;
+
+        let evts = evts
+            .into_iter()
+            .map(move |(seq_no, evt)| decode(seq_no, evt, evt_from_bytes));
+        Ok(stream::iter(evts))
+    }
+
+    async fn evts_by_tag<'a, E, T, EvtFromBytes, EvtFromBytesError>(
+        &'a self,
+        tag: T,
+        from_seq_no: SeqNo,
+        evt_from_bytes: EvtFromBytes,
+    ) -> Result<impl Stream<Item = Result<(SeqNo, E), Self::Error>> + Send, Self::Error>
+    where
+        E: Debug + Send + 'a,
+        EvtFromBytes: Fn(Bytes) -> Result<E, EvtFromBytesError> + Copy + Send + Sync + 'static,
+        EvtFromBytesError: StdError + Send + Sync + 'static,
+        T: Into<String> + Send,
+    {
+        let tag = tag.into();
+        debug!(tag, %from_seq_no, "Building events by tag stream");
+
+        let state = TagQuery {
+            evt_log: self.clone(),
+            tag,
+            next_seq_no: from_seq_no.as_u64(),
+            evts: VecDeque::new(),
+            last_seq_no: self.last_seq_no.subscribe(),
+        };
+        let evts = stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some((seq_no, evt)) = state.evts.pop_front() {
+                    state.next_seq_no = seq_no + 1;
+                    return Some((decode(seq_no, evt, evt_from_bytes), state));
+                }
+
+                // Mark the current last sequence number as seen before querying, such that no
+                // events persisted in the meantime are missed.
+                state.last_seq_no.borrow_and_update();
+                match state
+                    .evt_log
+                    .evts_by_tag_from(state.tag.clone(), state.next_seq_no)
+                    .await
+                {
+                    Ok(evts) if evts.is_empty() => state.last_seq_no.changed().await.ok()?,
+                    Ok(evts) => state.evts.extend(evts),
+                    Err(error) => return Some((Err(error), state)),
+                }
+            }
+        });
+
+        Ok(evts)
+    }
+}
+
+/// State of a live events-by-tag query.
+struct TagQuery {
+    evt_log: SqliteEvtLog,
+    tag: String,
+    next_seq_no: u64,
+    evts: VecDeque<(u64, Vec<u8>)>,
+    last_seq_no: watch::Receiver<u64>,
+}
+
+/// [SnapshotStore] based upon SQLite, either file based or in-memory.
+#[derive(Debug, Clone)]
+pub struct SqliteSnapshotStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSnapshotStore {
+    #[allow(missing_docs)]
+    pub async fn new(config: Config) -> Result<Self, Error> {
+        debug!(?config, "Creating SqliteSnapshotStore");
+        let conn = open(config, SNAPSHOTS_SCHEMA)?;
+        Ok(Self { conn })
+    }
+}
+
+impl SnapshotStore for SqliteSnapshotStore {
+    type Error = Error;
+
+    async fn save<'a, S, StateToBytes, StateToBytesError>(
+        &'a mut self,
+        id: Uuid,
+        seq_no: SeqNo,
+        state: S,
+        state_to_bytes: &'a StateToBytes,
+    ) -> Result<(), Self::Error>
+    where
+        S: Send + Sync + 'a,
+        StateToBytes: Fn(&S) -> Result<Bytes, StateToBytesError> + Send + Sync + 'static,
+        StateToBytesError: StdError + Send + Sync + 'static,
+    {
+        let bytes =
+            state_to_bytes(&state).map_err(|source| Error::StateToBytes(Box::new(source)))?;
+
+        blocking(&self.conn, move |conn| {
+            conn.execute(
+                "INSERT INTO snapshots (id, seq_no, state) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET seq_no = excluded.seq_no, state = excluded.state",
+                params![id.as_bytes(), seq_no.as_u64(), bytes.as_ref()],
+            )
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn load<'a, S, StateFromBytes, StateFromBytesError>(
+        &'a self,
+        id: Uuid,
+        state_from_bytes: StateFromBytes,
+    ) -> Result<Option<Snapshot<S>>, Self::Error>
+    where
+        S: 'a,
+        StateFromBytes: Fn(Bytes) -> Result<S, StateFromBytesError> + Copy + Send + Sync + 'static,
+        StateFromBytesError: StdError + Send + Sync + 'static,
+    {
+        let snapshot = blocking(&self.conn, move |conn| {
+            conn.query_row(
+                "SELECT seq_no, state FROM snapshots WHERE id = ?1",
+                params![id.as_bytes()],
+                |row| Ok((row.get::<_, u64>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()
+        })
+        .await?;
+
+        snapshot
+            .map(|(seq_no, state)| {
+                let state = state_from_bytes(state.into())
+                    .map_err(|source| Error::StateFromBytes(Box::new(source)))?;
+                Ok(Snapshot::new(seq_no.try_into()?, state))
+            })
+            .transpose()
+    }
+}
+
+/// Configuration for the [SqliteEvtLog] and the [SqliteSnapshotStore].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Path of the database file or `:memory:` for an in-memory database.
+    path: String,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SQLite error")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("Cannot run blocking SQLite task")]
+    Blocking(#[from] JoinError),
+
+    #[error("Invalid sequence number")]
+    InvalidSeqNo(#[from] TrySeqNoFromZero),
+
+    #[error("Cannot convert event to bytes")]
+    EvtToBytes(#[source] Box<dyn StdError + Send + Sync>),
+
+    #[error("Cannot convert bytes to event")]
+    EvtFromBytes(#[source] Box<dyn StdError + Send + Sync>),
+
+    #[error("Cannot convert snapshot state to bytes")]
+    StateToBytes(#[source] Box<dyn StdError + Send + Sync>),
+
+    #[error("Cannot convert bytes to snapshot state")]
+    StateFromBytes(#[source] Box<dyn StdError + Send + Sync>),
+}
+
+fn open(config: Config, schema: &str) -> Result<Arc<Mutex<Connection>>, Error> {
+    let conn = if config.path == ":memory:" {
+        Connection::open_in_memory()?
+    } else {
+        Connection::open(&config.path)?
+    };
+    conn.execute_batch(schema)?;
+    Ok(Arc::new(Mutex::new(conn)))
+}
+
+/// Run the given function on the given connection without blocking the async runtime.
+async fn blocking<F, T>(conn: &Arc<Mutex<Connection>>, f: F) -> Result<T, Error>
+where
+    F: FnOnce(&Connection) -> Result<T, rusqlite::Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let conn = conn.clone();
+    let result = task::spawn_blocking(move || f(&conn.lock())).await?;
+    Ok(result?)
+}
+
+fn decode<E, EvtFromBytes, EvtFromBytesError>(
+    seq_no: u64,
+    evt: Vec<u8>,
+    evt_from_bytes: EvtFromBytes,
+) -> Result<(SeqNo, E), Error>
+where
+    EvtFromBytes: Fn(Bytes) -> Result<E, EvtFromBytesError>,
+    EvtFromBytesError: StdError + Send + Sync + 'static,
+{
+    let seq_no = seq_no.try_into()?;
+    let evt = evt_from_bytes(evt.into()).map_err(|source| Error::EvtFromBytes(Box::new(source)))?;
+    Ok((seq_no, evt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eventsourced::convert;
+    use futures::{StreamExt, TryStreamExt};
+
+    fn config() -> Config {
+        Config {
+            path: ":memory:".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evt_log() -> Result<(), Error> {
+        let mut evt_log = SqliteEvtLog::new(config()).await?;
+        let id = Uuid::now_v7();
+        let to_bytes = convert::serde_json::to_bytes::<u64>;
+        let from_bytes = convert::serde_json::from_bytes::<u64>;
+
+        assert_eq!(evt_log.last_seq_no(id).await?, None);
+
+        for n in 1..=3 {
+            evt_log
+                .persist(id, &n, Some("tag".to_string()), &to_bytes)
+                .await?;
+        }
+        evt_log
+            .persist(Uuid::now_v7(), &42, None, &to_bytes)
+            .await?;
+        assert_eq!(evt_log.last_seq_no(id).await?, SeqNo::try_from(3).ok());
+
+        let evts = evt_log
+            .evts_by_id(id, SeqNo::try_from(2)?, from_bytes)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(evts.into_iter().map(|(_, n)| n).collect::<Vec<_>>(), [2, 3]);
+
+        let evts = evt_log.evts_by_tag("tag", SeqNo::MIN, from_bytes).await?;
+        let mut evts = Box::pin(evts);
+        for n in 1..=3 {
+            assert_eq!(evts.next().await.transpose()?.map(|(_, n)| n), Some(n));
+        }
+
+        // Events-by-tag queries are live.
+        evt_log
+            .persist(id, &4, Some("tag".to_string()), &to_bytes)
+            .await?;
+        assert_eq!(evts.next().await.transpose()?.map(|(_, n)| n), Some(4));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_store() -> Result<(), Error> {
+        let mut snapshot_store = SqliteSnapshotStore::new(config()).await?;
+        let id = Uuid::now_v7();
+        let to_bytes = convert::serde_json::to_bytes::<u64>;
+        let from_bytes = convert::serde_json::from_bytes::<u64>;
+
+        assert!(snapshot_store.load(id, from_bytes).await?.is_none());
+
+        snapshot_store.save(id, SeqNo::MIN, 42, &to_bytes).await?;
+        snapshot_store
+            .save(id, SeqNo::MIN.succ(), 666, &to_bytes)
+            .await?;
+        let snapshot = snapshot_store.load(id, from_bytes).await?;
+        assert!(matches!(
+            snapshot,
+            Some(Snapshot { seq_no, state: 666 }) if seq_no == SeqNo::MIN.succ()
+        ));
+
+        Ok(())
+    }
+}
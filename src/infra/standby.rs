@@ -0,0 +1,368 @@
+use crate::domain::{
+    account::{self, ACCOUNT_LIFECYCLE_TAG, ACCOUNT_TX_TAG},
+    clock::Clock,
+    euro_cent::EuroCent,
+};
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::StreamExt;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::Infallible,
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+use time::OffsetDateTime;
+use tokio::{sync::watch, task, time::sleep};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Delay before tailing the event log again after an error.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A passive instance in a secondary region for active/passive deployments: the event log is
+/// replicated by the backend, e.g. via a NATS stream mirror or DynamoDB global tables, and tailed
+/// to track how far replication has progressed and whether the replicated transactions are
+/// complete. Once promoted, e.g. after the primary region has failed, the instance becomes active.
+#[derive(Debug, Clone)]
+pub struct Standby {
+    clock: Arc<dyn Clock>,
+    promoted: Arc<watch::Sender<bool>>,
+    replication: Arc<RwLock<Replication>>,
+}
+
+impl Standby {
+    /// Create a [Standby], not yet promoted, and spawn tailing the given event log.
+    pub fn spawn<L>(clock: Arc<dyn Clock>, evt_log: L) -> Self
+    where
+        L: EvtLog,
+    {
+        let (promoted, _) = watch::channel(false);
+        let standby = Self {
+            clock,
+            promoted: Arc::new(promoted),
+            replication: Default::default(),
+        };
+
+        for tag in [ACCOUNT_LIFECYCLE_TAG, ACCOUNT_TX_TAG] {
+            task::spawn(tail(
+                tag,
+                evt_log.clone(),
+                standby.clock.clone(),
+                standby.replication.clone(),
+            ));
+        }
+
+        standby
+    }
+
+    /// Whether this instance has been promoted, i.e. is active.
+    pub fn is_promoted(&self) -> bool {
+        *self.promoted.borrow()
+    }
+
+    /// Receiver for whether this instance has been promoted, e.g. to gate [Leadership].
+    ///
+    /// [Leadership]: crate::infra::leader::Leadership
+    pub fn promoted(&self) -> watch::Receiver<bool> {
+        self.promoted.subscribe()
+    }
+
+    /// The current replication status.
+    pub fn status(&self) -> StandbyStatus {
+        let replication = self.replication.read();
+        let now = self.clock.now();
+        StandbyStatus {
+            promoted: self.is_promoted(),
+            last_seq_no: replication.last_seq_no,
+            last_evt_at: replication.last_evt_at,
+            rpo_secs: replication
+                .last_evt_at
+                .map(|last_evt_at| (now - last_evt_at).whole_seconds().max(0)),
+            gaps: replication.gaps(),
+        }
+    }
+
+    /// Promote this instance, i.e. make it active. Unless forced, promotion is refused if gaps
+    /// have been detected, i.e. replication is incomplete and promoting would lose transactions.
+    pub fn promote(&self, force: bool) -> Result<StandbyStatus, PromotionError> {
+        if self.is_promoted() {
+            return Err(PromotionError::AlreadyPromoted);
+        }
+
+        let status = self.status();
+        if !status.gaps.is_empty() && !force {
+            return Err(PromotionError::Gaps(status.gaps.len()));
+        }
+
+        self.promoted.send_replace(true);
+        info!(
+            last_seq_no = status.last_seq_no,
+            rpo_secs = status.rpo_secs,
+            gaps = status.gaps.len(),
+            force,
+            "Promoted standby"
+        );
+        metrics::counter!("standby_promotions_total", 1);
+
+        Ok(StandbyStatus {
+            promoted: true,
+            ..status
+        })
+    }
+}
+
+/// Replication status of a [Standby].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StandbyStatus {
+    pub promoted: bool,
+    /// Sequence number of the last replicated event, if any.
+    pub last_seq_no: Option<u64>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_evt_at: Option<OffsetDateTime>,
+    /// Recovery point objective, i.e. the age of the last replicated event in seconds, which is an
+    /// upper bound for the data lost when promoting now. Notice that it also grows while the
+    /// primary region is idle.
+    pub rpo_secs: Option<i64>,
+    pub gaps: Vec<Gap>,
+}
+
+/// A sequence gap, i.e. a transaction event whose old balance does not match the balance after
+/// the previous replicated transaction of the same account, hence events in between are missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Gap {
+    pub account_id: Uuid,
+    pub seq_no: u64,
+    pub prev_seq_no: u64,
+    pub expected_balance: EuroCent,
+    pub old_balance: EuroCent,
+}
+
+/// Errors of promoting a [Standby].
+#[derive(Debug, Error)]
+pub enum PromotionError {
+    #[error("standby already promoted")]
+    AlreadyPromoted,
+
+    #[error("{0} sequence gaps detected, replication incomplete")]
+    Gaps(usize),
+}
+
+/// Progress of tailing the replicated event log.
+#[derive(Debug, Default)]
+struct Replication {
+    last_seq_no: Option<u64>,
+    last_evt_at: Option<OffsetDateTime>,
+    /// Sequence number and resulting balance of the last transaction per account.
+    balances: HashMap<Uuid, (u64, EuroCent)>,
+    /// Reopenings by account and sequence number, which reset the balance.
+    reopenings: BTreeSet<(Uuid, u64)>,
+    candidate_gaps: Vec<Gap>,
+}
+
+impl Replication {
+    fn apply(&mut self, seq_no: u64, evt: &account::Evt, now: OffsetDateTime) {
+        self.last_seq_no = self.last_seq_no.max(Some(seq_no));
+        self.last_evt_at = self.last_evt_at.max(Some(evt.at()));
+
+        metrics::gauge!(
+            "standby_replication_lag_seconds",
+            (now - evt.at()).as_seconds_f64().max(0.0)
+        );
+
+        let (account_id, old_balance, balance) = match *evt {
+            account::Evt::Deposited {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            } => (account_id, old_balance, old_balance + amount),
+            account::Evt::Withdrawn {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            }
+            | account::Evt::HoldCaptured {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            } => (account_id, old_balance, old_balance - amount),
+            account::Evt::Adjusted {
+                account_id,
+                old_balance,
+                direction,
+                amount,
+                ..
+            } => (
+                account_id,
+                old_balance,
+                direction.apply(old_balance, amount),
+            ),
+            account::Evt::Reopened { account_id, .. } => {
+                self.reopenings.insert((account_id, seq_no));
+                return;
+            }
+            _ => return,
+        };
+
+        if let Some((prev_seq_no, expected_balance)) =
+            self.balances.insert(account_id, (seq_no, balance))
+        {
+            if old_balance != expected_balance {
+                warn!(%account_id, seq_no, prev_seq_no, "Sequence gap detected");
+                self.candidate_gaps.push(Gap {
+                    account_id,
+                    seq_no,
+                    prev_seq_no,
+                    expected_balance,
+                    old_balance,
+                });
+            }
+        }
+    }
+
+    /// The detected gaps, except for those explained by a reopening in between, which resets the
+    /// balance. Lifecycle and transaction events are tailed independently, hence reopenings are
+    /// only considered here.
+    fn gaps(&self) -> Vec<Gap> {
+        let gaps = self
+            .candidate_gaps
+            .iter()
+            .filter(|gap| {
+                self.reopenings
+                    .range((gap.account_id, gap.prev_seq_no)..(gap.account_id, gap.seq_no))
+                    .next()
+                    .is_none()
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        metrics::gauge!("standby_sequence_gaps", gaps.len() as f64);
+        gaps
+    }
+}
+
+/// Tail the events with the given tag, resuming after errors.
+async fn tail<L>(
+    tag: &'static str,
+    evt_log: L,
+    clock: Arc<dyn Clock>,
+    replication: Arc<RwLock<Replication>>,
+) where
+    L: EvtLog,
+{
+    let mut from_seq_no = SeqNo::MIN;
+    loop {
+        match evt_log
+            .evts_by_tag::<Bytes, _, _, _>(tag, from_seq_no, raw)
+            .await
+        {
+            Ok(evts) => {
+                let mut evts = Box::pin(evts);
+                while let Some(evt) = evts.next().await {
+                    let (seq_no, evt) = match evt {
+                        Ok(evt) => evt,
+                        Err(error) => {
+                            error!(error = format!("{error:#}"), tag, "Cannot get next event");
+                            break;
+                        }
+                    };
+                    from_seq_no = seq_no.succ();
+                    let Ok(evt) = serde_json::from_slice::<account::Evt>(&evt) else {
+                        warn!(%seq_no, "Cannot deserialize account event");
+                        continue;
+                    };
+                    replication
+                        .write()
+                        .apply(seq_no.as_u64(), &evt, clock.now());
+                }
+            }
+
+            Err(error) => {
+                error!(
+                    error = format!("{error:#}"),
+                    tag, "Cannot create events-by-tag query"
+                );
+            }
+        }
+
+        sleep(RETRY_DELAY).await;
+    }
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::account::AdjustmentDirection;
+
+    #[test]
+    fn test_replication() {
+        let account_id = Uuid::now_v7();
+        let at = OffsetDateTime::UNIX_EPOCH;
+        let deposited = |old_balance: u64, amount: u64| account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: old_balance.into(),
+            amount: amount.into(),
+            value_date: None,
+            channel: None,
+            at,
+        };
+
+        let mut replication = Replication::default();
+        replication.apply(1, &deposited(0, 100), at);
+        replication.apply(
+            3,
+            &account::Evt::Adjusted {
+                id: Uuid::now_v7(),
+                account_id,
+                old_balance: 100.into(),
+                direction: AdjustmentDirection::Debit,
+                amount: 30.into(),
+                reason: account::ReasonCode::FeeRefund,
+                justification: "test".to_string(),
+                at,
+            },
+            at,
+        );
+        assert_eq!(replication.last_seq_no, Some(3));
+        assert_eq!(replication.last_evt_at, Some(at));
+        assert!(replication.gaps().is_empty());
+
+        // A missing deposit of 20.
+        replication.apply(7, &deposited(90, 10), at);
+        assert_eq!(
+            replication.gaps(),
+            vec![Gap {
+                account_id,
+                seq_no: 7,
+                prev_seq_no: 3,
+                expected_balance: 70.into(),
+                old_balance: 90.into(),
+            }]
+        );
+
+        // Closed and reopened in between, the reopening replicated late.
+        replication.apply(11, &deposited(0, 5), at);
+        assert_eq!(replication.gaps().len(), 2);
+        replication.apply(
+            9,
+            &account::Evt::Reopened {
+                account_id,
+                justification: "test".to_string(),
+                at,
+            },
+            at,
+        );
+        assert_eq!(replication.gaps().len(), 1);
+        assert_eq!(replication.last_seq_no, Some(11));
+    }
+}
@@ -0,0 +1,421 @@
+use crate::domain::{account, clock::Clock, euro_cent::EuroCent, id::IdGenerator};
+use metrics::counter;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    num::{NonZeroU64, NonZeroUsize},
+    sync::Arc,
+};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use tokio::{task, time::interval};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Hook for step-up authentication, e.g. strong customer authentication (SCA): invoked when a
+/// withdrawal exceeds the thresholds and has been parked, to challenge the customer out of band,
+/// e.g. via a push notification to a banking app. Once authenticated, the parked command is
+/// confirmed via `POST /confirmations/:id`.
+pub trait StepUpAuth: Debug + Send + Sync + 'static {
+    /// Challenge the customer to confirm the given [PendingConfirmation]. Must not block, e.g.
+    /// only enqueue a notification.
+    fn challenge(&self, confirmation: &PendingConfirmation) -> anyhow::Result<()>;
+}
+
+/// [StepUpAuth] only logging challenges, e.g. for development.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogStepUpAuth;
+
+impl StepUpAuth for LogStepUpAuth {
+    fn challenge(&self, confirmation: &PendingConfirmation) -> anyhow::Result<()> {
+        info!(
+            id = %confirmation.id,
+            account_id = %confirmation.account_id,
+            reason = ?confirmation.reason,
+            "Challenging step-up authentication"
+        );
+        Ok(())
+    }
+}
+
+/// Velocity-based step-up authentication: withdrawals exceeding the configured thresholds, either
+/// by amount or by the amount or number of withdrawals from the same account within a window, or
+/// issued from a device not used within the window, are parked until confirmed or expired. Like
+/// [Quotes](crate::infra::quote::Quotes), pending confirmations and the withdrawals within the
+/// window are held in memory, i.e. per node.
+#[derive(Debug, Clone)]
+pub struct StepUp {
+    config: Config,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    auth: Arc<dyn StepUpAuth>,
+    pending: Arc<RwLock<HashMap<Uuid, PendingConfirmation>>>,
+    /// Times, amounts and device IDs of the withdrawals within the window by account.
+    withdrawals: Arc<RwLock<HashMap<Uuid, VecDeque<Withdrawal>>>>,
+}
+
+impl StepUp {
+    /// Create a [StepUp] and spawn expiring pending confirmations periodically.
+    pub fn spawn(
+        config: Config,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        auth: Arc<dyn StepUpAuth>,
+    ) -> Self {
+        let step_up = Self {
+            config,
+            clock,
+            ids,
+            auth,
+            pending: Default::default(),
+            withdrawals: Default::default(),
+        };
+
+        task::spawn({
+            let step_up = step_up.clone();
+            async move {
+                let mut interval = interval(std::time::Duration::from_secs(
+                    step_up.config.expiry_interval_secs.get(),
+                ));
+                loop {
+                    interval.tick().await;
+                    step_up.expire();
+                }
+            }
+        });
+
+        step_up
+    }
+
+    /// The reason why withdrawing the given amount from the given account via the device with the
+    /// given ID, if known, requires step-up authentication, if it does.
+    pub fn check(
+        &self,
+        account_id: Uuid,
+        amount: EuroCent,
+        device_id: Option<&str>,
+    ) -> Option<StepUpReason> {
+        if self
+            .config
+            .amount_threshold
+            .is_some_and(|threshold| amount > threshold)
+        {
+            return Some(StepUpReason::Amount);
+        }
+
+        let from = self.clock.now() - self.window();
+        let mut withdrawals = self.withdrawals.write();
+        let withdrawals = withdrawals.entry(account_id).or_default();
+        prune(withdrawals, from);
+        let total = withdrawals
+            .iter()
+            .fold(amount, |total, withdrawal| total + withdrawal.amount);
+        if self
+            .config
+            .window_amount_threshold
+            .is_some_and(|threshold| total > threshold)
+        {
+            return Some(StepUpReason::WindowAmount);
+        }
+        if self
+            .config
+            .window_count_threshold
+            .is_some_and(|threshold| withdrawals.len() + 1 > threshold.get())
+        {
+            return Some(StepUpReason::WindowCount);
+        }
+        if let Some(device_id) = device_id.filter(|_| self.config.unknown_device) {
+            let mut device_ids = withdrawals
+                .iter()
+                .filter_map(|withdrawal| withdrawal.device_id.as_deref())
+                .peekable();
+            if device_ids.peek().is_some() && device_ids.all(|known| known != device_id) {
+                return Some(StepUpReason::UnknownDevice);
+            }
+        }
+
+        None
+    }
+
+    /// Record a successful withdrawal of the given amount from the given account via the device
+    /// with the given ID, if known, for the velocity thresholds.
+    pub fn record(&self, account_id: Uuid, amount: EuroCent, device_id: Option<String>) {
+        let now = self.clock.now();
+        let mut withdrawals = self.withdrawals.write();
+        let withdrawals = withdrawals.entry(account_id).or_default();
+        prune(withdrawals, now - self.window());
+        withdrawals.push_back(Withdrawal {
+            at: now,
+            amount,
+            device_id,
+        });
+    }
+
+    /// Park the given withdrawal command pending confirmation and challenge the customer via the
+    /// [StepUpAuth] hook.
+    pub fn park(
+        &self,
+        account_id: Uuid,
+        transaction_id: Uuid,
+        amount: EuroCent,
+        cmd: account::Cmd,
+        reason: StepUpReason,
+    ) -> anyhow::Result<PendingConfirmation> {
+        let confirmation = PendingConfirmation {
+            id: self.ids.next_id(),
+            account_id,
+            transaction_id,
+            amount,
+            reason,
+            expires_at: self.clock.now() + Duration::seconds(self.config.ttl_secs.get() as i64),
+            cmd,
+        };
+
+        self.pending
+            .write()
+            .insert(confirmation.id, confirmation.clone());
+        if let Err(error) = self.auth.challenge(&confirmation) {
+            self.pending.write().remove(&confirmation.id);
+            return Err(error.context("Cannot challenge step-up authentication"));
+        }
+
+        debug!(id = %confirmation.id, %account_id, ?reason, "Parked withdrawal");
+        counter!("step_up_challenges_total", 1, "reason" => reason.as_str());
+        Ok(confirmation)
+    }
+
+    /// Take the [PendingConfirmation] with the given ID, such that its command can be handled once.
+    pub fn confirm(&self, id: Uuid) -> Result<PendingConfirmation, Error> {
+        let confirmation = self.pending.write().remove(&id).ok_or(Error::Unknown(id))?;
+        if confirmation.expires_at <= self.clock.now() {
+            return Err(Error::Expired(id));
+        }
+        counter!("step_up_confirmations_total", 1);
+        Ok(confirmation)
+    }
+
+    /// Remove the expired pending confirmations and the withdrawals outside of the window.
+    fn expire(&self) {
+        let now = self.clock.now();
+
+        let mut pending = self.pending.write();
+        let before = pending.len();
+        pending.retain(|_, confirmation| confirmation.expires_at > now);
+        let expired = before - pending.len();
+        drop(pending);
+        if expired > 0 {
+            debug!(expired, "Expired pending confirmations");
+            counter!("step_up_expirations_total", expired as u64);
+        }
+
+        let from = now - self.window();
+        self.withdrawals.write().retain(|_, withdrawals| {
+            prune(withdrawals, from);
+            !withdrawals.is_empty()
+        });
+    }
+
+    fn window(&self) -> Duration {
+        Duration::seconds(self.config.window_secs.get() as i64)
+    }
+}
+
+/// Configuration for [StepUp]. Thresholds not given are not checked.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Withdrawals exceeding this amount require step-up authentication.
+    amount_threshold: Option<EuroCent>,
+
+    window_secs: NonZeroU64,
+
+    /// Withdrawals for which the total amount withdrawn from the account within the window,
+    /// including their own, exceeds this require step-up authentication.
+    window_amount_threshold: Option<EuroCent>,
+
+    /// Withdrawals for which the number of withdrawals from the account within the window,
+    /// including themselves, exceeds this require step-up authentication.
+    window_count_threshold: Option<NonZeroUsize>,
+
+    /// Whether withdrawals issued from a device other than those used for the withdrawals from the
+    /// account within the window require step-up authentication.
+    #[serde(default)]
+    unknown_device: bool,
+
+    /// Time for confirming a parked withdrawal.
+    ttl_secs: NonZeroU64,
+
+    expiry_interval_secs: NonZeroU64,
+}
+
+/// The threshold exceeded by a withdrawal requiring step-up authentication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StepUpReason {
+    Amount,
+    WindowAmount,
+    WindowCount,
+    UnknownDevice,
+}
+
+impl StepUpReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepUpReason::Amount => "amount",
+            StepUpReason::WindowAmount => "window-amount",
+            StepUpReason::WindowCount => "window-count",
+            StepUpReason::UnknownDevice => "unknown-device",
+        }
+    }
+}
+
+/// A withdrawal parked pending confirmation via step-up authentication.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingConfirmation {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    /// The ID of the withdrawal, once confirmed.
+    pub transaction_id: Uuid,
+    pub amount: EuroCent,
+    pub reason: StepUpReason,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+    /// The parked command.
+    #[serde(skip)]
+    pub cmd: account::Cmd,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unknown confirmation '{0}'")]
+    Unknown(Uuid),
+
+    #[error("Confirmation '{0}' has expired")]
+    Expired(Uuid),
+}
+
+#[derive(Debug)]
+struct Withdrawal {
+    at: OffsetDateTime,
+    amount: EuroCent,
+    device_id: Option<String>,
+}
+
+/// Remove the withdrawals before the given time, which are at the front.
+fn prune(withdrawals: &mut VecDeque<Withdrawal>, from: OffsetDateTime) {
+    while withdrawals
+        .front()
+        .is_some_and(|withdrawal| withdrawal.at <= from)
+    {
+        withdrawals.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{clock::ManualClock, id::UuidV7Generator};
+
+    #[tokio::test]
+    async fn test_step_up() {
+        let clock = ManualClock::new(OffsetDateTime::UNIX_EPOCH);
+        let config = Config {
+            amount_threshold: Some(1_000u64.into()),
+            window_secs: NonZeroU64::new(60).unwrap(),
+            window_amount_threshold: Some(1_500u64.into()),
+            window_count_threshold: NonZeroUsize::new(3),
+            unknown_device: true,
+            ttl_secs: NonZeroU64::new(30).unwrap(),
+            expiry_interval_secs: NonZeroU64::new(3_600).unwrap(),
+        };
+        let step_up = StepUp::spawn(
+            config,
+            Arc::new(clock.clone()),
+            Arc::new(UuidV7Generator::new(Arc::new(clock.clone()))),
+            Arc::new(LogStepUpAuth),
+        );
+        let account_id = Uuid::now_v7();
+
+        assert_eq!(
+            step_up.check(account_id, 1_001u64.into(), None),
+            Some(StepUpReason::Amount)
+        );
+        assert_eq!(step_up.check(account_id, 1_000u64.into(), None), None);
+
+        step_up.record(account_id, 1_000u64.into(), Some("phone".to_string()));
+        assert_eq!(step_up.check(account_id, 1u64.into(), Some("phone")), None);
+        assert_eq!(
+            step_up.check(account_id, 1u64.into(), Some("laptop")),
+            Some(StepUpReason::UnknownDevice)
+        );
+        assert_eq!(
+            step_up.check(account_id, 501u64.into(), None),
+            Some(StepUpReason::WindowAmount)
+        );
+        step_up.record(account_id, 100u64.into(), None);
+        assert_eq!(
+            step_up.check(account_id, 1u64.into(), None),
+            Some(StepUpReason::WindowCount)
+        );
+
+        // Other accounts and withdrawals outside of the window are not considered.
+        assert_eq!(step_up.check(Uuid::now_v7(), 1u64.into(), None), None);
+        clock.advance(Duration::seconds(60));
+        assert_eq!(step_up.check(account_id, 1_000u64.into(), None), None);
+
+        // Confirmed once.
+        let transaction_id = Uuid::now_v7();
+        let cmd = account::Cmd::Withdraw(transaction_id, 1_001u64.into(), None, None);
+        let confirmation = step_up
+            .park(
+                account_id,
+                transaction_id,
+                1_001u64.into(),
+                cmd,
+                StepUpReason::Amount,
+            )
+            .unwrap();
+        assert_eq!(confirmation.expires_at, clock.now() + Duration::seconds(30));
+        let confirmed = step_up.confirm(confirmation.id).unwrap();
+        assert_eq!(confirmed.transaction_id, transaction_id);
+        assert!(matches!(
+            step_up.confirm(confirmation.id),
+            Err(Error::Unknown(_))
+        ));
+
+        // Expired.
+        let cmd = account::Cmd::Withdraw(transaction_id, 1_001u64.into(), None, None);
+        let confirmation = step_up
+            .park(
+                account_id,
+                transaction_id,
+                1_001u64.into(),
+                cmd.clone(),
+                StepUpReason::Amount,
+            )
+            .unwrap();
+        clock.advance(Duration::seconds(30));
+        assert!(matches!(
+            step_up.confirm(confirmation.id),
+            Err(Error::Expired(_))
+        ));
+        let confirmation = step_up
+            .park(
+                account_id,
+                transaction_id,
+                1_001u64.into(),
+                cmd,
+                StepUpReason::Amount,
+            )
+            .unwrap();
+        clock.advance(Duration::seconds(30));
+        step_up.expire();
+        assert!(matches!(
+            step_up.confirm(confirmation.id),
+            Err(Error::Unknown(_))
+        ));
+        assert!(step_up.withdrawals.read().is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+use super::TermDepositFactory;
+use crate::{
+    domain::{account, clock::Clock, euro_cent::EuroCent, id::IdGenerator, term_deposit},
+    infra::{account::AccountFactory, leader::Leadership, lru_cache_factory::Priority},
+};
+use anyhow::Context;
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    num::NonZeroU64,
+    sync::Arc,
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::{pin, select, task, time::interval};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Configuration for the maturity processor.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Interval for checking for matured term deposits.
+    interval_secs: NonZeroU64,
+}
+
+/// Spawn processing term deposits: those reaching maturity are matured and matured or early
+/// withdrawn ones are paid out to their accounts. Events are tracked on every node, but only the
+/// leader processes term deposits. Payouts from before spawning, i.e. replayed ones, are not
+/// deposited again.
+pub fn spawn<L, A, T>(
+    config: Config,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    evt_log: L,
+    account_factory: A,
+    term_deposit_factory: T,
+    leadership: Leadership,
+) where
+    L: EvtLog,
+    A: AccountFactory,
+    T: TermDepositFactory,
+{
+    task::spawn(async move {
+        let started_at = clock.now();
+
+        let evts = evt_log
+            .evts_by_tag::<Bytes, _, _, _>(term_deposit::TERM_DEPOSIT_TAG, SeqNo::MIN, raw)
+            .await
+            .context("Cannot create events-by-tag query");
+        let evts = match evts {
+            Ok(evts) => evts,
+            Err(error) => {
+                error!(error = format!("{error:#}"), "Cannot process term deposits");
+                return;
+            }
+        };
+
+        let mut open = HashMap::<Uuid, OffsetDateTime>::new();
+        let mut in_flight = HashSet::<Uuid>::new();
+        let mut interval = interval(Duration::from_secs(config.interval_secs.get()));
+        pin!(evts);
+        loop {
+            select! {
+                evt = evts.next() => {
+                    let evt = match evt {
+                        Some(Ok((_, evt))) => evt,
+                        Some(Err(error)) => {
+                            error!(error = format!("{error:#}"), "Cannot get next event");
+                            break;
+                        }
+                        None => break,
+                    };
+                    let Ok(evt) = serde_json::from_slice::<term_deposit::Evt>(&evt) else {
+                        warn!("Cannot deserialize term deposit event");
+                        continue;
+                    };
+                    debug!(term_deposit_id = %evt.term_deposit_id(), "Processing term deposit event");
+
+                    let payout = match evt {
+                        term_deposit::Evt::Opened { id, matures_at, .. } => {
+                            open.insert(id, matures_at);
+                            None
+                        }
+
+                        term_deposit::Evt::Matured {
+                            id,
+                            account_id,
+                            amount,
+                            interest,
+                            at,
+                        } => {
+                            open.remove(&id);
+                            in_flight.remove(&id);
+                            (at >= started_at).then_some((id, account_id, amount + interest))
+                        }
+
+                        term_deposit::Evt::WithdrawnEarly {
+                            id,
+                            account_id,
+                            amount,
+                            penalty,
+                            at,
+                        } => {
+                            open.remove(&id);
+                            in_flight.remove(&id);
+                            (at >= started_at).then_some((id, account_id, amount - penalty))
+                        }
+                    };
+                    if let Some((id, account_id, amount)) = payout {
+                        if leadership.is_leader() {
+                            pay_out(&account_factory, ids.as_ref(), id, account_id, amount).await;
+                        }
+                    }
+                }
+
+                _ = interval.tick() => {
+                    if !leadership.is_leader() {
+                        continue;
+                    }
+
+                    let now = clock.now();
+                    let matured = open
+                        .iter()
+                        .filter(|(id, matures_at)| **matures_at <= now && !in_flight.contains(id))
+                        .map(|(id, _)| *id)
+                        .collect::<Vec<_>>();
+                    for id in matured {
+                        if mature(&term_deposit_factory, id).await {
+                            in_flight.insert(id);
+                        }
+                    }
+                }
+            }
+        }
+
+        error!("Maturity processor terminated");
+    });
+}
+
+/// Mature the given term deposit; returns `false` on errors, such that it is retried.
+async fn mature<T>(term_deposit_factory: &T, id: Uuid) -> bool
+where
+    T: TermDepositFactory,
+{
+    debug!(%id, "Maturing term deposit");
+
+    let matured = async {
+        term_deposit_factory
+            .get(id)
+            .await
+            .context("Cannot get TermDeposit entity")?
+            .handle_cmd(term_deposit::Cmd::Mature)
+            .await
+            .context("Cannot handle Mature command")
+    }
+    .await;
+    match matured {
+        Ok(Ok(())) => true,
+
+        Ok(Err(error)) => {
+            error!(%id, %error, "Cannot mature term deposit");
+            false
+        }
+
+        Err(error) => {
+            error!(%id, error = format!("{error:#}"), "Cannot mature term deposit");
+            false
+        }
+    }
+}
+
+async fn pay_out<A>(
+    account_factory: &A,
+    ids: &dyn IdGenerator,
+    id: Uuid,
+    account_id: Uuid,
+    amount: EuroCent,
+) where
+    A: AccountFactory,
+{
+    debug!(%id, "Paying out term deposit");
+
+    let deposited = async {
+        account_factory
+            .get_with_priority(account_id, Priority::Bulk)
+            .await
+            .context("Cannot get Account entity")?
+            .handle_cmd(account::Cmd::Deposit(ids.next_id(), amount, None, None))
+            .await
+            .context("Cannot handle Deposit command")
+    }
+    .await;
+    if !matches!(deposited, Ok(Ok(_))) {
+        error!(%id, "Cannot pay out term deposit");
+    }
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
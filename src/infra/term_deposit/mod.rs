@@ -0,0 +1,51 @@
+pub mod maturity_processor;
+
+use crate::{
+    domain::{clock::Clock, term_deposit::TermDeposit},
+    infra::{
+        lru_cache_factory::{self, Evictor, LruCacheEntityFactory, ManagedEntity},
+        namespace::EntityType,
+    },
+};
+use eventsourced::EntityRef;
+use std::{future::Future, num::NonZeroU64, sync::Arc};
+use uuid::Uuid;
+
+/// A factory for [TermDeposit]s, either creating new ones or returning existing managed ones.
+pub trait TermDepositFactory: Clone + Send + Sync + 'static {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Create a new [TermDeposit] or return an existing managed one.
+    fn get(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = Result<EntityRef<TermDeposit>, Self::Error>> + Send + '_;
+}
+
+impl TermDepositFactory for LruCacheEntityFactory<TermDeposit> {
+    type Error = lru_cache_factory::Error;
+
+    async fn get(&self, id: Uuid) -> Result<EntityRef<TermDeposit>, Self::Error> {
+        self.entity(id).await
+    }
+}
+
+impl ManagedEntity for TermDeposit {
+    const ENTITY_TYPE: EntityType = EntityType::TermDeposit;
+
+    type Settings = ();
+
+    type Observer = ();
+
+    type Ref = EntityRef<TermDeposit>;
+
+    fn create(_settings: &(), clock: Arc<dyn Clock>, _snapshot_after: Option<NonZeroU64>) -> Self {
+        TermDeposit::default().with_clock(clock)
+    }
+
+    fn observe(&self) -> Self::Observer {}
+
+    fn into_ref(entity: EntityRef<Self>, _observer: (), _evictor: Evictor) -> Self::Ref {
+        entity
+    }
+}
@@ -0,0 +1,258 @@
+use super::{Transaction, TransactionKind, TransactionsProjection};
+use crate::{
+    domain::{account, clock::Clock, id::IdGenerator},
+    infra::{
+        dead_letter::DeadLetterQueue,
+        projection::{
+            ErrorPolicies, ProjectionHandler, ProjectionRunner, Projections, RestartConfig,
+        },
+    },
+};
+use eventsourced::EvtLog;
+use parking_lot::RwLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const NAME: &str = "transactions";
+
+#[derive(Debug, Clone)]
+pub struct InMemTransactionsProjection {
+    histories: Arc<RwLock<Histories>>,
+}
+
+#[derive(Debug, Default)]
+struct Histories {
+    by_account_id: HashMap<Uuid, Vec<Transaction>>,
+    purged: HashSet<Uuid>,
+}
+
+impl InMemTransactionsProjection {
+    /// Failed events are treated according to the
+    /// [ErrorPolicy](crate::infra::projection::ErrorPolicy) for this projection from the given
+    /// [ErrorPolicies], e.g. added to the given [DeadLetterQueue] and skipped. Progress is
+    /// recorded in the given [Projections]. Failed queries are restarted according to the given
+    /// [RestartConfig].
+    pub fn spawn<L, D>(
+        restart: RestartConfig,
+        error_policies: &ErrorPolicies,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        evt_log: L,
+        dead_letter_queue: D,
+        projections: Projections,
+    ) -> Self
+    where
+        L: EvtLog,
+        D: DeadLetterQueue,
+    {
+        let histories = Arc::new(RwLock::new(Histories::default()));
+
+        ProjectionRunner::new(
+            NAME,
+            vec![account::ACCOUNT_LIFECYCLE_TAG, account::ACCOUNT_TX_TAG],
+            clock,
+            ids,
+            evt_log,
+            dead_letter_queue,
+            projections,
+        )
+        .with_error_policy(error_policies.get(NAME))
+        .with_restart(restart)
+        .spawn(TransactionsHandler(histories.clone()));
+
+        Self { histories }
+    }
+}
+
+impl TransactionsProjection for InMemTransactionsProjection {
+    async fn transactions(
+        &self,
+        account_id: Uuid,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Option<Vec<Transaction>> {
+        let histories = self.histories.read();
+        let transactions = histories.by_account_id.get(&account_id)?;
+        let start = transactions.partition_point(|transaction| transaction.at < from);
+        let end = transactions.partition_point(|transaction| transaction.at < to);
+        Some(transactions[start..end.max(start)].to_vec())
+    }
+}
+
+struct TransactionsHandler(Arc<RwLock<Histories>>);
+
+impl ProjectionHandler for TransactionsHandler {
+    type Evt = account::Evt;
+
+    fn decode(&self, evt: &[u8]) -> Result<Self::Evt, serde_json::Error> {
+        serde_json::from_slice(evt)
+    }
+
+    async fn handle(
+        &mut self,
+        _seq_no: u64,
+        evt: Self::Evt,
+    ) -> anyhow::Result<Option<OffsetDateTime>> {
+        let evt_at = evt.at();
+        self.0.write().apply(evt);
+        Ok(Some(evt_at))
+    }
+}
+
+impl Histories {
+    /// Transactions are kept ordered by timestamp, because lifecycle and transaction events are
+    /// queried separately, hence a transaction might be projected before the creation of its
+    /// account. Purged accounts are forgotten, including their transactions projected later.
+    fn apply(&mut self, evt: account::Evt) {
+        let transaction = match evt {
+            account::Evt::Created { id, .. } => {
+                if !self.purged.contains(&id) {
+                    self.by_account_id.entry(id).or_default();
+                }
+                return;
+            }
+
+            account::Evt::Purged { account_id, .. } => {
+                self.by_account_id.remove(&account_id);
+                self.purged.insert(account_id);
+                return;
+            }
+
+            account::Evt::Deposited {
+                id,
+                account_id,
+                old_balance,
+                amount,
+                value_date,
+                channel,
+                at,
+            } => Transaction {
+                id,
+                account_id,
+                kind: TransactionKind::Deposit,
+                amount,
+                balance: old_balance + amount,
+                at,
+                value_date: value_date.unwrap_or(at),
+                channel,
+            },
+
+            account::Evt::Withdrawn {
+                id,
+                account_id,
+                old_balance,
+                amount,
+                value_date,
+                channel,
+                at,
+            } => Transaction {
+                id,
+                account_id,
+                kind: TransactionKind::Withdrawal,
+                amount,
+                balance: old_balance - amount,
+                at,
+                value_date: value_date.unwrap_or(at),
+                channel,
+            },
+
+            account::Evt::NotificationPrefsSet { .. }
+            | account::Evt::LabelsSet { .. }
+            | account::Evt::HoldPlaced { .. }
+            | account::Evt::HoldReleased { .. }
+            | account::Evt::HoldCaptured { .. }
+            | account::Evt::Adjusted { .. }
+            | account::Evt::Closed { .. }
+            | account::Evt::Reopened { .. } => return,
+        };
+
+        if self.purged.contains(&transaction.account_id) {
+            return;
+        }
+        let transactions = self
+            .by_account_id
+            .entry(transaction.account_id)
+            .or_default();
+        let index = transactions.partition_point(|other| other.at <= transaction.at);
+        transactions.insert(index, transaction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    #[tokio::test]
+    async fn test_apply() {
+        let mut histories = Histories::default();
+        let account_id = Uuid::now_v7();
+        let t0 = OffsetDateTime::UNIX_EPOCH;
+
+        histories.apply(account::Evt::Withdrawn {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: 42u64.into(),
+            amount: 2u64.into(),
+            value_date: None,
+            channel: None,
+            at: t0 + Duration::seconds(2),
+        });
+        histories.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: 0u64.into(),
+            amount: 42u64.into(),
+            value_date: Some(t0),
+            channel: None,
+            at: t0 + Duration::seconds(1),
+        });
+
+        let projection = InMemTransactionsProjection {
+            histories: Arc::new(RwLock::new(histories)),
+        };
+        let transactions = projection
+            .transactions(account_id, t0, t0 + Duration::seconds(3))
+            .await
+            .unwrap();
+        let kinds = transactions
+            .iter()
+            .map(|transaction| transaction.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![TransactionKind::Deposit, TransactionKind::Withdrawal]
+        );
+        assert_eq!(transactions[0].value_date, t0);
+        assert_eq!(transactions[1].balance, 40u64.into());
+
+        let transactions = projection
+            .transactions(
+                account_id,
+                t0 + Duration::seconds(2),
+                t0 + Duration::seconds(3),
+            )
+            .await
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(projection.transactions(Uuid::now_v7(), t0, t0).await, None);
+
+        // Purged accounts are forgotten, including transactions projected later.
+        let mut histories = projection.histories.write();
+        histories.apply(account::Evt::Purged { account_id, at: t0 });
+        histories.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id,
+            old_balance: 40u64.into(),
+            amount: 2u64.into(),
+            value_date: None,
+            channel: None,
+            at: t0,
+        });
+        assert!(histories.by_account_id.is_empty());
+    }
+}
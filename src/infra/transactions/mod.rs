@@ -0,0 +1,46 @@
+pub mod in_mem_transactions_projection;
+
+use crate::domain::{channel::Channel, euro_cent::EuroCent};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// A projection of the deposits and withdrawals of all accounts to their transaction histories.
+pub trait TransactionsProjection: Clone + Send + Sync + 'static {
+    /// The [Transaction]s of the account with the given ID handled at or after `from` and before
+    /// `to`, in the order of their timestamps; `None` if the account is unknown or purged.
+    fn transactions(
+        &self,
+        account_id: Uuid,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> impl Future<Output = Option<Vec<Transaction>>> + Send + '_;
+}
+
+/// A deposit to or withdrawal from an account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Transaction {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub kind: TransactionKind,
+    pub amount: EuroCent,
+    /// The balance of the account after the transaction.
+    pub balance: EuroCent,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    /// The date the transaction takes effect, which might be back- or future-dated; defaults to
+    /// `at`.
+    #[serde(with = "time::serde::rfc3339")]
+    pub value_date: OffsetDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<Channel>,
+}
+
+/// Kind of a [Transaction].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransactionKind {
+    Deposit,
+    Withdrawal,
+}
@@ -0,0 +1,51 @@
+pub mod process_manager;
+
+use crate::{
+    domain::{clock::Clock, transfer::Transfer},
+    infra::{
+        lru_cache_factory::{self, Evictor, LruCacheEntityFactory, ManagedEntity},
+        namespace::EntityType,
+    },
+};
+use eventsourced::EntityRef;
+use std::{future::Future, num::NonZeroU64, sync::Arc};
+use uuid::Uuid;
+
+/// A factory for [Transfer]s, either creating new ones or returning existing managed ones.
+pub trait TransferFactory: Clone + Send + Sync + 'static {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Create a new [Transfer] or return an existing managed one.
+    fn get(
+        &self,
+        id: Uuid,
+    ) -> impl Future<Output = Result<EntityRef<Transfer>, Self::Error>> + Send + '_;
+}
+
+impl TransferFactory for LruCacheEntityFactory<Transfer> {
+    type Error = lru_cache_factory::Error;
+
+    async fn get(&self, id: Uuid) -> Result<EntityRef<Transfer>, Self::Error> {
+        self.entity(id).await
+    }
+}
+
+impl ManagedEntity for Transfer {
+    const ENTITY_TYPE: EntityType = EntityType::Transfer;
+
+    type Settings = ();
+
+    type Observer = ();
+
+    type Ref = EntityRef<Transfer>;
+
+    fn create(_settings: &(), clock: Arc<dyn Clock>, _snapshot_after: Option<NonZeroU64>) -> Self {
+        Transfer::default().with_clock(clock)
+    }
+
+    fn observe(&self) -> Self::Observer {}
+
+    fn into_ref(entity: EntityRef<Self>, _observer: (), _evictor: Evictor) -> Self::Ref {
+        entity
+    }
+}
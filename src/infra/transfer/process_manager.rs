@@ -0,0 +1,568 @@
+use super::TransferFactory;
+use crate::{
+    domain::{
+        account,
+        euro_cent::EuroCent,
+        transfer::{self, TransferStatus},
+    },
+    infra::{
+        account::{history::AccountHistory, AccountFactory},
+        leader::Leadership,
+        projection::RestartConfig,
+    },
+};
+use anyhow::Context;
+use bytes::Bytes;
+use eventsourced::{EvtLog, SeqNo};
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    num::NonZeroU64,
+    time::Duration,
+};
+use tokio::{
+    select, task,
+    time::{interval, sleep},
+};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Configuration for the transfer process manager.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Interval for executing the next steps of pending transfers.
+    interval_secs: NonZeroU64,
+}
+
+/// Tags of the events to be tracked.
+const TAGS: [&str; 2] = [transfer::TRANSFER_TAG, account::ACCOUNT_LIFECYCLE_TAG];
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    from_account: Uuid,
+    to_account: Uuid,
+    amount: EuroCent,
+    status: TransferStatus,
+    /// Sweeps are credited with the ID of the closure, as formerly deposited directly.
+    sweep: bool,
+}
+
+/// Spawn processing transfers: the source account is debited with the transfer ID, then the target
+/// account is credited; if that is rejected, e.g. because the target account does not exist, the
+/// source account is credited back. Each executed step is recorded with the [Transfer] before
+/// the next one is executed, such that pending transfers are resumed, e.g. after a restart; the
+/// credits have IDs derived from the transfer ID. As account commands are not idempotent, a step
+/// is only executed, if the [AccountHistory] does not yet contain its transaction, e.g. because
+/// recording it has failed before. Closed accounts with a balance to sweep are tracked, too, and
+/// swept with a transfer starting debited, with the ID of the closure. Events are tracked on every
+/// node, but only the leader executes steps. Failed queries are restarted according to the given
+/// [RestartConfig].
+///
+/// [Transfer]: crate::domain::transfer::Transfer
+pub fn spawn<L, A, H, T>(
+    config: Config,
+    restart: RestartConfig,
+    evt_log: L,
+    account_factory: A,
+    account_history: H,
+    transfer_factory: T,
+    leadership: Leadership,
+) where
+    L: EvtLog,
+    A: AccountFactory,
+    H: AccountHistory,
+    T: TransferFactory,
+{
+    task::spawn(async move {
+        let mut transfers = Transfers::default();
+        let mut interval = interval(Duration::from_secs(config.interval_secs.get()));
+        let mut checkpoints = [None::<SeqNo>; TAGS.len()];
+        let mut restarts = 0;
+        'run: loop {
+            let mut tag_evts = Vec::with_capacity(TAGS.len());
+            for (n, tag) in TAGS.iter().enumerate() {
+                let from_seq_no = checkpoints[n].map_or(SeqNo::MIN, |seq_no| seq_no.succ());
+                match evt_log
+                    .evts_by_tag::<Bytes, _, _, _>(*tag, from_seq_no, raw)
+                    .await
+                {
+                    Ok(evts) => tag_evts.push(Box::pin(evts.map(move |evt| (n, evt)))),
+
+                    Err(error) => {
+                        error!(
+                            error = format!("{error:#}"),
+                            "Cannot create events-by-tag query"
+                        );
+                        if await_restart(&restart, &mut restarts).await {
+                            continue 'run;
+                        }
+                        break 'run;
+                    }
+                }
+            }
+            let mut evts = stream::select_all(tag_evts);
+
+            loop {
+                select! {
+                    evt = evts.next() => {
+                        match evt {
+                            Some((n, Ok((seq_no, evt)))) => {
+                                checkpoints[n] = Some(seq_no);
+                                restarts = 0;
+                                transfers.track(TAGS[n], &evt);
+                            }
+
+                            Some((_, Err(error))) => {
+                                error!(error = format!("{error:#}"), "Cannot get next event");
+                                if await_restart(&restart, &mut restarts).await {
+                                    continue 'run;
+                                }
+                                break 'run;
+                            }
+
+                            None => break 'run,
+                        }
+                    }
+
+                    _ = interval.tick() => {
+                        if !leadership.is_leader() {
+                            continue;
+                        }
+
+                        let sweeps = transfers.sweeps.drain().collect::<Vec<_>>();
+                        for (id, cmd) in sweeps {
+                            if !initiate_sweep(&transfer_factory, id, cmd.clone()).await {
+                                transfers.sweeps.insert(id, cmd);
+                            }
+                        }
+
+                        let next = transfers
+                            .pending
+                            .iter()
+                            .filter(|(id, _)| !transfers.in_flight.contains(id))
+                            .map(|(id, transfer)| (*id, *transfer))
+                            .collect::<Vec<_>>();
+                        for (id, transfer) in next {
+                            let executed = execute(
+                                &account_factory,
+                                &account_history,
+                                &transfer_factory,
+                                id,
+                                transfer,
+                            )
+                            .await;
+                            if executed {
+                                transfers.in_flight.insert(id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        error!("Transfer process manager terminated");
+    });
+}
+
+/// Tracked transfers and sweeps.
+#[derive(Debug, Default)]
+struct Transfers {
+    /// Transfers not yet final.
+    pending: HashMap<Uuid, Pending>,
+
+    /// Transfers the next step of which has been executed, but not yet tracked.
+    in_flight: HashSet<Uuid>,
+
+    /// Sweeps to be initiated by closure ID.
+    sweeps: HashMap<Uuid, transfer::Cmd>,
+
+    /// IDs of initiated sweeps, such that these are not initiated again.
+    swept: HashSet<Uuid>,
+}
+
+impl Transfers {
+    /// Track the pending transfers and their status from the given event with the given tag; a
+    /// transfer is no longer in flight once its next step has been recorded.
+    fn track(&mut self, tag: &'static str, evt: &[u8]) {
+        if tag == account::ACCOUNT_LIFECYCLE_TAG {
+            match serde_json::from_slice::<account::Evt>(evt) {
+                Ok(evt) => {
+                    if let Some((id, cmd)) = sweep(evt) {
+                        if !self.swept.contains(&id) {
+                            self.sweeps.insert(id, cmd);
+                        }
+                    }
+                }
+                Err(_) => warn!("Cannot deserialize account event"),
+            }
+            return;
+        }
+
+        let Ok(evt) = serde_json::from_slice::<transfer::Evt>(evt) else {
+            warn!("Cannot deserialize transfer event");
+            return;
+        };
+        let id = evt.transfer_id();
+        debug!(transfer_id = %id, "Processing transfer event");
+
+        let status = match evt {
+            transfer::Evt::Initiated {
+                from_account,
+                to_account,
+                amount,
+                ..
+            } => {
+                let transfer = Pending {
+                    from_account,
+                    to_account,
+                    amount,
+                    status: TransferStatus::Initiated,
+                    sweep: false,
+                };
+                self.pending.insert(id, transfer);
+                return;
+            }
+            transfer::Evt::SweepInitiated {
+                from_account,
+                to_account,
+                amount,
+                ..
+            } => {
+                let transfer = Pending {
+                    from_account,
+                    to_account,
+                    amount,
+                    status: TransferStatus::Debited,
+                    sweep: true,
+                };
+                self.pending.insert(id, transfer);
+                self.sweeps.remove(&id);
+                self.swept.insert(id);
+                return;
+            }
+            transfer::Evt::Debited { .. } => TransferStatus::Debited,
+            transfer::Evt::CreditRejected { .. } => TransferStatus::Compensating,
+            transfer::Evt::DebitRejected { .. }
+            | transfer::Evt::Credited { .. }
+            | transfer::Evt::Compensated { .. }
+            | transfer::Evt::CompensationRejected { .. } => {
+                self.pending.remove(&id);
+                self.in_flight.remove(&id);
+                return;
+            }
+        };
+        if let Some(transfer) = self.pending.get_mut(&id) {
+            transfer.status = status;
+        }
+        self.in_flight.remove(&id);
+    }
+}
+
+/// The closure ID and the command for initiating the sweep of the balance of a closed account, if
+/// the given event closes an account with a non-zero balance to be swept.
+fn sweep(evt: account::Evt) -> Option<(Uuid, transfer::Cmd)> {
+    match evt {
+        account::Evt::Closed {
+            id,
+            account_id,
+            old_balance,
+            sweep_to: Some(sweep_to),
+            ..
+        } if old_balance != EuroCent::default() => {
+            let cmd = transfer::Cmd::InitiateSweep {
+                id,
+                from_account: account_id,
+                to_account: sweep_to,
+                amount: old_balance,
+            };
+            Some((id, cmd))
+        }
+
+        _ => None,
+    }
+}
+
+/// Initiate the sweep with the given ID; returns `false` on technical errors, such that it is
+/// retried.
+async fn initiate_sweep<T>(transfer_factory: &T, id: Uuid, cmd: transfer::Cmd) -> bool
+where
+    T: TransferFactory,
+{
+    let initiated = async {
+        transfer_factory
+            .get(id)
+            .await
+            .context("Cannot get Transfer entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle InitiateSweep command")
+    }
+    .await;
+    match initiated {
+        Ok(Ok(_)) => {
+            debug!(transfer_id = %id, "Initiated sweep");
+            true
+        }
+
+        // E.g. already initiated before a restart.
+        Ok(Err(error)) => {
+            debug!(transfer_id = %id, %error, "Cannot initiate sweep");
+            true
+        }
+
+        Err(error) => {
+            error!(transfer_id = %id, error = format!("{error:#}"), "Cannot initiate sweep");
+            false
+        }
+    }
+}
+
+/// Execute the next step of the given transfer, unless already executed, and record it; returns
+/// `false` on technical errors, such that it is retried.
+async fn execute<A, H, T>(
+    account_factory: &A,
+    account_history: &H,
+    transfer_factory: &T,
+    id: Uuid,
+    transfer: Pending,
+) -> bool
+where
+    A: AccountFactory,
+    H: AccountHistory,
+    T: TransferFactory,
+{
+    debug!(transfer_id = %id, status = ?transfer.status, "Executing transfer step");
+
+    let Some((account_id, tx_id, cmd)) = step(id, &transfer) else {
+        return true;
+    };
+
+    let executed = account_history
+        .contains_tx(account_id, tx_id)
+        .await
+        .context("Cannot check account history");
+    let rejection = match executed {
+        Ok(true) => {
+            debug!(transfer_id = %id, %tx_id, "Transfer step already executed");
+            None
+        }
+
+        Ok(false) => {
+            let cmd_name = cmd.name();
+            let handled = async {
+                account_factory
+                    .get(account_id)
+                    .await
+                    .context("Cannot get Account entity")?
+                    .handle_cmd(cmd)
+                    .await
+                    .with_context(|| format!("Cannot handle {cmd_name} command"))
+            }
+            .await;
+            match handled {
+                Ok(Ok(_)) => None,
+                Ok(Err(error)) => Some(error.to_string()),
+                Err(error) => {
+                    error!(
+                        transfer_id = %id,
+                        error = format!("{error:#}"),
+                        "Cannot execute transfer step"
+                    );
+                    return false;
+                }
+            }
+        }
+
+        Err(error) => {
+            error!(transfer_id = %id, error = format!("{error:#}"), "Cannot execute transfer step");
+            return false;
+        }
+    };
+
+    let cmd = match (transfer.status, rejection) {
+        (TransferStatus::Initiated, None) => transfer::Cmd::RecordDebit,
+        (TransferStatus::Initiated, Some(reason)) => {
+            warn!(transfer_id = %id, reason, "Rejecting transfer");
+            transfer::Cmd::RejectDebit(reason)
+        }
+        (TransferStatus::Debited, None) => transfer::Cmd::RecordCredit,
+        (TransferStatus::Debited, Some(reason)) => {
+            warn!(transfer_id = %id, reason, "Compensating transfer");
+            transfer::Cmd::RejectCredit(reason)
+        }
+        (_, None) => transfer::Cmd::RecordCompensation,
+        (_, Some(reason)) => {
+            error!(
+                transfer_id = %id,
+                reason,
+                "Cannot credit back source account, manual booking required"
+            );
+            transfer::Cmd::RejectCompensation(reason)
+        }
+    };
+    let recorded = async {
+        transfer_factory
+            .get(id)
+            .await
+            .context("Cannot get Transfer entity")?
+            .handle_cmd(cmd)
+            .await
+            .context("Cannot handle Transfer command")?
+            .context("Cannot record transfer step")
+    }
+    .await;
+    if let Err(error) = recorded {
+        error!(transfer_id = %id, error = format!("{error:#}"), "Cannot record transfer step");
+        return false;
+    }
+
+    true
+}
+
+/// The account ID, transaction ID and account command for the next step of the given transfer, if
+/// any.
+fn step(id: Uuid, transfer: &Pending) -> Option<(Uuid, Uuid, account::Cmd)> {
+    let amount = transfer.amount;
+    match transfer.status {
+        TransferStatus::Initiated => Some((
+            transfer.from_account,
+            id,
+            account::Cmd::Withdraw(id, amount, None, None),
+        )),
+        TransferStatus::Debited => {
+            let tx_id = if transfer.sweep {
+                id
+            } else {
+                Uuid::new_v5(&id, b"credit")
+            };
+            let cmd = account::Cmd::Deposit(tx_id, amount, None, None);
+            Some((transfer.to_account, tx_id, cmd))
+        }
+        TransferStatus::Compensating => {
+            let tx_id = Uuid::new_v5(&id, b"compensation");
+            let cmd = account::Cmd::Deposit(tx_id, amount, None, None);
+            Some((transfer.from_account, tx_id, cmd))
+        }
+        _ => None,
+    }
+}
+
+/// Whether to restart after a failed query, waiting for the backoff if so.
+async fn await_restart(restart: &RestartConfig, restarts: &mut usize) -> bool {
+    let Some(backoff) = restart.next_backoff(restarts) else {
+        return false;
+    };
+
+    warn!(
+        restarts = *restarts,
+        ?backoff,
+        "Restarting transfer process manager"
+    );
+    sleep(backoff).await;
+    true
+}
+
+fn raw(bytes: Bytes) -> Result<Bytes, Infallible> {
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::OffsetDateTime;
+
+    #[test]
+    fn test_rejected_sweep() {
+        let closure_id = Uuid::now_v7();
+        let account_id = Uuid::now_v7();
+        let sweep_to = Uuid::now_v7();
+        let at = OffsetDateTime::UNIX_EPOCH;
+        let closed = |old_balance: u64| account::Evt::Closed {
+            id: closure_id,
+            account_id,
+            old_balance: old_balance.into(),
+            sweep_to: Some(sweep_to),
+            at,
+        };
+
+        // Only non-zero balances are swept.
+        let mut transfers = Transfers::default();
+        transfers.track(
+            account::ACCOUNT_LIFECYCLE_TAG,
+            &serde_json::to_vec(&closed(0)).unwrap(),
+        );
+        assert!(transfers.sweeps.is_empty());
+        transfers.track(
+            account::ACCOUNT_LIFECYCLE_TAG,
+            &serde_json::to_vec(&closed(42)).unwrap(),
+        );
+        assert_eq!(
+            transfers.sweeps.get(&closure_id),
+            Some(&transfer::Cmd::InitiateSweep {
+                id: closure_id,
+                from_account: account_id,
+                to_account: sweep_to,
+                amount: 42u64.into(),
+            })
+        );
+
+        // Sweeps start debited and are credited with the closure ID.
+        let sweep_initiated = transfer::Evt::SweepInitiated {
+            id: closure_id,
+            from_account: account_id,
+            to_account: sweep_to,
+            amount: 42u64.into(),
+            at,
+        };
+        transfers.track(
+            transfer::TRANSFER_TAG,
+            &serde_json::to_vec(&sweep_initiated).unwrap(),
+        );
+        assert!(transfers.sweeps.is_empty());
+        let sweep = transfers.pending[&closure_id];
+        assert_eq!(sweep.status, TransferStatus::Debited);
+        assert!(matches!(
+            step(closure_id, &sweep),
+            Some((to, tx_id, account::Cmd::Deposit(..))) if to == sweep_to && tx_id == closure_id
+        ));
+
+        // Replaying the closure does not initiate the sweep again.
+        transfers.track(
+            account::ACCOUNT_LIFECYCLE_TAG,
+            &serde_json::to_vec(&closed(42)).unwrap(),
+        );
+        assert!(transfers.sweeps.is_empty());
+
+        // A rejected credit is compensated by crediting back the closed account.
+        let credit_rejected = transfer::Evt::CreditRejected {
+            id: closure_id,
+            reason: "unknown account".to_string(),
+            at,
+        };
+        transfers.track(
+            transfer::TRANSFER_TAG,
+            &serde_json::to_vec(&credit_rejected).unwrap(),
+        );
+        let sweep = transfers.pending[&closure_id];
+        assert_eq!(sweep.status, TransferStatus::Compensating);
+        assert!(matches!(
+            step(closure_id, &sweep),
+            Some((from, _, account::Cmd::Deposit(..))) if from == account_id
+        ));
+
+        // Once final, the sweep is no longer pending.
+        let compensation_rejected = transfer::Evt::CompensationRejected {
+            id: closure_id,
+            reason: "closed".to_string(),
+            at,
+        };
+        transfers.track(
+            transfer::TRANSFER_TAG,
+            &serde_json::to_vec(&compensation_rejected).unwrap(),
+        );
+        assert!(transfers.pending.is_empty());
+    }
+}
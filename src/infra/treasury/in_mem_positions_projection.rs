@@ -0,0 +1,394 @@
+use super::{CurrencyPosition, DailyFlow, Positions, PositionsProjection};
+use crate::{
+    domain::{
+        account::{self, AdjustmentDirection},
+        clock::Clock,
+        euro_cent::EuroCent,
+        id::IdGenerator,
+        money::Money,
+    },
+    infra::{
+        dead_letter::DeadLetterQueue,
+        projection::{
+            ErrorPolicies, ProjectionHandler, ProjectionRunner, ProjectionStore, Projections,
+            RestartConfig,
+        },
+    },
+};
+use eventsourced::EvtLog;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+use time::{Date, OffsetDateTime};
+use uuid::Uuid;
+
+const NAME: &str = "treasury-positions";
+
+/// All accounts are denominated in EUR.
+const CURRENCY: &str = "EUR";
+
+#[derive(Debug, Clone)]
+pub struct InMemPositionsProjection {
+    config: Config,
+    positions: Arc<RwLock<PositionsState>>,
+}
+
+#[derive(Debug, Default)]
+struct PositionsState {
+    balances: HashMap<Uuid, EuroCent>,
+    total_liabilities: Money,
+    daily_flows: BTreeMap<Date, (Money, Money)>,
+    sandbox: HashSet<Uuid>,
+}
+
+impl InMemPositionsProjection {
+    /// Failed events are treated according to the
+    /// [ErrorPolicy](crate::infra::projection::ErrorPolicy) for this projection from the given
+    /// [ErrorPolicies], e.g. added to the given [DeadLetterQueue] and skipped. Progress is
+    /// recorded in the given [Projections]. The total liabilities and the flows of the day of the
+    /// latest event are also exposed as metrics, e.g. for alerting on unusual flows. If a
+    /// [ProjectionStore] is given, snapshots are saved and the projection resumes from these.
+    /// Failed queries are restarted according to the given [RestartConfig].
+    pub fn spawn<L, D>(
+        config: Config,
+        restart: RestartConfig,
+        error_policies: &ErrorPolicies,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        evt_log: L,
+        dead_letter_queue: D,
+        projections: Projections,
+        projection_store: Option<Arc<dyn ProjectionStore>>,
+    ) -> Self
+    where
+        L: EvtLog,
+        D: DeadLetterQueue,
+    {
+        let positions = Arc::new(RwLock::new(PositionsState::default()));
+
+        ProjectionRunner::new(
+            NAME,
+            vec![account::ACCOUNT_LIFECYCLE_TAG, account::ACCOUNT_TX_TAG],
+            clock,
+            ids,
+            evt_log,
+            dead_letter_queue,
+            projections,
+        )
+        .with_store(projection_store)
+        .with_error_policy(error_policies.get(NAME))
+        .with_restart(restart)
+        .spawn(PositionsHandler(positions.clone()));
+
+        Self { config, positions }
+    }
+}
+
+/// Configuration for the [InMemPositionsProjection].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Number of most recent days for which flows are exposed.
+    daily_flow_days: usize,
+}
+
+impl PositionsProjection for InMemPositionsProjection {
+    async fn positions(&self) -> Positions {
+        let positions = self.positions.read();
+        let daily_flows = positions
+            .daily_flows
+            .iter()
+            .rev()
+            .take(self.config.daily_flow_days)
+            .map(|(date, (inflow, outflow))| daily_flow(*date, *inflow, *outflow))
+            .collect();
+        Positions {
+            total_liabilities: positions.total_liabilities,
+            currencies: vec![CurrencyPosition {
+                currency: CURRENCY,
+                liabilities: positions.total_liabilities,
+            }],
+            daily_flows,
+        }
+    }
+}
+
+struct PositionsHandler(Arc<RwLock<PositionsState>>);
+
+impl ProjectionHandler for PositionsHandler {
+    type Evt = account::Evt;
+
+    fn decode(&self, evt: &[u8]) -> Result<Self::Evt, serde_json::Error> {
+        serde_json::from_slice(evt)
+    }
+
+    async fn handle(
+        &mut self,
+        _seq_no: u64,
+        evt: Self::Evt,
+    ) -> anyhow::Result<Option<OffsetDateTime>> {
+        let evt_at = evt.at();
+        self.0.write().apply(evt);
+        Ok(Some(evt_at))
+    }
+
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        let positions = self.0.read();
+        let snapshot = PositionsSnapshot {
+            balances: positions.balances.clone(),
+            total_liabilities: positions.total_liabilities,
+            daily_flows: positions.daily_flows.clone().into_iter().collect(),
+            sandbox: positions.sandbox.clone(),
+        };
+        serde_json::to_value(snapshot).ok()
+    }
+
+    fn restore(&mut self, snapshot: serde_json::Value) -> Result<bool, serde_json::Error> {
+        let snapshot = serde_json::from_value::<PositionsSnapshot>(snapshot)?;
+        *self.0.write() = PositionsState {
+            balances: snapshot.balances,
+            total_liabilities: snapshot.total_liabilities,
+            daily_flows: snapshot.daily_flows.into_iter().collect(),
+            sandbox: snapshot.sandbox,
+        };
+        Ok(true)
+    }
+}
+
+/// Daily flows are kept as list, because dates cannot be JSON keys.
+#[derive(Debug, Serialize, Deserialize)]
+struct PositionsSnapshot {
+    balances: HashMap<Uuid, EuroCent>,
+    total_liabilities: Money,
+    daily_flows: Vec<(Date, (Money, Money))>,
+    /// Missing in snapshots taken before sandbox accounts were introduced.
+    #[serde(default)]
+    sandbox: HashSet<Uuid>,
+}
+
+impl PositionsState {
+    /// Lifecycle and transaction events are queried separately, hence their relative order is not
+    /// guaranteed: like for the account summaries, balances are taken from the transaction events,
+    /// which carry the old balance, rather than accumulated. Sandbox accounts are excluded, yet
+    /// flows of their transactions applied before their Created event cannot be told apart.
+    fn apply(&mut self, evt: account::Evt) {
+        if self.sandbox.contains(&evt.account_id()) {
+            return;
+        }
+
+        let date = evt.at().date();
+        match evt {
+            account::Evt::Created {
+                id, sandbox: true, ..
+            } => {
+                self.set_balance(id, EuroCent::default());
+                self.balances.remove(&id);
+                self.sandbox.insert(id);
+            }
+
+            account::Evt::Created {
+                id,
+                opening_balance,
+                ..
+            } => {
+                if !self.balances.contains_key(&id) {
+                    self.set_balance(id, opening_balance);
+                }
+                self.record_flow(date, opening_balance, EuroCent::default());
+            }
+
+            account::Evt::Deposited {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            } => {
+                self.set_balance(account_id, old_balance + amount);
+                self.record_flow(date, amount, EuroCent::default());
+            }
+
+            account::Evt::Withdrawn {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            }
+            | account::Evt::HoldCaptured {
+                account_id,
+                old_balance,
+                amount,
+                ..
+            } => {
+                self.set_balance(account_id, old_balance - amount);
+                self.record_flow(date, EuroCent::default(), amount);
+            }
+
+            account::Evt::Adjusted {
+                account_id,
+                old_balance,
+                direction,
+                amount,
+                ..
+            } => {
+                self.set_balance(account_id, direction.apply(old_balance, amount));
+                match direction {
+                    AdjustmentDirection::Credit => {
+                        self.record_flow(date, amount, EuroCent::default())
+                    }
+                    AdjustmentDirection::Debit => {
+                        self.record_flow(date, EuroCent::default(), amount)
+                    }
+                }
+            }
+
+            account::Evt::Closed {
+                account_id,
+                old_balance,
+                ..
+            } => {
+                self.set_balance(account_id, EuroCent::default());
+                self.record_flow(date, EuroCent::default(), old_balance);
+            }
+
+            // Closed accounts have a zero balance, hence they can simply be removed.
+            account::Evt::Purged { account_id, .. } => {
+                self.balances.remove(&account_id);
+            }
+
+            account::Evt::NotificationPrefsSet { .. }
+            | account::Evt::LabelsSet { .. }
+            | account::Evt::HoldPlaced { .. }
+            | account::Evt::HoldReleased { .. }
+            | account::Evt::Reopened { .. } => {}
+        }
+    }
+
+    fn set_balance(&mut self, id: Uuid, balance: EuroCent) {
+        let old_balance = self.balances.insert(id, balance).unwrap_or_default();
+        self.total_liabilities =
+            self.total_liabilities - Money::from(old_balance) + Money::from(balance);
+        metrics::gauge!(
+            "treasury_liabilities_cents",
+            self.total_liabilities.cents() as f64,
+            "currency" => CURRENCY
+        );
+    }
+
+    fn record_flow(&mut self, date: Date, inflow: EuroCent, outflow: EuroCent) {
+        let flow = self.daily_flows.entry(date).or_default();
+        flow.0 = flow.0 + Money::from(inflow);
+        flow.1 = flow.1 + Money::from(outflow);
+        let flow = daily_flow(date, flow.0, flow.1);
+        metrics::gauge!("treasury_daily_inflow_cents", flow.inflow.cents() as f64);
+        metrics::gauge!("treasury_daily_outflow_cents", flow.outflow.cents() as f64);
+        metrics::gauge!(
+            "treasury_daily_net_flow_cents",
+            flow.net_flow.cents() as f64
+        );
+    }
+}
+
+fn daily_flow(date: Date, inflow: Money, outflow: Money) -> DailyFlow {
+    DailyFlow {
+        date,
+        inflow,
+        outflow,
+        net_flow: inflow - outflow,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::iban::{BankCode, Iban};
+
+    #[test]
+    fn test_apply() {
+        let mut positions = PositionsState::default();
+        let id = Uuid::now_v7();
+        let at = OffsetDateTime::UNIX_EPOCH;
+
+        // Transaction events might be applied before the Created event.
+        positions.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 100u64.into(),
+            amount: 42u64.into(),
+            value_date: None,
+            channel: None,
+            at,
+        });
+        positions.apply(account::Evt::Created {
+            id,
+            tenant: Default::default(),
+            customer: None,
+            iban: Iban::for_account(BankCode::try_from(12345678).unwrap(), id),
+            currency: Default::default(),
+            opening_balance: 100u64.into(),
+            external_ref: None,
+            product: Default::default(),
+            sandbox: false,
+            at,
+        });
+        positions.apply(account::Evt::Withdrawn {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 142u64.into(),
+            amount: 50u64.into(),
+            value_date: None,
+            channel: None,
+            at,
+        });
+
+        assert_eq!(positions.total_liabilities, Money::from(92));
+        let (inflow, outflow) = positions.daily_flows[&at.date()];
+        assert_eq!(
+            daily_flow(at.date(), inflow, outflow).net_flow,
+            Money::from(92)
+        );
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let mut positions = PositionsState::default();
+        let id = Uuid::now_v7();
+        let at = OffsetDateTime::UNIX_EPOCH;
+
+        positions.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 0u64.into(),
+            amount: 42u64.into(),
+            value_date: None,
+            channel: None,
+            at,
+        });
+        positions.apply(account::Evt::Created {
+            id,
+            tenant: Default::default(),
+            customer: None,
+            iban: Iban::for_account(BankCode::try_from(12345678).unwrap(), id),
+            currency: Default::default(),
+            opening_balance: 0u64.into(),
+            external_ref: None,
+            product: Default::default(),
+            sandbox: true,
+            at,
+        });
+        positions.apply(account::Evt::Deposited {
+            id: Uuid::now_v7(),
+            account_id: id,
+            old_balance: 42u64.into(),
+            amount: 42u64.into(),
+            value_date: None,
+            channel: None,
+            at,
+        });
+
+        assert_eq!(positions.total_liabilities, Money::from(0));
+        assert!(positions.balances.is_empty());
+    }
+}
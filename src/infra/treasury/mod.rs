@@ -0,0 +1,47 @@
+pub mod in_mem_positions_projection;
+
+use crate::domain::money::Money;
+use serde::{Serialize, Serializer};
+use std::future::Future;
+use time::Date;
+
+/// A projection of all transactions to the bank-level [Positions].
+pub trait PositionsProjection: Clone + Send + Sync + 'static {
+    /// The current [Positions].
+    fn positions(&self) -> impl Future<Output = Positions> + Send + '_;
+}
+
+/// Bank-level positions: the total customer liabilities, i.e. the sum of all account balances,
+/// broken down per currency, and the daily flows. Sums are [Money], hence cannot overflow.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Positions {
+    pub total_liabilities: Money,
+    pub currencies: Vec<CurrencyPosition>,
+    pub daily_flows: Vec<DailyFlow>,
+}
+
+/// Customer liabilities in a single currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CurrencyPosition {
+    pub currency: &'static str,
+    pub liabilities: Money,
+}
+
+/// Inflows, i.e. opening balances and deposits, and outflows, i.e. withdrawals and captured
+/// holds, of a single day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DailyFlow {
+    #[serde(serialize_with = "serialize_date")]
+    pub date: Date,
+    pub inflow: Money,
+    pub outflow: Money,
+    pub net_flow: Money,
+}
+
+/// Serialize dates like 2023-06-30.
+fn serialize_date<S>(date: &Date, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(date)
+}
@@ -6,10 +6,67 @@
 #![feature(return_position_impl_trait_in_trait)]
 #![feature(type_alias_impl_trait)]
 
+mod application;
 mod domain;
 mod infra;
 
-use crate::infra::account::in_mem_ids_projection::InMemAccountIdsProjection;
+use crate::{
+    application::{
+        analytics::{self, Analytics},
+        queries::Queries,
+    },
+    domain::{
+        account::{self, Account},
+        clock::{Clock, SystemClock},
+        consent::Consent,
+        dispute::Dispute,
+        id::{IdGenerator, UuidV7Generator},
+        loan::Loan,
+        mandate::Mandate,
+        redaction,
+        state_machine::Format,
+        term_deposit::TermDeposit,
+        transfer::Transfer,
+    },
+    infra::{
+        account::{
+            data_export::EvtLogDataExporter,
+            history::EvtLogAccountHistory,
+            in_mem_summaries_projection::{self, InMemAccountSummariesProjection},
+            portability::EvtLogAccountPorter,
+            AccountSummaries, AccountTenants,
+        },
+        backfill, backup,
+        books::BookKeeper,
+        cluster::{self, Cluster},
+        cmd_metrics,
+        dead_letter::{in_mem_dead_letter_queue::InMemDeadLetterQueue, DeadLetterStore},
+        dispute::deadline_processor,
+        gl_export::{self, GlExport},
+        leader::Leadership,
+        ledger::in_mem_ledger_projection::InMemLedgerProjection,
+        loan::servicer,
+        lru_cache_factory::{self, LruCacheEntityFactory, SerdeJsonCodec},
+        mandate::collection_processor,
+        migration,
+        namespace::{self, Namespace},
+        notification::{self, log_notifier::LogNotifier},
+        projection::{self, ProjectionStore, Projections},
+        quote::{self, Quotes},
+        receipt::{self, Receipts},
+        reporting::{self, Reporter},
+        retention::{self, Retention},
+        server::mode::Mode,
+        settlement::{self, Settlement},
+        snapshot_store::LenientSnapshotStore,
+        standby::Standby,
+        step_up::{self, LogStepUpAuth, StepUp},
+        term_deposit::maturity_processor,
+        transactions::in_mem_transactions_projection::InMemTransactionsProjection,
+        transfer::process_manager,
+        treasury::in_mem_positions_projection::{self, InMemPositionsProjection},
+    },
+};
 use anyhow::{Context, Result};
 use configured::Configured;
 #[cfg(feature = "nats")]
@@ -18,12 +75,20 @@ use eventsourced_nats::{NatsEvtLog, NatsEvtLogConfig, NatsSnapshotStore, NatsSna
 use eventsourced_postgres::{
     PostgresEvtLog, PostgresEvtLogConfig, PostgresSnapshotStore, PostgresSnapshotStoreConfig,
 };
-use infra::{
-    account::lru_cache_factory::{self, LruCacheAccountFactory},
-    server,
-};
+use futures::future::{self, Either};
+#[cfg(feature = "dynamodb")]
+use infra::dynamodb::{self, DynamoEvtLog, DynamoSnapshotStore};
+#[cfg(feature = "nats")]
+use infra::invalidation::{self, Invalidation};
+#[cfg(feature = "nats")]
+use infra::leader::nats_kv_leader_election;
+use infra::server;
+#[cfg(feature = "sqlite")]
+use infra::sqlite::{self, SqliteEvtLog, SqliteSnapshotStore};
+use metrics_exporter_prometheus::PrometheusBuilder;
 use serde::Deserialize;
-use std::{error::Error, future::Future};
+use std::{error::Error, fs, future::Future, path::Path, sync::Arc};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use tokio::{select, signal};
 use tracing::{debug, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
@@ -33,36 +98,149 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 pub struct Config {
     server: server::Config,
 
+    redaction: redaction::Config,
+
+    #[serde(default)]
+    cmd_metrics: cmd_metrics::Config,
+
     #[cfg(feature = "nats")]
     evt_log: NatsEvtLogConfig,
     #[cfg(feature = "postgres")]
     evt_log: PostgresEvtLogConfig,
+    #[cfg(feature = "sqlite")]
+    evt_log: sqlite::Config,
+    #[cfg(feature = "dynamodb")]
+    evt_log: dynamodb::EvtLogConfig,
 
     #[cfg(feature = "nats")]
     snapshot_store: NatsSnapshotStoreConfig,
     #[cfg(feature = "postgres")]
     snapshot_store: PostgresSnapshotStoreConfig,
+    #[cfg(feature = "sqlite")]
+    snapshot_store: sqlite::Config,
+    #[cfg(feature = "dynamodb")]
+    snapshot_store: dynamodb::SnapshotStoreConfig,
+
+    entity_ids: namespace::Config,
 
     account_factory: lru_cache_factory::Config,
+
+    #[serde(default)]
+    max_balances: account::MaxBalances,
+
+    consent_factory: lru_cache_factory::Config,
+
+    mandate_factory: lru_cache_factory::Config,
+
+    collection_processor: collection_processor::Config,
+
+    loan_factory: lru_cache_factory::Config,
+
+    loan_servicer: servicer::Config,
+
+    term_deposit_factory: lru_cache_factory::Config,
+
+    maturity_processor: maturity_processor::Config,
+
+    dispute_factory: lru_cache_factory::Config,
+
+    dispute_deadline_processor: deadline_processor::Config,
+
+    transfer_factory: lru_cache_factory::Config,
+
+    transfer_processor: process_manager::Config,
+
+    account_summaries_projection: in_mem_summaries_projection::Config,
+
+    treasury_positions_projection: in_mem_positions_projection::Config,
+
+    projection_restart: projection::RestartConfig,
+
+    #[serde(default)]
+    projection_error_policies: projection::ErrorPolicies,
+
+    reporting: reporting::Config,
+
+    gl_export: Option<gl_export::Config>,
+
+    retention: Option<retention::Config>,
+
+    settlement: settlement::Config,
+
+    quotes: quote::Config,
+
+    step_up: Option<step_up::Config>,
+
+    receipts: receipt::Config,
+
+    analytics: analytics::Config,
+
+    #[cfg(feature = "sled")]
+    projection_store: Option<infra::projection::sled_projection_store::Config>,
+
+    #[cfg(feature = "sled")]
+    dead_letter_store: Option<infra::dead_letter::sled_dead_letter_store::Config>,
+
+    cluster: Option<cluster::Config>,
+
+    #[cfg(feature = "nats")]
+    leader_election: Option<nats_kv_leader_election::Config>,
+
+    #[cfg(feature = "nats")]
+    invalidation: Option<invalidation::Config>,
+
+    #[cfg(feature = "auth")]
+    auth: infra::auth::Config,
+
+    #[cfg(feature = "oidc")]
+    oidc: Option<infra::oidc::Config>,
+
+    #[cfg(feature = "mtls")]
+    mtls: Option<server::mtls::Config>,
+
+    signing: Option<server::signing::Config>,
+
+    backfill: Option<BackfillConfig>,
+
+    backup: Option<backup::Config>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BackfillConfig {
+    #[serde(flatten)]
+    backfill: backfill::Config,
+
+    #[cfg(feature = "nats")]
+    target_evt_log: NatsEvtLogConfig,
+    #[cfg(feature = "postgres")]
+    target_evt_log: PostgresEvtLogConfig,
+    #[cfg(feature = "sqlite")]
+    target_evt_log: sqlite::Config,
+    #[cfg(feature = "dynamodb")]
+    target_evt_log: dynamodb::EvtLogConfig,
 }
 
 pub async fn run() -> Result<()> {
     // Load configuration.
-    let config = Config::load();
-    if let Err(error) = &config {
-        eprintln!(
-            "rusty-bank exited with ERROR:\n\tCannot load configuration\n\t{error}\n\t{:?}",
-            error.source()
-        );
-    };
-    let config = config?;
+    let config = load_config()?;
 
-    // Initialize tracing.
+    // Initialize redaction and tracing.
+    redaction::init(config.redaction.clone());
     init_tracing()?;
 
     // Log configuration.
     debug!(?config, "Starting");
 
+    // Initialize metrics.
+    cmd_metrics::init(config.cmd_metrics);
+    let metrics_handle = PrometheusBuilder::new()
+        .install_recorder()
+        .context("Cannot install metrics recorder")?;
+
+    // Create clock.
+    let clock = Arc::new(SystemClock);
+
     // Create event log.
     #[cfg(feature = "nats")]
     let evt_log = NatsEvtLog::new(config.evt_log)
@@ -72,6 +250,14 @@ pub async fn run() -> Result<()> {
     let evt_log = PostgresEvtLog::new(config.evt_log)
         .await
         .context("Cannot create event log")?;
+    #[cfg(feature = "sqlite")]
+    let evt_log = SqliteEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "dynamodb")]
+    let evt_log = DynamoEvtLog::new(config.evt_log, clock.clone())
+        .await
+        .context("Cannot create event log")?;
 
     // Create snapshot store.
     #[cfg(feature = "nats")]
@@ -82,25 +268,457 @@ pub async fn run() -> Result<()> {
     let snapshot_store = PostgresSnapshotStore::new(config.snapshot_store)
         .await
         .context("Cannot create snapshot store")?;
+    #[cfg(feature = "sqlite")]
+    let snapshot_store = SqliteSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    #[cfg(feature = "dynamodb")]
+    let snapshot_store = DynamoSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    // Snapshots which cannot be decoded, e.g. taken before account snapshots carried their
+    // sequence number, are ignored, such that the entities are recovered from their events.
+    let snapshot_store = LenientSnapshotStore::new(snapshot_store);
+
+    // Create Cluster, if configured.
+    let cluster = config
+        .cluster
+        .map(Cluster::new)
+        .transpose()
+        .context("Cannot create cluster")?;
+
+    // Elect leader, if configured, else this single instance is the leader.
+    #[cfg(feature = "nats")]
+    let leadership = match config.leader_election {
+        Some(config) => nats_kv_leader_election::spawn(config)
+            .await
+            .context("Cannot spawn leader election")?,
+        None => Leadership::standalone(),
+    };
+    #[cfg(not(feature = "nats"))]
+    let leadership = Leadership::standalone();
+
+    // Create IdGenerator.
+    let ids: Arc<dyn IdGenerator> = Arc::new(UuidV7Generator::new(clock.clone()));
+
+    // Spawn Standby, if in standby mode, such that singleton tasks only run once promoted.
+    let standby = (config.server.mode() == Mode::Standby)
+        .then(|| Standby::spawn(clock.clone(), evt_log.clone()));
+    let leadership = match &standby {
+        Some(standby) => leadership.and(standby.promoted()),
+        None => leadership,
+    };
+
+    // Create Auth.
+    #[cfg(feature = "auth")]
+    let auth = infra::auth::Auth::new(config.auth, clock.clone(), ids.clone());
+
+    // Discover OIDC issuer, if configured.
+    #[cfg(feature = "oidc")]
+    let oidc = match config.oidc {
+        Some(config) => Some(
+            infra::oidc::Oidc::discover(config)
+                .await
+                .context("Cannot discover OIDC issuer")?,
+        ),
+        None => None,
+    };
+
+    // Create Namespace for entity IDs.
+    let namespace = Namespace::new(config.entity_ids);
+
+    // Create DeadLetterQueue, persisting dead letters if configured.
+    #[cfg(feature = "sled")]
+    let dead_letter_store = config
+        .dead_letter_store
+        .map(infra::dead_letter::sled_dead_letter_store::SledDeadLetterStore::open)
+        .transpose()
+        .context("Cannot open dead letter store")?
+        .map(|store| Arc::new(store) as Arc<dyn DeadLetterStore>);
+    #[cfg(not(feature = "sled"))]
+    let dead_letter_store = None::<Arc<dyn DeadLetterStore>>;
+    let dead_letter_queue =
+        InMemDeadLetterQueue::new(dead_letter_store).context("Cannot create dead letter queue")?;
+
+    // Create Projections.
+    let projections = Projections::default();
 
-    // Create AccountFactory.
-    let account_factory = LruCacheAccountFactory::spawn(
-        config.account_factory,
+    // Open ProjectionStore, if configured.
+    #[cfg(feature = "sled")]
+    let projection_store = config
+        .projection_store
+        .map(infra::projection::sled_projection_store::SledProjectionStore::open)
+        .transpose()
+        .context("Cannot open projection store")?
+        .map(|store| Arc::new(store) as Arc<dyn ProjectionStore>);
+    #[cfg(not(feature = "sled"))]
+    let projection_store = None::<Arc<dyn ProjectionStore>>;
+
+    // Create AccountSummariesProjection and AccountFactory; without queries, account summaries are
+    // taken from the entities.
+    let (account_summaries, account_summaries_projection_terminated, account_factory) =
+        if config.server.mode().handles_queries() {
+            let (projection, terminated) = InMemAccountSummariesProjection::new(
+                config.account_summaries_projection,
+                config.projection_restart,
+                &config.projection_error_policies,
+                clock.clone(),
+                ids.clone(),
+                evt_log.clone(),
+                dead_letter_queue.clone(),
+                projections.clone(),
+                projection_store.clone(),
+            )
+            .await;
+            let account_factory = LruCacheEntityFactory::<Account>::spawn(
+                config.account_factory,
+                namespace,
+                config.max_balances,
+                clock.clone(),
+                evt_log.clone(),
+                snapshot_store.clone(),
+                SerdeJsonCodec,
+                AccountTenants(projection.clone()),
+            )
+            .await;
+            (
+                AccountSummaries::Projection(projection),
+                Either::Left(terminated),
+                account_factory,
+            )
+        } else {
+            let account_factory = LruCacheEntityFactory::<Account>::spawn(
+                config.account_factory,
+                namespace,
+                config.max_balances,
+                clock.clone(),
+                evt_log.clone(),
+                snapshot_store.clone(),
+                SerdeJsonCodec,
+                (),
+            )
+            .await;
+            (
+                AccountSummaries::Entities(account_factory.clone()),
+                Either::Right(future::pending()),
+                account_factory,
+            )
+        };
+
+    // Invalidate cached accounts mutated by other instances, if configured.
+    #[cfg(feature = "nats")]
+    if let Some(config) = config.invalidation {
+        Invalidation::connect(config, ids.clone())
+            .await?
+            .spawn(account_factory.clone())
+            .await
+            .context("Cannot spawn invalidation")?;
+    }
+
+    // Create ConsentFactory.
+    let consent_factory = LruCacheEntityFactory::<Consent>::spawn(
+        config.consent_factory,
+        namespace,
+        (),
+        clock.clone(),
         evt_log.clone(),
         snapshot_store.clone(),
+        SerdeJsonCodec,
+        (),
     )
     .await;
 
-    // Create AccountIdsProjection.
-    let (account_ids_projection, account_ids_projection_terminated) =
-        InMemAccountIdsProjection::new(evt_log).await;
+    // Create MandateFactory.
+    let mandate_factory = LruCacheEntityFactory::<Mandate>::spawn(
+        config.mandate_factory,
+        namespace,
+        (),
+        clock.clone(),
+        evt_log.clone(),
+        snapshot_store.clone(),
+        SerdeJsonCodec,
+        (),
+    )
+    .await;
+
+    // Create LoanFactory.
+    let loan_factory = LruCacheEntityFactory::<Loan>::spawn(
+        config.loan_factory,
+        namespace,
+        (),
+        clock.clone(),
+        evt_log.clone(),
+        snapshot_store.clone(),
+        SerdeJsonCodec,
+        (),
+    )
+    .await;
+
+    // Create TermDepositFactory.
+    let term_deposit_factory = LruCacheEntityFactory::<TermDeposit>::spawn(
+        config.term_deposit_factory,
+        namespace,
+        (),
+        clock.clone(),
+        evt_log.clone(),
+        snapshot_store.clone(),
+        SerdeJsonCodec,
+        (),
+    )
+    .await;
+
+    // Create DisputeFactory.
+    let dispute_factory = LruCacheEntityFactory::<Dispute>::spawn(
+        config.dispute_factory,
+        namespace,
+        (),
+        clock.clone(),
+        evt_log.clone(),
+        snapshot_store.clone(),
+        SerdeJsonCodec,
+        (),
+    )
+    .await;
+
+    // Create TransferFactory.
+    let transfer_factory = LruCacheEntityFactory::<Transfer>::spawn(
+        config.transfer_factory,
+        namespace,
+        (),
+        clock.clone(),
+        evt_log.clone(),
+        snapshot_store.clone(),
+        SerdeJsonCodec,
+        (),
+    )
+    .await;
+
+    // Create DataExporter.
+    let data_exporter = EvtLogDataExporter::new(evt_log.clone(), namespace, clock.clone());
+
+    // Create AccountHistory.
+    let account_history = EvtLogAccountHistory::new(evt_log.clone(), namespace, clock.clone());
+
+    // Create AccountPorter.
+    let account_porter = EvtLogAccountPorter::new(
+        evt_log.clone(),
+        snapshot_store.clone(),
+        namespace,
+        clock.clone(),
+    );
+
+    // Spawn alerting and background processing issuing commands, unless read-only.
+    if config.server.mode().handles_cmds() {
+        notification::spawn(
+            clock.clone(),
+            evt_log.clone(),
+            LogNotifier,
+            leadership.clone(),
+        );
+
+        collection_processor::spawn(
+            config.collection_processor,
+            clock.clone(),
+            evt_log.clone(),
+            account_factory.clone(),
+            mandate_factory.clone(),
+            leadership.clone(),
+        );
+
+        servicer::spawn(
+            config.loan_servicer,
+            clock.clone(),
+            ids.clone(),
+            evt_log.clone(),
+            account_factory.clone(),
+            loan_factory.clone(),
+            leadership.clone(),
+        );
+
+        maturity_processor::spawn(
+            config.maturity_processor,
+            clock.clone(),
+            ids.clone(),
+            evt_log.clone(),
+            account_factory.clone(),
+            term_deposit_factory.clone(),
+            leadership.clone(),
+        );
+
+        deadline_processor::spawn(
+            config.dispute_deadline_processor,
+            clock.clone(),
+            ids.clone(),
+            evt_log.clone(),
+            account_factory.clone(),
+            dispute_factory.clone(),
+            leadership.clone(),
+        );
+
+        process_manager::spawn(
+            config.transfer_processor,
+            config.projection_restart,
+            evt_log.clone(),
+            account_factory.clone(),
+            account_history.clone(),
+            transfer_factory.clone(),
+            leadership.clone(),
+        );
+    }
+
+    // Spawn purging of long-closed accounts, if configured and not read-only.
+    let retention = config
+        .retention
+        .filter(|_| config.server.mode().handles_cmds())
+        .map(|config| {
+            Retention::spawn(
+                config,
+                clock.clone(),
+                evt_log.clone(),
+                namespace,
+                account_summaries.clone(),
+                account_factory.clone(),
+                leadership.clone(),
+            )
+        });
+
+    // Spawn treasury PositionsProjection.
+    let positions_projection = InMemPositionsProjection::spawn(
+        config.treasury_positions_projection,
+        config.projection_restart,
+        &config.projection_error_policies,
+        clock.clone(),
+        ids.clone(),
+        evt_log.clone(),
+        dead_letter_queue.clone(),
+        projections.clone(),
+        projection_store,
+    );
+
+    // Spawn LedgerProjection.
+    let ledger_projection = InMemLedgerProjection::spawn(
+        config.projection_restart,
+        &config.projection_error_policies,
+        clock.clone(),
+        ids.clone(),
+        evt_log.clone(),
+        dead_letter_queue.clone(),
+        projections.clone(),
+    );
+
+    // Spawn TransactionsProjection.
+    let transactions_projection = InMemTransactionsProjection::spawn(
+        config.projection_restart,
+        &config.projection_error_policies,
+        clock.clone(),
+        ids.clone(),
+        evt_log.clone(),
+        dead_letter_queue.clone(),
+        projections.clone(),
+    );
+
+    // Spawn end-of-day settlement of merchant accounts, unless read-only.
+    let settlement = config.server.mode().handles_cmds().then(|| {
+        Settlement::spawn(
+            config.settlement,
+            clock.clone(),
+            evt_log.clone(),
+            ledger_projection.clone(),
+            account_factory.clone(),
+            leadership.clone(),
+        )
+    });
+
+    // Create Reporter.
+    let reporter = Reporter::spawn(
+        config.reporting,
+        clock.clone(),
+        ids.clone(),
+        ledger_projection.clone(),
+        leadership.clone(),
+    );
+
+    // Create BookKeeper.
+    let book_keeper = BookKeeper::spawn(
+        namespace,
+        clock.clone(),
+        evt_log.clone(),
+        snapshot_store,
+        ledger_projection.clone(),
+    )
+    .await
+    .context("Cannot create book keeper")?;
+    let book_keeper = match config.gl_export {
+        Some(config) => {
+            book_keeper.with_gl_export(GlExport::csv(config, clock.clone(), ids.clone()))
+        }
+        None => book_keeper,
+    };
+
+    // Create Receipts.
+    let receipts = Receipts::new(config.receipts, ledger_projection.clone());
+
+    // Create StepUp, if configured.
+    let step_up = config
+        .step_up
+        .map(|config| StepUp::spawn(config, clock.clone(), ids.clone(), Arc::new(LogStepUpAuth)));
+
+    // Create Quotes.
+    let quotes = Quotes::new(config.quotes, clock.clone(), ids.clone());
+
+    // Create Analytics.
+    let analytics = Analytics::new(config.analytics, ledger_projection.clone(), clock.clone());
+
+    // Create Queries for the read models.
+    let queries = Queries::new(
+        positions_projection,
+        ledger_projection,
+        transactions_projection,
+    );
 
     // Run server.
+    let components = server::Components {
+        account_summaries_projection: account_summaries,
+        account_factory,
+        consent_factory,
+        mandate_factory,
+        loan_factory,
+        term_deposit_factory,
+        dispute_factory,
+        transfer_factory,
+        queries,
+        analytics,
+        reporter,
+        retention,
+        settlement,
+        standby,
+        book_keeper,
+        quotes,
+        step_up,
+        receipts,
+        data_exporter,
+        account_history,
+        account_porter,
+        dead_letter_queue,
+        evt_log,
+        projections,
+        cluster,
+        ids,
+        clock,
+        leadership,
+        #[cfg(feature = "auth")]
+        auth,
+        #[cfg(feature = "oidc")]
+        oidc,
+        #[cfg(feature = "mtls")]
+        mtls: config.mtls,
+        signing: config.signing,
+        metrics_handle,
+    };
     let server = server::run(
         config.server,
-        account_ids_projection,
-        account_factory,
-        shutdown_signal(account_ids_projection_terminated),
+        components,
+        shutdown_signal(account_summaries_projection_terminated),
     );
     info!("Started");
     server.await?;
@@ -108,6 +726,347 @@ pub async fn run() -> Result<()> {
     Ok(())
 }
 
+/// Import legacy account data from the file given as the last argument, which is read as CSV if
+/// its extension is `csv`, else as NDJSON. With `--dry-run` the data is only validated. Must not
+/// run while the server is running.
+pub async fn import<A>(args: A) -> Result<()>
+where
+    A: IntoIterator<Item = String>,
+{
+    let mut dry_run = false;
+    let mut path = None;
+    for arg in args {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.context("Missing file to import")?;
+    let format = if path.ends_with(".csv") {
+        migration::Format::Csv
+    } else {
+        migration::Format::Ndjson
+    };
+
+    // Load configuration.
+    let config = load_config()?;
+
+    // Initialize redaction and tracing.
+    redaction::init(config.redaction.clone());
+    init_tracing()?;
+
+    // Read and validate legacy data.
+    let data = fs::read_to_string(&path).context(format!("Cannot read file {path}"))?;
+    let records = migration::parse(&data, format)?;
+    let accounts = migration::plan(records, config.server.bank_code())?;
+
+    // Create event log.
+    #[cfg(feature = "nats")]
+    let evt_log = NatsEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "postgres")]
+    let evt_log = PostgresEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "sqlite")]
+    let evt_log = SqliteEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "dynamodb")]
+    let evt_log = DynamoEvtLog::new(config.evt_log, Arc::new(SystemClock))
+        .await
+        .context("Cannot create event log")?;
+
+    let report = migration::import(
+        evt_log,
+        Namespace::new(config.entity_ids),
+        accounts,
+        dry_run,
+    )
+    .await?;
+    println!(
+        "{} {} accounts with {} events, skipped {} events already imported",
+        if dry_run { "Validated" } else { "Imported" },
+        report.accounts,
+        report.evts,
+        report.skipped
+    );
+
+    Ok(())
+}
+
+/// Backfill the account events from the configured event log into the target event log configured
+/// in the `backfill` section, retagging and upcasting them on the way. With `--dry-run` the events
+/// are only read and transformed. Must not run while the server is writing to the target.
+pub async fn backfill<A>(args: A) -> Result<()>
+where
+    A: IntoIterator<Item = String>,
+{
+    let dry_run = args.into_iter().any(|arg| arg == "--dry-run");
+
+    // Load configuration.
+    let config = load_config()?;
+    let backfill_config = config.backfill.context("Missing backfill configuration")?;
+
+    // Initialize redaction and tracing.
+    redaction::init(config.redaction.clone());
+    init_tracing()?;
+
+    // Create source and target event logs.
+    #[cfg(feature = "nats")]
+    let evt_log = NatsEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "nats")]
+    let target_evt_log = NatsEvtLog::new(backfill_config.target_evt_log)
+        .await
+        .context("Cannot create target event log")?;
+    #[cfg(feature = "postgres")]
+    let evt_log = PostgresEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "sqlite")]
+    let evt_log = SqliteEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "dynamodb")]
+    let evt_log = DynamoEvtLog::new(config.evt_log, Arc::new(SystemClock))
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "postgres")]
+    let target_evt_log = PostgresEvtLog::new(backfill_config.target_evt_log)
+        .await
+        .context("Cannot create target event log")?;
+    #[cfg(feature = "sqlite")]
+    let target_evt_log = SqliteEvtLog::new(backfill_config.target_evt_log)
+        .await
+        .context("Cannot create target event log")?;
+    #[cfg(feature = "dynamodb")]
+    let target_evt_log = DynamoEvtLog::new(backfill_config.target_evt_log, Arc::new(SystemClock))
+        .await
+        .context("Cannot create target event log")?;
+
+    let report = backfill::backfill(
+        backfill_config.backfill,
+        Namespace::new(config.entity_ids),
+        evt_log,
+        target_evt_log,
+        dry_run,
+    )
+    .await?;
+    println!(
+        "{} {} accounts with {} events, skipped {} events already backfilled",
+        if dry_run { "Checked" } else { "Backfilled" },
+        report.accounts,
+        report.evts,
+        report.skipped
+    );
+
+    Ok(())
+}
+
+/// Back up the events and snapshots of all accounts into the directory given as the last argument,
+/// as configured in the `backup` section. Can run while the server is running.
+pub async fn backup<A>(args: A) -> Result<()>
+where
+    A: IntoIterator<Item = String>,
+{
+    let dir = args
+        .into_iter()
+        .last()
+        .context("Missing backup directory")?;
+
+    // Load configuration.
+    let config = load_config()?;
+    let backup_config = config.backup.context("Missing backup configuration")?;
+
+    // Initialize redaction and tracing.
+    redaction::init(config.redaction.clone());
+    init_tracing()?;
+
+    // Create event log.
+    #[cfg(feature = "nats")]
+    let evt_log = NatsEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "postgres")]
+    let evt_log = PostgresEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "sqlite")]
+    let evt_log = SqliteEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "dynamodb")]
+    let evt_log = DynamoEvtLog::new(config.evt_log, Arc::new(SystemClock))
+        .await
+        .context("Cannot create event log")?;
+
+    // Create snapshot store.
+    #[cfg(feature = "nats")]
+    let snapshot_store = NatsSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    #[cfg(feature = "postgres")]
+    let snapshot_store = PostgresSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    #[cfg(feature = "sqlite")]
+    let snapshot_store = SqliteSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    #[cfg(feature = "dynamodb")]
+    let snapshot_store = DynamoSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    let snapshot_store = LenientSnapshotStore::new(snapshot_store);
+
+    // Create clock.
+    let clock = SystemClock;
+
+    let manifest = backup::backup(
+        backup_config,
+        Namespace::new(config.entity_ids),
+        evt_log,
+        snapshot_store,
+        Path::new(&dir),
+        clock.now(),
+    )
+    .await?;
+    println!(
+        "Backed up {} accounts with {} events and {} snapshots into {dir}",
+        manifest.heads.len(),
+        manifest
+            .heads
+            .values()
+            .map(|head| head.get())
+            .sum::<usize>(),
+        manifest.snapshots
+    );
+
+    Ok(())
+}
+
+/// Restore the events and snapshots of all accounts from the backup in the directory given as the
+/// last argument into the configured, empty event log and snapshot store. With `--until` followed
+/// by an RFC 3339 timestamp only the events up to this time are restored. Persisted projections
+/// are cleared, such that they are rebuilt on the next start. Must not run while the server is
+/// running.
+pub async fn restore<A>(args: A) -> Result<()>
+where
+    A: IntoIterator<Item = String>,
+{
+    let mut until = None;
+    let mut dir = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--until" => {
+                let timestamp = args.next().context("Missing timestamp for --until")?;
+                let timestamp = OffsetDateTime::parse(&timestamp, &Rfc3339)
+                    .context(format!("Invalid timestamp {timestamp}, expected RFC 3339"))?;
+                until = Some(timestamp);
+            }
+            _ => dir = Some(arg),
+        }
+    }
+    let dir = dir.context("Missing backup directory")?;
+
+    // Load configuration.
+    let config = load_config()?;
+
+    // Initialize redaction and tracing.
+    redaction::init(config.redaction.clone());
+    init_tracing()?;
+
+    // Create event log.
+    #[cfg(feature = "nats")]
+    let evt_log = NatsEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "postgres")]
+    let evt_log = PostgresEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "sqlite")]
+    let evt_log = SqliteEvtLog::new(config.evt_log)
+        .await
+        .context("Cannot create event log")?;
+    #[cfg(feature = "dynamodb")]
+    let evt_log = DynamoEvtLog::new(config.evt_log, Arc::new(SystemClock))
+        .await
+        .context("Cannot create event log")?;
+
+    // Create snapshot store.
+    #[cfg(feature = "nats")]
+    let snapshot_store = NatsSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    #[cfg(feature = "postgres")]
+    let snapshot_store = PostgresSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    #[cfg(feature = "sqlite")]
+    let snapshot_store = SqliteSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+    #[cfg(feature = "dynamodb")]
+    let snapshot_store = DynamoSnapshotStore::new(config.snapshot_store)
+        .await
+        .context("Cannot create snapshot store")?;
+
+    let report = backup::restore(
+        Namespace::new(config.entity_ids),
+        evt_log,
+        snapshot_store,
+        Path::new(&dir),
+        until,
+    )
+    .await?;
+
+    // Rebuild persisted projections; in-memory ones are rebuilt on each start anyway.
+    #[cfg(feature = "sled")]
+    if let Some(projection_store) = config.projection_store {
+        infra::projection::sled_projection_store::SledProjectionStore::open(projection_store)
+            .context("Cannot open projection store")?
+            .clear()?;
+    }
+
+    println!(
+        "Restored {} accounts with {} events and {} snapshots, skipped {} events already restored",
+        report.accounts, report.evts, report.snapshots, report.skipped
+    );
+
+    Ok(())
+}
+
+/// Print the account state machine as Mermaid diagram or, with `--dot`, as Graphviz diagram.
+pub fn state_machine<A>(args: A) -> Result<()>
+where
+    A: IntoIterator<Item = String>,
+{
+    let format = if args.into_iter().any(|arg| arg == "--dot") {
+        Format::Dot
+    } else {
+        Format::Mermaid
+    };
+    print!("{}", account::state_machine_diagram(format));
+
+    Ok(())
+}
+
+fn load_config() -> Result<Config> {
+    let config = Config::load();
+    if let Err(error) = &config {
+        eprintln!(
+            "rusty-bank exited with ERROR:\n\tCannot load configuration\n\t{error}\n\t{:?}",
+            error.source()
+        );
+    };
+    Ok(config?)
+}
+
 fn init_tracing() -> Result<()> {
     tracing_subscriber::registry()
         .with(EnvFilter::from_default_env())
@@ -116,15 +1075,15 @@ fn init_tracing() -> Result<()> {
         .context("Cannot initialize tracing")
 }
 
-async fn shutdown_signal<F>(account_ids_projection_terminated: F)
+async fn shutdown_signal<F>(account_summaries_projection_terminated: F)
 where
     F: Future<Output = ()>,
 {
     let ctrl_c = async { signal::ctrl_c().await.expect("Failed to listen for ctrl-c") };
 
     select! {
-        _ = account_ids_projection_terminated => {
-            warn!("Shutting down, because account IDs projection terminated");
+        _ = account_summaries_projection_terminated => {
+            warn!("Shutting down, because account summaries projection terminated");
         }
         _ = ctrl_c =>  {
             warn!("Shutting down, because ctrl-c received");
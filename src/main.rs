@@ -1,8 +1,26 @@
+use std::{env, process};
 use tracing::error;
 
 #[tokio::main]
 async fn main() {
-    if let Err(error) = rusty_bank::run().await {
+    let mut args = env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("import") => rusty_bank::import(args).await,
+        Some("backfill") => rusty_bank::backfill(args).await,
+        Some("backup") => rusty_bank::backup(args).await,
+        Some("restore") => rusty_bank::restore(args).await,
+        Some("state-machine") => rusty_bank::state_machine(args),
+        None => rusty_bank::run().await,
+        // Tracing is not yet initialized, hence printing to stderr.
+        Some(command) => {
+            eprintln!(
+                "Unknown command {command}, expected one of import, backfill, backup, restore, \
+                 state-machine or none to run the server"
+            );
+            process::exit(2);
+        }
+    };
+    if let Err(error) = result {
         error!(error = format!("{error:#}"), "rusty-bank exited with ERROR");
     };
 }